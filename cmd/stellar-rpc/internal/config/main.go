@@ -39,45 +39,71 @@ type Config struct {
 	LogFormat                                      LogFormat
 	LogLevel                                       logrus.Level
 	MaxEventsLimit                                 uint
+	MaxEventFilterContractIDs                      uint
 	MaxTransactionsLimit                           uint
 	MaxLedgersLimit                                uint
+	MaxLedgerEntriesLimit                          uint
+	MaxLedgerEntriesKeys                           uint
+	MaxLedgerEntriesRollbackLedgers                uint
+	MaxSimulateTransactionsLimit                   uint
+	MaxEventStreams                                uint
+	EventStreamPollInterval                        time.Duration
+	MaxEventWebhooks                               uint
+	MaxGetTransactionWaitDuration                  time.Duration
 	MaxHealthyLedgerLatency                        time.Duration
 	NetworkPassphrase                              string
 	PreflightWorkerCount                           uint
 	PreflightWorkerQueueSize                       uint
+	PreflightCacheSize                             uint
 	PreflightEnableDebug                           bool
+	MaxSimulationLedgerSkew                        uint32
+	MaxSimulationTimeSkew                          time.Duration
 	SQLiteDBPath                                   string
 	HistoryRetentionWindow                         uint32
+	EventRetentionWindow                           uint32
+	TransactionRetentionWindow                     uint32
 	SorobanFeeStatsLedgerRetentionWindow           uint32
 	ClassicFeeStatsLedgerRetentionWindow           uint32
 	RequestBacklogGlobalQueueLimit                 uint
 	RequestBacklogGetHealthQueueLimit              uint
 	RequestBacklogGetEventsQueueLimit              uint
+	RequestBacklogGetEventsCountQueueLimit         uint
 	RequestBacklogGetNetworkQueueLimit             uint
 	RequestBacklogGetVersionInfoQueueLimit         uint
 	RequestBacklogGetLatestLedgerQueueLimit        uint
 	RequestBacklogGetLedgerEntriesQueueLimit       uint
+	RequestBacklogGetContractDataQueueLimit        uint
 	RequestBacklogGetTransactionQueueLimit         uint
 	RequestBacklogGetTransactionsQueueLimit        uint
+	RequestBacklogGetTransactionsByHashQueueLimit  uint
 	RequestBacklogGetLedgersQueueLimit             uint
 	RequestBacklogSendTransactionQueueLimit        uint
 	RequestBacklogSimulateTransactionQueueLimit    uint
+	RequestBacklogSimulateTransactionsQueueLimit   uint
+	RequestBacklogDiagnoseTransactionQueueLimit    uint
 	RequestBacklogGetFeeStatsTransactionQueueLimit uint
 	RequestExecutionWarningThreshold               time.Duration
 	MaxRequestExecutionDuration                    time.Duration
 	MaxGetHealthExecutionDuration                  time.Duration
 	MaxGetEventsExecutionDuration                  time.Duration
+	MaxGetEventsCountExecutionDuration             time.Duration
 	MaxGetNetworkExecutionDuration                 time.Duration
 	MaxGetVersionInfoExecutionDuration             time.Duration
 	MaxGetLatestLedgerExecutionDuration            time.Duration
 	MaxGetLedgerEntriesExecutionDuration           time.Duration
+	MaxGetContractDataExecutionDuration            time.Duration
 	MaxGetTransactionExecutionDuration             time.Duration
 	MaxGetTransactionsExecutionDuration            time.Duration
+	MaxGetTransactionsByHashExecutionDuration      time.Duration
 	MaxGetLedgersExecutionDuration                 time.Duration
 	MaxSendTransactionExecutionDuration            time.Duration
 	MaxSimulateTransactionExecutionDuration        time.Duration
+	MaxSimulateTransactionsExecutionDuration       time.Duration
+	MaxDiagnoseTransactionExecutionDuration        time.Duration
 	MaxGetFeeStatsExecutionDuration                time.Duration
 	ServeLedgersFromDatastore                      bool
+	BackfillEventsFromDatastore                    bool
+	EventBackfillCacheSize                         uint
 	BufferedStorageBackendConfig                   ledgerbackend.BufferedStorageBackendConfig
 	DataStoreConfig                                datastore.DataStoreConfig
 
@@ -25,6 +25,10 @@ const (
 	defaultHTTPEndpoint             = "localhost:8000"
 	defaultCaptiveCoreHTTPPort      = 11626 // regular queries like /info
 	defaultCaptiveCoreHTTPQueryPort = 11628
+
+	// getTransaction requests hold their slot of the request backlog while
+	// waiting for their transaction, so they can't wait for long
+	maxGetTransactionWaitDuration = 30 * time.Second
 )
 
 // TODO: refactor and remove the linter exceptions
@@ -243,13 +247,37 @@ func (cfg *Config) options() Options {
 		{
 			Name: "history-retention-window",
 			Usage: fmt.Sprintf(
-				"configures history retention window for transactions and events, expressed in number of ledgers,"+
+				"configures history retention window for ledgers and transactions (and events, unless"+
+					" event-retention-window is set), expressed in number of ledgers,"+
 					" the default value is %d which corresponds to about 7 days of history",
 				SevenDayOfLedgers),
 			ConfigKey:    &cfg.HistoryRetentionWindow,
 			DefaultValue: uint32(SevenDayOfLedgers),
 			Validate:     positive,
 		},
+		{
+			Name: "event-retention-window",
+			Usage: "configures the retention window for events, expressed in number of ledgers," +
+				" which can exceed the history retention window to keep events for longer than transactions," +
+				" the default value is 0 which uses the history retention window",
+			ConfigKey:    &cfg.EventRetentionWindow,
+			DefaultValue: uint32(0),
+		},
+		{
+			Name: "transaction-retention-window",
+			Usage: "configures the retention window for the transaction hash index, expressed in number of ledgers," +
+				" which can exceed the history retention window so that getTransaction serves older transactions" +
+				" from their ledgers fetched from the datastore (requires serve-ledgers-from-datastore)," +
+				" the default value is 0 which uses the history retention window",
+			ConfigKey:    &cfg.TransactionRetentionWindow,
+			DefaultValue: uint32(0),
+			Validate: func(option *Option) error {
+				if cfg.TransactionRetentionWindow != 0 && !cfg.ServeLedgersFromDatastore {
+					return fmt.Errorf("%s requires serve-ledgers-from-datastore", option.Name)
+				}
+				return nil
+			},
+		},
 		{
 			Name:         "classic-fee-stats-retention-window",
 			Usage:        "configures classic fee stats retention window expressed in number of ledgers",
@@ -270,6 +298,13 @@ func (cfg *Config) options() Options {
 			ConfigKey:    &cfg.MaxEventsLimit,
 			DefaultValue: uint(10000),
 		},
+		{
+			Name:         "max-event-filter-contract-ids",
+			Usage:        "Maximum amount of contract IDs allowed in a single filter of getEvents",
+			ConfigKey:    &cfg.MaxEventFilterContractIDs,
+			DefaultValue: uint(50),
+			Validate:     positive,
+		},
 		{
 			Name:         "default-events-limit",
 			Usage:        "Default cap on the amount of events included in a single getEvents response",
@@ -286,6 +321,31 @@ func (cfg *Config) options() Options {
 				return nil
 			},
 		},
+		{
+			Name:         "max-event-streams",
+			Usage:        "Maximum number of concurrent event streams served at /events. 0 disables event streaming.",
+			ConfigKey:    &cfg.MaxEventStreams,
+			DefaultValue: uint(100),
+		},
+		{
+			Name:         "event-stream-poll-interval",
+			Usage:        "Interval at which event streams check for events of newly closed ledgers",
+			ConfigKey:    &cfg.EventStreamPollInterval,
+			DefaultValue: time.Second,
+			Validate: func(option *Option) error {
+				if cfg.EventStreamPollInterval <= 0 {
+					return fmt.Errorf("%s must be positive", option.Name)
+				}
+				return nil
+			},
+		},
+		{
+			Name: "max-event-webhooks",
+			Usage: "Maximum number of event webhooks registered through registerEventWebhook, which are delivered " +
+				"(polling like event streams) by POSTing their events to their URL. 0 disables event webhooks.",
+			ConfigKey:    &cfg.MaxEventWebhooks,
+			DefaultValue: uint(0),
+		},
 		{
 			Name:         "max-transactions-limit",
 			Usage:        "Maximum amount of transactions allowed in a single getTransactions response",
@@ -308,12 +368,67 @@ func (cfg *Config) options() Options {
 				return nil
 			},
 		},
+		{
+			Name: "max-get-transaction-wait-duration",
+			Usage: "Maximum duration a getTransaction request waits (with waitFor) for its transaction to be " +
+				"ingested, on top of max-get-transaction-execution-duration, up to 30s. 0 disables waiting.",
+			ConfigKey:    &cfg.MaxGetTransactionWaitDuration,
+			DefaultValue: 10 * time.Second,
+			Validate: func(option *Option) error {
+				if cfg.MaxGetTransactionWaitDuration < 0 {
+					return fmt.Errorf("%s cannot be negative", option.Name)
+				}
+				if cfg.MaxGetTransactionWaitDuration > maxGetTransactionWaitDuration {
+					return fmt.Errorf("%s cannot exceed %v", option.Name, maxGetTransactionWaitDuration)
+				}
+				return nil
+			},
+		},
 		{
 			Name:         "max-ledgers-limit",
 			Usage:        "Maximum amount of ledgers allowed in a single getLedgers response",
 			ConfigKey:    &cfg.MaxLedgersLimit,
 			DefaultValue: uint(200),
 		},
+		{
+			Name: "max-ledger-entries-limit",
+			Usage: "Maximum amount of keys looked up by a single getLedgerEntries request, or by a page of a " +
+				"paginated request (or of getContractData)",
+			ConfigKey:    &cfg.MaxLedgerEntriesLimit,
+			DefaultValue: uint(200),
+			Validate:     positive,
+		},
+		{
+			Name:         "max-ledger-entries-keys",
+			Usage:        "Maximum amount of keys allowed in a single paginated getLedgerEntries request",
+			ConfigKey:    &cfg.MaxLedgerEntriesKeys,
+			DefaultValue: uint(10000),
+			Validate: func(_ *Option) error {
+				if cfg.MaxLedgerEntriesKeys < cfg.MaxLedgerEntriesLimit {
+					return fmt.Errorf(
+						"max-ledger-entries-keys (%v) cannot be lower than max-ledger-entries-limit (%v)",
+						cfg.MaxLedgerEntriesKeys,
+						cfg.MaxLedgerEntriesLimit,
+					)
+				}
+				return nil
+			},
+		},
+		{
+			Name: "max-ledger-entries-rollback-ledgers",
+			Usage: "Maximum number of ledgers a getLedgerEntries request with atLedger, or a diagnoseTransaction " +
+				"request, can look back from the latest ledger, each of which is read to roll the entries back " +
+				"(120 by default, about 10 minutes)",
+			ConfigKey:    &cfg.MaxLedgerEntriesRollbackLedgers,
+			DefaultValue: uint(120),
+		},
+		{
+			Name:         "max-simulate-transactions-limit",
+			Usage:        "Maximum amount of transactions allowed in a single simulateTransactions request",
+			ConfigKey:    &cfg.MaxSimulateTransactionsLimit,
+			DefaultValue: uint(20),
+			Validate:     positive,
+		},
 		{
 			Name:         "default-ledgers-limit",
 			Usage:        "Default cap on the amount of ledgers included in a single getLedgers response",
@@ -351,12 +466,33 @@ func (cfg *Config) options() Options {
 			DefaultValue: uint(runtime.NumCPU()),
 			Validate:     positive,
 		},
+		{
+			Name: "preflight-cache-size",
+			Usage: "Maximum number of simulateTransaction results (of the latest ledger) cached, so that identical" +
+				" simulations aren't run again until a new ledger closes. 0 disables caching.",
+			ConfigKey:    &cfg.PreflightCacheSize,
+			DefaultValue: uint(0),
+		},
 		{
 			Name:         "preflight-enable-debug",
 			Usage:        "Enable debug information in preflighting (provides more detailed errors). It should not be enabled in production deployments.",
 			ConfigKey:    &cfg.PreflightEnableDebug,
 			DefaultValue: true,
 		},
+		{
+			Name: "max-simulation-ledger-skew",
+			Usage: "Maximum number of ledgers the sequence of the ledger seen by a simulation can be moved away from the" +
+				" simulated ledger through the ledgerOverride of simulateTransaction. 0 disables overriding it.",
+			ConfigKey:    &cfg.MaxSimulationLedgerSkew,
+			DefaultValue: uint32(SevenDayOfLedgers),
+		},
+		{
+			Name: "max-simulation-time-skew",
+			Usage: "Maximum duration the timestamp of the ledger seen by a simulation can be moved away from the" +
+				" current time through the ledgerOverride of simulateTransaction. 0 disables overriding it.",
+			ConfigKey:    &cfg.MaxSimulationTimeSkew,
+			DefaultValue: 7 * 24 * time.Hour,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("request-backlog-global-queue-limit"),
 			Usage:        "Maximum number of outstanding requests",
@@ -378,6 +514,13 @@ func (cfg *Config) options() Options {
 			DefaultValue: uint(1000),
 			Validate:     positive,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-events-count-queue-limit"),
+			Usage:        "Maximum number of outstanding GetEventsCount requests",
+			ConfigKey:    &cfg.RequestBacklogGetEventsCountQueueLimit,
+			DefaultValue: uint(100),
+			Validate:     positive,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-network-queue-limit"),
 			Usage:        "Maximum number of outstanding GetNetwork requests",
@@ -406,6 +549,13 @@ func (cfg *Config) options() Options {
 			DefaultValue: uint(1000),
 			Validate:     positive,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-contract-data-queue-limit"),
+			Usage:        "Maximum number of outstanding GetContractData requests",
+			ConfigKey:    &cfg.RequestBacklogGetContractDataQueueLimit,
+			DefaultValue: uint(1000),
+			Validate:     positive,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-transaction-queue-limit"),
 			Usage:        "Maximum number of outstanding GetTransaction requests",
@@ -420,6 +570,13 @@ func (cfg *Config) options() Options {
 			DefaultValue: uint(1000),
 			Validate:     positive,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-transactions-by-hash-queue-limit"),
+			Usage:        "Maximum number of outstanding GetTransactionsByHash requests",
+			ConfigKey:    &cfg.RequestBacklogGetTransactionsByHashQueueLimit,
+			DefaultValue: uint(100),
+			Validate:     positive,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-ledgers-queue-limit"),
 			Usage:        "Maximum number of outstanding getLedgers requests",
@@ -441,6 +598,20 @@ func (cfg *Config) options() Options {
 			DefaultValue: uint(100),
 			Validate:     positive,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("request-backlog-simulate-transactions-queue-limit"),
+			Usage:        "Maximum number of outstanding SimulateTransactions requests",
+			ConfigKey:    &cfg.RequestBacklogSimulateTransactionsQueueLimit,
+			DefaultValue: uint(10),
+			Validate:     positive,
+		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("request-backlog-diagnose-transaction-queue-limit"),
+			Usage:        "Maximum number of outstanding DiagnoseTransaction requests",
+			ConfigKey:    &cfg.RequestBacklogDiagnoseTransactionQueueLimit,
+			DefaultValue: uint(10),
+			Validate:     positive,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("request-backlog-get-fee-stats-queue-limit"),
 			Usage:        "Maximum number of outstanding GetFeeStats requests",
@@ -472,6 +643,12 @@ func (cfg *Config) options() Options {
 			ConfigKey:    &cfg.MaxGetEventsExecutionDuration,
 			DefaultValue: 10 * time.Second,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("max-get-events-count-execution-duration"),
+			Usage:        "The maximum duration of time allowed for processing a getEventsCount request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
+			ConfigKey:    &cfg.MaxGetEventsCountExecutionDuration,
+			DefaultValue: 10 * time.Second,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("max-get-network-execution-duration"),
 			Usage:        "The maximum duration of time allowed for processing a getNetwork request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
@@ -496,6 +673,12 @@ func (cfg *Config) options() Options {
 			ConfigKey:    &cfg.MaxGetLedgerEntriesExecutionDuration,
 			DefaultValue: 5 * time.Second,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("max-get-contract-data-execution-duration"),
+			Usage:        "The maximum duration of time allowed for processing a getContractData request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
+			ConfigKey:    &cfg.MaxGetContractDataExecutionDuration,
+			DefaultValue: 5 * time.Second,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("max-get-transaction-execution-duration"),
 			Usage:        "The maximum duration of time allowed for processing a getTransaction request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
@@ -508,6 +691,12 @@ func (cfg *Config) options() Options {
 			ConfigKey:    &cfg.MaxGetTransactionsExecutionDuration,
 			DefaultValue: 5 * time.Second,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("max-get-transactions-by-hash-execution-duration"),
+			Usage:        "The maximum duration of time allowed for processing a getTransactionsByHash request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
+			ConfigKey:    &cfg.MaxGetTransactionsByHashExecutionDuration,
+			DefaultValue: 10 * time.Second,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("max-get-ledgers-execution-duration"),
 			Usage:        "The maximum duration of time allowed for processing a getLedgers request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
@@ -526,6 +715,18 @@ func (cfg *Config) options() Options {
 			ConfigKey:    &cfg.MaxSimulateTransactionExecutionDuration,
 			DefaultValue: 15 * time.Second,
 		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("max-simulate-transactions-execution-duration"),
+			Usage:        "The maximum duration of time allowed for processing a simulateTransactions request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
+			ConfigKey:    &cfg.MaxSimulateTransactionsExecutionDuration,
+			DefaultValue: 30 * time.Second,
+		},
+		{
+			TomlKey:      strutils.KebabToConstantCase("max-diagnose-transaction-execution-duration"),
+			Usage:        "The maximum duration of time allowed for processing a diagnoseTransaction request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
+			ConfigKey:    &cfg.MaxDiagnoseTransactionExecutionDuration,
+			DefaultValue: 15 * time.Second,
+		},
 		{
 			TomlKey:      strutils.KebabToConstantCase("max-get-fee-stats-execution-duration"),
 			Usage:        "The maximum duration of time allowed for processing a getFeeStats request. When that time elapses, the rpc server would return -32001 and abort the request's execution",
@@ -539,6 +740,26 @@ func (cfg *Config) options() Options {
 			ConfigKey:    &cfg.ServeLedgersFromDatastore,
 			DefaultValue: false,
 		},
+		{
+			Name: "backfill-events-from-datastore",
+			Usage: "Serve the getEvents requests starting before the event retention window by replaying the " +
+				"ledgers of their checkpoints fetched from the datastore, instead of returning an out-of-range error. " +
+				"Requires serve-ledgers-from-datastore.",
+			ConfigKey:    &cfg.BackfillEventsFromDatastore,
+			DefaultValue: false,
+			Validate: func(option *Option) error {
+				if cfg.BackfillEventsFromDatastore && !cfg.ServeLedgersFromDatastore {
+					return fmt.Errorf("%s requires serve-ledgers-from-datastore", option.Name)
+				}
+				return nil
+			},
+		},
+		{
+			Name:         "event-backfill-cache-size",
+			Usage:        "Number of checkpoints whose backfilled events are cached",
+			ConfigKey:    &cfg.EventBackfillCacheSize,
+			DefaultValue: uint(16),
+		},
 		{
 			TomlKey:   "buffered_storage_backend_config",
 			ConfigKey: &cfg.BufferedStorageBackendConfig,
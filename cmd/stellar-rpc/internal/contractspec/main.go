@@ -0,0 +1,188 @@
+package contractspec
+
+import (
+	"bytes"
+	"encoding/binary"
+	"errors"
+	"fmt"
+	"io"
+
+	"github.com/stellar/go/xdr"
+)
+
+// SpecSectionName is the name of the Wasm custom section holding the spec of
+// a contract, a sequence of XDR-encoded ScSpecEntry
+const SpecSectionName = "contractspecv0"
+
+//nolint:gochecknoglobals
+var wasmHeader = []byte("\x00asm\x01\x00\x00\x00")
+
+// Field is a named parameter of an event
+type Field struct {
+	Name  string
+	Value xdr.ScVal
+}
+
+// EventSpecs returns the specs of the events of a contract, found in the spec
+// embedded in its Wasm.
+func EventSpecs(wasm []byte) ([]xdr.ScSpecEventV0, error) {
+	section, err := customSection(wasm, SpecSectionName)
+	if err != nil {
+		return nil, err
+	}
+
+	var specs []xdr.ScSpecEventV0
+	reader := bytes.NewReader(section)
+	for reader.Len() > 0 {
+		var entry xdr.ScSpecEntry
+		if _, err := xdr.Unmarshal(reader, &entry); err != nil {
+			return nil, fmt.Errorf("could not decode contract spec entry: %w", err)
+		}
+		if entry.Kind == xdr.ScSpecEntryKindScSpecEntryEventV0 && entry.EventV0 != nil {
+			specs = append(specs, *entry.EventV0)
+		}
+	}
+	return specs, nil
+}
+
+// customSection returns the concatenated contents of the custom sections of
+// a Wasm module with the given name.
+func customSection(wasm []byte, name string) ([]byte, error) {
+	if !bytes.HasPrefix(wasm, wasmHeader) {
+		return nil, errors.New("invalid wasm header")
+	}
+
+	var contents []byte
+	reader := bytes.NewReader(wasm[len(wasmHeader):])
+	for reader.Len() > 0 {
+		id, err := reader.ReadByte()
+		if err != nil {
+			return nil, err
+		}
+		size, err := binary.ReadUvarint(reader)
+		if err != nil || size > uint64(reader.Len()) {
+			return nil, errors.New("invalid wasm section size")
+		}
+		sectionSize := int(size) //nolint:gosec
+		offset := len(wasm) - reader.Len()
+		section := wasm[offset : offset+sectionSize]
+		if _, err := reader.Seek(int64(sectionSize), io.SeekCurrent); err != nil {
+			return nil, err
+		}
+
+		// custom sections start with their name
+		if id != 0 {
+			continue
+		}
+		sectionReader := bytes.NewReader(section)
+		nameSize, err := binary.ReadUvarint(sectionReader)
+		if err != nil || nameSize > uint64(sectionReader.Len()) {
+			return nil, errors.New("invalid wasm custom section name")
+		}
+		nameStart := len(section) - sectionReader.Len()
+		nameEnd := nameStart + int(nameSize) //nolint:gosec
+		if string(section[nameStart:nameEnd]) == name {
+			contents = append(contents, section[nameEnd:]...)
+		}
+	}
+	return contents, nil
+}
+
+// DecodeEvent names the topics and data of an event after the first of the
+// specs describing it, returning false if there is none.
+func DecodeEvent(specs []xdr.ScSpecEventV0, topics []xdr.ScVal, data xdr.ScVal) (string, []Field, bool) {
+	for _, spec := range specs {
+		if fields, ok := decodeEvent(spec, topics, data); ok {
+			return string(spec.Name), fields, true
+		}
+	}
+	return "", nil, false
+}
+
+func decodeEvent(spec xdr.ScSpecEventV0, topics []xdr.ScVal, data xdr.ScVal) ([]Field, bool) {
+	var topicParams, dataParams []xdr.ScSpecEventParamV0
+	for _, param := range spec.Params {
+		if param.Location == xdr.ScSpecEventParamLocationV0ScSpecEventParamLocationTopicList {
+			topicParams = append(topicParams, param)
+		} else {
+			dataParams = append(dataParams, param)
+		}
+	}
+
+	// The topics are the prefix topics of the event followed by its topic
+	// params
+	if len(topics) != len(spec.PrefixTopics)+len(topicParams) {
+		return nil, false
+	}
+	for i, prefix := range spec.PrefixTopics {
+		if topics[i].Type != xdr.ScValTypeScvSymbol || topics[i].Sym == nil || *topics[i].Sym != prefix {
+			return nil, false
+		}
+	}
+	fields := make([]Field, 0, len(spec.Params))
+	for i, param := range topicParams {
+		fields = append(fields, Field{Name: param.Name, Value: topics[len(spec.PrefixTopics)+i]})
+	}
+
+	dataFields, ok := decodeEventData(spec.DataFormat, dataParams, data)
+	if !ok {
+		return nil, false
+	}
+	return append(fields, dataFields...), true
+}
+
+func decodeEventData(format xdr.ScSpecEventDataFormat, params []xdr.ScSpecEventParamV0, data xdr.ScVal,
+) ([]Field, bool) {
+	switch format {
+	case xdr.ScSpecEventDataFormatScSpecEventDataFormatSingleValue:
+		switch len(params) {
+		case 0:
+			return nil, data.Type == xdr.ScValTypeScvVoid
+		case 1:
+			return []Field{{Name: params[0].Name, Value: data}}, true
+		default:
+			return nil, false
+		}
+	case xdr.ScSpecEventDataFormatScSpecEventDataFormatVec:
+		return decodeVecEventData(params, data)
+	case xdr.ScSpecEventDataFormatScSpecEventDataFormatMap:
+		return decodeMapEventData(params, data)
+	default:
+		return nil, false
+	}
+}
+
+func decodeVecEventData(params []xdr.ScSpecEventParamV0, data xdr.ScVal) ([]Field, bool) {
+	if data.Type != xdr.ScValTypeScvVec || data.Vec == nil || *data.Vec == nil || len(**data.Vec) != len(params) {
+		return nil, false
+	}
+	fields := make([]Field, 0, len(params))
+	for i, param := range params {
+		fields = append(fields, Field{Name: param.Name, Value: (**data.Vec)[i]})
+	}
+	return fields, true
+}
+
+func decodeMapEventData(params []xdr.ScSpecEventParamV0, data xdr.ScVal) ([]Field, bool) {
+	if data.Type != xdr.ScValTypeScvMap || data.Map == nil || *data.Map == nil || len(**data.Map) != len(params) {
+		return nil, false
+	}
+	fields := make([]Field, 0, len(params))
+	for _, param := range params {
+		value, ok := mapValue(**data.Map, param.Name)
+		if !ok {
+			return nil, false
+		}
+		fields = append(fields, Field{Name: param.Name, Value: value})
+	}
+	return fields, true
+}
+
+func mapValue(m xdr.ScMap, key string) (xdr.ScVal, bool) {
+	for _, entry := range m {
+		if entry.Key.Type == xdr.ScValTypeScvSymbol && entry.Key.Sym != nil && string(*entry.Key.Sym) == key {
+			return entry.Val, true
+		}
+	}
+	return xdr.ScVal{}, false
+}
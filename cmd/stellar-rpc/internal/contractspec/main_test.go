@@ -0,0 +1,95 @@
+package contractspec
+
+import (
+	"encoding/binary"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+)
+
+func wasmWithSpec(t *testing.T, entries ...xdr.ScSpecEntry) []byte {
+	var payload []byte
+	for _, entry := range entries {
+		b, err := entry.MarshalBinary()
+		require.NoError(t, err)
+		payload = append(payload, b...)
+	}
+	section := binary.AppendUvarint(nil, uint64(len(SpecSectionName)))
+	section = append(section, SpecSectionName...)
+	section = append(section, payload...)
+
+	wasm := append([]byte{}, wasmHeader...)
+	// an empty type section, which is skipped
+	wasm = append(wasm, 1, 1, 0)
+	wasm = append(wasm, 0)
+	wasm = binary.AppendUvarint(wasm, uint64(len(section)))
+	return append(wasm, section...)
+}
+
+func symbol(s string) xdr.ScVal {
+	sym := xdr.ScSymbol(s)
+	return xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &sym}
+}
+
+func u32(v uint32) xdr.ScVal {
+	u := xdr.Uint32(v)
+	return xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: &u}
+}
+
+func TestDecodeEvent(t *testing.T) {
+	topic := xdr.ScSpecEventParamLocationV0ScSpecEventParamLocationTopicList
+	data := xdr.ScSpecEventParamLocationV0ScSpecEventParamLocationData
+	transfer := xdr.ScSpecEventV0{
+		Name:         "Transfer",
+		PrefixTopics: []xdr.ScSymbol{"transfer"},
+		Params: []xdr.ScSpecEventParamV0{
+			{Name: "from", Type: xdr.ScSpecTypeDef{Type: xdr.ScSpecTypeScSpecTypeSymbol}, Location: topic},
+			{Name: "to", Type: xdr.ScSpecTypeDef{Type: xdr.ScSpecTypeScSpecTypeSymbol}, Location: topic},
+			{Name: "amount", Type: xdr.ScSpecTypeDef{Type: xdr.ScSpecTypeScSpecTypeU32}, Location: data},
+		},
+		DataFormat: xdr.ScSpecEventDataFormatScSpecEventDataFormatSingleValue,
+	}
+	updated := xdr.ScSpecEventV0{
+		Name:         "Updated",
+		PrefixTopics: []xdr.ScSymbol{"updated"},
+		Params: []xdr.ScSpecEventParamV0{
+			{Name: "old", Type: xdr.ScSpecTypeDef{Type: xdr.ScSpecTypeScSpecTypeU32}, Location: data},
+			{Name: "new", Type: xdr.ScSpecTypeDef{Type: xdr.ScSpecTypeScSpecTypeU32}, Location: data},
+		},
+		DataFormat: xdr.ScSpecEventDataFormatScSpecEventDataFormatMap,
+	}
+	wasm := wasmWithSpec(t,
+		xdr.ScSpecEntry{Kind: xdr.ScSpecEntryKindScSpecEntryEventV0, EventV0: &transfer},
+		xdr.ScSpecEntry{Kind: xdr.ScSpecEntryKindScSpecEntryEventV0, EventV0: &updated},
+	)
+
+	specs, err := EventSpecs(wasm)
+	require.NoError(t, err)
+	require.Len(t, specs, 2)
+
+	name, fields, ok := DecodeEvent(specs, []xdr.ScVal{symbol("transfer"), symbol("alice"), symbol("bob")}, u32(10))
+	require.True(t, ok)
+	require.Equal(t, "Transfer", name)
+	require.Equal(t, []Field{
+		{Name: "from", Value: symbol("alice")},
+		{Name: "to", Value: symbol("bob")},
+		{Name: "amount", Value: u32(10)},
+	}, fields)
+
+	m := &xdr.ScMap{{Key: symbol("new"), Val: u32(2)}, {Key: symbol("old"), Val: u32(1)}}
+	name, fields, ok = DecodeEvent(specs, []xdr.ScVal{symbol("updated")}, xdr.ScVal{Type: xdr.ScValTypeScvMap, Map: &m})
+	require.True(t, ok)
+	require.Equal(t, "Updated", name)
+	require.Equal(t, []Field{{Name: "old", Value: u32(1)}, {Name: "new", Value: u32(2)}}, fields)
+
+	// Events which don't match any spec
+	_, _, ok = DecodeEvent(specs, []xdr.ScVal{symbol("transfer"), symbol("alice")}, u32(10))
+	require.False(t, ok)
+	_, _, ok = DecodeEvent(specs, []xdr.ScVal{symbol("updated")}, u32(1))
+	require.False(t, ok)
+
+	_, err = EventSpecs([]byte("not wasm"))
+	require.Error(t, err)
+}
@@ -1,6 +1,7 @@
 package daemon
 
 import (
+	"cmp"
 	"context"
 	"errors"
 	"fmt"
@@ -33,6 +34,7 @@ import (
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/feewindow"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ingest"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/methods"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/preflight"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/rpcdatastore"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/util"
@@ -56,6 +58,8 @@ type Daemon struct {
 	ingestService       *ingest.Service
 	db                  *db.DB
 	jsonRPCHandler      *internal.Handler
+	eventStreamHandler  *internal.EventStreamHandler
+	webhookDispatcher   *methods.EventWebhookDispatcher
 	logger              *supportlog.Entry
 	preflightWorkerPool *preflight.WorkerPool
 	listener            net.Listener
@@ -109,6 +113,9 @@ func (d *Daemon) close() {
 		closeErrors = append(closeErrors, err)
 	}
 	d.jsonRPCHandler.Close()
+	if d.webhookDispatcher != nil {
+		d.webhookDispatcher.Close(shutdownCtx)
+	}
 	if err := d.db.Close(); err != nil {
 		d.logger.WithError(err).Error("Error closing db")
 		closeErrors = append(closeErrors, err)
@@ -193,6 +200,10 @@ func MustNew(cfg *config.Config, logger *supportlog.Entry) *Daemon {
 	daemon.ingestService = createIngestService(cfg, logger, daemon, feewindows, historyArchive)
 	daemon.preflightWorkerPool = createPreflightWorkerPool(cfg, logger, daemon)
 	daemon.jsonRPCHandler = createJSONRPCHandler(cfg, logger, daemon, feewindows)
+	daemon.eventStreamHandler = createEventStreamHandler(cfg, logger, daemon)
+	if cfg.MaxEventWebhooks > 0 {
+		daemon.webhookDispatcher = createEventWebhookDispatcher(cfg, logger, daemon)
+	}
 
 	daemon.setupHTTPServers(cfg)
 	daemon.registerMetrics()
@@ -289,6 +300,8 @@ func createIngestService(cfg *config.Config, logger *supportlog.Entry, daemon *D
 			daemon,
 			maxLedgerEntryWriteBatchSize,
 			cfg.HistoryRetentionWindow,
+			cmp.Or(cfg.EventRetentionWindow, cfg.HistoryRetentionWindow),
+			cmp.Or(cfg.TransactionRetentionWindow, cfg.HistoryRetentionWindow),
 			cfg.NetworkPassphrase,
 		),
 		NetworkPassPhrase: cfg.NetworkPassphrase,
@@ -307,6 +320,7 @@ func createPreflightWorkerPool(cfg *config.Config, logger *supportlog.Entry, dae
 			Daemon:            daemon,
 			WorkerCount:       cfg.PreflightWorkerCount,
 			JobQueueCapacity:  cfg.PreflightWorkerQueueSize,
+			CacheSize:         cfg.PreflightCacheSize,
 			EnableDebug:       cfg.PreflightEnableDebug,
 			NetworkPassphrase: cfg.NetworkPassphrase,
 			Logger:            logger,
@@ -329,12 +343,38 @@ func createJSONRPCHandler(cfg *config.Config, logger *supportlog.Entry, daemon *
 		LedgerReader:          db.NewLedgerReader(daemon.db),
 		TransactionReader:     db.NewTransactionReader(logger, daemon.db, cfg.NetworkPassphrase),
 		EventReader:           db.NewEventReader(logger, daemon.db, cfg.NetworkPassphrase),
+		EventWebhookStore:     db.NewEventWebhookStore(daemon.db),
+		ContractDataKeyReader: db.NewContractDataKeyReader(daemon.db),
 		PreflightGetter:       daemon.preflightWorkerPool,
 		DataStoreLedgerReader: dataStoreLedgerReader,
 	})
 	return &rpcHandler
 }
 
+func createEventStreamHandler(cfg *config.Config, logger *supportlog.Entry, daemon *Daemon,
+) *internal.EventStreamHandler {
+	return internal.NewEventStreamHandler(cfg, internal.HandlerParams{
+		Daemon:       daemon,
+		Logger:       logger,
+		LedgerReader: db.NewLedgerReader(daemon.db),
+		EventReader:  db.NewEventReader(logger, daemon.db, cfg.NetworkPassphrase),
+	})
+}
+
+func createEventWebhookDispatcher(cfg *config.Config, logger *supportlog.Entry, daemon *Daemon,
+) *methods.EventWebhookDispatcher {
+	return methods.NewEventWebhookDispatcher(
+		logger,
+		db.NewEventReader(logger, daemon.db, cfg.NetworkPassphrase),
+		db.NewLedgerReader(daemon.db),
+		db.NewEventWebhookStore(daemon.db),
+		cfg.MaxEventsLimit,
+		cfg.MaxEventFilterContractIDs,
+		cfg.NetworkPassphrase,
+		cfg.EventStreamPollInterval,
+	)
+}
+
 func (d *Daemon) setupHTTPServers(cfg *config.Config) {
 	var err error
 	d.listener, err = net.Listen("tcp", cfg.Endpoint)
@@ -342,18 +382,22 @@ func (d *Daemon) setupHTTPServers(cfg *config.Config) {
 		d.logger.WithError(err).WithField("endpoint", cfg.Endpoint).Fatal("cannot listen on endpoint")
 	}
 	d.server = &http.Server{
-		Handler:     createHTTPHandler(d.logger, d.jsonRPCHandler),
+		Handler:     createHTTPHandler(d.logger, d.jsonRPCHandler, d.eventStreamHandler),
 		ReadTimeout: defaultReadTimeout,
 	}
+	d.server.RegisterOnShutdown(d.eventStreamHandler.Close)
 
 	if cfg.AdminEndpoint != "" {
 		d.setupAdminServer(cfg)
 	}
 }
 
-func createHTTPHandler(logger *supportlog.Entry, jsonRPCHandler *internal.Handler) http.Handler {
+func createHTTPHandler(logger *supportlog.Entry, jsonRPCHandler *internal.Handler,
+	eventStreamHandler *internal.EventStreamHandler,
+) http.Handler {
 	httpHandler := supporthttp.NewAPIMux(logger)
 	httpHandler.Handle("/", jsonRPCHandler)
+	httpHandler.Handle("/events", eventStreamHandler)
 	return httpHandler
 }
 
@@ -477,6 +521,23 @@ func (d *Daemon) buildMigrations(ctx context.Context, cfg *config.Config, retent
 			maxFeeRetentionWindow, cfg.HistoryRetentionWindow)
 	}
 
+	// Events are looked up within the ledgers kept for the history retention
+	// window (extended back to the oldest stored event), so they can't be
+	// pruned before them.
+	if cfg.EventRetentionWindow != 0 && cfg.EventRetentionWindow < cfg.HistoryRetentionWindow {
+		d.logger.Fatalf(
+			"Event retention window (%d) cannot be shorter than history retention window (%d).",
+			cfg.EventRetentionWindow, cfg.HistoryRetentionWindow)
+	}
+
+	// Transactions are served from the ledgers of the history retention
+	// window, the datastore only serving the ones of older indexed hashes
+	if cfg.TransactionRetentionWindow != 0 && cfg.TransactionRetentionWindow < cfg.HistoryRetentionWindow {
+		d.logger.Fatalf(
+			"Transaction retention window (%d) cannot be shorter than history retention window (%d).",
+			cfg.TransactionRetentionWindow, cfg.HistoryRetentionWindow)
+	}
+
 	dataMigrations, err := db.BuildMigrations(
 		ctx, d.logger, d.db, cfg.NetworkPassphrase, retentionRange)
 	if err != nil {
@@ -514,6 +575,10 @@ func (d *Daemon) Run() {
 		})
 	}
 
+	if d.webhookDispatcher != nil {
+		panicGroup.Go(d.webhookDispatcher.Run)
+	}
+
 	// Shutdown gracefully when we receive an interrupt signal. First
 	// server.Shutdown closes all open listeners, then closes all idle
 	// connections. Finally, it waits a grace period (10s here) for connections
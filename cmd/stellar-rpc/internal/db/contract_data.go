@@ -0,0 +1,215 @@
+package db
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"strconv"
+
+	sq "github.com/Masterminds/squirrel"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/support/db"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+)
+
+const (
+	contractDataKeysTableName = "contract_data_keys"
+	// contractDataKeysFirstLedgerMetaKey is the meta key of the first ledger
+	// whose changes were indexed
+	contractDataKeysFirstLedgerMetaKey = "ContractDataKeysFirstLedger"
+)
+
+// ContractDataKeyReader enumerates the keys of the persistent contract data
+// entries of contracts. Only the entries created or updated by the ingested
+// ledgers, and not evicted since then, are indexed.
+type ContractDataKeyReader interface {
+	// GetContractDataKeys returns up to limit keys (ScVal XDR) of the entries
+	// of a contract, in the order of their XDR, after the given key (if any).
+	GetContractDataKeys(ctx context.Context, contractID []byte, after []byte, limit uint) ([][]byte, error)
+	// GetContractDataKeysFirstLedger returns the first ledger whose changes
+	// were indexed, 0 if none was.
+	GetContractDataKeysFirstLedger(ctx context.Context) (uint32, error)
+}
+
+type contractDataKeyReader struct {
+	db db.SessionInterface
+}
+
+func NewContractDataKeyReader(db db.SessionInterface) ContractDataKeyReader {
+	return contractDataKeyReader{db: db}
+}
+
+func (r contractDataKeyReader) GetContractDataKeys(ctx context.Context, contractID []byte, after []byte,
+	limit uint,
+) ([][]byte, error) {
+	query := sq.Select("key").
+		From(contractDataKeysTableName).
+		Where(sq.Eq{"contract_id": contractID}).
+		OrderBy("key ASC").
+		Limit(uint64(limit))
+	if len(after) > 0 {
+		query = query.Where(sq.Gt{"key": after})
+	}
+	var keys [][]byte
+	if err := r.db.Select(ctx, &keys, query); err != nil {
+		return nil, fmt.Errorf("db read failed for the contract data keys: %w", err)
+	}
+	return keys, nil
+}
+
+func (r contractDataKeyReader) GetContractDataKeysFirstLedger(ctx context.Context) (uint32, error) {
+	value, err := getMetaValue(ctx, r.db, contractDataKeysFirstLedgerMetaKey)
+	if errors.Is(err, ErrEmptyDB) {
+		return 0, nil
+	} else if err != nil {
+		return 0, err
+	}
+	firstLedger, err := strconv.ParseUint(value, 10, 32)
+	return uint32(firstLedger), err //nolint:gosec
+}
+
+type contractDataKey struct {
+	contractID xdr.ContractId
+	key        string
+}
+
+// updateContractDataKeys indexes the persistent contract data entries created
+// by the transactions of a ledger, and removes the deleted ones.
+func (txn *transactionHandler) updateContractDataKeys(ledgerSeq uint32, txs []ingest.LedgerTransaction) error {
+	// The migration indexes the stored ledgers before the new ones are
+	// ingested, so the first indexed ledger is the first one recorded
+	_, err := sq.Insert(metaTableName).
+		Options("OR IGNORE").
+		Values(contractDataKeysFirstLedgerMetaKey, strconv.FormatUint(uint64(ledgerSeq), 10)).
+		RunWith(txn.stmtCache).
+		Exec()
+	if err != nil {
+		return err
+	}
+	changed, exists, err := changedContractDataKeys(txs)
+	if err != nil {
+		return err
+	}
+
+	insert := sq.Insert(contractDataKeysTableName).
+		Options("OR IGNORE").
+		Columns("contract_id", "key")
+	var inserted int
+	var deleted sq.Or
+	for _, key := range changed {
+		if exists[key] {
+			insert = insert.Values(key.contractID[:], []byte(key.key))
+			inserted++
+		} else {
+			deleted = append(deleted, sq.Eq{"contract_id": key.contractID[:], "key": []byte(key.key)})
+		}
+	}
+	if inserted > 0 {
+		if _, err := insert.RunWith(txn.stmtCache).Exec(); err != nil {
+			return err
+		}
+	}
+	if len(deleted) > 0 {
+		_, err := sq.Delete(contractDataKeysTableName).Where(deleted).RunWith(txn.stmtCache).Exec()
+		return err
+	}
+	return nil
+}
+
+// deleteEvictedContractDataKeys removes the persistent contract data entries
+// evicted (archived) by a ledger from the index, which they are added back to
+// once restored.
+func (txn *transactionHandler) deleteEvictedContractDataKeys(lcm xdr.LedgerCloseMeta) error {
+	evictedKeys, err := lcm.EvictedLedgerKeys()
+	if err != nil {
+		return fmt.Errorf("couldn't read the evicted keys of ledger %d: %w", lcm.LedgerSequence(), err)
+	}
+	var deleted sq.Or
+	for _, evictedKey := range evictedKeys {
+		data, ok := evictedKey.GetContractData()
+		if !ok || data.Durability != xdr.ContractDataDurabilityPersistent {
+			continue
+		}
+		contractID, ok := data.Contract.GetContractId()
+		if !ok {
+			continue
+		}
+		keyXDR, err := data.Key.MarshalBinary()
+		if err != nil {
+			return err
+		}
+		deleted = append(deleted, sq.Eq{"contract_id": contractID[:], "key": keyXDR})
+	}
+	if len(deleted) == 0 {
+		return nil
+	}
+	_, err = sq.Delete(contractDataKeysTableName).Where(deleted).RunWith(txn.stmtCache).Exec()
+	return err
+}
+
+// changedContractDataKeys returns the keys of the persistent contract data
+// entries changed by the transactions of a ledger, along with whether each of
+// them exists after the ledger.
+func changedContractDataKeys(txs []ingest.LedgerTransaction) ([]contractDataKey, map[contractDataKey]bool, error) {
+	var changed []contractDataKey
+	exists := map[contractDataKey]bool{}
+	for _, tx := range txs {
+		changes, err := tx.GetChanges()
+		if err != nil {
+			return nil, nil, fmt.Errorf("couldn't read the changes of tx %d: %w", tx.Index, err)
+		}
+		for _, change := range changes {
+			entry := change.Post
+			if entry == nil {
+				entry = change.Pre
+			}
+			data, ok := entry.Data.GetContractData()
+			if !ok || data.Durability != xdr.ContractDataDurabilityPersistent {
+				continue
+			}
+			contractID, ok := data.Contract.GetContractId()
+			if !ok {
+				continue
+			}
+			keyXDR, err := data.Key.MarshalBinary()
+			if err != nil {
+				return nil, nil, err
+			}
+			key := contractDataKey{contractID: contractID, key: string(keyXDR)}
+			if _, seen := exists[key]; !seen {
+				changed = append(changed, key)
+			}
+			exists[key] = change.Post != nil
+		}
+	}
+	return changed, exists, nil
+}
+
+// contractDataKeysTableMigration fills the contract data keys table for the
+// stored ledgers, also removing the keys evicted by each of them.
+type contractDataKeysTableMigration struct {
+	*transactionIndexTableMigration
+}
+
+func (m contractDataKeysTableMigration) Apply(ctx context.Context, meta xdr.LedgerCloseMeta) error {
+	if err := m.transactionIndexTableMigration.Apply(ctx, meta); err != nil {
+		return err
+	}
+	return m.writer.deleteEvictedContractDataKeys(meta)
+}
+
+func newContractDataKeysTableMigration(
+	_ context.Context,
+	logger *log.Entry,
+	passphrase string,
+	ledgerSeqRange LedgerSeqRange,
+) migrationApplierFactory {
+	return migrationApplierFactoryF(func(db *DB) (MigrationApplier, error) {
+		return contractDataKeysTableMigration{
+			newTransactionIndexTableMigrationApplier(db, logger, passphrase, ledgerSeqRange,
+				(*transactionHandler).updateContractDataKeys),
+		}, nil
+	})
+}
@@ -0,0 +1,80 @@
+package db
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+)
+
+func TestContractDataKeys(t *testing.T) {
+	db := NewTestDB(t)
+	ctx := context.TODO()
+	reader := NewContractDataKeyReader(db)
+	firstLedger, err := reader.GetContractDataKeysFirstLedger(ctx)
+	require.NoError(t, err)
+	require.Zero(t, firstLedger)
+
+	contractID := xdr.ContractId{1}
+	value := xdr.Uint32(1)
+	data := xdr.ContractDataEntry{
+		Contract:   xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &contractID},
+		Key:        xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: &value},
+		Durability: xdr.ContractDataDurabilityPersistent,
+		Val:        xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: &value},
+	}
+	keyXDR, err := data.Key.MarshalBinary()
+	require.NoError(t, err)
+
+	// The first ledger creates the entry
+	created := txMeta(1, true)
+	created.V1.TxProcessing[0].TxApplyProcessing.V3.Operations = []xdr.OperationMeta{{
+		Changes: xdr.LedgerEntryChanges{{
+			Type: xdr.LedgerEntryChangeTypeLedgerEntryCreated,
+			Created: &xdr.LedgerEntry{
+				LastModifiedLedgerSeq: 101,
+				Data:                  xdr.LedgerEntryData{Type: xdr.LedgerEntryTypeContractData, ContractData: &data},
+			},
+		}},
+	}}
+	// and the second one (without transactions) evicts it
+	evicted := xdr.LedgerCloseMeta{
+		V: 1,
+		V1: &xdr.LedgerCloseMetaV1{
+			LedgerHeader: xdr.LedgerHeaderHistoryEntry{Header: xdr.LedgerHeader{LedgerSeq: 102}},
+			EvictedKeys: []xdr.LedgerKey{{
+				Type: xdr.LedgerEntryTypeContractData,
+				ContractData: &xdr.LedgerKeyContractData{
+					Contract:   data.Contract,
+					Key:        data.Key,
+					Durability: data.Durability,
+				},
+			}},
+		},
+	}
+
+	writer := NewReadWriter(log.DefaultLogger, db, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+	for i, lcm := range []xdr.LedgerCloseMeta{created, evicted} {
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+		require.NoError(t, write.TransactionWriter().InsertTransactions(lcm))
+		require.NoError(t, write.Commit(lcm))
+
+		keys, err := reader.GetContractDataKeys(ctx, contractID[:], nil, 10)
+		require.NoError(t, err)
+		if i == 0 {
+			require.Equal(t, [][]byte{keyXDR}, keys)
+		} else {
+			require.Empty(t, keys)
+		}
+	}
+
+	firstLedger, err = reader.GetContractDataKeysFirstLedger(ctx)
+	require.NoError(t, err)
+	require.EqualValues(t, 101, firstLedger)
+}
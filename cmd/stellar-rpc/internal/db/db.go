@@ -171,25 +171,30 @@ type ReadWriterMetrics struct {
 }
 
 type readWriter struct {
-	log                    *log.Entry
-	db                     *DB
-	maxBatchSize           int
-	historyRetentionWindow uint32
-	passphrase             string
+	log                        *log.Entry
+	db                         *DB
+	maxBatchSize               int
+	historyRetentionWindow     uint32
+	eventRetentionWindow       uint32
+	transactionRetentionWindow uint32
+	passphrase                 string
 
 	metrics ReadWriterMetrics
 }
 
 // NewReadWriter constructs a new readWriter instance and configures the size of
-// ledger entry batches when writing ledger entries and the retention window for
-// how many historical ledgers are recorded in the database, hooking up metrics
-// for various DB ops.
+// ledger entry batches when writing ledger entries and the retention windows for
+// how many historical ledgers (and their transactions), how many ledgers of
+// events and how many ledgers of transaction hashes are recorded in the
+// database, hooking up metrics for various DB ops.
 func NewReadWriter(
 	log *log.Entry,
 	db *DB,
 	daemon interfaces.Daemon,
 	maxBatchSize int,
 	historyRetentionWindow uint32,
+	eventRetentionWindow uint32,
+	transactionRetentionWindow uint32,
 	networkPassphrase string,
 ) ReadWriter {
 	// a metric for measuring latency of transaction store operations
@@ -211,11 +216,13 @@ func NewReadWriter(
 	daemon.MetricsRegistry().MustRegister(txDurationMetric, txCountMetric)
 
 	return &readWriter{
-		log:                    log,
-		db:                     db,
-		maxBatchSize:           maxBatchSize,
-		historyRetentionWindow: historyRetentionWindow,
-		passphrase:             networkPassphrase,
+		log:                        log,
+		db:                         db,
+		maxBatchSize:               maxBatchSize,
+		historyRetentionWindow:     historyRetentionWindow,
+		eventRetentionWindow:       eventRetentionWindow,
+		transactionRetentionWindow: transactionRetentionWindow,
+		passphrase:                 networkPassphrase,
 		metrics: ReadWriterMetrics{
 			TxIngestDuration: txDurationMetric.With(prometheus.Labels{"operation": "ingest"}),
 			TxCount:          txCountMetric,
@@ -242,10 +249,12 @@ func (rw *readWriter) NewTx(ctx context.Context) (WriteTx, error) {
 			_, err := db.ExecRaw(ctx, "PRAGMA wal_checkpoint(TRUNCATE)")
 			return err
 		},
-		tx:                     txSession,
-		stmtCache:              stmtCache,
-		historyRetentionWindow: rw.historyRetentionWindow,
-		ledgerWriter:           ledgerWriter{stmtCache: stmtCache},
+		tx:                         txSession,
+		stmtCache:                  stmtCache,
+		historyRetentionWindow:     rw.historyRetentionWindow,
+		eventRetentionWindow:       rw.eventRetentionWindow,
+		transactionRetentionWindow: rw.transactionRetentionWindow,
+		ledgerWriter:               ledgerWriter{stmtCache: stmtCache},
 
 		txWriter: transactionHandler{
 			log:        rw.log,
@@ -268,14 +277,16 @@ func (rw *readWriter) NewTx(ctx context.Context) (WriteTx, error) {
 }
 
 type writeTx struct {
-	globalCache            *dbCache
-	postCommit             func() error
-	tx                     db.SessionInterface
-	stmtCache              *sq.StmtCache
-	ledgerWriter           ledgerWriter
-	txWriter               transactionHandler
-	eventWriter            eventHandler
-	historyRetentionWindow uint32
+	globalCache                *dbCache
+	postCommit                 func() error
+	tx                         db.SessionInterface
+	stmtCache                  *sq.StmtCache
+	ledgerWriter               ledgerWriter
+	txWriter                   transactionHandler
+	eventWriter                eventHandler
+	historyRetentionWindow     uint32
+	eventRetentionWindow       uint32
+	transactionRetentionWindow uint32
 }
 
 func (w writeTx) LedgerWriter() LedgerWriter {
@@ -297,11 +308,16 @@ func (w writeTx) Commit(ledgerCloseMeta xdr.LedgerCloseMeta) error {
 	if err := w.ledgerWriter.trimLedgers(ledgerSeq, w.historyRetentionWindow); err != nil {
 		return err
 	}
-	if err := w.txWriter.trimTransactions(ledgerSeq, w.historyRetentionWindow); err != nil {
+	if err := w.txWriter.trimTransactions(ledgerSeq, w.transactionRetentionWindow); err != nil {
 		return err
 	}
+	for _, table := range []string{transactionContractsTableName, transactionAuthSignersTableName} {
+		if err := w.txWriter.trimTransactionIndex(table, ledgerSeq, w.historyRetentionWindow); err != nil {
+			return err
+		}
+	}
 
-	if err := w.eventWriter.trimEvents(ledgerSeq, w.historyRetentionWindow); err != nil {
+	if err := w.eventWriter.trimEvents(ledgerSeq, w.eventRetentionWindow); err != nil {
 		return err
 	}
 
@@ -2,6 +2,7 @@ package db
 
 import (
 	"context"
+	"database/sql"
 	"errors"
 	"fmt"
 	"io"
@@ -15,6 +16,7 @@ import (
 	"github.com/stellar/go/support/log"
 	"github.com/stellar/go/xdr"
 
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
 	"github.com/stellar/stellar-rpc/protocol"
 )
 
@@ -35,11 +37,15 @@ type EventReader interface {
 	GetEvents(
 		ctx context.Context,
 		cursorRange protocol.CursorRange,
+		order string,
 		contractIDs [][]byte,
 		topics NestedTopicArray,
 		eventTypes []int,
+		transactionHash *xdr.Hash,
 		f ScanFunction,
 	) error
+	GetOldestLedger(ctx context.Context) (ledgerbucketwindow.LedgerInfo, bool, error)
+	GetLedgerByCloseTime(ctx context.Context, closeTime int64) (uint32, bool, error)
 }
 
 type eventHandler struct {
@@ -54,17 +60,33 @@ func NewEventReader(log *log.Entry, db db.SessionInterface, passphrase string) E
 }
 
 func transactionEventsIntoDiagnosticEvents(allEvents ingest.TransactionEvents) []xdr.DiagnosticEvent {
+	diagEvents, _ := transactionEventsWithOperationIndexes(allEvents)
+	return diagEvents
+}
+
+// transactionEventsWithOperationIndexes returns the events of
+// transactionEventsIntoDiagnosticEvents along with the index of the operation
+// which emitted each of them. It is 0 for the events of Soroban transactions,
+// which have a single operation, and nil for the events of the transaction
+// itself.
+func transactionEventsWithOperationIndexes(allEvents ingest.TransactionEvents) ([]xdr.DiagnosticEvent, []*uint32) {
 	diagEvents := allEvents.DiagnosticEvents
+	opIndexes := make([]*uint32, len(diagEvents))
+	for i := range opIndexes {
+		opIndexes[i] = new(uint32)
+	}
 
 	// For smart contract transactions/operations, txMetaV3 and txMetaV4 will already include
 	// contract events in the diagnostic events.
 	if len(diagEvents) == 0 {
-		for _, opEvents := range allEvents.OperationEvents {
+		for opIndex, opEvents := range allEvents.OperationEvents {
 			for _, event := range opEvents {
 				diagEvents = append(diagEvents, xdr.DiagnosticEvent{
 					InSuccessfulContractCall: true,
 					Event:                    event,
 				})
+				opIndex32 := uint32(opIndex) //nolint:gosec
+				opIndexes = append(opIndexes, &opIndex32)
 			}
 		}
 	}
@@ -74,9 +96,62 @@ func transactionEventsIntoDiagnosticEvents(allEvents ingest.TransactionEvents) [
 			InSuccessfulContractCall: true,
 			Event:                    event.Event,
 		})
+		opIndexes = append(opIndexes, nil)
 	}
 
-	return diagEvents
+	return diagEvents, opIndexes
+}
+
+// Event is an event of a ledger along with what the events table stores about
+// it.
+type Event struct {
+	Cursor          protocol.Cursor
+	Event           xdr.DiagnosticEvent
+	LedgerCloseTime int64
+	TransactionHash xdr.Hash
+	OpIndex         *uint32 // nil for the events of the transaction itself
+}
+
+// ParseLedgerEvents returns the events of a ledger which InsertEvents stores,
+// i.e. those of its successful transactions, in the order of their cursors.
+func ParseLedgerEvents(passphrase string, lcm xdr.LedgerCloseMeta) ([]Event, error) {
+	txReader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(passphrase, lcm)
+	if err != nil {
+		return nil, errors.Join(err,
+			fmt.Errorf("failed to open transaction reader for ledger %d", lcm.LedgerSequence()),
+		)
+	}
+	defer txReader.Close()
+
+	var events []Event
+	for {
+		tx, err := txReader.Read()
+		if errors.Is(err, io.EOF) {
+			return events, nil
+		} else if err != nil {
+			return nil, err
+		}
+
+		if !tx.Result.Successful() {
+			continue
+		}
+
+		allEvents, err := tx.GetTransactionEvents()
+		if err != nil {
+			return nil, err
+		}
+
+		diagEvents, opIndexes := transactionEventsWithOperationIndexes(allEvents)
+		for index, e := range diagEvents {
+			events = append(events, Event{
+				Cursor:          protocol.Cursor{Ledger: lcm.LedgerSequence(), Tx: tx.Index, Event: uint32(index)}, //nolint:gosec
+				Event:           e,
+				LedgerCloseTime: lcm.LedgerCloseTime(),
+				TransactionHash: tx.Result.TransactionHash,
+				OpIndex:         opIndexes[index],
+			})
+		}
+	}
 }
 
 func (eventHandler *eventHandler) InsertEvents(lcm xdr.LedgerCloseMeta) error {
@@ -120,7 +195,7 @@ func (eventHandler *eventHandler) InsertEvents(lcm xdr.LedgerCloseMeta) error {
 			return err
 		}
 
-		diagEvents := transactionEventsIntoDiagnosticEvents(allEvents)
+		diagEvents, opIndexes := transactionEventsWithOperationIndexes(allEvents)
 
 		if len(diagEvents) == 0 {
 			continue
@@ -135,6 +210,7 @@ func (eventHandler *eventHandler) InsertEvents(lcm xdr.LedgerCloseMeta) error {
 				"ledger_close_time",
 				"transaction_hash",
 				"topic1", "topic2", "topic3", "topic4",
+				"operation_index",
 			)
 
 		for index, e := range diagEvents {
@@ -173,6 +249,7 @@ func (eventHandler *eventHandler) InsertEvents(lcm xdr.LedgerCloseMeta) error {
 				lcm.LedgerCloseTime(),
 				transactionHash,
 				topicList[0], topicList[1], topicList[2], topicList[3],
+				opIndexes[index],
 			)
 		}
 		// Ignore the last inserted ID as it is not needed
@@ -190,6 +267,7 @@ type ScanFunction func(
 	cursor protocol.Cursor,
 	ledgerCloseTimestamp int64,
 	txHash *xdr.Hash,
+	opIndex *uint32, // nil for the events of the transaction itself and the ones stored without it
 ) bool
 
 // trimEvents removes all Events which fall outside the ledger retention window.
@@ -208,6 +286,83 @@ func (eventHandler *eventHandler) trimEvents(latestLedgerSeq uint32, retentionWi
 	return err
 }
 
+// GetOldestLedger returns the ledger of the oldest stored event, which precedes
+// the oldest stored ledger when events are retained for longer than ledgers.
+// It returns false if there are no events.
+func (eventHandler *eventHandler) GetOldestLedger(ctx context.Context) (ledgerbucketwindow.LedgerInfo, bool, error) {
+	return eventHandler.getEventLedger(ctx, nil, "id ASC")
+}
+
+// GetLedgerByCloseTime returns the ledger of the oldest stored event closing at
+// or after closeTime (in seconds since the unix epoch). It returns false if
+// there is none.
+//
+// Close times aren't indexed, but they increase along with the event ids, so
+// the ledger is binary searched by id.
+func (eventHandler *eventHandler) GetLedgerByCloseTime(ctx context.Context, closeTime int64) (uint32, bool, error) {
+	oldest, ok, err := eventHandler.getEventLedger(ctx, nil, "id ASC")
+	if err != nil || !ok {
+		return 0, false, err
+	}
+	if oldest.CloseTime >= closeTime {
+		return oldest.Sequence, true, nil
+	}
+	latest, _, err := eventHandler.getEventLedger(ctx, nil, "id DESC")
+	if err != nil {
+		return 0, false, err
+	}
+	if latest.CloseTime < closeTime {
+		return 0, false, nil
+	}
+
+	// The oldest event closes before closeTime and the latest one doesn't
+	low, high := oldest.Sequence+1, latest.Sequence
+	result := latest.Sequence
+	for low < high {
+		mid := low + (high-low)/2
+		start := protocol.Cursor{Ledger: mid}.String()
+		ledger, _, err := eventHandler.getEventLedger(ctx, sq.GtOrEq{"id": start}, "id ASC")
+		if err != nil {
+			return 0, false, err
+		}
+		if ledger.CloseTime >= closeTime {
+			high = mid
+			result = ledger.Sequence
+		} else {
+			low = mid + 1
+		}
+	}
+	return result, true, nil
+}
+
+// getEventLedger returns the ledger of the first event matching where in the
+// given order.
+func (eventHandler *eventHandler) getEventLedger(ctx context.Context, where sq.Sqlizer, order string,
+) (ledgerbucketwindow.LedgerInfo, bool, error) {
+	query := sq.Select("id", "ledger_close_time").
+		From(eventTableName).
+		OrderBy(order).
+		Limit(1)
+	if where != nil {
+		query = query.Where(where)
+	}
+	var rows []struct {
+		ID              string `db:"id"`
+		LedgerCloseTime int64  `db:"ledger_close_time"`
+	}
+	if err := eventHandler.db.Select(ctx, &rows, query); err != nil {
+		return ledgerbucketwindow.LedgerInfo{}, false, fmt.Errorf("couldn't query event ledger: %w", err)
+	}
+	if len(rows) == 0 {
+		return ledgerbucketwindow.LedgerInfo{}, false, nil
+	}
+	cursor, err := protocol.ParseCursor(rows[0].ID)
+	if err != nil {
+		return ledgerbucketwindow.LedgerInfo{}, false, errors.Join(err, errors.New("failed to parse cursor"))
+	}
+	return ledgerbucketwindow.LedgerInfo{Sequence: cursor.Ledger, CloseTime: rows[0].LedgerCloseTime}, true, nil
+}
+
 // GetEvents applies f on all the events occurring in the given range with
 // specified contract IDs if provided. The events are returned in sorted
 // ascending Cursor order.
@@ -219,19 +374,26 @@ func (eventHandler *eventHandler) trimEvents(latestLedgerSeq uint32, retentionWi
 func (eventHandler *eventHandler) GetEvents(
 	ctx context.Context,
 	cursorRange protocol.CursorRange,
+	order string,
 	contractIDs [][]byte,
 	topics NestedTopicArray,
 	eventTypes []int,
+	transactionHash *xdr.Hash,
 	f ScanFunction,
 ) error {
 	start := time.Now()
 
+	orderBy := "id ASC"
+	if order == protocol.EventOrderDesc {
+		orderBy = "id DESC"
+	}
+
 	rowQ := sq.
-		Select(" id", "event_data", "transaction_hash", "ledger_close_time").
+		Select(" id", "event_data", "transaction_hash", "ledger_close_time", "operation_index").
 		From(eventTableName).
 		Where(sq.GtOrEq{"id": cursorRange.Start.String()}).
 		Where(sq.Lt{"id": cursorRange.End.String()}).
-		OrderBy("id ASC")
+		OrderBy(orderBy)
 
 	if len(contractIDs) > 0 {
 		rowQ = rowQ.Where(sq.Eq{"contract_id": contractIDs})
@@ -239,6 +401,9 @@ func (eventHandler *eventHandler) GetEvents(
 	if len(eventTypes) > 0 {
 		rowQ = rowQ.Where(sq.Eq{"event_type": eventTypes})
 	}
+	if transactionHash != nil {
+		rowQ = rowQ.Where(sq.Eq{"transaction_hash": transactionHash[:]})
+	}
 
 	if len(topics) > 0 {
 		var orConditions sq.Or
@@ -288,9 +453,11 @@ func (eventHandler *eventHandler) GetEvents(
 			eventData       []byte `db:"event_data"`
 			transactionHash []byte `db:"transaction_hash"`
 			ledgerCloseTime int64  `db:"ledger_close_time"`
+			// opIndex is NULL for the events ingested before it was stored
+			opIndex sql.NullInt64 `db:"operation_index"`
 		}
 
-		err = rows.Scan(&row.eventCursorID, &row.eventData, &row.transactionHash, &row.ledgerCloseTime)
+		err = rows.Scan(&row.eventCursorID, &row.eventData, &row.transactionHash, &row.ledgerCloseTime, &row.opIndex)
 		if err != nil {
 			return fmt.Errorf("failed to scan row: %w", err)
 		}
@@ -308,7 +475,12 @@ func (eventHandler *eventHandler) GetEvents(
 			return errors.Join(err, errors.New("failed to decode event"))
 		}
 		txHash := xdr.Hash(transactionHash)
-		if !f(eventXDR, cur, ledgerCloseTime, &txHash) {
+		var opIndex *uint32
+		if row.opIndex.Valid {
+			index := uint32(row.opIndex.Int64) //nolint:gosec
+			opIndex = &index
+		}
+		if !f(eventXDR, cur, ledgerCloseTime, &txHash, opIndex) {
 			return nil
 		}
 	}
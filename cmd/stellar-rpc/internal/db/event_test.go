@@ -2,12 +2,15 @@ package db
 
 import (
 	"context"
+	"fmt"
+	"strings"
 	"testing"
 	"time"
 
 	"github.com/sirupsen/logrus"
 	"github.com/stretchr/testify/require"
 
+	"github.com/stellar/go/ingest"
 	"github.com/stellar/go/keypair"
 	"github.com/stellar/go/network"
 	"github.com/stellar/go/support/log"
@@ -142,7 +145,7 @@ func TestInsertEvents(t *testing.T) {
 	log.SetLevel(logrus.TraceLevel)
 	now := time.Now().UTC()
 
-	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 	write, err := writer.NewTx(ctx)
 	require.NoError(t, err)
 	contractID := xdr.ContractId([32]byte{})
@@ -175,6 +178,136 @@ func TestInsertEvents(t *testing.T) {
 	end := protocol.Cursor{Ledger: 100}
 	cursorRange := protocol.CursorRange{Start: start, End: end}
 
-	err = eventReader.GetEvents(ctx, cursorRange, nil, nil, nil, nil)
+	err = eventReader.GetEvents(ctx, cursorRange, protocol.EventOrderAsc, nil, nil, nil, nil, nil)
 	require.NoError(t, err)
 }
+
+func TestEventRetentionWindow(t *testing.T) {
+	db := NewTestDB(t)
+	ctx := context.TODO()
+	log := log.DefaultLogger
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	event := contractEvent(xdr.ContractId([32]byte{}), xdr.ScVec{counterScVal}, counterScVal)
+
+	// Ledgers are kept for 2 ledgers and events for 4
+	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 10, 2, 4, 2, passphrase)
+	for sequence := uint32(1); sequence <= 6; sequence++ {
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(sequence, int64(sequence), transactionMetaWithEvents(event))
+		require.NoError(t, write.LedgerWriter().InsertLedger(ledgerCloseMeta))
+		require.NoError(t, write.EventWriter().InsertEvents(ledgerCloseMeta))
+		require.NoError(t, write.Commit(ledgerCloseMeta))
+	}
+
+	ledgerRange, err := NewLedgerReader(db).GetLedgerRange(ctx)
+	require.NoError(t, err)
+	require.Equal(t, uint32(5), ledgerRange.FirstLedger.Sequence)
+
+	eventReader := NewEventReader(log, db, passphrase)
+	oldest, ok, err := eventReader.GetOldestLedger(ctx)
+	require.NoError(t, err)
+	require.True(t, ok)
+	require.Equal(t, uint32(3), oldest.Sequence)
+	require.Equal(t, int64(3), oldest.CloseTime)
+
+	var ledgers []uint32
+	cursorRange := protocol.CursorRange{Start: protocol.Cursor{Ledger: 1}, End: protocol.Cursor{Ledger: 7}}
+	require.NoError(t, eventReader.GetEvents(ctx, cursorRange, protocol.EventOrderAsc, nil, nil, nil, nil,
+		func(_ xdr.DiagnosticEvent, cursor protocol.Cursor, _ int64, _ *xdr.Hash, _ *uint32) bool {
+			ledgers = append(ledgers, cursor.Ledger)
+			return true
+		}))
+	require.Equal(t, []uint32{3, 4, 5, 6}, ledgers)
+}
+
+func TestGetLedgerByCloseTime(t *testing.T) {
+	db := NewTestDB(t)
+	ctx := context.TODO()
+	log := log.DefaultLogger
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	event := contractEvent(xdr.ContractId([32]byte{}), xdr.ScVec{counterScVal}, counterScVal)
+
+	// Ledgers close every 5 seconds, and ledgers 4 and 5 have no events
+	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+	for sequence := uint32(1); sequence <= 8; sequence++ {
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+		var txMeta []xdr.TransactionMeta
+		if sequence != 4 && sequence != 5 {
+			txMeta = append(txMeta, transactionMetaWithEvents(event))
+		}
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(sequence, int64(sequence)*5, txMeta...)
+		require.NoError(t, write.LedgerWriter().InsertLedger(ledgerCloseMeta))
+		require.NoError(t, write.EventWriter().InsertEvents(ledgerCloseMeta))
+		require.NoError(t, write.Commit(ledgerCloseMeta))
+	}
+
+	eventReader := NewEventReader(log, db, passphrase)
+	for _, testCase := range []struct {
+		closeTime int64
+		ledger    uint32
+		ok        bool
+	}{
+		{0, 1, true},
+		{5, 1, true},
+		{6, 2, true},
+		{15, 3, true},
+		{16, 6, true},
+		{40, 8, true},
+		{41, 0, false},
+	} {
+		ledger, ok, err := eventReader.GetLedgerByCloseTime(ctx, testCase.closeTime)
+		require.NoError(t, err)
+		require.Equal(t, testCase.ok, ok, testCase.closeTime)
+		require.Equal(t, testCase.ledger, ledger, testCase.closeTime)
+	}
+}
+
+func TestEventTopicIndexes(t *testing.T) {
+	db := NewTestDB(t)
+	ctx := context.TODO()
+
+	for _, column := range []string{"contract_id", "topic1", "topic2", "topic3", "topic4"} {
+		var plan []struct {
+			ID      int    `db:"id"`
+			Parent  int    `db:"parent"`
+			NotUsed int    `db:"notused"`
+			Detail  string `db:"detail"`
+		}
+		query := fmt.Sprintf("EXPLAIN QUERY PLAN SELECT id FROM events WHERE %s IN (?, ?) AND id >= ? AND id < ?",
+			column)
+		require.NoError(t, db.SelectRaw(ctx, &plan, query, []byte{1}, []byte{2}, "a", "b"))
+		details := make([]string, 0, len(plan))
+		for _, step := range plan {
+			details = append(details, step.Detail)
+		}
+		// The range of ids is looked up within the index too
+		require.Contains(t, strings.Join(details, "\n"),
+			fmt.Sprintf("USING INDEX idx_%s (%s=? AND id>? AND id<?)", column, column))
+	}
+}
+
+func TestTransactionEventsWithOperationIndexes(t *testing.T) {
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	event := contractEvent(xdr.ContractId([32]byte{}), xdr.ScVec{counterScVal}, counterScVal)
+
+	events, opIndexes := transactionEventsWithOperationIndexes(ingest.TransactionEvents{
+		OperationEvents:   [][]xdr.ContractEvent{{event}, {}, {event, event}},
+		TransactionEvents: []xdr.TransactionEvent{{Event: event}},
+	})
+	zero, two := uint32(0), uint32(2)
+	require.Len(t, events, 4)
+	require.Equal(t, []*uint32{&zero, &two, &two, nil}, opIndexes)
+
+	// The events of Soroban transactions come from their diagnostic events
+	events, opIndexes = transactionEventsWithOperationIndexes(ingest.TransactionEvents{
+		OperationEvents:  [][]xdr.ContractEvent{{event}},
+		DiagnosticEvents: []xdr.DiagnosticEvent{{Event: event}, {Event: event}},
+	})
+	require.Len(t, events, 2)
+	require.Equal(t, []*uint32{&zero, &zero}, opIndexes)
+}
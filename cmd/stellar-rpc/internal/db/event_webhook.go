@@ -0,0 +1,97 @@
+package db
+
+import (
+	"context"
+	"fmt"
+
+	sq "github.com/Masterminds/squirrel"
+
+	"github.com/stellar/go/support/db"
+)
+
+const eventWebhookTableName = "event_webhooks"
+
+// EventWebhook is a registered event webhook. Request is the JSON-encoded
+// getEvents request of its first delivery, Cursor (empty until the first
+// delivery) the cursor its next delivery resumes from and Failures the number
+// of its consecutive failed delivery rounds.
+type EventWebhook struct {
+	ID       string `db:"id"`
+	URL      string `db:"url"`
+	Secret   string `db:"secret"`
+	Request  string `db:"request"`
+	Cursor   string `db:"cursor"`
+	Failures uint   `db:"failures"`
+}
+
+// EventWebhookStore stores the event webhooks along with the progress of their
+// deliveries.
+type EventWebhookStore interface {
+	InsertEventWebhook(ctx context.Context, webhook EventWebhook) error
+	// DeleteEventWebhook returns false if there is no webhook with the given id
+	DeleteEventWebhook(ctx context.Context, id string) (bool, error)
+	GetEventWebhooks(ctx context.Context) ([]EventWebhook, error)
+	UpdateEventWebhookCursor(ctx context.Context, id string, cursor string) error
+	UpdateEventWebhookFailures(ctx context.Context, id string, failures uint) error
+}
+
+type eventWebhookStore struct {
+	db db.SessionInterface
+}
+
+func NewEventWebhookStore(db db.SessionInterface) EventWebhookStore {
+	return eventWebhookStore{db: db}
+}
+
+func (s eventWebhookStore) InsertEventWebhook(ctx context.Context, webhook EventWebhook) error {
+	query := sq.Insert(eventWebhookTableName).
+		Columns("id", "url", "secret", "request", "cursor").
+		Values(webhook.ID, webhook.URL, webhook.Secret, webhook.Request, webhook.Cursor)
+	if _, err := s.db.Exec(ctx, query); err != nil {
+		return fmt.Errorf("could not insert event webhook: %w", err)
+	}
+	return nil
+}
+
+func (s eventWebhookStore) DeleteEventWebhook(ctx context.Context, id string) (bool, error) {
+	result, err := s.db.Exec(ctx, sq.Delete(eventWebhookTableName).Where(sq.Eq{"id": id}))
+	if err != nil {
+		return false, fmt.Errorf("could not delete event webhook: %w", err)
+	}
+	deleted, err := result.RowsAffected()
+	if err != nil {
+		return false, err
+	}
+	return deleted > 0, nil
+}
+
+func (s eventWebhookStore) GetEventWebhooks(ctx context.Context) ([]EventWebhook, error) {
+	query := sq.Select("id", "url", "secret", "request", "cursor", "failures").
+		From(eventWebhookTableName).
+		OrderBy("id ASC")
+	var webhooks []EventWebhook
+	if err := s.db.Select(ctx, &webhooks, query); err != nil {
+		return nil, fmt.Errorf("could not query event webhooks: %w", err)
+	}
+	return webhooks, nil
+}
+
+func (s eventWebhookStore) UpdateEventWebhookCursor(ctx context.Context, id string, cursor string) error {
+	query := sq.Update(eventWebhookTableName).
+		Set("cursor", cursor).
+		Where(sq.Eq{"id": id})
+	if _, err := s.db.Exec(ctx, query); err != nil {
+		return fmt.Errorf("could not update event webhook cursor: %w", err)
+	}
+	return nil
+}
+
+func (s eventWebhookStore) UpdateEventWebhookFailures(ctx context.Context, id string, failures uint) error {
+	query := sq.Update(eventWebhookTableName).
+		Set("failures", failures).
+		Where(sq.Eq{"id": id})
+	if _, err := s.db.Exec(ctx, query); err != nil {
+		return fmt.Errorf("could not update event webhook failures: %w", err)
+	}
+	return nil
+}
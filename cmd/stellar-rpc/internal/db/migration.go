@@ -10,8 +10,11 @@ import (
 )
 
 const (
-	transactionsMigrationName = "TransactionsTable"
-	eventsMigrationName       = "EventsTable"
+	transactionsMigrationName           = "TransactionsTable"
+	eventsMigrationName                 = "EventsTable"
+	transactionContractsMigrationName   = "TransactionContractsTable"
+	transactionAuthSignersMigrationName = "TransactionAuthSignersTable"
+	contractDataKeysMigrationName       = "ContractDataKeysTable"
 )
 
 type LedgerSeqRange struct {
@@ -208,8 +211,11 @@ func BuildMigrations(
 	// Add new DB migrations here:
 	//
 	currentMigrations := map[string]migrationApplierF{
-		transactionsMigrationName: newTransactionTableMigration,
-		eventsMigrationName:       newEventTableMigration,
+		transactionsMigrationName:           newTransactionTableMigration,
+		eventsMigrationName:                 newEventTableMigration,
+		transactionContractsMigrationName:   newTransactionContractsTableMigration,
+		transactionAuthSignersMigrationName: newTransactionAuthSignersTableMigration,
+		contractDataKeysMigrationName:       newContractDataKeysTableMigration,
 	}
 
 	migrations := make([]Migration, 0, len(currentMigrations))
@@ -1,9 +1,13 @@
 package db
 
 import (
+	"bytes"
+	"cmp"
 	"context"
 	"errors"
 	"io"
+	"math"
+	"slices"
 
 	"github.com/prometheus/client_golang/prometheus"
 
@@ -11,6 +15,7 @@ import (
 	"github.com/stellar/go/xdr"
 
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
+	"github.com/stellar/stellar-rpc/protocol"
 )
 
 type MockTransactionHandler struct {
@@ -77,6 +82,80 @@ func (txn *MockTransactionHandler) GetTransaction(_ context.Context, hash xdr.Ha
 	return itx, err
 }
 
+func (txn *MockTransactionHandler) GetTransactionPosition(_ context.Context, hash xdr.Hash) (
+	TransactionPosition, error,
+) {
+	tx, ok := txn.txs[hash.HexString()]
+	if !ok {
+		return TransactionPosition{}, ErrNoTransaction
+	}
+	return TransactionPosition{
+		LedgerSequence:   txn.txHashToMeta[hash.HexString()].LedgerSequence(),
+		ApplicationOrder: int32(tx.Index), //nolint:gosec
+	}, nil
+}
+
+func (txn *MockTransactionHandler) GetContractTransactions(_ context.Context, contractIDs [][]byte,
+	start TransactionPosition, endLedger uint32, order string, limit uint,
+) ([]TransactionPosition, error) {
+	return txn.getTransactionPositions(start, endLedger, order, limit, func(tx ingest.LedgerTransaction) (bool, error) {
+		txContractIDs, err := TransactionContractIDs(tx)
+		return slices.ContainsFunc(txContractIDs, func(id xdr.ContractId) bool {
+			return slices.ContainsFunc(contractIDs, func(contractID []byte) bool {
+				return bytes.Equal(contractID, id[:])
+			})
+		}), err
+	})
+}
+
+func (txn *MockTransactionHandler) GetAuthSignerTransactions(_ context.Context, signer string,
+	start TransactionPosition, endLedger uint32, order string, limit uint,
+) ([]TransactionPosition, error) {
+	return txn.getTransactionPositions(start, endLedger, order, limit, func(tx ingest.LedgerTransaction) (bool, error) {
+		signers, err := TransactionAuthSigners(tx)
+		return slices.Contains(signers, signer), err
+	})
+}
+
+// getTransactionPositions returns the positions of up to limit matching
+// transactions, from start to endLedger in the given order.
+func (txn *MockTransactionHandler) getTransactionPositions(start TransactionPosition, endLedger uint32,
+	order string, limit uint, matches func(ingest.LedgerTransaction) (bool, error),
+) ([]TransactionPosition, error) {
+	// Positions are compared in the order of the scan
+	compare := comparePositions
+	if order == protocol.TransactionsOrderDesc {
+		compare = func(a, b TransactionPosition) int { return comparePositions(b, a) }
+	}
+	end := TransactionPosition{LedgerSequence: endLedger, ApplicationOrder: math.MaxInt32}
+	if order == protocol.TransactionsOrderDesc {
+		end.ApplicationOrder = 0
+	}
+	var positions []TransactionPosition
+	for hash, tx := range txn.txs {
+		position := TransactionPosition{
+			LedgerSequence:   txn.txHashToMeta[hash].LedgerSequence(),
+			ApplicationOrder: int32(tx.Index), //nolint:gosec
+		}
+		if compare(position, start) < 0 || compare(position, end) > 0 {
+			continue
+		}
+		ok, err := matches(tx)
+		if err != nil {
+			return nil, err
+		}
+		if ok {
+			positions = append(positions, position)
+		}
+	}
+	slices.SortFunc(positions, compare)
+	return positions[:min(len(positions), int(limit))], nil
+}
+
+func comparePositions(a, b TransactionPosition) int {
+	return cmp.Or(cmp.Compare(a.LedgerSequence, b.LedgerSequence), cmp.Compare(a.ApplicationOrder, b.ApplicationOrder))
+}
+
 func (txn *MockTransactionHandler) RegisterMetrics(_, _ prometheus.Observer) {}
 
 type MockLedgerReader struct {
@@ -110,7 +189,7 @@ func (m *MockLedgerReader) GetLedgerRange(_ context.Context) (ledgerbucketwindow
 }
 
 func (m *MockLedgerReader) GetLatestLedgerSequence(_ context.Context) (uint32, error) {
-	return 0, nil
+	return m.txn.ledgerRange.LastLedger.Sequence, nil
 }
 
 func (m *MockLedgerReader) NewTx(_ context.Context) (LedgerReaderTx, error) {
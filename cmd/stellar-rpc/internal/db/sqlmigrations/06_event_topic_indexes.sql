@@ -0,0 +1,21 @@
+-- +migrate Up
+
+-- index events by each of their topic segments (and by contract id) along with
+-- their id, so that filtered event queries only look up the matching events
+-- within the requested range
+DROP INDEX idx_contract_id;
+DROP INDEX idx_topic1;
+CREATE INDEX idx_contract_id ON events (contract_id, id);
+CREATE INDEX idx_topic1 ON events (topic1, id);
+CREATE INDEX idx_topic2 ON events (topic2, id);
+CREATE INDEX idx_topic3 ON events (topic3, id);
+CREATE INDEX idx_topic4 ON events (topic4, id);
+
+-- +migrate Down
+DROP INDEX idx_topic4;
+DROP INDEX idx_topic3;
+DROP INDEX idx_topic2;
+DROP INDEX idx_topic1;
+DROP INDEX idx_contract_id;
+CREATE INDEX idx_contract_id ON events (contract_id);
+CREATE INDEX idx_topic1 ON events (topic1);
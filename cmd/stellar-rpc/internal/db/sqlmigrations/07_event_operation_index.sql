@@ -0,0 +1,7 @@
+-- +migrate Up
+
+-- index of the operation which emitted the event within its transaction
+ALTER TABLE events ADD COLUMN operation_index INTEGER;
+
+-- +migrate Down
+ALTER TABLE events DROP COLUMN operation_index;
@@ -0,0 +1,15 @@
+-- +migrate Up
+
+-- event webhooks, along with the getEvents request (as JSON) of their first
+-- delivery and the cursor their next delivery resumes from
+CREATE TABLE event_webhooks
+(
+    id      TEXT PRIMARY KEY,
+    url     TEXT NOT NULL,
+    secret  TEXT NOT NULL,
+    request TEXT NOT NULL,
+    cursor  TEXT NOT NULL
+);
+
+-- +migrate Down
+DROP TABLE event_webhooks;
@@ -0,0 +1,14 @@
+-- +migrate Up
+
+-- indexing table to find the transactions authorized by an address
+CREATE TABLE transaction_auth_signers (
+    signer TEXT NOT NULL, -- strkey address (G... or C...)
+    ledger_sequence INTEGER NOT NULL,
+    application_order INTEGER NOT NULL,
+    PRIMARY KEY (signer, ledger_sequence, application_order)
+);
+
+CREATE INDEX index_transaction_auth_signers_ledger_sequence ON transaction_auth_signers(ledger_sequence);
+
+-- +migrate Down
+drop table transaction_auth_signers cascade;
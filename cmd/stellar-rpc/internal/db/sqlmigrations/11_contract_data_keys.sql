@@ -0,0 +1,11 @@
+-- +migrate Up
+
+-- indexing table to enumerate the persistent contract data entries of a contract
+CREATE TABLE contract_data_keys (
+    contract_id BLOB NOT NULL, -- 32-byte binary
+    key BLOB NOT NULL, -- ScVal XDR
+    PRIMARY KEY (contract_id, key)
+);
+
+-- +migrate Down
+drop table contract_data_keys cascade;
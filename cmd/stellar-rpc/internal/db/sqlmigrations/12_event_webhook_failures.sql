@@ -0,0 +1,7 @@
+-- +migrate Up
+
+-- number of consecutive failed delivery rounds of the webhook
+ALTER TABLE event_webhooks ADD COLUMN failures INTEGER NOT NULL DEFAULT 0;
+
+-- +migrate Down
+ALTER TABLE event_webhooks DROP COLUMN failures;
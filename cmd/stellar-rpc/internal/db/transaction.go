@@ -16,6 +16,7 @@ import (
 	"github.com/stellar/go/xdr"
 
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
+	"github.com/stellar/stellar-rpc/protocol"
 )
 
 const (
@@ -34,6 +35,7 @@ type Transaction struct {
 	ApplicationOrder int32
 	Successful       bool
 	Ledger           ledgerbucketwindow.LedgerInfo
+	Fees             protocol.TransactionFees
 }
 
 // TransactionWriter is used during ingestion to write LCM.
@@ -45,6 +47,11 @@ type TransactionWriter interface {
 // TransactionReader provides all the public ways to read from the DB.
 type TransactionReader interface {
 	GetTransaction(ctx context.Context, hash xdr.Hash) (Transaction, error)
+	GetTransactionPosition(ctx context.Context, hash xdr.Hash) (TransactionPosition, error)
+	GetContractTransactions(ctx context.Context, contractIDs [][]byte, start TransactionPosition,
+		endLedger uint32, order string, limit uint) ([]TransactionPosition, error)
+	GetAuthSignerTransactions(ctx context.Context, signer string, start TransactionPosition,
+		endLedger uint32, order string, limit uint) ([]TransactionPosition, error)
 }
 
 type transactionHandler struct {
@@ -77,7 +84,8 @@ func (txn *transactionHandler) InsertTransactions(lcm xdr.LedgerCloseMeta) error
 	if txn.stmtCache == nil {
 		return errors.New("TransactionWriter incorrectly initialized without stmtCache")
 	} else if txCount == 0 {
-		return nil
+		// Ledgers without transactions still evict entries
+		return txn.deleteEvictedContractDataKeys(lcm)
 	}
 
 	reader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(txn.passphrase, lcm)
@@ -90,11 +98,13 @@ func (txn *transactionHandler) InsertTransactions(lcm xdr.LedgerCloseMeta) error
 	}
 
 	transactions := make(map[xdr.Hash]ingest.LedgerTransaction, txCount)
+	ledgerTxs := make([]ingest.LedgerTransaction, 0, txCount)
 	for i := range txCount {
 		tx, err := reader.Read()
 		if err != nil {
 			return fmt.Errorf("failed reading tx %d: %w", i, err)
 		}
+		ledgerTxs = append(ledgerTxs, tx)
 
 		// For fee-bump transactions, we store lookup entries for both the outer
 		// and inner hashes.
@@ -109,12 +119,26 @@ func (txn *transactionHandler) InsertTransactions(lcm xdr.LedgerCloseMeta) error
 	for hash, tx := range transactions {
 		query = query.Values(hash[:], lcm.LedgerSequence(), tx.Index)
 	}
-	_, err = query.RunWith(txn.stmtCache).Exec()
+	if _, err = query.RunWith(txn.stmtCache).Exec(); err != nil {
+		return err
+	}
+	if err = txn.insertTransactionContracts(lcm.LedgerSequence(), ledgerTxs); err != nil {
+		return err
+	}
+	if err = txn.insertTransactionAuthSigners(lcm.LedgerSequence(), ledgerTxs); err != nil {
+		return err
+	}
+	if err = txn.updateContractDataKeys(lcm.LedgerSequence(), ledgerTxs); err != nil {
+		return err
+	}
+	if err = txn.deleteEvictedContractDataKeys(lcm); err != nil {
+		return err
+	}
 
 	L.WithField("duration", time.Since(start)).
 		Debugf("Ingested %d transaction lookups", len(transactions))
 
-	return err
+	return nil
 }
 
 func (txn *transactionHandler) RegisterMetrics(ingest, count prometheus.Observer) {
@@ -122,7 +146,8 @@ func (txn *transactionHandler) RegisterMetrics(ingest, count prometheus.Observer
 	txn.countMetric = count
 }
 
-// trimTransactions removes all transactions which fall outside the ledger retention window.
+// trimTransactions removes the hashes of all the transactions which fall
+// outside the transaction retention window.
 func (txn *transactionHandler) trimTransactions(latestLedgerSeq uint32, retentionWindow uint32) error {
 	if latestLedgerSeq+1 <= retentionWindow {
 		return nil
@@ -166,6 +191,25 @@ func (txn *transactionHandler) GetTransaction(ctx context.Context, hash xdr.Hash
 	return tx, nil
 }
 
+// GetTransactionPosition looks up the ledger and the application order of a
+// transaction by its hash, which stay indexed for the transaction retention
+// window even after its ledger is trimmed.
+func (txn *transactionHandler) GetTransactionPosition(ctx context.Context, hash xdr.Hash) (
+	TransactionPosition, error,
+) {
+	var positions []TransactionPosition
+	query := sq.Select("ledger_sequence", "application_order").
+		From(transactionTableName).
+		Where(sq.Eq{"hash": hash[:]}).
+		Limit(1)
+	if err := txn.db.Select(ctx, &positions, query); err != nil {
+		return TransactionPosition{}, fmt.Errorf("db read failed for txhash %s: %w", hex.EncodeToString(hash[:]), err)
+	} else if len(positions) < 1 {
+		return TransactionPosition{}, ErrNoTransaction
+	}
+	return positions[0], nil
+}
+
 // getTransactionByHash actually performs the DB ops to cross-reference a
 // transaction hash with a particular set of ledger close meta and parses out
 // the relevant transaction efficiently by leveraging the `application_order` db
@@ -225,6 +269,7 @@ func ParseTransaction(lcm xdr.LedgerCloseMeta, ingestTx ingest.LedgerTransaction
 		CloseTime: lcm.LedgerCloseTime(),
 	}
 	tx.TransactionHash = ingestTx.Result.TransactionHash.HexString()
+	tx.Fees = transactionFees(ingestTx)
 
 	if tx.Result, err = ingestTx.Result.Result.MarshalBinary(); err != nil {
 		return tx, fmt.Errorf("couldn't encode transaction Result: %w", err)
@@ -259,6 +304,46 @@ func ParseTransaction(lcm xdr.LedgerCloseMeta, ingestTx ingest.LedgerTransaction
 	return tx, nil
 }
 
+// transactionFees breaks down the fee bid by a transaction and, for Soroban
+// transactions, the resource fee charged to it as recorded in its meta.
+func transactionFees(ingestTx ingest.LedgerTransaction) protocol.TransactionFees {
+	envelope := ingestTx.Envelope
+	var fees protocol.TransactionFees
+	innerTx := envelope.V1
+	if envelope.IsFeeBump() {
+		fees.InclusionFeeBid = envelope.FeeBumpFee()
+		innerTx = envelope.FeeBump.Tx.InnerTx.V1
+	} else {
+		fees.InclusionFeeBid = int64(envelope.Fee())
+	}
+	if innerTx == nil {
+		return fees
+	}
+	sorobanData, ok := innerTx.Tx.Ext.GetSorobanData()
+	if !ok {
+		return fees
+	}
+	fees.ResourceFee = int64(sorobanData.ResourceFee)
+	fees.InclusionFeeBid -= fees.ResourceFee
+
+	var sorobanMeta *xdr.SorobanTransactionMeta
+	switch ingestTx.UnsafeMeta.V {
+	case 3:
+		sorobanMeta = ingestTx.UnsafeMeta.V3.SorobanMeta
+	case 4:
+		sorobanMeta = ingestTx.UnsafeMeta.V4.SorobanMeta
+	}
+	if sorobanMeta == nil || sorobanMeta.Ext.V != 1 {
+		return fees
+	}
+	charged := sorobanMeta.Ext.V1
+	fees.NonRefundableResourceFeeCharged = int64(charged.TotalNonRefundableResourceFeeCharged)
+	fees.RefundableResourceFeeCharged = int64(charged.TotalRefundableResourceFeeCharged)
+	fees.ResourceFeeRefund = fees.ResourceFee - fees.NonRefundableResourceFeeCharged -
+		fees.RefundableResourceFeeCharged
+	return fees
+}
+
 type transactionTableMigration struct {
 	firstLedger uint32
 	lastLedger  uint32
@@ -0,0 +1,89 @@
+package db
+
+import (
+	"context"
+	"fmt"
+	"slices"
+
+	sq "github.com/Masterminds/squirrel"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/support/log"
+)
+
+const (
+	transactionAuthSignersTableName = "transaction_auth_signers"
+)
+
+// TransactionAuthSigners returns the addresses (G... or C...) which signed the
+// Soroban authorization entries of a transaction, i.e. the addresses of the
+// entries with address credentials. The source account authorizing entries
+// through its signature of the transaction isn't one of them.
+func TransactionAuthSigners(tx ingest.LedgerTransaction) ([]string, error) {
+	var signers []string
+	for _, op := range tx.Envelope.Operations() {
+		invocation, ok := op.Body.GetInvokeHostFunctionOp()
+		if !ok {
+			continue
+		}
+		for _, auth := range invocation.Auth {
+			credentials, ok := auth.Credentials.GetAddress()
+			if !ok {
+				continue
+			}
+			signer, err := credentials.Address.String()
+			if err != nil {
+				return nil, err
+			}
+			if !slices.Contains(signers, signer) {
+				signers = append(signers, signer)
+			}
+		}
+	}
+	return signers, nil
+}
+
+// insertTransactionAuthSigners indexes the addresses which signed the
+// authorization entries of the transactions of a ledger.
+func (txn *transactionHandler) insertTransactionAuthSigners(ledgerSeq uint32, txs []ingest.LedgerTransaction,
+) error {
+	return txn.insertTransactionIndex(transactionAuthSignersTableName, "signer", ledgerSeq, txs,
+		func(tx ingest.LedgerTransaction) ([]any, error) {
+			signers, err := TransactionAuthSigners(tx)
+			if err != nil {
+				return nil, fmt.Errorf("couldn't read the auth signers of tx %d: %w", tx.Index, err)
+			}
+			keys := make([]any, 0, len(signers))
+			for _, signer := range signers {
+				keys = append(keys, signer)
+			}
+			return keys, nil
+		})
+}
+
+// GetAuthSignerTransactions returns the positions of up to limit transactions
+// with an authorization entry signed by the given address, from start
+// (inclusive) to endLedger in the given order (protocol.TransactionsOrderAsc
+// or protocol.TransactionsOrderDesc).
+func (txn *transactionHandler) GetAuthSignerTransactions(ctx context.Context, signer string,
+	start TransactionPosition, endLedger uint32, order string, limit uint,
+) ([]TransactionPosition, error) {
+	query := sq.Select("ledger_sequence", "application_order").
+		From(transactionAuthSignersTableName).
+		Where(sq.Eq{"signer": signer})
+	positions, err := txn.getIndexedTransactions(ctx, query, start, endLedger, order, limit)
+	if err != nil {
+		return nil, fmt.Errorf("db read failed for the transactions of auth signer: %w", err)
+	}
+	return positions, nil
+}
+
+func newTransactionAuthSignersTableMigration(
+	_ context.Context,
+	logger *log.Entry,
+	passphrase string,
+	ledgerSeqRange LedgerSeqRange,
+) migrationApplierFactory {
+	return newTransactionIndexTableMigration(logger, passphrase, ledgerSeqRange,
+		(*transactionHandler).insertTransactionAuthSigners)
+}
@@ -0,0 +1,114 @@
+package db
+
+import (
+	"context"
+	"fmt"
+	"slices"
+
+	sq "github.com/Masterminds/squirrel"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+)
+
+const (
+	transactionContractsTableName = "transaction_contracts"
+)
+
+// TransactionContractIDs returns the contracts a transaction touched: the
+// contracts invoked by its host functions, directly or through their
+// authorized sub-invocations, and the contracts which emitted its events.
+func TransactionContractIDs(tx ingest.LedgerTransaction) ([]xdr.ContractId, error) {
+	var contractIDs []xdr.ContractId
+	add := func(id xdr.ContractId) {
+		if !slices.Contains(contractIDs, id) {
+			contractIDs = append(contractIDs, id)
+		}
+	}
+	addAddress := func(address xdr.ScAddress) {
+		if id, ok := address.GetContractId(); ok {
+			add(id)
+		}
+	}
+	for _, op := range tx.Envelope.Operations() {
+		invocation, ok := op.Body.GetInvokeHostFunctionOp()
+		if !ok {
+			continue
+		}
+		if args, ok := invocation.HostFunction.GetInvokeContract(); ok {
+			addAddress(args.ContractAddress)
+		}
+		for _, auth := range invocation.Auth {
+			addAuthorizedInvocationContracts(auth.RootInvocation, addAddress)
+		}
+	}
+
+	events, err := tx.GetTransactionEvents()
+	if err != nil {
+		return nil, err
+	}
+	for _, opEvents := range events.OperationEvents {
+		for _, event := range opEvents {
+			if event.ContractId != nil {
+				add(*event.ContractId)
+			}
+		}
+	}
+	return contractIDs, nil
+}
+
+func addAuthorizedInvocationContracts(invocation xdr.SorobanAuthorizedInvocation, addAddress func(xdr.ScAddress)) {
+	if args, ok := invocation.Function.GetContractFn(); ok {
+		addAddress(args.ContractAddress)
+	}
+	for _, sub := range invocation.SubInvocations {
+		addAuthorizedInvocationContracts(sub, addAddress)
+	}
+}
+
+// insertTransactionContracts indexes the contracts touched by the transactions
+// of a ledger.
+func (txn *transactionHandler) insertTransactionContracts(ledgerSeq uint32, txs []ingest.LedgerTransaction,
+) error {
+	return txn.insertTransactionIndex(transactionContractsTableName, "contract_id", ledgerSeq, txs,
+		func(tx ingest.LedgerTransaction) ([]any, error) {
+			contractIDs, err := TransactionContractIDs(tx)
+			if err != nil {
+				return nil, fmt.Errorf("couldn't read the contracts of tx %d: %w", tx.Index, err)
+			}
+			keys := make([]any, 0, len(contractIDs))
+			for _, contractID := range contractIDs {
+				keys = append(keys, contractID[:])
+			}
+			return keys, nil
+		})
+}
+
+// GetContractTransactions returns the positions of up to limit transactions
+// which touched one of the given contracts, from start (inclusive) to
+// endLedger in the given order (protocol.TransactionsOrderAsc or
+// protocol.TransactionsOrderDesc).
+func (txn *transactionHandler) GetContractTransactions(ctx context.Context, contractIDs [][]byte,
+	start TransactionPosition, endLedger uint32, order string, limit uint,
+) ([]TransactionPosition, error) {
+	query := sq.Select("ledger_sequence", "application_order").
+		Distinct().
+		From(transactionContractsTableName).
+		Where(sq.Eq{"contract_id": contractIDs})
+	positions, err := txn.getIndexedTransactions(ctx, query, start, endLedger, order, limit)
+	if err != nil {
+		return nil, fmt.Errorf("db read failed for the transactions of contracts: %w", err)
+	}
+	return positions, nil
+}
+
+func newTransactionContractsTableMigration(
+	_ context.Context,
+	logger *log.Entry,
+	passphrase string,
+	ledgerSeqRange LedgerSeqRange,
+) migrationApplierFactory {
+	return newTransactionIndexTableMigration(logger, passphrase, ledgerSeqRange,
+		(*transactionHandler).insertTransactionContracts)
+}
@@ -0,0 +1,168 @@
+package db
+
+import (
+	"context"
+	"fmt"
+
+	sq "github.com/Masterminds/squirrel"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// TransactionPosition locates a transaction by its ledger and its (1-based)
+// application order within the ledger.
+type TransactionPosition struct {
+	LedgerSequence   uint32 `db:"ledger_sequence"`
+	ApplicationOrder int32  `db:"application_order"`
+}
+
+// insertTransactionIndex indexes the transactions of a ledger in a table
+// mapping the keys (in keyColumn) of each transaction to its position.
+func (txn *transactionHandler) insertTransactionIndex(table string, keyColumn string, ledgerSeq uint32,
+	txs []ingest.LedgerTransaction, keys func(ingest.LedgerTransaction) ([]any, error),
+) error {
+	// Rows may already exist when the TransactionsTable migration is applied
+	// along with this table's
+	query := sq.Insert(table).
+		Options("OR IGNORE").
+		Columns(keyColumn, "ledger_sequence", "application_order")
+	rows := 0
+	for _, tx := range txs {
+		txKeys, err := keys(tx)
+		if err != nil {
+			return err
+		}
+		for _, key := range txKeys {
+			query = query.Values(key, ledgerSeq, tx.Index)
+			rows++
+		}
+	}
+	if rows == 0 {
+		return nil
+	}
+	_, err := query.RunWith(txn.stmtCache).Exec()
+	return err
+}
+
+// getIndexedTransactions restricts a query of the positions of the
+// transactions of an index table to up to limit transactions from start
+// (inclusive) to endLedger in the given order, and runs it.
+func (txn *transactionHandler) getIndexedTransactions(ctx context.Context, query sq.SelectBuilder,
+	start TransactionPosition, endLedger uint32, order string, limit uint,
+) ([]TransactionPosition, error) {
+	query = query.Limit(uint64(limit))
+	if order == protocol.TransactionsOrderDesc {
+		query = query.
+			Where(sq.Or{
+				sq.Lt{"ledger_sequence": start.LedgerSequence},
+				sq.And{
+					sq.Eq{"ledger_sequence": start.LedgerSequence},
+					sq.LtOrEq{"application_order": start.ApplicationOrder},
+				},
+			}).
+			Where(sq.GtOrEq{"ledger_sequence": endLedger}).
+			OrderBy("ledger_sequence DESC", "application_order DESC")
+	} else {
+		query = query.
+			Where(sq.Or{
+				sq.Gt{"ledger_sequence": start.LedgerSequence},
+				sq.And{
+					sq.Eq{"ledger_sequence": start.LedgerSequence},
+					sq.GtOrEq{"application_order": start.ApplicationOrder},
+				},
+			}).
+			Where(sq.LtOrEq{"ledger_sequence": endLedger}).
+			OrderBy("ledger_sequence ASC", "application_order ASC")
+	}
+
+	var positions []TransactionPosition
+	if err := txn.db.Select(ctx, &positions, query); err != nil {
+		return nil, err
+	}
+	return positions, nil
+}
+
+// trimTransactionIndex removes the rows of an index table of all the
+// transactions which fall outside the ledger retention window.
+func (txn *transactionHandler) trimTransactionIndex(table string, latestLedgerSeq uint32,
+	retentionWindow uint32,
+) error {
+	if latestLedgerSeq+1 <= retentionWindow {
+		return nil
+	}
+
+	cutoff := latestLedgerSeq + 1 - retentionWindow
+	_, err := sq.StatementBuilder.
+		RunWith(txn.stmtCache).
+		Delete(table).
+		Where(sq.Lt{"ledger_sequence": cutoff}).
+		Exec()
+	return err
+}
+
+// transactionIndexTableMigration fills a table indexing the transactions (or
+// what they changed) for the stored ledgers.
+type transactionIndexTableMigration struct {
+	firstLedger uint32
+	lastLedger  uint32
+	writer      *transactionHandler
+	insert      func(txn *transactionHandler, ledgerSeq uint32, txs []ingest.LedgerTransaction) error
+}
+
+func (t *transactionIndexTableMigration) ApplicableRange() LedgerSeqRange {
+	return LedgerSeqRange{
+		First: t.firstLedger,
+		Last:  t.lastLedger,
+	}
+}
+
+func (t *transactionIndexTableMigration) Apply(_ context.Context, meta xdr.LedgerCloseMeta) error {
+	reader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(t.writer.passphrase, meta)
+	if err != nil {
+		return fmt.Errorf("failed to open transaction reader for ledger %d: %w", meta.LedgerSequence(), err)
+	}
+	txs := make([]ingest.LedgerTransaction, 0, meta.CountTransactions())
+	for range meta.CountTransactions() {
+		tx, err := reader.Read()
+		if err != nil {
+			return err
+		}
+		txs = append(txs, tx)
+	}
+	return t.insert(t.writer, meta.LedgerSequence(), txs)
+}
+
+func newTransactionIndexTableMigration(
+	logger *log.Entry,
+	passphrase string,
+	ledgerSeqRange LedgerSeqRange,
+	insert func(txn *transactionHandler, ledgerSeq uint32, txs []ingest.LedgerTransaction) error,
+) migrationApplierFactory {
+	return migrationApplierFactoryF(func(db *DB) (MigrationApplier, error) {
+		return newTransactionIndexTableMigrationApplier(db, logger, passphrase, ledgerSeqRange, insert), nil
+	})
+}
+
+func newTransactionIndexTableMigrationApplier(
+	db *DB,
+	logger *log.Entry,
+	passphrase string,
+	ledgerSeqRange LedgerSeqRange,
+	insert func(txn *transactionHandler, ledgerSeq uint32, txs []ingest.LedgerTransaction) error,
+) *transactionIndexTableMigration {
+	return &transactionIndexTableMigration{
+		firstLedger: ledgerSeqRange.First,
+		lastLedger:  ledgerSeqRange.Last,
+		writer: &transactionHandler{
+			log:        logger,
+			db:         db,
+			stmtCache:  sq.NewStmtCache(db.GetTx()),
+			passphrase: passphrase,
+		},
+		insert: insert,
+	}
+}
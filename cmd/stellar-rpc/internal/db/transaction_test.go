@@ -10,7 +10,9 @@ import (
 	"github.com/stretchr/testify/assert"
 	"github.com/stretchr/testify/require"
 
+	"github.com/stellar/go/ingest"
 	"github.com/stellar/go/network"
+	"github.com/stellar/go/strkey"
 	"github.com/stellar/go/support/log"
 	"github.com/stellar/go/xdr"
 
@@ -71,7 +73,7 @@ func TestTransactionFound(t *testing.T) {
 	log := log.DefaultLogger
 	log.SetLevel(logrus.TraceLevel)
 
-	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 	write, err := writer.NewTx(ctx)
 	require.NoError(t, err)
 
@@ -100,7 +102,7 @@ func TestTransactionFound(t *testing.T) {
 	end := protocol.Cursor{Ledger: 1000}
 	cursorRange := protocol.CursorRange{Start: start, End: end}
 
-	err = eventReader.GetEvents(ctx, cursorRange, nil, nil, nil, nil)
+	err = eventReader.GetEvents(ctx, cursorRange, protocol.EventOrderAsc, nil, nil, nil, nil, nil)
 	require.NoError(t, err)
 
 	// check all 200 cases
@@ -116,12 +118,79 @@ func TestTransactionFound(t *testing.T) {
 	}
 }
 
+func TestTransactionFees(t *testing.T) {
+	innerTx := txEnvelope(1).V1
+	innerTx.Tx.Fee = 1100
+	innerTx.Tx.Ext = xdr.TransactionExt{V: 1, SorobanData: &xdr.SorobanTransactionData{ResourceFee: 1000}}
+	tx := ingest.LedgerTransaction{
+		Envelope: xdr.TransactionEnvelope{
+			Type: xdr.EnvelopeTypeEnvelopeTypeTxFeeBump,
+			FeeBump: &xdr.FeeBumpTransactionEnvelope{Tx: xdr.FeeBumpTransaction{
+				FeeSource: innerTx.Tx.SourceAccount,
+				Fee:       1300,
+				InnerTx:   xdr.FeeBumpTransactionInnerTx{Type: xdr.EnvelopeTypeEnvelopeTypeTx, V1: innerTx},
+			}},
+		},
+		UnsafeMeta: xdr.TransactionMeta{V: 3, V3: &xdr.TransactionMetaV3{SorobanMeta: &xdr.SorobanTransactionMeta{
+			Ext: xdr.SorobanTransactionMetaExt{V: 1, V1: &xdr.SorobanTransactionMetaExtV1{
+				TotalNonRefundableResourceFeeCharged: 400,
+				TotalRefundableResourceFeeCharged:    250,
+			}},
+		}}},
+	}
+	assert.Equal(t, protocol.TransactionFees{
+		InclusionFeeBid:                 300,
+		ResourceFee:                     1000,
+		NonRefundableResourceFeeCharged: 400,
+		RefundableResourceFeeCharged:    250,
+		ResourceFeeRefund:               350,
+	}, transactionFees(tx))
+
+	// Classic transactions only bid an inclusion fee
+	assert.Equal(t, protocol.TransactionFees{InclusionFeeBid: 1}, transactionFees(ingest.LedgerTransaction{
+		Envelope:   txEnvelope(1),
+		UnsafeMeta: xdr.TransactionMeta{V: 3, V3: &xdr.TransactionMetaV3{}},
+	}))
+}
+
+func TestTransactionAuthSigners(t *testing.T) {
+	account := xdr.MustAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON")
+	wallet := xdr.ContractId([32]byte{1})
+	addressAuth := func(address xdr.ScAddress) xdr.SorobanAuthorizationEntry {
+		return xdr.SorobanAuthorizationEntry{Credentials: xdr.SorobanCredentials{
+			Type:    xdr.SorobanCredentialsTypeSorobanCredentialsAddress,
+			Address: &xdr.SorobanAddressCredentials{Address: address},
+		}}
+	}
+	envelope := txEnvelope(1)
+	envelope.V1.Tx.Operations = []xdr.Operation{{Body: xdr.OperationBody{
+		Type: xdr.OperationTypeInvokeHostFunction,
+		InvokeHostFunctionOp: &xdr.InvokeHostFunctionOp{Auth: []xdr.SorobanAuthorizationEntry{
+			// The source account's entry is authorized by the transaction signature
+			{Credentials: xdr.SorobanCredentials{Type: xdr.SorobanCredentialsTypeSorobanCredentialsSourceAccount}},
+			addressAuth(xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &wallet}),
+			addressAuth(xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeAccount, AccountId: &account}),
+			addressAuth(xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &wallet}),
+		}},
+	}}}
+
+	signers, err := TransactionAuthSigners(ingest.LedgerTransaction{Envelope: envelope})
+	require.NoError(t, err)
+	walletAddress, err := strkey.Encode(strkey.VersionByteContract, wallet[:])
+	require.NoError(t, err)
+	assert.Equal(t, []string{walletAddress, account.Address()}, signers)
+
+	signers, err = TransactionAuthSigners(ingest.LedgerTransaction{Envelope: txEnvelope(1)})
+	require.NoError(t, err)
+	assert.Empty(t, signers)
+}
+
 func BenchmarkTransactionFetch(b *testing.B) {
 	db := NewTestDB(b)
 	ctx := context.TODO()
 	log := log.DefaultLogger
 
-	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 100, 1_000_000, passphrase)
+	writer := NewReadWriter(log, db, interfaces.MakeNoOpDeamon(), 100, 1_000_000, 1_000_000, 1_000_000, passphrase)
 	write, err := writer.NewTx(ctx)
 	require.NoError(b, err)
 
@@ -0,0 +1,66 @@
+package internal
+
+import (
+	"net/http"
+	"sync"
+
+	"github.com/prometheus/client_golang/prometheus"
+	"github.com/rs/cors"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/config"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/methods"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/network"
+)
+
+// EventStreamHandler is the HTTP handler which streams events as Server-Sent
+// Events
+type EventStreamHandler struct {
+	done      chan struct{}
+	closeOnce sync.Once
+	http.Handler
+}
+
+// Close ends the ongoing streams, so that they don't hold up the shutdown of
+// the server.
+func (h *EventStreamHandler) Close() {
+	h.closeOnce.Do(func() { close(h.done) })
+}
+
+// NewEventStreamHandler constructs an EventStreamHandler instance
+func NewEventStreamHandler(cfg *config.Config, params HandlerParams) *EventStreamHandler {
+	done := make(chan struct{})
+	streamHandler := methods.NewStreamEventsHandler(
+		params.Logger,
+		params.EventReader,
+		params.LedgerReader,
+		cfg.MaxEventsLimit,
+		cfg.MaxEventFilterContractIDs,
+		cfg.NetworkPassphrase,
+		cfg.EventStreamPollInterval,
+		done,
+	)
+
+	// Streams are long-lived, so they are limited in number rather than
+	// going through the global request backlog and duration limits
+	inflightStreams := prometheus.NewGauge(prometheus.GaugeOpts{
+		Namespace: params.Daemon.MetricsNamespace(), Subsystem: "network", Name: "event_streams",
+		Help: "Number of concurrently open event streams",
+	})
+	queueLimitedHandler := network.MakeHTTPBacklogQueueLimiter(
+		streamHandler,
+		inflightStreams,
+		uint64(cfg.MaxEventStreams),
+		params.Logger)
+
+	corsMiddleware := cors.New(cors.Options{
+		AllowedOrigins:         []string{},
+		AllowOriginRequestFunc: func(*http.Request, string) bool { return true },
+		AllowedHeaders:         []string{"*"},
+		AllowedMethods:         []string{"GET", "HEAD", "OPTIONS"},
+	})
+
+	return &EventStreamHandler{
+		done:    done,
+		Handler: corsMiddleware.Handler(queueLimitedHandler),
+	}
+}
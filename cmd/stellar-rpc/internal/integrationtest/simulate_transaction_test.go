@@ -235,6 +235,9 @@ func TestSimulateInvokeContractTransactionSucceeds(t *testing.T) {
 	require.Equal(t, xdr.ScValTypeScvVoid, obtainedAuth.Credentials.Address.Signature.Type)
 
 	require.NotZero(t, obtainedAuth.Credentials.Address.Nonce)
+	// Recorded against the latest ledger
+	require.Equal(t, xdr.Uint32(response.LatestLedger+protocol.DefaultSignatureExpirationLedgers),
+		obtainedAuth.Credentials.Address.SignatureExpirationLedger)
 	require.Equal(t, xdr.ScAddressTypeScAddressTypeAccount, obtainedAuth.Credentials.Address.Address.Type)
 	require.Equal(t, authAddrArg, obtainedAuth.Credentials.Address.Address.AccountId.Address())
 
@@ -449,6 +452,7 @@ func TestSimulateTransactionExtendAndRestoreFootprint(t *testing.T) {
 	simulationResult := infrastructure.SimulateTransactionFromTxParams(t, client, invokeIncPresistentEntryParams)
 	require.NotNil(t, simulationResult.RestorePreamble)
 	require.NotZero(t, simulationResult.RestorePreamble)
+	require.Contains(t, simulationResult.RestorePreamble.ArchivedKeysXDR, keyB64)
 
 	params := infrastructure.PreflightTransactionParamsLocally(
 		t,
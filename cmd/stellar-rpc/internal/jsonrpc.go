@@ -55,6 +55,8 @@ type HandlerParams struct {
 	FeeStatWindows        *feewindow.FeeWindows
 	TransactionReader     db.TransactionReader
 	EventReader           db.EventReader
+	EventWebhookStore     db.EventWebhookStore
+	ContractDataKeyReader db.ContractDataKeyReader
 	LedgerReader          db.LedgerReader
 	Logger                *log.Entry
 	PreflightGetter       methods.PreflightGetter
@@ -155,6 +157,16 @@ func NewJSONRPCHandler(cfg *config.Config, params HandlerParams) Handler {
 	}
 
 	retentionWindow := cfg.HistoryRetentionWindow
+	var eventBackfill *methods.EventBackfill
+	if cfg.BackfillEventsFromDatastore {
+		eventBackfill = methods.NewEventBackfill(params.DataStoreLedgerReader, cfg.NetworkPassphrase,
+			cfg.CheckpointFrequency, cfg.EventBackfillCacheSize)
+	}
+	transactionReader := params.TransactionReader
+	if cfg.ServeLedgersFromDatastore {
+		transactionReader = methods.NewDatastoreTransactionReader(transactionReader, params.DataStoreLedgerReader,
+			cfg.NetworkPassphrase)
+	}
 
 	handlers := []struct {
 		methodName           string
@@ -178,13 +190,52 @@ func NewJSONRPCHandler(cfg *config.Config, params HandlerParams) Handler {
 				params.EventReader,
 				cfg.MaxEventsLimit,
 				cfg.DefaultEventsLimit,
+				cfg.MaxEventFilterContractIDs,
 				params.LedgerReader,
+				cfg.NetworkPassphrase,
+				params.Daemon.FastCoreClient(),
+				eventBackfill,
 			),
 
 			longName:             toSnakeCase(protocol.GetEventsMethodName),
 			queueLimit:           cfg.RequestBacklogGetEventsQueueLimit,
 			requestDurationLimit: cfg.MaxGetEventsExecutionDuration,
 		},
+		{
+			methodName: protocol.GetEventsCountMethodName,
+			underlyingHandler: methods.NewGetEventsCountHandler(
+				params.Logger,
+				params.EventReader,
+				cfg.MaxEventFilterContractIDs,
+				params.LedgerReader,
+				cfg.NetworkPassphrase,
+			),
+			longName:             toSnakeCase(protocol.GetEventsCountMethodName),
+			queueLimit:           cfg.RequestBacklogGetEventsCountQueueLimit,
+			requestDurationLimit: cfg.MaxGetEventsCountExecutionDuration,
+		},
+		{
+			methodName: protocol.RegisterEventWebhookMethodName,
+			underlyingHandler: methods.NewRegisterEventWebhookHandler(
+				params.Logger,
+				params.EventReader,
+				params.LedgerReader,
+				params.EventWebhookStore,
+				cfg.MaxEventWebhooks,
+				cfg.MaxEventFilterContractIDs,
+				cfg.NetworkPassphrase,
+			),
+			longName:             toSnakeCase(protocol.RegisterEventWebhookMethodName),
+			queueLimit:           cfg.RequestBacklogGetEventsQueueLimit,
+			requestDurationLimit: cfg.MaxGetEventsExecutionDuration,
+		},
+		{
+			methodName:           protocol.DeleteEventWebhookMethodName,
+			underlyingHandler:    methods.NewDeleteEventWebhookHandler(params.EventWebhookStore),
+			longName:             toSnakeCase(protocol.DeleteEventWebhookMethodName),
+			queueLimit:           cfg.RequestBacklogGetEventsQueueLimit,
+			requestDurationLimit: cfg.MaxGetEventsExecutionDuration,
+		},
 		{
 			methodName: protocol.GetNetworkMethodName,
 			underlyingHandler: methods.NewGetNetworkHandler(
@@ -222,26 +273,45 @@ func NewJSONRPCHandler(cfg *config.Config, params HandlerParams) Handler {
 		{
 			methodName: protocol.GetLedgerEntriesMethodName,
 			underlyingHandler: methods.NewGetLedgerEntriesHandler(params.Logger,
-				params.Daemon.FastCoreClient(), params.LedgerReader),
+				params.Daemon.FastCoreClient(), params.LedgerReader, cfg.NetworkPassphrase,
+				cfg.MaxLedgerEntriesLimit, cfg.MaxLedgerEntriesKeys, cfg.MaxLedgerEntriesRollbackLedgers),
 			longName:             toSnakeCase(protocol.GetLedgerEntriesMethodName),
 			queueLimit:           cfg.RequestBacklogGetLedgerEntriesQueueLimit,
 			requestDurationLimit: cfg.MaxGetLedgerEntriesExecutionDuration,
 		},
 		{
-			methodName:           protocol.GetTransactionMethodName,
-			underlyingHandler:    methods.NewGetTransactionHandler(params.Logger, params.TransactionReader, params.LedgerReader),
-			longName:             toSnakeCase(protocol.GetTransactionMethodName),
-			queueLimit:           cfg.RequestBacklogGetTransactionQueueLimit,
-			requestDurationLimit: cfg.MaxGetTransactionExecutionDuration,
+			methodName: protocol.GetContractDataMethodName,
+			underlyingHandler: methods.NewGetContractDataHandler(params.Logger, params.ContractDataKeyReader,
+				params.Daemon.FastCoreClient(), params.LedgerReader, cfg.MaxLedgerEntriesLimit),
+			longName:             toSnakeCase(protocol.GetContractDataMethodName),
+			queueLimit:           cfg.RequestBacklogGetContractDataQueueLimit,
+			requestDurationLimit: cfg.MaxGetContractDataExecutionDuration,
+		},
+		{
+			methodName: protocol.GetTransactionMethodName,
+			underlyingHandler: methods.NewGetTransactionHandler(params.Logger, transactionReader, params.LedgerReader,
+				cfg.MaxGetTransactionWaitDuration),
+			longName:   toSnakeCase(protocol.GetTransactionMethodName),
+			queueLimit: cfg.RequestBacklogGetTransactionQueueLimit,
+			// Requests may wait for their transaction before being processed
+			requestDurationLimit: cfg.MaxGetTransactionExecutionDuration + cfg.MaxGetTransactionWaitDuration,
 		},
 		{
 			methodName: protocol.GetTransactionsMethodName,
 			underlyingHandler: methods.NewGetTransactionsHandler(params.Logger, params.LedgerReader,
-				cfg.MaxTransactionsLimit, cfg.DefaultTransactionsLimit, cfg.NetworkPassphrase),
+				params.TransactionReader, cfg.MaxTransactionsLimit, cfg.DefaultTransactionsLimit, cfg.NetworkPassphrase),
 			longName:             toSnakeCase(protocol.GetTransactionsMethodName),
 			queueLimit:           cfg.RequestBacklogGetTransactionsQueueLimit,
 			requestDurationLimit: cfg.MaxGetTransactionsExecutionDuration,
 		},
+		{
+			methodName: protocol.GetTransactionsByHashMethodName,
+			underlyingHandler: methods.NewGetTransactionsByHashHandler(params.Logger, transactionReader,
+				params.LedgerReader),
+			longName:             toSnakeCase(protocol.GetTransactionsByHashMethodName),
+			queueLimit:           cfg.RequestBacklogGetTransactionsByHashQueueLimit,
+			requestDurationLimit: cfg.MaxGetTransactionsByHashExecutionDuration,
+		},
 		{
 			methodName: protocol.SendTransactionMethodName,
 			underlyingHandler: methods.NewSendTransactionHandler(
@@ -254,12 +324,41 @@ func NewJSONRPCHandler(cfg *config.Config, params HandlerParams) Handler {
 			methodName: protocol.SimulateTransactionMethodName,
 			underlyingHandler: methods.NewSimulateTransactionHandler(
 				params.Logger, params.LedgerReader,
-				params.Daemon.FastCoreClient(), params.PreflightGetter),
+				params.Daemon.FastCoreClient(), params.PreflightGetter, params.FeeStatWindows,
+				methods.LedgerOverrideLimits{
+					MaxLedgerSkew: cfg.MaxSimulationLedgerSkew,
+					MaxTimeSkew:   cfg.MaxSimulationTimeSkew,
+				},
+				uint32(cfg.CaptiveCoreHTTPQuerySnapshotLedgers)),
 
 			longName:             toSnakeCase(protocol.SimulateTransactionMethodName),
 			queueLimit:           cfg.RequestBacklogSimulateTransactionQueueLimit,
 			requestDurationLimit: cfg.MaxSimulateTransactionExecutionDuration,
 		},
+		{
+			methodName: protocol.SimulateTransactionsMethodName,
+			underlyingHandler: methods.NewSimulateTransactionsHandler(
+				params.Logger, params.LedgerReader,
+				params.Daemon.FastCoreClient(), params.PreflightGetter, params.FeeStatWindows,
+				methods.LedgerOverrideLimits{
+					MaxLedgerSkew: cfg.MaxSimulationLedgerSkew,
+					MaxTimeSkew:   cfg.MaxSimulationTimeSkew,
+				},
+				uint32(cfg.CaptiveCoreHTTPQuerySnapshotLedgers), cfg.MaxSimulateTransactionsLimit),
+			longName:             toSnakeCase(protocol.SimulateTransactionsMethodName),
+			queueLimit:           cfg.RequestBacklogSimulateTransactionsQueueLimit,
+			requestDurationLimit: cfg.MaxSimulateTransactionsExecutionDuration,
+		},
+		{
+			methodName: protocol.DiagnoseTransactionMethodName,
+			underlyingHandler: methods.NewDiagnoseTransactionHandler(
+				params.Logger, params.TransactionReader, params.LedgerReader,
+				params.Daemon.FastCoreClient(), params.PreflightGetter, params.FeeStatWindows,
+				cfg.NetworkPassphrase, cfg.MaxLedgerEntriesRollbackLedgers),
+			longName:             toSnakeCase(protocol.DiagnoseTransactionMethodName),
+			queueLimit:           cfg.RequestBacklogDiagnoseTransactionQueueLimit,
+			requestDurationLimit: cfg.MaxDiagnoseTransactionExecutionDuration,
+		},
 		{
 			methodName:           protocol.GetFeeStatsMethodName,
 			underlyingHandler:    methods.NewGetFeeStatsHandler(params.FeeStatWindows, params.LedgerReader, params.Logger),
@@ -0,0 +1,274 @@
+package ledgerentries
+
+import (
+	"context"
+	"fmt"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+)
+
+// NewLedgerEntryHistoryGetter creates a LedgerEntryGetter which gets the value
+// of the given ledger entries at a past (stored) ledger: the entries changed
+// since then are rolled back to their state before their first change, as
+// recorded in the meta of the stored ledgers (including the ledger upgrades),
+// and the others are looked up in Stellar Core. Temporary entries evicted since
+// then can't be recovered.
+func NewLedgerEntryHistoryGetter(coreClient interfaces.FastCoreClient, ledgerReader db.LedgerReader,
+	networkPassphrase string, atLedger uint32,
+) LedgerEntryGetter {
+	return &historyLedgerEntryGetter{
+		core:              coreLedgerEntryGetter{coreClient: coreClient, latestLedgerReader: ledgerReader},
+		ledgerReader:      ledgerReader,
+		networkPassphrase: networkPassphrase,
+		atLedger:          atLedger,
+	}
+}
+
+// NewLedgerEntryBeforeTransactionGetter creates a LedgerEntryGetter which gets
+// the value of the given ledger entries right before a (stored) transaction was
+// applied, i.e. at the ledger preceding it along with the fees charged in its
+// ledger and the changes of the transactions applied before it (see
+// NewLedgerEntryHistoryGetter). The getter reports the ledger of the
+// transaction as the one it read the entries at.
+func NewLedgerEntryBeforeTransactionGetter(coreClient interfaces.FastCoreClient, ledgerReader db.LedgerReader,
+	networkPassphrase string, ledger uint32, applicationOrder int32,
+) LedgerEntryGetter {
+	return beforeTransactionLedgerEntryGetter{
+		history: historyLedgerEntryGetter{
+			core:              coreLedgerEntryGetter{coreClient: coreClient, latestLedgerReader: ledgerReader},
+			ledgerReader:      ledgerReader,
+			networkPassphrase: networkPassphrase,
+			atLedger:          ledger - 1,
+			beforeTx:          applicationOrder,
+		},
+	}
+}
+
+type historyLedgerEntryGetter struct {
+	core              coreLedgerEntryGetter
+	ledgerReader      db.LedgerReader
+	networkPassphrase string
+	atLedger          uint32
+	// beforeTx, if set, is the application order of the transaction of the
+	// ledger following atLedger right before which the entries are read: the
+	// fees charged in that ledger and the changes of the transactions applied
+	// before it are kept
+	beforeTx int32
+}
+
+type beforeTransactionLedgerEntryGetter struct {
+	history historyLedgerEntryGetter
+}
+
+func (g beforeTransactionLedgerEntryGetter) GetLedgerEntries(
+	ctx context.Context,
+	keys []xdr.LedgerKey,
+) ([]LedgerKeyAndEntry, uint32, error) {
+	entries, _, err := g.history.GetLedgerEntries(ctx, keys)
+	return entries, g.history.atLedger + 1, err
+}
+
+// ledgerEntryHistory holds the state, at the ledger of the getter, of the
+// entries changed since then.
+type ledgerEntryHistory struct {
+	// keys holds the keys of the looked up entries, along with their TTL
+	keys map[string]bool
+	// before holds the entries changed since the ledger, which didn't exist
+	// if nil
+	before map[string]*xdr.LedgerEntry
+	// evicted holds the entries evicted since the ledger
+	evicted map[string]bool
+}
+
+func (g historyLedgerEntryGetter) GetLedgerEntries(
+	ctx context.Context,
+	keys []xdr.LedgerKey,
+) ([]LedgerKeyAndEntry, uint32, error) {
+	history := ledgerEntryHistory{
+		keys:    map[string]bool{},
+		before:  map[string]*xdr.LedgerEntry{},
+		evicted: map[string]bool{},
+	}
+	ttlKeys := make([]string, len(keys))
+	for i, key := range keys {
+		keyB64, err := xdr.MarshalBase64(key)
+		if err != nil {
+			return nil, 0, err
+		}
+		history.keys[keyB64] = true
+		if key.Type == xdr.LedgerEntryTypeContractData || key.Type == xdr.LedgerEntryTypeContractCode {
+			ttlKey, err := ttlLedgerKey(key)
+			if err != nil {
+				return nil, 0, err
+			}
+			if ttlKeys[i], err = xdr.MarshalBase64(ttlKey); err != nil {
+				return nil, 0, err
+			}
+			history.keys[ttlKeys[i]] = true
+		}
+	}
+
+	latestLedger, err := g.ledgerReader.GetLatestLedgerSequence(ctx)
+	if err != nil {
+		return nil, 0, fmt.Errorf("could not get latest ledger: %w", err)
+	}
+	// The entries are looked up at the latest ledger, so that they are
+	// consistent with the changes of the streamed ledgers
+	current := g.core
+	current.atLedger = latestLedger
+	entries, _, err := current.GetLedgerEntries(ctx, keys)
+	if err != nil {
+		return nil, 0, err
+	}
+	err = g.ledgerReader.StreamLedgerRange(ctx, g.atLedger+1, latestLedger,
+		history.rollBack(g.networkPassphrase, g.atLedger+1, g.beforeTx))
+	if err != nil {
+		return nil, 0, fmt.Errorf("could not read the changes since ledger %d: %w", g.atLedger, err)
+	}
+
+	currentEntries := make(map[string]LedgerKeyAndEntry, len(entries))
+	for _, entry := range entries {
+		keyB64, err := xdr.MarshalBase64(entry.Key)
+		if err != nil {
+			return nil, 0, err
+		}
+		currentEntries[keyB64] = entry
+	}
+	result := make([]LedgerKeyAndEntry, 0, len(keys))
+	for i, key := range keys {
+		if entry, ok := history.entryAt(key, ttlKeys[i], currentEntries); ok {
+			result = append(result, entry)
+		}
+	}
+	return result, latestLedger, nil
+}
+
+// rollBack returns a function recording the state before the first change
+// (in a streamed ledger) of the looked up entries. If beforeTx is set, the fees
+// of firstLedger and the transactions applied before the beforeTx-th one are
+// kept.
+func (h ledgerEntryHistory) rollBack(networkPassphrase string, firstLedger uint32, beforeTx int32,
+) db.StreamLedgerFn {
+	return func(ledger xdr.LedgerCloseMeta) error {
+		reader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(networkPassphrase, ledger)
+		if err != nil {
+			return err
+		}
+		txs := make([]ingest.LedgerTransaction, 0, ledger.CountTransactions())
+		for range ledger.CountTransactions() {
+			tx, err := reader.Read()
+			if err != nil {
+				return fmt.Errorf("could not read the transactions of ledger %d: %w", ledger.LedgerSequence(), err)
+			}
+			txs = append(txs, tx)
+		}
+		if ledger.LedgerSequence() == firstLedger && beforeTx > 0 {
+			txs = txs[min(int(beforeTx)-1, len(txs)):]
+		} else {
+			// Fees are charged before the transactions are applied
+			for _, tx := range txs {
+				if err := h.recordChanges(tx.GetFeeChanges()); err != nil {
+					return err
+				}
+			}
+		}
+		for _, tx := range txs {
+			changes, err := tx.GetChanges()
+			if err != nil {
+				return err
+			}
+			if err := h.recordChanges(changes); err != nil {
+				return err
+			}
+		}
+		// Upgrades (e.g. of the network config settings) are applied after the
+		// transactions
+		for _, upgrade := range ledger.UpgradesProcessing() {
+			if err := h.recordChanges(ingest.GetChangesFromLedgerEntryChanges(upgrade.Changes)); err != nil {
+				return err
+			}
+		}
+
+		evictedKeys, err := ledger.EvictedLedgerKeys()
+		if err != nil {
+			return err
+		}
+		for _, key := range evictedKeys {
+			keyB64, err := xdr.MarshalBase64(key)
+			if err != nil {
+				return err
+			}
+			h.evicted[keyB64] = true
+		}
+		return nil
+	}
+}
+
+func (h ledgerEntryHistory) recordChanges(changes []ingest.Change) error {
+	for _, change := range changes {
+		entry := change.Pre
+		if entry == nil {
+			entry = change.Post
+		}
+		key, err := entry.LedgerKey()
+		if err != nil {
+			return err
+		}
+		keyB64, err := xdr.MarshalBase64(key)
+		if err != nil {
+			return err
+		}
+		if h.keys[keyB64] && !h.changed(keyB64) {
+			h.before[keyB64] = change.Pre
+		}
+	}
+	return nil
+}
+
+// entryAt returns the entry of a key (along with its TTL) at the ledger of the
+// history, false if it didn't exist.
+func (h ledgerEntryHistory) entryAt(key xdr.LedgerKey, ttlKey string,
+	currentEntries map[string]LedgerKeyAndEntry,
+) (LedgerKeyAndEntry, bool) {
+	keyB64, err := xdr.MarshalBase64(key)
+	if err != nil {
+		return LedgerKeyAndEntry{}, false
+	}
+	entry, exists := currentEntries[keyB64]
+	if before, changed := h.before[keyB64]; changed {
+		if before == nil {
+			return LedgerKeyAndEntry{}, false
+		}
+		entry.Key, entry.Entry, exists = key, *before, true
+	}
+	if !exists {
+		return LedgerKeyAndEntry{}, false
+	}
+	// Entries changed or evicted since then weren't archived at the time
+	entry.Archived = entry.Archived && !h.evicted[keyB64] && !h.changed(keyB64)
+	if before, changed := h.before[ttlKey]; changed && before != nil {
+		liveUntil := uint32(before.Data.MustTtl().LiveUntilLedgerSeq)
+		entry.LiveUntilLedgerSeq = &liveUntil
+	}
+	return entry, true
+}
+
+func (h ledgerEntryHistory) changed(key string) bool {
+	_, changed := h.before[key]
+	return changed
+}
+
+func ttlLedgerKey(key xdr.LedgerKey) (xdr.LedgerKey, error) {
+	keyHash, err := xdr.HashXdr(key)
+	if err != nil {
+		return xdr.LedgerKey{}, err
+	}
+	return xdr.LedgerKey{
+		Type: xdr.LedgerEntryTypeTtl,
+		Ttl:  &xdr.LedgerKeyTtl{KeyHash: keyHash},
+	}, nil
+}
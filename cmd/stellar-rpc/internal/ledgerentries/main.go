@@ -15,6 +15,7 @@ type LedgerKeyAndEntry struct {
 	Key                xdr.LedgerKey
 	Entry              xdr.LedgerEntry
 	LiveUntilLedgerSeq *uint32 // optional live-until ledger seq, when applicable.
+	Archived           bool    // whether the entry was evicted to the archive.
 }
 
 type LedgerEntryGetter interface {
@@ -83,8 +84,9 @@ func (c coreLedgerEntryGetter) GetLedgerEntries(
 		// We can reuse the key from the request because the entries are
 		// returned in order.
 		newEntry := LedgerKeyAndEntry{
-			Key:   keys[i],
-			Entry: xdrEntry,
+			Key:      keys[i],
+			Entry:    xdrEntry,
+			Archived: entry.State == coreProto.LedgerEntryStateArchived,
 		}
 		if entry.LiveUntilLedgerSeq != 0 || entry.State == coreProto.LedgerEntryStateArchived {
 			// Core doesn't provide the specific TTL in which an entry was archived.
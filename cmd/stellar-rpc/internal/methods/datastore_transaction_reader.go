@@ -0,0 +1,67 @@
+package methods
+
+import (
+	"context"
+	"errors"
+	"fmt"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/rpcdatastore"
+)
+
+// datastoreTransactionReader serves the transactions whose hashes are still
+// indexed but whose ledgers fell outside the history retention window, from
+// their ledgers fetched from the datastore.
+type datastoreTransactionReader struct {
+	db.TransactionReader
+
+	datastore         rpcdatastore.LedgerReader
+	networkPassphrase string
+}
+
+func NewDatastoreTransactionReader(reader db.TransactionReader, datastore rpcdatastore.LedgerReader,
+	networkPassphrase string,
+) db.TransactionReader {
+	return datastoreTransactionReader{
+		TransactionReader: reader,
+		datastore:         datastore,
+		networkPassphrase: networkPassphrase,
+	}
+}
+
+func (r datastoreTransactionReader) GetTransaction(ctx context.Context, hash xdr.Hash) (db.Transaction, error) {
+	tx, err := r.TransactionReader.GetTransaction(ctx, hash)
+	if !errors.Is(err, db.ErrNoTransaction) {
+		return tx, err
+	}
+	position, err := r.GetTransactionPosition(ctx, hash)
+	if err != nil {
+		return db.Transaction{}, err
+	}
+
+	ledgers, err := r.datastore.GetLedgers(ctx, position.LedgerSequence, position.LedgerSequence)
+	if err != nil {
+		return db.Transaction{}, fmt.Errorf("could not fetch ledger %d from the datastore: %w",
+			position.LedgerSequence, err)
+	}
+	if len(ledgers) == 0 {
+		return db.Transaction{}, db.ErrNoTransaction
+	}
+	ledger := ledgers[0]
+	reader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(r.networkPassphrase, ledger)
+	if err != nil {
+		return db.Transaction{}, fmt.Errorf("failed to create ledger reader: %w", err)
+	}
+	if err := reader.Seek(int(position.ApplicationOrder) - 1); err != nil {
+		return db.Transaction{}, fmt.Errorf("failed to index to tx %d in ledger %d: %w",
+			position.ApplicationOrder, position.LedgerSequence, err)
+	}
+	ingestTx, err := reader.Read()
+	if err != nil {
+		return db.Transaction{}, err
+	}
+	return db.ParseTransaction(ledger, ingestTx)
+}
@@ -0,0 +1,54 @@
+package methods
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+)
+
+func TestDatastoreTransactionReader(t *testing.T) {
+	dbx := NewTestDB(t)
+	ctx := context.TODO()
+
+	// Ledgers are kept for a single ledger, transaction hashes for 10
+	writer := db.NewReadWriter(log.DefaultLogger, dbx, interfaces.MakeNoOpDeamon(), 10, 1, 1, 10, passphrase)
+	ledgers := []xdr.LedgerCloseMeta{txMeta(1, true), txMeta(2, false)}
+	for _, ledger := range ledgers {
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+		require.NoError(t, write.LedgerWriter().InsertLedger(ledger))
+		require.NoError(t, write.TransactionWriter().InsertTransactions(ledger))
+		require.NoError(t, write.Commit(ledger))
+	}
+
+	dbReader := db.NewTransactionReader(log.DefaultLogger, dbx, passphrase)
+	_, err := dbReader.GetTransaction(ctx, txHash(1))
+	require.ErrorIs(t, err, db.ErrNoTransaction)
+
+	// The transaction of the trimmed ledger is served from the datastore
+	store := new(MockDatastoreReader)
+	store.On("GetLedgers", ctx, uint32(101), uint32(101)).Return([]xdr.LedgerCloseMeta{ledgers[0]}, nil).Once()
+	reader := NewDatastoreTransactionReader(dbReader, store, passphrase)
+	tx, err := reader.GetTransaction(ctx, txHash(1))
+	require.NoError(t, err)
+	assert.Equal(t, txHash(1).HexString(), tx.TransactionHash)
+	assert.EqualValues(t, 101, tx.Ledger.Sequence)
+	assert.True(t, tx.Successful)
+	store.AssertExpectations(t)
+
+	// The transactions of the stored ledgers are served locally
+	tx, err = reader.GetTransaction(ctx, txHash(2))
+	require.NoError(t, err)
+	assert.False(t, tx.Successful)
+
+	_, err = reader.GetTransaction(ctx, txHash(3))
+	require.ErrorIs(t, err, db.ErrNoTransaction)
+}
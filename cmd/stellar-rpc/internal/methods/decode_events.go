@@ -0,0 +1,144 @@
+package methods
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/contractspec"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/xdr2json"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// getContractEventSpecs returns the event specs embedded in the Wasm of the
+// given contracts, leaving out the contracts which can't be found, which
+// aren't Wasm contracts (e.g. Stellar Asset Contracts) or whose spec is
+// invalid.
+func getContractEventSpecs(
+	ctx context.Context, getter ledgerentries.LedgerEntryGetter, contractIDs []xdr.ContractId,
+) (map[xdr.ContractId][]xdr.ScSpecEventV0, error) {
+	instanceKeys := make([]xdr.LedgerKey, 0, len(contractIDs))
+	for _, contractID := range contractIDs {
+		instanceKeys = append(instanceKeys, xdr.LedgerKey{
+			Type: xdr.LedgerEntryTypeContractData,
+			ContractData: &xdr.LedgerKeyContractData{
+				Contract:   xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &contractID},
+				Key:        xdr.ScVal{Type: xdr.ScValTypeScvLedgerKeyContractInstance},
+				Durability: xdr.ContractDataDurabilityPersistent,
+			},
+		})
+	}
+	instances, _, err := getter.GetLedgerEntries(ctx, instanceKeys)
+	if err != nil {
+		return nil, fmt.Errorf("could not get contract instances: %w", err)
+	}
+
+	wasmHashes := make(map[xdr.ContractId]xdr.Hash, len(instances))
+	codeKeys := make([]xdr.LedgerKey, 0, len(instances))
+	for _, instance := range instances {
+		data := instance.Entry.Data.ContractData
+		if data == nil || data.Contract.ContractId == nil || data.Val.Instance == nil {
+			continue
+		}
+		executable := data.Val.Instance.Executable
+		if executable.Type != xdr.ContractExecutableTypeContractExecutableWasm || executable.WasmHash == nil {
+			continue
+		}
+		wasmHash := *executable.WasmHash
+		wasmHashes[*data.Contract.ContractId] = wasmHash
+		codeKeys = append(codeKeys, xdr.LedgerKey{
+			Type:         xdr.LedgerEntryTypeContractCode,
+			ContractCode: &xdr.LedgerKeyContractCode{Hash: wasmHash},
+		})
+	}
+	if len(codeKeys) == 0 {
+		return map[xdr.ContractId][]xdr.ScSpecEventV0{}, nil
+	}
+	codes, _, err := getter.GetLedgerEntries(ctx, codeKeys)
+	if err != nil {
+		return nil, fmt.Errorf("could not get contract code: %w", err)
+	}
+
+	specsByWasmHash := make(map[xdr.Hash][]xdr.ScSpecEventV0, len(codes))
+	for _, code := range codes {
+		entry := code.Entry.Data.ContractCode
+		if entry == nil {
+			continue
+		}
+		if specs, err := contractspec.EventSpecs(entry.Code); err == nil {
+			specsByWasmHash[entry.Hash] = specs
+		}
+	}
+	specsByContract := make(map[xdr.ContractId][]xdr.ScSpecEventV0, len(wasmHashes))
+	for contractID, wasmHash := range wasmHashes {
+		if specs, ok := specsByWasmHash[wasmHash]; ok {
+			specsByContract[contractID] = specs
+		}
+	}
+	return specsByContract, nil
+}
+
+// decodeEvents decodes the events found by getEvents after the event spec of
+// their contract, into the Decoded field of their results.
+func (h eventsRPCHandler) decodeEvents(
+	ctx context.Context, found []entry, results []protocol.EventInfo, format string,
+) error {
+	seen := map[xdr.ContractId]bool{}
+	var contractIDs []xdr.ContractId
+	for _, entry := range found {
+		if contractID := entry.event.Event.ContractId; contractID != nil && !seen[*contractID] {
+			seen[*contractID] = true
+			contractIDs = append(contractIDs, *contractID)
+		}
+	}
+	if len(contractIDs) == 0 {
+		return nil
+	}
+	specs, err := getContractEventSpecs(ctx, h.ledgerEntryGetter, contractIDs)
+	if err != nil {
+		return err
+	}
+
+	for i, entry := range found {
+		v0, ok := entry.event.Event.Body.GetV0()
+		if !ok || entry.event.Event.ContractId == nil {
+			continue
+		}
+		name, fields, ok := contractspec.DecodeEvent(specs[*entry.event.Event.ContractId], v0.Topics, v0.Data)
+		if !ok {
+			continue
+		}
+		if results[i].Decoded, err = decodedEvent(name, fields, format); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func decodedEvent(name string, fields []contractspec.Field, format string) (*protocol.DecodedEvent, error) {
+	decoded := &protocol.DecodedEvent{Name: name}
+	switch format {
+	case protocol.FormatJSON:
+		decoded.FieldsJSON = make(map[string]json.RawMessage, len(fields))
+		for _, field := range fields {
+			value, err := xdr2json.ConvertInterface(field.Value)
+			if err != nil {
+				return nil, err
+			}
+			decoded.FieldsJSON[field.Name] = value
+		}
+	default:
+		decoded.FieldsXDR = make(map[string]string, len(fields))
+		for _, field := range fields {
+			value, err := xdr.MarshalBase64(field.Value)
+			if err != nil {
+				return nil, err
+			}
+			decoded.FieldsXDR[field.Name] = value
+		}
+	}
+	return decoded, nil
+}
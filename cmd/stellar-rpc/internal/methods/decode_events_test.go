@@ -0,0 +1,119 @@
+package methods
+
+import (
+	"context"
+	"encoding/binary"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/contractspec"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+type mapLedgerEntryGetter map[string]xdr.LedgerEntry
+
+func (m mapLedgerEntryGetter) GetLedgerEntries(
+	_ context.Context, keys []xdr.LedgerKey,
+) ([]ledgerentries.LedgerKeyAndEntry, uint32, error) {
+	result := make([]ledgerentries.LedgerKeyAndEntry, 0, len(keys))
+	for _, key := range keys {
+		serializedKey, err := key.MarshalBinaryBase64()
+		if err != nil {
+			return nil, 0, err
+		}
+		if entry, ok := m[serializedKey]; ok {
+			result = append(result, ledgerentries.LedgerKeyAndEntry{Key: key, Entry: entry})
+		}
+	}
+	return result, 0, nil
+}
+
+func (m mapLedgerEntryGetter) add(t *testing.T, data xdr.LedgerEntryData) {
+	entry := xdr.LedgerEntry{Data: data}
+	key, err := entry.LedgerKey()
+	require.NoError(t, err)
+	serializedKey, err := key.MarshalBinaryBase64()
+	require.NoError(t, err)
+	m[serializedKey] = entry
+}
+
+func wasmWithEventSpec(t *testing.T, spec xdr.ScSpecEventV0) []byte {
+	payload, err := xdr.ScSpecEntry{Kind: xdr.ScSpecEntryKindScSpecEntryEventV0, EventV0: &spec}.MarshalBinary()
+	require.NoError(t, err)
+	section := binary.AppendUvarint(nil, uint64(len(contractspec.SpecSectionName)))
+	section = append(section, contractspec.SpecSectionName...)
+	section = append(section, payload...)
+
+	wasm := []byte("\x00asm\x01\x00\x00\x00\x00")
+	wasm = binary.AppendUvarint(wasm, uint64(len(section)))
+	return append(wasm, section...)
+}
+
+func TestDecodeEvents(t *testing.T) {
+	contractID := xdr.ContractId([32]byte{1})
+	unknownContractID := xdr.ContractId([32]byte{2})
+	wasm := wasmWithEventSpec(t, xdr.ScSpecEventV0{
+		Name:         "Increment",
+		PrefixTopics: []xdr.ScSymbol{"COUNTER"},
+		Params: []xdr.ScSpecEventParamV0{{
+			Name:     "count",
+			Type:     xdr.ScSpecTypeDef{Type: xdr.ScSpecTypeScSpecTypeU32},
+			Location: xdr.ScSpecEventParamLocationV0ScSpecEventParamLocationData,
+		}},
+		DataFormat: xdr.ScSpecEventDataFormatScSpecEventDataFormatSingleValue,
+	})
+	wasmHash := xdr.Hash([32]byte{3})
+
+	getter := mapLedgerEntryGetter{}
+	getter.add(t, xdr.LedgerEntryData{
+		Type: xdr.LedgerEntryTypeContractData,
+		ContractData: &xdr.ContractDataEntry{
+			Contract:   xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &contractID},
+			Key:        xdr.ScVal{Type: xdr.ScValTypeScvLedgerKeyContractInstance},
+			Durability: xdr.ContractDataDurabilityPersistent,
+			Val: xdr.ScVal{
+				Type: xdr.ScValTypeScvContractInstance,
+				Instance: &xdr.ScContractInstance{Executable: xdr.ContractExecutable{
+					Type:     xdr.ContractExecutableTypeContractExecutableWasm,
+					WasmHash: &wasmHash,
+				}},
+			},
+		},
+	})
+	getter.add(t, xdr.LedgerEntryData{
+		Type:         xdr.LedgerEntryTypeContractCode,
+		ContractCode: &xdr.ContractCodeEntry{Hash: wasmHash, Code: wasm},
+	})
+
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	count := xdr.Uint32(7)
+	countScVal := xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: &count}
+	found := []entry{
+		{event: xdr.DiagnosticEvent{Event: contractEvent(contractID, xdr.ScVec{counterScVal}, countScVal)}},
+		{event: xdr.DiagnosticEvent{Event: contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)}},
+		{event: xdr.DiagnosticEvent{Event: contractEvent(unknownContractID, xdr.ScVec{counterScVal}, countScVal)}},
+	}
+
+	handler := eventsRPCHandler{ledgerEntryGetter: getter}
+	results := make([]protocol.EventInfo, len(found))
+	require.NoError(t, handler.decodeEvents(context.Background(), found, results, ""))
+	countXDR, err := xdr.MarshalBase64(countScVal)
+	require.NoError(t, err)
+	require.Equal(t, &protocol.DecodedEvent{
+		Name:      "Increment",
+		FieldsXDR: map[string]string{"count": countXDR},
+	}, results[0].Decoded)
+	// the data of the second event doesn't match the spec, and there is no
+	// spec for the contract of the third one
+	require.Nil(t, results[1].Decoded)
+	require.Nil(t, results[2].Decoded)
+
+	results = make([]protocol.EventInfo, len(found))
+	require.NoError(t, handler.decodeEvents(context.Background(), found, results, protocol.FormatJSON))
+	require.JSONEq(t, `{"u32":7}`, string(results[0].Decoded.FieldsJSON["count"]))
+}
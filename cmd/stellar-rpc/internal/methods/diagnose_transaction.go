@@ -0,0 +1,262 @@
+package methods
+
+import (
+	"context"
+	"encoding/base64"
+	"errors"
+	"fmt"
+	"slices"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/feewindow"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// transactionSimulator simulates a transaction at a stored ledger, against the
+// state right before the transaction applied at the given application order
+// in that ledger.
+type transactionSimulator func(
+	ctx context.Context, request protocol.SimulateTransactionRequest, ledger uint32, applicationOrder int32,
+) protocol.SimulateTransactionResponse
+
+// DiagnoseTransaction simulates a Soroban transaction again, against the state
+// right before it was applied, and compares the simulation with the resources
+// declared and used by the transaction. The state is rebuilt from the meta of
+// the ledgers stored since then, at most maxRollbackLedgers of them.
+func DiagnoseTransaction(
+	ctx context.Context,
+	log *log.Entry,
+	reader db.TransactionReader,
+	ledgerReader db.LedgerReader,
+	maxRollbackLedgers uint,
+	request protocol.DiagnoseTransactionRequest,
+	simulate transactionSimulator,
+) (protocol.DiagnoseTransactionResponse, error) {
+	txHash, err := parseTransactionHash(request.Hash)
+	if err != nil {
+		return protocol.DiagnoseTransactionResponse{}, err
+	}
+	tx, err := reader.GetTransaction(ctx, txHash)
+	if errors.Is(err, db.ErrNoTransaction) {
+		return protocol.DiagnoseTransactionResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: "transaction not found",
+		}
+	} else if err != nil {
+		log.WithError(err).
+			WithField("hash", txHash).
+			Errorf("failed to fetch transaction")
+		return protocol.DiagnoseTransactionResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+
+	envelopeB64 := base64.StdEncoding.EncodeToString(tx.Envelope)
+	txEnvelope, _, err := getTransactionEnvelope(envelopeB64)
+	if err != nil {
+		return protocol.DiagnoseTransactionResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	declared, ok := envelopeSorobanData(txEnvelope)
+	if !ok {
+		return protocol.DiagnoseTransactionResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: "only Soroban transactions can be diagnosed",
+		}
+	}
+	if err := checkDiagnosisLedger(ctx, ledgerReader, tx.Ledger.Sequence, maxRollbackLedgers); err != nil {
+		return protocol.DiagnoseTransactionResponse{}, err
+	}
+	response := protocol.DiagnoseTransactionResponse{
+		Ledger: tx.Ledger.Sequence,
+		Status: protocol.TransactionStatusFailed,
+	}
+	if tx.Successful {
+		response.Status = protocol.TransactionStatusSuccess
+	}
+	response.ResourceFeeCharged, err = getResourceFeeCharged(tx.Meta)
+	var used map[string]int64
+	if err == nil {
+		used, err = coreMetrics(tx.Events)
+	}
+	if err != nil {
+		return protocol.DiagnoseTransactionResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+
+	response.Simulation = simulate(ctx, protocol.SimulateTransactionRequest{Transaction: envelopeB64},
+		tx.Ledger.Sequence, tx.ApplicationOrder)
+	response.LatestLedger = response.Simulation.LatestLedger
+	if response.Simulation.Error != "" {
+		return response, nil
+	}
+	if err := diagnoseSimulation(&response, declared, used); err != nil {
+		return protocol.DiagnoseTransactionResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	return response, nil
+}
+
+// checkDiagnosisLedger checks that the state right before a transaction can
+// still be rebuilt, which requires the ledger of the transaction and every one
+// since then to be stored, at most maxRollbackLedgers of them.
+func checkDiagnosisLedger(ctx context.Context, ledgerReader db.LedgerReader, ledger uint32,
+	maxRollbackLedgers uint,
+) error {
+	ledgerRange, err := ledgerReader.GetLedgerRange(ctx)
+	if err != nil {
+		return &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	first, last := ledgerRange.FirstLedger.Sequence, ledgerRange.LastLedger.Sequence
+	// The ledger of the transaction and every one since then are read to roll
+	// the state back
+	if uint(last) >= maxRollbackLedgers {
+		first = max(first, last+1-uint32(maxRollbackLedgers)) //nolint:gosec
+	}
+	if ledger < first || ledger > last {
+		return &jrpc2.Error{
+			Code: jrpc2.InvalidParams,
+			Message: fmt.Sprintf("the state of ledger %d, of the transaction, can no longer be rebuilt "+
+				"(only the ones of ledgers %d to %d can)", ledger, first, last),
+		}
+	}
+	return nil
+}
+
+// getResourceFeeCharged returns the resource fee charged to a Soroban
+// transaction, as recorded in its meta.
+func getResourceFeeCharged(metaXDR []byte) (int64, error) {
+	var meta xdr.TransactionMeta
+	if err := xdr.SafeUnmarshal(metaXDR, &meta); err != nil {
+		return 0, err
+	}
+	var sorobanMeta *xdr.SorobanTransactionMeta
+	switch meta.V {
+	case 3:
+		sorobanMeta = meta.V3.SorobanMeta
+	case 4:
+		sorobanMeta = meta.V4.SorobanMeta
+	default:
+		return 0, fmt.Errorf("unexpected meta version: %d", meta.V)
+	}
+	if sorobanMeta == nil || sorobanMeta.Ext.V != 1 {
+		return 0, nil
+	}
+	fees := sorobanMeta.Ext.V1
+	return int64(fees.TotalNonRefundableResourceFeeCharged + fees.TotalRefundableResourceFeeCharged), nil
+}
+
+// diagnoseSimulation compares the resources and the footprint declared by the
+// transaction, and the resources it used (its core metrics, if any), with the
+// ones of its successful simulation.
+func diagnoseSimulation(
+	response *protocol.DiagnoseTransactionResponse, declared xdr.SorobanTransactionData, used map[string]int64,
+) error {
+	var simulated xdr.SorobanTransactionData
+	if err := xdr.SafeUnmarshalBase64(response.Simulation.TransactionDataXDR, &simulated); err != nil {
+		return err
+	}
+	minResourceFee := response.Simulation.MinResourceFee
+	resourceFeeCharged := response.ResourceFeeCharged
+	usedMetric := func(name string) *int64 {
+		if value, ok := used[name]; ok {
+			return &value
+		}
+		return nil
+	}
+	response.Resources = []protocol.DiagnosedResource{
+		{
+			Name:      "instructions",
+			Declared:  int64(declared.Resources.Instructions),
+			Simulated: int64(simulated.Resources.Instructions),
+			Used:      usedMetric("cpu_insn"),
+		},
+		{
+			Name:      "diskReadBytes",
+			Declared:  int64(declared.Resources.DiskReadBytes),
+			Simulated: int64(simulated.Resources.DiskReadBytes),
+			Used:      usedMetric("ledger_read_byte"),
+		},
+		{
+			Name:      "writeBytes",
+			Declared:  int64(declared.Resources.WriteBytes),
+			Simulated: int64(simulated.Resources.WriteBytes),
+			Used:      usedMetric("ledger_write_byte"),
+		},
+		{
+			Name:      "resourceFee",
+			Declared:  int64(declared.ResourceFee),
+			Simulated: minResourceFee,
+			Used:      &resourceFeeCharged,
+		},
+	}
+	for _, resource := range response.Resources {
+		// The used read bytes aren't only read from disk
+		if resource.Name == "diskReadBytes" {
+			continue
+		}
+		if resource.Used != nil && *resource.Used > resource.Simulated {
+			response.Underestimates = append(response.Underestimates, fmt.Sprintf(
+				"%s: %d used, %d simulated", resource.Name, *resource.Used, resource.Simulated))
+		}
+	}
+	var err error
+	response.Shortfalls, err = validateInnerResources(declared, simulated, minResourceFee)
+	if err != nil {
+		return err
+	}
+
+	simulatedKeys, err := ledgerKeySet(slices.Concat(
+		simulated.Resources.Footprint.ReadOnly, simulated.Resources.Footprint.ReadWrite))
+	if err != nil {
+		return err
+	}
+	for _, key := range slices.Concat(declared.Resources.Footprint.ReadOnly, declared.Resources.Footprint.ReadWrite) {
+		keyB64, err := key.MarshalBinaryBase64()
+		if err != nil {
+			return err
+		}
+		if !simulatedKeys[keyB64] {
+			response.UnusedFootprintKeys = append(response.UnusedFootprintKeys, keyB64)
+		}
+	}
+	return nil
+}
+
+// NewDiagnoseTransactionHandler returns a JSON rpc handler comparing executed
+// transactions with their simulation
+func NewDiagnoseTransactionHandler(logger *log.Entry, reader db.TransactionReader,
+	ledgerReader db.LedgerReader,
+	coreClient interfaces.FastCoreClient, getter PreflightGetter, feeWindows *feewindow.FeeWindows,
+	networkPassphrase string, maxRollbackLedgers uint,
+) jrpc2.Handler {
+	simulate := func(ctx context.Context, request protocol.SimulateTransactionRequest, ledger uint32,
+		applicationOrder int32,
+	) protocol.SimulateTransactionResponse {
+		state := ledgerentries.NewLedgerEntryBeforeTransactionGetter(coreClient, ledgerReader, networkPassphrase,
+			ledger, applicationOrder)
+		return simulateTransactionAt(ctx, logger, ledgerReader, coreClient, getter, feeWindows, request,
+			ledger, state)
+	}
+	return NewHandler(func(ctx context.Context, request protocol.DiagnoseTransactionRequest,
+	) (protocol.DiagnoseTransactionResponse, error) {
+		return DiagnoseTransaction(ctx, logger, reader, ledgerReader, maxRollbackLedgers, request, simulate)
+	})
+}
@@ -0,0 +1,120 @@
+package methods
+
+import (
+	"context"
+	"encoding/hex"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestDiagnoseTransaction(t *testing.T) {
+	ctx := context.TODO()
+	store := db.NewMockTransactionStore("passphrase")
+	ledgerReader := db.NewMockLedgerReader(store)
+	var simulationRequest protocol.SimulateTransactionRequest
+	var simulationLedger uint32
+	var simulationApplicationOrder int32
+	simulationResponse := protocol.SimulateTransactionResponse{Error: "failed", LatestLedger: 101}
+	simulate := func(_ context.Context, request protocol.SimulateTransactionRequest, ledger uint32,
+		applicationOrder int32,
+	) protocol.SimulateTransactionResponse {
+		simulationRequest = request
+		simulationLedger, simulationApplicationOrder = ledger, applicationOrder
+		return simulationResponse
+	}
+
+	hash := "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+	_, err := DiagnoseTransaction(ctx, log.DefaultLogger, store, ledgerReader, 120,
+		protocol.DiagnoseTransactionRequest{Hash: hash}, simulate)
+	require.EqualError(t, err, "[-32602] transaction not found")
+
+	require.NoError(t, store.InsertTransactions(txMeta(1, true)))
+	xdrHash := txHash(1)
+	hash = hex.EncodeToString(xdrHash[:])
+	response, err := DiagnoseTransaction(ctx, log.DefaultLogger, store, ledgerReader, 120,
+		protocol.DiagnoseTransactionRequest{Hash: hash}, simulate)
+	require.NoError(t, err)
+	// Simulated against the state right before the transaction, in its ledger
+	expectedEnvelope, err := xdr.MarshalBase64(txEnvelope(1))
+	require.NoError(t, err)
+	require.Equal(t, protocol.SimulateTransactionRequest{Transaction: expectedEnvelope}, simulationRequest)
+	require.Equal(t, uint32(101), simulationLedger)
+	require.Equal(t, int32(1), simulationApplicationOrder)
+	require.Equal(t, protocol.DiagnoseTransactionResponse{
+		LatestLedger: 101,
+		Ledger:       101,
+		Status:       protocol.TransactionStatusSuccess,
+		Simulation:   simulationResponse,
+	}, response)
+
+	key := xdr.LedgerKey{
+		Type: xdr.LedgerEntryTypeAccount,
+		Account: &xdr.LedgerKeyAccount{
+			AccountId: xdr.MustAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON"),
+		},
+	}
+	keyB64, err := key.MarshalBinaryBase64()
+	require.NoError(t, err)
+	simulationResponse = protocol.SimulateTransactionResponse{LatestLedger: 101, MinResourceFee: 50}
+	simulationResponse.TransactionDataXDR, err = xdr.MarshalBase64(xdr.SorobanTransactionData{
+		Resources: xdr.SorobanResources{
+			Footprint:    xdr.LedgerFootprint{ReadOnly: []xdr.LedgerKey{key}},
+			Instructions: 10,
+		},
+		ResourceFee: 50,
+	})
+	require.NoError(t, err)
+	response, err = DiagnoseTransaction(ctx, log.DefaultLogger, store, ledgerReader, 120,
+		protocol.DiagnoseTransactionRequest{Hash: hash}, simulate)
+	require.NoError(t, err)
+	require.Equal(t, protocol.DiagnosedResource{Name: "instructions", Simulated: 10}, response.Resources[0])
+	require.Equal(t, []string{
+		"instructions: 0 declared, at least 10 required",
+		"resourceFee: 0 declared, at least 50 required",
+		"footprint: missing read-only key " + keyB64,
+	}, response.Shortfalls)
+	require.Empty(t, response.UnusedFootprintKeys)
+	require.Empty(t, response.Underestimates)
+
+	// The ledgers since the transaction are too many to roll the state back
+	require.NoError(t, store.InsertTransactions(txMeta(2, true)))
+	_, err = DiagnoseTransaction(ctx, log.DefaultLogger, store, ledgerReader, 1,
+		protocol.DiagnoseTransactionRequest{Hash: hash}, simulate)
+	require.EqualError(t, err, "[-32602] the state of ledger 101, of the transaction, can no longer be rebuilt "+
+		"(only the ones of ledgers 102 to 102 can)")
+}
+
+func TestDiagnoseSimulationUsedResources(t *testing.T) {
+	simulated, err := xdr.MarshalBase64(xdr.SorobanTransactionData{
+		Resources: xdr.SorobanResources{Instructions: 10, DiskReadBytes: 5, WriteBytes: 20},
+	})
+	require.NoError(t, err)
+	response := protocol.DiagnoseTransactionResponse{
+		ResourceFeeCharged: 30,
+		Simulation:         protocol.SimulateTransactionResponse{TransactionDataXDR: simulated, MinResourceFee: 30},
+	}
+	declared := xdr.SorobanTransactionData{
+		Resources:   xdr.SorobanResources{Instructions: 15, DiskReadBytes: 5, WriteBytes: 20},
+		ResourceFee: 30,
+	}
+	used := map[string]int64{"cpu_insn": 12, "ledger_read_byte": 8}
+	require.NoError(t, diagnoseSimulation(&response, declared, used))
+
+	usedInstructions, usedReadBytes, usedFee := int64(12), int64(8), int64(30)
+	require.Equal(t, []protocol.DiagnosedResource{
+		{Name: "instructions", Declared: 15, Simulated: 10, Used: &usedInstructions},
+		{Name: "diskReadBytes", Declared: 5, Simulated: 5, Used: &usedReadBytes},
+		{Name: "writeBytes", Declared: 20, Simulated: 20},
+		{Name: "resourceFee", Declared: 30, Simulated: 30, Used: &usedFee},
+	}, response.Resources)
+	// The read bytes used also count the bytes which aren't read from disk
+	require.Equal(t, []string{"instructions: 12 used, 10 simulated"}, response.Underestimates)
+	require.Empty(t, response.Shortfalls)
+}
@@ -0,0 +1,175 @@
+package methods
+
+import (
+	"bytes"
+	"context"
+	"fmt"
+	"slices"
+	"sync"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/rpcdatastore"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// eventBackfillCheckpoints is the maximum number of checkpoints a getEvents
+// request backfills, so that requests over sparse events fetch a bounded
+// number of ledgers and are paginated instead.
+const eventBackfillCheckpoints = 4
+
+// EventBackfill serves the events of the ledgers preceding the local event
+// retention window, by replaying the ledgers of their checkpoints fetched
+// from the datastore. The events of up to cacheSize checkpoints are cached.
+type EventBackfill struct {
+	reader              rpcdatastore.LedgerReader
+	networkPassphrase   string
+	checkpointFrequency uint32
+
+	lock      sync.Mutex
+	cacheSize int
+	cache     map[uint32][]db.Event
+	// cached holds the checkpoints of the cache, from the oldest cached
+	cached []uint32
+}
+
+func NewEventBackfill(reader rpcdatastore.LedgerReader, networkPassphrase string, checkpointFrequency uint32,
+	cacheSize uint,
+) *EventBackfill {
+	return &EventBackfill{
+		reader:              reader,
+		networkPassphrase:   networkPassphrase,
+		checkpointFrequency: checkpointFrequency,
+		cacheSize:           int(cacheSize),
+		cache:               map[uint32][]db.Event{},
+	}
+}
+
+// ledgerRange returns the range of the ledgers which can be backfilled, given
+// the range of the locally stored events.
+func (b *EventBackfill) ledgerRange(ctx context.Context, localRange ledgerbucketwindow.LedgerRange,
+) (ledgerbucketwindow.LedgerRange, bool, error) {
+	available, err := b.reader.GetAvailableLedgerRange(ctx)
+	if err != nil {
+		return ledgerbucketwindow.LedgerRange{}, false, err
+	}
+	if available.FirstLedger >= localRange.FirstLedger.Sequence {
+		return ledgerbucketwindow.LedgerRange{}, false, nil
+	}
+	return ledgerbucketwindow.LedgerRange{
+		FirstLedger: ledgerbucketwindow.LedgerInfo{Sequence: available.FirstLedger},
+		LastLedger:  ledgerbucketwindow.LedgerInfo{Sequence: localRange.FirstLedger.Sequence - 1},
+	}, true, nil
+}
+
+// clampCursorRange bounds the number of checkpoints scanned by a request.
+func (b *EventBackfill) clampCursorRange(cursorRange protocol.CursorRange) protocol.CursorRange {
+	end := b.checkpointStart(cursorRange.Start.Ledger) + eventBackfillCheckpoints*b.checkpointFrequency
+	if end < cursorRange.End.Ledger {
+		cursorRange.End = protocol.Cursor{Ledger: end}
+	}
+	return cursorRange
+}
+
+func (b *EventBackfill) checkpointStart(ledger uint32) uint32 {
+	return ledger / b.checkpointFrequency * b.checkpointFrequency
+}
+
+// GetEvents scans the backfilled events of cursorRange (in ascending order)
+// like db.EventReader.GetEvents. The topics are left to be matched by the
+// filters of the caller.
+func (b *EventBackfill) GetEvents(
+	ctx context.Context,
+	cursorRange protocol.CursorRange,
+	contractIDs [][]byte,
+	eventTypes []int,
+	transactionHash *xdr.Hash,
+	f db.ScanFunction,
+) error {
+	start := b.checkpointStart(cursorRange.Start.Ledger)
+	for checkpoint := start; checkpoint < cursorRange.End.Ledger; checkpoint += b.checkpointFrequency {
+		events, err := b.getCheckpointEvents(ctx, checkpoint, cursorRange.End.Ledger-1)
+		if err != nil {
+			return err
+		}
+		for _, event := range events {
+			if event.Cursor.Cmp(cursorRange.Start) < 0 || event.Cursor.Cmp(cursorRange.End) >= 0 ||
+				!backfilledEventMatches(event, contractIDs, eventTypes, transactionHash) {
+				continue
+			}
+			if !f(event.Event, event.Cursor, event.LedgerCloseTime, &event.TransactionHash, event.OpIndex) {
+				return nil
+			}
+		}
+	}
+	return nil
+}
+
+func backfilledEventMatches(event db.Event, contractIDs [][]byte, eventTypes []int, transactionHash *xdr.Hash,
+) bool {
+	if len(contractIDs) > 0 && (event.Event.Event.ContractId == nil ||
+		!slices.ContainsFunc(contractIDs, func(id []byte) bool {
+			return bytes.Equal(id, event.Event.Event.ContractId[:])
+		})) {
+		return false
+	}
+	if len(eventTypes) > 0 && !slices.Contains(eventTypes, int(event.Event.Event.Type)) {
+		return false
+	}
+	return transactionHash == nil || *transactionHash == event.TransactionHash
+}
+
+// getCheckpointEvents returns the events of the ledgers of a checkpoint, up to
+// lastLedger. Only the events of whole checkpoints are cached.
+func (b *EventBackfill) getCheckpointEvents(ctx context.Context, checkpoint uint32, lastLedger uint32,
+) ([]db.Event, error) {
+	b.lock.Lock()
+	events, ok := b.cache[checkpoint]
+	b.lock.Unlock()
+	if ok {
+		return events, nil
+	}
+
+	available, err := b.reader.GetAvailableLedgerRange(ctx)
+	if err != nil {
+		return nil, err
+	}
+	start := max(checkpoint, available.FirstLedger)
+	end := checkpoint + b.checkpointFrequency - 1
+	whole := end <= lastLedger
+	end = min(end, lastLedger)
+	ledgers, err := b.reader.GetLedgers(ctx, start, end)
+	if err != nil {
+		return nil, fmt.Errorf("could not fetch ledgers %d - %d from the datastore: %w", start, end, err)
+	}
+	for _, ledger := range ledgers {
+		ledgerEvents, err := db.ParseLedgerEvents(b.networkPassphrase, ledger)
+		if err != nil {
+			return nil, err
+		}
+		events = append(events, ledgerEvents...)
+	}
+	if whole {
+		b.cacheCheckpointEvents(checkpoint, events)
+	}
+	return events, nil
+}
+
+func (b *EventBackfill) cacheCheckpointEvents(checkpoint uint32, events []db.Event) {
+	b.lock.Lock()
+	defer b.lock.Unlock()
+	if b.cacheSize == 0 {
+		return
+	}
+	if _, ok := b.cache[checkpoint]; ok {
+		return
+	}
+	if len(b.cached) >= b.cacheSize {
+		delete(b.cache, b.cached[0])
+		b.cached = b.cached[1:]
+	}
+	b.cache[checkpoint] = events
+	b.cached = append(b.cached, checkpoint)
+}
@@ -0,0 +1,88 @@
+package methods
+
+import (
+	"context"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestGetEventsBackfill(t *testing.T) {
+	dbx := newTestDB(t)
+	ctx := context.TODO()
+	logger := log.DefaultLogger
+	now := time.Now().Unix()
+
+	contractID := xdr.ContractId([32]byte{1})
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	txMeta := transactionMetaWithEvents(contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal))
+
+	// Events are stored locally from ledger 20
+	writer := db.NewReadWriter(logger, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+	write, err := writer.NewTx(ctx)
+	require.NoError(t, err)
+	ledgerCloseMeta := ledgerCloseMetaWithEvents(20, now, txMeta)
+	require.NoError(t, write.LedgerWriter().InsertLedger(ledgerCloseMeta))
+	require.NoError(t, write.EventWriter().InsertEvents(ledgerCloseMeta))
+	require.NoError(t, write.Commit(ledgerCloseMeta))
+
+	// The whole checkpoint of ledger 9 is fetched (and cached), the one of
+	// ledger 17 up to the oldest local ledger
+	store := new(MockDatastoreReader)
+	store.On("GetAvailableLedgerRange", ctx).Return(protocol.LedgerSeqRange{FirstLedger: 2}, nil)
+	store.On("GetLedgers", ctx, uint32(8), uint32(15)).
+		Return([]xdr.LedgerCloseMeta{ledgerCloseMetaWithEvents(9, now-100, txMeta)}, nil).Once()
+	store.On("GetLedgers", ctx, uint32(16), uint32(19)).
+		Return([]xdr.LedgerCloseMeta{ledgerCloseMetaWithEvents(17, now-50, txMeta)}, nil).Twice()
+
+	handler := eventsRPCHandler{
+		dbReader:     db.NewEventReader(logger, dbx, passphrase),
+		maxLimit:     10,
+		defaultLimit: 10,
+		logger:       logger,
+		ledgerReader: db.NewLedgerReader(dbx),
+		backfill:     NewEventBackfill(store, passphrase, 8, 1),
+	}
+	request := protocol.GetEventsRequest{
+		StartLedger: 9,
+		Filters: []protocol.EventFilter{{
+			ContractIDs: []string{strkey.MustEncode(strkey.VersionByteContract, contractID[:])},
+		}},
+	}
+	for range 2 {
+		response, err := handler.getEvents(ctx, request)
+		require.NoError(t, err)
+		require.Len(t, response.Events, 2)
+		assert.EqualValues(t, 9, response.Events[0].Ledger)
+		assert.EqualValues(t, 17, response.Events[1].Ledger)
+		assert.EqualValues(t, 20, response.OldestLedger)
+
+		// The next page starts from the local events
+		cursor, err := protocol.ParseCursor(response.Cursor)
+		require.NoError(t, err)
+		next, err := handler.getEvents(ctx, protocol.GetEventsRequest{
+			Filters:    request.Filters,
+			Pagination: &protocol.PaginationOptions{Cursor: &cursor},
+		})
+		require.NoError(t, err)
+		require.Len(t, next.Events, 1)
+		assert.EqualValues(t, 20, next.Events[0].Ledger)
+	}
+	store.AssertExpectations(t)
+
+	// Without backfill, the request is out of range
+	handler.backfill = nil
+	_, err = handler.getEvents(ctx, request)
+	require.ErrorContains(t, err, "startLedger must be within the ledger range: 20 - 20")
+}
@@ -0,0 +1,302 @@
+package methods
+
+import (
+	"bytes"
+	"context"
+	"crypto/hmac"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net"
+	"net/http"
+	"sync"
+	"syscall"
+	"time"
+
+	"github.com/stellar/go/support/log"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+const (
+	// eventWebhookDeliveryTimeout bounds every POST to a webhook
+	eventWebhookDeliveryTimeout = 10 * time.Second
+	// eventWebhookRoundTimeout bounds a round of deliveries of a webhook,
+	// catching up with the latest ledger
+	eventWebhookRoundTimeout = time.Minute
+	// maxEventWebhookFailures is the number of consecutive failed rounds after
+	// which a webhook is deleted
+	maxEventWebhookFailures = 100
+)
+
+var errEventWebhookBehind = errors.New("the cursor of the webhook is out of the event retention window")
+
+// EventWebhookDispatcher delivers the events matching the filters of the
+// registered webhooks, as the ledgers containing them are ingested.
+//
+// Webhooks are delivered independently, each by its own goroutine, in batches
+// of up to maxLimit events. The cursor of a webhook only moves forward once its
+// delivery gets a 2xx response, failed deliveries being retried on every poll,
+// so events are delivered at least once. Webhooks failing
+// maxEventWebhookFailures rounds in a row, or whose cursor falls out of the
+// retention window, are deleted.
+type EventWebhookDispatcher struct {
+	events       eventsRPCHandler
+	store        db.EventWebhookStore
+	client       *http.Client
+	pollInterval time.Duration
+	// ipAllowed tells whether webhooks can be delivered to an address
+	ipAllowed func(ip net.IP) bool
+	done      chan struct{}
+	closeOnce sync.Once
+	stopped   chan struct{}
+	// delivering holds the ids of the webhooks whose round is in progress
+	delivering   map[string]bool
+	deliveringMu sync.Mutex
+	deliveries   sync.WaitGroup
+}
+
+func NewEventWebhookDispatcher(
+	logger *log.Entry,
+	dbReader db.EventReader,
+	ledgerReader db.LedgerReader,
+	store db.EventWebhookStore,
+	maxLimit uint,
+	maxContractIDs uint,
+	networkPassphrase string,
+	pollInterval time.Duration,
+) *EventWebhookDispatcher {
+	d := &EventWebhookDispatcher{
+		events: eventsRPCHandler{
+			dbReader:          dbReader,
+			maxLimit:          maxLimit,
+			defaultLimit:      maxLimit,
+			maxContractIDs:    maxContractIDs,
+			logger:            logger.WithField("subsys", "event_webhooks"),
+			ledgerReader:      ledgerReader,
+			networkPassphrase: networkPassphrase,
+		},
+		store:        store,
+		pollInterval: pollInterval,
+		ipAllowed:    isPublicIP,
+		done:         make(chan struct{}),
+		stopped:      make(chan struct{}),
+		delivering:   map[string]bool{},
+	}
+	// The addresses are checked once resolved, right before connecting, so
+	// that a host can't resolve to a public address on registration and to a
+	// private one on delivery
+	dialer := &net.Dialer{
+		Timeout: eventWebhookDeliveryTimeout,
+		Control: func(_, address string, _ syscall.RawConn) error {
+			return d.checkDialAddress(address)
+		},
+	}
+	d.client = &http.Client{
+		Timeout:   eventWebhookDeliveryTimeout,
+		Transport: &http.Transport{DialContext: dialer.DialContext},
+	}
+	return d
+}
+
+func (d *EventWebhookDispatcher) checkDialAddress(address string) error {
+	host, _, err := net.SplitHostPort(address)
+	if err != nil {
+		return err
+	}
+	if ip := net.ParseIP(host); ip == nil || !d.ipAllowed(ip) {
+		return fmt.Errorf("webhooks can't be delivered to non-public address %s", host)
+	}
+	return nil
+}
+
+// isPublicIP tells whether an address is publicly routable, as opposed to e.g.
+// loopback, private, link-local (including cloud metadata services) or
+// unspecified addresses.
+func isPublicIP(ip net.IP) bool {
+	return ip.IsGlobalUnicast() && !ip.IsPrivate() && !ip.IsLoopback()
+}
+
+// Run delivers the webhooks until Close is called.
+func (d *EventWebhookDispatcher) Run() {
+	defer close(d.stopped)
+	// Deferred calls run in reverse order: the deliveries are cancelled before
+	// they are waited for
+	defer d.deliveries.Wait()
+	ctx, cancel := context.WithCancel(context.Background())
+	defer cancel()
+	go func() {
+		select {
+		case <-d.done:
+			cancel()
+		case <-ctx.Done():
+		}
+	}()
+
+	ticker := time.NewTicker(d.pollInterval)
+	defer ticker.Stop()
+	for {
+		d.deliverAll(ctx)
+		select {
+		case <-ctx.Done():
+			return
+		case <-ticker.C:
+		}
+	}
+}
+
+// Close stops the deliveries, waiting for Run to return until shutdownCtx is
+// done.
+func (d *EventWebhookDispatcher) Close(shutdownCtx context.Context) {
+	d.closeOnce.Do(func() { close(d.done) })
+	select {
+	case <-d.stopped:
+	case <-shutdownCtx.Done():
+	}
+}
+
+// deliverAll starts a round of deliveries of every webhook whose previous
+// round is over, without waiting for them.
+func (d *EventWebhookDispatcher) deliverAll(ctx context.Context) {
+	// The webhooks are read while holding the lock, so that their cursor is
+	// up to date once their previous round is over
+	d.deliveringMu.Lock()
+	defer d.deliveringMu.Unlock()
+	webhooks, err := d.store.GetEventWebhooks(ctx)
+	if err != nil {
+		d.events.logger.WithError(err).Warn("could not get event webhooks")
+		return
+	}
+	for _, webhook := range webhooks {
+		if d.delivering[webhook.ID] {
+			continue
+		}
+		d.delivering[webhook.ID] = true
+		d.deliveries.Add(1)
+		go func() {
+			defer d.deliveries.Done()
+			d.deliverRound(ctx, webhook)
+			d.deliveringMu.Lock()
+			delete(d.delivering, webhook.ID)
+			d.deliveringMu.Unlock()
+		}()
+	}
+}
+
+// deliverRound delivers the pending events of a webhook, keeping track of its
+// consecutive failures and deleting it once it can't be delivered anymore.
+func (d *EventWebhookDispatcher) deliverRound(ctx context.Context, webhook db.EventWebhook) {
+	roundCtx, cancel := context.WithTimeout(ctx, eventWebhookRoundTimeout)
+	defer cancel()
+	err := d.deliver(roundCtx, webhook)
+	if ctx.Err() != nil {
+		// The dispatcher is closed
+		return
+	}
+	logger := d.events.logger.WithField("webhook", webhook.ID)
+	failures := webhook.Failures + 1
+	switch {
+	case err == nil && webhook.Failures == 0:
+		return
+	case err == nil:
+		err = d.store.UpdateEventWebhookFailures(ctx, webhook.ID, 0)
+	case errors.Is(err, errEventWebhookBehind) || failures >= maxEventWebhookFailures:
+		logger.WithError(err).WithField("failures", failures).Warn("deleting event webhook")
+		_, err = d.store.DeleteEventWebhook(ctx, webhook.ID)
+	default:
+		logger.WithError(err).Warn("could not deliver event webhook")
+		err = d.store.UpdateEventWebhookFailures(ctx, webhook.ID, failures)
+	}
+	if err != nil {
+		logger.WithError(err).Warn("could not update event webhook")
+	}
+}
+
+// deliver delivers the events of a webhook from its cursor up to the latest
+// ledger.
+func (d *EventWebhookDispatcher) deliver(ctx context.Context, webhook db.EventWebhook) error {
+	var request protocol.GetEventsRequest
+	if err := json.Unmarshal([]byte(webhook.Request), &request); err != nil {
+		return fmt.Errorf("invalid webhook request: %w", err)
+	}
+	ledgerRange, err := d.events.getLedgerRange(ctx)
+	if err != nil {
+		return err
+	}
+	cursor := webhook.Cursor
+	for {
+		if cursor != "" {
+			parsed, err := protocol.ParseCursor(cursor)
+			if err != nil {
+				return err
+			}
+			request.StartLedger = 0
+			request.Pagination = &protocol.PaginationOptions{Cursor: &parsed}
+			if parsed.Ledger < ledgerRange.FirstLedger.Sequence {
+				return errEventWebhookBehind
+			}
+		} else if request.StartLedger < ledgerRange.FirstLedger.Sequence {
+			return errEventWebhookBehind
+		}
+		response, err := d.events.getEvents(ctx, request)
+		if err != nil {
+			return err
+		}
+		if len(response.Events) > 0 {
+			delivery := protocol.EventWebhookDelivery{
+				WebhookID: webhook.ID,
+				Events:    response.Events,
+				Cursor:    response.Cursor,
+			}
+			if err := d.post(ctx, webhook, delivery); err != nil {
+				return err
+			}
+		}
+		if response.Cursor != cursor {
+			if err := d.store.UpdateEventWebhookCursor(ctx, webhook.ID, response.Cursor); err != nil {
+				return err
+			}
+			cursor = response.Cursor
+		}
+		if !response.HasMore {
+			return nil
+		}
+	}
+}
+
+// signEventWebhookDelivery returns the value of the
+// protocol.EventWebhookSignatureHeader header of a delivery body.
+func signEventWebhookDelivery(secret string, body []byte) string {
+	mac := hmac.New(sha256.New, []byte(secret))
+	mac.Write(body)
+	return "sha256=" + hex.EncodeToString(mac.Sum(nil))
+}
+
+func (d *EventWebhookDispatcher) post(ctx context.Context, webhook db.EventWebhook,
+	delivery protocol.EventWebhookDelivery,
+) error {
+	body, err := json.Marshal(delivery)
+	if err != nil {
+		return err
+	}
+	req, err := http.NewRequestWithContext(ctx, http.MethodPost, webhook.URL, bytes.NewReader(body))
+	if err != nil {
+		return err
+	}
+	req.Header.Set("Content-Type", "application/json")
+	req.Header.Set(protocol.EventWebhookSignatureHeader, signEventWebhookDelivery(webhook.Secret, body))
+	resp, err := d.client.Do(req)
+	if err != nil {
+		return err
+	}
+	if err := resp.Body.Close(); err != nil {
+		return err
+	}
+	if resp.StatusCode < http.StatusOK || resp.StatusCode >= http.StatusMultipleChoices {
+		return fmt.Errorf("unexpected response status: %s", resp.Status)
+	}
+	return nil
+}
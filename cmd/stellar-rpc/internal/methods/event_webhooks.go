@@ -0,0 +1,182 @@
+package methods
+
+import (
+	"cmp"
+	"context"
+	"crypto/rand"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net"
+	"net/url"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/support/log"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+type eventWebhooksHandler struct {
+	events eventsRPCHandler
+	store  db.EventWebhookStore
+	// maxWebhooks is the maximum number of registered webhooks, webhooks are
+	// disabled if 0
+	maxWebhooks uint
+	// ipAllowed tells whether webhooks can be delivered to an address
+	ipAllowed func(ip net.IP) bool
+}
+
+func randomHex(size int) (string, error) {
+	b := make([]byte, size)
+	if _, err := rand.Read(b); err != nil {
+		return "", err
+	}
+	return hex.EncodeToString(b), nil
+}
+
+func (h eventWebhooksHandler) registerEventWebhook(ctx context.Context, request protocol.RegisterEventWebhookRequest,
+) (protocol.RegisterEventWebhookResponse, error) {
+	if h.maxWebhooks == 0 {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InvalidRequest, Message: "event webhooks are disabled",
+		}
+	}
+	eventsRequest := request.EventsRequest()
+	err := request.Valid(cmp.Or(h.events.maxContractIDs, protocol.MaxContractIDsLimit))
+	if err == nil {
+		err = eventsRequest.ExpandTokens(h.events.networkPassphrase)
+	}
+	if err == nil {
+		err = h.checkURL(ctx, request.URL)
+	}
+	if err != nil {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InvalidParams, Message: err.Error(),
+		}
+	}
+
+	// Validate the filters, and that the start is within the ledger range
+	ledgerRange, err := h.events.getLedgerRange(ctx)
+	if err != nil {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+	if _, err := newEventsScan(eventsRequest, ledgerRange); err != nil {
+		return protocol.RegisterEventWebhookResponse{}, err
+	}
+
+	webhooks, err := h.store.GetEventWebhooks(ctx)
+	if err != nil {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+	if uint(len(webhooks)) >= h.maxWebhooks {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidRequest,
+			Message: fmt.Sprintf("the maximum number of event webhooks (%d) is reached", h.maxWebhooks),
+		}
+	}
+
+	webhook, err := newEventWebhook(request)
+	if err != nil {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+	if err := h.store.InsertEventWebhook(ctx, webhook); err != nil {
+		return protocol.RegisterEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+	return protocol.RegisterEventWebhookResponse{ID: webhook.ID, Secret: webhook.Secret}, nil
+}
+
+// checkURL checks that the host of a webhook url only resolves to allowed
+// addresses. The addresses are checked again on delivery, since they can change.
+func (h eventWebhooksHandler) checkURL(ctx context.Context, rawURL string) error {
+	parsed, err := url.Parse(rawURL)
+	if err != nil {
+		return err
+	}
+	addrs, err := net.DefaultResolver.LookupIPAddr(ctx, parsed.Hostname())
+	if err != nil {
+		return fmt.Errorf("could not resolve url host: %w", err)
+	}
+	for _, addr := range addrs {
+		if !h.ipAllowed(addr.IP) {
+			return errors.New("url must only resolve to public addresses")
+		}
+	}
+	return nil
+}
+
+func newEventWebhook(request protocol.RegisterEventWebhookRequest) (db.EventWebhook, error) {
+	id, err := randomHex(16)
+	if err != nil {
+		return db.EventWebhook{}, err
+	}
+	secret, err := randomHex(32)
+	if err != nil {
+		return db.EventWebhook{}, err
+	}
+	// Tokens are stored unexpanded, getEvents expands them on every delivery
+	eventsRequest, err := json.Marshal(request.EventsRequest())
+	if err != nil {
+		return db.EventWebhook{}, err
+	}
+	return db.EventWebhook{ID: id, URL: request.URL, Secret: secret, Request: string(eventsRequest)}, nil
+}
+
+func (h eventWebhooksHandler) deleteEventWebhook(ctx context.Context, request protocol.DeleteEventWebhookRequest,
+) (protocol.DeleteEventWebhookResponse, error) {
+	deleted, err := h.store.DeleteEventWebhook(ctx, request.ID)
+	if err != nil {
+		return protocol.DeleteEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+	if !deleted {
+		return protocol.DeleteEventWebhookResponse{}, &jrpc2.Error{
+			Code: jrpc2.InvalidParams, Message: "unknown event webhook: " + request.ID,
+		}
+	}
+	return protocol.DeleteEventWebhookResponse{}, nil
+}
+
+// NewRegisterEventWebhookHandler returns a json rpc handler to register event
+// webhooks, delivered by an EventWebhookDispatcher.
+func NewRegisterEventWebhookHandler(
+	logger *log.Entry,
+	dbReader db.EventReader,
+	ledgerReader db.LedgerReader,
+	store db.EventWebhookStore,
+	maxWebhooks uint,
+	maxContractIDs uint,
+	networkPassphrase string,
+) jrpc2.Handler {
+	handler := eventWebhooksHandler{
+		events: eventsRPCHandler{
+			dbReader:          dbReader,
+			maxContractIDs:    maxContractIDs,
+			logger:            logger,
+			ledgerReader:      ledgerReader,
+			networkPassphrase: networkPassphrase,
+		},
+		store:       store,
+		maxWebhooks: maxWebhooks,
+		ipAllowed:   isPublicIP,
+	}
+	return NewHandler(handler.registerEventWebhook)
+}
+
+// NewDeleteEventWebhookHandler returns a json rpc handler to delete event
+// webhooks.
+func NewDeleteEventWebhookHandler(store db.EventWebhookStore) jrpc2.Handler {
+	handler := eventWebhooksHandler{store: store}
+	return NewHandler(handler.deleteEventWebhook)
+}
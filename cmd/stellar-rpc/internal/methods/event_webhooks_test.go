@@ -0,0 +1,151 @@
+package methods
+
+import (
+	"context"
+	"encoding/json"
+	"io"
+	"net"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// insertEventWebhookTestLedger inserts a ledger with an event of each contract
+func insertEventWebhookTestLedger(t *testing.T, dbx *db.DB, contractIDs ...xdr.ContractId) {
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	txMeta := make([]xdr.TransactionMeta, 0, len(contractIDs))
+	for _, contractID := range contractIDs {
+		txMeta = append(txMeta, transactionMetaWithEvents(contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)))
+	}
+	writer := db.NewReadWriter(log.DefaultLogger, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+	write, err := writer.NewTx(context.TODO())
+	require.NoError(t, err)
+	ledgerCloseMeta := ledgerCloseMetaWithEvents(1, time.Now().Unix(), txMeta...)
+	require.NoError(t, write.LedgerWriter().InsertLedger(ledgerCloseMeta))
+	require.NoError(t, write.EventWriter().InsertEvents(ledgerCloseMeta))
+	require.NoError(t, write.Commit(ledgerCloseMeta))
+}
+
+// newEventWebhookTestServer returns a webhook server checking the signature of
+// the deliveries and recording them, responding with the given status.
+func newEventWebhookTestServer(t *testing.T, secret *string, status *int,
+	deliveries *[]protocol.EventWebhookDelivery,
+) *httptest.Server {
+	return httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		body, err := io.ReadAll(r.Body)
+		require.NoError(t, err)
+		assert.Equal(t, signEventWebhookDelivery(*secret, body), r.Header.Get(protocol.EventWebhookSignatureHeader))
+		var delivery protocol.EventWebhookDelivery
+		require.NoError(t, json.Unmarshal(body, &delivery))
+		*deliveries = append(*deliveries, delivery)
+		w.WriteHeader(*status)
+	}))
+}
+
+func TestEventWebhooks(t *testing.T) {
+	dbx := newTestDB(t)
+	ctx := context.TODO()
+	logger := log.DefaultLogger
+
+	contractA := xdr.ContractId([32]byte{1})
+	insertEventWebhookTestLedger(t, dbx, contractA, xdr.ContractId([32]byte{2}))
+
+	var deliveries []protocol.EventWebhookDelivery
+	status := http.StatusInternalServerError
+	var secret string
+	server := newEventWebhookTestServer(t, &secret, &status, &deliveries)
+	defer server.Close()
+
+	store := db.NewEventWebhookStore(dbx)
+	eventReader := db.NewEventReader(logger, dbx, passphrase)
+	ledgerReader := db.NewLedgerReader(dbx)
+	handler := eventWebhooksHandler{
+		events: eventsRPCHandler{
+			dbReader:     eventReader,
+			logger:       logger,
+			ledgerReader: ledgerReader,
+		},
+		store:       store,
+		maxWebhooks: 1,
+		ipAllowed:   isPublicIP,
+	}
+	idA := strkey.MustEncode(strkey.VersionByteContract, contractA[:])
+	request := protocol.RegisterEventWebhookRequest{
+		URL:         server.URL,
+		StartLedger: 1,
+		Filters:     []protocol.EventFilter{{ContractIDs: []string{idA}}},
+	}
+	// The test server listens on a loopback address
+	_, err := handler.registerEventWebhook(ctx, request)
+	require.EqualError(t, err, "[-32602] url must only resolve to public addresses")
+	allowAllIPs := func(net.IP) bool { return true }
+	handler.ipAllowed = allowAllIPs
+	registered, err := handler.registerEventWebhook(ctx, request)
+	require.NoError(t, err)
+	secret = registered.Secret
+	_, err = handler.registerEventWebhook(ctx, request)
+	require.EqualError(t, err, "[-32600] the maximum number of event webhooks (1) is reached")
+
+	dispatcher := NewEventWebhookDispatcher(logger, eventReader, ledgerReader, store, 10, 0, passphrase, time.Second)
+	webhooks, err := store.GetEventWebhooks(ctx)
+	require.NoError(t, err)
+	require.Len(t, webhooks, 1)
+	require.ErrorContains(t, dispatcher.deliver(ctx, webhooks[0]), "non-public address")
+	require.Empty(t, deliveries)
+	dispatcher.ipAllowed = allowAllIPs
+
+	// Failed deliveries don't move the cursor, so they are retried
+	dispatcher.deliverRound(ctx, webhooks[0])
+	require.Len(t, deliveries, 1)
+	webhooks, err = store.GetEventWebhooks(ctx)
+	require.NoError(t, err)
+	assert.Empty(t, webhooks[0].Cursor)
+	assert.EqualValues(t, 1, webhooks[0].Failures)
+
+	status = http.StatusOK
+	dispatcher.deliverRound(ctx, webhooks[0])
+	require.Len(t, deliveries, 2)
+	assert.Equal(t, registered.ID, deliveries[1].WebhookID)
+	require.Len(t, deliveries[1].Events, 1)
+	assert.Equal(t, idA, deliveries[1].Events[0].ContractID)
+	webhooks, err = store.GetEventWebhooks(ctx)
+	require.NoError(t, err)
+	assert.Equal(t, deliveries[1].Cursor, webhooks[0].Cursor)
+	assert.Zero(t, webhooks[0].Failures)
+
+	// Nothing new to deliver
+	require.NoError(t, dispatcher.deliver(ctx, webhooks[0]))
+	require.Len(t, deliveries, 2)
+
+	_, err = handler.deleteEventWebhook(ctx, protocol.DeleteEventWebhookRequest{ID: registered.ID})
+	require.NoError(t, err)
+	_, err = handler.deleteEventWebhook(ctx, protocol.DeleteEventWebhookRequest{ID: registered.ID})
+	require.EqualError(t, err, "[-32602] unknown event webhook: "+registered.ID)
+
+	// Webhooks failing too many times in a row are deleted
+	registered, err = handler.registerEventWebhook(ctx, request)
+	require.NoError(t, err)
+	secret = registered.Secret
+	require.NoError(t, store.UpdateEventWebhookFailures(ctx, registered.ID, maxEventWebhookFailures-1))
+	webhooks, err = store.GetEventWebhooks(ctx)
+	require.NoError(t, err)
+	status = http.StatusInternalServerError
+	dispatcher.deliverRound(ctx, webhooks[0])
+	require.Len(t, deliveries, 3)
+	webhooks, err = store.GetEventWebhooks(ctx)
+	require.NoError(t, err)
+	assert.Empty(t, webhooks)
+}
@@ -0,0 +1,224 @@
+package methods
+
+import (
+	"bytes"
+	"context"
+	"encoding/base64"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// genesisLedger is the first ledger of a network, the index holding all the
+// entries if it was the first indexed one.
+const genesisLedger = 2
+
+// contractDataKeyScanLimit is the maximum number of indexed keys a
+// getContractData request scans, so that requests with a key prefix matching
+// sparse entries are paginated instead of scanning all the keys of the contract.
+const contractDataKeyScanLimit = 10000
+
+type contractDataHandler struct {
+	logger       *log.Entry
+	keyReader    db.ContractDataKeyReader
+	ledgerReader db.LedgerReader
+	getter       ledgerentries.LedgerEntryGetter
+	maxLimit     uint
+}
+
+// NewGetContractDataHandler returns a JSON RPC handler which enumerates the
+// persistent contract data entries of a contract, as indexed by the ingested
+// ledgers, and retrieves them from Stellar Core. Unless the ledgers were indexed
+// since the genesis one, the entries untouched since the first indexed ledger
+// are missing, which the responses tell with complete: false.
+func NewGetContractDataHandler(logger *log.Entry, keyReader db.ContractDataKeyReader,
+	coreClient interfaces.FastCoreClient, latestLedgerReader db.LedgerReader, maxLimit uint,
+) jrpc2.Handler {
+	h := contractDataHandler{
+		logger:       logger,
+		keyReader:    keyReader,
+		ledgerReader: latestLedgerReader,
+		getter:       ledgerentries.NewLedgerEntryGetter(coreClient, latestLedgerReader),
+		maxLimit:     maxLimit,
+	}
+	return NewHandler(h.getContractData)
+}
+
+func (h contractDataHandler) getContractData(ctx context.Context, request protocol.GetContractDataRequest,
+) (protocol.GetContractDataResponse, error) {
+	if err := request.IsValid(h.maxLimit); err != nil {
+		return protocol.GetContractDataResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: err.Error(),
+		}
+	}
+	contractID := xdr.ContractId(strkey.MustDecode(strkey.VersionByteContract, request.ContractID))
+	var prefix *xdr.ScVal
+	if request.KeyPrefix != "" {
+		prefix = &xdr.ScVal{}
+		if err := xdr.SafeUnmarshalBase64(request.KeyPrefix, prefix); err != nil {
+			return protocol.GetContractDataResponse{}, &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: err.Error(),
+			}
+		}
+	}
+	limit := h.maxLimit
+	var after []byte
+	if request.Pagination != nil {
+		if request.Pagination.Limit > 0 {
+			limit = request.Pagination.Limit
+		}
+		var err error
+		if after, err = base64.StdEncoding.DecodeString(request.Pagination.Cursor); err != nil {
+			return protocol.GetContractDataResponse{}, &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: "invalid cursor: " + request.Pagination.Cursor,
+			}
+		}
+	}
+
+	keys, cursor, err := h.matchingKeys(ctx, contractID, prefix, after, limit)
+	if err != nil {
+		return protocol.GetContractDataResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	entries, latestLedger, err := h.getEntries(ctx, keys, request.Format)
+	if err != nil {
+		h.logger.WithError(err).WithField("request", request).
+			Info("could not obtain contract data entries")
+		return protocol.GetContractDataResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	firstIndexedLedger, err := h.keyReader.GetContractDataKeysFirstLedger(ctx)
+	if err != nil {
+		return protocol.GetContractDataResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	return protocol.GetContractDataResponse{
+		Entries:            entries,
+		LatestLedger:       latestLedger,
+		FirstIndexedLedger: firstIndexedLedger,
+		Complete:           firstIndexedLedger != 0 && firstIndexedLedger <= genesisLedger,
+		Cursor:             cursor,
+	}, nil
+}
+
+// matchingKeys scans the indexed keys of the contract after the given one (in
+// the order of their XDR), returning the ledger keys of up to limit of them
+// matching the prefix, along with the cursor of the next page if there may be
+// more.
+func (h contractDataHandler) matchingKeys(ctx context.Context, contractID xdr.ContractId, prefix *xdr.ScVal,
+	after []byte, limit uint,
+) ([]xdr.LedgerKey, string, error) {
+	var keys []xdr.LedgerKey
+	for scanned := 0; scanned < contractDataKeyScanLimit; {
+		batch, err := h.keyReader.GetContractDataKeys(ctx, contractID[:], after, limit)
+		if err != nil {
+			return nil, "", err
+		}
+		for _, keyXDR := range batch {
+			scanned++
+			after = keyXDR
+			var key xdr.ScVal
+			if err := xdr.SafeUnmarshal(keyXDR, &key); err != nil {
+				return nil, "", err
+			}
+			if prefix != nil && !scValHasPrefix(key, *prefix) {
+				continue
+			}
+			keys = append(keys, xdr.LedgerKey{
+				Type: xdr.LedgerEntryTypeContractData,
+				ContractData: &xdr.LedgerKeyContractData{
+					Contract:   xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &contractID},
+					Key:        key,
+					Durability: xdr.ContractDataDurabilityPersistent,
+				},
+			})
+			if len(keys) >= int(limit) { //nolint:gosec
+				return keys, base64.StdEncoding.EncodeToString(after), nil
+			}
+		}
+		if len(batch) < int(limit) { //nolint:gosec
+			// all the keys of the contract were scanned
+			return keys, "", nil
+		}
+	}
+	return keys, base64.StdEncoding.EncodeToString(after), nil
+}
+
+// getEntries retrieves the entries of the given keys (skipping the ones which
+// don't exist anymore), in the same order.
+func (h contractDataHandler) getEntries(ctx context.Context, keys []xdr.LedgerKey, format string,
+) ([]protocol.LedgerEntryResult, uint32, error) {
+	if len(keys) == 0 {
+		latestLedger, err := h.ledgerReader.GetLatestLedgerSequence(ctx)
+		return []protocol.LedgerEntryResult{}, latestLedger, err
+	}
+	keysB64 := make([]string, 0, len(keys))
+	for _, key := range keys {
+		keyB64, err := xdr.MarshalBase64(key)
+		if err != nil {
+			return nil, 0, err
+		}
+		keysB64 = append(keysB64, keyB64)
+	}
+	keysAndEntries, latestLedger, err := h.getter.GetLedgerEntries(ctx, keys)
+	if err != nil {
+		return nil, 0, err
+	}
+	if err := sortKeysAndEntriesAccordingToRequest(keysB64, keysAndEntries); err != nil {
+		return nil, 0, err
+	}
+	results := make([]protocol.LedgerEntryResult, 0, len(keysAndEntries))
+	for _, keyAndEntry := range keysAndEntries {
+		result, err := ledgerKeyEntryToResult(keyAndEntry, format, latestLedger)
+		if err != nil {
+			return nil, 0, err
+		}
+		results = append(results, result)
+	}
+	return results, latestLedger, nil
+}
+
+// scValHasPrefix returns true if the key is the prefix or, if both are
+// vectors, if the key starts with the elements of the prefix.
+func scValHasPrefix(key xdr.ScVal, prefix xdr.ScVal) bool {
+	keyVec, keyIsVec := key.GetVec()
+	prefixVec, prefixIsVec := prefix.GetVec()
+	if !keyIsVec || !prefixIsVec || keyVec == nil || prefixVec == nil {
+		return scValEqual(key, prefix)
+	}
+	if len(*prefixVec) > len(*keyVec) {
+		return false
+	}
+	for i, element := range *prefixVec {
+		if !scValEqual((*keyVec)[i], element) {
+			return false
+		}
+	}
+	return true
+}
+
+func scValEqual(a xdr.ScVal, b xdr.ScVal) bool {
+	aXDR, err := a.MarshalBinary()
+	if err != nil {
+		return false
+	}
+	bXDR, err := b.MarshalBinary()
+	return err == nil && bytes.Equal(aXDR, bXDR)
+}
@@ -0,0 +1,125 @@
+package methods
+
+import (
+	"bytes"
+	"context"
+	"slices"
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+type inMemoryContractDataKeyReader [][]byte
+
+func (r inMemoryContractDataKeyReader) GetContractDataKeys(_ context.Context, _ []byte, after []byte,
+	limit uint,
+) ([][]byte, error) {
+	keys := slices.Clone(r)
+	slices.SortFunc(keys, bytes.Compare)
+	keys = slices.DeleteFunc(keys, func(key []byte) bool { return bytes.Compare(key, after) <= 0 })
+	return keys[:min(len(keys), int(limit))], nil //nolint:gosec
+}
+
+func (r inMemoryContractDataKeyReader) GetContractDataKeysFirstLedger(context.Context) (uint32, error) {
+	return 5, nil
+}
+
+// contractDataLedgerEntryGetter returns contract data entries for all the
+// requested keys
+type contractDataLedgerEntryGetter struct{}
+
+func (contractDataLedgerEntryGetter) GetLedgerEntries(_ context.Context, keys []xdr.LedgerKey,
+) ([]ledgerentries.LedgerKeyAndEntry, uint32, error) {
+	result := make([]ledgerentries.LedgerKeyAndEntry, 0, len(keys))
+	for _, key := range slices.Backward(keys) {
+		result = append(result, ledgerentries.LedgerKeyAndEntry{
+			Key: key,
+			Entry: xdr.LedgerEntry{Data: xdr.LedgerEntryData{
+				Type: xdr.LedgerEntryTypeContractData,
+				ContractData: &xdr.ContractDataEntry{
+					Contract:   key.ContractData.Contract,
+					Key:        key.ContractData.Key,
+					Durability: key.ContractData.Durability,
+					Val:        key.ContractData.Key,
+				},
+			}},
+		})
+	}
+	return result, 10, nil
+}
+
+func TestGetContractData(t *testing.T) {
+	symbol := func(s string) xdr.ScVal {
+		sym := xdr.ScSymbol(s)
+		return xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &sym}
+	}
+	vec := func(elements ...xdr.ScVal) xdr.ScVal {
+		v := xdr.ScVec(elements)
+		pv := &v
+		return xdr.ScVal{Type: xdr.ScValTypeScvVec, Vec: &pv}
+	}
+	keys := []xdr.ScVal{
+		vec(symbol("Balance"), symbol("a")),
+		vec(symbol("Balance"), symbol("b")),
+		vec(symbol("Allowance"), symbol("a")),
+		symbol("Admin"),
+	}
+	var keyReader inMemoryContractDataKeyReader
+	for _, key := range keys {
+		keyXDR, err := key.MarshalBinary()
+		require.NoError(t, err)
+		keyReader = append(keyReader, keyXDR)
+	}
+	handler := contractDataHandler{
+		logger:       log.DefaultLogger,
+		keyReader:    keyReader,
+		ledgerReader: db.NewMockLedgerReader(db.NewMockTransactionStore(passphrase)),
+		getter:       contractDataLedgerEntryGetter{},
+		maxLimit:     2,
+	}
+	ctx := context.TODO()
+	request := protocol.GetContractDataRequest{
+		ContractID: strkey.MustEncode(strkey.VersionByteContract, make([]byte, 32)),
+	}
+
+	var found int
+	for {
+		response, err := handler.getContractData(ctx, request)
+		require.NoError(t, err)
+		found += len(response.Entries)
+		if response.Cursor == "" {
+			break
+		}
+		request.Pagination = &protocol.LedgerPaginationOptions{Cursor: response.Cursor}
+	}
+	assert.Equal(t, len(keys), found)
+
+	balancesPrefix, err := xdr.MarshalBase64(vec(symbol("Balance")))
+	require.NoError(t, err)
+	request.KeyPrefix = balancesPrefix
+	request.Pagination = nil
+	response, err := handler.getContractData(ctx, request)
+	require.NoError(t, err)
+	require.Len(t, response.Entries, 2)
+	for i, entry := range response.Entries {
+		var data xdr.LedgerEntryData
+		require.NoError(t, xdr.SafeUnmarshalBase64(entry.DataXDR, &data))
+		assert.Equal(t, keys[i], data.MustContractData().Key)
+	}
+	assert.EqualValues(t, 10, response.LatestLedger)
+	assert.EqualValues(t, 5, response.FirstIndexedLedger)
+	assert.False(t, response.Complete)
+
+	request.Pagination = &protocol.LedgerPaginationOptions{Limit: 3}
+	_, err = handler.getContractData(ctx, request)
+	require.ErrorContains(t, err, "limit must not exceed 2")
+}
@@ -1,6 +1,7 @@
 package methods
 
 import (
+	"cmp"
 	"context"
 	"encoding/json"
 	"fmt"
@@ -14,7 +15,10 @@ import (
 	"github.com/stellar/go/support/log"
 	"github.com/stellar/go/xdr"
 
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/xdr2json"
 	"github.com/stellar/stellar-rpc/protocol"
 )
@@ -28,8 +32,20 @@ type eventsRPCHandler struct {
 	dbReader     db.EventReader
 	maxLimit     uint
 	defaultLimit uint
-	logger       *log.Entry
-	ledgerReader db.LedgerReader
+	// maxContractIDs is the maximum number of contract IDs per filter,
+	// protocol.MaxContractIDsLimit if 0
+	maxContractIDs uint
+	logger         *log.Entry
+	ledgerReader   db.LedgerReader
+	// networkPassphrase is used to compute the contract ID of the assets of
+	// the token filters
+	networkPassphrase string
+	// ledgerEntryGetter is used to fetch the contract specs when decoding
+	// events
+	ledgerEntryGetter ledgerentries.LedgerEntryGetter
+	// backfill serves the events preceding the local retention window, which
+	// are out of range if it is nil
+	backfill *EventBackfill
 }
 
 func combineContractIDs(filters []protocol.EventFilter) ([][]byte, error) {
@@ -78,8 +94,15 @@ func combineTopics(filters []protocol.EventFilter) ([][][]byte, error) {
 
 		for _, topicFilter := range filter.Topics {
 			for i, segmentFilter := range topicFilter {
-				if segmentFilter.Wildcard == nil && segmentFilter.ScVal != nil {
-					encodedTopic, err := segmentFilter.ScVal.MarshalBinary()
+				if segmentFilter.Wildcard != nil {
+					continue
+				}
+				values := segmentFilter.Alternatives
+				if segmentFilter.ScVal != nil {
+					values = []xdr.ScVal{*segmentFilter.ScVal}
+				}
+				for _, value := range values {
+					encodedTopic, err := value.MarshalBinary()
 					if err != nil {
 						return [][][]byte{}, fmt.Errorf("failed to marshal segment: %w", err)
 					}
@@ -97,6 +120,8 @@ type entry struct {
 	ledgerCloseTimestamp int64
 	event                xdr.DiagnosticEvent
 	txHash               *xdr.Hash
+	opIndex              *uint32
+	matchedFilters       []int
 }
 
 // TODO: remove this linter exclusions
@@ -104,44 +129,297 @@ type entry struct {
 //nolint:cyclop,funlen
 func (h eventsRPCHandler) getEvents(ctx context.Context, request protocol.GetEventsRequest,
 ) (protocol.GetEventsResponse, error) {
-	if err := request.Valid(h.maxLimit); err != nil {
+	maxContractIDs := cmp.Or(h.maxContractIDs, protocol.MaxContractIDsLimit)
+	err := request.ValidWithContractIDsLimit(h.maxLimit, maxContractIDs)
+	if err == nil {
+		err = request.ExpandTokens(h.networkPassphrase)
+	}
+	if err != nil {
 		return protocol.GetEventsResponse{}, &jrpc2.Error{
 			Code: jrpc2.InvalidParams, Message: err.Error(),
 		}
 	}
 
-	ledgerRange, err := h.ledgerReader.GetLedgerRange(ctx)
+	ledgerRange, err := h.getLedgerRange(ctx)
+	if err == nil {
+		err = h.resolveTimes(ctx, &request, ledgerRange)
+	}
 	if err != nil {
 		return protocol.GetEventsResponse{}, &jrpc2.Error{
 			Code: jrpc2.InternalError, Message: err.Error(),
 		}
 	}
 
-	start := protocol.Cursor{Ledger: request.StartLedger}
 	limit := h.defaultLimit
-	if request.Pagination != nil {
-		if request.Pagination.Cursor != nil {
-			start = *request.Pagination.Cursor
-			// increment event index because, when paginating, we start with the
-			// item right after the cursor
-			start.Event++
+	if request.Pagination != nil && request.Pagination.Limit > 0 {
+		limit = request.Pagination.Limit
+	}
+	scan, err := h.newEventsScanWithBackfill(ctx, request, ledgerRange)
+	if err != nil {
+		return protocol.GetEventsResponse{}, err
+	}
+	found := make([]entry, 0, limit+1)
+
+	// Scan function to apply filters, finding an extra event to tell whether
+	// there are more
+	eventScanFunction := func(
+		event xdr.DiagnosticEvent, cursor protocol.Cursor, ledgerCloseTimestamp int64, txHash *xdr.Hash, opIndex *uint32,
+	) bool {
+		matchedFilters := request.MatchingFilters(event)
+		if len(request.Filters) == 0 || len(matchedFilters) > 0 {
+			found = append(found, entry{cursor, ledgerCloseTimestamp, event, txHash, opIndex, matchedFilters})
 		}
-		if request.Pagination.Limit > 0 {
-			limit = request.Pagination.Limit
+		return uint(len(found)) <= limit
+	}
+
+	if err := h.scanEvents(ctx, scan, request.Order, eventScanFunction); err != nil {
+		return protocol.GetEventsResponse{}, err
+	}
+	moreFound := uint(len(found)) > limit
+	if moreFound {
+		found = found[:limit]
+	}
+	var latestMatchingLedger uint32
+	if request.IncludeLatestMatchingLedger {
+		latestMatchingLedger, err = h.latestMatchingLedger(ctx, request, scan, ledgerRange)
+		if err != nil {
+			return protocol.GetEventsResponse{}, err
 		}
 	}
-	endLedger := start.Ledger + LedgerScanLimit
-	// endLedger should not exceed ledger retention window
-	endLedger = min(ledgerRange.LastLedger.Sequence+1, endLedger)
+
+	results := make([]protocol.EventInfo, 0, len(found))
+	for _, entry := range found {
+		info, err := eventInfoForEvent(
+			entry.event,
+			entry.cursor,
+			time.Unix(entry.ledgerCloseTimestamp, 0).UTC().Format(time.RFC3339),
+			entry.txHash.HexString(),
+			request.Format,
+		)
+		if err != nil {
+			return protocol.GetEventsResponse{}, errors.Wrap(err, "could not parse event")
+		}
+		info.OpIndex = entry.opIndex
+		info.MatchedFilters = entry.matchedFilters
+		results = append(results, info)
+	}
+	if request.Decode {
+		if err := h.decodeEvents(ctx, found, results, request.Format); err != nil {
+			return protocol.GetEventsResponse{}, errors.Wrap(err, "could not decode events")
+		}
+	}
+
+	var cursor protocol.Cursor
+	switch {
+	case uint(len(results)) == limit:
+		cursor = found[len(found)-1].cursor
+	case request.Order == protocol.EventOrderDesc:
+		// cursor represents the start of the search window, which is exclusive
+		// when paginating backwards
+		cursor = scan.cursorRange.Start
+	default:
+		// cursor represents end of the search window if events does not reach limit
+		// here endLedger is always exclusive when fetching events
+		// so search window is max Cursor value with endLedger - 1
+		cursor = protocol.MaxCursor
+		cursor.Ledger = scan.cursorRange.End.Ledger - 1
+	}
+	lastScannedLedger := scan.cursorRange.End.Ledger - 1
+	switch {
+	case moreFound:
+		lastScannedLedger = found[len(found)-1].cursor.Ledger
+	case request.Order == protocol.EventOrderDesc:
+		lastScannedLedger = scan.cursorRange.Start.Ledger
+	}
+
+	return protocol.GetEventsResponse{
+		Events:               results,
+		Cursor:               cursor.Encode(),
+		HasMore:              moreFound || !scan.reachesRangeEnd(request, ledgerRange),
+		LastScannedLedger:    lastScannedLedger,
+		LatestMatchingLedger: latestMatchingLedger,
+
+		LatestLedger:          ledgerRange.LastLedger.Sequence,
+		OldestLedger:          ledgerRange.FirstLedger.Sequence,
+		LatestLedgerCloseTime: ledgerRange.LastLedger.CloseTime,
+		OldestLedgerCloseTime: ledgerRange.FirstLedger.CloseTime,
+	}, nil
+}
+
+// getLedgerRange returns the range of ledgers whose events are stored, which
+// starts before the oldest stored ledger when events are retained for longer.
+func (h eventsRPCHandler) getLedgerRange(ctx context.Context) (ledgerbucketwindow.LedgerRange, error) {
+	ledgerRange, err := h.ledgerReader.GetLedgerRange(ctx)
+	if err != nil {
+		return ledgerbucketwindow.LedgerRange{}, err
+	}
+	oldestEventLedger, ok, err := h.dbReader.GetOldestLedger(ctx)
+	if err != nil {
+		return ledgerbucketwindow.LedgerRange{}, err
+	}
+	if ok && oldestEventLedger.Sequence < ledgerRange.FirstLedger.Sequence {
+		ledgerRange.FirstLedger = oldestEventLedger
+	}
+	return ledgerRange, nil
+}
+
+// eventsScan holds the parameters of the database scan of the events of a
+// request, whose results are then matched against its filters.
+type eventsScan struct {
+	cursorRange     protocol.CursorRange
+	contractIDs     [][]byte
+	topics          db.NestedTopicArray
+	eventTypes      []int
+	transactionHash *xdr.Hash
+	// backfill is set when the events are scanned from the backfill of the
+	// handler rather than from the database
+	backfill bool
+}
+
+// reachesRangeEnd tells whether the scan reaches the end of the range of the
+// request (its start, when in descending order), so that there is nothing
+// left to scan once all its events are returned.
+func (s eventsScan) reachesRangeEnd(request protocol.GetEventsRequest, ledgerRange ledgerbucketwindow.LedgerRange,
+) bool {
+	if request.Order == protocol.EventOrderDesc {
+		return s.cursorRange.Start.Ledger <= max(request.StartLedger, ledgerRange.FirstLedger.Sequence)
+	}
+	end := ledgerRange.LastLedger.Sequence + 1
 	if request.EndLedger != 0 {
-		endLedger = min(request.EndLedger, endLedger)
+		end = min(request.EndLedger, end)
 	}
+	return s.cursorRange.End.Ledger >= end
+}
 
-	end := protocol.Cursor{Ledger: endLedger}
-	cursorRange := protocol.CursorRange{Start: start, End: end}
+// latestMatchingLedger returns the latest ledger, up to the end of the range
+// of the request, with an event matching its filters, or 0 if there is none.
+// Only the last LedgerScanLimit ledgers of the range are looked up.
+func (h eventsRPCHandler) latestMatchingLedger(ctx context.Context, request protocol.GetEventsRequest,
+	scan eventsScan, ledgerRange ledgerbucketwindow.LedgerRange,
+) (uint32, error) {
+	end := ledgerRange.LastLedger.Sequence + 1
+	if request.EndLedger != 0 {
+		end = min(request.EndLedger, end)
+	}
+	start := max(end, LedgerScanLimit) - LedgerScanLimit
+	start = max(start, ledgerRange.FirstLedger.Sequence)
+	if start >= end {
+		return 0, nil
+	}
 
-	if start.Ledger < ledgerRange.FirstLedger.Sequence || start.Ledger > ledgerRange.LastLedger.Sequence {
-		return protocol.GetEventsResponse{}, &jrpc2.Error{
+	latestScan := scan
+	latestScan.cursorRange = protocol.CursorRange{
+		Start: protocol.Cursor{Ledger: start},
+		End:   protocol.Cursor{Ledger: end},
+	}
+	latestScan.backfill = false
+	var latest uint32
+	err := h.scanEvents(ctx, latestScan, protocol.EventOrderDesc, func(
+		event xdr.DiagnosticEvent, cursor protocol.Cursor, _ int64, _ *xdr.Hash, _ *uint32,
+	) bool {
+		if len(request.Filters) == 0 || len(request.MatchingFilters(event)) > 0 {
+			latest = cursor.Ledger
+			return false
+		}
+		return true
+	})
+	return latest, err
+}
+
+// resolveTimes replaces the start and end times of the request with the
+// ledgers they resolve to. A start time after the latest event resolves to the
+// latest ledger, so that there are no events rather than an error, and an end
+// time after the latest ledger leaves the end open.
+func (h eventsRPCHandler) resolveTimes(ctx context.Context, request *protocol.GetEventsRequest,
+	ledgerRange ledgerbucketwindow.LedgerRange,
+) error {
+	if request.StartTime != nil {
+		ledger, ok, err := h.dbReader.GetLedgerByCloseTime(ctx, request.StartTime.Unix())
+		if err != nil {
+			return err
+		}
+		request.StartLedger = ledgerRange.LastLedger.Sequence
+		if ok {
+			request.StartLedger = ledger
+		}
+	}
+	if request.EndTime != nil {
+		ledger, ok, err := h.dbReader.GetLedgerByCloseTime(ctx, request.EndTime.Unix())
+		if err != nil {
+			return err
+		}
+		switch {
+		case ok:
+			request.EndLedger = max(ledger, request.StartLedger)
+		case request.EndTime.Unix() <= ledgerRange.LastLedger.CloseTime:
+			// none of the events up to the latest ledger close after it
+			request.EndLedger = ledgerRange.LastLedger.Sequence + 1
+		}
+	}
+	request.StartTime, request.EndTime = nil, nil
+	return nil
+}
+
+// newEventsScanWithBackfill returns the scan of the request, which is
+// backfilled when it starts before the local retention window, in ascending
+// order.
+func (h eventsRPCHandler) newEventsScanWithBackfill(ctx context.Context, request protocol.GetEventsRequest,
+	ledgerRange ledgerbucketwindow.LedgerRange,
+) (eventsScan, error) {
+	startLedger := request.StartLedger
+	if request.Pagination != nil && request.Pagination.Cursor != nil {
+		startLedger = request.Pagination.Cursor.Ledger
+		// The cursor ending the backfilled ledgers continues from the first
+		// local ledger
+		ledgerEnd := protocol.MaxCursor
+		ledgerEnd.Ledger = ledgerRange.FirstLedger.Sequence - 1
+		if *request.Pagination.Cursor == ledgerEnd {
+			pagination := *request.Pagination
+			pagination.Cursor = nil
+			request.Pagination = &pagination
+			request.StartLedger = ledgerRange.FirstLedger.Sequence
+			startLedger = request.StartLedger
+		}
+	}
+	if h.backfill == nil || request.Order == protocol.EventOrderDesc || startLedger >= ledgerRange.FirstLedger.Sequence {
+		return newEventsScan(request, ledgerRange)
+	}
+	backfillRange, ok, err := h.backfill.ledgerRange(ctx, ledgerRange)
+	if err != nil {
+		return eventsScan{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+	if !ok {
+		return newEventsScan(request, ledgerRange)
+	}
+	scan, err := newEventsScan(request, backfillRange)
+	if err != nil {
+		return eventsScan{}, err
+	}
+	scan.cursorRange = h.backfill.clampCursorRange(scan.cursorRange)
+	scan.backfill = true
+	return scan, nil
+}
+
+func newEventsScan(request protocol.GetEventsRequest, ledgerRange ledgerbucketwindow.LedgerRange,
+) (eventsScan, error) {
+	cursorRange, startLedger := getEventsCursorRange(
+		request, ledgerRange.FirstLedger.Sequence, ledgerRange.LastLedger.Sequence)
+	// Cursors keep pointing to the same events, unless they have been trimmed
+	if request.Pagination != nil && request.Pagination.Cursor != nil &&
+		startLedger < ledgerRange.FirstLedger.Sequence {
+		return eventsScan{}, &jrpc2.Error{
+			Code: protocol.CursorTooOldErrorCode,
+			Message: fmt.Sprintf(
+				"cursor is too old: its ledger (%d) precedes the oldest ledger (%d)",
+				startLedger,
+				ledgerRange.FirstLedger.Sequence,
+			),
+		}
+	}
+	if startLedger < ledgerRange.FirstLedger.Sequence || startLedger > ledgerRange.LastLedger.Sequence {
+		return eventsScan{}, &jrpc2.Error{
 			Code: jrpc2.InvalidRequest,
 			Message: fmt.Sprintf(
 				"startLedger must be within the ledger range: %d - %d",
@@ -151,78 +429,101 @@ func (h eventsRPCHandler) getEvents(ctx context.Context, request protocol.GetEve
 		}
 	}
 
-	found := make([]entry, 0, limit)
-
 	contractIDs, err := combineContractIDs(request.Filters)
 	if err != nil {
-		return protocol.GetEventsResponse{}, &jrpc2.Error{
+		return eventsScan{}, &jrpc2.Error{
 			Code: jrpc2.InvalidParams, Message: err.Error(),
 		}
 	}
 
 	topics, err := combineTopics(request.Filters)
 	if err != nil {
-		return protocol.GetEventsResponse{}, &jrpc2.Error{
+		return eventsScan{}, &jrpc2.Error{
 			Code: jrpc2.InvalidParams, Message: err.Error(),
 		}
 	}
 
-	eventTypes := combineEventTypes(request.Filters)
-
-	// Scan function to apply filters
-	eventScanFunction := func(
-		event xdr.DiagnosticEvent, cursor protocol.Cursor, ledgerCloseTimestamp int64, txHash *xdr.Hash,
-	) bool {
-		if request.Matches(event) {
-			found = append(found, entry{cursor, ledgerCloseTimestamp, event, txHash})
+	var transactionHash *xdr.Hash
+	if request.TransactionHash != "" {
+		hash, err := parseTransactionHash(request.TransactionHash)
+		if err != nil {
+			return eventsScan{}, err
 		}
-		return uint(len(found)) < limit
+		transactionHash = &hash
 	}
 
-	err = h.dbReader.GetEvents(ctx, cursorRange, contractIDs, topics, eventTypes, eventScanFunction)
+	return eventsScan{
+		cursorRange:     cursorRange,
+		contractIDs:     contractIDs,
+		topics:          topics,
+		eventTypes:      combineEventTypes(request.Filters),
+		transactionHash: transactionHash,
+	}, nil
+}
+
+func (h eventsRPCHandler) scanEvents(ctx context.Context, scan eventsScan, order string, f db.ScanFunction) error {
+	if scan.backfill {
+		err := h.backfill.GetEvents(ctx, scan.cursorRange, scan.contractIDs, scan.eventTypes, scan.transactionHash, f)
+		if err != nil {
+			return &jrpc2.Error{
+				Code: jrpc2.InternalError, Message: err.Error(),
+			}
+		}
+		return nil
+	}
+	err := h.dbReader.GetEvents(ctx, scan.cursorRange, order, scan.contractIDs, scan.topics, scan.eventTypes,
+		scan.transactionHash, f)
 	if err != nil {
-		return protocol.GetEventsResponse{}, &jrpc2.Error{
+		return &jrpc2.Error{
 			Code: jrpc2.InvalidRequest, Message: err.Error(),
 		}
 	}
+	return nil
+}
 
-	results := make([]protocol.EventInfo, 0, len(found))
-	for _, entry := range found {
-		info, err := eventInfoForEvent(
-			entry.event,
-			entry.cursor,
-			time.Unix(entry.ledgerCloseTimestamp, 0).UTC().Format(time.RFC3339),
-			entry.txHash.HexString(),
-			request.Format,
-		)
-		if err != nil {
-			return protocol.GetEventsResponse{}, errors.Wrap(err, "could not parse event")
-		}
-		results = append(results, info)
+// getEventsCursorRange returns the range of events to scan for the request,
+// along with the ledger which must be within the ledger range (the
+// startLedger of the request, or the ledger of its cursor).
+func getEventsCursorRange(
+	request protocol.GetEventsRequest, firstLedger, latestLedger uint32,
+) (protocol.CursorRange, uint32) {
+	var cursor *protocol.Cursor
+	if request.Pagination != nil {
+		cursor = request.Pagination.Cursor
 	}
 
-	var cursor string
-	if uint(len(results)) == limit {
-		lastEvent := results[len(results)-1]
-		cursor = lastEvent.ID
-	} else {
-		// cursor represents end of the search window if events does not reach limit
-		// here endLedger is always exclusive when fetching events
-		// so search window is max Cursor value with endLedger - 1
-		maxCursor := protocol.MaxCursor
-		maxCursor.Ledger = endLedger - 1
-		cursor = maxCursor.String()
+	if request.Order == protocol.EventOrderDesc {
+		// scan backwards from the end of the ledger range, or from the cursor
+		// (exclusive)
+		end := protocol.Cursor{Ledger: latestLedger + 1}
+		if request.EndLedger != 0 {
+			end.Ledger = min(request.EndLedger, end.Ledger)
+		}
+		startLedger := request.StartLedger
+		if cursor != nil {
+			end = *cursor
+			startLedger = cursor.Ledger
+		}
+		// startLedger should not precede the ledger retention window
+		start := protocol.Cursor{Ledger: max(end.Ledger, LedgerScanLimit) - LedgerScanLimit}
+		start.Ledger = max(start.Ledger, request.StartLedger, firstLedger)
+		return protocol.CursorRange{Start: start, End: end}, startLedger
 	}
 
-	return protocol.GetEventsResponse{
-		Events: results,
-		Cursor: cursor,
-
-		LatestLedger:          ledgerRange.LastLedger.Sequence,
-		OldestLedger:          ledgerRange.FirstLedger.Sequence,
-		LatestLedgerCloseTime: ledgerRange.LastLedger.CloseTime,
-		OldestLedgerCloseTime: ledgerRange.FirstLedger.CloseTime,
-	}, nil
+	start := protocol.Cursor{Ledger: request.StartLedger}
+	if cursor != nil {
+		start = *cursor
+		// increment event index because, when paginating, we start with the
+		// item right after the cursor
+		start.Event++
+	}
+	endLedger := start.Ledger + LedgerScanLimit
+	// endLedger should not exceed ledger retention window
+	endLedger = min(latestLedger+1, endLedger)
+	if request.EndLedger != 0 {
+		endLedger = min(request.EndLedger, endLedger)
+	}
+	return protocol.CursorRange{Start: start, End: protocol.Cursor{Ledger: endLedger}}, start.Ledger
 }
 
 func eventInfoForEvent(
@@ -247,7 +548,6 @@ func eventInfoForEvent(
 		ID:                       cursor.String(),
 		InSuccessfulContractCall: event.InSuccessfulContractCall,
 		TransactionHash:          txHash,
-		OpIndex:                  cursor.Op,
 		TxIndex:                  cursor.Tx,
 	}
 
@@ -304,14 +604,22 @@ func NewGetEventsHandler(
 	dbReader db.EventReader,
 	maxLimit uint,
 	defaultLimit uint,
+	maxContractIDs uint,
 	ledgerReader db.LedgerReader,
+	networkPassphrase string,
+	coreClient interfaces.FastCoreClient,
+	backfill *EventBackfill,
 ) jrpc2.Handler {
 	eventsHandler := eventsRPCHandler{
-		dbReader:     dbReader,
-		maxLimit:     maxLimit,
-		defaultLimit: defaultLimit,
-		logger:       logger,
-		ledgerReader: ledgerReader,
+		dbReader:          dbReader,
+		maxLimit:          maxLimit,
+		defaultLimit:      defaultLimit,
+		maxContractIDs:    maxContractIDs,
+		logger:            logger,
+		ledgerReader:      ledgerReader,
+		networkPassphrase: networkPassphrase,
+		ledgerEntryGetter: ledgerentries.NewLedgerEntryGetter(coreClient, ledgerReader),
+		backfill:          backfill,
 	}
 	return NewHandler(eventsHandler.getEvents)
 }
@@ -0,0 +1,116 @@
+package methods
+
+import (
+	"cmp"
+	"context"
+	"slices"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// getEventsCount counts the matching events while scanning them, without
+// building (and converting) an EventInfo for each of them like getEvents.
+func (h eventsRPCHandler) getEventsCount(ctx context.Context, request protocol.GetEventsCountRequest,
+) (protocol.GetEventsCountResponse, error) {
+	if err := request.Valid(cmp.Or(h.maxContractIDs, protocol.MaxContractIDsLimit)); err != nil {
+		return protocol.GetEventsCountResponse{}, &jrpc2.Error{
+			Code: jrpc2.InvalidParams, Message: err.Error(),
+		}
+	}
+
+	ledgerRange, err := h.getLedgerRange(ctx)
+	if err != nil {
+		return protocol.GetEventsCountResponse{}, &jrpc2.Error{
+			Code: jrpc2.InternalError, Message: err.Error(),
+		}
+	}
+
+	eventsRequest := request.EventsRequest()
+	if err := eventsRequest.ExpandTokens(h.networkPassphrase); err != nil {
+		return protocol.GetEventsCountResponse{}, &jrpc2.Error{
+			Code: jrpc2.InvalidParams, Message: err.Error(),
+		}
+	}
+	scan, err := newEventsScan(eventsRequest, ledgerRange)
+	if err != nil {
+		return protocol.GetEventsCountResponse{}, err
+	}
+
+	var total uint64
+	byLedger := map[uint32]uint64{}
+	byContract := map[string]uint64{}
+	eventScanFunction := func(
+		event xdr.DiagnosticEvent, cursor protocol.Cursor, _ int64, _ *xdr.Hash, _ *uint32,
+	) bool {
+		if !eventsRequest.Matches(event) {
+			return true
+		}
+		total++
+		if request.GroupBy == protocol.EventsCountGroupByContract {
+			var contractID string
+			if event.Event.ContractId != nil {
+				contractID = strkey.MustEncode(strkey.VersionByteContract, (*event.Event.ContractId)[:])
+			}
+			byContract[contractID]++
+		} else {
+			byLedger[cursor.Ledger]++
+		}
+		return true
+	}
+
+	if err := h.scanEvents(ctx, scan, protocol.EventOrderAsc, eventScanFunction); err != nil {
+		return protocol.GetEventsCountResponse{}, err
+	}
+
+	return protocol.GetEventsCountResponse{
+		Counts:       eventsCounts(byLedger, byContract),
+		Total:        total,
+		EndLedger:    scan.cursorRange.End.Ledger,
+		LatestLedger: ledgerRange.LastLedger.Sequence,
+		OldestLedger: ledgerRange.FirstLedger.Sequence,
+	}, nil
+}
+
+func eventsCounts(byLedger map[uint32]uint64, byContract map[string]uint64) []protocol.EventsCount {
+	counts := make([]protocol.EventsCount, 0, len(byLedger)+len(byContract))
+	for ledger, count := range byLedger {
+		counts = append(counts, protocol.EventsCount{Ledger: ledger, Count: count})
+	}
+	for contractID, count := range byContract {
+		counts = append(counts, protocol.EventsCount{ContractID: contractID, Count: count})
+	}
+	slices.SortFunc(counts, func(a, b protocol.EventsCount) int {
+		return cmp.Or(
+			cmp.Compare(a.Ledger, b.Ledger),
+			cmp.Compare(b.Count, a.Count),
+			cmp.Compare(a.ContractID, b.ContractID),
+		)
+	})
+	return counts
+}
+
+// NewGetEventsCountHandler returns a json rpc handler to count the events
+// matching filters
+func NewGetEventsCountHandler(
+	logger *log.Entry,
+	dbReader db.EventReader,
+	maxContractIDs uint,
+	ledgerReader db.LedgerReader,
+	networkPassphrase string,
+) jrpc2.Handler {
+	eventsHandler := eventsRPCHandler{
+		dbReader:          dbReader,
+		maxContractIDs:    maxContractIDs,
+		logger:            logger,
+		ledgerReader:      ledgerReader,
+		networkPassphrase: networkPassphrase,
+	}
+	return NewHandler(eventsHandler.getEventsCount)
+}
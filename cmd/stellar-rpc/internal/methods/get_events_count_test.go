@@ -0,0 +1,77 @@
+package methods
+
+import (
+	"context"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestGetEventsCount(t *testing.T) {
+	dbx := newTestDB(t)
+	ctx := context.TODO()
+	logger := log.DefaultLogger
+
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	contractA := xdr.ContractId([32]byte{1})
+	contractB := xdr.ContractId([32]byte{2})
+	eventA := contractEvent(contractA, xdr.ScVec{counterScVal}, counterScVal)
+	eventB := contractEvent(contractB, xdr.ScVec{counterScVal}, counterScVal)
+
+	writer := db.NewReadWriter(logger, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+	for i, txMeta := range [][]xdr.TransactionMeta{
+		{transactionMetaWithEvents(eventA, eventA), transactionMetaWithEvents(eventB)},
+		{transactionMetaWithEvents(eventA)},
+	} {
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+		ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(uint32(i+1), time.Now().Unix(), txMeta...)
+		require.NoError(t, ledgerW.InsertLedger(ledgerCloseMeta))
+		require.NoError(t, eventW.InsertEvents(ledgerCloseMeta))
+		require.NoError(t, write.Commit(ledgerCloseMeta))
+	}
+
+	handler := eventsRPCHandler{
+		dbReader:     db.NewEventReader(logger, dbx, passphrase),
+		ledgerReader: db.NewLedgerReader(dbx),
+	}
+
+	response, err := handler.getEventsCount(ctx, protocol.GetEventsCountRequest{StartLedger: 1})
+	require.NoError(t, err)
+	assert.Equal(t, []protocol.EventsCount{{Ledger: 1, Count: 3}, {Ledger: 2, Count: 1}}, response.Counts)
+	assert.Equal(t, uint64(4), response.Total)
+	assert.Equal(t, uint32(3), response.EndLedger)
+
+	idA := strkey.MustEncode(strkey.VersionByteContract, contractA[:])
+	idB := strkey.MustEncode(strkey.VersionByteContract, contractB[:])
+	response, err = handler.getEventsCount(ctx, protocol.GetEventsCountRequest{
+		StartLedger: 1,
+		GroupBy:     protocol.EventsCountGroupByContract,
+	})
+	require.NoError(t, err)
+	assert.Equal(t, []protocol.EventsCount{{ContractID: idA, Count: 3}, {ContractID: idB, Count: 1}}, response.Counts)
+
+	response, err = handler.getEventsCount(ctx, protocol.GetEventsCountRequest{
+		StartLedger: 1,
+		EndLedger:   2,
+		Filters:     []protocol.EventFilter{{ContractIDs: []string{idB}}},
+	})
+	require.NoError(t, err)
+	assert.Equal(t, []protocol.EventsCount{{Ledger: 1, Count: 1}}, response.Counts)
+	assert.Equal(t, uint64(1), response.Total)
+
+	_, err = handler.getEventsCount(ctx, protocol.GetEventsCountRequest{StartLedger: 1, GroupBy: "topic"})
+	require.EqualError(t, err, "[-32602] groupBy must be either 'ledger' or 'contract'")
+}
@@ -8,6 +8,7 @@ import (
 	"testing"
 	"time"
 
+	"github.com/creachadair/jrpc2"
 	"github.com/sirupsen/logrus"
 	"github.com/stretchr/testify/assert"
 	"github.com/stretchr/testify/require"
@@ -40,7 +41,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 		ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
@@ -81,6 +82,13 @@ func TestGetEvents(t *testing.T) {
 			StartLedger: 3,
 		})
 		require.EqualError(t, err, "[-32600] startLedger must be within the ledger range: 2 - 2")
+
+		_, err = handler.getEvents(context.TODO(), protocol.GetEventsRequest{
+			Pagination: &protocol.PaginationOptions{Cursor: &protocol.Cursor{Ledger: 1}},
+		})
+		var jsonRPCErr *jrpc2.Error
+		require.ErrorAs(t, err, &jsonRPCErr)
+		assert.Equal(t, jrpc2.Code(protocol.CursorTooOldErrorCode), jsonRPCErr.Code)
 	})
 
 	t.Run("no filtering returns all", func(t *testing.T) {
@@ -89,7 +97,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -126,7 +134,8 @@ func TestGetEvents(t *testing.T) {
 			ledgerReader: db.NewLedgerReader(dbx),
 		}
 		results, err := handler.getEvents(context.TODO(), protocol.GetEventsRequest{
-			StartLedger: 1,
+			StartLedger:                 1,
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -153,17 +162,19 @@ func TestGetEvents(t *testing.T) {
 				ValueXDR:                 value,
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(i).HexString(),
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
 				TxIndex:                  uint32(i + 1),
 			})
 		}
 		cursor := protocol.MaxCursor
 		cursor.Ledger = 1
-		cursorStr := cursor.String()
+		cursorStr := cursor.Encode()
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -179,7 +190,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -245,7 +256,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -291,6 +302,7 @@ func TestGetEvents(t *testing.T) {
 					},
 				}},
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -314,17 +326,20 @@ func TestGetEvents(t *testing.T) {
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(4).HexString(),
 				TxIndex:                  5,
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
+				MatchedFilters:           []int{0},
 			},
 		}
 
 		cursor := protocol.MaxCursor
 		cursor.Ledger = 1
-		cursorStr := cursor.String()
+		cursorStr := cursor.Encode()
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -344,6 +359,7 @@ func TestGetEvents(t *testing.T) {
 					},
 				}},
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -369,6 +385,8 @@ func TestGetEvents(t *testing.T) {
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -385,7 +403,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -430,7 +448,7 @@ func TestGetEvents(t *testing.T) {
 
 		cursor := protocol.MaxCursor
 		cursor.Ledger = 1
-		cursorStr := cursor.String()
+		cursorStr := cursor.Encode()
 
 		scVal := xdr.ScVal{
 			Type: xdr.ScValTypeScvU64,
@@ -458,6 +476,7 @@ func TestGetEvents(t *testing.T) {
 			protocol.GetEventsResponse{
 				Events:                []protocol.EventInfo{},
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -485,6 +504,7 @@ func TestGetEvents(t *testing.T) {
 			protocol.GetEventsResponse{
 				Events:                []protocol.EventInfo{},
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -506,7 +526,8 @@ func TestGetEvents(t *testing.T) {
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(4).HexString(),
 				TxIndex:                  5,
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
+				MatchedFilters:           []int{0},
 			},
 		}
 		require.NoError(t, err)
@@ -541,6 +562,7 @@ func TestGetEvents(t *testing.T) {
 					},
 				},
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -566,6 +588,8 @@ func TestGetEvents(t *testing.T) {
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -581,7 +605,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -660,6 +684,7 @@ func TestGetEvents(t *testing.T) {
 					},
 				},
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -681,16 +706,19 @@ func TestGetEvents(t *testing.T) {
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(3).HexString(),
 				TxIndex:                  4,
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
+				MatchedFilters:           []int{0},
 			},
 		}
 		cursor := protocol.MaxCursor
 		cursor.Ledger = 1
-		cursorStr := cursor.String()
+		cursorStr := cursor.Encode()
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -706,7 +734,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 		ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
@@ -754,6 +782,7 @@ func TestGetEvents(t *testing.T) {
 			Filters: []protocol.EventFilter{
 				{EventType: map[string]interface{}{protocol.EventTypeSystem: nil}},
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -770,16 +799,19 @@ func TestGetEvents(t *testing.T) {
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(0).HexString(),
 				TxIndex:                  1,
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
+				MatchedFilters:           []int{0},
 			},
 		}
 		cursor := protocol.MaxCursor
 		cursor.Ledger = 1
-		cursorStr := cursor.String()
+		cursorStr := cursor.Encode()
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursorStr,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -795,7 +827,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -828,9 +860,10 @@ func TestGetEvents(t *testing.T) {
 			ledgerReader: db.NewLedgerReader(dbx),
 		}
 		results, err := handler.getEvents(context.TODO(), protocol.GetEventsRequest{
-			StartLedger: 1,
-			Filters:     []protocol.EventFilter{},
-			Pagination:  &protocol.PaginationOptions{Limit: 10},
+			StartLedger:                 1,
+			Filters:                     []protocol.EventFilter{},
+			Pagination:                  &protocol.PaginationOptions{Limit: 10},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -855,15 +888,18 @@ func TestGetEvents(t *testing.T) {
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(i).HexString(),
 				TxIndex:                  uint32(i + 1),
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
 			})
 		}
-		cursor := expected[len(expected)-1].ID
+		cursor := encodedCursor(t, expected[len(expected)-1].ID)
 
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursor,
+				HasMore:               true,
+				LastScannedLedger:     1,
+				LatestMatchingLedger:  1,
 				LatestLedger:          1,
 				OldestLedger:          1,
 				LatestLedgerCloseTime: now.Unix(),
@@ -879,7 +915,7 @@ func TestGetEvents(t *testing.T) {
 		log := log.DefaultLogger
 		log.SetLevel(logrus.TraceLevel)
 
-		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 		write, err := writer.NewTx(ctx)
 		require.NoError(t, err)
 
@@ -945,6 +981,7 @@ func TestGetEvents(t *testing.T) {
 				Cursor: id,
 				Limit:  2,
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -968,14 +1005,17 @@ func TestGetEvents(t *testing.T) {
 				InSuccessfulContractCall: true,
 				TransactionHash:          ledgerCloseMeta.TransactionHash(i).HexString(),
 				TxIndex:                  uint32(i + 1),
-				OpIndex:                  0,
+				OpIndex:                  new(uint32),
 			})
 		}
-		cursor := expected[len(expected)-1].ID
+		cursor := encodedCursor(t, expected[len(expected)-1].ID)
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                expected,
 				Cursor:                cursor,
+				HasMore:               true,
+				LastScannedLedger:     5,
+				LatestMatchingLedger:  5,
 				LatestLedger:          5,
 				OldestLedger:          5,
 				LatestLedgerCloseTime: now.Unix(),
@@ -989,6 +1029,7 @@ func TestGetEvents(t *testing.T) {
 				Cursor: &protocol.Cursor{Ledger: 5, Tx: 2, Op: 0, Event: 1},
 				Limit:  2,
 			},
+			IncludeLatestMatchingLedger: true,
 		})
 		require.NoError(t, err)
 
@@ -999,11 +1040,13 @@ func TestGetEvents(t *testing.T) {
 		// so search window is always max Cursor value with endLedger - 1
 		rawCursor := protocol.MaxCursor
 		rawCursor.Ledger = uint32(endLedger - 1)
-		cursor = rawCursor.String()
+		cursor = rawCursor.Encode()
 		assert.Equal(t,
 			protocol.GetEventsResponse{
 				Events:                []protocol.EventInfo{},
 				Cursor:                cursor,
+				LastScannedLedger:     5,
+				LatestMatchingLedger:  5,
 				LatestLedger:          5,
 				OldestLedger:          5,
 				LatestLedgerCloseTime: now.Unix(),
@@ -1012,6 +1055,216 @@ func TestGetEvents(t *testing.T) {
 			results,
 		)
 	})
+
+	t.Run("in descending order", func(t *testing.T) {
+		dbx := newTestDB(t)
+		ctx := context.TODO()
+		log := log.DefaultLogger
+		log.SetLevel(logrus.TraceLevel)
+
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+
+		ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
+		store := db.NewEventReader(log, dbx, passphrase)
+
+		contractID := xdr.ContractId([32]byte{})
+		event := contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(5, now.Unix(),
+			transactionMetaWithEvents(event, event),
+			transactionMetaWithEvents(event, event),
+		)
+		require.NoError(t, ledgerW.InsertLedger(ledgerCloseMeta), "ingestion failed for ledger ")
+		require.NoError(t, eventW.InsertEvents(ledgerCloseMeta), "ingestion failed for events ")
+		require.NoError(t, write.Commit(ledgerCloseMeta))
+
+		handler := eventsRPCHandler{
+			dbReader:     store,
+			maxLimit:     10000,
+			defaultLimit: 100,
+			ledgerReader: db.NewLedgerReader(dbx),
+		}
+		eventIDs := func(events []protocol.EventInfo) []string {
+			ids := make([]string, 0, len(events))
+			for _, event := range events {
+				ids = append(ids, event.ID)
+			}
+			return ids
+		}
+
+		results, err := handler.getEvents(ctx, protocol.GetEventsRequest{
+			StartLedger: 5,
+			Order:       protocol.EventOrderDesc,
+			Pagination:  &protocol.PaginationOptions{Limit: 3},
+		})
+		require.NoError(t, err)
+		assert.Equal(t, []string{
+			protocol.Cursor{Ledger: 5, Tx: 2, Op: 0, Event: 1}.String(),
+			protocol.Cursor{Ledger: 5, Tx: 2, Op: 0, Event: 0}.String(),
+			protocol.Cursor{Ledger: 5, Tx: 1, Op: 0, Event: 1}.String(),
+		}, eventIDs(results.Events))
+		assert.Equal(t, encodedCursor(t, results.Events[2].ID), results.Cursor)
+		assert.True(t, results.HasMore)
+
+		cursor, err := protocol.ParseCursor(results.Cursor)
+		require.NoError(t, err)
+		results, err = handler.getEvents(ctx, protocol.GetEventsRequest{
+			Order:      protocol.EventOrderDesc,
+			Pagination: &protocol.PaginationOptions{Cursor: &cursor, Limit: 3},
+		})
+		require.NoError(t, err)
+		assert.Equal(t, []string{
+			protocol.Cursor{Ledger: 5, Tx: 1, Op: 0, Event: 0}.String(),
+		}, eventIDs(results.Events))
+		// the search window starts at the oldest ledger
+		assert.Equal(t, protocol.Cursor{Ledger: 5}.Encode(), results.Cursor)
+		assert.False(t, results.HasMore)
+		assert.Equal(t, uint32(5), results.LastScannedLedger)
+	})
+
+	t.Run("filtering by transaction hash", func(t *testing.T) {
+		dbx := newTestDB(t)
+		ctx := context.TODO()
+		log := log.DefaultLogger
+		log.SetLevel(logrus.TraceLevel)
+
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+
+		ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
+		store := db.NewEventReader(log, dbx, passphrase)
+
+		contractID := xdr.ContractId([32]byte{})
+		event := contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(1, now.Unix(),
+			transactionMetaWithEvents(event),
+			transactionMetaWithEvents(event, event),
+			transactionMetaWithEvents(event),
+		)
+		require.NoError(t, ledgerW.InsertLedger(ledgerCloseMeta), "ingestion failed for ledger ")
+		require.NoError(t, eventW.InsertEvents(ledgerCloseMeta), "ingestion failed for events ")
+		require.NoError(t, write.Commit(ledgerCloseMeta))
+
+		handler := eventsRPCHandler{
+			dbReader:     store,
+			maxLimit:     10000,
+			defaultLimit: 100,
+			ledgerReader: db.NewLedgerReader(dbx),
+		}
+		txHash := ledgerCloseMeta.TransactionHash(1).HexString()
+		results, err := handler.getEvents(ctx, protocol.GetEventsRequest{
+			StartLedger:     1,
+			TransactionHash: txHash,
+		})
+		require.NoError(t, err)
+		require.Len(t, results.Events, 2)
+		for i, event := range results.Events {
+			assert.Equal(t, protocol.Cursor{Ledger: 1, Tx: 2, Op: 0, Event: uint32(i)}.String(), event.ID)
+			assert.Equal(t, txHash, event.TransactionHash)
+		}
+
+		results, err = handler.getEvents(ctx, protocol.GetEventsRequest{
+			StartLedger:     1,
+			TransactionHash: xdr.Hash{}.HexString(),
+		})
+		require.NoError(t, err)
+		assert.Empty(t, results.Events)
+	})
+
+	t.Run("with several filters", func(t *testing.T) {
+		dbx := newTestDB(t)
+		ctx := context.TODO()
+		log := log.DefaultLogger
+
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+		write, err := writer.NewTx(ctx)
+		require.NoError(t, err)
+
+		contractA := xdr.ContractId([32]byte{1})
+		contractB := xdr.ContractId([32]byte{2})
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(1, now.Unix(),
+			transactionMetaWithEvents(contractEvent(contractA, xdr.ScVec{counterScVal}, counterScVal)),
+			transactionMetaWithEvents(contractEvent(contractB, xdr.ScVec{counterScVal}, counterScVal)),
+		)
+		require.NoError(t, write.LedgerWriter().InsertLedger(ledgerCloseMeta))
+		require.NoError(t, write.EventWriter().InsertEvents(ledgerCloseMeta))
+		require.NoError(t, write.Commit(ledgerCloseMeta))
+
+		handler := eventsRPCHandler{
+			dbReader:     db.NewEventReader(log, dbx, passphrase),
+			maxLimit:     10000,
+			defaultLimit: 100,
+			ledgerReader: db.NewLedgerReader(dbx),
+		}
+		results, err := handler.getEvents(ctx, protocol.GetEventsRequest{
+			StartLedger: 1,
+			Filters: []protocol.EventFilter{
+				{ContractIDs: []string{strkey.MustEncode(strkey.VersionByteContract, contractA[:])}},
+				{Topics: []protocol.TopicFilter{{{ScVal: &counterScVal}}}},
+			},
+		})
+		require.NoError(t, err)
+		require.Len(t, results.Events, 2)
+		assert.Equal(t, []int{0, 1}, results.Events[0].MatchedFilters)
+		assert.Equal(t, []int{1}, results.Events[1].MatchedFilters)
+	})
+
+	t.Run("with endLedger", func(t *testing.T) {
+		dbx := newTestDB(t)
+		ctx := context.TODO()
+		log := log.DefaultLogger
+		log.SetLevel(logrus.TraceLevel)
+
+		writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+		store := db.NewEventReader(log, dbx, passphrase)
+		contractID := xdr.ContractId([32]byte{})
+		event := contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)
+		for ledger := uint32(1); ledger <= 4; ledger++ {
+			write, err := writer.NewTx(ctx)
+			require.NoError(t, err)
+			ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
+			ledgerCloseMeta := ledgerCloseMetaWithEvents(ledger, now.Unix(), transactionMetaWithEvents(event))
+			require.NoError(t, ledgerW.InsertLedger(ledgerCloseMeta), "ingestion failed for ledger ")
+			require.NoError(t, eventW.InsertEvents(ledgerCloseMeta), "ingestion failed for events ")
+			require.NoError(t, write.Commit(ledgerCloseMeta))
+		}
+
+		handler := eventsRPCHandler{
+			dbReader:     store,
+			maxLimit:     10000,
+			defaultLimit: 100,
+			ledgerReader: db.NewLedgerReader(dbx),
+		}
+		results, err := handler.getEvents(ctx, protocol.GetEventsRequest{
+			StartLedger: 1,
+			EndLedger:   3,
+			Pagination:  &protocol.PaginationOptions{Limit: 1},
+		})
+		require.NoError(t, err)
+		require.Len(t, results.Events, 1)
+		assert.Equal(t, int32(1), results.Events[0].Ledger)
+
+		// the following pages stay within the range
+		var ledgers []int32
+		for range 3 {
+			cursor, err := protocol.ParseCursor(results.Cursor)
+			require.NoError(t, err)
+			results, err = handler.getEvents(ctx, protocol.GetEventsRequest{
+				EndLedger:  3,
+				Pagination: &protocol.PaginationOptions{Cursor: &cursor, Limit: 1},
+			})
+			require.NoError(t, err)
+			for _, event := range results.Events {
+				ledgers = append(ledgers, event.Ledger)
+			}
+		}
+		assert.Equal(t, []int32{2}, ledgers)
+		endCursor := protocol.MaxCursor
+		endCursor.Ledger = 2
+		assert.Equal(t, endCursor.Encode(), results.Cursor)
+	})
 }
 
 func BenchmarkGetEvents(b *testing.B) {
@@ -1029,7 +1282,7 @@ func BenchmarkGetEvents(b *testing.B) {
 	contractID := xdr.ContractId([32]byte{})
 	now := time.Now().UTC()
 
-	writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, passphrase)
+	writer := db.NewReadWriter(log, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
 	write, err := writer.NewTx(ctx)
 	require.NoError(b, err)
 	ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
@@ -1078,6 +1331,13 @@ func BenchmarkGetEvents(b *testing.B) {
 	log.Infof("Benchmark Results: %v ms/op ", msPerOp)
 }
 
+// encodedCursor returns the cursor of an event id, as returned by getEvents
+func encodedCursor(t *testing.T, id string) string {
+	cursor, err := protocol.ParseCursor(id)
+	require.NoError(t, err)
+	return cursor.Encode()
+}
+
 func getTxMetaWithContractEvents(contractID xdr.ContractId) []xdr.TransactionMeta {
 	var counters [1000]xdr.ScSymbol
 	for j := 0; j < len(counters); j++ {
@@ -1,9 +1,11 @@
 package methods
 
 import (
+	"cmp"
 	"context"
 	"fmt"
 	"sort"
+	"strconv"
 
 	"github.com/creachadair/jrpc2"
 
@@ -20,91 +22,212 @@ import (
 //nolint:gochecknoglobals
 var ErrLedgerTTLEntriesCannotBeQueriedDirectly = "ledger ttl entries cannot be queried directly"
 
-const getLedgerEntriesMaxKeys = 200
+type ledgerEntriesHandler struct {
+	logger       *log.Entry
+	getter       ledgerentries.LedgerEntryGetter
+	ledgerReader db.LedgerReader
+	// historyGetter returns the getter of the entries at a past ledger
+	historyGetter func(atLedger uint32) ledgerentries.LedgerEntryGetter
+	// maxLimit is the maximum number of keys looked up by a request (or by a
+	// page of a paginated request)
+	maxLimit uint
+	// maxKeys is the maximum number of keys of a paginated request
+	maxKeys uint
+	// maxRollbackLedgers is the maximum number of ledgers the entries can be
+	// rolled back by
+	maxRollbackLedgers uint
+}
 
 // NewGetLedgerEntriesHandler returns a JSON RPC handler which retrieves ledger entries from Stellar Core.
+// Entries at a past ledger are rolled back using the changes of the stored ledgers
+// (up to maxRollbackLedgers of them).
 func NewGetLedgerEntriesHandler(
 	logger *log.Entry,
 	coreClient interfaces.FastCoreClient,
 	latestLedgerReader db.LedgerReader,
+	networkPassphrase string,
+	maxLimit uint,
+	maxKeys uint,
+	maxRollbackLedgers uint,
 ) jrpc2.Handler {
-	getter := ledgerentries.NewLedgerEntryGetter(coreClient, latestLedgerReader)
-	return newGetLedgerEntriesHandlerFromGetter(logger, getter)
+	h := ledgerEntriesHandler{
+		logger:       logger,
+		getter:       ledgerentries.NewLedgerEntryGetter(coreClient, latestLedgerReader),
+		ledgerReader: latestLedgerReader,
+		historyGetter: func(atLedger uint32) ledgerentries.LedgerEntryGetter {
+			return ledgerentries.NewLedgerEntryHistoryGetter(coreClient, latestLedgerReader, networkPassphrase, atLedger)
+		},
+		maxLimit:           maxLimit,
+		maxKeys:            maxKeys,
+		maxRollbackLedgers: maxRollbackLedgers,
+	}
+	return NewHandler(h.getLedgerEntries)
 }
 
-func newGetLedgerEntriesHandlerFromGetter(logger *log.Entry, getter ledgerentries.LedgerEntryGetter) jrpc2.Handler {
-	return NewHandler(func(ctx context.Context, request protocol.GetLedgerEntriesRequest,
-	) (protocol.GetLedgerEntriesResponse, error) {
-		if err := protocol.IsValidFormat(request.Format); err != nil {
-			return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
-				Code:    jrpc2.InvalidParams,
-				Message: err.Error(),
-			}
+func (h ledgerEntriesHandler) getLedgerEntries(ctx context.Context, request protocol.GetLedgerEntriesRequest,
+) (protocol.GetLedgerEntriesResponse, error) {
+	if err := protocol.IsValidFormat(request.Format); err != nil {
+		return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: err.Error(),
 		}
+	}
 
-		if len(request.Keys) > getLedgerEntriesMaxKeys {
-			return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
-				Code:    jrpc2.InvalidParams,
-				Message: fmt.Sprintf("key count (%d) exceeds maximum supported (%d)", len(request.Keys), getLedgerEntriesMaxKeys),
-			}
+	requestKeys, cursor, err := h.pageKeys(request)
+	if err != nil {
+		return protocol.GetLedgerEntriesResponse{}, err
+	}
+	ledgerKeys, err := h.parseLedgerKeys(request, requestKeys)
+	if err != nil {
+		return protocol.GetLedgerEntriesResponse{}, err
+	}
+	getter, err := h.getterAt(ctx, request.AtLedger)
+	if err != nil {
+		return protocol.GetLedgerEntriesResponse{}, err
+	}
+
+	ledgerKeysAndEntries, latestLedger, err := getter.GetLedgerEntries(ctx, ledgerKeys)
+	if err != nil {
+		h.logger.WithError(err).WithField("request", request).
+			Info("could not obtain ledger entries")
+		return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
 		}
-		var ledgerKeys []xdr.LedgerKey
-		for i, requestKey := range request.Keys {
-			var ledgerKey xdr.LedgerKey
-			if err := xdr.SafeUnmarshalBase64(requestKey, &ledgerKey); err != nil {
-				logger.WithError(err).WithField("request", request).
-					Infof("could not unmarshal requestKey %s at index %d from getLedgerEntries request", requestKey, i)
-				return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
-					Code:    jrpc2.InvalidParams,
-					Message: fmt.Sprintf("cannot unmarshal key value %s at index %d", requestKey, i),
-				}
-			}
-			if ledgerKey.Type == xdr.LedgerEntryTypeTtl {
-				logger.WithField("request", request).
-					Infof("could not provide ledger ttl entry %s at index %d from getLedgerEntries request", requestKey, i)
-				return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
-					Code:    jrpc2.InvalidParams,
-					Message: ErrLedgerTTLEntriesCannotBeQueriedDirectly,
-				}
-			}
-			ledgerKeys = append(ledgerKeys, ledgerKey)
+	}
+	err = sortKeysAndEntriesAccordingToRequest(requestKeys, ledgerKeysAndEntries)
+	if err != nil {
+		return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
 		}
+	}
 
-		ledgerKeysAndEntries, latestLedger, err := getter.GetLedgerEntries(ctx, ledgerKeys)
+	ledgerEntryResults := make([]protocol.LedgerEntryResult, 0, len(ledgerKeys))
+	for _, ledgerKeyAndEntry := range ledgerKeysAndEntries {
+		result, err := ledgerKeyEntryToResult(ledgerKeyAndEntry, request.Format, cmp.Or(request.AtLedger, latestLedger))
 		if err != nil {
-			logger.WithError(err).WithField("request", request).
-				Info("could not obtain ledger entries")
 			return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
 				Code:    jrpc2.InternalError,
 				Message: err.Error(),
 			}
 		}
-		err = sortKeysAndEntriesAccordingToRequest(request.Keys, ledgerKeysAndEntries)
-		if err != nil {
-			return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
-				Code:    jrpc2.InternalError,
-				Message: err.Error(),
+		ledgerEntryResults = append(ledgerEntryResults, result)
+	}
+
+	response := protocol.GetLedgerEntriesResponse{
+		Entries:      ledgerEntryResults,
+		LatestLedger: latestLedger,
+		Cursor:       cursor,
+	}
+	return response, nil
+}
+
+// getterAt returns the getter of the entries at the given ledger, which must
+// be stored and at most maxRollbackLedgers before the latest ledger (the latest
+// ledger if zero).
+func (h ledgerEntriesHandler) getterAt(ctx context.Context, atLedger uint32) (ledgerentries.LedgerEntryGetter, error) {
+	if atLedger == 0 {
+		return h.getter, nil
+	}
+	ledgerRange, err := h.ledgerReader.GetLedgerRange(ctx)
+	if err != nil {
+		return nil, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
+	first, last := ledgerRange.FirstLedger.Sequence, ledgerRange.LastLedger.Sequence
+	// Every ledger since then is read to roll the entries back
+	if uint(last) > h.maxRollbackLedgers {
+		first = max(first, last-uint32(h.maxRollbackLedgers)) //nolint:gosec
+	}
+	if atLedger < first || atLedger > last {
+		return nil, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: fmt.Sprintf("atLedger must be between %d and the latest ledger: %d", first, last),
+		}
+	}
+	if atLedger == last {
+		return h.getter, nil
+	}
+	return h.historyGetter(atLedger), nil
+}
+
+// pageKeys returns the keys of the request to look up, along with the cursor
+// of the next page if some keys are left. Requests without pagination must
+// fit in a single page.
+func (h ledgerEntriesHandler) pageKeys(request protocol.GetLedgerEntriesRequest) ([]string, string, error) {
+	if request.Pagination == nil {
+		if len(request.Keys) > int(h.maxLimit) { //nolint:gosec
+			return nil, "", &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: fmt.Sprintf("key count (%d) exceeds maximum supported (%d)", len(request.Keys), h.maxLimit),
 			}
 		}
+		return request.Keys, "", nil
+	}
 
-		ledgerEntryResults := make([]protocol.LedgerEntryResult, 0, len(ledgerKeys))
-		for _, ledgerKeyAndEntry := range ledgerKeysAndEntries {
-			result, err := ledgerKeyEntryToResult(ledgerKeyAndEntry, request.Format)
-			if err != nil {
-				return protocol.GetLedgerEntriesResponse{}, &jrpc2.Error{
-					Code:    jrpc2.InternalError,
-					Message: err.Error(),
-				}
+	if len(request.Keys) > int(h.maxKeys) { //nolint:gosec
+		return nil, "", &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: fmt.Sprintf("key count (%d) exceeds maximum supported (%d)", len(request.Keys), h.maxKeys),
+		}
+	}
+	limit := h.maxLimit
+	if request.Pagination.Limit > 0 {
+		if request.Pagination.Limit > h.maxLimit {
+			return nil, "", &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: fmt.Sprintf("limit must not exceed %d", h.maxLimit),
+			}
+		}
+		limit = request.Pagination.Limit
+	}
+	start := 0
+	if request.Pagination.Cursor != "" {
+		var err error
+		start, err = strconv.Atoi(request.Pagination.Cursor)
+		if err != nil || start < 0 || start > len(request.Keys) {
+			return nil, "", &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: "invalid cursor: " + request.Pagination.Cursor,
 			}
-			ledgerEntryResults = append(ledgerEntryResults, result)
 		}
+	}
+
+	end := min(start+int(limit), len(request.Keys)) //nolint:gosec
+	cursor := ""
+	if end < len(request.Keys) {
+		cursor = strconv.Itoa(end)
+	}
+	return request.Keys[start:end], cursor, nil
+}
 
-		response := protocol.GetLedgerEntriesResponse{
-			Entries:      ledgerEntryResults,
-			LatestLedger: latestLedger,
+func (h ledgerEntriesHandler) parseLedgerKeys(request protocol.GetLedgerEntriesRequest, requestKeys []string,
+) ([]xdr.LedgerKey, error) {
+	ledgerKeys := make([]xdr.LedgerKey, 0, len(requestKeys))
+	for i, requestKey := range requestKeys {
+		var ledgerKey xdr.LedgerKey
+		if err := xdr.SafeUnmarshalBase64(requestKey, &ledgerKey); err != nil {
+			h.logger.WithError(err).WithField("request", request).
+				Infof("could not unmarshal requestKey %s at index %d from getLedgerEntries request", requestKey, i)
+			return nil, &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: fmt.Sprintf("cannot unmarshal key value %s at index %d", requestKey, i),
+			}
 		}
-		return response, nil
-	})
+		if ledgerKey.Type == xdr.LedgerEntryTypeTtl {
+			h.logger.WithField("request", request).
+				Infof("could not provide ledger ttl entry %s at index %d from getLedgerEntries request", requestKey, i)
+			return nil, &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: ErrLedgerTTLEntriesCannotBeQueriedDirectly,
+			}
+		}
+		ledgerKeys = append(ledgerKeys, ledgerKey)
+	}
+	return ledgerKeys, nil
 }
 
 type keyEntriesAndOrdering struct {
@@ -153,7 +276,7 @@ func sortKeysAndEntriesAccordingToRequest(b64RequestKeys []string, keyEntries []
 }
 
 func ledgerKeyEntryToResult(keyEntry ledgerentries.LedgerKeyAndEntry,
-	format string,
+	format string, latestLedger uint32,
 ) (protocol.LedgerEntryResult, error) {
 	result := protocol.LedgerEntryResult{}
 	switch format {
@@ -185,5 +308,19 @@ func ledgerKeyEntryToResult(keyEntry ledgerentries.LedgerKeyAndEntry,
 	}
 	result.LastModifiedLedger = uint32(keyEntry.Entry.LastModifiedLedgerSeq)
 	result.LiveUntilLedgerSeq = keyEntry.LiveUntilLedgerSeq
+	result.State = ledgerEntryState(keyEntry, latestLedger)
 	return result, nil
 }
+
+// ledgerEntryState returns whether an entry is live, expired (its TTL ended
+// before the latest ledger but it wasn't evicted yet) or archived.
+func ledgerEntryState(keyEntry ledgerentries.LedgerKeyAndEntry, latestLedger uint32) string {
+	switch {
+	case keyEntry.Archived:
+		return protocol.LedgerEntryStateArchived
+	case keyEntry.LiveUntilLedgerSeq != nil && *keyEntry.LiveUntilLedgerSeq < latestLedger:
+		return protocol.LedgerEntryStateExpired
+	default:
+		return protocol.LedgerEntryStateLive
+	}
+}
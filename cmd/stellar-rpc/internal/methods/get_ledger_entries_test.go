@@ -0,0 +1,194 @@
+package methods
+
+import (
+	"context"
+	"encoding/json"
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/keypair"
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// accountsLedgerEntryGetter returns account entries for all the requested keys
+type accountsLedgerEntryGetter struct{}
+
+func (accountsLedgerEntryGetter) GetLedgerEntries(_ context.Context, keys []xdr.LedgerKey,
+) ([]ledgerentries.LedgerKeyAndEntry, uint32, error) {
+	result := make([]ledgerentries.LedgerKeyAndEntry, 0, len(keys))
+	for _, key := range keys {
+		result = append(result, ledgerentries.LedgerKeyAndEntry{
+			Key: key,
+			Entry: xdr.LedgerEntry{Data: xdr.LedgerEntryData{
+				Type:    xdr.LedgerEntryTypeAccount,
+				Account: &xdr.AccountEntry{AccountId: key.Account.AccountId},
+			}},
+		})
+	}
+	return result, 10, nil
+}
+
+func TestGetLedgerEntries_Pagination(t *testing.T) {
+	keys := make([]string, 0, 5)
+	for range 5 {
+		key := xdr.LedgerKey{
+			Type:    xdr.LedgerEntryTypeAccount,
+			Account: &xdr.LedgerKeyAccount{AccountId: xdr.MustAddress(keypair.MustRandom().Address())},
+		}
+		keyB64, err := xdr.MarshalBase64(key)
+		require.NoError(t, err)
+		keys = append(keys, keyB64)
+	}
+	handler := ledgerEntriesHandler{
+		logger:   log.DefaultLogger,
+		getter:   accountsLedgerEntryGetter{},
+		maxLimit: 2,
+		maxKeys:  5,
+	}
+	ctx := context.TODO()
+
+	// Requests without pagination must fit in a single page
+	_, err := handler.getLedgerEntries(ctx, protocol.GetLedgerEntriesRequest{Keys: keys})
+	require.ErrorContains(t, err, "key count (5) exceeds maximum supported (2)")
+
+	request := protocol.GetLedgerEntriesRequest{Keys: keys, Pagination: &protocol.LedgerPaginationOptions{}}
+	var found []string
+	for range 3 {
+		response, err := handler.getLedgerEntries(ctx, request)
+		require.NoError(t, err)
+		for _, entry := range response.Entries {
+			found = append(found, entry.KeyXDR)
+		}
+		request.Pagination.Cursor = response.Cursor
+	}
+	assert.Equal(t, keys, found)
+	assert.Empty(t, request.Pagination.Cursor)
+
+	request.Pagination = &protocol.LedgerPaginationOptions{Cursor: "4", Limit: 1}
+	response, err := handler.getLedgerEntries(ctx, request)
+	require.NoError(t, err)
+	require.Len(t, response.Entries, 1)
+	assert.Equal(t, keys[4], response.Entries[0].KeyXDR)
+	assert.Empty(t, response.Cursor)
+
+	request.Pagination = &protocol.LedgerPaginationOptions{Cursor: "6"}
+	_, err = handler.getLedgerEntries(ctx, request)
+	require.ErrorContains(t, err, "invalid cursor")
+
+	request.Pagination = &protocol.LedgerPaginationOptions{Limit: 3}
+	_, err = handler.getLedgerEntries(ctx, request)
+	require.ErrorContains(t, err, "limit must not exceed 2")
+
+	request.Keys = append(request.Keys, keys[0])
+	request.Pagination = &protocol.LedgerPaginationOptions{}
+	_, err = handler.getLedgerEntries(ctx, request)
+	require.ErrorContains(t, err, "key count (6) exceeds maximum supported (5)")
+}
+
+// rangeLedgerReader stores the ledgers of a fixed range
+type rangeLedgerReader struct {
+	db.LedgerReader
+	first, last uint32
+}
+
+func (r rangeLedgerReader) GetLedgerRange(context.Context) (ledgerbucketwindow.LedgerRange, error) {
+	return ledgerbucketwindow.LedgerRange{
+		FirstLedger: ledgerbucketwindow.LedgerInfo{Sequence: r.first},
+		LastLedger:  ledgerbucketwindow.LedgerInfo{Sequence: r.last},
+	}, nil
+}
+
+func TestGetLedgerEntries_AtLedger(t *testing.T) {
+	key, err := xdr.MarshalBase64(xdr.LedgerKey{
+		Type:    xdr.LedgerEntryTypeAccount,
+		Account: &xdr.LedgerKeyAccount{AccountId: xdr.MustAddress(keypair.MustRandom().Address())},
+	})
+	require.NoError(t, err)
+	var historyLedgers []uint32
+	handler := ledgerEntriesHandler{
+		logger:       log.DefaultLogger,
+		getter:       accountsLedgerEntryGetter{},
+		ledgerReader: rangeLedgerReader{first: 5, last: 10},
+		historyGetter: func(atLedger uint32) ledgerentries.LedgerEntryGetter {
+			historyLedgers = append(historyLedgers, atLedger)
+			return accountsLedgerEntryGetter{}
+		},
+		maxLimit:           1,
+		maxKeys:            1,
+		maxRollbackLedgers: 10,
+	}
+	ctx := context.TODO()
+
+	for _, atLedger := range []uint32{4, 11} {
+		_, err = handler.getLedgerEntries(ctx, protocol.GetLedgerEntriesRequest{Keys: []string{key}, AtLedger: atLedger})
+		require.ErrorContains(t, err, "atLedger must be between 5 and the latest ledger: 10")
+	}
+	// The entries can't be rolled back further than the maximum rollback
+	handler.maxRollbackLedgers = 3
+	_, err = handler.getLedgerEntries(ctx, protocol.GetLedgerEntriesRequest{Keys: []string{key}, AtLedger: 6})
+	require.ErrorContains(t, err, "atLedger must be between 7 and the latest ledger: 10")
+
+	// Only the entries at a past ledger are rolled back
+	for _, atLedger := range []uint32{0, 7, 10} {
+		response, err := handler.getLedgerEntries(ctx,
+			protocol.GetLedgerEntriesRequest{Keys: []string{key}, AtLedger: atLedger})
+		require.NoError(t, err)
+		require.Len(t, response.Entries, 1)
+		assert.Equal(t, key, response.Entries[0].KeyXDR)
+	}
+	assert.Equal(t, []uint32{7}, historyLedgers)
+}
+
+func TestGetLedgerEntries_JSONFormat(t *testing.T) {
+	address := keypair.MustRandom().Address()
+	key, err := xdr.MarshalBase64(xdr.LedgerKey{
+		Type:    xdr.LedgerEntryTypeAccount,
+		Account: &xdr.LedgerKeyAccount{AccountId: xdr.MustAddress(address)},
+	})
+	require.NoError(t, err)
+	handler := ledgerEntriesHandler{
+		logger:   log.DefaultLogger,
+		getter:   accountsLedgerEntryGetter{},
+		maxLimit: 1,
+		maxKeys:  1,
+	}
+
+	response, err := handler.getLedgerEntries(context.TODO(), protocol.GetLedgerEntriesRequest{
+		Keys:   []string{key},
+		Format: protocol.FormatJSON,
+	})
+	require.NoError(t, err)
+	require.Len(t, response.Entries, 1)
+	entry := response.Entries[0]
+	assert.Empty(t, entry.KeyXDR)
+	assert.Empty(t, entry.DataXDR)
+
+	// The entry data is decoded rather than base64 XDR
+	var data map[string]any
+	require.NoError(t, json.Unmarshal(entry.DataJSON, &data))
+	assert.Contains(t, data, "account")
+	assert.Contains(t, string(entry.DataJSON), address)
+	assert.Contains(t, string(entry.KeyJSON), address)
+}
+
+func TestLedgerEntryState(t *testing.T) {
+	liveUntil := uint32(100)
+	assert.Equal(t, protocol.LedgerEntryStateLive, ledgerEntryState(ledgerentries.LedgerKeyAndEntry{}, 200))
+	assert.Equal(t, protocol.LedgerEntryStateLive,
+		ledgerEntryState(ledgerentries.LedgerKeyAndEntry{LiveUntilLedgerSeq: &liveUntil}, 100))
+	assert.Equal(t, protocol.LedgerEntryStateExpired,
+		ledgerEntryState(ledgerentries.LedgerKeyAndEntry{LiveUntilLedgerSeq: &liveUntil}, 101))
+
+	// Core doesn't provide the TTL of archived entries
+	archivedTTL := uint32(0)
+	assert.Equal(t, protocol.LedgerEntryStateArchived,
+		ledgerEntryState(ledgerentries.LedgerKeyAndEntry{LiveUntilLedgerSeq: &archivedTTL, Archived: true}, 101))
+}
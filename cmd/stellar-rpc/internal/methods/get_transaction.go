@@ -2,10 +2,10 @@ package methods
 
 import (
 	"context"
-	"encoding/base64"
 	"encoding/hex"
 	"errors"
 	"fmt"
+	"time"
 
 	"github.com/creachadair/jrpc2"
 
@@ -30,21 +30,9 @@ func GetTransaction(
 		}
 	}
 
-	// parse hash
-	if hex.DecodedLen(len(request.Hash)) != len(xdr.Hash{}) {
-		return protocol.GetTransactionResponse{}, &jrpc2.Error{
-			Code:    jrpc2.InvalidParams,
-			Message: fmt.Sprintf("unexpected hash length (%d)", len(request.Hash)),
-		}
-	}
-
-	var txHash xdr.Hash
-	_, err := hex.Decode(txHash[:], []byte(request.Hash))
+	txHash, err := parseTransactionHash(request.Hash)
 	if err != nil {
-		return protocol.GetTransactionResponse{}, &jrpc2.Error{
-			Code:    jrpc2.InvalidParams,
-			Message: fmt.Sprintf("incorrect hash: %v", err),
-		}
+		return protocol.GetTransactionResponse{}, err
 	}
 
 	storeRange, err := ledgerReader.GetLedgerRange(ctx)
@@ -80,35 +68,33 @@ func GetTransaction(
 	response.ApplicationOrder = tx.ApplicationOrder
 	response.FeeBump = tx.FeeBump
 	response.Ledger = tx.Ledger.Sequence
+	response.TransactionFees = tx.Fees
 	response.LedgerCloseTime = tx.Ledger.CloseTime
 
-	switch request.Format {
-	case protocol.FormatJSON:
-		result, envelope, meta, convErr := transactionToJSON(tx)
-		if convErr != nil {
-			return response, &jrpc2.Error{
-				Code:    jrpc2.InternalError,
-				Message: convErr.Error(),
-			}
+	encode := encodeTransactionDetails
+	if request.EventsOnly {
+		encode = encodeTransactionEvents
+	}
+	if convErr := encode(tx, request.Format, &response.TransactionDetails); convErr != nil {
+		return response, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: convErr.Error(),
+		}
+	}
+
+	if response.ResourceUsage, err = transactionResourceUsage(tx); err != nil {
+		return response, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
 		}
-		diagEvents, convErr := jsonifySlice(xdr.DiagnosticEvent{}, tx.Events)
-		if convErr != nil {
+	}
+	if request.IncludeStateChanges {
+		if response.StateChanges, err = transactionStateChanges(tx.Meta, request.Format); err != nil {
 			return response, &jrpc2.Error{
 				Code:    jrpc2.InternalError,
-				Message: convErr.Error(),
+				Message: err.Error(),
 			}
 		}
-
-		response.ResultJSON = result
-		response.EnvelopeJSON = envelope
-		response.ResultMetaJSON = meta
-		response.DiagnosticEventsJSON = diagEvents
-
-	default:
-		response.ResultXDR = base64.StdEncoding.EncodeToString(tx.Result)
-		response.EnvelopeXDR = base64.StdEncoding.EncodeToString(tx.Envelope)
-		response.ResultMetaXDR = base64.StdEncoding.EncodeToString(tx.Meta)
-		response.DiagnosticEventsXDR = base64EncodeSlice(tx.Events)
 	}
 
 	response.Status = protocol.TransactionStatusFailed
@@ -118,13 +104,66 @@ func GetTransaction(
 	return response, nil
 }
 
-// NewGetTransactionHandler returns a get transaction json rpc handler
+// parseTransactionHash decodes the hex-encoded hash of a transaction
+func parseTransactionHash(hash string) (xdr.Hash, error) {
+	var txHash xdr.Hash
+	if hex.DecodedLen(len(hash)) != len(txHash) {
+		return xdr.Hash{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: fmt.Sprintf("unexpected hash length (%d)", len(hash)),
+		}
+	}
+	if _, err := hex.Decode(txHash[:], []byte(hash)); err != nil {
+		return xdr.Hash{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: fmt.Sprintf("incorrect hash: %v", err),
+		}
+	}
+	return txHash, nil
+}
+
+// getTransactionWaitPollInterval is the interval at which getTransaction
+// requests waiting for their transaction look it up.
+const getTransactionWaitPollInterval = 500 * time.Millisecond
+
+// waitForTransaction waits until the transaction is stored, the timeout
+// expires or the request is cancelled. Lookup errors are left to be reported
+// by GetTransaction.
+func waitForTransaction(ctx context.Context, reader db.TransactionReader, txHash xdr.Hash,
+	timeout time.Duration,
+) {
+	timer := time.NewTimer(timeout)
+	defer timer.Stop()
+	ticker := time.NewTicker(getTransactionWaitPollInterval)
+	defer ticker.Stop()
+	for {
+		if _, err := reader.GetTransaction(ctx, txHash); !errors.Is(err, db.ErrNoTransaction) {
+			return
+		}
+		select {
+		case <-ctx.Done():
+			return
+		case <-timer.C:
+			return
+		case <-ticker.C:
+		}
+	}
+}
 
+// NewGetTransactionHandler returns a get transaction json rpc handler, whose
+// requests wait for up to maxWait for their transaction.
 func NewGetTransactionHandler(logger *log.Entry, getter db.TransactionReader,
-	ledgerReader db.LedgerReader,
+	ledgerReader db.LedgerReader, maxWait time.Duration,
 ) jrpc2.Handler {
 	return NewHandler(func(ctx context.Context, request protocol.GetTransactionRequest,
 	) (protocol.GetTransactionResponse, error) {
+		if wait := min(time.Duration(request.WaitFor)*time.Second, maxWait); wait > 0 { //nolint:gosec
+			txHash, err := parseTransactionHash(request.Hash)
+			if err != nil {
+				return protocol.GetTransactionResponse{}, err
+			}
+			waitForTransaction(ctx, getter, txHash, wait)
+		}
 		return GetTransaction(ctx, logger, getter, ledgerReader, request)
 	})
 }
@@ -5,6 +5,7 @@ import (
 	"encoding/hex"
 	"encoding/json"
 	"testing"
+	"time"
 
 	"github.com/sirupsen/logrus"
 	"github.com/stretchr/testify/require"
@@ -84,6 +85,7 @@ func TestGetTransaction(t *testing.T) {
 			ResultMetaXDR:       expectedTxMeta,
 			Ledger:              101,
 			DiagnosticEventsXDR: []string{},
+			TransactionFees:     protocol.TransactionFees{InclusionFeeBid: 1},
 		},
 		LedgerCloseTime: 2625,
 	}, tx)
@@ -115,6 +117,7 @@ func TestGetTransaction(t *testing.T) {
 			ResultMetaXDR:       expectedTxMeta,
 			Ledger:              101,
 			DiagnosticEventsXDR: []string{},
+			TransactionFees:     protocol.TransactionFees{InclusionFeeBid: 1},
 		},
 		LedgerCloseTime: 2625,
 	}, tx)
@@ -152,6 +155,7 @@ func TestGetTransaction(t *testing.T) {
 			ResultMetaXDR:       expectedTxMeta,
 			Ledger:              102,
 			DiagnosticEventsXDR: []string{},
+			TransactionFees:     protocol.TransactionFees{InclusionFeeBid: 1},
 		},
 		LedgerCloseTime: 2650,
 	}, tx)
@@ -197,6 +201,7 @@ func TestGetTransaction(t *testing.T) {
 			ResultMetaXDR:       expectedTxMeta,
 			Ledger:              103,
 			DiagnosticEventsXDR: []string{expectedEventsMeta},
+			TransactionFees:     protocol.TransactionFees{InclusionFeeBid: 1},
 		},
 		LedgerCloseTime:       2675,
 		LatestLedger:          103,
@@ -204,6 +209,19 @@ func TestGetTransaction(t *testing.T) {
 		OldestLedger:          101,
 		OldestLedgerCloseTime: 2625,
 	}, tx)
+
+	// Only the events are returned when requested
+	tx, err = GetTransaction(ctx, log, store, ledgerReader,
+		protocol.GetTransactionRequest{
+			Hash:       hash,
+			EventsOnly: true,
+		})
+	require.NoError(t, err)
+	require.Equal(t, protocol.TransactionStatusSuccess, tx.Status)
+	require.Equal(t, []string{expectedEventsMeta}, tx.DiagnosticEventsXDR)
+	require.Empty(t, tx.EnvelopeXDR)
+	require.Empty(t, tx.ResultXDR)
+	require.Empty(t, tx.ResultMetaXDR)
 }
 
 func ledgerCloseTime(ledgerSequence uint32) int64 {
@@ -385,6 +403,27 @@ func emptyTxMeta(acctSeq uint32) xdr.LedgerCloseMeta {
 	}
 }
 
+func TestWaitForTransaction(t *testing.T) {
+	ctx := context.TODO()
+	store := db.NewMockTransactionStore("passphrase")
+
+	// Transactions which aren't found are waited for until the timeout
+	start := time.Now()
+	waitForTransaction(ctx, store, txHash(1), time.Second)
+	require.GreaterOrEqual(t, time.Since(start), time.Second)
+
+	require.NoError(t, store.InsertTransactions(txMeta(1, true)))
+	start = time.Now()
+	waitForTransaction(ctx, store, txHash(1), time.Minute)
+	require.Less(t, time.Since(start), time.Second)
+
+	cancelledCtx, cancel := context.WithCancel(ctx)
+	cancel()
+	start = time.Now()
+	waitForTransaction(cancelledCtx, store, txHash(2), time.Minute)
+	require.Less(t, time.Since(start), time.Second)
+}
+
 func TestGetTransaction_JSONFormat(t *testing.T) {
 	mockDBReader := db.NewMockTransactionStore(NetworkPassphrase)
 	mockLedgerReader := db.NewMockLedgerReader(mockDBReader)
@@ -1,11 +1,12 @@
 package methods
 
 import (
+	"cmp"
 	"context"
-	"encoding/base64"
 	"errors"
 	"fmt"
 	"io"
+	"slices"
 	"strconv"
 
 	"github.com/creachadair/jrpc2"
@@ -17,11 +18,13 @@ import (
 	"github.com/stellar/go/xdr"
 
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerbucketwindow"
 	"github.com/stellar/stellar-rpc/protocol"
 )
 
 type transactionsRPCHandler struct {
 	ledgerReader      db.LedgerReader
+	transactionReader db.TransactionReader
 	maxLimit          uint
 	defaultLimit      uint
 	logger            *log.Entry
@@ -29,8 +32,15 @@ type transactionsRPCHandler struct {
 }
 
 // initializePagination sets the pagination limit and cursor
-func (h transactionsRPCHandler) initializePagination(request protocol.GetTransactionsRequest) (toid.ID, uint, error) {
+func (h transactionsRPCHandler) initializePagination(request protocol.GetTransactionsRequest,
+	latestLedger uint32,
+) (toid.ID, uint, error) {
+	descending := request.Order == protocol.TransactionsOrderDesc
 	start := toid.New(int32(request.StartLedger), 1, 1)
+	if descending {
+		// descending scans start from the last transaction of the start ledger
+		start = toid.New(int32(cmp.Or(request.StartLedger, latestLedger)), toid.TransactionMask, 1) //nolint:gosec
+	}
 	limit := h.defaultLimit
 	if request.Pagination != nil {
 		if request.Pagination.Cursor != "" {
@@ -42,9 +52,13 @@ func (h transactionsRPCHandler) initializePagination(request protocol.GetTransac
 				}
 			}
 			*start = toid.Parse(cursorInt)
-			// increment tx index because, when paginating,
+			// increment (or decrement, when descending) tx index because, when paginating,
 			// we start with the item right after the cursor
-			start.TransactionOrder++
+			if descending {
+				start.TransactionOrder--
+			} else {
+				start.TransactionOrder++
+			}
 		}
 		if request.Pagination.Limit > 0 {
 			limit = request.Pagination.Limit
@@ -73,11 +87,11 @@ func (h transactionsRPCHandler) fetchLedgerData(ctx context.Context, ledgerSeq u
 }
 
 // processTransactionsInLedger cycles through all the transactions in a ledger, extracts the transaction info
-// and builds the list of transactions.
+// and builds the list of the transactions matching the filter.
 func (h transactionsRPCHandler) processTransactionsInLedger(
 	ledger xdr.LedgerCloseMeta, start toid.ID,
 	txns *[]protocol.TransactionInfo, limit uint,
-	format string,
+	format string, filter transactionsFilter,
 ) (*toid.ID, bool, error) {
 	reader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(h.networkPassphrase, ledger)
 	if err != nil {
@@ -114,67 +128,171 @@ func (h transactionsRPCHandler) processTransactionsInLedger(
 				Message: err.Error(),
 			}
 		}
+		if !filter.matches(ledgerSeq, ingestTx) {
+			continue
+		}
+
+		txInfo, err := buildTransactionInfo(ledger, ingestTx, format)
+		if err != nil {
+			return nil, false, err
+		}
+
+		*txns = append(*txns, txInfo)
+		if len(*txns) >= int(limit) {
+			return cursor, true, nil
+		}
+	}
+
+	return cursor, false, nil
+}
+
+// processTransactionsInLedgerDesc is processTransactionsInLedger for descending scans, which go through the
+// transactions of a ledger latest first, from the one at start.
+func (h transactionsRPCHandler) processTransactionsInLedgerDesc(
+	ledger xdr.LedgerCloseMeta, start toid.ID,
+	txns *[]protocol.TransactionInfo, limit uint,
+	format string, filter transactionsFilter,
+) (*toid.ID, bool, error) {
+	reader, err := ingest.NewLedgerTransactionReaderFromLedgerCloseMeta(h.networkPassphrase, ledger)
+	if err != nil {
+		return nil, false, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
+		}
+	}
 
-		tx, err := db.ParseTransaction(ledger, ingestTx)
+	ledgerSeq := ledger.LedgerSequence()
+	endTxIdx := ledger.CountTransactions()
+	if int32(ledgerSeq) == start.LedgerSequence {
+		endTxIdx = max(0, min(endTxIdx, int(start.TransactionOrder)))
+	}
+	ledgerTxs := make([]ingest.LedgerTransaction, 0, endTxIdx)
+	for range endTxIdx {
+		ingestTx, err := reader.Read()
 		if err != nil {
 			return nil, false, &jrpc2.Error{
 				Code:    jrpc2.InternalError,
 				Message: err.Error(),
 			}
 		}
+		ledgerTxs = append(ledgerTxs, ingestTx)
+	}
 
-		txInfo := protocol.TransactionInfo{
-			TransactionDetails: protocol.TransactionDetails{
-				TransactionHash:  tx.TransactionHash,
-				ApplicationOrder: tx.ApplicationOrder,
-				FeeBump:          tx.FeeBump,
-				Ledger:           tx.Ledger.Sequence,
-			},
-			LedgerCloseTime: tx.Ledger.CloseTime,
+	for _, ingestTx := range slices.Backward(ledgerTxs) {
+		if !filter.matches(ledgerSeq, ingestTx) {
+			continue
 		}
 
-		switch format {
-		case protocol.FormatJSON:
-			result, envelope, meta, convErr := transactionToJSON(tx)
-			if convErr != nil {
-				return nil, false, &jrpc2.Error{
-					Code:    jrpc2.InternalError,
-					Message: convErr.Error(),
-				}
-			}
+		txInfo, err := buildTransactionInfo(ledger, ingestTx, format)
+		if err != nil {
+			return nil, false, err
+		}
 
-			diagEvents, convErr := jsonifySlice(xdr.DiagnosticEvent{}, tx.Events)
-			if convErr != nil {
-				return nil, false, &jrpc2.Error{
-					Code:    jrpc2.InternalError,
-					Message: convErr.Error(),
-				}
-			}
+		*txns = append(*txns, txInfo)
+		if len(*txns) >= int(limit) {
+			return toid.New(int32(ledgerSeq), int32(ingestTx.Index), 1), true, nil //nolint:gosec
+		}
+	}
 
-			txInfo.ResultJSON = result
-			txInfo.ResultMetaJSON = envelope
-			txInfo.EnvelopeJSON = meta
-			txInfo.DiagnosticEventsJSON = diagEvents
+	// the next page starts from the previous ledger
+	return toid.New(int32(ledgerSeq), 0, 1), false, nil
+}
 
-		default:
-			txInfo.ResultXDR = base64.StdEncoding.EncodeToString(tx.Result)
-			txInfo.ResultMetaXDR = base64.StdEncoding.EncodeToString(tx.Meta)
-			txInfo.EnvelopeXDR = base64.StdEncoding.EncodeToString(tx.Envelope)
-			txInfo.DiagnosticEventsXDR = base64EncodeSlice(tx.Events)
+// buildTransactionInfo extracts the info of a transaction of a ledger, in the requested format.
+func buildTransactionInfo(ledger xdr.LedgerCloseMeta, ingestTx ingest.LedgerTransaction,
+	format string,
+) (protocol.TransactionInfo, error) {
+	tx, err := db.ParseTransaction(ledger, ingestTx)
+	if err != nil {
+		return protocol.TransactionInfo{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
 		}
+	}
+	return newTransactionInfo(tx, format)
+}
+
+// newTransactionInfo builds the info of a transaction, in the requested format.
+func newTransactionInfo(tx db.Transaction, format string) (protocol.TransactionInfo, error) {
+	txInfo := protocol.TransactionInfo{
+		TransactionDetails: protocol.TransactionDetails{
+			TransactionHash:  tx.TransactionHash,
+			ApplicationOrder: tx.ApplicationOrder,
+			FeeBump:          tx.FeeBump,
+			Ledger:           tx.Ledger.Sequence,
+			TransactionFees:  tx.Fees,
+		},
+		LedgerCloseTime: tx.Ledger.CloseTime,
+	}
 
-		txInfo.Status = protocol.TransactionStatusFailed
-		if tx.Successful {
-			txInfo.Status = protocol.TransactionStatusSuccess
+	if convErr := encodeTransactionDetails(tx, format, &txInfo.TransactionDetails); convErr != nil {
+		return protocol.TransactionInfo{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: convErr.Error(),
 		}
+	}
 
-		*txns = append(*txns, txInfo)
-		if len(*txns) >= int(limit) {
-			return cursor, true, nil
+	txInfo.Status = protocol.TransactionStatusFailed
+	if tx.Successful {
+		txInfo.Status = protocol.TransactionStatusSuccess
+	}
+	return txInfo, nil
+}
+
+// scanEndLedger returns the last ledger scanned by a request (in its order). The latest (or, when
+// descending, the oldest) ledger acts as the end ledger range for the request, filtered requests
+// scan a bounded number of ledgers.
+func scanEndLedger(request protocol.GetTransactionsRequest, start toid.ID,
+	ledgerRange ledgerbucketwindow.LedgerRange, filter transactionsFilter,
+) int32 {
+	if request.Order == protocol.TransactionsOrderDesc {
+		endLedger := int32(ledgerRange.FirstLedger.Sequence) //nolint:gosec
+		if !filter.empty() {
+			endLedger = max(endLedger, start.LedgerSequence-transactionsLedgerScanLimit+1)
 		}
+		return endLedger
+	}
+	endLedger := int32(ledgerRange.LastLedger.Sequence)
+	if !filter.empty() {
+		endLedger = min(endLedger, start.LedgerSequence+transactionsLedgerScanLimit-1)
 	}
+	return endLedger
+}
 
-	return cursor, false, nil
+// scanLedgers iterates through each ledger and its transactions, in the order of the request,
+// until limit or the end ledger is reached.
+func (h transactionsRPCHandler) scanLedgers(ctx context.Context, readTx db.LedgerReaderTx,
+	request protocol.GetTransactionsRequest, start toid.ID, endLedger int32, limit uint, filter transactionsFilter,
+) ([]protocol.TransactionInfo, *toid.ID, error) {
+	process, step := h.processTransactionsInLedger, int32(1)
+	if request.Order == protocol.TransactionsOrderDesc {
+		process, step = h.processTransactionsInLedgerDesc, -1
+	}
+	txns := make([]protocol.TransactionInfo, 0, limit)
+	var done bool
+	cursor := toid.New(0, 0, 0)
+	// multiplying by step compares the ledgers in the order of the scan
+	for ledgerSeq := start.LedgerSequence; ledgerSeq*step <= endLedger*step; ledgerSeq += step {
+		if !filter.scansLedger(uint32(ledgerSeq)) {
+			continue
+		}
+		ledger, err := h.fetchLedgerData(ctx, uint32(ledgerSeq), readTx)
+		if err != nil {
+			return nil, nil, err
+		}
+
+		cursor, done, err = process(ledger, start, &txns, limit, request.Format, filter)
+		if err != nil {
+			return nil, nil, err
+		}
+		if done {
+			return txns, cursor, nil
+		}
+	}
+	if filter.end != nil {
+		cursor = filter.end
+	}
+	return txns, cursor, nil
 }
 
 // getTransactionsByLedgerSequence fetches transactions between the start and end ledgers, inclusive of both.
@@ -209,29 +327,28 @@ func (h transactionsRPCHandler) getTransactionsByLedgerSequence(ctx context.Cont
 		}
 	}
 
-	start, limit, err := h.initializePagination(request)
+	start, limit, err := h.initializePagination(request, ledgerRange.LastLedger.Sequence)
 	if err != nil {
 		return protocol.GetTransactionsResponse{}, err
 	}
 
-	// Iterate through each ledger and its transactions until limit or end range is reached.
-	// The latest ledger acts as the end ledger range for the request.
-	txns := make([]protocol.TransactionInfo, 0, limit)
-	var done bool
-	cursor := toid.New(0, 0, 0)
-	for ledgerSeq := start.LedgerSequence; ledgerSeq <= int32(ledgerRange.LastLedger.Sequence); ledgerSeq++ {
-		ledger, err := h.fetchLedgerData(ctx, uint32(ledgerSeq), readTx)
-		if err != nil {
-			return protocol.GetTransactionsResponse{}, err
+	filter, err := newTransactionsFilter(request)
+	if err != nil {
+		return protocol.GetTransactionsResponse{}, err
+	}
+	endLedger := scanEndLedger(request, start, ledgerRange, filter)
+	err = filter.loadIndexedTransactions(ctx, h.transactionReader, start, uint32(endLedger), //nolint:gosec
+		request.Order, limit)
+	if err != nil {
+		return protocol.GetTransactionsResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: err.Error(),
 		}
+	}
 
-		cursor, done, err = h.processTransactionsInLedger(ledger, start, &txns, limit, request.Format)
-		if err != nil {
-			return protocol.GetTransactionsResponse{}, err
-		}
-		if done {
-			break
-		}
+	txns, cursor, err := h.scanLedgers(ctx, readTx, request, start, endLedger, limit, filter)
+	if err != nil {
+		return protocol.GetTransactionsResponse{}, err
 	}
 
 	return protocol.GetTransactionsResponse{
@@ -244,11 +361,12 @@ func (h transactionsRPCHandler) getTransactionsByLedgerSequence(ctx context.Cont
 	}, nil
 }
 
-func NewGetTransactionsHandler(logger *log.Entry, ledgerReader db.LedgerReader, maxLimit,
-	defaultLimit uint, networkPassphrase string,
+func NewGetTransactionsHandler(logger *log.Entry, ledgerReader db.LedgerReader,
+	transactionReader db.TransactionReader, maxLimit, defaultLimit uint, networkPassphrase string,
 ) jrpc2.Handler {
 	transactionsHandler := transactionsRPCHandler{
 		ledgerReader:      ledgerReader,
+		transactionReader: transactionReader,
 		maxLimit:          maxLimit,
 		defaultLimit:      defaultLimit,
 		logger:            logger,
@@ -0,0 +1,90 @@
+package methods
+
+import (
+	"context"
+	"errors"
+	"fmt"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func getTransactionsByHash(
+	ctx context.Context,
+	log *log.Entry,
+	reader db.TransactionReader,
+	ledgerReader db.LedgerReader,
+	request protocol.GetTransactionsByHashRequest,
+) (protocol.GetTransactionsByHashResponse, error) {
+	if err := request.IsValid(); err != nil {
+		return protocol.GetTransactionsByHashResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: err.Error(),
+		}
+	}
+	txHashes := make([]xdr.Hash, 0, len(request.Hashes))
+	for _, hash := range request.Hashes {
+		txHash, err := parseTransactionHash(hash)
+		if err != nil {
+			return protocol.GetTransactionsByHashResponse{}, err
+		}
+		txHashes = append(txHashes, txHash)
+	}
+
+	storeRange, err := ledgerReader.GetLedgerRange(ctx)
+	if err != nil {
+		return protocol.GetTransactionsByHashResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InternalError,
+			Message: fmt.Sprintf("unable to get ledger range: %v", err),
+		}
+	}
+
+	response := protocol.GetTransactionsByHashResponse{
+		Transactions:          make([]protocol.TransactionInfo, 0, len(txHashes)),
+		LatestLedger:          storeRange.LastLedger.Sequence,
+		LatestLedgerCloseTime: storeRange.LastLedger.CloseTime,
+		OldestLedger:          storeRange.FirstLedger.Sequence,
+		OldestLedgerCloseTime: storeRange.FirstLedger.CloseTime,
+	}
+	for i, txHash := range txHashes {
+		txInfo := protocol.TransactionInfo{}
+		tx, err := reader.GetTransaction(ctx, txHash)
+		switch {
+		case errors.Is(err, db.ErrNoTransaction):
+			txInfo.Status = protocol.TransactionStatusNotFound
+		case err != nil:
+			log.WithError(err).
+				WithField("hash", txHash).
+				Errorf("failed to fetch transaction")
+			return protocol.GetTransactionsByHashResponse{}, &jrpc2.Error{
+				Code:    jrpc2.InternalError,
+				Message: err.Error(),
+			}
+		default:
+			if txInfo, err = newTransactionInfo(tx, request.Format); err != nil {
+				return protocol.GetTransactionsByHashResponse{}, err
+			}
+		}
+		// The hashes of fee-bump transactions may be the ones of their inner
+		// transaction, like in getTransaction
+		txInfo.TransactionHash = request.Hashes[i]
+		response.Transactions = append(response.Transactions, txInfo)
+	}
+	return response, nil
+}
+
+// NewGetTransactionsByHashHandler returns a json rpc handler looking up
+// several transactions by hash at once.
+func NewGetTransactionsByHashHandler(logger *log.Entry, reader db.TransactionReader,
+	ledgerReader db.LedgerReader,
+) jrpc2.Handler {
+	return NewHandler(func(ctx context.Context, request protocol.GetTransactionsByHashRequest,
+	) (protocol.GetTransactionsByHashResponse, error) {
+		return getTransactionsByHash(ctx, logger, reader, ledgerReader, request)
+	})
+}
@@ -0,0 +1,46 @@
+package methods
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/support/log"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestGetTransactionsByHash(t *testing.T) {
+	ctx := context.TODO()
+	store := db.NewMockTransactionStore("passphrase")
+	ledgerReader := db.NewMockLedgerReader(store)
+	require.NoError(t, store.InsertTransactions(txMeta(1, true)))
+	require.NoError(t, store.InsertTransactions(txMeta(2, false)))
+
+	hashes := []string{txHash(2).HexString(), txHash(3).HexString(), txHash(1).HexString()}
+	response, err := getTransactionsByHash(ctx, log.DefaultLogger, store, ledgerReader,
+		protocol.GetTransactionsByHashRequest{Hashes: hashes})
+	require.NoError(t, err)
+	assert.EqualValues(t, 102, response.LatestLedger)
+	assert.EqualValues(t, 101, response.OldestLedger)
+	require.Len(t, response.Transactions, 3)
+	for i, tx := range response.Transactions {
+		assert.Equal(t, hashes[i], tx.TransactionHash)
+	}
+	assert.Equal(t, protocol.TransactionStatusFailed, response.Transactions[0].Status)
+	assert.EqualValues(t, 102, response.Transactions[0].Ledger)
+	assert.Equal(t, protocol.TransactionStatusNotFound, response.Transactions[1].Status)
+	assert.Empty(t, response.Transactions[1].EnvelopeXDR)
+	assert.Equal(t, protocol.TransactionStatusSuccess, response.Transactions[2].Status)
+	assert.NotEmpty(t, response.Transactions[2].EnvelopeXDR)
+
+	_, err = getTransactionsByHash(ctx, log.DefaultLogger, store, ledgerReader,
+		protocol.GetTransactionsByHashRequest{Hashes: []string{"ab"}})
+	require.EqualError(t, err, "[-32602] unexpected hash length (2)")
+	_, err = getTransactionsByHash(ctx, log.DefaultLogger, store, ledgerReader,
+		protocol.GetTransactionsByHashRequest{})
+	require.EqualError(t, err, "[-32602] hashes must not be empty")
+}
@@ -5,17 +5,20 @@ import (
 	"encoding/json"
 	"fmt"
 	"testing"
+	"time"
 
 	"github.com/creachadair/jrpc2"
 	"github.com/stretchr/testify/assert"
 	"github.com/stretchr/testify/require"
 
+	"github.com/stellar/go/strkey"
 	"github.com/stellar/go/support/log"
 	"github.com/stellar/go/toid"
 	"github.com/stellar/go/xdr"
 
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/xdr2json"
 	"github.com/stellar/stellar-rpc/protocol"
 )
 
@@ -34,6 +37,7 @@ var expectedTransactionInfo = protocol.TransactionInfo{
 		ResultMetaXDR:       "AAAAAwAAAAAAAAAAAAAAAAAAAAAAAAAA",
 		ResultXDR:           "AAAAAAAAAGQAAAAAAAAAAAAAAAA=",
 		DiagnosticEventsXDR: []string{},
+		TransactionFees:     protocol.TransactionFees{InclusionFeeBid: 1},
 	},
 	LedgerCloseTime: 125,
 }
@@ -259,6 +263,14 @@ func TestGetTransactions_JSONFormat(t *testing.T) {
 	require.NotNilf(t, tx["resultJson"], "field: 'resultJson'")
 	require.Nilf(t, tx["resultMetaXdr"], "field: 'resultMetaXdr'")
 	require.NotNilf(t, tx["resultMetaJson"], "field: 'resultMetaJson'")
+
+	// Check the envelope and meta aren't swapped
+	envJs, err := xdr2json.ConvertInterface(createTestLedger(1).TransactionEnvelopes()[0])
+	require.NoError(t, err)
+	var envelope map[string]interface{}
+	require.NoError(t, json.Unmarshal(envJs, &envelope))
+	require.Equal(t, envelope, tx["envelopeJson"])
+	require.Contains(t, tx["resultMetaJson"], "v3")
 }
 
 func TestGetTransactions_NoResults(t *testing.T) {
@@ -280,6 +292,171 @@ func TestGetTransactions_NoResults(t *testing.T) {
 	require.Empty(t, txns.Transactions)
 }
 
+func TestGetTransactions_Descending(t *testing.T) {
+	testDB := setupDB(t, 10, 0)
+	handler := transactionsRPCHandler{
+		ledgerReader:      db.NewLedgerReader(testDB),
+		maxLimit:          100,
+		defaultLimit:      10,
+		networkPassphrase: NetworkPassphrase,
+	}
+
+	// Without a start ledger, descending requests start from the latest ledger
+	request := protocol.GetTransactionsRequest{
+		Order:      protocol.TransactionsOrderDesc,
+		Pagination: &protocol.LedgerPaginationOptions{Limit: 3},
+	}
+	response, err := handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	require.Len(t, response.Transactions, 3)
+	for i, expected := range []toid.ID{*toid.New(10, 2, 0), *toid.New(10, 1, 0), *toid.New(9, 2, 0)} {
+		assert.Equal(t, uint32(expected.LedgerSequence), response.Transactions[i].Ledger)
+		assert.Equal(t, expected.TransactionOrder, response.Transactions[i].ApplicationOrder)
+	}
+	assert.Equal(t, toid.New(9, 2, 1).String(), response.Cursor)
+
+	request.Pagination.Cursor = response.Cursor
+	response, err = handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	require.Len(t, response.Transactions, 3)
+	assert.Equal(t, uint32(9), response.Transactions[0].Ledger)
+	assert.Equal(t, int32(1), response.Transactions[0].ApplicationOrder)
+	assert.Equal(t, toid.New(8, 1, 1).String(), response.Cursor)
+
+	// The scan stops at the oldest ledger
+	request = protocol.GetTransactionsRequest{
+		StartLedger: 2,
+		Order:       protocol.TransactionsOrderDesc,
+	}
+	response, err = handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	require.Len(t, response.Transactions, 4)
+	assert.Equal(t, uint32(2), response.Transactions[0].Ledger)
+	assert.Equal(t, uint32(1), response.Transactions[3].Ledger)
+	assert.Equal(t, toid.New(1, 0, 1).String(), response.Cursor)
+}
+
+func TestGetTransactions_AccountFilter(t *testing.T) {
+	testDB := setupDB(t, 10, 0)
+	handler := transactionsRPCHandler{
+		ledgerReader:      db.NewLedgerReader(testDB),
+		maxLimit:          100,
+		defaultLimit:      10,
+		networkPassphrase: NetworkPassphrase,
+	}
+
+	// The test transactions are sent by a muxed account of this account
+	request := protocol.GetTransactionsRequest{
+		StartLedger: 1,
+		Account:     "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ",
+	}
+	response, err := handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	assert.Len(t, response.Transactions, 10)
+	assert.Equal(t, toid.New(5, 2, 1).String(), response.Cursor)
+
+	// The cursor moves past the scanned transactions which don't match
+	request.Account = "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H"
+	response, err = handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	assert.Empty(t, response.Transactions)
+	assert.Equal(t, toid.New(10, 2, 1).String(), response.Cursor)
+
+	request.Account = "MA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVAAAAAAAAAAAAAJLK"
+	_, err = handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.ErrorContains(t, err, "account must be a valid account ID")
+}
+
+func TestGetTransactions_StatusFilter(t *testing.T) {
+	testDB := setupDB(t, 10, 0)
+	handler := transactionsRPCHandler{
+		ledgerReader:      db.NewLedgerReader(testDB),
+		maxLimit:          100,
+		defaultLimit:      10,
+		networkPassphrase: NetworkPassphrase,
+	}
+
+	// Each test ledger has a successful transaction followed by a failed one
+	request := protocol.GetTransactionsRequest{
+		StartLedger: 1,
+		Status:      protocol.TransactionStatusFailed,
+	}
+	response, err := handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	assert.Len(t, response.Transactions, 10)
+	for _, tx := range response.Transactions {
+		assert.Equal(t, protocol.TransactionStatusFailed, tx.Status)
+		assert.EqualValues(t, 2, tx.ApplicationOrder)
+	}
+	assert.Equal(t, toid.New(10, 2, 1).String(), response.Cursor)
+
+	request.Status = protocol.TransactionStatusSuccess
+	request.Pagination = &protocol.LedgerPaginationOptions{Limit: 3}
+	response, err = handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.NoError(t, err)
+	require.Len(t, response.Transactions, 3)
+	assert.Equal(t, protocol.TransactionStatusSuccess, response.Transactions[2].Status)
+	assert.Equal(t, toid.New(3, 1, 1).String(), response.Cursor)
+
+	request.Status = protocol.TransactionStatusNotFound
+	_, err = handler.getTransactionsByLedgerSequence(context.TODO(), request)
+	require.ErrorContains(t, err, "status must be either 'SUCCESS' or 'FAILED'")
+}
+
+func TestGetTransactions_ContractFilter(t *testing.T) {
+	testDB := NewTestDB(t)
+	ctx := context.TODO()
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	contractA, contractB := xdr.ContractId([32]byte{1}), xdr.ContractId([32]byte{2})
+	// The transactions touch the contracts emitting their events
+	for sequence, contractIDs := range [][]xdr.ContractId{{contractA, contractB}, {contractB}, {contractA}} {
+		txMeta := make([]xdr.TransactionMeta, 0, len(contractIDs))
+		for _, contractID := range contractIDs {
+			txMeta = append(txMeta, transactionMetaWithEvents(contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)))
+		}
+		ledgerCloseMeta := ledgerCloseMetaWithEvents(uint32(sequence+1), time.Now().Unix(), txMeta...)
+		tx, err := db.NewReadWriter(log.DefaultLogger, testDB, interfaces.MakeNoOpDeamon(), 150, 100, 100, 100, passphrase).
+			NewTx(ctx)
+		require.NoError(t, err)
+		require.NoError(t, tx.LedgerWriter().InsertLedger(ledgerCloseMeta))
+		require.NoError(t, tx.TransactionWriter().InsertTransactions(ledgerCloseMeta))
+		require.NoError(t, tx.Commit(ledgerCloseMeta))
+	}
+	handler := transactionsRPCHandler{
+		ledgerReader:      db.NewLedgerReader(testDB),
+		transactionReader: db.NewTransactionReader(log.DefaultLogger, testDB, passphrase),
+		maxLimit:          100,
+		defaultLimit:      10,
+		networkPassphrase: passphrase,
+	}
+
+	request := protocol.GetTransactionsRequest{
+		StartLedger: 1,
+		Pagination:  &protocol.LedgerPaginationOptions{Limit: 1},
+		ContractIDs: []string{strkey.MustEncode(strkey.VersionByteContract, contractA[:])},
+	}
+	response, err := handler.getTransactionsByLedgerSequence(ctx, request)
+	require.NoError(t, err)
+	require.Len(t, response.Transactions, 1)
+	assert.Equal(t, uint32(1), response.Transactions[0].Ledger)
+	assert.Equal(t, int32(1), response.Transactions[0].ApplicationOrder)
+
+	// The ledger without transactions touching the contract is skipped
+	request.Pagination.Cursor = response.Cursor
+	response, err = handler.getTransactionsByLedgerSequence(ctx, request)
+	require.NoError(t, err)
+	require.Len(t, response.Transactions, 1)
+	assert.Equal(t, uint32(3), response.Transactions[0].Ledger)
+	assert.Equal(t, toid.New(3, 1, 1).String(), response.Cursor)
+
+	request.Pagination.Cursor = response.Cursor
+	response, err = handler.getTransactionsByLedgerSequence(ctx, request)
+	require.NoError(t, err)
+	assert.Empty(t, response.Transactions)
+	assert.Equal(t, toid.New(3, toid.TransactionMask, 1).String(), response.Cursor)
+}
+
 // createTestLedger Creates a test ledger with 2 transactions
 func createTestLedger(sequence uint32) xdr.LedgerCloseMeta {
 	sequence -= 100
@@ -312,7 +489,8 @@ func setupDB(t *testing.T, numLedgers int, skipLedger int) *db.DB {
 			continue
 		}
 		ledgerCloseMeta := createTestLedger(uint32(sequence))
-		tx, err := db.NewReadWriter(log.DefaultLogger, testDB, daemon, 150, 100, passphrase).NewTx(context.Background())
+		tx, err := db.NewReadWriter(log.DefaultLogger, testDB, daemon, 150, 100, 100, 100, passphrase).
+			NewTx(context.Background())
 		require.NoError(t, err)
 		require.NoError(t, tx.LedgerWriter().InsertLedger(ledgerCloseMeta))
 		require.NoError(t, tx.Commit(ledgerCloseMeta))
@@ -326,7 +504,8 @@ func setupDBNoTxs(t *testing.T, numLedgers int) *db.DB {
 	for sequence := 1; sequence <= numLedgers; sequence++ {
 		ledgerCloseMeta := createEmptyTestLedger(uint32(sequence))
 
-		tx, err := db.NewReadWriter(log.DefaultLogger, testDB, daemon, 150, 100, passphrase).NewTx(context.Background())
+		tx, err := db.NewReadWriter(log.DefaultLogger, testDB, daemon, 150, 100, 100, 100, passphrase).
+			NewTx(context.Background())
 		require.NoError(t, err)
 		require.NoError(t, tx.LedgerWriter().InsertLedger(ledgerCloseMeta))
 		require.NoError(t, tx.Commit(ledgerCloseMeta))
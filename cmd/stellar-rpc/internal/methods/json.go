@@ -1,12 +1,53 @@
 package methods
 
 import (
+	"encoding/base64"
+
 	"github.com/stellar/go/xdr"
 
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/xdr2json"
+	"github.com/stellar/stellar-rpc/protocol"
 )
 
+// encodeTransactionDetails sets the envelope, result, meta and diagnostic
+// events of the details of a transaction, as XDR or JSON depending on the
+// requested format.
+func encodeTransactionDetails(tx db.Transaction, format string, details *protocol.TransactionDetails) error {
+	switch format {
+	case protocol.FormatJSON:
+		result, envelope, meta, err := transactionToJSON(tx)
+		if err != nil {
+			return err
+		}
+
+		details.ResultJSON = result
+		details.EnvelopeJSON = envelope
+		details.ResultMetaJSON = meta
+
+	default:
+		details.ResultXDR = base64.StdEncoding.EncodeToString(tx.Result)
+		details.EnvelopeXDR = base64.StdEncoding.EncodeToString(tx.Envelope)
+		details.ResultMetaXDR = base64.StdEncoding.EncodeToString(tx.Meta)
+	}
+	return encodeTransactionEvents(tx, format, details)
+}
+
+// encodeTransactionEvents sets the diagnostic events of the details of a
+// transaction, as XDR or JSON depending on the requested format.
+func encodeTransactionEvents(tx db.Transaction, format string, details *protocol.TransactionDetails) error {
+	if format == protocol.FormatJSON {
+		diagEvents, err := jsonifySlice(xdr.DiagnosticEvent{}, tx.Events)
+		if err != nil {
+			return err
+		}
+		details.DiagnosticEventsJSON = diagEvents
+		return nil
+	}
+	details.DiagnosticEventsXDR = base64EncodeSlice(tx.Events)
+	return nil
+}
+
 func transactionToJSON(tx db.Transaction) (
 	[]byte,
 	[]byte,
@@ -7,6 +7,7 @@ import (
 	"errors"
 	"fmt"
 	"strings"
+	"time"
 
 	"github.com/creachadair/jrpc2"
 
@@ -15,6 +16,7 @@ import (
 
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/feewindow"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/ledgerentries"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/preflight"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/xdr2json"
@@ -23,10 +25,21 @@ import (
 
 type PreflightGetter interface {
 	GetPreflight(ctx context.Context, params preflight.GetterParameters) (preflight.Preflight, error)
+	GetPreflights(ctx context.Context, params []preflight.GetterParameters) ([]preflight.Preflight, error)
 }
 
 var errMissingDiff = errors.New("no ledger difference found")
 
+const (
+	// minBaseFee is the minimum inclusion fee per operation, in stroops
+	minBaseFee = 100
+	// ed25519SignatureSize is the size of the signatures of Stellar accounts
+	ed25519SignatureSize = 64
+	// defaultSignatureValidityLedgers is how long (~8 minutes) signatures of
+	// recorded auth entries are suggested to remain valid for
+	defaultSignatureValidityLedgers = 100
+)
+
 func LedgerEntryChangeFromXDRDiff(diff preflight.XDRDiff, format string) (protocol.LedgerEntryChange, error) {
 	if err := protocol.IsValidFormat(format); err != nil {
 		return protocol.LedgerEntryChange{}, err
@@ -71,6 +84,7 @@ func LedgerEntryChangeFromXDRDiff(diff preflight.XDRDiff, format string) (protoc
 	if err != nil {
 		return protocol.LedgerEntryChange{}, err
 	}
+	result.EntryKind = getLedgerEntryKind(key.Type)
 
 	switch format {
 	case protocol.FormatJSON:
@@ -101,6 +115,18 @@ func LedgerEntryChangeFromXDRDiff(diff preflight.XDRDiff, format string) (protoc
 	return result, nil
 }
 
+// The diffs include the classic entries modified by the invocation (e.g. the
+// trustlines of Stellar Asset Contract transfers), as they are part of the
+// read-write footprint too.
+func getLedgerEntryKind(entryType xdr.LedgerEntryType) string {
+	switch entryType {
+	case xdr.LedgerEntryTypeContractData, xdr.LedgerEntryTypeContractCode, xdr.LedgerEntryTypeTtl:
+		return protocol.LedgerEntryKindContract
+	default:
+		return protocol.LedgerEntryKindClassic
+	}
+}
+
 func AddLedgerEntryChangeJSON(l *protocol.LedgerEntryChange, diff preflight.XDRDiff, key xdr.LedgerKey) error {
 	var err error
 	beforePresent := len(diff.Before) > 0
@@ -119,7 +145,7 @@ func AddLedgerEntryChangeJSON(l *protocol.LedgerEntryChange, diff preflight.XDRD
 	}
 
 	if afterPresent {
-		l.BeforeJSON, err = xdr2json.ConvertBytes(xdr.LedgerEntry{}, diff.After)
+		l.AfterJSON, err = xdr2json.ConvertBytes(xdr.LedgerEntry{}, diff.After)
 		if err != nil {
 			return err
 		}
@@ -133,6 +159,12 @@ func getRestorePreamble(preflight preflight.Preflight, format string) (*protocol
 	if len(preflight.PreRestoreTransactionData) == 0 {
 		return restorePreamble, nil
 	}
+	// The RestoreFootprint operation restores its read-write footprint
+	var txData xdr.SorobanTransactionData
+	if err := xdr.SafeUnmarshal(preflight.PreRestoreTransactionData, &txData); err != nil {
+		return nil, err
+	}
+	archivedKeys := txData.Resources.Footprint.ReadWrite
 	switch format {
 	case protocol.FormatJSON:
 		txDataJs, err := xdr2json.ConvertBytes(
@@ -146,21 +178,54 @@ func getRestorePreamble(preflight preflight.Preflight, format string) (*protocol
 			TransactionDataJSON: txDataJs,
 			MinResourceFee:      preflight.PreRestoreMinFee,
 		}
+		restorePreamble.ArchivedKeysJSON = make([]json.RawMessage, len(archivedKeys))
+		for i, key := range archivedKeys {
+			if restorePreamble.ArchivedKeysJSON[i], err = xdr2json.ConvertInterface(key); err != nil {
+				return nil, err
+			}
+		}
 
 	default:
 		restorePreamble = &protocol.RestorePreamble{
 			TransactionDataXDR: base64.StdEncoding.EncodeToString(preflight.PreRestoreTransactionData),
 			MinResourceFee:     preflight.PreRestoreMinFee,
 		}
+		restorePreamble.ArchivedKeysXDR = make([]string, len(archivedKeys))
+		for i, key := range archivedKeys {
+			var err error
+			if restorePreamble.ArchivedKeysXDR[i], err = key.MarshalBinaryBase64(); err != nil {
+				return nil, err
+			}
+		}
 	}
 	return restorePreamble, nil
 }
 
-func getSimulationResults(preflight preflight.Preflight, format string) ([]protocol.SimulateHostFunctionResult, error) {
+func getSimulationResults(
+	preflight preflight.Preflight, format string, latestLedger uint32,
+) ([]protocol.SimulateHostFunctionResult, error) {
 	var results []protocol.SimulateHostFunctionResult
 	if len(preflight.Result) == 0 {
 		return nil, nil
 	}
+	authTree, err := getAuthTree(preflight.Auth)
+	if err != nil {
+		return nil, err
+	}
+	requiredSigners, err := getRequiredSigners(preflight.Auth, latestLedger)
+	if err != nil {
+		return nil, err
+	}
+	var contractAddress string
+	if preflight.CreatedContractID != nil {
+		contractAddress, err = xdr.ScAddress{
+			Type:       xdr.ScAddressTypeScAddressTypeContract,
+			ContractId: preflight.CreatedContractID,
+		}.String()
+		if err != nil {
+			return nil, err
+		}
+	}
 	switch format {
 	case protocol.FormatJSON:
 		rvJs, err := xdr2json.ConvertBytes(xdr.ScVal{}, preflight.Result)
@@ -177,6 +242,9 @@ func getSimulationResults(preflight preflight.Preflight, format string) ([]proto
 			{
 				ReturnValueJSON: rvJs,
 				AuthJSON:        auths,
+				AuthTree:        authTree,
+				RequiredSigners: requiredSigners,
+				ContractAddress: contractAddress,
 			},
 		}
 
@@ -185,50 +253,429 @@ func getSimulationResults(preflight preflight.Preflight, format string) ([]proto
 		auth := base64EncodeSlice(preflight.Auth)
 		results = []protocol.SimulateHostFunctionResult{
 			{
-				ReturnValueXDR: &rv,
-				AuthXDR:        &auth,
+				ReturnValueXDR:  &rv,
+				AuthXDR:         &auth,
+				AuthTree:        authTree,
+				RequiredSigners: requiredSigners,
+				ContractAddress: contractAddress,
 			},
 		}
 	}
 	return results, nil
 }
 
-func formatResponse(preflight preflight.Preflight,
-	format string, latestLedger uint32,
-) (protocol.SimulateTransactionResponse, error) {
-	results, err := getSimulationResults(preflight, format)
-	if err != nil {
-		return protocol.SimulateTransactionResponse{}, err
+// getResourceUsage returns the breakdown of the resources of successful
+// invocations, nil for the other simulations.
+func getResourceUsage(preflight preflight.Preflight) *protocol.SimulationResourceUsage {
+	if preflight.Error != "" || len(preflight.Result) == 0 {
+		return nil
+	}
+	usage := &protocol.SimulationResourceUsage{
+		LinearMemoryBytes: preflight.LinearMemoryBytes,
+		ContractEvents:    uint32(len(preflight.ContractEvents)),
+		ReturnValueBytes:  uint32(len(preflight.Result)),
+	}
+	for _, event := range preflight.ContractEvents {
+		usage.ContractEventsBytes += uint32(len(event))
 	}
+	return usage
+}
 
-	restorePreamble, err := getRestorePreamble(preflight, format)
-	if err != nil {
-		return protocol.SimulateTransactionResponse{}, err
+// getResourceFeeBreakdown returns the split of the resource fee of successful
+// simulations, nil for the failed ones.
+func getResourceFeeBreakdown(preflight preflight.Preflight) *protocol.ResourceFeeBreakdown {
+	if preflight.Error != "" || preflight.FeeBreakdown == nil {
+		return nil
 	}
+	breakdown := protocol.ResourceFeeBreakdown(*preflight.FeeBreakdown)
+	return &breakdown
+}
 
-	stateChanges := make([]protocol.LedgerEntryChange, len(preflight.LedgerEntryDiff))
+func getStateChanges(diffs []preflight.XDRDiff, format string) ([]protocol.LedgerEntryChange, error) {
+	stateChanges := make([]protocol.LedgerEntryChange, len(diffs))
 	for i := range stateChanges {
-		var err error
-		change, err := LedgerEntryChangeFromXDRDiff(preflight.LedgerEntryDiff[i], format)
+		change, err := LedgerEntryChangeFromXDRDiff(diffs[i], format)
 		if err != nil {
 			// Intentionally ignore "no before and after" entries because
 			// they're possible but shouldn't result in a full failure.
 			if errors.Is(err, errMissingDiff) {
 				continue
 			}
-			return protocol.SimulateTransactionResponse{}, err
+			return nil, err
 		}
 
 		stateChanges[i] = change
 	}
+	return stateChanges, nil
+}
+
+func getFootprintBytes(
+	entries []preflight.FootprintEntryBytes, format string,
+) ([]protocol.FootprintEntryBytes, error) {
+	if len(entries) == 0 {
+		return nil, nil
+	}
+	result := make([]protocol.FootprintEntryBytes, len(entries))
+	for i, entry := range entries {
+		result[i].ReadBytes = entry.ReadBytes
+		result[i].WriteBytes = entry.WriteBytes
+		switch format {
+		case protocol.FormatJSON:
+			keyJSON, err := xdr2json.ConvertBytes(xdr.LedgerKey{}, entry.Key)
+			if err != nil {
+				return nil, err
+			}
+			result[i].KeyJSON = keyJSON
+		default:
+			result[i].KeyXDR = base64.StdEncoding.EncodeToString(entry.Key)
+		}
+	}
+	return result, nil
+}
+
+func getRestoreStatuses(
+	statuses []preflight.RestoreKeyStatus, format string,
+) ([]protocol.RestoreKeyStatus, error) {
+	if len(statuses) == 0 {
+		return nil, nil
+	}
+	result := make([]protocol.RestoreKeyStatus, len(statuses))
+	for i, status := range statuses {
+		result[i].Status = status.Status
+		switch format {
+		case protocol.FormatJSON:
+			keyJSON, err := xdr2json.ConvertBytes(xdr.LedgerKey{}, status.Key)
+			if err != nil {
+				return nil, err
+			}
+			result[i].KeyJSON = keyJSON
+		default:
+			result[i].KeyXDR = base64.StdEncoding.EncodeToString(status.Key)
+		}
+	}
+	return result, nil
+}
+
+func getRentChanges(changes []preflight.RentChange, format string) ([]protocol.RentChange, error) {
+	if len(changes) == 0 {
+		return nil, nil
+	}
+	result := make([]protocol.RentChange, len(changes))
+	for i, change := range changes {
+		result[i].SizeBytes = change.SizeBytes
+		result[i].OldLiveUntilLedger = change.OldLiveUntilLedger
+		result[i].NewLiveUntilLedger = change.NewLiveUntilLedger
+		result[i].RentFee = change.RentFee
+		switch format {
+		case protocol.FormatJSON:
+			keyJSON, err := xdr2json.ConvertBytes(xdr.LedgerKey{}, change.Key)
+			if err != nil {
+				return nil, err
+			}
+			result[i].KeyJSON = keyJSON
+		default:
+			result[i].KeyXDR = base64.StdEncoding.EncodeToString(change.Key)
+		}
+	}
+	return result, nil
+}
+
+func getCallTrace(frames []*preflight.CallFrame, format string) ([]protocol.CallFrame, error) {
+	if len(frames) == 0 {
+		return nil, nil
+	}
+	result := make([]protocol.CallFrame, len(frames))
+	for i, frame := range frames {
+		contractID := frame.ContractID
+		contractAddress, err := xdr.ScAddress{
+			Type:       xdr.ScAddressTypeScAddressTypeContract,
+			ContractId: &contractID,
+		}.String()
+		if err != nil {
+			return nil, err
+		}
+		result[i].ContractAddress = contractAddress
+		result[i].Function = frame.Function
+		if err = formatCallFrameValues(&result[i], frame, format); err != nil {
+			return nil, err
+		}
+		result[i].SubCalls, err = getCallTrace(frame.SubCalls, format)
+		if err != nil {
+			return nil, err
+		}
+	}
+	return result, nil
+}
+
+func formatCallFrameValues(result *protocol.CallFrame, frame *preflight.CallFrame, format string) error {
+	if frame.Trap != nil {
+		result.Trap = &protocol.CallTrap{Message: frame.Trap.Message}
+	}
+	switch format {
+	case protocol.FormatJSON:
+		return formatCallFrameJSON(result, frame)
+	default:
+		return formatCallFrameXDR(result, frame)
+	}
+}
+
+func formatCallFrameJSON(result *protocol.CallFrame, frame *preflight.CallFrame) error {
+	var err error
+	result.ArgsJSON = make([]json.RawMessage, len(frame.Args))
+	for i, arg := range frame.Args {
+		if result.ArgsJSON[i], err = xdr2json.ConvertInterface(arg); err != nil {
+			return err
+		}
+	}
+	if frame.ReturnValue != nil {
+		if result.ReturnValueJSON, err = xdr2json.ConvertInterface(*frame.ReturnValue); err != nil {
+			return err
+		}
+	}
+	if frame.Trap != nil {
+		result.Trap.ErrorJSON, err = xdr2json.ConvertInterface(frame.Trap.Error)
+	}
+	return err
+}
+
+func formatCallFrameXDR(result *protocol.CallFrame, frame *preflight.CallFrame) error {
+	var err error
+	result.ArgsXDR = make([]string, len(frame.Args))
+	for i, arg := range frame.Args {
+		if result.ArgsXDR[i], err = xdr.MarshalBase64(arg); err != nil {
+			return err
+		}
+	}
+	if frame.ReturnValue != nil {
+		if result.ReturnValueXDR, err = xdr.MarshalBase64(*frame.ReturnValue); err != nil {
+			return err
+		}
+	}
+	if frame.Trap != nil {
+		result.Trap.ErrorXDR, err = xdr.MarshalBase64(frame.Trap.Error)
+	}
+	return err
+}
+
+func getErrorDetails(details *preflight.ErrorDetails, format string) (*protocol.SimulationErrorDetails, error) {
+	if details == nil {
+		return nil, nil //nolint:nilnil // the error (if any) wasn't categorized
+	}
+	result := &protocol.SimulationErrorDetails{Category: details.Category}
+	if len(details.ContractID) > 0 {
+		var contractID xdr.ContractId
+		copy(contractID[:], details.ContractID)
+		contractAddress, err := xdr.ScAddress{
+			Type:       xdr.ScAddressTypeScAddressTypeContract,
+			ContractId: &contractID,
+		}.String()
+		if err != nil {
+			return nil, err
+		}
+		result.ContractAddress = contractAddress
+	}
+	if len(details.Error) == 0 {
+		return result, nil
+	}
+	var err error
+	switch format {
+	case protocol.FormatJSON:
+		result.ErrorJSON, err = xdr2json.ConvertBytes(xdr.ScError{}, details.Error)
+	default:
+		result.ErrorXDR = base64.StdEncoding.EncodeToString(details.Error)
+	}
+	if err != nil {
+		return nil, err
+	}
+	return result, nil
+}
+
+func getWasmDiagnostics(diagnostics []preflight.WasmDiagnostic, format string) ([]protocol.WasmDiagnostic, error) {
+	if len(diagnostics) == 0 {
+		return nil, nil
+	}
+	result := make([]protocol.WasmDiagnostic, len(diagnostics))
+	for i, diagnostic := range diagnostics {
+		result[i].Kind = diagnostic.Kind
+		result[i].Message = diagnostic.Message
+		var err error
+		switch format {
+		case protocol.FormatJSON:
+			if result[i].ErrorJSON, err = xdr2json.ConvertInterface(diagnostic.Error); err != nil {
+				return nil, err
+			}
+			result[i].ArgsJSON = make([]json.RawMessage, len(diagnostic.Args))
+			for j, arg := range diagnostic.Args {
+				if result[i].ArgsJSON[j], err = xdr2json.ConvertInterface(arg); err != nil {
+					return nil, err
+				}
+			}
+		default:
+			if result[i].ErrorXDR, err = xdr.MarshalBase64(diagnostic.Error); err != nil {
+				return nil, err
+			}
+			result[i].ArgsXDR = make([]string, len(diagnostic.Args))
+			for j, arg := range diagnostic.Args {
+				if result[i].ArgsXDR[j], err = xdr.MarshalBase64(arg); err != nil {
+					return nil, err
+				}
+			}
+		}
+	}
+	return result, nil
+}
+
+// getRequiredSigners returns the addresses which have to sign the given auth
+// entries (in order of appearance), with the earliest signature expiration of
+// their entries. Entries authorized by the source account are covered by the
+// transaction signature.
+func getRequiredSigners(authXDRs [][]byte, latestLedger uint32) ([]protocol.RequiredSigner, error) {
+	var signers []protocol.RequiredSigner
+	signerIndexes := map[string]int{}
+	for _, authXDR := range authXDRs {
+		var auth xdr.SorobanAuthorizationEntry
+		if err := xdr.SafeUnmarshal(authXDR, &auth); err != nil {
+			return nil, err
+		}
+		credentials, ok := auth.Credentials.GetAddress()
+		if !ok {
+			continue
+		}
+		address, err := credentials.Address.String()
+		if err != nil {
+			return nil, err
+		}
+		expirationLedger := uint32(credentials.SignatureExpirationLedger)
+		if expirationLedger == 0 {
+			// Recorded entries aren't signed yet
+			expirationLedger = latestLedger + defaultSignatureValidityLedgers
+		}
+		if i, ok := signerIndexes[address]; ok {
+			signers[i].SignatureExpirationLedger = min(signers[i].SignatureExpirationLedger, expirationLedger)
+			continue
+		}
+		signerIndexes[address] = len(signers)
+		signers = append(signers, protocol.RequiredSigner{
+			Address:                   address,
+			SignatureExpirationLedger: expirationLedger,
+		})
+	}
+	return signers, nil
+}
+
+func getAuthTree(authXDRs [][]byte) ([]protocol.AuthorizationTree, error) {
+	trees := make([]protocol.AuthorizationTree, len(authXDRs))
+	for i, authXDR := range authXDRs {
+		var auth xdr.SorobanAuthorizationEntry
+		if err := xdr.SafeUnmarshal(authXDR, &auth); err != nil {
+			return nil, err
+		}
+		if auth.Credentials.Type == xdr.SorobanCredentialsTypeSorobanCredentialsAddress {
+			address, err := auth.Credentials.MustAddress().Address.String()
+			if err != nil {
+				return nil, err
+			}
+			trees[i].Address = address
+		}
+		invocation, err := getAuthorizedInvocation(auth.RootInvocation)
+		if err != nil {
+			return nil, err
+		}
+		trees[i].Invocation = invocation
+	}
+	return trees, nil
+}
+
+func getAuthorizedInvocation(invocation xdr.SorobanAuthorizedInvocation) (protocol.AuthorizedInvocation, error) {
+	var result protocol.AuthorizedInvocation
+	switch invocation.Function.Type {
+	case xdr.SorobanAuthorizedFunctionTypeSorobanAuthorizedFunctionTypeContractFn:
+		contractFn := invocation.Function.MustContractFn()
+		contractAddress, err := contractFn.ContractAddress.String()
+		if err != nil {
+			return protocol.AuthorizedInvocation{}, err
+		}
+		result.ContractAddress = contractAddress
+		result.FunctionName = string(contractFn.FunctionName)
+	default:
+		result.CreateContract = true
+	}
+	for _, subInvocation := range invocation.SubInvocations {
+		sub, err := getAuthorizedInvocation(subInvocation)
+		if err != nil {
+			return protocol.AuthorizedInvocation{}, err
+		}
+		result.SubInvocations = append(result.SubInvocations, sub)
+	}
+	return result, nil
+}
+
+func formatResponse(preflight preflight.Preflight,
+	format string, latestLedger uint32,
+) (protocol.SimulateTransactionResponse, error) {
+	results, err := getSimulationResults(preflight, format, latestLedger)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	restorePreamble, err := getRestorePreamble(preflight, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	stateChanges, err := getStateChanges(preflight.LedgerEntryDiff, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	footprintBytes, err := getFootprintBytes(preflight.FootprintBytes, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	rentChanges, err := getRentChanges(preflight.RentChanges, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	restoreStatuses, err := getRestoreStatuses(preflight.RestoreStatuses, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	callTrace, err := getCallTrace(preflight.CallTrace, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	errorDetails, err := getErrorDetails(preflight.ErrorDetails, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
+
+	wasmDiagnostics, err := getWasmDiagnostics(preflight.WasmDiagnostics, format)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{}, err
+	}
 
 	simResp := protocol.SimulateTransactionResponse{
-		Error:           preflight.Error,
-		Results:         results,
-		MinResourceFee:  preflight.MinFee,
-		LatestLedger:    latestLedger,
-		RestorePreamble: restorePreamble,
-		StateChanges:    stateChanges,
+		Error:                preflight.Error,
+		ErrorDetails:         errorDetails,
+		Results:              results,
+		MinResourceFee:       preflight.MinFee,
+		LatestLedger:         latestLedger,
+		RestorePreamble:      restorePreamble,
+		StateChanges:         stateChanges,
+		FootprintBytes:       footprintBytes,
+		RentChanges:          rentChanges,
+		RestoreStatuses:      restoreStatuses,
+		CallTrace:            callTrace,
+		WasmDiagnostics:      wasmDiagnostics,
+		Warnings:             preflight.Warnings,
+		ResourceUsage:        getResourceUsage(preflight),
+		Logs:                 preflight.Logs,
+		ResourceFeeBreakdown: getResourceFeeBreakdown(preflight),
+		StubbedCheckAuth:     preflight.StubbedCheckAuth,
 	}
 
 	switch format {
@@ -240,121 +687,661 @@ func formatResponse(preflight preflight.Preflight,
 			return protocol.SimulateTransactionResponse{}, err
 		}
 
-		simResp.EventsJSON, err = jsonifySlice(xdr.DiagnosticEvent{}, preflight.Events)
+		simResp.EventsJSON, err = jsonifySlice(xdr.DiagnosticEvent{}, preflight.DiagnosticEvents)
+		if err != nil {
+			return protocol.SimulateTransactionResponse{}, err
+		}
+
+		simResp.ContractEventsJSON, err = jsonifySlice(xdr.ContractEvent{}, preflight.ContractEvents)
 		if err != nil {
 			return protocol.SimulateTransactionResponse{}, err
 		}
 
 	default:
-		simResp.EventsXDR = base64EncodeSlice(preflight.Events)
+		simResp.EventsXDR = base64EncodeSlice(preflight.DiagnosticEvents)
+		simResp.ContractEventsXDR = base64EncodeSlice(preflight.ContractEvents)
 		simResp.TransactionDataXDR = base64.StdEncoding.EncodeToString(preflight.TransactionData)
 	}
 
 	return simResp, nil
 }
 
-// NewSimulateTransactionHandler returns a JSON rpc handler to run preflight simulations
+// LedgerOverrideLimits bounds how far the ledger seen by a simulation can be
+// skewed from the simulated one through the ledgerOverride of the request.
+type LedgerOverrideLimits struct {
+	MaxLedgerSkew uint32
+	MaxTimeSkew   time.Duration
+}
+
+// NewSimulateTransactionHandler returns a JSON rpc handler to run preflight
+// simulations, against one of the last snapshotLedgers ledgers (whose state
+// Stellar Core serves).
 func NewSimulateTransactionHandler(logger *log.Entry,
 	ledgerReader db.LedgerReader,
-	coreClient interfaces.FastCoreClient, getter PreflightGetter,
+	coreClient interfaces.FastCoreClient, getter PreflightGetter, feeWindows *feewindow.FeeWindows,
+	overrideLimits LedgerOverrideLimits, snapshotLedgers uint32,
 ) jrpc2.Handler {
 	return NewHandler(func(ctx context.Context, request protocol.SimulateTransactionRequest,
-	) protocol.SimulateTransactionResponse {
-		if err := protocol.IsValidFormat(request.Format); err != nil {
-			return protocol.SimulateTransactionResponse{Error: err.Error()}
-		}
-		var txEnvelope xdr.TransactionEnvelope
-		if err := xdr.SafeUnmarshalBase64(request.Transaction, &txEnvelope); err != nil {
-			logger.WithError(err).WithField("request", request).
-				Info("could not unmarshal simulate transaction envelope")
-			return protocol.SimulateTransactionResponse{
-				Error: "Could not unmarshal transaction",
-			}
+	) (protocol.SimulateTransactionResponse, error) {
+		// A ledger which can't be simulated is an invalid parameter rather
+		// than a failure of the simulation
+		if _, _, err := getSimulationLedger(ctx, ledgerReader, request.Ledger, snapshotLedgers); err != nil {
+			return protocol.SimulateTransactionResponse{}, err
 		}
-		if len(txEnvelope.Operations()) != 1 {
-			return protocol.SimulateTransactionResponse{
-				Error: "Transaction contains more than one operation",
-			}
+		return SimulateTransaction(ctx, logger, ledgerReader, coreClient, getter, feeWindows, overrideLimits,
+			snapshotLedgers, request), nil
+	})
+}
+
+// SimulateTransaction runs the preflight simulation of the requested
+// transaction, reporting its failures in the error of the response.
+func SimulateTransaction(ctx context.Context, logger *log.Entry,
+	ledgerReader db.LedgerReader,
+	coreClient interfaces.FastCoreClient, getter PreflightGetter, feeWindows *feewindow.FeeWindows,
+	overrideLimits LedgerOverrideLimits, snapshotLedgers uint32, request protocol.SimulateTransactionRequest,
+) protocol.SimulateTransactionResponse {
+	simulation, failure := prepareSimulation(ctx, logger, ledgerReader, coreClient, overrideLimits,
+		snapshotLedgers, request)
+	if simulation == nil {
+		return failure
+	}
+	result, err := getter.GetPreflight(ctx, simulation.params)
+	return simulation.respond(ctx, ledgerReader, feeWindows, result, err)
+}
+
+// simulateTransactionAt runs the preflight simulation of the requested
+// transaction at a stored ledger, against the state served by the given getter
+// rather than by Stellar Core (e.g. a state rebuilt from the stored ledgers).
+func simulateTransactionAt(ctx context.Context, logger *log.Entry,
+	ledgerReader db.LedgerReader,
+	coreClient interfaces.FastCoreClient, getter PreflightGetter, feeWindows *feewindow.FeeWindows,
+	request protocol.SimulateTransactionRequest, ledger uint32, state ledgerentries.LedgerEntryGetter,
+) protocol.SimulateTransactionResponse {
+	simulation, failure := prepareSimulation(ctx, logger, ledgerReader, coreClient, LedgerOverrideLimits{}, 1,
+		request)
+	if simulation == nil {
+		return failure
+	}
+	bucketListSize, protocolVersion, err := getBucketListSizeAndProtocolVersion(ctx, ledgerReader, ledger)
+	if err != nil {
+		return protocol.SimulateTransactionResponse{
+			Error:        err.Error(),
+			LatestLedger: simulation.latestLedger,
 		}
-		op := txEnvelope.Operations()[0]
+	}
+	simulation.params.BucketListSize = bucketListSize
+	simulation.params.ProtocolVersion = protocolVersion
+	simulation.params.LedgerSeq = ledger
+	simulation.params.LedgerEntryGetter = state
+	simulation.params.RebuiltState = true
+	result, err := getter.GetPreflight(ctx, simulation.params)
+	return simulation.respond(ctx, ledgerReader, feeWindows, result, err)
+}
+
+// preparedSimulation holds a validated simulation request along with the
+// parameters of its preflight.
+type preparedSimulation struct {
+	request      protocol.SimulateTransactionRequest
+	txEnvelope   xdr.TransactionEnvelope
+	feeBump      *xdr.FeeBumpTransaction
+	latestLedger uint32
+	params       preflight.GetterParameters
+}
 
-		if err := validateAuthMode(op.Body, &request.AuthMode); err != nil {
-			return protocol.SimulateTransactionResponse{Error: err.Error()}
+// prepareSimulation validates the request and builds the parameters of its
+// preflight. If the request can't be simulated, it returns nil along with the
+// response reporting why.
+func prepareSimulation(ctx context.Context, logger *log.Entry,
+	ledgerReader db.LedgerReader, coreClient interfaces.FastCoreClient,
+	overrideLimits LedgerOverrideLimits, snapshotLedgers uint32, request protocol.SimulateTransactionRequest,
+) (*preparedSimulation, protocol.SimulateTransactionResponse) {
+	if err := protocol.IsValidFormat(request.Format); err != nil {
+		return nil, protocol.SimulateTransactionResponse{Error: err.Error()}
+	}
+	txEnvelope, feeBump, err := getRequestTransactionEnvelope(request)
+	if err != nil {
+		logger.WithError(err).WithField("request", request).
+			Info("could not unmarshal simulate transaction envelope")
+		if request.Operation != "" {
+			return nil, protocol.SimulateTransactionResponse{Error: err.Error()}
+		}
+		return nil, protocol.SimulateTransactionResponse{
+			Error: "Could not unmarshal transaction",
 		}
+	}
+	// Soroban operations can't be combined with any other operation in a
+	// transaction (core rejects those), so there is never more than one
+	// operation to simulate.
+	if len(txEnvelope.Operations()) != 1 {
+		return nil, protocol.SimulateTransactionResponse{
+			Error: "Transaction contains more than one operation",
+		}
+	}
+	op := txEnvelope.Operations()[0]
 
-		var sourceAccount xdr.AccountId
-		if opSourceAccount := op.SourceAccount; opSourceAccount != nil {
-			sourceAccount = opSourceAccount.ToAccountId()
-		} else {
-			sourceAccount = txEnvelope.SourceAccount().ToAccountId()
+	if err := validateAuthMode(op.Body, &request.AuthMode); err != nil {
+		return nil, protocol.SimulateTransactionResponse{Error: err.Error()}
+	}
+
+	ledgerEntryOverrides, configUpgradeSet, err := getSimulationOverrides(request)
+	if err != nil {
+		return nil, protocol.SimulateTransactionResponse{Error: err.Error()}
+	}
+
+	footprint, err := getFootprint(txEnvelope, op)
+	if err != nil {
+		return nil, protocol.SimulateTransactionResponse{Error: err.Error()}
+	}
+
+	latestLedger, simulationLedger, err := getSimulationLedger(ctx, ledgerReader, request.Ledger, snapshotLedgers)
+	if err == nil {
+		err = validateLedgerOverride(request.LedgerOverride, overrideLimits, simulationLedger)
+	}
+	if err != nil {
+		return nil, protocol.SimulateTransactionResponse{Error: err.Error()}
+	}
+	bucketListSize, protocolVersion, err := getBucketListSizeAndProtocolVersion(ctx, ledgerReader, simulationLedger)
+	if err != nil {
+		return nil, protocol.SimulateTransactionResponse{
+			Error:        err.Error(),
+			LatestLedger: latestLedger,
 		}
+	}
 
-		footprint := xdr.LedgerFootprint{}
-		switch op.Body.Type {
-		case xdr.OperationTypeInvokeHostFunction: // no-op
-		case xdr.OperationTypeExtendFootprintTtl, xdr.OperationTypeRestoreFootprint:
-			if txEnvelope.Type != xdr.EnvelopeTypeEnvelopeTypeTx && txEnvelope.V1.Tx.Ext.V != 1 {
-				return protocol.SimulateTransactionResponse{
-					Error: "To perform a SimulateTransaction for ExtendFootprintTtl or RestoreFootprint operations," +
-						" SorobanTransactionData must be provided",
-				}
-			}
-			footprint = txEnvelope.V1.Tx.Ext.SorobanData.Resources.Footprint
+	resourceConfig := protocol.DefaultResourceConfig()
+	if request.ResourceConfig != nil {
+		resourceConfig = *request.ResourceConfig
+	}
+	return &preparedSimulation{
+		request:      request,
+		txEnvelope:   txEnvelope,
+		feeBump:      feeBump,
+		latestLedger: latestLedger,
+		params: preflight.GetterParameters{
+			BucketListSize:           bucketListSize,
+			SourceAccount:            getSourceAccount(txEnvelope, op),
+			OperationBody:            op.Body,
+			Footprint:                footprint,
+			ResourceConfig:           resourceConfig,
+			AuthMode:                 request.AuthMode,
+			ProtocolVersion:          protocolVersion,
+			LedgerEntryGetter:        ledgerentries.NewLedgerEntryAtGetter(coreClient, simulationLedger),
+			LedgerSeq:                simulationLedger,
+			LedgerEntryOverrides:     ledgerEntryOverrides,
+			ConfigUpgradeSet:         configUpgradeSet,
+			SimulatedProtocolVersion: request.ProtocolVersion,
+			LedgerOverride:           request.LedgerOverride,
+		},
+	}, protocol.SimulateTransactionResponse{}
+}
 
-		default:
-			return protocol.SimulateTransactionResponse{
-				Error: "Transaction contains unsupported operation type: " + op.Body.Type.String(),
-			}
+// respond builds the response of the simulation from the result of its
+// preflight.
+func (s *preparedSimulation) respond(ctx context.Context, ledgerReader db.LedgerReader,
+	feeWindows *feewindow.FeeWindows, result preflight.Preflight, err error,
+) protocol.SimulateTransactionResponse {
+	if err != nil {
+		return protocol.SimulateTransactionResponse{
+			Error:        err.Error(),
+			LatestLedger: s.latestLedger,
+		}
+	}
+
+	simResp, err := formatResponse(result, s.request.Format, s.latestLedger)
+	if err == nil && result.Error == "" {
+		err = addTransactionEstimates(&simResp, s.txEnvelope, s.feeBump, result, feeWindows)
+	}
+	if err == nil && s.request.ValidatePreconditions {
+		simResp.PreconditionFailures, err = getPreconditionFailures(
+			ctx, ledgerReader, s.params.LedgerEntryGetter, s.txEnvelope, s.params.LedgerSeq)
+	}
+	if err != nil {
+		return protocol.SimulateTransactionResponse{
+			Error:        err.Error(),
+			LatestLedger: s.latestLedger,
 		}
+	}
+	return simResp
+}
 
-		latestLedger, err := ledgerReader.GetLatestLedgerSequence(ctx)
-		if err != nil {
-			return protocol.SimulateTransactionResponse{
-				Error: err.Error(),
+// getSourceAccount returns the account on behalf of which the operation is
+// run, which is the source account of the transaction unless the operation
+// has its own.
+func getSourceAccount(txEnvelope xdr.TransactionEnvelope, op xdr.Operation) xdr.AccountId {
+	if op.SourceAccount != nil {
+		return op.SourceAccount.ToAccountId()
+	}
+	return txEnvelope.SourceAccount().ToAccountId()
+}
+
+// getRequestTransactionEnvelope returns the transaction to simulate, which is
+// built around the operation of the request when it has no transaction.
+func getRequestTransactionEnvelope(request protocol.SimulateTransactionRequest,
+) (xdr.TransactionEnvelope, *xdr.FeeBumpTransaction, error) {
+	if request.Operation == "" {
+		return getTransactionEnvelope(request.Transaction)
+	}
+	if request.Transaction != "" {
+		return xdr.TransactionEnvelope{}, nil, errors.New("transaction and operation cannot be both provided")
+	}
+	txEnvelope, err := newPlaceholderTransactionEnvelope(request.Operation, request.SourceAccount)
+	return txEnvelope, nil, err
+}
+
+// newPlaceholderTransactionEnvelope wraps an InvokeHostFunctionOp in an
+// unsigned transaction with a zero sequence number and fee, which is all the
+// simulation needs. Fee estimates are computed for it like for any other
+// transaction.
+func newPlaceholderTransactionEnvelope(operationB64, sourceAccount string) (xdr.TransactionEnvelope, error) {
+	var invokeOp xdr.InvokeHostFunctionOp
+	if err := xdr.SafeUnmarshalBase64(operationB64, &invokeOp); err != nil {
+		return xdr.TransactionEnvelope{}, fmt.Errorf("could not unmarshal operation: %w", err)
+	}
+	if sourceAccount == "" {
+		return xdr.TransactionEnvelope{}, errors.New("sourceAccount is required to simulate an operation")
+	}
+	var source xdr.MuxedAccount
+	if err := source.SetAddress(sourceAccount); err != nil {
+		return xdr.TransactionEnvelope{}, fmt.Errorf("invalid sourceAccount: %w", err)
+	}
+	return xdr.TransactionEnvelope{
+		Type: xdr.EnvelopeTypeEnvelopeTypeTx,
+		V1: &xdr.TransactionV1Envelope{
+			Tx: xdr.Transaction{
+				SourceAccount: source,
+				Operations: []xdr.Operation{{
+					Body: xdr.OperationBody{
+						Type:                 xdr.OperationTypeInvokeHostFunction,
+						InvokeHostFunctionOp: &invokeOp,
+					},
+				}},
+			},
+		},
+	}, nil
+}
+
+// getTransactionEnvelope decodes the transaction to simulate. Fee-bump
+// transactions are simulated through their inner transaction, which is
+// returned along with the fee-bump.
+func getTransactionEnvelope(transaction string) (xdr.TransactionEnvelope, *xdr.FeeBumpTransaction, error) {
+	var txEnvelope xdr.TransactionEnvelope
+	if err := xdr.SafeUnmarshalBase64(transaction, &txEnvelope); err != nil {
+		return xdr.TransactionEnvelope{}, nil, err
+	}
+	if !txEnvelope.IsFeeBump() {
+		return txEnvelope, nil, nil
+	}
+	feeBump := txEnvelope.FeeBump.Tx
+	innerTxEnvelope := xdr.TransactionEnvelope{
+		Type: xdr.EnvelopeTypeEnvelopeTypeTx,
+		V1:   feeBump.InnerTx.V1,
+	}
+	return innerTxEnvelope, &feeBump, nil
+}
+
+// addTransactionEstimates completes the response of a successful simulation
+// with the fee requirements of the fee-bump or, for other transactions, the
+// size and suggested total fee of the transaction once assembled.
+func addTransactionEstimates(
+	simResp *protocol.SimulateTransactionResponse, txEnvelope xdr.TransactionEnvelope,
+	feeBump *xdr.FeeBumpTransaction, result preflight.Preflight, feeWindows *feewindow.FeeWindows,
+) error {
+	var err error
+	if feeBump != nil {
+		simResp.FeeBump, err = getFeeBumpSimulation(*feeBump, result)
+		return err
+	}
+	// Only V1 envelopes can carry the transaction data
+	if txEnvelope.Type != xdr.EnvelopeTypeEnvelopeTypeTx {
+		return nil
+	}
+	simResp.TransactionSize, err = getAssembledTransactionSize(txEnvelope, result)
+	if err != nil {
+		return err
+	}
+	// Bidding the 90th percentile of the recent inclusion fees gets the
+	// transaction included even under moderate surge pricing, while only the
+	// fee required by the ledger is actually charged.
+	inclusionFee := max(int64(feeWindows.SorobanInclusionFeeWindow.GetFeeDistribution().P90), minBaseFee)
+	simResp.SuggestedFee = int64(len(txEnvelope.Operations()))*inclusionFee + result.MinFee
+	return nil
+}
+
+// getAssembledTransactionSize estimates the size of the transaction envelope
+// once assembled with the simulated transaction data and auth entries, and
+// signed by its source account. The signatures of the auth entries, which
+// depend on the signing accounts, aren't accounted for.
+func getAssembledTransactionSize(txEnvelope xdr.TransactionEnvelope, result preflight.Preflight) (uint32, error) {
+	// Copy the envelope so that the one of the request isn't modified
+	envelopeXDR, err := txEnvelope.MarshalBinary()
+	if err != nil {
+		return 0, err
+	}
+	var assembled xdr.TransactionEnvelope
+	if err := xdr.SafeUnmarshal(envelopeXDR, &assembled); err != nil {
+		return 0, err
+	}
+	var transactionData xdr.SorobanTransactionData
+	if err := xdr.SafeUnmarshal(result.TransactionData, &transactionData); err != nil {
+		return 0, err
+	}
+	assembled.V1.Tx.Ext = xdr.TransactionExt{V: 1, SorobanData: &transactionData}
+
+	if invokeOp, ok := assembled.V1.Tx.Operations[0].Body.GetInvokeHostFunctionOp(); ok && len(result.Auth) > 0 {
+		invokeOp.Auth = make([]xdr.SorobanAuthorizationEntry, len(result.Auth))
+		for i, authXDR := range result.Auth {
+			if err := xdr.SafeUnmarshal(authXDR, &invokeOp.Auth[i]); err != nil {
+				return 0, err
 			}
 		}
-		bucketListSize, protocolVersion, err := getBucketListSizeAndProtocolVersion(ctx, ledgerReader, latestLedger)
+		assembled.V1.Tx.Operations[0].Body.InvokeHostFunctionOp = &invokeOp
+	}
+	if len(assembled.V1.Signatures) == 0 {
+		assembled.V1.Signatures = []xdr.DecoratedSignature{{Signature: make([]byte, ed25519SignatureSize)}}
+	}
+
+	assembledXDR, err := assembled.MarshalBinary()
+	if err != nil {
+		return 0, err
+	}
+	return uint32(len(assembledXDR)), nil
+}
+
+// getFeeBumpSimulation computes the fee the fee-bump transaction must bid and
+// checks that the resources declared by the (already signed) inner transaction
+// cover the simulated ones.
+func getFeeBumpSimulation(
+	feeBump xdr.FeeBumpTransaction, preflight preflight.Preflight,
+) (*protocol.FeeBumpSimulation, error) {
+	var simulatedData xdr.SorobanTransactionData
+	if err := xdr.SafeUnmarshal(preflight.TransactionData, &simulatedData); err != nil {
+		return nil, err
+	}
+	innerTx := feeBump.InnerTx.V1.Tx
+
+	var validationErrors []string
+	resourceFee := preflight.MinFee
+	if innerData, ok := innerTx.Ext.GetSorobanData(); ok {
+		// The inner transaction is charged the resource fee it declares
+		resourceFee = int64(innerData.ResourceFee)
+		var err error
+		validationErrors, err = validateInnerResources(innerData, simulatedData, preflight.MinFee)
 		if err != nil {
-			return protocol.SimulateTransactionResponse{
-				Error:        err.Error(),
-				LatestLedger: latestLedger,
-			}
+			return nil, err
 		}
+	} else {
+		validationErrors = append(validationErrors, "inner transaction is missing SorobanTransactionData")
+	}
 
-		resourceConfig := protocol.DefaultResourceConfig()
-		if request.ResourceConfig != nil {
-			resourceConfig = *request.ResourceConfig
+	// The fee-bump pays the inclusion fee of the inner operations plus one
+	// for itself
+	minFee := int64(len(innerTx.Operations)+1)*minBaseFee + resourceFee
+	if int64(feeBump.Fee) < minFee {
+		validationErrors = append(validationErrors,
+			fmt.Sprintf("fee: %d bid, at least %d required", feeBump.Fee, minFee))
+	}
+	return &protocol.FeeBumpSimulation{
+		MinFee:           minFee,
+		ValidationErrors: validationErrors,
+	}, nil
+}
+
+func validateInnerResources(
+	declared xdr.SorobanTransactionData, simulated xdr.SorobanTransactionData, minResourceFee int64,
+) ([]string, error) {
+	var validationErrors []string
+	check := func(name string, value, required int64) {
+		if value < required {
+			validationErrors = append(validationErrors,
+				fmt.Sprintf("%s: %d declared, at least %d required", name, value, required))
 		}
-		ledgerEntryGetter := ledgerentries.NewLedgerEntryAtGetter(coreClient, latestLedger)
+	}
+	check("instructions", int64(declared.Resources.Instructions), int64(simulated.Resources.Instructions))
+	check("diskReadBytes", int64(declared.Resources.DiskReadBytes), int64(simulated.Resources.DiskReadBytes))
+	check("writeBytes", int64(declared.Resources.WriteBytes), int64(simulated.Resources.WriteBytes))
+	check("resourceFee", int64(declared.ResourceFee), minResourceFee)
 
-		params := preflight.GetterParameters{
-			BucketListSize:    bucketListSize,
-			SourceAccount:     sourceAccount,
-			OperationBody:     op.Body,
-			Footprint:         footprint,
-			ResourceConfig:    resourceConfig,
-			AuthMode:          request.AuthMode,
-			ProtocolVersion:   protocolVersion,
-			LedgerEntryGetter: ledgerEntryGetter,
-			LedgerSeq:         latestLedger,
+	readWrite, err := ledgerKeySet(declared.Resources.Footprint.ReadWrite)
+	if err != nil {
+		return nil, err
+	}
+	readOnly, err := ledgerKeySet(declared.Resources.Footprint.ReadOnly)
+	if err != nil {
+		return nil, err
+	}
+	for _, key := range simulated.Resources.Footprint.ReadWrite {
+		keyB64, err := key.MarshalBinaryBase64()
+		if err != nil {
+			return nil, err
 		}
-		result, err := getter.GetPreflight(ctx, params)
+		if !readWrite[keyB64] {
+			validationErrors = append(validationErrors, "footprint: missing read-write key "+keyB64)
+		}
+	}
+	for _, key := range simulated.Resources.Footprint.ReadOnly {
+		keyB64, err := key.MarshalBinaryBase64()
 		if err != nil {
-			return protocol.SimulateTransactionResponse{
-				Error:        err.Error(),
-				LatestLedger: latestLedger,
-			}
+			return nil, err
 		}
+		if !readOnly[keyB64] && !readWrite[keyB64] {
+			validationErrors = append(validationErrors, "footprint: missing read-only key "+keyB64)
+		}
+	}
+	return validationErrors, nil
+}
 
-		simResp, err := formatResponse(result, request.Format, latestLedger)
+func ledgerKeySet(keys []xdr.LedgerKey) (map[string]bool, error) {
+	set := make(map[string]bool, len(keys))
+	for _, key := range keys {
+		keyB64, err := key.MarshalBinaryBase64()
 		if err != nil {
-			return protocol.SimulateTransactionResponse{
-				Error:        err.Error(),
-				LatestLedger: latestLedger,
+			return nil, err
+		}
+		set[keyB64] = true
+	}
+	return set, nil
+}
+
+// getPreconditionFailures checks the sequence number and the preconditions of
+// the transaction like core does when it's submitted for the ledger following
+// the simulated one, whose close time is approximated by the one of the
+// simulated ledger.
+func getPreconditionFailures(
+	ctx context.Context, ledgerReader db.LedgerReader, ledgerEntryGetter ledgerentries.LedgerEntryGetter,
+	txEnvelope xdr.TransactionEnvelope, simulationLedger uint32,
+) ([]protocol.PreconditionFailure, error) {
+	closeMeta, ok, err := ledgerReader.GetLedger(ctx, simulationLedger)
+	if err != nil {
+		return nil, err
+	}
+	if !ok {
+		return nil, fmt.Errorf("missing meta for ledger (%d)", simulationLedger)
+	}
+	closeTime := uint64(closeMeta.LedgerHeaderHistoryEntry().Header.ScpValue.CloseTime)
+	failures := getBoundsPreconditionFailures(txEnvelope, closeTime, simulationLedger+1)
+
+	sourceAccount := txEnvelope.SourceAccount().ToAccountId()
+	key := xdr.LedgerKey{
+		Type:    xdr.LedgerEntryTypeAccount,
+		Account: &xdr.LedgerKeyAccount{AccountId: sourceAccount},
+	}
+	entries, _, err := ledgerEntryGetter.GetLedgerEntries(ctx, []xdr.LedgerKey{key})
+	if err != nil {
+		return nil, err
+	}
+	if len(entries) == 0 {
+		return append(failures, protocol.PreconditionFailure{
+			Code:    protocol.PreconditionNoAccount,
+			Message: fmt.Sprintf("source account %s doesn't exist", sourceAccount.Address()),
+		}), nil
+	}
+	account := entries[0].Entry.Data.MustAccount()
+	return append(failures, getSequencePreconditionFailures(txEnvelope, account, closeTime, simulationLedger+1)...), nil
+}
+
+// getBoundsPreconditionFailures checks the time and ledger bounds of the
+// transaction against the ledger it would be included in.
+func getBoundsPreconditionFailures(
+	txEnvelope xdr.TransactionEnvelope, closeTime uint64, ledgerSeq uint32,
+) []protocol.PreconditionFailure {
+	var failures []protocol.PreconditionFailure
+	failf := func(code, format string, args ...any) {
+		failures = append(failures, protocol.PreconditionFailure{Code: code, Message: fmt.Sprintf(format, args...)})
+	}
+	if timeBounds := txEnvelope.TimeBounds(); timeBounds != nil {
+		if closeTime < uint64(timeBounds.MinTime) {
+			failf(protocol.PreconditionTooEarly, "time bounds: valid from %d, ledger closes at %d",
+				timeBounds.MinTime, closeTime)
+		}
+		if timeBounds.MaxTime != 0 && uint64(timeBounds.MaxTime) < closeTime {
+			failf(protocol.PreconditionTooLate, "time bounds: valid until %d, ledger closes at %d",
+				timeBounds.MaxTime, closeTime)
+		}
+	}
+	if ledgerBounds := txEnvelope.LedgerBounds(); ledgerBounds != nil {
+		if ledgerSeq < uint32(ledgerBounds.MinLedger) {
+			failf(protocol.PreconditionTooEarly, "ledger bounds: valid from ledger %d, next ledger is %d",
+				ledgerBounds.MinLedger, ledgerSeq)
+		}
+		if ledgerBounds.MaxLedger != 0 && uint32(ledgerBounds.MaxLedger) <= ledgerSeq {
+			failf(protocol.PreconditionTooLate, "ledger bounds: valid before ledger %d, next ledger is %d",
+				ledgerBounds.MaxLedger, ledgerSeq)
+		}
+	}
+	return failures
+}
+
+// getSequencePreconditionFailures checks the sequence number of the
+// transaction, along with the minimum sequence number, age and ledger gap it
+// requires, against its source account.
+func getSequencePreconditionFailures(
+	txEnvelope xdr.TransactionEnvelope, account xdr.AccountEntry, closeTime uint64, ledgerSeq uint32,
+) []protocol.PreconditionFailure {
+	var failures []protocol.PreconditionFailure
+	failf := func(code, format string, args ...any) {
+		failures = append(failures, protocol.PreconditionFailure{Code: code, Message: fmt.Sprintf(format, args...)})
+	}
+	accountSeq, txSeq := int64(account.SeqNum), txEnvelope.SeqNum()
+	if minSeqNum := txEnvelope.MinSeqNum(); minSeqNum != nil {
+		if accountSeq < *minSeqNum || accountSeq >= txSeq {
+			failf(protocol.PreconditionBadSeq,
+				"sequence number %d requires the one of the account to be in [%d, %d), it is %d",
+				txSeq, *minSeqNum, txSeq, accountSeq)
+		}
+	} else if accountSeq+1 != txSeq {
+		failf(protocol.PreconditionBadSeq, "sequence number %d, the next one of the account is %d",
+			txSeq, accountSeq+1)
+	}
+
+	seqTime, seqLedger := getAccountSeqTimeAndLedger(account)
+	if minSeqAge := txEnvelope.MinSeqAge(); minSeqAge > 0 && closeTime-seqTime < minSeqAge {
+		failf(protocol.PreconditionBadMinSeqAgeOrGap,
+			"minimum sequence age: %d seconds required, the account sequence number is %d seconds old",
+			minSeqAge, closeTime-seqTime)
+	}
+	if minSeqLedgerGap := txEnvelope.MinSeqLedgerGap(); minSeqLedgerGap > 0 && ledgerSeq-seqLedger < minSeqLedgerGap {
+		failf(protocol.PreconditionBadMinSeqAgeOrGap,
+			"minimum sequence ledger gap: %d ledgers required, the account sequence number is %d ledgers old",
+			minSeqLedgerGap, ledgerSeq-seqLedger)
+	}
+	return failures
+}
+
+// getAccountSeqTimeAndLedger returns when the sequence number of the account
+// was last bumped, which is zero if it hasn't been since protocol 19.
+func getAccountSeqTimeAndLedger(account xdr.AccountEntry) (uint64, uint32) {
+	v1, ok := account.Ext.GetV1()
+	if !ok {
+		return 0, 0
+	}
+	v2, ok := v1.Ext.GetV2()
+	if !ok {
+		return 0, 0
+	}
+	v3, ok := v2.Ext.GetV3()
+	if !ok {
+		return 0, 0
+	}
+	return uint64(v3.SeqTime), uint32(v3.SeqLedger)
+}
+
+// getFootprint returns the footprint given in the transaction's soroban data,
+// which is required for ExtendFootprintTtl and RestoreFootprint operations.
+func getFootprint(txEnvelope xdr.TransactionEnvelope, op xdr.Operation) (xdr.LedgerFootprint, error) {
+	switch op.Body.Type {
+	case xdr.OperationTypeInvokeHostFunction:
+		// The footprint of a transaction simulated again (if any) is only
+		// used to prefetch its entries
+		if txEnvelope.Type == xdr.EnvelopeTypeEnvelopeTypeTx {
+			if sorobanData, ok := txEnvelope.V1.Tx.Ext.GetSorobanData(); ok {
+				return sorobanData.Resources.Footprint, nil
 			}
 		}
-		return simResp
-	})
+		return xdr.LedgerFootprint{}, nil
+	case xdr.OperationTypeExtendFootprintTtl, xdr.OperationTypeRestoreFootprint:
+		if txEnvelope.Type != xdr.EnvelopeTypeEnvelopeTypeTx && txEnvelope.V1.Tx.Ext.V != 1 {
+			return xdr.LedgerFootprint{}, errors.New(
+				"to perform a SimulateTransaction for ExtendFootprintTtl or RestoreFootprint operations," +
+					" SorobanTransactionData must be provided")
+		}
+		return txEnvelope.V1.Tx.Ext.SorobanData.Resources.Footprint, nil
+	default:
+		return xdr.LedgerFootprint{}, fmt.Errorf("transaction contains unsupported operation type: %s", op.Body.Type.String())
+	}
+}
+
+// getSimulationLedger returns the latest ledger along with the ledger to
+// simulate against, which is the requested one (if any) as long as Stellar Core
+// still serves its state, i.e. it's one of the last snapshotLedgers ledgers.
+func getSimulationLedger(
+	ctx context.Context,
+	ledgerReader db.LedgerReader,
+	requestedLedger uint32,
+	snapshotLedgers uint32,
+) (uint32, uint32, error) {
+	latestLedger, err := ledgerReader.GetLatestLedgerSequence(ctx)
+	if err != nil {
+		return 0, 0, err
+	}
+	if requestedLedger == 0 {
+		return latestLedger, latestLedger, nil
+	}
+	oldestLedger := latestLedger - min(max(snapshotLedgers, 1), latestLedger) + 1
+	if requestedLedger < oldestLedger || requestedLedger > latestLedger {
+		return 0, 0, &jrpc2.Error{
+			Code: jrpc2.InvalidParams,
+			Message: fmt.Sprintf(
+				"ledger must be between %d and the latest ledger: %d, the only ledgers whose state can be simulated",
+				oldestLedger, latestLedger,
+			),
+		}
+	}
+	return latestLedger, requestedLedger, nil
+}
+
+// validateLedgerOverride checks that the ledger override of the request skews
+// the ledger seen by the simulation within the limits of the server. The
+// timestamp of the ledger seen by simulations is otherwise the current time.
+func validateLedgerOverride(
+	override *protocol.LedgerOverride, limits LedgerOverrideLimits, simulationLedger uint32,
+) error {
+	if override == nil {
+		return nil
+	}
+	if override.Sequence != 0 {
+		skew := max(override.Sequence, simulationLedger) - min(override.Sequence, simulationLedger)
+		if skew > limits.MaxLedgerSkew {
+			return fmt.Errorf("ledgerOverride.sequence is %d ledgers away from the simulated ledger (at most %d allowed)",
+				skew, limits.MaxLedgerSkew)
+		}
+	}
+	if override.Timestamp != 0 {
+		maxSkew := int64(limits.MaxTimeSkew / time.Second)
+		if skew := override.Timestamp - time.Now().Unix(); skew > maxSkew || skew < -maxSkew {
+			return fmt.Errorf("ledgerOverride.timestamp is more than %v away from the current time", limits.MaxTimeSkew)
+		}
+	}
+	return nil
 }
 
 // Ensures the given auth mode is valid for the given operation body. Auth mode
@@ -370,7 +1357,8 @@ func validateAuthMode(opBody xdr.OperationBody, authModeRef *string) error {
 
 	// Prior to parsing, validate auth mode.
 	switch authMode {
-	case "", protocol.AuthModeEnforce, protocol.AuthModeRecord, protocol.AuthModeRecordAllowNonroot:
+	case "", protocol.AuthModeEnforce, protocol.AuthModeRecord, protocol.AuthModeRecordAllowNonroot,
+		protocol.AuthModeAuto, protocol.AuthModeEnforceMockSignatures:
 	default:
 		return fmt.Errorf(
 			"optional 'authMode' must be one of %s when included",
@@ -378,6 +1366,8 @@ func validateAuthMode(opBody xdr.OperationBody, authModeRef *string) error {
 				protocol.AuthModeEnforce,
 				protocol.AuthModeRecord,
 				protocol.AuthModeRecordAllowNonroot,
+				protocol.AuthModeAuto,
+				protocol.AuthModeEnforceMockSignatures,
 			}, ","),
 		)
 	}
@@ -387,14 +1377,9 @@ func validateAuthMode(opBody xdr.OperationBody, authModeRef *string) error {
 		hasAuth := len(opBody.MustInvokeHostFunctionOp().Auth) > 0
 
 		if authMode == "" {
-			// Interpret the best course of action based on the payload:
-			//  - default is enforcement with auth payload, recording without
-			//  - recording with an auth payload isn't allowed
-			if hasAuth {
-				*authModeRef = protocol.AuthModeEnforce
-			} else {
-				*authModeRef = protocol.AuthModeRecord
-			}
+			// The preflight library picks the best course of action based on
+			// the payload: enforcement with an auth payload, recording without
+			*authModeRef = protocol.AuthModeAuto
 		} else if hasAuth && (authMode == protocol.AuthModeRecord ||
 			authMode == protocol.AuthModeRecordAllowNonroot) {
 			// If the operation has auth included already, it's invalid for the
@@ -418,6 +1403,47 @@ func validateAuthMode(opBody xdr.OperationBody, authModeRef *string) error {
 	return nil
 }
 
+func getSimulationOverrides(request protocol.SimulateTransactionRequest,
+) ([]xdr.LedgerEntry, *xdr.ConfigUpgradeSet, error) {
+	ledgerEntryOverrides, err := getLedgerEntryOverrides(request.LedgerEntryOverrides)
+	if err != nil {
+		return nil, nil, err
+	}
+	configUpgradeSet, err := getConfigUpgradeSet(request.ConfigUpgradeSet)
+	if err != nil {
+		return nil, nil, err
+	}
+	return ledgerEntryOverrides, configUpgradeSet, nil
+}
+
+func getLedgerEntryOverrides(overridesB64 []string) ([]xdr.LedgerEntry, error) {
+	overrides := make([]xdr.LedgerEntry, len(overridesB64))
+	for i, overrideB64 := range overridesB64 {
+		if err := xdr.SafeUnmarshalBase64(overrideB64, &overrides[i]); err != nil {
+			return nil, fmt.Errorf("could not unmarshal ledger entry override %d: %w", i, err)
+		}
+	}
+	return overrides, nil
+}
+
+func getConfigUpgradeSet(upgradeSetB64 string) (*xdr.ConfigUpgradeSet, error) {
+	if upgradeSetB64 == "" {
+		return nil, nil //nolint:nilnil // no upgrade set to simulate against
+	}
+	var upgradeSet xdr.ConfigUpgradeSet
+	if err := xdr.SafeUnmarshalBase64(upgradeSetB64, &upgradeSet); err != nil {
+		return nil, fmt.Errorf("could not unmarshal config upgrade set: %w", err)
+	}
+	seen := make(map[xdr.ConfigSettingId]bool, len(upgradeSet.UpdatedEntry))
+	for _, setting := range upgradeSet.UpdatedEntry {
+		if seen[setting.ConfigSettingId] {
+			return nil, fmt.Errorf("duplicate config setting in config upgrade set: %s", setting.ConfigSettingId)
+		}
+		seen[setting.ConfigSettingId] = true
+	}
+	return &upgradeSet, nil
+}
+
 func base64EncodeSlice(in [][]byte) []string {
 	result := make([]string, len(in))
 	for i, v := range in {
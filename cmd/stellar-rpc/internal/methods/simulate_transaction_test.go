@@ -1,14 +1,19 @@
 package methods
 
 import (
+	"context"
 	"encoding/base64"
 	"encoding/json"
 	"testing"
+	"time"
 
+	"github.com/creachadair/jrpc2"
 	"github.com/stretchr/testify/require"
 
+	"github.com/stellar/go/strkey"
 	"github.com/stellar/go/xdr"
 
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/feewindow"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/preflight"
 	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/xdr2json"
 	"github.com/stellar/stellar-rpc/protocol"
@@ -55,6 +60,7 @@ func TestLedgerEntryChange(t *testing.T) {
 			},
 			expectedOutput: protocol.LedgerEntryChange{
 				Type:      protocol.LedgerEntryChangeTypeCreated,
+				EntryKind: protocol.LedgerEntryKindClassic,
 				KeyXDR:    keyB64,
 				BeforeXDR: nil,
 				AfterXDR:  &entryB64,
@@ -68,6 +74,7 @@ func TestLedgerEntryChange(t *testing.T) {
 			},
 			expectedOutput: protocol.LedgerEntryChange{
 				Type:      protocol.LedgerEntryChangeTypeDeleted,
+				EntryKind: protocol.LedgerEntryKindClassic,
 				KeyXDR:    keyB64,
 				BeforeXDR: &entryB64,
 				AfterXDR:  nil,
@@ -81,6 +88,7 @@ func TestLedgerEntryChange(t *testing.T) {
 			},
 			expectedOutput: protocol.LedgerEntryChange{
 				Type:      protocol.LedgerEntryChangeTypeUpdated,
+				EntryKind: protocol.LedgerEntryKindClassic,
 				KeyXDR:    keyB64,
 				BeforeXDR: &entryB64,
 				AfterXDR:  &entryB64,
@@ -104,11 +112,701 @@ func TestLedgerEntryChange(t *testing.T) {
 		require.NoError(t, err, test.name)
 
 		require.Equal(t, keyJs, changeJs.KeyJSON)
-		if changeJs.AfterJSON != nil {
-			require.Equal(t, entryJs, changeJs.AfterJSON)
+		if test.input.After != nil {
+			require.Equal(t, entryJs, changeJs.AfterJSON, test.name)
+		} else {
+			require.Nil(t, changeJs.AfterJSON, test.name)
 		}
-		if changeJs.BeforeJSON != nil {
-			require.Equal(t, entryJs, changeJs.BeforeJSON)
+		if test.input.Before != nil {
+			require.Equal(t, entryJs, changeJs.BeforeJSON, test.name)
+		} else {
+			require.Nil(t, changeJs.BeforeJSON, test.name)
 		}
 	}
 }
+
+func TestGetLedgerEntryKind(t *testing.T) {
+	require.Equal(t, protocol.LedgerEntryKindContract, getLedgerEntryKind(xdr.LedgerEntryTypeContractData))
+	require.Equal(t, protocol.LedgerEntryKindContract, getLedgerEntryKind(xdr.LedgerEntryTypeTtl))
+	require.Equal(t, protocol.LedgerEntryKindClassic, getLedgerEntryKind(xdr.LedgerEntryTypeTrustline))
+}
+
+func TestFormatResponseSeparatesEvents(t *testing.T) {
+	contractEvent := xdr.ContractEvent{
+		Type: xdr.ContractEventTypeContract,
+		Body: xdr.ContractEventBody{
+			V: 0,
+			V0: &xdr.ContractEventV0{
+				Data: xdr.ScVal{Type: xdr.ScValTypeScvVoid},
+			},
+		},
+	}
+	diagnosticEvent := xdr.DiagnosticEvent{
+		InSuccessfulContractCall: true,
+		Event:                    contractEvent,
+	}
+	contractEventXDR, err := contractEvent.MarshalBinary()
+	require.NoError(t, err)
+	diagnosticEventXDR, err := diagnosticEvent.MarshalBinary()
+	require.NoError(t, err)
+
+	result := preflight.Preflight{
+		ContractEvents:   [][]byte{contractEventXDR},
+		DiagnosticEvents: [][]byte{diagnosticEventXDR, diagnosticEventXDR},
+	}
+
+	resp, err := formatResponse(result, protocol.FormatBase64, 10)
+	require.NoError(t, err)
+	require.Equal(t, []string{base64.StdEncoding.EncodeToString(contractEventXDR)}, resp.ContractEventsXDR)
+	require.Len(t, resp.EventsXDR, 2)
+	require.Equal(t, base64.StdEncoding.EncodeToString(diagnosticEventXDR), resp.EventsXDR[0])
+}
+
+func TestGetSimulationLedger(t *testing.T) {
+	ctx := context.Background()
+	ledgerReader := &MockLedgerReader{}
+	ledgerReader.On("GetLatestLedgerSequence", ctx).Return(uint32(100), nil)
+
+	latestLedger, simulationLedger, err := getSimulationLedger(ctx, ledgerReader, 0, 4)
+	require.NoError(t, err)
+	require.Equal(t, uint32(100), latestLedger)
+	require.Equal(t, uint32(100), simulationLedger)
+
+	latestLedger, simulationLedger, err = getSimulationLedger(ctx, ledgerReader, 97, 4)
+	require.NoError(t, err)
+	require.Equal(t, uint32(100), latestLedger)
+	require.Equal(t, uint32(97), simulationLedger)
+
+	// Stellar Core no longer serves the state of older ledgers
+	_, _, err = getSimulationLedger(ctx, ledgerReader, 96, 4)
+	var jsonRPCErr *jrpc2.Error
+	require.ErrorAs(t, err, &jsonRPCErr)
+	require.Equal(t, jrpc2.InvalidParams, jsonRPCErr.Code)
+	require.Equal(t, "ledger must be between 97 and the latest ledger: 100, the only ledgers whose state can be "+
+		"simulated", jsonRPCErr.Message)
+	_, _, err = getSimulationLedger(ctx, ledgerReader, 101, 4)
+	require.ErrorAs(t, err, &jsonRPCErr)
+}
+
+func TestGetAuthTree(t *testing.T) {
+	accountID := xdr.MustAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON")
+	contractID := xdr.ContractId{0xa, 0xb, 0xc}
+	auth := xdr.SorobanAuthorizationEntry{
+		Credentials: xdr.SorobanCredentials{
+			Type: xdr.SorobanCredentialsTypeSorobanCredentialsAddress,
+			Address: &xdr.SorobanAddressCredentials{
+				Address: xdr.ScAddress{
+					Type:      xdr.ScAddressTypeScAddressTypeAccount,
+					AccountId: &accountID,
+				},
+				Signature: xdr.ScVal{Type: xdr.ScValTypeScvVoid},
+			},
+		},
+		RootInvocation: xdr.SorobanAuthorizedInvocation{
+			Function: xdr.SorobanAuthorizedFunction{
+				Type: xdr.SorobanAuthorizedFunctionTypeSorobanAuthorizedFunctionTypeContractFn,
+				ContractFn: &xdr.InvokeContractArgs{
+					ContractAddress: xdr.ScAddress{
+						Type:       xdr.ScAddressTypeScAddressTypeContract,
+						ContractId: &contractID,
+					},
+					FunctionName: "deploy",
+				},
+			},
+			SubInvocations: []xdr.SorobanAuthorizedInvocation{
+				{
+					Function: xdr.SorobanAuthorizedFunction{
+						Type: xdr.SorobanAuthorizedFunctionTypeSorobanAuthorizedFunctionTypeCreateContractHostFn,
+						CreateContractHostFn: &xdr.CreateContractArgs{
+							ContractIdPreimage: xdr.ContractIdPreimage{
+								Type:      xdr.ContractIdPreimageTypeContractIdPreimageFromAsset,
+								FromAsset: &xdr.Asset{Type: xdr.AssetTypeAssetTypeNative},
+							},
+							Executable: xdr.ContractExecutable{
+								Type: xdr.ContractExecutableTypeContractExecutableStellarAsset,
+							},
+						},
+					},
+				},
+			},
+		},
+	}
+	authXDR, err := auth.MarshalBinary()
+	require.NoError(t, err)
+
+	tree, err := getAuthTree([][]byte{authXDR})
+	require.NoError(t, err)
+	require.Equal(t, []protocol.AuthorizationTree{
+		{
+			Address: accountID.Address(),
+			Invocation: protocol.AuthorizedInvocation{
+				ContractAddress: strkey.MustEncode(strkey.VersionByteContract, contractID[:]),
+				FunctionName:    "deploy",
+				SubInvocations: []protocol.AuthorizedInvocation{
+					{CreateContract: true},
+				},
+			},
+		},
+	}, tree)
+}
+
+func TestGetFootprintBytes(t *testing.T) {
+	var key xdr.LedgerKey
+	require.NoError(t, key.SetAccount(xdr.MustAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON")))
+	keyB64, err := key.MarshalBinaryBase64()
+	require.NoError(t, err)
+	keyXDR, err := key.MarshalBinary()
+	require.NoError(t, err)
+	entries := []preflight.FootprintEntryBytes{{Key: keyXDR, ReadBytes: 100, WriteBytes: 120}}
+
+	footprintBytes, err := getFootprintBytes(entries, "")
+	require.NoError(t, err)
+	require.Equal(t, []protocol.FootprintEntryBytes{
+		{KeyXDR: keyB64, ReadBytes: 100, WriteBytes: 120},
+	}, footprintBytes)
+
+	footprintBytes, err = getFootprintBytes(entries, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Len(t, footprintBytes, 1)
+	require.Empty(t, footprintBytes[0].KeyXDR)
+	require.NotEmpty(t, footprintBytes[0].KeyJSON)
+	require.Equal(t, uint32(120), footprintBytes[0].WriteBytes)
+}
+
+func TestGetResourceUsage(t *testing.T) {
+	linearMemoryBytes := uint64(65536)
+	result := preflight.Preflight{
+		Result:            []byte{0, 0, 0, 1},
+		ContractEvents:    [][]byte{make([]byte, 40), make([]byte, 60)},
+		LinearMemoryBytes: &linearMemoryBytes,
+	}
+	require.Equal(t, &protocol.SimulationResourceUsage{
+		LinearMemoryBytes:   &linearMemoryBytes,
+		ContractEvents:      2,
+		ContractEventsBytes: 100,
+		ReturnValueBytes:    4,
+	}, getResourceUsage(result))
+
+	result.Error = "failed"
+	require.Nil(t, getResourceUsage(result))
+}
+
+func TestGetRentChanges(t *testing.T) {
+	var key xdr.LedgerKey
+	require.NoError(t, key.SetContractCode(xdr.Hash{0x1}))
+	keyB64, err := key.MarshalBinaryBase64()
+	require.NoError(t, err)
+	keyXDR, err := key.MarshalBinary()
+	require.NoError(t, err)
+	changes := []preflight.RentChange{{
+		Key: keyXDR, SizeBytes: 300, OldLiveUntilLedger: 10, NewLiveUntilLedger: 110, RentFee: 42,
+	}}
+
+	rentChanges, err := getRentChanges(changes, "")
+	require.NoError(t, err)
+	require.Equal(t, []protocol.RentChange{
+		{KeyXDR: keyB64, SizeBytes: 300, OldLiveUntilLedger: 10, NewLiveUntilLedger: 110, RentFee: 42},
+	}, rentChanges)
+
+	rentChanges, err = getRentChanges(changes, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Len(t, rentChanges, 1)
+	require.Empty(t, rentChanges[0].KeyXDR)
+	require.NotEmpty(t, rentChanges[0].KeyJSON)
+	require.Equal(t, int64(42), rentChanges[0].RentFee)
+}
+
+func TestGetRestoreStatuses(t *testing.T) {
+	var key xdr.LedgerKey
+	require.NoError(t, key.SetContractCode(xdr.Hash{0x1}))
+	keyB64, err := key.MarshalBinaryBase64()
+	require.NoError(t, err)
+	keyXDR, err := key.MarshalBinary()
+	require.NoError(t, err)
+	statuses := []preflight.RestoreKeyStatus{{Key: keyXDR, Status: protocol.RestoreStatusNotFound}}
+
+	restoreStatuses, err := getRestoreStatuses(statuses, "")
+	require.NoError(t, err)
+	require.Equal(t, []protocol.RestoreKeyStatus{
+		{KeyXDR: keyB64, Status: protocol.RestoreStatusNotFound},
+	}, restoreStatuses)
+
+	restoreStatuses, err = getRestoreStatuses(statuses, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Len(t, restoreStatuses, 1)
+	require.Empty(t, restoreStatuses[0].KeyXDR)
+	require.NotEmpty(t, restoreStatuses[0].KeyJSON)
+
+	restoreStatuses, err = getRestoreStatuses(nil, "")
+	require.NoError(t, err)
+	require.Nil(t, restoreStatuses)
+}
+
+func TestValidateAuthMode(t *testing.T) {
+	opBody := xdr.OperationBody{
+		Type:                 xdr.OperationTypeInvokeHostFunction,
+		InvokeHostFunctionOp: &xdr.InvokeHostFunctionOp{},
+	}
+	authMode := ""
+	require.NoError(t, validateAuthMode(opBody, &authMode))
+	// The preflight library decides whether to enforce or record
+	require.Equal(t, protocol.AuthModeAuto, authMode)
+
+	opBody.InvokeHostFunctionOp.Auth = []xdr.SorobanAuthorizationEntry{{}}
+	authMode = protocol.AuthModeAuto
+	require.NoError(t, validateAuthMode(opBody, &authMode))
+	authMode = protocol.AuthModeEnforceMockSignatures
+	require.NoError(t, validateAuthMode(opBody, &authMode))
+	authMode = protocol.AuthModeRecord
+	require.ErrorContains(t, validateAuthMode(opBody, &authMode), "with an auth footprint")
+	authMode = "bogus"
+	require.ErrorContains(t, validateAuthMode(opBody, &authMode), "auto")
+}
+
+func TestGetFeeBumpSimulation(t *testing.T) {
+	var key xdr.LedgerKey
+	require.NoError(t, key.SetContractCode(xdr.Hash{0x1}))
+	source := xdr.MustMuxedAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON")
+	declaredData := xdr.SorobanTransactionData{
+		Resources: xdr.SorobanResources{
+			Footprint:     xdr.LedgerFootprint{ReadOnly: []xdr.LedgerKey{key}},
+			Instructions:  1000,
+			DiskReadBytes: 100,
+		},
+		ResourceFee: 500,
+	}
+	innerTx := xdr.TransactionV1Envelope{
+		Tx: xdr.Transaction{
+			SourceAccount: source,
+			Operations: []xdr.Operation{{
+				Body: xdr.OperationBody{
+					Type: xdr.OperationTypeInvokeHostFunction,
+					InvokeHostFunctionOp: &xdr.InvokeHostFunctionOp{
+						HostFunction: xdr.HostFunction{
+							Type: xdr.HostFunctionTypeHostFunctionTypeUploadContractWasm,
+							Wasm: &[]byte{},
+						},
+					},
+				},
+			}},
+			Ext: xdr.TransactionExt{V: 1, SorobanData: &declaredData},
+		},
+	}
+	feeBumpEnvelope := xdr.TransactionEnvelope{
+		Type: xdr.EnvelopeTypeEnvelopeTypeTxFeeBump,
+		FeeBump: &xdr.FeeBumpTransactionEnvelope{
+			Tx: xdr.FeeBumpTransaction{
+				FeeSource: source,
+				Fee:       600,
+				InnerTx: xdr.FeeBumpTransactionInnerTx{
+					Type: xdr.EnvelopeTypeEnvelopeTypeTx,
+					V1:   &innerTx,
+				},
+			},
+		},
+	}
+	feeBumpB64, err := xdr.MarshalBase64(feeBumpEnvelope)
+	require.NoError(t, err)
+
+	txEnvelope, feeBump, err := getTransactionEnvelope(feeBumpB64)
+	require.NoError(t, err)
+	require.NotNil(t, feeBump)
+	require.Equal(t, xdr.EnvelopeTypeEnvelopeTypeTx, txEnvelope.Type)
+	require.Len(t, txEnvelope.Operations(), 1)
+
+	simulatedData := declaredData
+	simulatedData.Resources.Instructions = 2000
+	simulatedDataXDR, err := simulatedData.MarshalBinary()
+	require.NoError(t, err)
+	feeBumpSimulation, err := getFeeBumpSimulation(*feeBump, preflight.Preflight{
+		TransactionData: simulatedDataXDR,
+		MinFee:          400,
+	})
+	require.NoError(t, err)
+	// one operation plus the fee-bump, plus the declared resource fee
+	require.Equal(t, int64(2*minBaseFee+500), feeBumpSimulation.MinFee)
+	require.Equal(t, []string{
+		"instructions: 1000 declared, at least 2000 required",
+		"fee: 600 bid, at least 700 required",
+	}, feeBumpSimulation.ValidationErrors)
+}
+
+func TestGetRequestTransactionEnvelope(t *testing.T) {
+	invokeOp := xdr.InvokeHostFunctionOp{
+		HostFunction: xdr.HostFunction{
+			Type: xdr.HostFunctionTypeHostFunctionTypeUploadContractWasm,
+			Wasm: &[]byte{},
+		},
+	}
+	invokeOpB64, err := xdr.MarshalBase64(invokeOp)
+	require.NoError(t, err)
+	const address = "GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON"
+
+	txEnvelope, feeBump, err := getRequestTransactionEnvelope(protocol.SimulateTransactionRequest{
+		Operation:     invokeOpB64,
+		SourceAccount: address,
+	})
+	require.NoError(t, err)
+	require.Nil(t, feeBump)
+	require.Len(t, txEnvelope.Operations(), 1)
+	op := txEnvelope.Operations()[0]
+	require.Equal(t, invokeOp, op.Body.MustInvokeHostFunctionOp())
+	require.Equal(t, xdr.MustAddress(address), getSourceAccount(txEnvelope, op))
+
+	_, _, err = getRequestTransactionEnvelope(protocol.SimulateTransactionRequest{Operation: invokeOpB64})
+	require.ErrorContains(t, err, "sourceAccount is required")
+	_, _, err = getRequestTransactionEnvelope(protocol.SimulateTransactionRequest{
+		Transaction:   "AAAA",
+		Operation:     invokeOpB64,
+		SourceAccount: address,
+	})
+	require.ErrorContains(t, err, "cannot be both provided")
+}
+
+func TestGetPreconditionFailures(t *testing.T) {
+	minSeqNum := xdr.SequenceNumber(5)
+	txEnvelope := xdr.TransactionEnvelope{
+		Type: xdr.EnvelopeTypeEnvelopeTypeTx,
+		V1: &xdr.TransactionV1Envelope{
+			Tx: xdr.Transaction{
+				SourceAccount: xdr.MustMuxedAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON"),
+				SeqNum:        10,
+				Cond: xdr.Preconditions{
+					Type: xdr.PreconditionTypePrecondV2,
+					V2: &xdr.PreconditionsV2{
+						TimeBounds:      &xdr.TimeBounds{MinTime: 1000, MaxTime: 2000},
+						LedgerBounds:    &xdr.LedgerBounds{MinLedger: 100, MaxLedger: 200},
+						MinSeqNum:       &minSeqNum,
+						MinSeqAge:       60,
+						MinSeqLedgerGap: 10,
+					},
+				},
+			},
+		},
+	}
+
+	require.Empty(t, getBoundsPreconditionFailures(txEnvelope, 1500, 150))
+	failures := getBoundsPreconditionFailures(txEnvelope, 500, 200)
+	require.Equal(t, []protocol.PreconditionFailure{
+		{Code: protocol.PreconditionTooEarly, Message: "time bounds: valid from 1000, ledger closes at 500"},
+		{Code: protocol.PreconditionTooLate, Message: "ledger bounds: valid before ledger 200, next ledger is 200"},
+	}, failures)
+
+	account := xdr.AccountEntry{
+		SeqNum: 7,
+		Ext: xdr.AccountEntryExt{V: 1, V1: &xdr.AccountEntryExtensionV1{
+			Ext: xdr.AccountEntryExtensionV1Ext{V: 2, V2: &xdr.AccountEntryExtensionV2{
+				Ext: xdr.AccountEntryExtensionV2Ext{V: 3, V3: &xdr.AccountEntryExtensionV3{
+					SeqLedger: 140,
+					SeqTime:   1400,
+				}},
+			}},
+		}},
+	}
+	require.Empty(t, getSequencePreconditionFailures(txEnvelope, account, 1500, 150))
+
+	account.SeqNum = 10
+	failures = getSequencePreconditionFailures(txEnvelope, account, 1430, 145)
+	require.Equal(t, []protocol.PreconditionFailure{
+		{
+			Code:    protocol.PreconditionBadSeq,
+			Message: "sequence number 10 requires the one of the account to be in [5, 10), it is 10",
+		},
+		{
+			Code:    protocol.PreconditionBadMinSeqAgeOrGap,
+			Message: "minimum sequence age: 60 seconds required, the account sequence number is 30 seconds old",
+		},
+		{
+			Code:    protocol.PreconditionBadMinSeqAgeOrGap,
+			Message: "minimum sequence ledger gap: 10 ledgers required, the account sequence number is 5 ledgers old",
+		},
+	}, failures)
+}
+
+func TestAddTransactionEstimates(t *testing.T) {
+	var key xdr.LedgerKey
+	require.NoError(t, key.SetContractCode(xdr.Hash{0x1}))
+	transactionData := xdr.SorobanTransactionData{
+		Resources: xdr.SorobanResources{
+			Footprint: xdr.LedgerFootprint{ReadOnly: []xdr.LedgerKey{key}},
+		},
+		ResourceFee: 500,
+	}
+	transactionDataXDR, err := transactionData.MarshalBinary()
+	require.NoError(t, err)
+	txEnvelope := xdr.TransactionEnvelope{
+		Type: xdr.EnvelopeTypeEnvelopeTypeTx,
+		V1: &xdr.TransactionV1Envelope{
+			Tx: xdr.Transaction{
+				SourceAccount: xdr.MustMuxedAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON"),
+				Operations: []xdr.Operation{{
+					Body: xdr.OperationBody{
+						Type: xdr.OperationTypeInvokeHostFunction,
+						InvokeHostFunctionOp: &xdr.InvokeHostFunctionOp{
+							HostFunction: xdr.HostFunction{
+								Type: xdr.HostFunctionTypeHostFunctionTypeUploadContractWasm,
+								Wasm: &[]byte{},
+							},
+						},
+					},
+				}},
+			},
+		},
+	}
+	result := preflight.Preflight{TransactionData: transactionDataXDR, MinFee: 500}
+	feeWindows := &feewindow.FeeWindows{SorobanInclusionFeeWindow: feewindow.NewFeeWindow(10)}
+
+	var simResp protocol.SimulateTransactionResponse
+	require.NoError(t, addTransactionEstimates(&simResp, txEnvelope, nil, result, feeWindows))
+	// Without recent fees, the minimum inclusion fee is suggested
+	require.Equal(t, int64(minBaseFee+500), simResp.SuggestedFee)
+
+	assembled := *txEnvelope.V1
+	assembled.Tx.Ext = xdr.TransactionExt{V: 1, SorobanData: &transactionData}
+	assembled.Signatures = []xdr.DecoratedSignature{{Signature: make([]byte, ed25519SignatureSize)}}
+	assembledXDR, err := xdr.TransactionEnvelope{Type: xdr.EnvelopeTypeEnvelopeTypeTx, V1: &assembled}.MarshalBinary()
+	require.NoError(t, err)
+	require.Equal(t, uint32(len(assembledXDR)), simResp.TransactionSize)
+
+	// The envelope of the request is left untouched
+	require.Empty(t, txEnvelope.V1.Signatures)
+	require.Zero(t, txEnvelope.V1.Tx.Ext.V)
+}
+
+func TestGetCallTrace(t *testing.T) {
+	arg := xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: (*xdr.Uint32)(new(uint32))}
+	argB64, err := xdr.MarshalBase64(arg)
+	require.NoError(t, err)
+	scError := xdr.ScError{Type: xdr.ScErrorTypeSceContext, Code: (*xdr.ScErrorCode)(new(int32))}
+	scErrorB64, err := xdr.MarshalBase64(scError)
+	require.NoError(t, err)
+	frames := []*preflight.CallFrame{{
+		ContractID:  xdr.ContractId{0x1},
+		Function:    "outer",
+		Args:        []xdr.ScVal{arg},
+		ReturnValue: &arg,
+		SubCalls: []*preflight.CallFrame{{
+			ContractID: xdr.ContractId{0x2},
+			Function:   "inner",
+			Trap:       &preflight.Trap{Error: scError, Message: "boom"},
+		}},
+	}}
+
+	callTrace, err := getCallTrace(frames, "")
+	require.NoError(t, err)
+	require.Len(t, callTrace, 1)
+	require.Equal(t, "outer", callTrace[0].Function)
+	contractAddress := strkey.MustEncode(strkey.VersionByteContract, frames[0].ContractID[:])
+	require.Equal(t, contractAddress, callTrace[0].ContractAddress)
+	require.Equal(t, []string{argB64}, callTrace[0].ArgsXDR)
+	require.Equal(t, argB64, callTrace[0].ReturnValueXDR)
+	require.Nil(t, callTrace[0].Trap)
+	require.Len(t, callTrace[0].SubCalls, 1)
+	require.Empty(t, callTrace[0].SubCalls[0].ReturnValueXDR)
+	require.Equal(t, &protocol.CallTrap{ErrorXDR: scErrorB64, Message: "boom"}, callTrace[0].SubCalls[0].Trap)
+
+	callTrace, err = getCallTrace(frames, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Empty(t, callTrace[0].ArgsXDR)
+	require.Len(t, callTrace[0].ArgsJSON, 1)
+	require.NotEmpty(t, callTrace[0].ReturnValueJSON)
+	require.NotEmpty(t, callTrace[0].SubCalls[0].Trap.ErrorJSON)
+}
+
+func TestGetErrorDetails(t *testing.T) {
+	details, err := getErrorDetails(nil, "")
+	require.NoError(t, err)
+	require.Nil(t, details)
+
+	scError := xdr.ScError{Type: xdr.ScErrorTypeSceBudget, Code: (*xdr.ScErrorCode)(new(int32))}
+	scErrorXDR, err := scError.MarshalBinary()
+	require.NoError(t, err)
+	contractID := xdr.ContractId{0x1}
+	preflightDetails := &preflight.ErrorDetails{
+		Category:   protocol.ErrorCategoryBudget,
+		Error:      scErrorXDR,
+		ContractID: contractID[:],
+	}
+
+	details, err = getErrorDetails(preflightDetails, "")
+	require.NoError(t, err)
+	require.Equal(t, &protocol.SimulationErrorDetails{
+		Category:        protocol.ErrorCategoryBudget,
+		ErrorXDR:        base64.StdEncoding.EncodeToString(scErrorXDR),
+		ContractAddress: strkey.MustEncode(strkey.VersionByteContract, contractID[:]),
+	}, details)
+
+	details, err = getErrorDetails(preflightDetails, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Empty(t, details.ErrorXDR)
+	require.NotEmpty(t, details.ErrorJSON)
+
+	details, err = getErrorDetails(&preflight.ErrorDetails{Category: protocol.ErrorCategoryStorage}, "")
+	require.NoError(t, err)
+	require.Equal(t, &protocol.SimulationErrorDetails{Category: protocol.ErrorCategoryStorage}, details)
+}
+
+func TestGetWasmDiagnostics(t *testing.T) {
+	arg := xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: (*xdr.Uint32)(new(uint32))}
+	argB64, err := xdr.MarshalBase64(arg)
+	require.NoError(t, err)
+	scError := xdr.ScError{Type: xdr.ScErrorTypeSceWasmVm, Code: (*xdr.ScErrorCode)(new(int32))}
+	scErrorB64, err := xdr.MarshalBase64(scError)
+	require.NoError(t, err)
+	diagnostics := []preflight.WasmDiagnostic{{
+		Kind:    protocol.WasmDiagnosticMemoryLimits,
+		Error:   scError,
+		Message: "unsupported memory size",
+		Args:    []xdr.ScVal{arg},
+	}}
+
+	result, err := getWasmDiagnostics(diagnostics, "")
+	require.NoError(t, err)
+	require.Equal(t, []protocol.WasmDiagnostic{{
+		Kind:     protocol.WasmDiagnosticMemoryLimits,
+		Message:  "unsupported memory size",
+		ErrorXDR: scErrorB64,
+		ArgsXDR:  []string{argB64},
+	}}, result)
+
+	result, err = getWasmDiagnostics(diagnostics, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Empty(t, result[0].ErrorXDR)
+	require.NotEmpty(t, result[0].ErrorJSON)
+	require.Len(t, result[0].ArgsJSON, 1)
+}
+
+func TestGetRequiredSigners(t *testing.T) {
+	accountID := xdr.MustAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON")
+	contractID := xdr.ContractId{0xa, 0xb, 0xc}
+	addressAuth := func(address xdr.ScAddress, expirationLedger uint32) []byte {
+		auth := xdr.SorobanAuthorizationEntry{
+			Credentials: xdr.SorobanCredentials{
+				Type: xdr.SorobanCredentialsTypeSorobanCredentialsAddress,
+				Address: &xdr.SorobanAddressCredentials{
+					Address:                   address,
+					SignatureExpirationLedger: xdr.Uint32(expirationLedger),
+					Signature:                 xdr.ScVal{Type: xdr.ScValTypeScvVoid},
+				},
+			},
+		}
+		authXDR, err := auth.MarshalBinary()
+		require.NoError(t, err)
+		return authXDR
+	}
+	account := xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeAccount, AccountId: &accountID}
+	contract := xdr.ScAddress{Type: xdr.ScAddressTypeScAddressTypeContract, ContractId: &contractID}
+	sourceAuth, err := xdr.SorobanAuthorizationEntry{
+		Credentials: xdr.SorobanCredentials{Type: xdr.SorobanCredentialsTypeSorobanCredentialsSourceAccount},
+	}.MarshalBinary()
+	require.NoError(t, err)
+
+	signers, err := getRequiredSigners([][]byte{
+		addressAuth(account, 0),
+		sourceAuth,
+		addressAuth(contract, 500),
+		addressAuth(account, 150),
+	}, 100)
+	require.NoError(t, err)
+	require.Equal(t, []protocol.RequiredSigner{
+		{Address: accountID.Address(), SignatureExpirationLedger: 150},
+		{
+			Address:                   strkey.MustEncode(strkey.VersionByteContract, contractID[:]),
+			SignatureExpirationLedger: 500,
+		},
+	}, signers)
+}
+
+func TestGetRestorePreamble(t *testing.T) {
+	var key xdr.LedgerKey
+	require.NoError(t, key.SetContractCode(xdr.Hash{0x1}))
+	keyB64, err := key.MarshalBinaryBase64()
+	require.NoError(t, err)
+	txDataXDR, err := xdr.SorobanTransactionData{
+		Resources: xdr.SorobanResources{
+			Footprint: xdr.LedgerFootprint{ReadWrite: []xdr.LedgerKey{key}},
+		},
+	}.MarshalBinary()
+	require.NoError(t, err)
+	result := preflight.Preflight{PreRestoreTransactionData: txDataXDR, PreRestoreMinFee: 100}
+
+	restorePreamble, err := getRestorePreamble(result, "")
+	require.NoError(t, err)
+	require.Equal(t, &protocol.RestorePreamble{
+		TransactionDataXDR: base64.StdEncoding.EncodeToString(txDataXDR),
+		MinResourceFee:     100,
+		ArchivedKeysXDR:    []string{keyB64},
+	}, restorePreamble)
+
+	restorePreamble, err = getRestorePreamble(result, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Empty(t, restorePreamble.ArchivedKeysXDR)
+	require.Len(t, restorePreamble.ArchivedKeysJSON, 1)
+}
+
+func TestGetConfigUpgradeSet(t *testing.T) {
+	upgradeSet, err := getConfigUpgradeSet("")
+	require.NoError(t, err)
+	require.Nil(t, upgradeSet)
+
+	maxSizeBytes := xdr.Uint32(1000)
+	setting := xdr.ConfigSettingEntry{
+		ConfigSettingId:      xdr.ConfigSettingIdConfigSettingContractMaxSizeBytes,
+		ContractMaxSizeBytes: &maxSizeBytes,
+	}
+	upgradeSetB64, err := xdr.MarshalBase64(xdr.ConfigUpgradeSet{UpdatedEntry: []xdr.ConfigSettingEntry{setting}})
+	require.NoError(t, err)
+	upgradeSet, err = getConfigUpgradeSet(upgradeSetB64)
+	require.NoError(t, err)
+	require.Equal(t, []xdr.ConfigSettingEntry{setting}, upgradeSet.UpdatedEntry)
+
+	upgradeSetB64, err = xdr.MarshalBase64(xdr.ConfigUpgradeSet{UpdatedEntry: []xdr.ConfigSettingEntry{setting, setting}})
+	require.NoError(t, err)
+	_, err = getConfigUpgradeSet(upgradeSetB64)
+	require.ErrorContains(t, err, "duplicate config setting")
+
+	_, err = getConfigUpgradeSet("not base64")
+	require.Error(t, err)
+}
+
+func TestValidateLedgerOverride(t *testing.T) {
+	limits := LedgerOverrideLimits{MaxLedgerSkew: 10, MaxTimeSkew: time.Hour}
+	require.NoError(t, validateLedgerOverride(nil, LedgerOverrideLimits{}, 100))
+	require.NoError(t, validateLedgerOverride(&protocol.LedgerOverride{Sequence: 110}, limits, 100))
+	require.NoError(t, validateLedgerOverride(&protocol.LedgerOverride{Sequence: 90}, limits, 100))
+	require.EqualError(t,
+		validateLedgerOverride(&protocol.LedgerOverride{Sequence: 111}, limits, 100),
+		"ledgerOverride.sequence is 11 ledgers away from the simulated ledger (at most 10 allowed)")
+	require.Error(t, validateLedgerOverride(&protocol.LedgerOverride{Sequence: 101}, LedgerOverrideLimits{}, 100))
+
+	now := time.Now()
+	require.NoError(t, validateLedgerOverride(
+		&protocol.LedgerOverride{Timestamp: now.Add(-time.Minute).Unix()}, limits, 100))
+	require.EqualError(t,
+		validateLedgerOverride(&protocol.LedgerOverride{Timestamp: now.Add(2 * time.Hour).Unix()}, limits, 100),
+		"ledgerOverride.timestamp is more than 1h0m0s away from the current time")
+	require.Error(t, validateLedgerOverride(&protocol.LedgerOverride{Timestamp: -1}, limits, 100))
+}
+
+func TestGetResourceFeeBreakdown(t *testing.T) {
+	require.Nil(t, getResourceFeeBreakdown(preflight.Preflight{}))
+
+	breakdown := &preflight.FeeBreakdown{Instructions: 100, ReadEntries: 20, WriteBytes: 30, Rent: 1000}
+	require.Nil(t, getResourceFeeBreakdown(preflight.Preflight{Error: "failed", FeeBreakdown: breakdown}))
+
+	result := getResourceFeeBreakdown(preflight.Preflight{FeeBreakdown: breakdown})
+	require.NotNil(t, result)
+	encoded, err := json.Marshal(result)
+	require.NoError(t, err)
+	require.JSONEq(t, `{
+		"instructions": "100",
+		"readEntries": "20",
+		"writeEntries": "0",
+		"readBytes": "0",
+		"writeBytes": "30",
+		"historical": "0",
+		"transactionSize": "0",
+		"events": "0",
+		"rent": "1000"
+	}`, string(encoded))
+}
@@ -0,0 +1,143 @@
+package methods
+
+import (
+	"context"
+	"fmt"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/feewindow"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/preflight"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// batchSimulator simulates transactions against the same ledger, returning a
+// response per transaction in the same order.
+type batchSimulator func(
+	ctx context.Context, requests []protocol.SimulateTransactionRequest,
+) []protocol.SimulateTransactionResponse
+
+// SimulateTransactions simulates the transactions of the request, all of them
+// against the state of the same ledger so that their results are consistent
+// with each other.
+func SimulateTransactions(
+	ctx context.Context,
+	ledgerReader db.LedgerReader,
+	snapshotLedgers uint32,
+	maxTransactions uint,
+	request protocol.SimulateTransactionsRequest,
+	simulate batchSimulator,
+) (protocol.SimulateTransactionsResponse, error) {
+	if len(request.Transactions) == 0 {
+		return protocol.SimulateTransactionsResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: "transactions must not be empty",
+		}
+	}
+	if uint(len(request.Transactions)) > maxTransactions {
+		return protocol.SimulateTransactionsResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: fmt.Sprintf("at most %d transactions can be simulated at once", maxTransactions),
+		}
+	}
+	if err := protocol.IsValidFormat(request.Format); err != nil {
+		return protocol.SimulateTransactionsResponse{}, &jrpc2.Error{
+			Code:    jrpc2.InvalidParams,
+			Message: err.Error(),
+		}
+	}
+	// Pinning the ledger keeps the later transactions from being simulated
+	// against a newer ledger closed meanwhile
+	latestLedger, simulationLedger, err := getSimulationLedger(ctx, ledgerReader, request.Ledger, snapshotLedgers)
+	if err != nil {
+		return protocol.SimulateTransactionsResponse{}, err
+	}
+
+	requests := make([]protocol.SimulateTransactionRequest, len(request.Transactions))
+	for i, transaction := range request.Transactions {
+		requests[i] = protocol.SimulateTransactionRequest{
+			Transaction:    transaction,
+			ResourceConfig: request.ResourceConfig,
+			AuthMode:       request.AuthMode,
+			Format:         request.Format,
+			Ledger:         simulationLedger,
+		}
+	}
+	response := protocol.SimulateTransactionsResponse{
+		Results:      simulate(ctx, requests),
+		Ledger:       simulationLedger,
+		LatestLedger: latestLedger,
+	}
+	for _, result := range response.Results {
+		response.LatestLedger = max(response.LatestLedger, result.LatestLedger)
+	}
+	return response, nil
+}
+
+// simulateBatch runs the preflights of the InvokeHostFunction transactions
+// as a single batch, sharing one snapshot of the ledger, and the ones of the
+// other transactions (which can't be batched) in turn.
+func simulateBatch(ctx context.Context, logger *log.Entry,
+	ledgerReader db.LedgerReader,
+	coreClient interfaces.FastCoreClient, getter PreflightGetter, feeWindows *feewindow.FeeWindows,
+	overrideLimits LedgerOverrideLimits, snapshotLedgers uint32, requests []protocol.SimulateTransactionRequest,
+) []protocol.SimulateTransactionResponse {
+	responses := make([]protocol.SimulateTransactionResponse, len(requests))
+	var batch []*preparedSimulation
+	var batchIndexes []int
+	for i, request := range requests {
+		simulation, failure := prepareSimulation(ctx, logger, ledgerReader, coreClient, overrideLimits,
+			snapshotLedgers, request)
+		switch {
+		case simulation == nil:
+			responses[i] = failure
+		case simulation.params.OperationBody.Type == xdr.OperationTypeInvokeHostFunction &&
+			simulation.params.ResourceConfig.ExtraFootprint == "":
+			batch = append(batch, simulation)
+			batchIndexes = append(batchIndexes, i)
+		default:
+			result, err := getter.GetPreflight(ctx, simulation.params)
+			responses[i] = simulation.respond(ctx, ledgerReader, feeWindows, result, err)
+		}
+	}
+	if len(batch) == 0 {
+		return responses
+	}
+
+	params := make([]preflight.GetterParameters, len(batch))
+	for i, simulation := range batch {
+		params[i] = simulation.params
+	}
+	results, err := getter.GetPreflights(ctx, params)
+	for i, simulation := range batch {
+		var result preflight.Preflight
+		if err == nil {
+			result = results[i]
+		}
+		responses[batchIndexes[i]] = simulation.respond(ctx, ledgerReader, feeWindows, result, err)
+	}
+	return responses
+}
+
+// NewSimulateTransactionsHandler returns a JSON rpc handler simulating several
+// transactions against the same ledger
+func NewSimulateTransactionsHandler(logger *log.Entry,
+	ledgerReader db.LedgerReader,
+	coreClient interfaces.FastCoreClient, getter PreflightGetter, feeWindows *feewindow.FeeWindows,
+	overrideLimits LedgerOverrideLimits, snapshotLedgers uint32, maxTransactions uint,
+) jrpc2.Handler {
+	simulate := func(ctx context.Context, requests []protocol.SimulateTransactionRequest,
+	) []protocol.SimulateTransactionResponse {
+		return simulateBatch(ctx, logger, ledgerReader, coreClient, getter, feeWindows, overrideLimits,
+			snapshotLedgers, requests)
+	}
+	return NewHandler(func(ctx context.Context, request protocol.SimulateTransactionsRequest,
+	) (protocol.SimulateTransactionsResponse, error) {
+		return SimulateTransactions(ctx, ledgerReader, snapshotLedgers, maxTransactions, request, simulate)
+	})
+}
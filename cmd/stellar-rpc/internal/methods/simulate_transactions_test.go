@@ -0,0 +1,60 @@
+package methods
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestSimulateTransactions(t *testing.T) {
+	ctx := context.TODO()
+	store := db.NewMockTransactionStore("passphrase")
+	require.NoError(t, store.InsertTransactions(txMeta(1, true)))
+	ledgerReader := db.NewMockLedgerReader(store)
+	var requests []protocol.SimulateTransactionRequest
+	simulate := func(_ context.Context, batch []protocol.SimulateTransactionRequest,
+	) []protocol.SimulateTransactionResponse {
+		requests = append(requests, batch...)
+		responses := make([]protocol.SimulateTransactionResponse, len(batch))
+		for i, request := range batch {
+			responses[i] = protocol.SimulateTransactionResponse{Error: "failed " + request.Transaction, LatestLedger: 102}
+		}
+		return responses
+	}
+
+	_, err := SimulateTransactions(ctx, ledgerReader, 4, 2, protocol.SimulateTransactionsRequest{}, simulate)
+	require.EqualError(t, err, "[-32602] transactions must not be empty")
+	_, err = SimulateTransactions(ctx, ledgerReader, 4, 2, protocol.SimulateTransactionsRequest{
+		Transactions: []string{"a", "b", "c"},
+	}, simulate)
+	require.EqualError(t, err, "[-32602] at most 2 transactions can be simulated at once")
+	_, err = SimulateTransactions(ctx, ledgerReader, 4, 2, protocol.SimulateTransactionsRequest{
+		Transactions: []string{"a"},
+		Ledger:       200,
+	}, simulate)
+	require.ErrorContains(t, err, "[-32602] ledger must be between 98 and the latest ledger: 101")
+	require.Empty(t, requests)
+
+	response, err := SimulateTransactions(ctx, ledgerReader, 4, 2, protocol.SimulateTransactionsRequest{
+		Transactions: []string{"a", "b"},
+		AuthMode:     protocol.AuthModeRecord,
+		Ledger:       101,
+	}, simulate)
+	require.NoError(t, err)
+	require.Equal(t, []protocol.SimulateTransactionRequest{
+		{Transaction: "a", AuthMode: protocol.AuthModeRecord, Ledger: 101},
+		{Transaction: "b", AuthMode: protocol.AuthModeRecord, Ledger: 101},
+	}, requests)
+	require.Equal(t, protocol.SimulateTransactionsResponse{
+		Results: []protocol.SimulateTransactionResponse{
+			{Error: "failed a", LatestLedger: 102},
+			{Error: "failed b", LatestLedger: 102},
+		},
+		Ledger:       101,
+		LatestLedger: 102,
+	}, response)
+}
@@ -0,0 +1,201 @@
+package methods
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net/http"
+	"strconv"
+	"strings"
+	"time"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/support/log"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// lastEventIDHeader is sent by Server-Sent Events clients reconnecting to a
+// stream, with the id of the last message they received.
+const lastEventIDHeader = "Last-Event-ID"
+
+type eventStreamHandler struct {
+	events       eventsRPCHandler
+	pollInterval time.Duration
+	done         <-chan struct{}
+}
+
+// NewStreamEventsHandler returns an HTTP handler streaming the events matching
+// the filters of its request as Server-Sent Events, as the ledgers containing
+// them are ingested.
+//
+// The stream is requested through the query parameters `startLedger` or
+// `cursor`, `filters` (the JSON encoded filters of getEvents) and `xdrFormat`.
+// The id of every message is a cursor, which clients resume the stream from by
+// passing it in the `Last-Event-ID` header (as done by EventSource when
+// reconnecting) or the `cursor` parameter. Messages carrying only an id are
+// sent after every scan of the latest ledgers, so that resuming doesn't rescan
+// the ledgers without matching events (and which keep idle streams alive).
+// Streams ending on an error send it in an `error` message.
+//
+// Streams end once done is closed.
+func NewStreamEventsHandler(
+	logger *log.Entry,
+	dbReader db.EventReader,
+	ledgerReader db.LedgerReader,
+	maxLimit uint,
+	maxContractIDs uint,
+	networkPassphrase string,
+	pollInterval time.Duration,
+	done <-chan struct{},
+) http.Handler {
+	return eventStreamHandler{
+		events: eventsRPCHandler{
+			dbReader:          dbReader,
+			maxLimit:          maxLimit,
+			defaultLimit:      maxLimit,
+			maxContractIDs:    maxContractIDs,
+			logger:            logger,
+			ledgerReader:      ledgerReader,
+			networkPassphrase: networkPassphrase,
+		},
+		pollInterval: pollInterval,
+		done:         done,
+	}
+}
+
+func parseStreamEventsRequest(r *http.Request) (protocol.GetEventsRequest, error) {
+	query := r.URL.Query()
+	request := protocol.GetEventsRequest{Format: query.Get("xdrFormat")}
+	if filters := query.Get("filters"); filters != "" {
+		if err := json.Unmarshal([]byte(filters), &request.Filters); err != nil {
+			return protocol.GetEventsRequest{}, fmt.Errorf("invalid filters: %w", err)
+		}
+	}
+	cursor := query.Get("cursor")
+	if lastEventID := r.Header.Get(lastEventIDHeader); lastEventID != "" {
+		cursor = lastEventID
+	}
+	if cursor != "" {
+		parsed, err := protocol.ParseCursor(cursor)
+		if err != nil {
+			return protocol.GetEventsRequest{}, err
+		}
+		request.Pagination = &protocol.PaginationOptions{Cursor: &parsed}
+		return request, nil
+	}
+	startLedger, err := strconv.ParseUint(query.Get("startLedger"), 10, 32)
+	if err != nil {
+		return protocol.GetEventsRequest{}, fmt.Errorf("invalid startLedger: %w", err)
+	}
+	request.StartLedger = uint32(startLedger)
+	return request, nil
+}
+
+func (h eventStreamHandler) ServeHTTP(w http.ResponseWriter, r *http.Request) {
+	flusher, ok := w.(http.Flusher)
+	if !ok {
+		http.Error(w, "streaming is not supported", http.StatusInternalServerError)
+		return
+	}
+	request, err := parseStreamEventsRequest(r)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+	// Validate the request, and that its start is within the ledger range,
+	// before committing to a stream
+	response, err := h.events.getEvents(r.Context(), request)
+	if err != nil {
+		status := http.StatusBadRequest
+		var jsonRPCErr *jrpc2.Error
+		if errors.As(err, &jsonRPCErr) && jsonRPCErr.Code == jrpc2.InternalError {
+			status = http.StatusInternalServerError
+		}
+		http.Error(w, eventStreamErrorMessage(err), status)
+		return
+	}
+
+	w.Header().Set("Content-Type", "text/event-stream")
+	w.Header().Set("Cache-Control", "no-cache")
+	w.WriteHeader(http.StatusOK)
+
+	ticker := time.NewTicker(h.pollInterval)
+	defer ticker.Stop()
+	for {
+		if err := writeEventStreamMessages(w, response); err != nil {
+			h.events.logger.WithError(err).Debug("could not write to event stream")
+			return
+		}
+		flusher.Flush()
+
+		cursor, err := protocol.ParseCursor(response.Cursor)
+		if err != nil {
+			writeEventStreamError(w, err)
+			return
+		}
+		request.StartLedger = 0
+		request.Pagination = &protocol.PaginationOptions{Cursor: &cursor}
+
+		// Keep going right away while there are events to catch up with
+		if !response.HasMore {
+			select {
+			case <-r.Context().Done():
+				return
+			case <-h.done:
+				return
+			case <-ticker.C:
+			}
+		}
+
+		response, err = h.events.getEvents(r.Context(), request)
+		if err != nil {
+			if r.Context().Err() == nil {
+				writeEventStreamError(w, err)
+			}
+			return
+		}
+	}
+}
+
+func writeEventStreamMessages(w http.ResponseWriter, response protocol.GetEventsResponse) error {
+	var b strings.Builder
+	lastID := ""
+	for _, event := range response.Events {
+		data, err := json.Marshal(event)
+		if err != nil {
+			return err
+		}
+		fmt.Fprintf(&b, "id: %s\nevent: event\ndata: %s\n\n", event.ID, data)
+		lastID = event.ID
+	}
+	// Checkpoint the end of the scanned ledgers, unless it is the last event
+	checkpoint, err := protocol.ParseCursor(response.Cursor)
+	if err != nil {
+		return err
+	}
+	if checkpoint.String() != lastID {
+		fmt.Fprintf(&b, "id: %s\n\n", response.Cursor)
+	}
+	_, err = w.Write([]byte(b.String()))
+	return err
+}
+
+func eventStreamErrorMessage(err error) string {
+	var jsonRPCErr *jrpc2.Error
+	if errors.As(err, &jsonRPCErr) {
+		return jsonRPCErr.Message
+	}
+	return err.Error()
+}
+
+func writeEventStreamError(w http.ResponseWriter, err error) {
+	message := strings.ReplaceAll(eventStreamErrorMessage(err), "\n", " ")
+	//nolint:errcheck // the stream ends right after
+	fmt.Fprintf(w, "event: error\ndata: %s\n\n", message)
+	if flusher, ok := w.(http.Flusher); ok {
+		flusher.Flush()
+	}
+}
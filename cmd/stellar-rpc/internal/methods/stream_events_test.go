@@ -0,0 +1,87 @@
+package methods
+
+import (
+	"context"
+	"net/http"
+	"net/http/httptest"
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/support/log"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/daemon/interfaces"
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestStreamEvents(t *testing.T) {
+	dbx := newTestDB(t)
+	ctx := context.TODO()
+	logger := log.DefaultLogger
+
+	writer := db.NewReadWriter(logger, dbx, interfaces.MakeNoOpDeamon(), 10, 10, 10, 10, passphrase)
+	write, err := writer.NewTx(ctx)
+	require.NoError(t, err)
+	ledgerW, eventW := write.LedgerWriter(), write.EventWriter()
+
+	counter := xdr.ScSymbol("COUNTER")
+	counterScVal := xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &counter}
+	contractID := xdr.ContractId([32]byte{})
+	ledgerCloseMeta := ledgerCloseMetaWithEvents(2, time.Now().Unix(),
+		transactionMetaWithEvents(contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)),
+		transactionMetaWithEvents(contractEvent(contractID, xdr.ScVec{counterScVal}, counterScVal)),
+	)
+	require.NoError(t, ledgerW.InsertLedger(ledgerCloseMeta))
+	require.NoError(t, eventW.InsertEvents(ledgerCloseMeta))
+	require.NoError(t, write.Commit(ledgerCloseMeta))
+
+	// Streams end right after catching up with the latest ledger
+	done := make(chan struct{})
+	close(done)
+	handler := NewStreamEventsHandler(logger, db.NewEventReader(logger, dbx, passphrase),
+		db.NewLedgerReader(dbx), 100, protocol.MaxContractIDsLimit, passphrase, time.Second, done)
+
+	firstID := protocol.Cursor{Ledger: 2, Tx: 1, Op: 0, Event: 0}.String()
+	secondID := protocol.Cursor{Ledger: 2, Tx: 2, Op: 0, Event: 0}.String()
+	checkpoint := protocol.MaxCursor
+	checkpoint.Ledger = 2
+
+	recorder := httptest.NewRecorder()
+	handler.ServeHTTP(recorder, httptest.NewRequest(http.MethodGet, "/events?startLedger=2", nil))
+	require.Equal(t, http.StatusOK, recorder.Code)
+	assert.Equal(t, "text/event-stream", recorder.Header().Get("Content-Type"))
+	body := recorder.Body.String()
+	assert.Contains(t, body, "id: "+firstID+"\nevent: event\ndata: {")
+	assert.Contains(t, body, "id: "+secondID+"\nevent: event\ndata: {")
+	assert.True(t, strings.HasSuffix(body, "id: "+checkpoint.Encode()+"\n\n"))
+
+	// Resuming from the last acknowledged event
+	request := httptest.NewRequest(http.MethodGet, "/events?startLedger=2", nil)
+	request.Header.Set("Last-Event-ID", firstID)
+	recorder = httptest.NewRecorder()
+	handler.ServeHTTP(recorder, request)
+	require.Equal(t, http.StatusOK, recorder.Code)
+	body = recorder.Body.String()
+	assert.NotContains(t, body, "id: "+firstID)
+	assert.Contains(t, body, "id: "+secondID)
+
+	// Resuming from a checkpoint
+	recorder = httptest.NewRecorder()
+	handler.ServeHTTP(recorder, httptest.NewRequest(http.MethodGet, "/events?cursor="+checkpoint.String(), nil))
+	require.Equal(t, http.StatusOK, recorder.Code)
+	assert.NotContains(t, recorder.Body.String(), "event: event")
+
+	recorder = httptest.NewRecorder()
+	handler.ServeHTTP(recorder, httptest.NewRequest(http.MethodGet, "/events?startLedger=3", nil))
+	assert.Equal(t, http.StatusBadRequest, recorder.Code)
+	assert.Contains(t, recorder.Body.String(), "startLedger must be within the ledger range: 2 - 2")
+
+	recorder = httptest.NewRecorder()
+	handler.ServeHTTP(recorder, httptest.NewRequest(http.MethodGet, "/events", nil))
+	assert.Equal(t, http.StatusBadRequest, recorder.Code)
+}
@@ -0,0 +1,100 @@
+package methods
+
+import (
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// coreMetricsTopic is the first topic of the diagnostic events by which core
+// reports the resources used by Soroban transactions (the second topic being
+// the name of the metric).
+const coreMetricsTopic = "core_metrics"
+
+// transactionResourceUsage compares the resources declared by a Soroban
+// transaction with the ones reported by its core metrics events, if any. The
+// read bytes are declared as the bytes read from disk, but include all the
+// ledger bytes read when used.
+func transactionResourceUsage(tx db.Transaction) ([]protocol.TransactionResourceUsage, error) {
+	var envelope xdr.TransactionEnvelope
+	if err := xdr.SafeUnmarshal(tx.Envelope, &envelope); err != nil {
+		return nil, err
+	}
+	declared, ok := envelopeSorobanData(envelope)
+	if !ok {
+		return nil, nil
+	}
+	metrics, err := coreMetrics(tx.Events)
+	if err != nil || len(metrics) == 0 {
+		return nil, err
+	}
+
+	resources := declared.Resources
+	footprint := resources.Footprint
+	return []protocol.TransactionResourceUsage{
+		{
+			Name:     "instructions",
+			Declared: int64(resources.Instructions),
+			Used:     metrics["cpu_insn"],
+		},
+		{
+			Name:     "readEntries",
+			Declared: int64(len(footprint.ReadOnly) + len(footprint.ReadWrite)),
+			Used:     metrics["read_entry"],
+		},
+		{
+			Name:     "writeEntries",
+			Declared: int64(len(footprint.ReadWrite)),
+			Used:     metrics["write_entry"],
+		},
+		{
+			Name:     "readBytes",
+			Declared: int64(resources.DiskReadBytes),
+			Used:     metrics["ledger_read_byte"],
+		},
+		{
+			Name:     "writeBytes",
+			Declared: int64(resources.WriteBytes),
+			Used:     metrics["ledger_write_byte"],
+		},
+	}, nil
+}
+
+// envelopeSorobanData returns the Soroban data of a transaction (of the inner
+// transaction, for fee-bump transactions).
+func envelopeSorobanData(envelope xdr.TransactionEnvelope) (xdr.SorobanTransactionData, bool) {
+	tx := envelope.V1
+	if envelope.IsFeeBump() {
+		tx = envelope.FeeBump.Tx.InnerTx.V1
+	}
+	if tx == nil {
+		return xdr.SorobanTransactionData{}, false
+	}
+	return tx.Tx.Ext.GetSorobanData()
+}
+
+// coreMetrics returns the values of the core metrics reported among the
+// (XDR-encoded) diagnostic events of a transaction, by name.
+func coreMetrics(events [][]byte) (map[string]int64, error) {
+	metrics := map[string]int64{}
+	for _, eventXDR := range events {
+		var event xdr.DiagnosticEvent
+		if err := xdr.SafeUnmarshal(eventXDR, &event); err != nil {
+			return nil, err
+		}
+		v0, ok := event.Event.Body.GetV0()
+		if !ok || len(v0.Topics) != 2 {
+			continue
+		}
+		if topic, ok := v0.Topics[0].GetSym(); !ok || topic != coreMetricsTopic {
+			continue
+		}
+		name, nameOk := v0.Topics[1].GetSym()
+		value, valueOk := v0.Data.GetU64()
+		if nameOk && valueOk {
+			metrics[string(name)] = int64(value) //nolint:gosec
+		}
+	}
+	return metrics, nil
+}
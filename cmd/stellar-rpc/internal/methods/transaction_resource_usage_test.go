@@ -0,0 +1,71 @@
+package methods
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestTransactionResourceUsage(t *testing.T) {
+	envelope := txEnvelope(1)
+	envelope.V1.Tx.Ext.SorobanData = &xdr.SorobanTransactionData{
+		Resources: xdr.SorobanResources{
+			Footprint: xdr.LedgerFootprint{
+				ReadOnly:  []xdr.LedgerKey{{}, {}},
+				ReadWrite: []xdr.LedgerKey{{}},
+			},
+			Instructions:  1_000_000,
+			DiskReadBytes: 1000,
+			WriteBytes:    500,
+		},
+	}
+	envelopeXDR, err := envelope.MarshalBinary()
+	require.NoError(t, err)
+
+	metricEvent := func(name string, value uint64) []byte {
+		metricsTopic, metricName, metricValue := xdr.ScSymbol(coreMetricsTopic), xdr.ScSymbol(name), xdr.Uint64(value)
+		event := xdr.DiagnosticEvent{Event: xdr.ContractEvent{
+			Type: xdr.ContractEventTypeDiagnostic,
+			Body: xdr.ContractEventBody{V: 0, V0: &xdr.ContractEventV0{
+				Topics: []xdr.ScVal{
+					{Type: xdr.ScValTypeScvSymbol, Sym: &metricsTopic},
+					{Type: xdr.ScValTypeScvSymbol, Sym: &metricName},
+				},
+				Data: xdr.ScVal{Type: xdr.ScValTypeScvU64, U64: &metricValue},
+			}},
+		}}
+		eventXDR, err := event.MarshalBinary()
+		require.NoError(t, err)
+		return eventXDR
+	}
+
+	// Without metrics events, the used resources are unknown
+	usage, err := transactionResourceUsage(db.Transaction{Envelope: envelopeXDR})
+	require.NoError(t, err)
+	assert.Nil(t, usage)
+
+	usage, err = transactionResourceUsage(db.Transaction{
+		Envelope: envelopeXDR,
+		Events: [][]byte{
+			metricEvent("cpu_insn", 800_000),
+			metricEvent("read_entry", 3),
+			metricEvent("write_entry", 1),
+			metricEvent("ledger_read_byte", 1200),
+			metricEvent("ledger_write_byte", 300),
+		},
+	})
+	require.NoError(t, err)
+	assert.Equal(t, []protocol.TransactionResourceUsage{
+		{Name: "instructions", Declared: 1_000_000, Used: 800_000},
+		{Name: "readEntries", Declared: 3, Used: 3},
+		{Name: "writeEntries", Declared: 1, Used: 1},
+		{Name: "readBytes", Declared: 1000, Used: 1200},
+		{Name: "writeBytes", Declared: 500, Used: 300},
+	}, usage)
+}
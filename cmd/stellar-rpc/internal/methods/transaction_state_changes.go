@@ -0,0 +1,130 @@
+package methods
+
+import (
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/preflight"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// transactionStateChanges returns the ledger entries created, updated and
+// deleted by a transaction, as recorded in its meta. Entries changed several
+// times (e.g. by several operations) are reported once, from their state
+// before the transaction to their state after it.
+func transactionStateChanges(metaXDR []byte, format string) ([]protocol.LedgerEntryChange, error) {
+	var meta xdr.TransactionMeta
+	if err := xdr.SafeUnmarshal(metaXDR, &meta); err != nil {
+		return nil, err
+	}
+
+	var keys []string
+	diffs := map[string]*preflight.XDRDiff{}
+	for _, change := range transactionMetaChanges(meta) {
+		key, before, after, err := ledgerEntryChangeStates(change)
+		if err != nil {
+			return nil, err
+		}
+		keyB64, err := xdr.MarshalBase64(key)
+		if err != nil {
+			return nil, err
+		}
+		diff, ok := diffs[keyB64]
+		if !ok {
+			diff = &preflight.XDRDiff{}
+			if before != nil {
+				if diff.Before, err = before.MarshalBinary(); err != nil {
+					return nil, err
+				}
+			}
+			diffs[keyB64] = diff
+			keys = append(keys, keyB64)
+		}
+		if change.Type == xdr.LedgerEntryChangeTypeLedgerEntryState {
+			continue
+		}
+		diff.After = nil
+		if after != nil {
+			if diff.After, err = after.MarshalBinary(); err != nil {
+				return nil, err
+			}
+		}
+	}
+
+	changes := make([]protocol.LedgerEntryChange, 0, len(keys))
+	for _, key := range keys {
+		diff := diffs[key]
+		// Entries created and deleted by the transaction are left out
+		if len(diff.Before) == 0 && len(diff.After) == 0 {
+			continue
+		}
+		change, err := LedgerEntryChangeFromXDRDiff(*diff, format)
+		if err != nil {
+			return nil, err
+		}
+		changes = append(changes, change)
+	}
+	return changes, nil
+}
+
+// ledgerEntryChangeStates returns the key of the entry of a change, along
+// with its state before the change (only known for the State changes
+// preceding the other changes of an entry) and after it.
+func ledgerEntryChangeStates(change xdr.LedgerEntryChange) (xdr.LedgerKey, *xdr.LedgerEntry, *xdr.LedgerEntry,
+	error,
+) {
+	var entry *xdr.LedgerEntry
+	switch change.Type {
+	case xdr.LedgerEntryChangeTypeLedgerEntryRemoved:
+		return *change.Removed, nil, nil, nil
+	case xdr.LedgerEntryChangeTypeLedgerEntryState:
+		key, err := change.State.LedgerKey()
+		return key, change.State, nil, err
+	case xdr.LedgerEntryChangeTypeLedgerEntryCreated:
+		entry = change.Created
+	case xdr.LedgerEntryChangeTypeLedgerEntryUpdated:
+		entry = change.Updated
+	case xdr.LedgerEntryChangeTypeLedgerEntryRestored:
+		entry = change.Restored
+	}
+	if entry == nil {
+		return xdr.LedgerKey{}, nil, nil, errMissingDiff
+	}
+	key, err := entry.LedgerKey()
+	return key, nil, entry, err
+}
+
+// transactionMetaChanges returns the ledger entry changes of the meta of a
+// transaction, in the order they were applied.
+func transactionMetaChanges(meta xdr.TransactionMeta) []xdr.LedgerEntryChange {
+	var changes []xdr.LedgerEntryChange
+	switch meta.V {
+	case 0:
+		for _, op := range *meta.Operations {
+			changes = append(changes, op.Changes...)
+		}
+	case 1:
+		changes = append(changes, meta.V1.TxChanges...)
+		for _, op := range meta.V1.Operations {
+			changes = append(changes, op.Changes...)
+		}
+	case 2:
+		changes = append(changes, meta.V2.TxChangesBefore...)
+		for _, op := range meta.V2.Operations {
+			changes = append(changes, op.Changes...)
+		}
+		changes = append(changes, meta.V2.TxChangesAfter...)
+	case 3:
+		changes = append(changes, meta.V3.TxChangesBefore...)
+		for _, op := range meta.V3.Operations {
+			changes = append(changes, op.Changes...)
+		}
+		changes = append(changes, meta.V3.TxChangesAfter...)
+	case 4:
+		changes = append(changes, meta.V4.TxChangesBefore...)
+		for _, op := range meta.V4.Operations {
+			changes = append(changes, op.Changes...)
+		}
+		changes = append(changes, meta.V4.TxChangesAfter...)
+	}
+	return changes
+}
@@ -0,0 +1,68 @@
+package methods
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestTransactionStateChanges(t *testing.T) {
+	account := func(address string, balance xdr.Int64) xdr.LedgerEntry {
+		return xdr.LedgerEntry{
+			Data: xdr.LedgerEntryData{
+				Type:    xdr.LedgerEntryTypeAccount,
+				Account: &xdr.AccountEntry{AccountId: xdr.MustAddress(address), Balance: balance},
+			},
+		}
+	}
+	state := func(entry xdr.LedgerEntry) xdr.LedgerEntryChange {
+		return xdr.LedgerEntryChange{Type: xdr.LedgerEntryChangeTypeLedgerEntryState, State: &entry}
+	}
+	updated := func(entry xdr.LedgerEntry) xdr.LedgerEntryChange {
+		return xdr.LedgerEntryChange{Type: xdr.LedgerEntryChangeTypeLedgerEntryUpdated, Updated: &entry}
+	}
+	source := "GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON"
+	created := account("GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ", 10)
+	deleted := account("GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H", 5)
+	deletedKey, err := deleted.LedgerKey()
+	require.NoError(t, err)
+
+	// The source account is updated both before the operations and by them
+	meta := xdr.TransactionMeta{V: 3, V3: &xdr.TransactionMetaV3{
+		TxChangesBefore: xdr.LedgerEntryChanges{state(account(source, 100)), updated(account(source, 90))},
+		Operations: []xdr.OperationMeta{{Changes: xdr.LedgerEntryChanges{
+			state(account(source, 90)),
+			updated(account(source, 80)),
+			{Type: xdr.LedgerEntryChangeTypeLedgerEntryCreated, Created: &created},
+			state(deleted),
+			{Type: xdr.LedgerEntryChangeTypeLedgerEntryRemoved, Removed: &deletedKey},
+		}}},
+	}}
+	metaXDR, err := meta.MarshalBinary()
+	require.NoError(t, err)
+
+	changes, err := transactionStateChanges(metaXDR, "")
+	require.NoError(t, err)
+	require.Len(t, changes, 3)
+	assert.Equal(t, protocol.LedgerEntryChangeTypeUpdated, changes[0].Type)
+	before, err := xdr.MarshalBase64(account(source, 100))
+	require.NoError(t, err)
+	after, err := xdr.MarshalBase64(account(source, 80))
+	require.NoError(t, err)
+	assert.Equal(t, &before, changes[0].BeforeXDR)
+	assert.Equal(t, &after, changes[0].AfterXDR)
+	assert.Equal(t, protocol.LedgerEntryChangeTypeCreated, changes[1].Type)
+	assert.Equal(t, protocol.LedgerEntryChangeTypeDeleted, changes[2].Type)
+	assert.Nil(t, changes[2].AfterXDR)
+
+	changes, err = transactionStateChanges(metaXDR, protocol.FormatJSON)
+	require.NoError(t, err)
+	require.Len(t, changes, 3)
+	assert.NotEmpty(t, changes[0].BeforeJSON)
+	assert.Nil(t, changes[0].BeforeXDR)
+}
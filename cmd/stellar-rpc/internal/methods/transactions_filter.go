@@ -0,0 +1,166 @@
+package methods
+
+import (
+	"context"
+	"slices"
+
+	"github.com/creachadair/jrpc2"
+
+	"github.com/stellar/go/ingest"
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/toid"
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/db"
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// transactionsLedgerScanLimit is the maximum number of ledgers a filtered
+// getTransactions request scans, so that requests matching sparse
+// transactions are paginated instead of scanning the whole retention window.
+const transactionsLedgerScanLimit = 1000
+
+// transactionsFilter holds the criteria of the transactions returned by
+// getTransactions.
+type transactionsFilter struct {
+	account     string
+	contractIDs [][]byte
+	// successful restricts the transactions to the successful (or failed)
+	// ones, if set
+	successful *bool
+	memo       *protocol.MemoFilter
+	opTypes    []xdr.OperationType
+	authSigner string
+
+	// indexedTxs holds the application orders of the transactions touching
+	// contractIDs (or, without them, authorized by authSigner) by ledger, as
+	// looked up in the index of the scanned ledgers
+	indexedTxs map[uint32][]int32
+	// end is the cursor of the end of the scan, when the scanned ledgers
+	// are restricted to the ones with indexed transactions
+	end *toid.ID
+}
+
+func newTransactionsFilter(request protocol.GetTransactionsRequest) (transactionsFilter, error) {
+	filter := transactionsFilter{account: request.Account, memo: request.Memo, authSigner: request.AuthSigner}
+	if request.Status != "" {
+		successful := request.Status == protocol.TransactionStatusSuccess
+		filter.successful = &successful
+	}
+	for _, name := range request.OperationTypes {
+		opType, ok := protocol.ParseOperationType(name)
+		if !ok {
+			return transactionsFilter{}, &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: "unknown operation type: " + name,
+			}
+		}
+		filter.opTypes = append(filter.opTypes, opType)
+	}
+	for _, id := range request.ContractIDs {
+		contractID, err := strkey.Decode(strkey.VersionByteContract, id)
+		if err != nil {
+			return transactionsFilter{}, &jrpc2.Error{
+				Code:    jrpc2.InvalidParams,
+				Message: err.Error(),
+			}
+		}
+		filter.contractIDs = append(filter.contractIDs, contractID)
+	}
+	return filter, nil
+}
+
+// empty returns true if the filter matches all the transactions.
+func (f transactionsFilter) empty() bool {
+	return f.account == "" && len(f.contractIDs) == 0 && f.successful == nil && f.memo == nil &&
+		len(f.opTypes) == 0 && f.authSigner == ""
+}
+
+// loadIndexedTransactions looks up the (up to limit) transactions touching
+// the contracts of the filter (or, without them, authorized by its auth
+// signer) from start to the end of endLedger, in the order of the request.
+func (f *transactionsFilter) loadIndexedTransactions(ctx context.Context, reader db.TransactionReader,
+	start toid.ID, endLedger uint32, order string, limit uint,
+) error {
+	startPosition := db.TransactionPosition{
+		LedgerSequence:   uint32(start.LedgerSequence), //nolint:gosec
+		ApplicationOrder: start.TransactionOrder,
+	}
+	var positions []db.TransactionPosition
+	var err error
+	switch {
+	case len(f.contractIDs) > 0:
+		positions, err = reader.GetContractTransactions(ctx, f.contractIDs, startPosition, endLedger, order, limit)
+	case f.authSigner != "":
+		positions, err = reader.GetAuthSignerTransactions(ctx, f.authSigner, startPosition, endLedger, order, limit)
+	default:
+		return nil
+	}
+	if err != nil {
+		return err
+	}
+
+	f.indexedTxs = make(map[uint32][]int32, len(positions))
+	for _, position := range positions {
+		f.indexedTxs[position.LedgerSequence] = append(f.indexedTxs[position.LedgerSequence],
+			position.ApplicationOrder)
+	}
+	// The next page starts after the last looked up transaction if there may
+	// be more, otherwise after the scanned ledgers
+	f.end = toid.New(int32(endLedger), toid.TransactionMask, 1) //nolint:gosec
+	if order == protocol.TransactionsOrderDesc {
+		f.end = toid.New(int32(endLedger), 0, 1) //nolint:gosec
+	}
+	if len(positions) >= int(limit) { //nolint:gosec
+		last := positions[len(positions)-1]
+		f.end = toid.New(int32(last.LedgerSequence), last.ApplicationOrder, 1) //nolint:gosec
+	}
+	return nil
+}
+
+// scansLedger returns true if the ledger may contain matching transactions.
+func (f transactionsFilter) scansLedger(ledgerSeq uint32) bool {
+	return f.indexedTxs == nil || len(f.indexedTxs[ledgerSeq]) > 0
+}
+
+func (f transactionsFilter) matches(ledgerSeq uint32, tx ingest.LedgerTransaction) bool {
+	if f.account != "" && !f.matchesAccount(tx) {
+		return false
+	}
+	if f.successful != nil && tx.Result.Successful() != *f.successful {
+		return false
+	}
+	if f.memo != nil && !f.memo.Matches(tx.Envelope.Memo()) {
+		return false
+	}
+	if len(f.opTypes) > 0 && !slices.ContainsFunc(tx.Envelope.Operations(), func(op xdr.Operation) bool {
+		return slices.Contains(f.opTypes, op.Body.Type)
+	}) {
+		return false
+	}
+	if f.authSigner != "" && !f.matchesAuthSigner(tx) {
+		return false
+	}
+	return f.indexedTxs == nil || slices.Contains(f.indexedTxs[ledgerSeq], int32(tx.Index)) //nolint:gosec
+}
+
+// matchesAuthSigner returns true if the auth signer signed one of the
+// authorization entries of the transaction.
+func (f transactionsFilter) matchesAuthSigner(tx ingest.LedgerTransaction) bool {
+	signers, err := db.TransactionAuthSigners(tx)
+	return err == nil && slices.Contains(signers, f.authSigner)
+}
+
+// matchesAccount returns true if the account is the source (of the inner
+// transaction, for fee-bump transactions) or the fee source of the transaction.
+func (f transactionsFilter) matchesAccount(tx ingest.LedgerTransaction) bool {
+	if accountAddress(tx.Envelope.SourceAccount()) == f.account {
+		return true
+	}
+	return tx.Envelope.IsFeeBump() && accountAddress(tx.Envelope.FeeBumpAccount()) == f.account
+}
+
+func accountAddress(account xdr.MuxedAccount) string {
+	accountID := account.ToAccountId()
+	return accountID.Address()
+}
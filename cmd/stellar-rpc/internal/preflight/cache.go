@@ -0,0 +1,88 @@
+package preflight
+
+import (
+	"encoding/json"
+	"sync"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// resultCache holds the preflights run against the latest ledger, so that
+// identical simulations (e.g. clients polling a token balance) aren't run
+// again until a new ledger closes. Preflights of older ledgers are neither
+// cached nor returned.
+type resultCache struct {
+	lock      sync.Mutex
+	maxSize   int
+	ledgerSeq uint32
+	results   map[string]Preflight
+}
+
+func newResultCache(maxSize uint) *resultCache {
+	return &resultCache{
+		maxSize: int(maxSize),
+		results: map[string]Preflight{},
+	}
+}
+
+func (c *resultCache) get(key string, ledgerSeq uint32) (Preflight, bool) {
+	c.lock.Lock()
+	defer c.lock.Unlock()
+	if ledgerSeq != c.ledgerSeq {
+		return Preflight{}, false
+	}
+	result, ok := c.results[key]
+	return result, ok
+}
+
+func (c *resultCache) put(key string, ledgerSeq uint32, result Preflight) {
+	c.lock.Lock()
+	defer c.lock.Unlock()
+	switch {
+	case ledgerSeq < c.ledgerSeq:
+		return
+	case ledgerSeq > c.ledgerSeq:
+		// A new ledger invalidates every cached preflight
+		c.ledgerSeq = ledgerSeq
+		clear(c.results)
+	}
+	if len(c.results) >= c.maxSize {
+		return
+	}
+	c.results[key] = result
+}
+
+// resultCacheKey identifies the preflights which can be cached, i.e. the
+// InvokeHostFunction ones simulated against the actual ledger state and
+// ledger. The ledger sequence is handled by the cache itself.
+func resultCacheKey(params GetterParameters) (string, bool) {
+	if params.OperationBody.Type != xdr.OperationTypeInvokeHostFunction || params.RebuiltState ||
+		len(params.LedgerEntryOverrides) > 0 || params.ConfigUpgradeSet != nil || params.LedgerOverride != nil {
+		return "", false
+	}
+	opXDR, err := params.OperationBody.MarshalBinary()
+	if err != nil {
+		return "", false
+	}
+	sourceXDR, err := params.SourceAccount.MarshalBinary()
+	if err != nil {
+		return "", false
+	}
+	key, err := json.Marshal(struct {
+		Op                       []byte
+		Source                   []byte
+		ResourceConfig           protocol.ResourceConfig
+		AuthMode                 string
+		ProtocolVersion          uint32
+		SimulatedProtocolVersion uint32
+	}{
+		opXDR, sourceXDR, params.ResourceConfig, params.AuthMode, params.ProtocolVersion,
+		params.SimulatedProtocolVersion,
+	})
+	if err != nil {
+		return "", false
+	}
+	return string(key), true
+}
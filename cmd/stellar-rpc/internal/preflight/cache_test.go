@@ -0,0 +1,86 @@
+package preflight
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestResultCache(t *testing.T) {
+	cache := newResultCache(2)
+	result := Preflight{MinFee: 42}
+
+	cache.put("a", 10, result)
+	cached, ok := cache.get("a", 10)
+	require.True(t, ok)
+	require.Equal(t, result, cached)
+	_, ok = cache.get("b", 10)
+	require.False(t, ok)
+
+	// Preflights of older ledgers are ignored
+	cache.put("b", 9, result)
+	_, ok = cache.get("b", 9)
+	require.False(t, ok)
+
+	// The cache is bounded
+	cache.put("b", 10, result)
+	cache.put("c", 10, result)
+	_, ok = cache.get("c", 10)
+	require.False(t, ok)
+
+	// A new ledger invalidates the cached preflights
+	cache.put("c", 11, result)
+	_, ok = cache.get("a", 10)
+	require.False(t, ok)
+	_, ok = cache.get("a", 11)
+	require.False(t, ok)
+	_, ok = cache.get("c", 11)
+	require.True(t, ok)
+}
+
+func TestResultCacheKey(t *testing.T) {
+	params := GetterParameters{
+		OperationBody: xdr.OperationBody{
+			Type: xdr.OperationTypeInvokeHostFunction,
+			InvokeHostFunctionOp: &xdr.InvokeHostFunctionOp{
+				HostFunction: xdr.HostFunction{
+					Type: xdr.HostFunctionTypeHostFunctionTypeUploadContractWasm,
+					Wasm: &[]byte{1, 2, 3},
+				},
+			},
+		},
+		SourceAccount: xdr.MustAddress("GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H"),
+	}
+	key, ok := resultCacheKey(params)
+	require.True(t, ok)
+
+	other := params
+	other.AuthMode = "enforce"
+	otherKey, ok := resultCacheKey(other)
+	require.True(t, ok)
+	require.NotEqual(t, key, otherKey)
+
+	other = params
+	other.LedgerEntryOverrides = []xdr.LedgerEntry{{}}
+	_, ok = resultCacheKey(other)
+	require.False(t, ok)
+
+	other = params
+	other.LedgerOverride = &protocol.LedgerOverride{Sequence: 1}
+	_, ok = resultCacheKey(other)
+	require.False(t, ok)
+
+	other = params
+	other.RebuiltState = true
+	_, ok = resultCacheKey(other)
+	require.False(t, ok)
+
+	other = params
+	other.OperationBody = xdr.OperationBody{Type: xdr.OperationTypeRestoreFootprint}
+	_, ok = resultCacheKey(other)
+	require.False(t, ok)
+}
@@ -0,0 +1,132 @@
+package preflight
+
+import (
+	"github.com/stellar/go/xdr"
+)
+
+// CallFrame is a contract function invocation reconstructed from the
+// diagnostic events of a simulation.
+type CallFrame struct {
+	ContractID  xdr.ContractId
+	Function    string
+	Args        []xdr.ScVal
+	ReturnValue *xdr.ScVal // missing if the call didn't return
+	Trap        *Trap      // first error raised while the call was executing, if any
+	SubCalls    []*CallFrame
+}
+
+type Trap struct {
+	Error   xdr.ScError
+	Message string
+}
+
+// GetCallTrace rebuilds the tree of contract calls made during a simulation
+// from its fn_call, fn_return and error diagnostic events.
+func GetCallTrace(diagnosticEventsXDR [][]byte) ([]*CallFrame, error) {
+	var roots []*CallFrame
+	var stack []*CallFrame
+	for _, eventXDR := range diagnosticEventsXDR {
+		var event xdr.DiagnosticEvent
+		if err := xdr.SafeUnmarshal(eventXDR, &event); err != nil {
+			return nil, err
+		}
+		body, ok := event.Event.Body.GetV0()
+		if !ok || len(body.Topics) == 0 {
+			continue
+		}
+		sym, ok := body.Topics[0].GetSym()
+		if !ok {
+			continue
+		}
+		switch sym {
+		case "fn_call":
+			frame, ok := newCallFrame(body)
+			if !ok {
+				continue
+			}
+			if len(stack) == 0 {
+				roots = append(roots, frame)
+			} else {
+				parent := stack[len(stack)-1]
+				parent.SubCalls = append(parent.SubCalls, frame)
+			}
+			stack = append(stack, frame)
+		case "fn_return":
+			if len(stack) == 0 {
+				continue
+			}
+			returnValue := body.Data
+			stack[len(stack)-1].ReturnValue = &returnValue
+			stack = stack[:len(stack)-1]
+		case "error":
+			stack = recordTrap(stack, event.Event.ContractId, body)
+		}
+	}
+	return roots, nil
+}
+
+// The topics of a fn_call event are ["fn_call", contract ID, function name]
+func newCallFrame(body xdr.ContractEventV0) (*CallFrame, bool) {
+	if len(body.Topics) != 3 {
+		return nil, false
+	}
+	contractIDBytes, ok := body.Topics[1].GetBytes()
+	if !ok || len(contractIDBytes) != len(xdr.ContractId{}) {
+		return nil, false
+	}
+	function, ok := body.Topics[2].GetSym()
+	if !ok {
+		return nil, false
+	}
+	frame := &CallFrame{
+		Function: string(function),
+		Args:     getEventArgs(body.Data),
+	}
+	copy(frame.ContractID[:], contractIDBytes)
+	return frame, true
+}
+
+// Events with multiple arguments carry them in a vector, so a call with a
+// single vector argument can't be told apart from a call with several.
+func getEventArgs(data xdr.ScVal) []xdr.ScVal {
+	if data.Type == xdr.ScValTypeScvVoid {
+		return nil
+	}
+	if vec, ok := data.GetVec(); ok && vec != nil && len(**vec) > 1 {
+		return **vec
+	}
+	return []xdr.ScVal{data}
+}
+
+// recordTrap attaches the error to the innermost frame of the contract which
+// raised it. Frames called by that contract which are still open never
+// returned, so they are closed (and marked as trapped if they weren't yet).
+func recordTrap(stack []*CallFrame, contractID *xdr.ContractId, body xdr.ContractEventV0) []*CallFrame {
+	if len(body.Topics) != 2 {
+		return stack
+	}
+	scError, ok := body.Topics[1].GetError()
+	if !ok {
+		return stack
+	}
+	trap := &Trap{Error: scError}
+	message := body.Data
+	if vec, ok := body.Data.GetVec(); ok && vec != nil && len(**vec) > 0 {
+		message = (**vec)[0]
+	}
+	if str, ok := message.GetStr(); ok {
+		trap.Message = string(str)
+	}
+
+	for contractID != nil && len(stack) > 0 && stack[len(stack)-1].ContractID != *contractID {
+		top := stack[len(stack)-1]
+		if top.Trap == nil {
+			top.Trap = trap
+		}
+		stack = stack[:len(stack)-1]
+	}
+	if len(stack) > 0 && stack[len(stack)-1].Trap == nil {
+		stack[len(stack)-1].Trap = trap
+	}
+	return stack
+}
@@ -21,45 +21,45 @@ const (
 	dbMetricsDurationConversionValue = 1000.0
 )
 
-type workerResult struct {
-	preflight Preflight
-	err       error
-}
-
-type workerRequest struct {
-	ctx        context.Context //nolint:containedctx
-	params     Parameters
-	resultChan chan<- workerResult
-}
-
 type WorkerPool struct {
 	networkPassphrase          string
 	enableDebug                bool
 	logger                     *log.Entry
 	isClosed                   atomic.Bool
-	requestChan                chan workerRequest
 	concurrentRequestsMetric   prometheus.Gauge
 	errorFullCounter           prometheus.Counter
 	durationMetric             *prometheus.SummaryVec
 	ledgerEntriesFetchedMetric prometheus.Summary
-	wg                         sync.WaitGroup
+	cacheHitsCounter           prometheus.Counter
+	cache                      *resultCache   // nil if caching is disabled
+	wg                         sync.WaitGroup // preflights in progress
 }
 
 type WorkerPoolConfig struct {
 	Daemon            interfaces.Daemon
 	WorkerCount       uint
 	JobQueueCapacity  uint
+	CacheSize         uint // maximum number of cached preflights, 0 to disable caching
 	EnableDebug       bool
 	NetworkPassphrase string
 	Logger            *log.Entry
 }
 
 func NewPreflightWorkerPool(cfg WorkerPoolConfig) *WorkerPool {
+	// The preflights are queued and run by a pool of threads of the preflight
+	// library, which bounds how many of them run at once however many requests
+	// come in. The pool lives as long as the process, so it's only started by
+	// the first worker pool.
+	if !startWorkerPool(cfg.WorkerCount, cfg.JobQueueCapacity) {
+		cfg.Logger.Warn("could not start the preflight worker pool, it may already be running")
+	}
 	preflightWP := WorkerPool{
 		networkPassphrase: cfg.NetworkPassphrase,
 		enableDebug:       cfg.EnableDebug,
 		logger:            cfg.Logger,
-		requestChan:       make(chan workerRequest, cfg.JobQueueCapacity),
+	}
+	if cfg.CacheSize > 0 {
+		preflightWP.cache = newResultCache(cfg.CacheSize)
 	}
 	requestQueueMetric := prometheus.NewGaugeFunc(prometheus.GaugeOpts{
 		Namespace: cfg.Daemon.MetricsNamespace(),
@@ -67,13 +67,13 @@ func NewPreflightWorkerPool(cfg WorkerPoolConfig) *WorkerPool {
 		Name:      "queue_length",
 		Help:      "number of preflight requests in the queue",
 	}, func() float64 {
-		return float64(len(preflightWP.requestChan))
+		return float64(workerPoolQueueLength())
 	})
 	preflightWP.concurrentRequestsMetric = prometheus.NewGauge(prometheus.GaugeOpts{
 		Namespace: cfg.Daemon.MetricsNamespace(),
 		Subsystem: "preflight_pool",
 		Name:      "concurrent_requests",
-		Help:      "number of preflight requests currently running",
+		Help:      "number of preflight requests currently queued or running",
 	})
 	preflightWP.errorFullCounter = prometheus.NewCounter(prometheus.CounterOpts{
 		Namespace: cfg.Daemon.MetricsNamespace(),
@@ -95,44 +95,29 @@ func NewPreflightWorkerPool(cfg WorkerPoolConfig) *WorkerPool {
 		Help:       "ledger entries fetched by simulate transaction calls",
 		Objectives: map[float64]float64{0.5: 0.05, 0.9: 0.01, 0.99: 0.001}, //nolint:mnd
 	})
+	preflightWP.cacheHitsCounter = prometheus.NewCounter(prometheus.CounterOpts{
+		Namespace: cfg.Daemon.MetricsNamespace(),
+		Subsystem: "preflight_pool",
+		Name:      "cache_hits",
+		Help:      "number of preflight requests answered from the cache",
+	})
 	cfg.Daemon.MetricsRegistry().MustRegister(
 		requestQueueMetric,
 		preflightWP.concurrentRequestsMetric,
 		preflightWP.errorFullCounter,
 		preflightWP.durationMetric,
 		preflightWP.ledgerEntriesFetchedMetric,
+		preflightWP.cacheHitsCounter,
 	)
-	for range cfg.WorkerCount {
-		preflightWP.wg.Add(1)
-		go preflightWP.work()
-	}
 	return &preflightWP
 }
 
-func (pwp *WorkerPool) work() {
-	defer pwp.wg.Done()
-	for request := range pwp.requestChan {
-		pwp.concurrentRequestsMetric.Inc()
-		startTime := time.Now()
-		preflight, err := GetPreflight(request.ctx, request.params)
-		status := "ok"
-		if err != nil {
-			status = "error"
-		}
-		pwp.durationMetric.With(
-			prometheus.Labels{"type": "all", "status": status},
-		).Observe(time.Since(startTime).Seconds())
-		pwp.concurrentRequestsMetric.Dec()
-		request.resultChan <- workerResult{preflight, err}
-	}
-}
-
+// Close rejects new preflights and waits for the ones in progress.
 func (pwp *WorkerPool) Close() {
 	if !pwp.isClosed.CompareAndSwap(false, true) {
 		// it was already closed
 		return
 	}
-	close(pwp.requestChan)
 	pwp.wg.Wait()
 }
 
@@ -155,30 +140,102 @@ func (m *metricsLedgerEntryGetterWrapper) GetLedgerEntries(ctx context.Context,
 }
 
 type GetterParameters struct {
-	BucketListSize    uint64
-	SourceAccount     xdr.AccountId
-	OperationBody     xdr.OperationBody
-	Footprint         xdr.LedgerFootprint
-	ResourceConfig    protocol.ResourceConfig
-	AuthMode          string
-	ProtocolVersion   uint32
-	LedgerEntryGetter ledgerentries.LedgerEntryGetter
-	LedgerSeq         uint32
+	BucketListSize           uint64
+	SourceAccount            xdr.AccountId
+	OperationBody            xdr.OperationBody
+	Footprint                xdr.LedgerFootprint
+	ResourceConfig           protocol.ResourceConfig
+	AuthMode                 string
+	ProtocolVersion          uint32
+	LedgerEntryGetter        ledgerentries.LedgerEntryGetter
+	LedgerSeq                uint32
+	LedgerEntryOverrides     []xdr.LedgerEntry
+	ConfigUpgradeSet         *xdr.ConfigUpgradeSet
+	SimulatedProtocolVersion uint32
+	LedgerOverride           *protocol.LedgerOverride
+	// RebuiltState tells that LedgerEntryGetter serves a state rebuilt from the
+	// stored ledgers instead of the one of LedgerSeq, so the result isn't cached
+	RebuiltState bool
 }
 
 func (pwp *WorkerPool) GetPreflight(ctx context.Context, params GetterParameters) (Preflight, error) {
 	if pwp.isClosed.Load() {
 		return Preflight{}, errors.New("preflight worker pool is closed")
 	}
+	cacheKey, cacheable := "", false
+	if pwp.cache != nil {
+		cacheKey, cacheable = resultCacheKey(params)
+	}
+	if cacheable {
+		if result, ok := pwp.cache.get(cacheKey, params.LedgerSeq); ok {
+			pwp.cacheHitsCounter.Inc()
+			return result, nil
+		}
+	}
 	wrappedGetter := &metricsLedgerEntryGetterWrapper{
 		LedgerEntryGetter: params.LedgerEntryGetter,
 	}
-	preflightParams := Parameters{
+	pwp.wg.Add(1)
+	defer pwp.wg.Done()
+	pwp.concurrentRequestsMetric.Inc()
+	startTime := time.Now()
+	result, err := GetPreflight(ctx, pwp.getPreflightParameters(params, wrappedGetter))
+	pwp.concurrentRequestsMetric.Dec()
+	if err == nil && result.Error == workerPoolQueueFullError {
+		pwp.errorFullCounter.Inc()
+		return Preflight{}, ErrPreflightQueueFull
+	}
+	pwp.observePreflight(startTime, wrappedGetter, err)
+	// Failed preflights may have failed for transient reasons, and the ones
+	// recording auth carry nonces which are meant to differ between requests
+	if cacheable && err == nil && result.Error == "" && len(result.Auth) == 0 {
+		pwp.cache.put(cacheKey, params.LedgerSeq, result)
+	}
+	return result, err
+}
+
+// GetPreflights simulates a batch of InvokeHostFunction operations against a
+// single snapshot of the ledger, which the operations share the ledger entry
+// getter, ledger information and configuration of (taken from the first
+// parameters). The results aren't cached.
+func (pwp *WorkerPool) GetPreflights(ctx context.Context, params []GetterParameters) ([]Preflight, error) {
+	if pwp.isClosed.Load() {
+		return nil, errors.New("preflight worker pool is closed")
+	}
+	if len(params) == 0 {
+		return nil, nil
+	}
+	wrappedGetter := &metricsLedgerEntryGetterWrapper{
+		LedgerEntryGetter: params[0].LedgerEntryGetter,
+	}
+	preflightParams := make([]Parameters, len(params))
+	for i, p := range params {
+		preflightParams[i] = pwp.getPreflightParameters(p, wrappedGetter)
+	}
+	pwp.wg.Add(1)
+	defer pwp.wg.Done()
+	pwp.concurrentRequestsMetric.Inc()
+	startTime := time.Now()
+	results, err := GetInvokeHostFunctionPreflights(ctx, preflightParams)
+	pwp.concurrentRequestsMetric.Dec()
+	// The whole batch is rejected when the queue is full
+	if err == nil && results[0].Error == workerPoolQueueFullError {
+		pwp.errorFullCounter.Inc()
+		return nil, ErrPreflightQueueFull
+	}
+	pwp.observePreflight(startTime, wrappedGetter, err)
+	return results, err
+}
+
+func (pwp *WorkerPool) getPreflightParameters(params GetterParameters,
+	getter *metricsLedgerEntryGetterWrapper,
+) Parameters {
+	return Parameters{
 		Logger:            pwp.logger,
 		SourceAccount:     params.SourceAccount,
 		OpBody:            params.OperationBody,
 		NetworkPassphrase: pwp.networkPassphrase,
-		LedgerEntryGetter: wrappedGetter,
+		LedgerEntryGetter: getter,
 		LedgerSeq:         params.LedgerSeq,
 		BucketListSize:    params.BucketListSize,
 		Footprint:         params.Footprint,
@@ -186,26 +243,26 @@ func (pwp *WorkerPool) GetPreflight(ctx context.Context, params GetterParameters
 		EnableDebug:       pwp.enableDebug,
 		AuthMode:          params.AuthMode,
 		ProtocolVersion:   params.ProtocolVersion,
+
+		LedgerEntryOverrides:     params.LedgerEntryOverrides,
+		ConfigUpgradeSet:         params.ConfigUpgradeSet,
+		SimulatedProtocolVersion: params.SimulatedProtocolVersion,
+		LedgerOverride:           params.LedgerOverride,
 	}
-	resultC := make(chan workerResult)
-	select {
-	case pwp.requestChan <- workerRequest{ctx, preflightParams, resultC}:
-		result := <-resultC
-		if wrappedGetter.ledgerEntriesFetched > 0 {
-			status := "ok"
-			if result.err != nil {
-				status = "error"
-			}
-			pwp.durationMetric.With(
-				prometheus.Labels{"type": "db", "status": status},
-			).Observe(float64(wrappedGetter.totalDurationMs) / dbMetricsDurationConversionValue)
-		}
-		pwp.ledgerEntriesFetchedMetric.Observe(float64(wrappedGetter.ledgerEntriesFetched))
-		return result.preflight, result.err
-	case <-ctx.Done():
-		return Preflight{}, ctx.Err()
-	default:
-		pwp.errorFullCounter.Inc()
-		return Preflight{}, ErrPreflightQueueFull
+}
+
+func (pwp *WorkerPool) observePreflight(startTime time.Time, getter *metricsLedgerEntryGetterWrapper, err error) {
+	status := "ok"
+	if err != nil {
+		status = "error"
+	}
+	pwp.durationMetric.With(
+		prometheus.Labels{"type": "all", "status": status},
+	).Observe(time.Since(startTime).Seconds())
+	if getter.ledgerEntriesFetched > 0 {
+		pwp.durationMetric.With(
+			prometheus.Labels{"type": "db", "status": status},
+		).Observe(float64(getter.totalDurationMs) / dbMetricsDurationConversionValue)
 	}
+	pwp.ledgerEntriesFetchedMetric.Observe(float64(getter.ledgerEntriesFetched))
 }
@@ -19,8 +19,15 @@ import "C"
 
 import (
 	"context"
+	"crypto/sha256"
+	"encoding/json"
+	"errors"
 	"fmt"
+	"runtime"
 	"runtime/cgo"
+	"slices"
+	"strings"
+	"sync"
 	"time"
 	"unsafe"
 
@@ -35,16 +42,166 @@ type snapshotSourceHandle struct {
 	ledgerEntryGetter ledgerentries.LedgerEntryGetter
 	ctx               context.Context //nolint:containedctx
 	logger            *log.Entry
+	arena             *xdrArena // owns the ledger entries handed over to the preflight library
+}
+
+func newSnapshotSourceHandle(ctx context.Context, params Parameters, arena *xdrArena) snapshotSourceHandle {
+	ledgerEntryGetter := params.LedgerEntryGetter
+	if params.ConfigUpgradeSet != nil {
+		ledgerEntryGetter = newConfigUpgradeLedgerEntryGetter(ledgerEntryGetter, *params.ConfigUpgradeSet)
+	}
+	return snapshotSourceHandle{
+		ledgerEntryGetter: ledgerEntryGetter,
+		ctx:               ctx,
+		logger:            params.Logger,
+		arena:             arena,
+	}
+}
+
+// configUpgradeLedgerEntryGetter returns the config settings of an upgrade
+// set in place of the ones obtained from the wrapped getter
+type configUpgradeLedgerEntryGetter struct {
+	ledgerentries.LedgerEntryGetter
+	settings map[xdr.ConfigSettingId]xdr.ConfigSettingEntry
+}
+
+func newConfigUpgradeLedgerEntryGetter(getter ledgerentries.LedgerEntryGetter,
+	upgradeSet xdr.ConfigUpgradeSet,
+) *configUpgradeLedgerEntryGetter {
+	settings := make(map[xdr.ConfigSettingId]xdr.ConfigSettingEntry, len(upgradeSet.UpdatedEntry))
+	for _, setting := range upgradeSet.UpdatedEntry {
+		settings[setting.ConfigSettingId] = setting
+	}
+	return &configUpgradeLedgerEntryGetter{
+		LedgerEntryGetter: getter,
+		settings:          settings,
+	}
+}
+
+func (g *configUpgradeLedgerEntryGetter) GetLedgerEntries(ctx context.Context,
+	keys []xdr.LedgerKey,
+) ([]ledgerentries.LedgerKeyAndEntry, uint32, error) {
+	entries, ledgerSeq, err := g.LedgerEntryGetter.GetLedgerEntries(ctx, keys)
+	if err != nil {
+		return nil, 0, err
+	}
+	// Upgraded settings are replaced in place, so that the entries keep the
+	// order of the wrapped getter
+	result := make([]ledgerentries.LedgerKeyAndEntry, 0, len(keys))
+	upgraded := make(map[xdr.ConfigSettingId]bool)
+	for _, entry := range entries {
+		if configSetting, ok := entry.Key.GetConfigSetting(); ok {
+			if setting, ok := g.settings[configSetting.ConfigSettingId]; ok {
+				entry.Entry = newConfigSettingLedgerEntry(setting, ledgerSeq)
+				upgraded[configSetting.ConfigSettingId] = true
+			}
+		}
+		result = append(result, entry)
+	}
+	for _, key := range keys {
+		configSetting, ok := key.GetConfigSetting()
+		if !ok || upgraded[configSetting.ConfigSettingId] {
+			continue
+		}
+		if setting, ok := g.settings[configSetting.ConfigSettingId]; ok {
+			result = append(result, ledgerentries.LedgerKeyAndEntry{
+				Key:   key,
+				Entry: newConfigSettingLedgerEntry(setting, ledgerSeq),
+			})
+		}
+	}
+	return result, ledgerSeq, nil
+}
+
+func newConfigSettingLedgerEntry(setting xdr.ConfigSettingEntry, ledgerSeq uint32) xdr.LedgerEntry {
+	return xdr.LedgerEntry{
+		LastModifiedLedgerSeq: xdr.Uint32(ledgerSeq),
+		Data: xdr.LedgerEntryData{
+			Type:          xdr.LedgerEntryTypeConfigSetting,
+			ConfigSetting: &setting,
+		},
+	}
 }
 
 // Current base reserve is 0.5XLM (in stroops)
 const defaultBaseReserve = 5_000_000
 
+// Error of the preflights rejected by the worker pool of the preflight library
+// (see QUEUE_FULL_ERROR in lib/preflight/src/pool.rs)
+const workerPoolQueueFullError = "preflight queue full"
+
+// Error of the preflights cancelled through their cancel token (see
+// CANCELLED_ERROR in lib/preflight/src/cancel.rs)
+const cancelledError = "cancelled"
+
+// ErrPreflightCancelled is returned for the preflights aborted because their
+// context was done (e.g. the client went away or the request timed out).
+var ErrPreflightCancelled = errors.New("preflight cancelled")
+
+// cancelToken cancels the preflights it's passed to once its context is done.
+// The preflight library only checks it between host executions and on their
+// ledger entry reads: a contract executing without reading ledger entries
+// can't be interrupted, and runs until it completes or exhausts its budget.
+type cancelToken struct {
+	token *C.preflight_cancel_token_t
+	done  chan struct{}
+	wg    sync.WaitGroup
+}
+
+func newCancelToken(ctx context.Context) *cancelToken {
+	t := &cancelToken{
+		token: C.preflight_new_cancel_token(),
+		done:  make(chan struct{}),
+	}
+	if ctx.Err() != nil {
+		// Don't let the preflights start before the token is cancelled
+		C.preflight_cancel(t.token)
+	}
+	t.wg.Add(1)
+	go func() {
+		defer t.wg.Done()
+		select {
+		case <-ctx.Done():
+			C.preflight_cancel(t.token)
+		case <-t.done:
+		}
+	}()
+	return t
+}
+
+// getCancellationError returns the error to report instead of the results of
+// the preflights, if any of them was cancelled.
+func getCancellationError(ctx context.Context, preflights ...Preflight) error {
+	for _, preflight := range preflights {
+		// The error may be followed by a backtrace
+		if strings.HasPrefix(preflight.Error, cancelledError) {
+			return fmt.Errorf("%w: %w", ErrPreflightCancelled, ctx.Err())
+		}
+	}
+	return nil
+}
+
+// Release frees the token, which must happen after the preflights it was
+// passed to have returned.
+func (t *cancelToken) Release() {
+	close(t.done)
+	t.wg.Wait()
+	C.preflight_free_cancel_token(t.token)
+}
+
+func startWorkerPool(workerCount, queueSize uint) bool {
+	return bool(C.preflight_start_worker_pool(C.size_t(workerCount), C.size_t(queueSize)))
+}
+
+func workerPoolQueueLength() int {
+	return int(C.preflight_worker_pool_queue_length())
+}
+
 // SnapshotSourceGet takes a LedgerKey XDR in base64 string and returns its matching LedgerEntry XDR in base64 string
-// It's used by the Rust preflight code to obtain ledger entries.
+// as of the given ledger. It's used by the Rust preflight code to obtain ledger entries.
 //
 //export SnapshotSourceGet
-func SnapshotSourceGet(handle C.uintptr_t, cLedgerKey C.xdr_t) C.ledger_entry_and_ttl_t {
+func SnapshotSourceGet(handle C.uintptr_t, cLedgerKey C.xdr_t, ledgerSeq C.uint32_t) C.ledger_entry_and_ttl_t {
 	h, ok := cgo.Handle(handle).Value().(snapshotSourceHandle)
 	if !ok {
 		panic("invalid handle type: expected snapshotSourceHandle")
@@ -54,11 +211,15 @@ func SnapshotSourceGet(handle C.uintptr_t, cLedgerKey C.xdr_t) C.ledger_entry_an
 	if err := xdr.SafeUnmarshal(ledgerKeyXDR, &ledgerKey); err != nil {
 		panic(err)
 	}
-	entries, _, err := h.ledgerEntryGetter.GetLedgerEntries(h.ctx, []xdr.LedgerKey{ledgerKey})
+	entries, readLedgerSeq, err := h.ledgerEntryGetter.GetLedgerEntries(h.ctx, []xdr.LedgerKey{ledgerKey})
 	if err != nil {
 		h.logger.WithError(err).Error("SnapshotSourceGet(): GetLedgerEntries() failed")
 		return C.ledger_entry_and_ttl_t{}
 	}
+	if readLedgerSeq != uint32(ledgerSeq) {
+		h.logger.Errorf("SnapshotSourceGet(): GetLedgerEntries() read ledger %d instead of %d", readLedgerSeq, ledgerSeq)
+		return C.ledger_entry_and_ttl_t{}
+	}
 	if len(entries) > 1 {
 		h.logger.WithError(err).Error("SnapshotSourceGet(): GetLedgerEntries() returned more than one entry")
 		return C.ledger_entry_and_ttl_t{}
@@ -66,32 +227,94 @@ func SnapshotSourceGet(handle C.uintptr_t, cLedgerKey C.xdr_t) C.ledger_entry_an
 	if len(entries) == 0 {
 		return C.ledger_entry_and_ttl_t{}
 	}
-	out, err := entries[0].Entry.MarshalBinary()
+	return newLedgerEntryAndTTL(h.arena, entries[0])
+}
+
+// SnapshotSourceGetMany is the batched counterpart of SnapshotSourceGet, which the Rust preflight code uses to
+// prefetch the entries it's going to read in a single GetLedgerEntries() call. It returns one (possibly missing)
+// entry per key, or an empty vector on failure.
+//
+//export SnapshotSourceGetMany
+func SnapshotSourceGetMany(handle C.uintptr_t, cLedgerKeys C.xdr_vector_t, ledgerSeq C.uint32_t,
+) C.ledger_entry_and_ttl_vector_t {
+	h, ok := cgo.Handle(handle).Value().(snapshotSourceHandle)
+	if !ok {
+		panic("invalid handle type: expected snapshotSourceHandle")
+	}
+	ledgerKeysXDR := GoXDRVector(cLedgerKeys)
+	ledgerKeys := make([]xdr.LedgerKey, len(ledgerKeysXDR))
+	for i, ledgerKeyXDR := range ledgerKeysXDR {
+		// Keys may be unknown to this XDR version (e.g. config settings of a newer protocol), in which case they're
+		// fetched one by one later on, if at all
+		if err := xdr.SafeUnmarshal(ledgerKeyXDR, &ledgerKeys[i]); err != nil {
+			h.logger.WithError(err).Debug("SnapshotSourceGetMany(): cannot unmarshal ledger key")
+			return C.ledger_entry_and_ttl_vector_t{}
+		}
+	}
+	entries, readLedgerSeq, err := h.ledgerEntryGetter.GetLedgerEntries(h.ctx, ledgerKeys)
 	if err != nil {
-		panic(err)
+		h.logger.WithError(err).Error("SnapshotSourceGetMany(): GetLedgerEntries() failed")
+		return C.ledger_entry_and_ttl_vector_t{}
+	}
+	if readLedgerSeq != uint32(ledgerSeq) {
+		h.logger.Errorf("SnapshotSourceGetMany(): GetLedgerEntries() read ledger %d instead of %d", readLedgerSeq, ledgerSeq)
+		return C.ledger_entry_and_ttl_vector_t{}
+	}
+	if len(ledgerKeys) == 0 {
+		return C.ledger_entry_and_ttl_vector_t{}
 	}
 
-	result := C.ledger_entry_and_ttl_t{
-		entry: C.xdr_t{
-			xdr: (*C.uchar)(C.CBytes(out)),
-			len: C.size_t(len(out)),
-		},
-		ttl: -1, // missing TTL
+	// Only the existing entries are returned, in no particular order
+	entriesByKey := make(map[string]ledgerentries.LedgerKeyAndEntry, len(entries))
+	for _, entry := range entries {
+		keyXDR, err := entry.Key.MarshalBinary()
+		if err != nil {
+			panic(err)
+		}
+		entriesByKey[string(keyXDR)] = entry
 	}
-	if entries[0].LiveUntilLedgerSeq != nil {
-		result.ttl = C.int64_t(*entries[0].LiveUntilLedgerSeq)
+	array := (*C.ledger_entry_and_ttl_t)(h.arena.malloc(
+		C.size_t(len(ledgerKeys)) * C.size_t(unsafe.Sizeof(C.ledger_entry_and_ttl_t{}))))
+	arraySlice := unsafe.Slice(array, len(ledgerKeys))
+	for i, ledgerKeyXDR := range ledgerKeysXDR {
+		arraySlice[i] = C.ledger_entry_and_ttl_t{}
+		if entry, ok := entriesByKey[string(ledgerKeyXDR)]; ok {
+			arraySlice[i] = newLedgerEntryAndTTL(h.arena, entry)
+		}
+	}
+	return C.ledger_entry_and_ttl_vector_t{
+		array: array,
+		len:   C.size_t(len(ledgerKeys)),
 	}
-	return result
 }
 
-func FreeGoXDR(xdr C.xdr_t) {
-	C.free(unsafe.Pointer(xdr.xdr))
+func newLedgerEntryAndTTL(arena *xdrArena, entry ledgerentries.LedgerKeyAndEntry) C.ledger_entry_and_ttl_t {
+	out, err := entry.Entry.MarshalBinary()
+	if err != nil {
+		panic(err)
+	}
+	result := C.ledger_entry_and_ttl_t{
+		entry: arena.XDR(out),
+		ttl:   -1, // missing TTL
+	}
+	if entry.LiveUntilLedgerSeq != nil {
+		result.ttl = C.int64_t(*entry.LiveUntilLedgerSeq)
+	}
+	return result
 }
 
+// FreeGoLedgerEntryAndTTL is a no-op, since the entries returned by
+// SnapshotSourceGet are owned by the arena of the preflight call, which releases
+// them once the call returns.
+//
 //export FreeGoLedgerEntryAndTTL
-func FreeGoLedgerEntryAndTTL(leTTL C.ledger_entry_and_ttl_t) {
-	C.free(unsafe.Pointer(leTTL.entry.xdr))
-}
+func FreeGoLedgerEntryAndTTL(C.ledger_entry_and_ttl_t) {}
+
+// FreeGoLedgerEntryAndTTLVector is a no-op for the same reason as
+// FreeGoLedgerEntryAndTTL.
+//
+//export FreeGoLedgerEntryAndTTLVector
+func FreeGoLedgerEntryAndTTLVector(C.ledger_entry_and_ttl_vector_t) {}
 
 type Parameters struct {
 	Logger            *log.Entry
@@ -106,6 +329,24 @@ type Parameters struct {
 	EnableDebug       bool
 	AuthMode          string
 	ProtocolVersion   uint32
+
+	// LedgerEntryOverrides replace (or add to) the ledger entries obtained
+	// from LedgerEntryGetter during the simulation
+	LedgerEntryOverrides []xdr.LedgerEntry
+
+	// ConfigUpgradeSet optionally replaces the network config settings read
+	// from LedgerEntryGetter during the simulation
+	ConfigUpgradeSet *xdr.ConfigUpgradeSet
+
+	// SimulatedProtocolVersion optionally pins the protocol to simulate with,
+	// which can be at most one version away from ProtocolVersion (the one of
+	// the ledger)
+	SimulatedProtocolVersion uint32
+
+	// LedgerOverride optionally replaces the sequence and timestamp of the
+	// ledger seen by the simulation, but not the ledger its state is read
+	// from (LedgerSeq)
+	LedgerOverride *protocol.LedgerOverride
 }
 
 type XDRDiff struct {
@@ -113,9 +354,32 @@ type XDRDiff struct {
 	After  []byte // optional after XDR
 }
 
+type FootprintEntryBytes struct {
+	Key        []byte // LedgerKey XDR
+	ReadBytes  uint32
+	WriteBytes uint32
+}
+
+type RentChange struct {
+	Key                []byte // LedgerKey XDR
+	SizeBytes          uint32 // size the rent is charged on, after the change
+	OldLiveUntilLedger uint32
+	NewLiveUntilLedger uint32
+	RentFee            int64
+}
+
+// ErrorDetails categorizes the error of a preflight
+type ErrorDetails struct {
+	Category   string // one of the protocol.ErrorCategory* values
+	Error      []byte // ScError XDR, empty if the error didn't come from the host
+	ContractID []byte // innermost contract call executing when the error was raised, empty if unknown
+}
+
 type Preflight struct {
 	Error                     string
-	Events                    [][]byte // DiagnosticEvents XDR
+	ErrorDetails              *ErrorDetails // missing if the error couldn't be categorized
+	ContractEvents            [][]byte // ContractEvents XDR
+	DiagnosticEvents          [][]byte // DiagnosticEvents XDR
 	TransactionData           []byte   // SorobanTransactionData XDR
 	MinFee                    int64
 	Result                    []byte   // XDR SCVal in base64
@@ -125,15 +389,114 @@ type Preflight struct {
 	PreRestoreTransactionData []byte // SorobanTransactionData XDR
 	PreRestoreMinFee          int64
 	LedgerEntryDiff           []XDRDiff
+	FootprintBytes            []FootprintEntryBytes
+	RentChanges               []RentChange
+	CallTrace                 []*CallFrame // only when debugging is enabled
+	WasmValidationEvents      [][]byte     // DiagnosticEvents XDR explaining why an uploaded Wasm module is rejected
+	WasmDiagnostics           []WasmDiagnostic
+	CreatedContractID         *xdr.ContractId // only for the host functions creating a contract
+	Warnings                  []protocol.SimulationWarning
+	Spans                     []Span             // phases of the preflight, see recordSpans()
+	LinearMemoryBytes         *uint64            // only when ResourceConfig.MeasureLinearMemory is set
+	Logs                      []string           // diagnostic events as printed by the host, only when debugging
+	RestoreStatuses           []RestoreKeyStatus // only for restores
+	// Optional sections of the result by tag, see preflight_extension_t. The
+	// sections of the tags unknown to this version are kept as they are.
+	Extensions map[uint32][]byte
+	// Only for successful simulations
+	FeeBreakdown *FeeBreakdown
+	// Contract accounts (one per authorization the invocation required of
+	// them) whose __check_auth wasn't run, only when
+	// ResourceConfig.SkipCheckAuth is set
+	StubbedCheckAuth []string
+}
+
+// RestoreKeyStatus tells whether a key of a restore can be restored.
+type RestoreKeyStatus struct {
+	Key    []byte // LedgerKey XDR
+	Status string // one of the protocol.RestoreStatus* values
 }
 
-func CXDR(xdr []byte) C.xdr_t {
+// FeeBreakdown splits the resource fee of the transaction data by what it pays
+// for, as decoded from the PREFLIGHT_EXTENSION_FEE_BREAKDOWN extension.
+type FeeBreakdown struct {
+	Instructions    int64 `json:"instructions"`
+	ReadEntries     int64 `json:"readEntries"`
+	WriteEntries    int64 `json:"writeEntries"`
+	ReadBytes       int64 `json:"readBytes"`
+	WriteBytes      int64 `json:"writeBytes"`
+	Historical      int64 `json:"historical"`
+	TransactionSize int64 `json:"transactionSize"`
+	Events          int64 `json:"events"`
+	Rent            int64 `json:"rent"`
+}
+
+// Span is a phase of a preflight run by the preflight library, e.g. loading
+// the network configuration or executing the host function.
+type Span struct {
+	Name     string
+	Start    time.Time
+	Duration time.Duration
+}
+
+// xdrArena hands XDR buffers owned by Go over to the preflight library without
+// copying them. The buffers are pinned (so that C memory can point to them)
+// until Release is called, which must happen once the library is done with
+// them, i.e. after the preflight call returns. The operations of a batch are
+// simulated in parallel, so the arena is used concurrently by their callbacks.
+type xdrArena struct {
+	mu     sync.Mutex
+	pinner runtime.Pinner
+	arrays []unsafe.Pointer // C memory holding the vectors handed over
+}
+
+func (a *xdrArena) XDR(xdr []byte) C.xdr_t {
+	if len(xdr) == 0 {
+		return C.xdr_t{}
+	}
+	a.mu.Lock()
+	a.pinner.Pin(&xdr[0])
+	a.mu.Unlock()
 	return C.xdr_t{
-		xdr: (*C.uchar)(C.CBytes(xdr)),
+		xdr: (*C.uchar)(unsafe.Pointer(&xdr[0])),
 		len: C.size_t(len(xdr)),
 	}
 }
 
+func (a *xdrArena) XDRVector(xdrs [][]byte) C.xdr_vector_t {
+	if len(xdrs) == 0 {
+		return C.xdr_vector_t{}
+	}
+	array := (*C.xdr_t)(a.malloc(C.size_t(len(xdrs)) * C.size_t(unsafe.Sizeof(C.xdr_t{}))))
+	arraySlice := unsafe.Slice(array, len(xdrs))
+	for i, v := range xdrs {
+		arraySlice[i] = a.XDR(v)
+	}
+	return C.xdr_vector_t{
+		array: array,
+		len:   C.size_t(len(xdrs)),
+	}
+}
+
+// malloc allocates C memory freed by Release
+func (a *xdrArena) malloc(size C.size_t) unsafe.Pointer {
+	array := C.malloc(size)
+	a.mu.Lock()
+	a.arrays = append(a.arrays, array)
+	a.mu.Unlock()
+	return array
+}
+
+func (a *xdrArena) Release() {
+	a.mu.Lock()
+	defer a.mu.Unlock()
+	a.pinner.Unpin()
+	for _, array := range a.arrays {
+		C.free(array)
+	}
+	a.arrays = nil
+}
+
 func GoXDR(xdr C.xdr_t) []byte {
 	return C.GoBytes(unsafe.Pointer(xdr.xdr), C.int(xdr.len))
 }
@@ -157,117 +520,661 @@ func GoXDRDiffVector(xdrDiffVector C.xdr_diff_vector_t) []XDRDiff {
 	return result
 }
 
+func GoFootprintEntryBytesVector(vector C.footprint_entry_bytes_vector_t) []FootprintEntryBytes {
+	result := make([]FootprintEntryBytes, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for i, v := range inputSlice {
+		result[i].Key = GoXDR(v.key)
+		result[i].ReadBytes = uint32(v.read_bytes)
+		result[i].WriteBytes = uint32(v.write_bytes)
+	}
+	return result
+}
+
+func GoRentChangeVector(vector C.rent_change_vector_t) []RentChange {
+	result := make([]RentChange, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for i, v := range inputSlice {
+		result[i].Key = GoXDR(v.key)
+		result[i].SizeBytes = uint32(v.size_bytes)
+		result[i].OldLiveUntilLedger = uint32(v.old_live_until_ledger)
+		result[i].NewLiveUntilLedger = uint32(v.new_live_until_ledger)
+		result[i].RentFee = int64(v.rent_fee)
+	}
+	return result
+}
+
+func GoPreflightWarningVector(vector C.preflight_warning_vector_t) []protocol.SimulationWarning {
+	result := make([]protocol.SimulationWarning, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for i, v := range inputSlice {
+		switch v.kind {
+		case C.WARNING_RESOURCE_NEAR_LIMIT:
+			result[i].Kind = protocol.WarningResourceNearLimit
+		case C.WARNING_NON_ROOT_AUTH:
+			result[i].Kind = protocol.WarningNonRootAuth
+		case C.WARNING_DEPRECATED_HOST_FUNCTION:
+			result[i].Kind = protocol.WarningDeprecatedHostFunction
+		case C.WARNING_ENTRY_NEAR_ARCHIVAL:
+			result[i].Kind = protocol.WarningEntryNearArchival
+		case C.WARNING_LINEAR_MEMORY_UNMEASURED:
+			result[i].Kind = protocol.WarningLinearMemoryUnmeasured
+		}
+		result[i].Message = C.GoString(v.message)
+	}
+	return result
+}
+
+func GoPreflightSpanVector(vector C.preflight_span_vector_t) []Span {
+	result := make([]Span, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for i, v := range inputSlice {
+		result[i] = Span{
+			Name:     C.GoString(v.name),
+			Start:    time.Unix(0, int64(v.start_unix_nanos)), //nolint:gosec // fits until 2262
+			Duration: time.Duration(v.duration_nanos),        //nolint:gosec
+		}
+	}
+	return result
+}
+
+func GoRestoreKeyStatusVector(vector C.restore_key_status_vector_t) []RestoreKeyStatus {
+	result := make([]RestoreKeyStatus, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for i, v := range inputSlice {
+		result[i].Key = GoXDR(v.key)
+		switch v.status {
+		case C.RESTORE_STATUS_RESTORABLE:
+			result[i].Status = protocol.RestoreStatusRestorable
+		case C.RESTORE_STATUS_ALREADY_LIVE:
+			result[i].Status = protocol.RestoreStatusAlreadyLive
+		case C.RESTORE_STATUS_NOT_FOUND:
+			result[i].Status = protocol.RestoreStatusNotFound
+		case C.RESTORE_STATUS_TOO_LARGE:
+			result[i].Status = protocol.RestoreStatusTooLarge
+		}
+	}
+	return result
+}
+
+func GoPreflightExtensionVector(vector C.preflight_extension_vector_t) map[uint32][]byte {
+	if vector.len == 0 {
+		return nil
+	}
+	result := make(map[uint32][]byte, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for _, v := range inputSlice {
+		result[uint32(v.tag)] = GoXDR(v.data)
+	}
+	return result
+}
+
+func GoStringVector(vector C.string_vector_t) []string {
+	result := make([]string, vector.len)
+	inputSlice := unsafe.Slice(vector.array, vector.len)
+	for i, v := range inputSlice {
+		result[i] = C.GoString(v)
+	}
+	return result
+}
+
 func GetPreflight(ctx context.Context, params Parameters) (Preflight, error) {
+	ctx, span := startPreflightSpan(ctx)
+	defer span.End()
+	var preflight Preflight
+	var err error
 	switch params.OpBody.Type {
 	case xdr.OperationTypeInvokeHostFunction:
-		return getInvokeHostFunctionPreflight(ctx, params)
-	case xdr.OperationTypeExtendFootprintTtl, xdr.OperationTypeRestoreFootprint:
-		return getFootprintTTLPreflight(ctx, params)
+		preflight, err = getInvokeHostFunctionPreflight(ctx, params)
+	case xdr.OperationTypeExtendFootprintTtl:
+		preflight, err = getExtendTTLPreflight(ctx, params)
+	case xdr.OperationTypeRestoreFootprint:
+		preflight, err = getRestorePreflight(ctx, params)
 	default:
 		return Preflight{}, fmt.Errorf("unsupported operation type: %s", params.OpBody.Type.String())
 	}
+	if err == nil {
+		recordSpans(ctx, preflight.Spans)
+	}
+	return preflight, err
 }
 
 func getLedgerInfo(params Parameters) C.ledger_info_t {
-	return C.ledger_info_t{
-		network_passphrase: C.CString(params.NetworkPassphrase),
-		sequence_number:    C.uint32_t(params.LedgerSeq),
-		protocol_version:   C.uint32_t(params.ProtocolVersion),
-		timestamp:          C.uint64_t(time.Now().Unix()),
-		base_reserve:       defaultBaseReserve,
-		bucket_list_size:   C.uint64_t(params.BucketListSize),
+	ledgerInfo := C.ledger_info_t{
+		network_passphrase:         C.CString(params.NetworkPassphrase),
+		sequence_number:            C.uint32_t(params.LedgerSeq),
+		protocol_version:           C.uint32_t(params.ProtocolVersion),
+		timestamp:                  C.uint64_t(time.Now().Unix()),
+		base_reserve:               defaultBaseReserve,
+		bucket_list_size:           C.uint64_t(params.BucketListSize),
+		simulated_protocol_version: C.uint32_t(params.SimulatedProtocolVersion),
+	}
+	if override := params.LedgerOverride; override != nil {
+		ledgerInfo.sequence_number_override = C.uint32_t(override.Sequence)
+		ledgerInfo.timestamp_override = C.uint64_t(override.Timestamp)
+	}
+	return ledgerInfo
+}
+
+func getAdjustmentFactor(factor *protocol.AdjustmentFactor) C.adjustment_factor_t {
+	if factor == nil {
+		// leave the default adjustment in place
+		return C.adjustment_factor_t{}
+	}
+	return C.adjustment_factor_t{
+		set:                   true,
+		multiplicative_factor: C.double(factor.MultiplicativeFactor),
+		additive_factor:       C.uint32_t(factor.AdditiveFactor),
+	}
+}
+
+func getLedgerKeysCXDR(arena *xdrArena, keys []xdr.LedgerKey) (C.xdr_vector_t, error) {
+	keysXDR := make([][]byte, len(keys))
+	for i, key := range keys {
+		keyXDR, err := key.MarshalBinary()
+		if err != nil {
+			return C.xdr_vector_t{}, fmt.Errorf("cannot marshal ledger key: %w", err)
+		}
+		keysXDR[i] = keyXDR
 	}
+	return arena.XDRVector(keysXDR), nil
 }
 
-func getFootprintTTLPreflight(ctx context.Context, params Parameters) (Preflight, error) {
-	opBodyXDR, err := params.OpBody.MarshalBinary()
+func getLedgerEntriesCXDR(arena *xdrArena, entries []xdr.LedgerEntry) (C.xdr_vector_t, error) {
+	entriesXDR := make([][]byte, len(entries))
+	for i, entry := range entries {
+		entryXDR, err := entry.MarshalBinary()
+		if err != nil {
+			return C.xdr_vector_t{}, fmt.Errorf("cannot marshal ledger entry: %w", err)
+		}
+		entriesXDR[i] = entryXDR
+	}
+	return arena.XDRVector(entriesXDR), nil
+}
+
+func getExtendTTLPreflight(ctx context.Context, params Parameters) (Preflight, error) {
+	var arena xdrArena
+	defer arena.Release()
+	keysCXDR, err := getLedgerKeysCXDR(&arena, params.Footprint.ReadOnly)
 	if err != nil {
 		return Preflight{}, err
 	}
-	opBodyCXDR := CXDR(opBodyXDR)
-	footprintXDR, err := params.Footprint.MarshalBinary()
+	overridesCXDR, err := getLedgerEntriesCXDR(&arena, params.LedgerEntryOverrides)
 	if err != nil {
-		return Preflight{}, fmt.Errorf("cannot marshal footprint: %w", err)
-	}
-	footprintCXDR := CXDR(footprintXDR)
-	ssh := snapshotSourceHandle{
-		ledgerEntryGetter: params.LedgerEntryGetter,
-		ctx:               ctx,
-		logger:            params.Logger,
+		return Preflight{}, err
 	}
+	ssh := newSnapshotSourceHandle(ctx, params, &arena)
 	handle := cgo.NewHandle(ssh)
 	defer handle.Delete()
 
-	res := C.preflight_footprint_ttl_op(
+	res := C.preflight_extend_ttl_op(
 		C.uintptr_t(handle),
-		opBodyCXDR,
-		footprintCXDR,
+		keysCXDR,
+		C.uint32_t(params.OpBody.MustExtendFootprintTtlOp().ExtendTo),
 		getLedgerInfo(params),
+		overridesCXDR,
 	)
 
-	FreeGoXDR(opBodyCXDR)
-	FreeGoXDR(footprintCXDR)
-
 	return GoPreflight(res), nil
 }
 
-func getInvokeHostFunctionPreflight(ctx context.Context, params Parameters) (Preflight, error) {
-	invokeHostFunctionXDR, err := params.OpBody.MustInvokeHostFunctionOp().MarshalBinary()
+func getRestorePreflight(ctx context.Context, params Parameters) (Preflight, error) {
+	var arena xdrArena
+	defer arena.Release()
+	keysCXDR, err := getLedgerKeysCXDR(&arena, params.Footprint.ReadWrite)
 	if err != nil {
 		return Preflight{}, err
 	}
-	invokeHostFunctionCXDR := CXDR(invokeHostFunctionXDR)
-	sourceAccountXDR, err := params.SourceAccount.MarshalBinary()
+	overridesCXDR, err := getLedgerEntriesCXDR(&arena, params.LedgerEntryOverrides)
 	if err != nil {
 		return Preflight{}, err
 	}
-	sourceAccountCXDR := CXDR(sourceAccountXDR)
+	ssh := newSnapshotSourceHandle(ctx, params, &arena)
+	handle := cgo.NewHandle(ssh)
+	defer handle.Delete()
 
-	ssh := snapshotSourceHandle{
-		ledgerEntryGetter: params.LedgerEntryGetter,
-		ctx:               ctx,
-		logger:            params.Logger,
-	}
+	res := C.preflight_restore_op(
+		C.uintptr_t(handle),
+		keysCXDR,
+		getLedgerInfo(params),
+		overridesCXDR,
+	)
+
+	return GoPreflight(res), nil
+}
+
+// NetworkConfig is the network configuration the preflight library loads from
+// the config settings of a ledger, whatever the protocol version of the ledger.
+type NetworkConfig struct {
+	Fees          NetworkFees          `json:"fees"`
+	Limits        NetworkLimits        `json:"limits"`
+	StateArchival NetworkStateArchival `json:"stateArchival"`
+}
+
+type NetworkFees struct {
+	FeePerInstructionIncrement int64 `json:"feePerInstructionIncrement"`
+	FeePerReadEntry            int64 `json:"feePerReadEntry"` // only the entries read from disk since protocol 23
+	FeePerWriteEntry           int64 `json:"feePerWriteEntry"`
+	FeePerRead1KB              int64 `json:"feePerRead1kb"`
+	FeePerWrite1KB             int64 `json:"feePerWrite1kb"`
+	FeePerRent1KB              int64 `json:"feePerRent1kb"` // the write fee before protocol 23
+	FeePerHistorical1KB        int64 `json:"feePerHistorical1kb"`
+	FeePerContractEvent1KB     int64 `json:"feePerContractEvent1kb"`
+	FeePerTransactionSize1KB   int64 `json:"feePerTransactionSize1kb"`
+}
+
+type NetworkLimits struct {
+	TxMaxInstructions int64  `json:"txMaxInstructions"`
+	TxMemoryLimit     uint32 `json:"txMemoryLimit"`
+}
+
+type NetworkStateArchival struct {
+	MinTemporaryTTL               uint32 `json:"minTemporaryTtl"`
+	MinPersistentTTL              uint32 `json:"minPersistentTtl"`
+	MaxEntryTTL                   uint32 `json:"maxEntryTtl"`
+	PersistentRentRateDenominator int64  `json:"persistentRentRateDenominator"`
+	TemporaryRentRateDenominator  int64  `json:"temporaryRentRateDenominator"`
+}
+
+// GetNetworkConfig returns the network configuration of the ledger of the
+// parameters (of which only the ledger entry getter, ledger sequence, bucket
+// list size, protocol version and config upgrade set are used).
+func GetNetworkConfig(ctx context.Context, params Parameters) (NetworkConfig, error) {
+	var arena xdrArena
+	defer arena.Release()
+	ssh := newSnapshotSourceHandle(ctx, params, &arena)
 	handle := cgo.NewHandle(ssh)
 	defer handle.Delete()
+
+	res := C.preflight_get_network_config(C.uintptr_t(handle), getLedgerInfo(params))
+	defer C.free_network_config_result(res)
+	if errorMsg := C.GoString(res.error); errorMsg != "" {
+		return NetworkConfig{}, errors.New(errorMsg)
+	}
+	var config NetworkConfig
+	if err := json.Unmarshal([]byte(C.GoString(res.json)), &config); err != nil {
+		return NetworkConfig{}, fmt.Errorf("cannot decode network config: %w", err)
+	}
+	return config, nil
+}
+
+func getResourceConfig(config protocol.ResourceConfig) C.resource_config_t {
 	resourceConfig := C.resource_config_t{
-		instruction_leeway: C.uint64_t(params.ResourceConfig.InstructionLeeway),
+		instruction_leeway:    C.uint64_t(config.InstructionLeeway),
+		instructions:          getAdjustmentFactor(config.Instructions),
+		read_bytes:            getAdjustmentFactor(config.ReadBytes),
+		write_bytes:           getAdjustmentFactor(config.WriteBytes),
+		tx_size:               getAdjustmentFactor(config.TxSize),
+		refundable_fee:        getAdjustmentFactor(config.RefundableFee),
+		skip_restore_preamble: C.bool(config.SkipRestorePreamble),
+		relax_network_limits:  C.bool(config.RelaxNetworkLimits),
+		max_instructions:      C.uint64_t(config.MaxInstructions),
+		max_memory_bytes:      C.uint64_t(config.MaxMemoryBytes),
+		minimize_footprint:    C.bool(config.MinimizeFootprint),
+		skip_check_auth:       C.bool(config.SkipCheckAuth),
+		measure_linear_memory: C.bool(config.MeasureLinearMemory),
 	}
+	if config.PrngSeed != nil {
+		resourceConfig.has_prng_seed = true
+		resourceConfig.prng_seed = C.uint64_t(*config.PrngSeed)
+	}
+	resourceConfig.signature_expiration_ledgers = C.uint32_t(protocol.DefaultSignatureExpirationLedgers)
+	if config.SignatureExpirationLedgers != nil {
+		resourceConfig.signature_expiration_ledgers = C.uint32_t(*config.SignatureExpirationLedgers)
+	}
+	return resourceConfig
+}
 
-	// Convert string to enum integer (see shared.rs::AuthMode) for FFI boundary.
-	var authMode uint32
-	switch params.AuthMode {
+// getAuthMode converts the auth mode string to the enum integer (see
+// shared.rs::AuthMode) used across the FFI boundary.
+func getAuthMode(authMode string) (uint32, error) {
+	switch authMode {
 	case protocol.AuthModeEnforce:
-		authMode = 0
+		return 0, nil
 	case protocol.AuthModeRecord:
-		authMode = 1
+		return 1, nil
 	case protocol.AuthModeRecordAllowNonroot:
-		authMode = 2
+		return 2, nil
+	case protocol.AuthModeAuto:
+		return 3, nil
+	case protocol.AuthModeEnforceMockSignatures:
+		return 4, nil
 	default:
-		return Preflight{}, fmt.Errorf("invalid auth mode: '%s'", params.AuthMode)
+		return 0, fmt.Errorf("invalid auth mode: '%s'", authMode)
+	}
+}
+
+func getInvokeHostFunctionPreflight(ctx context.Context, params Parameters) (Preflight, error) {
+	var arena xdrArena
+	defer arena.Release()
+	invokeHostFunctionXDR, err := params.OpBody.MustInvokeHostFunctionOp().MarshalBinary()
+	if err != nil {
+		return Preflight{}, err
+	}
+	sourceAccountXDR, err := params.SourceAccount.MarshalBinary()
+	if err != nil {
+		return Preflight{}, err
+	}
+	overridesCXDR, err := getLedgerEntriesCXDR(&arena, params.LedgerEntryOverrides)
+	if err != nil {
+		return Preflight{}, err
+	}
+	footprintKeys := append(slices.Clone(params.Footprint.ReadOnly), params.Footprint.ReadWrite...)
+	footprintKeysCXDR, err := getLedgerKeysCXDR(&arena, footprintKeys)
+	if err != nil {
+		return Preflight{}, err
+	}
+	extraFootprintXDR, err := getExtraFootprintXDR(params.ResourceConfig.ExtraFootprint)
+	if err != nil {
+		return Preflight{}, err
 	}
 
+	ssh := newSnapshotSourceHandle(ctx, params, &arena)
+	handle := cgo.NewHandle(ssh)
+	defer handle.Delete()
+
+	authMode, err := getAuthMode(params.AuthMode)
+	if err != nil {
+		return Preflight{}, err
+	}
+	cancel := newCancelToken(ctx)
+	defer cancel.Release()
+
 	res := C.preflight_invoke_hf_op(
 		C.uintptr_t(handle),
-		invokeHostFunctionCXDR,
-		sourceAccountCXDR,
+		arena.XDR(invokeHostFunctionXDR),
+		arena.XDR(sourceAccountXDR),
 		getLedgerInfo(params),
-		resourceConfig,
+		overridesCXDR,
+		footprintKeysCXDR,
+		arena.XDR(extraFootprintXDR),
+		getResourceConfig(params.ResourceConfig),
 		C.bool(params.EnableDebug),
 		C.uint32_t(authMode),
+		cancel.token,
 	)
-	FreeGoXDR(invokeHostFunctionCXDR)
-	FreeGoXDR(sourceAccountCXDR)
+	preflight := GoPreflight(res)
+	if err := getCancellationError(ctx, preflight); err != nil {
+		return Preflight{}, err
+	}
+	if err := decodeInvokeHostFunctionPreflight(&preflight, params); err != nil {
+		return Preflight{}, err
+	}
+	return preflight, nil
+}
 
-	return GoPreflight(res), nil
+// decodeInvokeHostFunctionPreflight fills the parts of the result of an
+// InvokeHostFunction preflight which are decoded on the Go side.
+func decodeInvokeHostFunctionPreflight(preflight *Preflight, params Parameters) error {
+	var err error
+	if params.EnableDebug {
+		preflight.CallTrace, err = GetCallTrace(preflight.DiagnosticEvents)
+		if err != nil {
+			return err
+		}
+	}
+	preflight.WasmDiagnostics, err = GetWasmDiagnostics(preflight.WasmValidationEvents)
+	if err != nil {
+		return err
+	}
+	preflight.CreatedContractID, err = getCreatedContractID(
+		params.OpBody.MustInvokeHostFunctionOp().HostFunction, params.NetworkPassphrase)
+	return err
+}
+
+// getExtraFootprintXDR decodes the base64 footprint to add to the simulated one
+// into the XDR passed to the preflight library, nil if there is none.
+func getExtraFootprintXDR(extraFootprintB64 string) ([]byte, error) {
+	if extraFootprintB64 == "" {
+		return nil, nil
+	}
+	var footprint xdr.LedgerFootprint
+	if err := xdr.SafeUnmarshalBase64(extraFootprintB64, &footprint); err != nil {
+		return nil, fmt.Errorf("could not unmarshal extraFootprint: %w", err)
+	}
+	return footprint.MarshalBinary()
+}
+
+// getCreatedContractID derives the ID of the contract created by the host
+// function (if it creates one) the way the host does, from the hash of its
+// preimage.
+func getCreatedContractID(hostFunction xdr.HostFunction, networkPassphrase string) (*xdr.ContractId, error) {
+	var contractIDPreimage xdr.ContractIdPreimage
+	switch hostFunction.Type {
+	case xdr.HostFunctionTypeHostFunctionTypeCreateContract:
+		contractIDPreimage = hostFunction.MustCreateContract().ContractIdPreimage
+	case xdr.HostFunctionTypeHostFunctionTypeCreateContractV2:
+		contractIDPreimage = hostFunction.MustCreateContractV2().ContractIdPreimage
+	default:
+		return nil, nil //nolint:nilnil // no contract is created
+	}
+	preimage := xdr.HashIdPreimage{
+		Type: xdr.EnvelopeTypeEnvelopeTypeContractId,
+		ContractId: &xdr.HashIdPreimageContractId{
+			NetworkId:          sha256.Sum256([]byte(networkPassphrase)),
+			ContractIdPreimage: contractIDPreimage,
+		},
+	}
+	preimageXDR, err := preimage.MarshalBinary()
+	if err != nil {
+		return nil, err
+	}
+	contractID := xdr.ContractId(sha256.Sum256(preimageXDR))
+	return &contractID, nil
+}
+
+// GetInvokeHostFunctionPreflights simulates a batch of InvokeHostFunction
+// operations against a single ledger snapshot, returning one result per
+// operation in the same order. The operations are simulated in parallel by the
+// preflight library, so the ledger entry getter is called concurrently.
+//
+// The ledger entry getter, ledger information, ledger entry overrides, config
+// upgrade set, resource config, debug flag and auth mode are shared by the whole
+// batch and taken from the first parameters.
+func GetInvokeHostFunctionPreflights(ctx context.Context, params []Parameters) ([]Preflight, error) {
+	if len(params) == 0 {
+		return nil, nil
+	}
+	shared := params[0]
+	invokeHostFunctionXDRs, sourceAccountXDRs, err := getBatchXDRs(params)
+	if err != nil {
+		return nil, err
+	}
+	authMode, err := getAuthMode(shared.AuthMode)
+	if err != nil {
+		return nil, err
+	}
+	var arena xdrArena
+	defer arena.Release()
+	overridesCXDR, err := getLedgerEntriesCXDR(&arena, shared.LedgerEntryOverrides)
+	if err != nil {
+		return nil, err
+	}
+
+	ssh := newSnapshotSourceHandle(ctx, shared, &arena)
+	handle := cgo.NewHandle(ssh)
+	defer handle.Delete()
+	ctx, span := startPreflightSpan(ctx)
+	defer span.End()
+	cancel := newCancelToken(ctx)
+	defer cancel.Release()
+
+	res := C.preflight_invoke_hf_ops(
+		C.uintptr_t(handle),
+		arena.XDRVector(invokeHostFunctionXDRs),
+		arena.XDRVector(sourceAccountXDRs),
+		getLedgerInfo(shared),
+		overridesCXDR,
+		getResourceConfig(shared.ResourceConfig),
+		C.bool(shared.EnableDebug),
+		C.uint32_t(authMode),
+		cancel.token,
+	)
+	preflights := GoPreflights(res)
+	if err := getCancellationError(ctx, preflights...); err != nil {
+		return nil, err
+	}
+	return decodeInvokeHostFunctionPreflights(ctx, preflights, params)
+}
+
+// GetInvokeHostFunctionPreflightsFromSnapshot is like
+// GetInvokeHostFunctionPreflights, but the operations are simulated against the
+// given ledger entries, which are handed over to the simulation at once instead
+// of being fetched from the ledger entry getter (which isn't used). The snapshot
+// must hold every entry the operations may read, including the network config
+// settings, the other entries being considered missing.
+func GetInvokeHostFunctionPreflightsFromSnapshot(
+	ctx context.Context,
+	params []Parameters,
+	snapshot []ledgerentries.LedgerKeyAndEntry,
+) ([]Preflight, error) {
+	if len(params) == 0 {
+		return nil, nil
+	}
+	shared := params[0]
+	if shared.ConfigUpgradeSet != nil {
+		return nil, errors.New("config upgrade sets aren't supported when simulating against a snapshot")
+	}
+	invokeHostFunctionXDRs, sourceAccountXDRs, err := getBatchXDRs(params)
+	if err != nil {
+		return nil, err
+	}
+	authMode, err := getAuthMode(shared.AuthMode)
+	if err != nil {
+		return nil, err
+	}
+	var arena xdrArena
+	defer arena.Release()
+	overridesCXDR, err := getLedgerEntriesCXDR(&arena, shared.LedgerEntryOverrides)
+	if err != nil {
+		return nil, err
+	}
+	snapshotC, err := getLedgerSnapshot(&arena, snapshot)
+	if err != nil {
+		return nil, err
+	}
+	ctx, span := startPreflightSpan(ctx)
+	defer span.End()
+	cancel := newCancelToken(ctx)
+	defer cancel.Release()
+
+	res := C.preflight_invoke_hf_ops_from_snapshot(
+		snapshotC,
+		arena.XDRVector(invokeHostFunctionXDRs),
+		arena.XDRVector(sourceAccountXDRs),
+		getLedgerInfo(shared),
+		overridesCXDR,
+		getResourceConfig(shared.ResourceConfig),
+		C.bool(shared.EnableDebug),
+		C.uint32_t(authMode),
+		cancel.token,
+	)
+	preflights := GoPreflights(res)
+	if err := getCancellationError(ctx, preflights...); err != nil {
+		return nil, err
+	}
+	return decodeInvokeHostFunctionPreflights(ctx, preflights, params)
+}
+
+// decodeInvokeHostFunctionPreflights decodes the results of a batch of
+// InvokeHostFunction preflights, which share the debug flag of the batch.
+func decodeInvokeHostFunctionPreflights(
+	ctx context.Context, preflights []Preflight, params []Parameters,
+) ([]Preflight, error) {
+	for i := range preflights {
+		recordSpans(ctx, preflights[i].Spans)
+		batchParams := params[i]
+		batchParams.EnableDebug = params[0].EnableDebug
+		if err := decodeInvokeHostFunctionPreflight(&preflights[i], batchParams); err != nil {
+			return nil, err
+		}
+	}
+	return preflights, nil
+}
+
+// getBatchXDRs returns the InvokeHostFunctionOp and source account XDRs of a
+// batch of operations, checking that they can be simulated together.
+func getBatchXDRs(params []Parameters) ([][]byte, [][]byte, error) {
+	invokeHostFunctionXDRs := make([][]byte, len(params))
+	sourceAccountXDRs := make([][]byte, len(params))
+	for i, p := range params {
+		if p.OpBody.Type != xdr.OperationTypeInvokeHostFunction {
+			return nil, nil, fmt.Errorf("unsupported operation type in batch: %s", p.OpBody.Type.String())
+		}
+		if p.AuthMode != params[0].AuthMode {
+			return nil, nil, fmt.Errorf("mismatched auth modes in batch: '%s' and '%s'", params[0].AuthMode, p.AuthMode)
+		}
+		if p.ResourceConfig.ExtraFootprint != "" {
+			return nil, nil, errors.New("extraFootprint isn't supported in batch")
+		}
+		var err error
+		invokeHostFunctionXDRs[i], err = p.OpBody.MustInvokeHostFunctionOp().MarshalBinary()
+		if err != nil {
+			return nil, nil, err
+		}
+		sourceAccountXDRs[i], err = p.SourceAccount.MarshalBinary()
+		if err != nil {
+			return nil, nil, err
+		}
+	}
+	return invokeHostFunctionXDRs, sourceAccountXDRs, nil
+}
+
+// getLedgerSnapshot lays out the given entries in C memory owned by the arena,
+// which the simulation copies before reading them.
+func getLedgerSnapshot(arena *xdrArena, entries []ledgerentries.LedgerKeyAndEntry) (C.ledger_snapshot_t, error) {
+	if len(entries) == 0 {
+		return C.ledger_snapshot_t{}, nil
+	}
+	array := (*C.ledger_snapshot_entry_t)(arena.malloc(
+		C.size_t(len(entries)) * C.size_t(unsafe.Sizeof(C.ledger_snapshot_entry_t{}))))
+	arraySlice := unsafe.Slice(array, len(entries))
+	for i, entry := range entries {
+		keyXDR, err := entry.Key.MarshalBinary()
+		if err != nil {
+			return C.ledger_snapshot_t{}, err
+		}
+		arraySlice[i] = C.ledger_snapshot_entry_t{
+			key:           arena.XDR(keyXDR),
+			entry_and_ttl: newLedgerEntryAndTTL(arena, entry),
+		}
+	}
+	return C.ledger_snapshot_t{
+		array: array,
+		len:   C.size_t(len(entries)),
+	}, nil
 }
 
 func GoPreflight(result *C.preflight_result_t) Preflight {
 	defer C.free_preflight_result(result)
+	return goPreflight(result)
+}
+
+func GoPreflights(results *C.preflight_result_vector_t) []Preflight {
+	defer C.free_preflight_result_vector(results)
 
+	inputSlice := unsafe.Slice(results.array, results.len)
+	preflights := make([]Preflight, len(inputSlice))
+	for i := range inputSlice {
+		preflights[i] = goPreflight(&inputSlice[i])
+	}
+	return preflights
+}
+
+func goPreflight(result *C.preflight_result_t) Preflight {
+	// The other fields can't be read if the preflight library lays out its
+	// results differently
+	if result.version != C.PREFLIGHT_RESULT_VERSION {
+		return Preflight{
+			Error: fmt.Sprintf("unsupported preflight result version %d (expected %d)",
+				result.version, C.PREFLIGHT_RESULT_VERSION),
+		}
+	}
 	preflight := Preflight{
 		Error:                     C.GoString(result.error),
-		Events:                    GoXDRVector(result.events),
+		ErrorDetails:              goErrorDetails(result.error_details),
+		ContractEvents:            GoXDRVector(result.contract_events),
+		DiagnosticEvents:          GoXDRVector(result.diagnostic_events),
 		TransactionData:           GoXDR(result.transaction_data),
 		MinFee:                    int64(result.min_fee),
 		Result:                    GoXDR(result.result),
@@ -277,6 +1184,85 @@ func GoPreflight(result *C.preflight_result_t) Preflight {
 		PreRestoreTransactionData: GoXDR(result.pre_restore_transaction_data),
 		PreRestoreMinFee:          int64(result.pre_restore_min_fee),
 		LedgerEntryDiff:           GoXDRDiffVector(result.ledger_entry_diff),
+		FootprintBytes:            GoFootprintEntryBytesVector(result.footprint_bytes),
+		RentChanges:               GoRentChangeVector(result.rent_changes),
+		WasmValidationEvents:      GoXDRVector(result.wasm_validation_events),
+		Warnings:                  GoPreflightWarningVector(result.warnings),
+		Spans:                     GoPreflightSpanVector(result.spans),
+		Logs:                      GoStringVector(result.logs),
+		RestoreStatuses:           GoRestoreKeyStatusVector(result.restore_statuses),
+		Extensions:                GoPreflightExtensionVector(result.extensions),
 	}
+	if result.has_linear_memory_bytes {
+		linearMemoryBytes := uint64(result.linear_memory_bytes)
+		preflight.LinearMemoryBytes = &linearMemoryBytes
+	}
+	preflight.FeeBreakdown = goFeeBreakdown(preflight.Extensions)
+	preflight.StubbedCheckAuth = goStubbedCheckAuth(preflight.Extensions)
 	return preflight
 }
+
+func goFeeBreakdown(extensions map[uint32][]byte) *FeeBreakdown {
+	data, ok := extensions[C.PREFLIGHT_EXTENSION_FEE_BREAKDOWN]
+	if !ok {
+		return nil
+	}
+	var breakdown FeeBreakdown
+	if err := json.Unmarshal(data, &breakdown); err != nil {
+		return nil
+	}
+	return &breakdown
+}
+
+func goStubbedCheckAuth(extensions map[uint32][]byte) []string {
+	data, ok := extensions[C.PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH]
+	if !ok {
+		return nil
+	}
+	var addresses xdr.ScVal
+	if err := xdr.SafeUnmarshal(data, &addresses); err != nil {
+		return nil
+	}
+	vec, ok := addresses.GetVec()
+	if !ok || vec == nil {
+		return nil
+	}
+	contracts := []string{}
+	for _, val := range **vec {
+		address, ok := val.GetAddress()
+		if !ok {
+			return nil
+		}
+		contract, err := address.String()
+		if err != nil {
+			return nil
+		}
+		contracts = append(contracts, contract)
+	}
+	return contracts
+}
+
+func goErrorDetails(details C.error_details_t) *ErrorDetails {
+	var category string
+	switch details.category {
+	case C.ERROR_CATEGORY_STORAGE:
+		category = protocol.ErrorCategoryStorage
+	case C.ERROR_CATEGORY_BUDGET:
+		category = protocol.ErrorCategoryBudget
+	case C.ERROR_CATEGORY_AUTH:
+		category = protocol.ErrorCategoryAuth
+	case C.ERROR_CATEGORY_CONTRACT:
+		category = protocol.ErrorCategoryContract
+	case C.ERROR_CATEGORY_HOST:
+		category = protocol.ErrorCategoryHost
+	case C.ERROR_CATEGORY_INVALID_INPUT:
+		category = protocol.ErrorCategoryInvalidInput
+	default:
+		return nil
+	}
+	return &ErrorDetails{
+		Category:   category,
+		Error:      GoXDR(details.sc_error),
+		ContractID: GoXDR(details.contract_id),
+	}
+}
@@ -3,10 +3,13 @@ package preflight
 import (
 	"context"
 	"crypto/sha256"
+	"fmt"
 	"os"
 	"path"
 	"runtime"
+	"strings"
 	"testing"
+	"time"
 
 	"github.com/stretchr/testify/require"
 
@@ -348,6 +351,7 @@ func getPreflightParameters(t testing.TB) Parameters {
 		},
 		NetworkPassphrase: "foo",
 		LedgerEntryGetter: ledgerEntryGetter,
+		LedgerSeq:         latestSimulateTransactionLedgerSeq,
 		BucketListSize:    200,
 		// TODO: test with multiple protocol versions
 		ProtocolVersion: 22,
@@ -364,6 +368,41 @@ func TestGetPreflight(t *testing.T) {
 	require.Empty(t, result.Error)
 }
 
+// countingLedgerEntryGetter counts the GetLedgerEntries() calls, i.e. the
+// round trips from the preflight library
+type countingLedgerEntryGetter struct {
+	ledgerentries.LedgerEntryGetter
+	calls int
+}
+
+func (g *countingLedgerEntryGetter) GetLedgerEntries(ctx context.Context,
+	keys []xdr.LedgerKey,
+) ([]ledgerentries.LedgerKeyAndEntry, uint32, error) {
+	g.calls++
+	return g.LedgerEntryGetter.GetLedgerEntries(ctx, keys)
+}
+
+func TestGetPreflightPrefetchesFootprint(t *testing.T) {
+	params := getPreflightParameters(t)
+	getter := &countingLedgerEntryGetter{LedgerEntryGetter: params.LedgerEntryGetter}
+	params.LedgerEntryGetter = getter
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	calls := getter.calls
+
+	// Simulate again with the footprint declared
+	var transactionData xdr.SorobanTransactionData
+	require.NoError(t, xdr.SafeUnmarshal(result.TransactionData, &transactionData))
+	params.Footprint = transactionData.Resources.Footprint
+	getter.calls = 0
+	prefetchedResult, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, prefetchedResult.Error)
+	require.Equal(t, result.TransactionData, prefetchedResult.TransactionData)
+	require.Less(t, getter.calls, calls)
+}
+
 func TestGetPreflightDebug(t *testing.T) {
 	params := getPreflightParameters(t)
 	// Cause an error: non-existent function
@@ -386,6 +425,690 @@ func TestGetPreflightDebug(t *testing.T) {
 	require.Contains(t, resultWithoutDebug.Error, "DebugInfo not available")
 }
 
+func TestGetPreflightCallTrace(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Len(t, result.CallTrace, 1)
+	frame := result.CallTrace[0]
+	require.Equal(t, mockContractID, frame.ContractID)
+	require.Equal(t, "hello", frame.Function)
+	require.Len(t, frame.Args, 1)
+	require.NotNil(t, frame.ReturnValue)
+	require.Nil(t, frame.Trap)
+
+	// Cause a trap: non-existent function
+	params.OpBody.InvokeHostFunctionOp.HostFunction.InvokeContract.FunctionName = "bar"
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Len(t, result.CallTrace, 1)
+	require.Nil(t, result.CallTrace[0].ReturnValue)
+	require.NotNil(t, result.CallTrace[0].Trap)
+
+	params.EnableDebug = false
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.CallTrace)
+}
+
+func TestGetPreflightInvalidAdjustmentFactor(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.ResourceConfig.Instructions = &protocol.AdjustmentFactor{MultiplicativeFactor: 0.5}
+
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "invalid instructions multiplicative factor")
+	require.NotNil(t, result.ErrorDetails)
+	require.Equal(t, protocol.ErrorCategoryInvalidInput, result.ErrorDetails.Category)
+}
+
+func TestGetPreflightRestoreValidatesKeys(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.OpBody = xdr.OperationBody{
+		Type:               xdr.OperationTypeRestoreFootprint,
+		RestoreFootprintOp: &xdr.RestoreFootprintOp{},
+	}
+
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "no ledger keys provided")
+
+	var accountKey xdr.LedgerKey
+	require.NoError(t, accountKey.SetAccount(params.SourceAccount))
+	params.Footprint.ReadWrite = []xdr.LedgerKey{accountKey}
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "has no TTL")
+	require.NotNil(t, result.ErrorDetails)
+	require.Equal(t, protocol.ErrorCategoryInvalidInput, result.ErrorDetails.Category)
+}
+
+func TestGetPreflightRestoreStatuses(t *testing.T) {
+	params := getPreflightParameters(t)
+	instanceKey, err := mockLedgerEntriesWithoutTTLs[0].LedgerKey()
+	require.NoError(t, err)
+	codeKey, err := mockLedgerEntriesWithoutTTLs[1].LedgerKey()
+	require.NoError(t, err)
+	var missingKey xdr.LedgerKey
+	require.NoError(t, missingKey.SetContractCode(xdr.Hash{0x1}))
+	params.OpBody = xdr.OperationBody{
+		Type:               xdr.OperationTypeRestoreFootprint,
+		RestoreFootprintOp: &xdr.RestoreFootprintOp{},
+	}
+	params.Footprint.ReadWrite = []xdr.LedgerKey{instanceKey, missingKey}
+
+	// The simulation fails on the missing key
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.NotEmpty(t, result.Error)
+	require.Len(t, result.RestoreStatuses, 2)
+	require.Equal(t, protocol.RestoreStatusAlreadyLive, result.RestoreStatuses[0].Status)
+	require.Equal(t, protocol.RestoreStatusNotFound, result.RestoreStatuses[1].Status)
+	missingKeyXDR, err := missingKey.MarshalBinary()
+	require.NoError(t, err)
+	require.Equal(t, missingKeyXDR, result.RestoreStatuses[1].Key)
+
+	// Once archived, the contract code exceeds the write limit of the mock
+	// network but fits in a raised one
+	archivedLedgerSeq := uint32(entryTTLValue + 1)
+	params.LedgerEntryGetter, err = newInMemoryLedgerEntryGetter(mockLedgerEntries, archivedLedgerSeq)
+	require.NoError(t, err)
+	params.LedgerSeq = archivedLedgerSeq
+	params.Footprint.ReadWrite = []xdr.LedgerKey{codeKey}
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Len(t, result.RestoreStatuses, 1)
+	require.Equal(t, protocol.RestoreStatusTooLarge, result.RestoreStatuses[0].Status)
+
+	for _, entry := range mockLedgerEntries {
+		if setting, ok := entry.Data.GetConfigSetting(); ok && setting.ContractLedgerCost != nil {
+			ledgerCost := *setting.ContractLedgerCost
+			ledgerCost.TxMaxWriteBytes = 1 << 20
+			setting.ContractLedgerCost = &ledgerCost
+			params.ConfigUpgradeSet = &xdr.ConfigUpgradeSet{UpdatedEntry: []xdr.ConfigSettingEntry{setting}}
+		}
+	}
+	require.NotNil(t, params.ConfigUpgradeSet)
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Len(t, result.RestoreStatuses, 1)
+	require.Equal(t, protocol.RestoreStatusRestorable, result.RestoreStatuses[0].Status)
+}
+
+func TestGetPreflightExtendTTLRentChanges(t *testing.T) {
+	params := getPreflightParameters(t)
+	key, err := mockLedgerEntriesWithoutTTLs[0].LedgerKey()
+	require.NoError(t, err)
+	params.OpBody = xdr.OperationBody{
+		Type:                 xdr.OperationTypeExtendFootprintTtl,
+		ExtendFootprintTtlOp: &xdr.ExtendFootprintTtlOp{ExtendTo: 2 * entryTTLValue},
+	}
+	params.Footprint.ReadOnly = []xdr.LedgerKey{key}
+
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Len(t, result.RentChanges, 1)
+	keyXDR, err := key.MarshalBinary()
+	require.NoError(t, err)
+	change := result.RentChanges[0]
+	require.Equal(t, keyXDR, change.Key)
+	require.Equal(t, uint32(entryTTLValue), change.OldLiveUntilLedger)
+	require.Equal(t, uint32(latestSimulateTransactionLedgerSeq+2*entryTTLValue), change.NewLiveUntilLedger)
+	require.Positive(t, change.RentFee)
+	require.LessOrEqual(t, change.RentFee, result.MinFee)
+
+	// The extension shows up as an update of the TTL entry
+	require.Len(t, result.LedgerEntryDiff, 1)
+	var before, after xdr.LedgerEntry
+	require.NoError(t, xdr.SafeUnmarshal(result.LedgerEntryDiff[0].Before, &before))
+	require.NoError(t, xdr.SafeUnmarshal(result.LedgerEntryDiff[0].After, &after))
+	require.Equal(t, xdr.Uint32(entryTTLValue), before.Data.MustTtl().LiveUntilLedgerSeq)
+	require.Equal(t, xdr.Uint32(change.NewLiveUntilLedger), after.Data.MustTtl().LiveUntilLedgerSeq)
+	require.Equal(t, before.Data.MustTtl().KeyHash, after.Data.MustTtl().KeyHash)
+}
+
+func TestGetPreflightSkipRestorePreamble(t *testing.T) {
+	params := getPreflightParameters(t)
+	// Simulate once the contract entries are archived
+	archivedLedgerSeq := uint32(entryTTLValue + 1)
+	ledgerEntryGetter, err := newInMemoryLedgerEntryGetter(mockLedgerEntries, archivedLedgerSeq)
+	require.NoError(t, err)
+	params.LedgerEntryGetter = ledgerEntryGetter
+	params.LedgerSeq = archivedLedgerSeq
+
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.NotEmpty(t, result.PreRestoreTransactionData)
+
+	params.ResourceConfig.SkipRestorePreamble = true
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.NotEmpty(t, result.TransactionData)
+	require.Empty(t, result.PreRestoreTransactionData)
+	require.Zero(t, result.PreRestoreMinFee)
+}
+
+func TestGetPreflightRelaxNetworkLimits(t *testing.T) {
+	const txMaxInstructions = 1000
+	entries := make([]xdr.LedgerEntry, 0, len(mockLedgerEntries))
+	for _, entry := range mockLedgerEntries {
+		if setting, ok := entry.Data.GetConfigSetting(); ok && setting.ContractCompute != nil {
+			compute := *setting.ContractCompute
+			compute.TxMaxInstructions = txMaxInstructions
+			setting.ContractCompute = &compute
+			entry.Data.ConfigSetting = &setting
+		}
+		entries = append(entries, entry)
+	}
+	params := getPreflightParameters(t)
+	ledgerEntryGetter, err := newInMemoryLedgerEntryGetter(entries, latestSimulateTransactionLedgerSeq)
+	require.NoError(t, err)
+	params.LedgerEntryGetter = ledgerEntryGetter
+
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.NotEmpty(t, result.Error)
+
+	params.ResourceConfig.RelaxNetworkLimits = true
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Greater(t, result.CPUInstructions, uint64(txMaxInstructions))
+
+	var transactionData xdr.SorobanTransactionData
+	require.NoError(t, xdr.SafeUnmarshal(result.TransactionData, &transactionData))
+	require.Greater(t, uint64(transactionData.Resources.Instructions), uint64(txMaxInstructions))
+}
+
+func TestGetPreflightConfigUpgradeSet(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+
+	// Upgrade the instruction limit to below what the invocation needs
+	for _, entry := range mockLedgerEntries {
+		if setting, ok := entry.Data.GetConfigSetting(); ok && setting.ContractCompute != nil {
+			compute := *setting.ContractCompute
+			compute.TxMaxInstructions = xdr.Int64(result.CPUInstructions / 2)
+			setting.ContractCompute = &compute
+			params.ConfigUpgradeSet = &xdr.ConfigUpgradeSet{
+				UpdatedEntry: []xdr.ConfigSettingEntry{setting},
+			}
+		}
+	}
+	require.NotNil(t, params.ConfigUpgradeSet)
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.NotEmpty(t, result.Error)
+}
+
+func TestGetPreflightSpans(t *testing.T) {
+	params := getPreflightParameters(t)
+	before := time.Now()
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+
+	names := make([]string, 0, len(result.Spans))
+	for _, span := range result.Spans {
+		names = append(names, span.Name)
+		require.WithinDuration(t, before, span.Start, time.Minute)
+		require.GreaterOrEqual(t, span.Duration, time.Duration(0))
+	}
+	require.Contains(t, names, "preflight.config_load")
+	require.Contains(t, names, "preflight.snapshot_prefetch")
+	require.Contains(t, names, "preflight.execution")
+	require.Contains(t, names, "preflight.restore_simulation")
+}
+
+func TestGetPreflightLinearMemory(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Nil(t, result.LinearMemoryBytes)
+
+	params.ResourceConfig.MeasureLinearMemory = true
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	// test_hello_world.wasm runs in its initial 17 pages of linear memory
+	require.NotNil(t, result.LinearMemoryBytes)
+	require.Equal(t, uint64(17*65536), *result.LinearMemoryBytes)
+}
+
+func TestGetPreflightSimulatedProtocolVersion(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.SimulatedProtocolVersion = params.ProtocolVersion
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+
+	// The state of the ledger is only simulated across one upgrade boundary
+	params.SimulatedProtocolVersion = params.ProtocolVersion - 2
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "cannot simulate protocol 20")
+	require.NotNil(t, result.ErrorDetails)
+	require.Equal(t, protocol.ErrorCategoryInvalidInput, result.ErrorDetails.Category)
+}
+
+func TestGetNetworkConfig(t *testing.T) {
+	params := getPreflightParameters(t)
+	config, err := GetNetworkConfig(context.Background(), params)
+	require.NoError(t, err)
+	require.Equal(t, NetworkLimits{TxMaxInstructions: 100000000, TxMemoryLimit: 100000000}, config.Limits)
+	require.Equal(t, int64(100), config.Fees.FeePerHistorical1KB)
+	require.Equal(t, uint32(100), config.StateArchival.MaxEntryTTL)
+	require.Equal(t, uint32(100), config.StateArchival.MinTemporaryTTL)
+	require.Equal(t, uint32(100), config.StateArchival.MinPersistentTTL)
+
+	// The settings of a config upgrade set replace the ones of the ledger
+	params.ConfigUpgradeSet = &xdr.ConfigUpgradeSet{
+		UpdatedEntry: []xdr.ConfigSettingEntry{{
+			ConfigSettingId: xdr.ConfigSettingIdConfigSettingContractComputeV0,
+			ContractCompute: &xdr.ConfigSettingContractComputeV0{
+				LedgerMaxInstructions:           100000000,
+				TxMaxInstructions:               5000000,
+				FeeRatePerInstructionsIncrement: 100,
+				TxMemoryLimit:                   100000000,
+			},
+		}},
+	}
+	config, err = GetNetworkConfig(context.Background(), params)
+	require.NoError(t, err)
+	require.Equal(t, int64(5000000), config.Limits.TxMaxInstructions)
+}
+
+func TestGetPreflightAutoAuthMode(t *testing.T) {
+	params := getPreflightParameters(t)
+	recorded, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, recorded.Error)
+
+	// Without auth entries, the auto mode records
+	params.AuthMode = protocol.AuthModeAuto
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Equal(t, recorded.Auth, result.Auth)
+	require.Equal(t, recorded.TransactionData, result.TransactionData)
+}
+
+func TestGetPreflightMockSignatureAuthMode(t *testing.T) {
+	// The invocation doesn't require any authorization
+	params := getPreflightParameters(t)
+	params.AuthMode = protocol.AuthModeEnforceMockSignatures
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Empty(t, result.Auth)
+
+	params.OpBody.InvokeHostFunctionOp.Auth = []xdr.SorobanAuthorizationEntry{{
+		Credentials: xdr.SorobanCredentials{Type: xdr.SorobanCredentialsTypeSorobanCredentialsSourceAccount},
+		RootInvocation: xdr.SorobanAuthorizedInvocation{
+			Function: xdr.SorobanAuthorizedFunction{
+				Type:       xdr.SorobanAuthorizedFunctionTypeSorobanAuthorizedFunctionTypeContractFn,
+				ContractFn: params.OpBody.InvokeHostFunctionOp.HostFunction.InvokeContract,
+			},
+		},
+	}}
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "0 missing, 1 unexpected")
+	require.NotNil(t, result.ErrorDetails)
+	require.Equal(t, protocol.ErrorCategoryAuth, result.ErrorDetails.Category)
+}
+
+func TestGetPreflightSkipCheckAuth(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.ResourceConfig.SkipCheckAuth = true
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.NotNil(t, result.StubbedCheckAuth)
+	require.Empty(t, result.StubbedCheckAuth)
+
+	// The entries are matched against the recorded authorizations instead of
+	// being enforced
+	params.AuthMode = protocol.AuthModeEnforce
+	params.OpBody.InvokeHostFunctionOp.Auth = []xdr.SorobanAuthorizationEntry{{
+		Credentials: xdr.SorobanCredentials{Type: xdr.SorobanCredentialsTypeSorobanCredentialsSourceAccount},
+		RootInvocation: xdr.SorobanAuthorizedInvocation{
+			Function: xdr.SorobanAuthorizedFunction{
+				Type:       xdr.SorobanAuthorizedFunctionTypeSorobanAuthorizedFunctionTypeContractFn,
+				ContractFn: params.OpBody.InvokeHostFunctionOp.HostFunction.InvokeContract,
+			},
+		},
+	}}
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "0 missing, 1 unexpected")
+}
+
+func TestGetPreflightExtraFootprint(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+
+	extraKey := xdr.LedgerKey{
+		Type: xdr.LedgerEntryTypeAccount,
+		Account: &xdr.LedgerKeyAccount{
+			AccountId: xdr.MustAddress("GBXGQJWVLWOYHFLVTKWV5FGHA3LNYY2JQKM7OAJAUEQFU6LPCSEFVXON"),
+		},
+	}
+	params.ResourceConfig.ExtraFootprint, err = xdr.MarshalBase64(xdr.LedgerFootprint{
+		ReadOnly: []xdr.LedgerKey{extraKey},
+	})
+	require.NoError(t, err)
+	extended, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, extended.Error)
+	var transactionData xdr.SorobanTransactionData
+	require.NoError(t, xdr.SafeUnmarshal(extended.TransactionData, &transactionData))
+	require.Contains(t, transactionData.Resources.Footprint.ReadOnly, extraKey)
+	require.Greater(t, extended.MinFee, result.MinFee)
+
+	params.ResourceConfig.ExtraFootprint = "not base64"
+	_, err = GetPreflight(context.Background(), params)
+	require.ErrorContains(t, err, "could not unmarshal extraFootprint")
+}
+
+func TestGetPreflightLogs(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Len(t, result.Logs, len(result.DiagnosticEvents))
+	require.NotEmpty(t, result.Logs)
+	require.Contains(t, strings.Join(result.Logs, "\n"), "core_metrics")
+
+	params.EnableDebug = false
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	require.Empty(t, result.Logs)
+}
+
+func TestGetPreflightCancelled(t *testing.T) {
+	params := getPreflightParameters(t)
+	ctx, cancel := context.WithCancel(context.Background())
+	cancel()
+	_, err := GetPreflight(ctx, params)
+	require.ErrorIs(t, err, ErrPreflightCancelled)
+	require.ErrorIs(t, err, context.Canceled)
+
+	_, err = GetInvokeHostFunctionPreflights(ctx, []Parameters{params, params})
+	require.ErrorIs(t, err, ErrPreflightCancelled)
+
+	// Preflights which aren't cancelled are unaffected
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+}
+
+func TestGetPreflightWarnings(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	// The TTL of the mock contract entries is about to run out
+	require.NotEmpty(t, result.Warnings)
+	for _, warning := range result.Warnings {
+		require.Equal(t, protocol.WarningEntryNearArchival, warning.Kind)
+		require.Contains(t, warning.Message, "archived after ledger 1000")
+	}
+
+	// Lower the instruction limit to what the invocation declares
+	var transactionData xdr.SorobanTransactionData
+	require.NoError(t, xdr.SafeUnmarshal(result.TransactionData, &transactionData))
+	entries := make([]xdr.LedgerEntry, 0, len(mockLedgerEntries))
+	for _, entry := range mockLedgerEntries {
+		if setting, ok := entry.Data.GetConfigSetting(); ok && setting.ContractCompute != nil {
+			compute := *setting.ContractCompute
+			compute.TxMaxInstructions = xdr.Int64(transactionData.Resources.Instructions)
+			setting.ContractCompute = &compute
+			entry.Data.ConfigSetting = &setting
+		}
+		entries = append(entries, entry)
+	}
+	params.LedgerEntryGetter, err = newInMemoryLedgerEntryGetter(entries, latestSimulateTransactionLedgerSeq)
+	require.NoError(t, err)
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+	message := fmt.Sprintf("instructions: %d used out of the %d allowed per transaction",
+		transactionData.Resources.Instructions, transactionData.Resources.Instructions)
+	require.Contains(t, result.Warnings, protocol.SimulationWarning{
+		Kind:    protocol.WarningResourceNearLimit,
+		Message: message,
+	})
+}
+
+func TestGetPreflightBudgetCaps(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+
+	params.ResourceConfig.MaxInstructions = result.CPUInstructions / 2
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "budget exceeded at frame 1")
+	require.Contains(t, result.Error, "function hello")
+}
+
+func TestGetPreflightMinimizeFootprint(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.ResourceConfig.PrngSeed = new(uint64)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, result.Error)
+
+	// Every key recorded by the invocation is needed, so none is dropped
+	params.ResourceConfig.MinimizeFootprint = true
+	minimized, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, minimized.Error)
+	var transactionData, minimizedTransactionData xdr.SorobanTransactionData
+	require.NoError(t, xdr.SafeUnmarshal(result.TransactionData, &transactionData))
+	require.NoError(t, xdr.SafeUnmarshal(minimized.TransactionData, &minimizedTransactionData))
+	require.Equal(t, transactionData.Resources, minimizedTransactionData.Resources)
+}
+
+func TestGetPreflightErrorDetails(t *testing.T) {
+	params := getPreflightParameters(t)
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Nil(t, result.ErrorDetails)
+
+	params.ResourceConfig.MaxInstructions = result.CPUInstructions / 2
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.NotNil(t, result.ErrorDetails)
+	require.Equal(t, protocol.ErrorCategoryBudget, result.ErrorDetails.Category)
+	require.Equal(t, mockContractID[:], result.ErrorDetails.ContractID)
+	var scError xdr.ScError
+	require.NoError(t, scError.UnmarshalBinary(result.ErrorDetails.Error))
+	require.Equal(t, xdr.ScErrorTypeSceBudget, scError.Type)
+}
+
+func TestGetPreflightWasmDiagnostics(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.EnableDebug = false
+	wasm := []byte("not a wasm module")
+	params.OpBody.InvokeHostFunctionOp.HostFunction = xdr.HostFunction{
+		Type: xdr.HostFunctionTypeHostFunctionTypeUploadContractWasm,
+		Wasm: &wasm,
+	}
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.NotEmpty(t, result.Error)
+	require.NotEmpty(t, result.WasmDiagnostics)
+	require.NotEmpty(t, result.WasmDiagnostics[0].Message)
+}
+
+func TestGetCreatedContractID(t *testing.T) {
+	params := getPreflightParameters(t)
+	contractID, err := getCreatedContractID(
+		params.OpBody.InvokeHostFunctionOp.HostFunction, params.NetworkPassphrase)
+	require.NoError(t, err)
+	require.Nil(t, contractID)
+
+	contractIDPreimage := xdr.ContractIdPreimage{
+		Type: xdr.ContractIdPreimageTypeContractIdPreimageFromAddress,
+		FromAddress: &xdr.ContractIdPreimageFromAddress{
+			Address: xdr.ScAddress{
+				Type:      xdr.ScAddressTypeScAddressTypeAccount,
+				AccountId: &params.SourceAccount,
+			},
+			Salt: xdr.Uint256{0x1},
+		},
+	}
+	preimageXDR, err := xdr.HashIdPreimage{
+		Type: xdr.EnvelopeTypeEnvelopeTypeContractId,
+		ContractId: &xdr.HashIdPreimageContractId{
+			NetworkId:          sha256.Sum256([]byte(params.NetworkPassphrase)),
+			ContractIdPreimage: contractIDPreimage,
+		},
+	}.MarshalBinary()
+	require.NoError(t, err)
+	expected := xdr.ContractId(sha256.Sum256(preimageXDR))
+
+	contractID, err = getCreatedContractID(xdr.HostFunction{
+		Type: xdr.HostFunctionTypeHostFunctionTypeCreateContract,
+		CreateContract: &xdr.CreateContractArgs{
+			ContractIdPreimage: contractIDPreimage,
+			Executable:         xdr.ContractExecutable{Type: xdr.ContractExecutableTypeContractExecutableStellarAsset},
+		},
+	}, params.NetworkPassphrase)
+	require.NoError(t, err)
+	require.Equal(t, &expected, contractID)
+
+	contractID, err = getCreatedContractID(xdr.HostFunction{
+		Type: xdr.HostFunctionTypeHostFunctionTypeCreateContractV2,
+		CreateContractV2: &xdr.CreateContractArgsV2{
+			ContractIdPreimage: contractIDPreimage,
+			Executable:         xdr.ContractExecutable{Type: xdr.ContractExecutableTypeContractExecutableStellarAsset},
+		},
+	}, params.NetworkPassphrase)
+	require.NoError(t, err)
+	require.Equal(t, &expected, contractID)
+}
+
+func TestGetPreflightPrngSeed(t *testing.T) {
+	params := getPreflightParameters(t)
+	seed := uint64(42)
+	params.ResourceConfig.PrngSeed = &seed
+
+	first, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Empty(t, first.Error)
+	second, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Equal(t, first, second)
+}
+
+func TestGetResourceConfigSignatureExpirationLedgers(t *testing.T) {
+	config := protocol.DefaultResourceConfig()
+	require.EqualValues(t, protocol.DefaultSignatureExpirationLedgers,
+		getResourceConfig(config).signature_expiration_ledgers)
+	// 0 leaves the expiration of the recorded auth entries unset
+	for _, ledgers := range []uint32{0, 500} {
+		config.SignatureExpirationLedgers = &ledgers
+		require.EqualValues(t, ledgers, getResourceConfig(config).signature_expiration_ledgers)
+	}
+}
+
+func TestGetPreflightLedgerEntryOverrides(t *testing.T) {
+	params := getPreflightParameters(t)
+	params.LedgerEntryOverrides = []xdr.LedgerEntry{
+		{
+			LastModifiedLedgerSeq: latestSimulateTransactionLedgerSeq,
+			Data: xdr.LedgerEntryData{
+				Type: xdr.LedgerEntryTypeContractCode,
+				ContractCode: &xdr.ContractCodeEntry{
+					Hash: mockContractHash,
+					Code: []byte("not a wasm module"),
+				},
+			},
+		},
+	}
+	result, err := GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.NotEmpty(t, result.Error)
+
+	params.LedgerEntryOverrides = []xdr.LedgerEntry{
+		{
+			LastModifiedLedgerSeq: latestSimulateTransactionLedgerSeq,
+			Data: xdr.LedgerEntryData{
+				Type: xdr.LedgerEntryTypeData,
+				Data: &xdr.DataEntry{
+					AccountId: params.SourceAccount,
+					DataName:  "foo",
+					DataValue: xdr.DataValue("bar"),
+				},
+			},
+		},
+	}
+	result, err = GetPreflight(context.Background(), params)
+	require.NoError(t, err)
+	require.Contains(t, result.Error, "is not supported")
+}
+
+func TestGetInvokeHostFunctionPreflights(t *testing.T) {
+	params := getPreflightParameters(t)
+	results, err := GetInvokeHostFunctionPreflights(context.Background(), []Parameters{params, params})
+	require.NoError(t, err)
+	require.Len(t, results, 2)
+	for _, result := range results {
+		require.Empty(t, result.Error)
+	}
+
+	mismatched := params
+	mismatched.AuthMode = protocol.AuthModeEnforce
+	_, err = GetInvokeHostFunctionPreflights(context.Background(), []Parameters{params, mismatched})
+	require.ErrorContains(t, err, "mismatched auth modes")
+}
+
+func TestGetInvokeHostFunctionPreflightsFromSnapshot(t *testing.T) {
+	params := getPreflightParameters(t)
+	keys := make([]xdr.LedgerKey, 0, len(mockLedgerEntries))
+	for _, entry := range mockLedgerEntries {
+		key, err := entry.LedgerKey()
+		require.NoError(t, err)
+		keys = append(keys, key)
+	}
+	snapshot, _, err := params.LedgerEntryGetter.GetLedgerEntries(context.Background(), keys)
+	require.NoError(t, err)
+
+	expected, err := GetInvokeHostFunctionPreflights(context.Background(), []Parameters{params, params})
+	require.NoError(t, err)
+	// The ledger entry getter isn't used
+	params.LedgerEntryGetter = nil
+	results, err := GetInvokeHostFunctionPreflightsFromSnapshot(context.Background(), []Parameters{params, params}, snapshot)
+	require.NoError(t, err)
+	require.Len(t, results, 2)
+	for i, result := range results {
+		require.Empty(t, result.Error)
+		require.Equal(t, expected[i].TransactionData, result.TransactionData)
+		require.Equal(t, expected[i].Result, result.Result)
+	}
+
+	// Without the network config settings, nothing can be simulated
+	results, err = GetInvokeHostFunctionPreflightsFromSnapshot(context.Background(), []Parameters{params}, nil)
+	require.NoError(t, err)
+	require.Len(t, results, 1)
+	require.NotEmpty(t, results[0].Error)
+}
+
 func BenchmarkGetPreflight(b *testing.B) {
 	params := getPreflightParameters(b)
 
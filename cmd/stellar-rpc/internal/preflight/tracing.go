@@ -0,0 +1,30 @@
+package preflight
+
+import (
+	"context"
+
+	"go.opentelemetry.io/otel"
+	"go.opentelemetry.io/otel/trace"
+)
+
+// tracerName identifies the spans of the preflights among the ones of the
+// server.
+const tracerName = "github.com/stellar/stellar-rpc/cmd/stellar-rpc/internal/preflight"
+
+// startPreflightSpan starts the span of a whole preflight (or batch of
+// preflights), whose phases are recorded as children of it by recordSpans().
+func startPreflightSpan(ctx context.Context) (context.Context, trace.Span) {
+	return otel.Tracer(tracerName).Start(ctx, "preflight")
+}
+
+// recordSpans reports the phases of a preflight timed by the preflight library
+// as children of the span of the context, so that they show up in the traces
+// of the server along with the spans recorded in Go. Spans are only recorded
+// once a tracer provider is registered with otel.SetTracerProvider().
+func recordSpans(ctx context.Context, spans []Span) {
+	tracer := otel.Tracer(tracerName)
+	for _, span := range spans {
+		_, s := tracer.Start(ctx, span.Name, trace.WithTimestamp(span.Start))
+		s.End(trace.WithTimestamp(span.Start.Add(span.Duration)))
+	}
+}
@@ -0,0 +1,74 @@
+package preflight
+
+import (
+	"strings"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+// WasmDiagnostic is a reason an uploaded Wasm module is rejected by the
+// validation of the host.
+type WasmDiagnostic struct {
+	Kind    string // one of the protocol.WasmDiagnostic* values
+	Error   xdr.ScError
+	Message string
+	Args    []xdr.ScVal
+}
+
+// GetWasmDiagnostics extracts the reasons an uploaded Wasm module is rejected
+// from the error diagnostic events of its validation.
+func GetWasmDiagnostics(validationEventsXDR [][]byte) ([]WasmDiagnostic, error) {
+	var diagnostics []WasmDiagnostic
+	for _, eventXDR := range validationEventsXDR {
+		var event xdr.DiagnosticEvent
+		if err := xdr.SafeUnmarshal(eventXDR, &event); err != nil {
+			return nil, err
+		}
+		body, ok := event.Event.Body.GetV0()
+		if !ok || len(body.Topics) != 2 {
+			continue
+		}
+		if sym, ok := body.Topics[0].GetSym(); !ok || sym != "error" {
+			continue
+		}
+		scError, ok := body.Topics[1].GetError()
+		if !ok {
+			continue
+		}
+		diagnostic := WasmDiagnostic{Error: scError}
+		// The data is either the message or a vector of the message followed
+		// by its arguments
+		message := body.Data
+		if vec, ok := body.Data.GetVec(); ok && vec != nil && len(**vec) > 0 {
+			message = (**vec)[0]
+			diagnostic.Args = (**vec)[1:]
+		}
+		if str, ok := message.GetStr(); ok {
+			diagnostic.Message = string(str)
+		}
+		diagnostic.Kind = getWasmDiagnosticKind(diagnostic.Message)
+		diagnostics = append(diagnostics, diagnostic)
+	}
+	return diagnostics, nil
+}
+
+// The host doesn't tell apart its validation failures other than through
+// their messages (and the Debug representation of the Wasm VM errors).
+func getWasmDiagnosticKind(message string) string {
+	message = strings.ToLower(message)
+	switch {
+	case strings.Contains(message, "import") || strings.Contains(message, "host function"):
+		return protocol.WasmDiagnosticUnsupportedImport
+	case strings.Contains(message, "export") || strings.Contains(message, "no linear memory"):
+		return protocol.WasmDiagnosticMissingExport
+	case strings.Contains(message, "memory") || strings.Contains(message, "segment"):
+		return protocol.WasmDiagnosticMemoryLimits
+	case strings.Contains(message, "protocol") || strings.Contains(message, "pre-release") ||
+		strings.Contains(message, "interface version") || strings.Contains(message, "metadata"):
+		return protocol.WasmDiagnosticInterfaceVersion
+	default:
+		return protocol.WasmDiagnosticInvalidModule
+	}
+}
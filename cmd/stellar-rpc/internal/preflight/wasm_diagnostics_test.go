@@ -0,0 +1,56 @@
+package preflight
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+
+	"github.com/stellar/stellar-rpc/protocol"
+)
+
+func TestGetWasmDiagnostics(t *testing.T) {
+	errorSym := xdr.ScSymbol("error")
+	scError := xdr.ScError{Type: xdr.ScErrorTypeSceWasmVm, Code: (*xdr.ScErrorCode)(new(int32))}
+	message := xdr.ScString("unsupported memory size")
+	arg := xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: (*xdr.Uint32)(new(uint32))}
+	data := &xdr.ScVec{{Type: xdr.ScValTypeScvString, Str: &message}, arg}
+	event := xdr.DiagnosticEvent{
+		Event: xdr.ContractEvent{
+			Type: xdr.ContractEventTypeDiagnostic,
+			Body: xdr.ContractEventBody{
+				V0: &xdr.ContractEventV0{
+					Topics: []xdr.ScVal{
+						{Type: xdr.ScValTypeScvSymbol, Sym: &errorSym},
+						{Type: xdr.ScValTypeScvError, Error: &scError},
+					},
+					Data: xdr.ScVal{Type: xdr.ScValTypeScvVec, Vec: &data},
+				},
+			},
+		},
+	}
+	eventXDR, err := event.MarshalBinary()
+	require.NoError(t, err)
+
+	diagnostics, err := GetWasmDiagnostics([][]byte{eventXDR})
+	require.NoError(t, err)
+	require.Equal(t, []WasmDiagnostic{{
+		Kind:    protocol.WasmDiagnosticMemoryLimits,
+		Error:   scError,
+		Message: string(message),
+		Args:    []xdr.ScVal{arg},
+	}}, diagnostics)
+}
+
+func TestGetWasmDiagnosticKind(t *testing.T) {
+	for message, kind := range map[string]string{
+		"contract calls a host function not yet supported by current protocol": protocol.WasmDiagnosticUnsupportedImport,
+		"no linear memory named `memory`":                                      protocol.WasmDiagnosticMissingExport,
+		"data segment(s) content exceeds memory size":                          protocol.WasmDiagnosticMemoryLimits,
+		"contract missing environment interface version":                       protocol.WasmDiagnosticInterfaceVersion,
+		"unsupported wasm section type":                                        protocol.WasmDiagnosticInvalidModule,
+	} {
+		require.Equal(t, kind, getWasmDiagnosticKind(message), message)
+	}
+}
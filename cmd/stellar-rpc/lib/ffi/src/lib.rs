@@ -1,11 +1,39 @@
 extern crate libc;
 
 use std::ffi::{CStr, CString};
+use std::mem;
 use std::ptr::null_mut;
 use std::slice;
 
+/// Builds the C representation of a Rust value.
+///
+/// Any memory allocated for the C value is owned by it and has to be released
+/// exactly once with [`CDrop::drop_c`], usually by the function freeing the
+/// enclosing structure once the caller across the FFI boundary is done with it.
+pub trait CReprOf<T> {
+    fn c_repr_of(input: T) -> Self;
+}
+
+/// Copies a C value into an owned Rust value, leaving the C memory untouched.
+pub trait AsRust<T> {
+    /// # Safety
+    ///
+    /// Every pointer in `self` must either be null or valid for reads of the
+    /// length it's paired with (or be a null-terminated string).
+    unsafe fn as_rust(&self) -> T;
+}
+
+/// Releases the memory owned by a C value built with [`CReprOf`].
+pub trait CDrop {
+    /// # Safety
+    ///
+    /// The value must have been built by Rust through [`CReprOf`] and must not
+    /// have been dropped before. Never call this on memory allocated across the
+    /// FFI boundary.
+    unsafe fn drop_c(self);
+}
+
 #[repr(C)]
-#[derive(Copy, Clone)]
 pub struct CXDR {
     pub xdr: *mut libc::c_uchar,
     pub len: libc::size_t,
@@ -22,59 +50,115 @@ impl Default for CXDR {
     }
 }
 
-/// Converts a Rust string to a C byte array.
-///
-/// The memory allocated to the C string must be freed when you're done with it
-/// by calling `free_c_string`.
+impl CXDR {
+    /// Borrows the bytes without copying them, e.g. to decode an XDR value
+    /// straight from memory owned across the FFI boundary.
+    ///
+    /// # Safety
+    ///
+    /// `xdr` must either be null or valid for reads of `len` bytes, and must
+    /// stay so (unmodified) for as long as the returned slice is used.
+    #[must_use]
+    pub unsafe fn as_slice<'a>(&self) -> &'a [u8] {
+        if self.xdr.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.xdr, self.len) }
+    }
+}
+
+impl CReprOf<Vec<u8>> for CXDR {
+    fn c_repr_of(input: Vec<u8>) -> Self {
+        let (xdr, len) = vec_to_c_array(input);
+        CXDR { xdr, len }
+    }
+}
+
+impl CReprOf<Option<Vec<u8>>> for CXDR {
+    fn c_repr_of(input: Option<Vec<u8>>) -> Self {
+        input.map_or_else(CXDR::default, CXDR::c_repr_of)
+    }
+}
+
+impl AsRust<Vec<u8>> for CXDR {
+    unsafe fn as_rust(&self) -> Vec<u8> {
+        unsafe { self.as_slice() }.to_vec()
+    }
+}
+
+impl CDrop for CXDR {
+    unsafe fn drop_c(self) {
+        unsafe { drop(c_array_to_vec(self.xdr, self.len)) };
+    }
+}
+
+/// Converts a Rust string to a null-terminated C string.
 ///
 /// # Panics
 ///
-/// If `str` is valid, this never panics; just be cool.
-#[must_use]
-pub fn string_to_c(str: String) -> *mut libc::c_char {
-    CString::new(str).unwrap().into_raw()
+/// If the string contains an interior null byte.
+impl CReprOf<String> for *mut libc::c_char {
+    fn c_repr_of(input: String) -> Self {
+        CString::new(input).unwrap().into_raw()
+    }
 }
 
-/// Frees the memory previously allocated by Rust in `string_to_c`.
+/// Copies a null-terminated C string.
 ///
-/// # Safety
+/// # Panics
 ///
-/// You should take care to only free the same string once, and don't free
-/// pointers to strings allocated from across the FFI boundary.
-pub unsafe fn free_c_string(str: *mut libc::c_char) {
-    if str.is_null() {
-        return;
+/// If the string isn't valid UTF-8.
+impl AsRust<String> for *const libc::c_char {
+    unsafe fn as_rust(&self) -> String {
+        let c_str = unsafe { CStr::from_ptr(*self) };
+        c_str.to_str().unwrap().to_string()
     }
-    unsafe {
-        _ = CString::from_raw(str);
+}
+
+impl CDrop for *mut libc::c_char {
+    unsafe fn drop_c(self) {
+        if self.is_null() {
+            return;
+        }
+        unsafe {
+            _ = CString::from_raw(self);
+        }
     }
 }
 
-/// Frees the memory allocated to a generic XDR structure.
+/// Hands a vector over to C as a pointer and a length.
 ///
-/// # Panics
+/// The memory must be reclaimed with [`c_array_to_vec`].
 ///
-/// If `str` is a valid null-terminated C string, this won't panic.
-///
-/// # Safety
+/// # Panics
 ///
-/// You should take care to only free the same struct once, and don't free
-/// pointers to structs allocated from across the FFI boundary.
+/// If the capacity of the vector can't be shrunk to its length.
 #[must_use]
-pub unsafe fn from_c_string(str: *const libc::c_char) -> String {
-    let c_str = unsafe { CStr::from_ptr(str) };
-    c_str.to_str().unwrap().to_string()
+pub fn vec_to_c_array<T>(mut v: Vec<T>) -> (*mut T, libc::size_t) {
+    // Make sure length and capacity are the same
+    // (this allows using the length as the capacity when deallocating the vector)
+    v.shrink_to_fit();
+    let len = v.len();
+    assert_eq!(len, v.capacity());
+
+    // Get the pointer to our vector, we will deallocate it in c_array_to_vec()
+    // TODO: replace by `out_vec.into_raw_parts()` once the API stabilizes
+    let ptr = v.as_mut_ptr();
+    mem::forget(v);
+
+    (ptr, len)
 }
 
-/// Transforms an FFI-compatible raw XDR structure into a Rust-managed chunk of
-/// memory.
+/// Takes back ownership of an array previously handed over with
+/// [`vec_to_c_array`].
 ///
 /// # Safety
 ///
-/// Unless the structure itself is whack (e.g., you've free'd it before or the
-/// buffer is mangled), this is safe to use.
-#[must_use]
-pub unsafe fn from_c_xdr(xdr: CXDR) -> Vec<u8> {
-    let s = unsafe { slice::from_raw_parts(xdr.xdr, xdr.len) };
-    s.to_vec()
+/// `ptr` and `len` must come from a single call to [`vec_to_c_array`] (or
+/// `ptr` must be null) and the array must not have been reclaimed before.
+pub unsafe fn c_array_to_vec<T>(ptr: *mut T, len: libc::size_t) -> Vec<T> {
+    if ptr.is_null() {
+        return Vec::new();
+    }
+    unsafe { Vec::from_raw_parts(ptr, len, len) }
 }
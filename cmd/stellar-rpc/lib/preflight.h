@@ -12,6 +12,9 @@ typedef struct ledger_info_t {
   const char *network_passphrase;
   uint32_t base_reserve;
   uint64_t bucket_list_size;
+  uint32_t simulated_protocol_version; // Protocol to simulate with, 0 for protocol_version (that of the ledger)
+  uint32_t sequence_number_override; // Sequence the ledger is seen with, 0 for sequence_number (the state is still read from it)
+  uint64_t timestamp_override; // Timestamp the ledger is seen with, 0 for timestamp
 } ledger_info_t;
 
 typedef struct xdr_vector_t {
@@ -29,45 +32,273 @@ typedef struct xdr_diff_vector_t {
     size_t len;
 } xdr_diff_vector_t;
 
+typedef struct footprint_entry_bytes_t {
+    xdr_t    key; // LedgerKey XDR
+    uint32_t read_bytes;
+    uint32_t write_bytes;
+} footprint_entry_bytes_t;
+
+typedef struct footprint_entry_bytes_vector_t {
+    footprint_entry_bytes_t *array;
+    size_t                  len;
+} footprint_entry_bytes_vector_t;
+
+typedef struct rent_change_t {
+    xdr_t    key; // LedgerKey XDR
+    uint32_t size_bytes; // Size the rent is charged on, after the change
+    uint32_t old_live_until_ledger;
+    uint32_t new_live_until_ledger;
+    int64_t  rent_fee;
+} rent_change_t;
+
+typedef struct rent_change_vector_t {
+    rent_change_t *array;
+    size_t        len;
+} rent_change_vector_t;
+
+typedef struct adjustment_factor_t {
+    bool     set; // Whether the factors below override the default adjustment
+    double   multiplicative_factor;
+    uint32_t additive_factor;
+} adjustment_factor_t;
+
 typedef struct resource_config_t {
-    uint64_t instruction_leeway; // Allow this many extra instructions when budgeting
+    uint64_t            instruction_leeway; // Allow this many extra instructions when budgeting
+    adjustment_factor_t instructions;
+    adjustment_factor_t read_bytes;
+    adjustment_factor_t write_bytes;
+    adjustment_factor_t tx_size;
+    adjustment_factor_t refundable_fee;
+    bool                has_prng_seed; // Seed the PRNG with prng_seed instead of randomly
+    uint64_t            prng_seed;
+    bool                skip_restore_preamble; // Don't simulate the RestoreFootprint preamble of archived entries
+    bool                relax_network_limits; // Lift the transaction CPU and memory limits of the network
+    uint64_t            max_instructions; // Abort the simulation beyond this many instructions, 0 if uncapped
+    uint64_t            max_memory_bytes; // Abort the simulation beyond this many memory bytes, 0 if uncapped
+    bool                minimize_footprint; // Drop the read-only keys the invocation succeeds without
+    bool                skip_check_auth; // Don't run the __check_auth of contract accounts, see CResourceConfig
+    uint32_t            signature_expiration_ledgers; // Expire recorded signatures this many ledgers ahead, 0 to leave unset
+    bool                measure_linear_memory; // Replay the invocation to measure the linear memory of its Wasm VMs
 } resource_config_t;
 
+// Categories of preflight errors
+#define ERROR_CATEGORY_NONE          0 // No error, or raised outside of the simulation
+#define ERROR_CATEGORY_STORAGE       1
+#define ERROR_CATEGORY_BUDGET        2
+#define ERROR_CATEGORY_AUTH          3
+#define ERROR_CATEGORY_CONTRACT      4
+#define ERROR_CATEGORY_HOST          5
+#define ERROR_CATEGORY_INVALID_INPUT 6 // The inputs (e.g. their XDR) are invalid
+
+typedef struct error_details_t {
+    uint32_t category; // One of the ERROR_CATEGORY_* values
+    xdr_t    sc_error; // ScError XDR, empty if the error didn't come from the host
+    xdr_t    contract_id; // ID of the innermost contract call executing when the error was raised, empty if unknown
+} error_details_t;
+
+// Kinds of non-fatal preflight findings
+#define WARNING_RESOURCE_NEAR_LIMIT      1 // Within 5% of a transaction limit of the network
+#define WARNING_NON_ROOT_AUTH            2 // Authorization recorded for a non-root call
+#define WARNING_DEPRECATED_HOST_FUNCTION 3
+#define WARNING_ENTRY_NEAR_ARCHIVAL      4 // Footprint entry whose TTL is about to run out
+#define WARNING_LINEAR_MEMORY_UNMEASURED 5 // Replay measuring the linear memory failed
+
+typedef struct preflight_warning_t {
+    uint32_t kind; // One of the WARNING_* values
+    char     *message;
+} preflight_warning_t;
+
+typedef struct preflight_warning_vector_t {
+    preflight_warning_t *array;
+    size_t              len;
+} preflight_warning_vector_t;
+
+// A phase of a preflight (e.g. loading the network config or executing the host function)
+typedef struct preflight_span_t {
+    char     *name;
+    uint64_t start_unix_nanos;
+    uint64_t duration_nanos;
+} preflight_span_t;
+
+typedef struct preflight_span_vector_t {
+    preflight_span_t *array;
+    size_t           len;
+} preflight_span_vector_t;
+
+// Whether a key of a restore can be restored
+#define RESTORE_STATUS_RESTORABLE   1
+#define RESTORE_STATUS_ALREADY_LIVE 2
+#define RESTORE_STATUS_NOT_FOUND    3
+#define RESTORE_STATUS_TOO_LARGE    4 // Larger than the write limit of a transaction
+
+typedef struct restore_key_status_t {
+    xdr_t    key; // LedgerKey XDR
+    uint32_t status; // One of the RESTORE_STATUS_* values
+} restore_key_status_t;
+
+typedef struct restore_key_status_vector_t {
+    restore_key_status_t *array;
+    size_t               len;
+} restore_key_status_vector_t;
+
+typedef struct string_vector_t {
+    char   **array;
+    size_t len;
+} string_vector_t;
+
+// Version of the layout of preflight_result_t, bumped whenever its fields change
+#define PREFLIGHT_RESULT_VERSION 2
+
+// Tags of the extensions of preflight results
+#define PREFLIGHT_EXTENSION_FEE_BREAKDOWN      1 // JSON object splitting the resource fee by what it pays for
+#define PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH 2 // ScVal XDR vector of the contract accounts whose __check_auth wasn't run
+
+// An optional section of a preflight result, whose tag tells how to decode its data. Unknown tags are to be skipped
+typedef struct preflight_extension_t {
+    uint32_t tag;
+    xdr_t    data;
+} preflight_extension_t;
+
+typedef struct preflight_extension_vector_t {
+    preflight_extension_t *array;
+    size_t                len;
+} preflight_extension_vector_t;
+
 typedef struct preflight_result_t {
-    char             *error; // Error string in case of error, otherwise null
-    xdr_vector_t      auth; // array of SorobanAuthorizationEntries
-    xdr_t             result; // XDR SCVal
-    xdr_t             transaction_data;
-    int64_t           min_fee; // Minimum recommended resource fee
-    xdr_vector_t      events; // array of XDR DiagnosticEvents
-    uint64_t          cpu_instructions;
-    uint64_t          memory_bytes;
-    xdr_t             pre_restore_transaction_data; // SorobanTransactionData XDR for a prerequired RestoreFootprint operation
-    int64_t           pre_restore_min_fee; // Minimum recommended resource fee for a prerequired RestoreFootprint operation
-    xdr_diff_vector_t ledger_entry_diff; // Contains the ledger entry changes which would be caused by the transaction execution
+    uint32_t                       version; // Always PREFLIGHT_RESULT_VERSION, to be checked before reading the other fields
+    char                           *error; // Error string in case of error, otherwise null
+    xdr_vector_t                   auth; // array of SorobanAuthorizationEntries
+    xdr_t                          result; // XDR SCVal
+    xdr_t                          transaction_data;
+    int64_t                        min_fee; // Minimum recommended resource fee
+    xdr_vector_t                   contract_events; // array of XDR ContractEvents
+    xdr_vector_t                   diagnostic_events; // array of XDR DiagnosticEvents
+    uint64_t                       cpu_instructions;
+    uint64_t                       memory_bytes;
+    xdr_t                          pre_restore_transaction_data; // SorobanTransactionData XDR for a prerequired RestoreFootprint operation
+    int64_t                        pre_restore_min_fee; // Minimum recommended resource fee for a prerequired RestoreFootprint operation
+    xdr_diff_vector_t              ledger_entry_diff; // Contains the ledger entry changes which would be caused by the transaction execution
+    footprint_entry_bytes_vector_t footprint_bytes; // Bytes read and written for each entry of the footprint
+    rent_change_vector_t           rent_changes; // Rent charged for each entry whose TTL is extended or restored
+    error_details_t                error_details; // Structured counterpart of the error string
+    xdr_vector_t                   wasm_validation_events; // array of XDR DiagnosticEvents explaining why an uploaded Wasm module was rejected
+    preflight_warning_vector_t     warnings; // Non-fatal findings of the simulation
+    preflight_span_vector_t        spans; // Timings of the phases of the preflight
+    bool                           has_linear_memory_bytes; // Whether linear_memory_bytes was measured
+    uint64_t                       linear_memory_bytes; // Linear memory allocated by the Wasm VMs of the invocation
+    string_vector_t                logs; // Diagnostic events as printed by the host, only when debugging is enabled
+    restore_key_status_vector_t    restore_statuses; // Whether each key of a restore can be restored
+    preflight_extension_vector_t   extensions; // Optional sections, for outputs added without changing the layout
 } preflight_result_t;
 
+typedef struct preflight_result_vector_t {
+    preflight_result_t *array;
+    size_t             len;
+} preflight_result_vector_t;
+
+// Token through which preflights are cancelled, see preflight_new_cancel_token()
+typedef struct preflight_cancel_token_t preflight_cancel_token_t;
+
 preflight_result_t *preflight_invoke_hf_op(uintptr_t handle, // Go Handle to forward to SnapshotSourceGet
                                            const xdr_t invoke_hf_op, // InvokeHostFunctionOp XDR
                                            const xdr_t source_account, // AccountId XDR
                                            const ledger_info_t ledger_info,
+                                           const xdr_vector_t ledger_entry_overrides, // array of LedgerEntry XDR
+                                           const xdr_vector_t footprint_keys, // array of LedgerKey XDR declared by the transaction, prefetched
+                                           const xdr_t extra_footprint, // LedgerFootprint XDR added to the simulated one, empty if none
                                            const resource_config_t resource_config,
                                            bool enable_debug,
-                                           const uint32_t auth_mode);
+                                           const uint32_t auth_mode, // 0: enforce, 1: record, 2: record allowing non-root auth, 3: enforce if the op has auth entries, record otherwise, 4: enforce with mock signatures
+                                           const preflight_cancel_token_t *cancel_token); // null if the preflight can't be cancelled
+
+// Simulates every operation against the same snapshot, returning one result per operation
+preflight_result_vector_t *preflight_invoke_hf_ops(uintptr_t          handle, // Go Handle to forward to SnapshotSourceGet
+                                                   const xdr_vector_t invoke_hf_ops, // array of InvokeHostFunctionOp XDR
+                                                   const xdr_vector_t source_accounts, // array of AccountId XDR, one per operation
+                                                   const ledger_info_t ledger_info,
+                                                   const xdr_vector_t ledger_entry_overrides, // array of LedgerEntry XDR
+                                                   const resource_config_t resource_config,
+                                                   bool enable_debug,
+                                                   const uint32_t auth_mode, // 0: enforce, 1: record, 2: record allowing non-root auth, 3: enforce if the op has auth entries, record otherwise, 4: enforce with mock signatures
+                                                   const preflight_cancel_token_t *cancel_token); // null if the preflight can't be cancelled
 
-preflight_result_t *preflight_footprint_ttl_op(uintptr_t   handle, // Go Handle to forward to SnapshotSourceGet
-                                               const xdr_t op_body, // OperationBody XDR
-                                               const xdr_t footprint, // LedgerFootprint XDR
-                                               const ledger_info_t ledger_info);
+preflight_result_t *preflight_extend_ttl_op(uintptr_t          handle, // Go Handle to forward to SnapshotSourceGet
+                                            const xdr_vector_t keys_to_extend, // array of LedgerKey XDR
+                                            const uint32_t     extend_to,
+                                            const ledger_info_t ledger_info,
+                                            const xdr_vector_t ledger_entry_overrides); // array of LedgerEntry XDR
 
+preflight_result_t *preflight_restore_op(uintptr_t          handle, // Go Handle to forward to SnapshotSourceGet
+                                         const xdr_vector_t keys_to_restore, // array of LedgerKey XDR
+                                         const ledger_info_t ledger_info,
+                                         const xdr_vector_t ledger_entry_overrides); // array of LedgerEntry XDR
 
-// LedgerKey XDR to LedgerEntry XDR AND TTL
+
+// LedgerKey XDR to LedgerEntry XDR AND TTL, as of the given ledger
 typedef struct ledger_entry_and_ttl_t {
     xdr_t entry;
     int64_t ttl; // TTL missing if -1
 } ledger_entry_and_ttl_t;
-extern ledger_entry_and_ttl_t SnapshotSourceGet(uintptr_t handle, xdr_t ledger_key);
+extern ledger_entry_and_ttl_t SnapshotSourceGet(uintptr_t handle, xdr_t ledger_key, uint32_t ledger_seq);
+
+typedef struct ledger_entry_and_ttl_vector_t {
+    ledger_entry_and_ttl_t *array;
+    size_t                 len;
+} ledger_entry_and_ttl_vector_t;
+// Batched SnapshotSourceGet, returning one entry (null if missing) per key or an empty vector on failure
+extern ledger_entry_and_ttl_vector_t SnapshotSourceGetMany(uintptr_t handle, xdr_vector_t ledger_keys, uint32_t ledger_seq);
+
+// A ledger entry of a snapshot, see preflight_invoke_hf_ops_from_snapshot()
+typedef struct ledger_snapshot_entry_t {
+    xdr_t                  key; // LedgerKey XDR
+    ledger_entry_and_ttl_t entry_and_ttl;
+} ledger_snapshot_entry_t;
+
+typedef struct ledger_snapshot_t {
+    ledger_snapshot_entry_t *array;
+    size_t                  len;
+} ledger_snapshot_t;
+
+// Like preflight_invoke_hf_ops(), but reading every entry from the given snapshot (the entries missing from it are
+// considered not to exist) instead of calling back into Go
+preflight_result_vector_t *preflight_invoke_hf_ops_from_snapshot(const ledger_snapshot_t snapshot,
+                                                                 const xdr_vector_t invoke_hf_ops, // array of InvokeHostFunctionOp XDR
+                                                                 const xdr_vector_t source_accounts, // array of AccountId XDR, one per operation
+                                                                 const ledger_info_t ledger_info,
+                                                                 const xdr_vector_t ledger_entry_overrides, // array of LedgerEntry XDR
+                                                                 const resource_config_t resource_config,
+                                                                 bool enable_debug,
+                                                                 const uint32_t auth_mode, // 0: enforce, 1: record, 2: record allowing non-root auth, 3: enforce if the op has auth entries, record otherwise, 4: enforce with mock signatures
+                                                                 const preflight_cancel_token_t *cancel_token); // null if the preflight can't be cancelled
+
+typedef struct network_config_result_t {
+    char *json; // Network config (fees, limits and state archival settings) as JSON, empty in case of error
+    char *error; // Error string in case of error, otherwise empty
+} network_config_result_t;
+
+network_config_result_t *preflight_get_network_config(uintptr_t           handle, // Go Handle to forward to SnapshotSourceGet
+                                                      const ledger_info_t ledger_info);
+
+// Returns a token to pass to the preflights to cancel, to be freed once they have returned
+preflight_cancel_token_t *preflight_new_cancel_token(void);
+
+// Makes the preflights the token was passed to fail with a "cancelled" error, from any thread
+void preflight_cancel(const preflight_cancel_token_t *token);
+
+void preflight_free_cancel_token(preflight_cancel_token_t *token);
+
+// Runs all subsequent preflights on a pool of worker_count threads, with up to queue_size preflights
+// waiting for a free worker. Returns false if the pool couldn't be started (e.g. it already was)
+bool preflight_start_worker_pool(size_t worker_count, size_t queue_size);
+
+// Number of preflights waiting for a free worker
+size_t preflight_worker_pool_queue_length(void);
 
 void free_preflight_result(preflight_result_t *result);
 
+void free_preflight_result_vector(preflight_result_vector_t *result);
+
+void free_network_config_result(network_config_result_t *result);
+
 extern void FreeGoLedgerEntryAndTTL(ledger_entry_and_ttl_t ledger_entry_and_ttl);
+extern void FreeGoLedgerEntryAndTTLVector(ledger_entry_and_ttl_vector_t ledger_entries_and_ttls);
@@ -0,0 +1,75 @@
+// Tokens through which callers abort in-flight preflights, e.g. once the client
+// went away or the request timed out.
+//
+// Neither the host created by the simulation nor the replay hosts built by this
+// crate expose a hook into their budget (its metering can't be observed from
+// outside soroban-env-host), so the token can't be checked while the host
+// executes. It is checked on every ledger entry read by the host, before and
+// after each replay of the invocation, and between the phases of a preflight.
+// Execution itself can't be interrupted: a contract spinning without reading
+// ledger entries runs until it exhausts its budget. A cancelled preflight fails
+// with `CANCELLED_ERROR`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Reported to the caller when a preflight is cancelled. Go matches on it to
+// tell apart a cancelled preflight from a failed one.
+pub(crate) const CANCELLED_ERROR: &str = "cancelled";
+
+pub struct CancelToken(AtomicBool);
+
+// A token borrowed from the caller for the duration of a preflight, null if the
+// preflight can't be cancelled.
+#[derive(Clone, Copy)]
+pub(crate) struct CancelTokenRef(pub(crate) *const CancelToken);
+
+// The token is only ever read atomically, and the caller keeps it alive until
+// the preflight has returned, so it can be checked from any thread.
+unsafe impl Send for CancelTokenRef {}
+unsafe impl Sync for CancelTokenRef {}
+
+impl Default for CancelTokenRef {
+    fn default() -> Self {
+        CancelTokenRef(std::ptr::null())
+    }
+}
+
+impl CancelTokenRef {
+    pub(crate) fn is_cancelled(self) -> bool {
+        !self.0.is_null() && unsafe { (*self.0).0.load(Ordering::Relaxed) }
+    }
+}
+
+/// Returns a new token, to be passed to the preflights to cancel and freed
+/// with `preflight_free_cancel_token()` once they have returned.
+#[no_mangle]
+pub extern "C" fn preflight_new_cancel_token() -> *mut CancelToken {
+    Box::into_raw(Box::new(CancelToken(AtomicBool::new(false))))
+}
+
+/// Cancels the preflights the token was passed to, which can be called from
+/// any thread while they run.
+///
+/// # Safety
+///
+/// The token must have been returned by `preflight_new_cancel_token()` and
+/// not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn preflight_cancel(token: *const CancelToken) {
+    if let Some(token) = token.as_ref() {
+        token.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Frees the token.
+///
+/// # Safety
+///
+/// The token must have been returned by `preflight_new_cancel_token()`, and
+/// the preflights it was passed to must have returned.
+#[no_mangle]
+pub unsafe extern "C" fn preflight_free_cancel_token(token: *mut CancelToken) {
+    if !token.is_null() {
+        drop(Box::from_raw(token));
+    }
+}
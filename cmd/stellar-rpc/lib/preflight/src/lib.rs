@@ -5,17 +5,25 @@ extern crate libc;
 extern crate serde_json;
 extern crate sha2;
 
-pub(crate) use anyhow::{anyhow, bail, Result};
+pub(crate) use anyhow::{anyhow, bail, Context, Result};
 pub(crate) use sha2::{Digest, Sha256};
 
 // We really do need everything.
 #[allow(clippy::wildcard_imports)]
 use ffi::*;
 extern crate soroban_env_host_curr;
+#[cfg(feature = "next")]
+extern crate soroban_env_host_next;
 extern crate soroban_env_host_prev;
 extern crate soroban_simulation_curr;
+#[cfg(feature = "next")]
+extern crate soroban_simulation_next;
 extern crate soroban_simulation_prev;
 
+mod cancel;
+mod pool;
+mod spans;
+
 // We support two different versions of soroban simultaneously, switching on the
 // protocol version each supports. This is the exact same mechanism we use in
 // stellar-core to switch soroban hosts on protocol boundaries, and allows
@@ -31,6 +39,98 @@ extern crate soroban_simulation_prev;
 //
 // This is a bit of a hack, but it works well enough for our purposes and works
 // around the absence of parametric modules in the Rust language.
+//
+// With the `next` feature, `shared.rs` is mounted a third time at `crate::next`
+// with an unreleased soroban, so that the upcoming protocol can be preflighted
+// before it ships.
+
+// Protocol 23 charges the reads of the entries kept on disk only, and rent at
+// its own rate. The fee helpers are the same for every protocol from there on,
+// so they are generated in each module mounting one.
+macro_rules! disk_read_fees {
+    () => {
+        pub(crate) fn fees_json(
+            network_config: &soroban_simulation::NetworkConfig,
+        ) -> serde_json::Value {
+            let fees = &network_config.fee_configuration;
+            serde_json::json!({
+                "feePerInstructionIncrement": fees.fee_per_instruction_increment,
+                "feePerReadEntry": fees.fee_per_disk_read_entry,
+                "feePerWriteEntry": fees.fee_per_write_entry,
+                "feePerRead1kb": fees.fee_per_disk_read_1kb,
+                "feePerWrite1kb": fees.fee_per_write_1kb,
+                "feePerRent1kb": network_config.rent_fee_configuration.fee_per_rent_1kb,
+                "feePerHistorical1kb": fees.fee_per_historical_1kb,
+                "feePerContractEvent1kb": fees.fee_per_contract_event_1kb,
+                "feePerTransactionSize1kb": fees.fee_per_transaction_size_1kb,
+            })
+        }
+
+        // The entries kept on disk are the classic entries and the archived ones
+        // restored by an invocation, along with the written entries. Returns the
+        // read entries and read bytes fees.
+        pub(crate) fn read_fees(
+            network_config: &soroban_simulation::NetworkConfig,
+            transaction_data: &xdr::SorobanTransactionData,
+        ) -> (i64, i64) {
+            let fees = &network_config.fee_configuration;
+            let resources = &transaction_data.resources;
+            let footprint = &resources.footprint;
+            let mut read_entries = footprint
+                .read_only
+                .iter()
+                .chain(footprint.read_write.iter())
+                .filter(|k| {
+                    !matches!(
+                        k,
+                        xdr::LedgerKey::ContractData(_) | xdr::LedgerKey::ContractCode(_)
+                    )
+                })
+                .count()
+                + footprint.read_write.len();
+            if let xdr::SorobanTransactionDataExt::V1(ext) = &transaction_data.ext {
+                read_entries += ext.archived_soroban_entries.len();
+            }
+            (
+                fees.fee_per_disk_read_entry
+                    .saturating_mul(i64::try_from(read_entries).unwrap_or(i64::MAX)),
+                crate::fee_per_increment(
+                    resources.disk_read_bytes,
+                    fees.fee_per_disk_read_1kb,
+                    soroban_env_host::fees::DATA_SIZE_1KB_INCREMENT,
+                ),
+            )
+        }
+
+        // Only the classic entries (read from disk) count towards the read
+        // bytes, the contract data and code being kept in memory.
+        pub(crate) fn drop_read_entry_bytes(
+            resources: &mut xdr::SorobanResources,
+            key: &xdr::LedgerKey,
+            entry_xdr_size: u32,
+        ) {
+            if !matches!(
+                key,
+                xdr::LedgerKey::ContractData(_) | xdr::LedgerKey::ContractCode(_)
+            ) {
+                resources.disk_read_bytes = resources.disk_read_bytes.saturating_sub(entry_xdr_size);
+            }
+        }
+
+        pub(crate) fn add_read_entry_bytes(
+            resources: &mut xdr::SorobanResources,
+            key: &xdr::LedgerKey,
+            entry_xdr_size: u32,
+        ) {
+            if !matches!(
+                key,
+                xdr::LedgerKey::ContractData(_) | xdr::LedgerKey::ContractCode(_)
+            ) {
+                resources.disk_read_bytes = resources.disk_read_bytes.saturating_add(entry_xdr_size);
+            }
+        }
+    };
+}
 
 #[path = "."]
 mod curr {
@@ -60,6 +160,111 @@ mod curr {
             shared::get_fallible_from_go_ledger_storage(self, key.as_ref())
         }
     }
+
+    // Protocol 23 charges rent on contract code by the in-memory size of its
+    // compiled module rather than its XDR size.
+    pub(crate) fn entry_size_for_rent(
+        network_config: &soroban_simulation::NetworkConfig,
+        entry: &xdr::LedgerEntry,
+        entry_xdr_size: u32,
+    ) -> anyhow::Result<u32> {
+        let budget = soroban_env_host::budget::Budget::try_from_configs(
+            u64::try_from(network_config.tx_max_instructions)?,
+            u64::from(network_config.tx_memory_limit),
+            network_config.cpu_cost_params.clone(),
+            network_config.memory_cost_params.clone(),
+        )?;
+        Ok(soroban_env_host::e2e_invoke::entry_size_for_rent(
+            &budget,
+            entry,
+            entry_xdr_size,
+        )?)
+    }
+
+    disk_read_fees!();
+
+    pub(crate) fn rent_change(
+        _key: &xdr::LedgerKey,
+        is_persistent: bool,
+        old_size_bytes: u32,
+        new_size_bytes: u32,
+        old_live_until_ledger: u32,
+        new_live_until_ledger: u32,
+    ) -> soroban_env_host::fees::LedgerEntryRentChange {
+        soroban_env_host::fees::LedgerEntryRentChange {
+            is_persistent,
+            old_size_bytes,
+            new_size_bytes,
+            old_live_until_ledger,
+            new_live_until_ledger,
+        }
+    }
+}
+
+#[cfg(feature = "next")]
+#[path = "."]
+mod next {
+    pub(crate) use soroban_env_host_next as soroban_env_host;
+    pub(crate) use soroban_env_host_next::xdr;
+    pub(crate) use soroban_simulation_next as soroban_simulation;
+
+    #[allow(clippy::duplicate_mod)]
+    pub(crate) mod shared;
+
+    pub(crate) const PROTOCOL: u32 = soroban_env_host::meta::INTERFACE_VERSION.protocol;
+
+    use std::{rc::Rc, result::Result};
+
+    impl soroban_env_host::storage::SnapshotSource for crate::GoLedgerStorage {
+        fn get(
+            &self,
+            key: &Rc<xdr::LedgerKey>,
+        ) -> Result<
+            Option<soroban_env_host::storage::EntryWithLiveUntil>,
+            soroban_env_host::HostError,
+        > {
+            shared::get_fallible_from_go_ledger_storage(self, key.as_ref())
+        }
+    }
+
+    pub(crate) fn entry_size_for_rent(
+        network_config: &soroban_simulation::NetworkConfig,
+        entry: &xdr::LedgerEntry,
+        entry_xdr_size: u32,
+    ) -> anyhow::Result<u32> {
+        let budget = soroban_env_host::budget::Budget::try_from_configs(
+            u64::try_from(network_config.tx_max_instructions)?,
+            u64::from(network_config.tx_memory_limit),
+            network_config.cpu_cost_params.clone(),
+            network_config.memory_cost_params.clone(),
+        )?;
+        Ok(soroban_env_host::e2e_invoke::entry_size_for_rent(
+            &budget,
+            entry,
+            entry_xdr_size,
+        )?)
+    }
+
+    disk_read_fees!();
+
+    // The upcoming protocol charges rent on contract code differently.
+    pub(crate) fn rent_change(
+        key: &xdr::LedgerKey,
+        is_persistent: bool,
+        old_size_bytes: u32,
+        new_size_bytes: u32,
+        old_live_until_ledger: u32,
+        new_live_until_ledger: u32,
+    ) -> soroban_env_host::fees::LedgerEntryRentChange {
+        soroban_env_host::fees::LedgerEntryRentChange {
+            is_persistent,
+            is_code_entry: matches!(key, xdr::LedgerKey::ContractCode(_)),
+            old_size_bytes,
+            new_size_bytes,
+            old_live_until_ledger,
+            new_live_until_ledger,
+        }
+    }
 }
 
 #[path = "."]
@@ -85,13 +290,98 @@ mod prev {
             shared::get_fallible_from_go_ledger_storage(self, key.as_ref())
         }
     }
+
+    // Protocol 22 charges rent on the XDR size of every entry.
+    #[allow(clippy::unnecessary_wraps)]
+    pub(crate) fn entry_size_for_rent(
+        _network_config: &soroban_simulation::NetworkConfig,
+        _entry: &xdr::LedgerEntry,
+        entry_xdr_size: u32,
+    ) -> anyhow::Result<u32> {
+        Ok(entry_xdr_size)
+    }
+
+    // Protocol 22 charges the reads of every entry, and rent at the write fee.
+    pub(crate) fn fees_json(
+        network_config: &soroban_simulation::NetworkConfig,
+    ) -> serde_json::Value {
+        let fees = &network_config.fee_configuration;
+        serde_json::json!({
+            "feePerInstructionIncrement": fees.fee_per_instruction_increment,
+            "feePerReadEntry": fees.fee_per_read_entry,
+            "feePerWriteEntry": fees.fee_per_write_entry,
+            "feePerRead1kb": fees.fee_per_read_1kb,
+            "feePerWrite1kb": fees.fee_per_write_1kb,
+            "feePerRent1kb": network_config.rent_fee_configuration.fee_per_write_1kb,
+            "feePerHistorical1kb": fees.fee_per_historical_1kb,
+            "feePerContractEvent1kb": fees.fee_per_contract_event_1kb,
+            "feePerTransactionSize1kb": fees.fee_per_transaction_size_1kb,
+        })
+    }
+
+    // Protocol 22 charges the reads of every entry, the written entries being
+    // charged twice. Returns the read entries and read bytes fees.
+    pub(crate) fn read_fees(
+        network_config: &soroban_simulation::NetworkConfig,
+        transaction_data: &xdr::SorobanTransactionData,
+    ) -> (i64, i64) {
+        let fees = &network_config.fee_configuration;
+        let resources = &transaction_data.resources;
+        let footprint = &resources.footprint;
+        let read_entries = footprint.read_only.len() + 2 * footprint.read_write.len();
+        (
+            fees.fee_per_read_entry
+                .saturating_mul(i64::try_from(read_entries).unwrap_or(i64::MAX)),
+            crate::fee_per_increment(
+                resources.read_bytes,
+                fees.fee_per_read_1kb,
+                soroban_env_host::fees::DATA_SIZE_1KB_INCREMENT,
+            ),
+        )
+    }
+
+    // Protocol 22 counts every entry towards the read bytes.
+    pub(crate) fn drop_read_entry_bytes(
+        resources: &mut xdr::SorobanResources,
+        _key: &xdr::LedgerKey,
+        entry_xdr_size: u32,
+    ) {
+        resources.read_bytes = resources.read_bytes.saturating_sub(entry_xdr_size);
+    }
+
+    pub(crate) fn add_read_entry_bytes(
+        resources: &mut xdr::SorobanResources,
+        _key: &xdr::LedgerKey,
+        entry_xdr_size: u32,
+    ) {
+        resources.read_bytes = resources.read_bytes.saturating_add(entry_xdr_size);
+    }
+
+    pub(crate) fn rent_change(
+        _key: &xdr::LedgerKey,
+        is_persistent: bool,
+        old_size_bytes: u32,
+        new_size_bytes: u32,
+        old_live_until_ledger: u32,
+        new_live_until_ledger: u32,
+    ) -> soroban_env_host::fees::LedgerEntryRentChange {
+        soroban_env_host::fees::LedgerEntryRentChange {
+            is_persistent,
+            old_size_bytes,
+            new_size_bytes,
+            old_live_until_ledger,
+            new_live_until_ledger,
+        }
+    }
 }
 
-use std::cell::RefCell;
-use std::ffi::CString;
-use std::mem;
+use cancel::{CancelToken, CancelTokenRef};
+use std::collections::HashMap;
 use std::panic;
 use std::ptr::null_mut;
+use std::slice;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::UNIX_EPOCH;
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -102,10 +392,46 @@ pub struct CLedgerInfo {
     pub network_passphrase: *const libc::c_char,
     pub base_reserve: u32,
     pub bucket_list_size: u64,
+    // Protocol to simulate with instead of `protocol_version` (that of the
+    // ledger), 0 to simulate with the protocol of the ledger
+    pub simulated_protocol_version: u32,
+    // Sequence and timestamp the ledger is seen with by the simulation instead
+    // of `sequence_number` and `timestamp`, 0 to keep them. The state is still
+    // read from the ledger `sequence_number`.
+    pub sequence_number_override: u32,
+    pub timestamp_override: u64,
+}
+
+// The Rust counterpart of `CLedgerInfo`
+#[derive(Clone)]
+pub(crate) struct PreflightLedgerInfo {
+    pub(crate) protocol_version: u32,
+    pub(crate) sequence_number: u32,
+    pub(crate) timestamp: u64,
+    pub(crate) network_passphrase: String,
+    pub(crate) base_reserve: u32,
+    pub(crate) bucket_list_size: u64,
+    pub(crate) sequence_number_override: Option<u32>,
+    pub(crate) timestamp_override: Option<u64>,
+}
+
+impl AsRust<PreflightLedgerInfo> for CLedgerInfo {
+    unsafe fn as_rust(&self) -> PreflightLedgerInfo {
+        PreflightLedgerInfo {
+            protocol_version: self.protocol_version,
+            sequence_number: self.sequence_number,
+            timestamp: self.timestamp,
+            network_passphrase: unsafe { self.network_passphrase.as_rust() },
+            base_reserve: self.base_reserve,
+            bucket_list_size: self.bucket_list_size,
+            sequence_number_override: (self.sequence_number_override != 0)
+                .then_some(self.sequence_number_override),
+            timestamp_override: (self.timestamp_override != 0).then_some(self.timestamp_override),
+        }
+    }
 }
 
 #[repr(C)]
-#[derive(Copy, Clone)]
 pub struct CXDRVector {
     pub array: *mut CXDR,
     pub len: libc::size_t,
@@ -120,15 +446,72 @@ impl Default for CXDRVector {
     }
 }
 
+impl<T> CReprOf<Vec<T>> for CXDRVector
+where
+    CXDR: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_xdrs = input.into_iter().map(CXDR::c_repr_of).collect();
+        let (array, len) = vec_to_c_array(c_xdrs);
+        CXDRVector { array, len }
+    }
+}
+
+impl CXDRVector {
+    // Borrows each XDR without copying it, see `CXDR::as_slice()`
+    unsafe fn as_slices<'a>(&self) -> Vec<&'a [u8]> {
+        if self.array.is_null() {
+            return Vec::new();
+        }
+        let xdrs = unsafe { slice::from_raw_parts(self.array, self.len) };
+        xdrs.iter().map(|xdr| unsafe { xdr.as_slice() }).collect()
+    }
+}
+
+impl AsRust<Vec<Vec<u8>>> for CXDRVector {
+    unsafe fn as_rust(&self) -> Vec<Vec<u8>> {
+        if self.array.is_null() {
+            return Vec::new();
+        }
+        let xdrs = unsafe { slice::from_raw_parts(self.array, self.len) };
+        xdrs.iter().map(|xdr| unsafe { xdr.as_rust() }).collect()
+    }
+}
+
+impl CDrop for CXDRVector {
+    unsafe fn drop_c(self) {
+        for xdr in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { xdr.drop_c() };
+        }
+    }
+}
+
 #[repr(C)]
-#[derive(Copy, Clone)]
 pub struct CXDRDiff {
     pub before: CXDR,
     pub after: CXDR,
 }
 
+// (before, after) XDR, `None` meaning the entry is absent
+impl CReprOf<(Option<Vec<u8>>, Option<Vec<u8>>)> for CXDRDiff {
+    fn c_repr_of((before, after): (Option<Vec<u8>>, Option<Vec<u8>>)) -> Self {
+        CXDRDiff {
+            before: CXDR::c_repr_of(before),
+            after: CXDR::c_repr_of(after),
+        }
+    }
+}
+
+impl CDrop for CXDRDiff {
+    unsafe fn drop_c(self) {
+        unsafe {
+            self.before.drop_c();
+            self.after.drop_c();
+        }
+    }
+}
+
 #[repr(C)]
-#[derive(Copy, Clone)]
 pub struct CXDRDiffVector {
     pub array: *mut CXDRDiff,
     pub len: libc::size_t,
@@ -143,15 +526,650 @@ impl Default for CXDRDiffVector {
     }
 }
 
+impl<T> CReprOf<Vec<T>> for CXDRDiffVector
+where
+    CXDRDiff: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_diffs = input.into_iter().map(CXDRDiff::c_repr_of).collect();
+        let (array, len) = vec_to_c_array(c_diffs);
+        CXDRDiffVector { array, len }
+    }
+}
+
+impl CDrop for CXDRDiffVector {
+    unsafe fn drop_c(self) {
+        for diff in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { diff.drop_c() };
+        }
+    }
+}
+
 #[repr(C)]
-#[derive(Copy, Clone)]
+pub struct CFootprintEntryBytes {
+    // LedgerKey XDR
+    pub key: CXDR,
+    pub read_bytes: u32,
+    pub write_bytes: u32,
+}
+
+// (key XDR, read bytes, write bytes)
+impl CReprOf<(Vec<u8>, u32, u32)> for CFootprintEntryBytes {
+    fn c_repr_of((key, read_bytes, write_bytes): (Vec<u8>, u32, u32)) -> Self {
+        CFootprintEntryBytes {
+            key: CXDR::c_repr_of(key),
+            read_bytes,
+            write_bytes,
+        }
+    }
+}
+
+impl CDrop for CFootprintEntryBytes {
+    unsafe fn drop_c(self) {
+        unsafe { self.key.drop_c() };
+    }
+}
+
+#[repr(C)]
+pub struct CFootprintEntryBytesVector {
+    pub array: *mut CFootprintEntryBytes,
+    pub len: libc::size_t,
+}
+
+impl Default for CFootprintEntryBytesVector {
+    fn default() -> Self {
+        CFootprintEntryBytesVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> CReprOf<Vec<T>> for CFootprintEntryBytesVector
+where
+    CFootprintEntryBytes: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_entries = input
+            .into_iter()
+            .map(CFootprintEntryBytes::c_repr_of)
+            .collect();
+        let (array, len) = vec_to_c_array(c_entries);
+        CFootprintEntryBytesVector { array, len }
+    }
+}
+
+impl CDrop for CFootprintEntryBytesVector {
+    unsafe fn drop_c(self) {
+        for entry in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { entry.drop_c() };
+        }
+    }
+}
+
+#[repr(C)]
+pub struct CRentChange {
+    // LedgerKey XDR
+    pub key: CXDR,
+    // Size the rent is charged on, after the change
+    pub size_bytes: u32,
+    pub old_live_until_ledger: u32,
+    pub new_live_until_ledger: u32,
+    pub rent_fee: i64,
+}
+
+// (key XDR, size, old live until ledger, new live until ledger, rent fee)
+impl CReprOf<(Vec<u8>, u32, u32, u32, i64)> for CRentChange {
+    fn c_repr_of(
+        (key, size_bytes, old_live_until_ledger, new_live_until_ledger, rent_fee): (
+            Vec<u8>,
+            u32,
+            u32,
+            u32,
+            i64,
+        ),
+    ) -> Self {
+        CRentChange {
+            key: CXDR::c_repr_of(key),
+            size_bytes,
+            old_live_until_ledger,
+            new_live_until_ledger,
+            rent_fee,
+        }
+    }
+}
+
+impl CDrop for CRentChange {
+    unsafe fn drop_c(self) {
+        unsafe { self.key.drop_c() };
+    }
+}
+
+#[repr(C)]
+pub struct CRentChangeVector {
+    pub array: *mut CRentChange,
+    pub len: libc::size_t,
+}
+
+impl Default for CRentChangeVector {
+    fn default() -> Self {
+        CRentChangeVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> CReprOf<Vec<T>> for CRentChangeVector
+where
+    CRentChange: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_changes = input.into_iter().map(CRentChange::c_repr_of).collect();
+        let (array, len) = vec_to_c_array(c_changes);
+        CRentChangeVector { array, len }
+    }
+}
+
+impl CDrop for CRentChangeVector {
+    unsafe fn drop_c(self) {
+        for change in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { change.drop_c() };
+        }
+    }
+}
+
+// Kinds of non-fatal preflight findings
+pub const WARNING_RESOURCE_NEAR_LIMIT: u32 = 1;
+pub const WARNING_NON_ROOT_AUTH: u32 = 2;
+pub const WARNING_DEPRECATED_HOST_FUNCTION: u32 = 3;
+pub const WARNING_ENTRY_NEAR_ARCHIVAL: u32 = 4;
+pub const WARNING_LINEAR_MEMORY_UNMEASURED: u32 = 5;
+
+#[repr(C)]
+pub struct CPreflightWarning {
+    // One of the WARNING_* constants
+    pub kind: u32,
+    pub message: *mut libc::c_char,
+}
+
+// (kind, message)
+impl CReprOf<(u32, String)> for CPreflightWarning {
+    fn c_repr_of((kind, message): (u32, String)) -> Self {
+        CPreflightWarning {
+            kind,
+            message: CReprOf::c_repr_of(message),
+        }
+    }
+}
+
+impl CDrop for CPreflightWarning {
+    unsafe fn drop_c(self) {
+        unsafe { self.message.drop_c() };
+    }
+}
+
+#[repr(C)]
+pub struct CPreflightWarningVector {
+    pub array: *mut CPreflightWarning,
+    pub len: libc::size_t,
+}
+
+impl Default for CPreflightWarningVector {
+    fn default() -> Self {
+        CPreflightWarningVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> CReprOf<Vec<T>> for CPreflightWarningVector
+where
+    CPreflightWarning: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_warnings = input
+            .into_iter()
+            .map(CPreflightWarning::c_repr_of)
+            .collect();
+        let (array, len) = vec_to_c_array(c_warnings);
+        CPreflightWarningVector { array, len }
+    }
+}
+
+impl CDrop for CPreflightWarningVector {
+    unsafe fn drop_c(self) {
+        for warning in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { warning.drop_c() };
+        }
+    }
+}
+
+// Machine-readable categories of preflight errors
+pub const ERROR_CATEGORY_NONE: u32 = 0;
+pub const ERROR_CATEGORY_STORAGE: u32 = 1;
+pub const ERROR_CATEGORY_BUDGET: u32 = 2;
+pub const ERROR_CATEGORY_AUTH: u32 = 3;
+pub const ERROR_CATEGORY_CONTRACT: u32 = 4;
+pub const ERROR_CATEGORY_HOST: u32 = 5;
+pub const ERROR_CATEGORY_INVALID_INPUT: u32 = 6;
+
+// Context of the errors caused by the inputs of the caller (e.g. XDR which
+// can't be decoded), which are reported as ERROR_CATEGORY_INVALID_INPUT
+// rather than as failures of the simulation
+#[derive(Debug)]
+pub(crate) struct InvalidInput;
+
+impl std::fmt::Display for InvalidInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid input")
+    }
+}
+
+#[repr(C)]
+pub struct CErrorDetails {
+    // One of the ERROR_CATEGORY_* constants, ERROR_CATEGORY_NONE if there is no
+    // error or it was raised outside of the simulation for another reason than
+    // invalid input
+    pub category: u32,
+    // ScError XDR, empty if the error didn't come from the host
+    pub sc_error: CXDR,
+    // Contract ID (32 bytes) of the innermost contract call executing when the
+    // error was raised, empty if unknown
+    pub contract_id: CXDR,
+}
+
+impl Default for CErrorDetails {
+    fn default() -> Self {
+        CErrorDetails {
+            category: ERROR_CATEGORY_NONE,
+            sc_error: CXDR::default(),
+            contract_id: CXDR::default(),
+        }
+    }
+}
+
+impl CDrop for CErrorDetails {
+    unsafe fn drop_c(self) {
+        unsafe {
+            self.sc_error.drop_c();
+            self.contract_id.drop_c();
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct CAdjustmentFactor {
+    // Whether the factors below override the default adjustment
+    pub set: bool,
+    pub multiplicative_factor: f64,
+    pub additive_factor: u32,
+}
+
+// Holding no pointers, this is also taken as is by the Rust API of `shared.rs`
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
 pub struct CResourceConfig {
     pub instruction_leeway: u64,
+    pub instructions: CAdjustmentFactor,
+    pub read_bytes: CAdjustmentFactor,
+    pub write_bytes: CAdjustmentFactor,
+    pub tx_size: CAdjustmentFactor,
+    pub refundable_fee: CAdjustmentFactor,
+    // When set, the PRNG (used e.g. for auth nonces) is seeded from
+    // `prng_seed` rather than randomly, making the simulation reproducible
+    pub has_prng_seed: bool,
+    pub prng_seed: u64,
+    // When set, the RestoreFootprint preamble required by archived entries
+    // isn't simulated (archived entries are still treated as restored)
+    pub skip_restore_preamble: bool,
+    // When set, the transaction-level CPU and memory limits of the network are
+    // lifted, so that the resources needed by transactions exceeding them can
+    // still be simulated
+    pub relax_network_limits: bool,
+    // Caller-imposed caps on the instructions and memory of the invocation,
+    // which abort the simulation as soon as they are exceeded (0 if uncapped)
+    pub max_instructions: u64,
+    pub max_memory_bytes: u64,
+    // When set, the read-only keys of the recorded footprint which the
+    // invocation succeeds without are dropped from it, by replaying it with
+    // each of them removed
+    pub minimize_footprint: bool,
+    // When set, the `__check_auth()` of the contract accounts authorizing the
+    // invocation isn't run: enforced auth entries are matched against the
+    // recorded authorizations instead (see `check_mock_signature_auth()`),
+    // and the recorded contract accounts are reported in the
+    // PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH extension
+    pub skip_check_auth: bool,
+    // Number of ledgers past the simulated one the signatures of the recorded
+    // address credentials are suggested to expire at, 0 to leave their
+    // expiration unset
+    pub signature_expiration_ledgers: u32,
+    // Replays a successful invocation to measure the linear memory allocated by
+    // its Wasm VMs, which doubles the cost of the preflight
+    pub measure_linear_memory: bool,
+}
+
+impl CResourceConfig {
+    fn base_prng_seed(&self) -> Option<[u8; 32]> {
+        self.has_prng_seed
+            .then(|| Sha256::digest(self.prng_seed.to_be_bytes()).into())
+    }
+}
+
+// Whether a key of a restore can be restored
+pub const RESTORE_STATUS_RESTORABLE: u32 = 1;
+pub const RESTORE_STATUS_ALREADY_LIVE: u32 = 2;
+pub const RESTORE_STATUS_NOT_FOUND: u32 = 3;
+pub const RESTORE_STATUS_TOO_LARGE: u32 = 4;
+
+#[repr(C)]
+pub struct CRestoreKeyStatus {
+    // LedgerKey XDR
+    pub key: CXDR,
+    // One of the RESTORE_STATUS_* constants
+    pub status: u32,
+}
+
+// (key XDR, status)
+impl CReprOf<(Vec<u8>, u32)> for CRestoreKeyStatus {
+    fn c_repr_of((key, status): (Vec<u8>, u32)) -> Self {
+        CRestoreKeyStatus {
+            key: CXDR::c_repr_of(key),
+            status,
+        }
+    }
+}
+
+impl CDrop for CRestoreKeyStatus {
+    unsafe fn drop_c(self) {
+        unsafe { self.key.drop_c() };
+    }
+}
+
+#[repr(C)]
+pub struct CRestoreKeyStatusVector {
+    pub array: *mut CRestoreKeyStatus,
+    pub len: libc::size_t,
+}
+
+impl Default for CRestoreKeyStatusVector {
+    fn default() -> Self {
+        CRestoreKeyStatusVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> CReprOf<Vec<T>> for CRestoreKeyStatusVector
+where
+    CRestoreKeyStatus: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_statuses = input
+            .into_iter()
+            .map(CRestoreKeyStatus::c_repr_of)
+            .collect();
+        let (array, len) = vec_to_c_array(c_statuses);
+        CRestoreKeyStatusVector { array, len }
+    }
+}
+
+impl CDrop for CRestoreKeyStatusVector {
+    unsafe fn drop_c(self) {
+        for status in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { status.drop_c() };
+        }
+    }
+}
+
+#[repr(C)]
+pub struct CStringVector {
+    pub array: *mut *mut libc::c_char,
+    pub len: libc::size_t,
+}
+
+impl Default for CStringVector {
+    fn default() -> Self {
+        CStringVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl CReprOf<Vec<String>> for CStringVector {
+    fn c_repr_of(input: Vec<String>) -> Self {
+        let c_strings = input.into_iter().map(CReprOf::c_repr_of).collect();
+        let (array, len) = vec_to_c_array(c_strings);
+        CStringVector { array, len }
+    }
+}
+
+impl CDrop for CStringVector {
+    unsafe fn drop_c(self) {
+        for string in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { string.drop_c() };
+        }
+    }
+}
+
+// A phase of a preflight, see `spans::SpanRecorder`
+#[repr(C)]
+pub struct CPreflightSpan {
+    pub name: *mut libc::c_char,
+    // Start of the phase, in nanoseconds since the Unix epoch
+    pub start_unix_nanos: u64,
+    pub duration_nanos: u64,
+}
+
+impl CReprOf<spans::Span> for CPreflightSpan {
+    fn c_repr_of(span: spans::Span) -> Self {
+        let start = span.start.duration_since(UNIX_EPOCH).unwrap_or_default();
+        CPreflightSpan {
+            name: CReprOf::c_repr_of(span.name.to_string()),
+            start_unix_nanos: u64::try_from(start.as_nanos()).unwrap_or(u64::MAX),
+            duration_nanos: u64::try_from(span.duration.as_nanos()).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+impl CDrop for CPreflightSpan {
+    unsafe fn drop_c(self) {
+        unsafe { self.name.drop_c() };
+    }
+}
+
+#[repr(C)]
+pub struct CPreflightSpanVector {
+    pub array: *mut CPreflightSpan,
+    pub len: libc::size_t,
+}
+
+impl Default for CPreflightSpanVector {
+    fn default() -> Self {
+        CPreflightSpanVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl CReprOf<Vec<spans::Span>> for CPreflightSpanVector {
+    fn c_repr_of(input: Vec<spans::Span>) -> Self {
+        let c_spans = input.into_iter().map(CPreflightSpan::c_repr_of).collect();
+        let (array, len) = vec_to_c_array(c_spans);
+        CPreflightSpanVector { array, len }
+    }
+}
+
+impl CDrop for CPreflightSpanVector {
+    unsafe fn drop_c(self) {
+        for span in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { span.drop_c() };
+        }
+    }
+}
+
+// Version of the layout of `CPreflightResult`, to be bumped whenever its
+// fields change. Outputs which callers can do without are better added as
+// extensions, which leave the layout untouched.
+pub const PREFLIGHT_RESULT_VERSION: u32 = 2;
+
+pub const PREFLIGHT_EXTENSION_FEE_BREAKDOWN: u32 = 1;
+pub const PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH: u32 = 2;
+
+// The resource fee of a simulated transaction split by what it pays for, the
+// parts adding up to the resource fee. All the parts but the rent are
+// computed like the host does. The rent is what is left, so that it includes
+// the margin the refundable fee is adjusted by.
+#[derive(Default)]
+pub(crate) struct FeeBreakdown {
+    pub(crate) instructions: i64,
+    // Written entries are charged as read too
+    pub(crate) read_entries: i64,
+    pub(crate) write_entries: i64,
+    pub(crate) read_bytes: i64,
+    pub(crate) write_bytes: i64,
+    // Storing the transaction and its result in the history archives
+    pub(crate) historical: i64,
+    // Propagating the transaction across the network
+    pub(crate) transaction_size: i64,
+    // Contract events and return value of an invocation
+    pub(crate) events: i64,
+    pub(crate) rent: i64,
+}
+
+impl FeeBreakdown {
+    // Every part but the rent, which the simulated resources determine
+    pub(crate) fn resource_fees(&self) -> [i64; 8] {
+        [
+            self.instructions,
+            self.read_entries,
+            self.write_entries,
+            self.read_bytes,
+            self.write_bytes,
+            self.historical,
+            self.transaction_size,
+            self.events,
+        ]
+    }
+
+    // Adds what the parts of another breakdown of the same transaction grew
+    // by, e.g. once its resources were increased
+    pub(crate) fn add_increase(&mut self, before: &FeeBreakdown, after: &FeeBreakdown) {
+        self.instructions += after.instructions - before.instructions;
+        self.read_entries += after.read_entries - before.read_entries;
+        self.write_entries += after.write_entries - before.write_entries;
+        self.read_bytes += after.read_bytes - before.read_bytes;
+        self.write_bytes += after.write_bytes - before.write_bytes;
+        self.historical += after.historical - before.historical;
+        self.transaction_size += after.transaction_size - before.transaction_size;
+        self.events += after.events - before.events;
+    }
+
+    fn to_json(&self) -> Vec<u8> {
+        serde_json::json!({
+            "instructions": self.instructions,
+            "readEntries": self.read_entries,
+            "writeEntries": self.write_entries,
+            "readBytes": self.read_bytes,
+            "writeBytes": self.write_bytes,
+            "historical": self.historical,
+            "transactionSize": self.transaction_size,
+            "events": self.events,
+            "rent": self.rent,
+        })
+        .to_string()
+        .into_bytes()
+    }
+}
+
+// Fee of a resource charged per increment (e.g. per 1KB), rounded up like the
+// host's fee computation (which doesn't expose it) does.
+fn fee_per_increment(value: u32, fee_rate: i64, increment: i64) -> i64 {
+    let increment = increment.max(1);
+    let fee = i64::from(value).saturating_mul(fee_rate);
+    fee / increment + i64::from(fee % increment > 0)
+}
+
+// An optional section of a preflight result, whose tag tells how to decode its
+// data. Callers skip the tags they don't know about.
+//
+// Tags:
+//
+//   - PREFLIGHT_EXTENSION_FEE_BREAKDOWN: JSON object splitting the resource
+//     fee of the transaction data by what it pays for, see `FeeBreakdown`
+//   - PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH: ScVal XDR of the vector of the
+//     contract accounts whose `__check_auth()` wasn't run, one per
+//     authorization the invocation required of them
+#[repr(C)]
+pub struct CPreflightExtension {
+    pub tag: u32,
+    pub data: CXDR,
+}
+
+// (tag, data)
+impl CReprOf<(u32, Vec<u8>)> for CPreflightExtension {
+    fn c_repr_of((tag, data): (u32, Vec<u8>)) -> Self {
+        CPreflightExtension {
+            tag,
+            data: CXDR::c_repr_of(data),
+        }
+    }
+}
+
+impl CDrop for CPreflightExtension {
+    unsafe fn drop_c(self) {
+        unsafe { self.data.drop_c() };
+    }
+}
+
+#[repr(C)]
+pub struct CPreflightExtensionVector {
+    pub array: *mut CPreflightExtension,
+    pub len: libc::size_t,
+}
+
+impl Default for CPreflightExtensionVector {
+    fn default() -> Self {
+        CPreflightExtensionVector {
+            array: null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> CReprOf<Vec<T>> for CPreflightExtensionVector
+where
+    CPreflightExtension: CReprOf<T>,
+{
+    fn c_repr_of(input: Vec<T>) -> Self {
+        let c_extensions = input
+            .into_iter()
+            .map(CPreflightExtension::c_repr_of)
+            .collect();
+        let (array, len) = vec_to_c_array(c_extensions);
+        CPreflightExtensionVector { array, len }
+    }
+}
+
+impl CDrop for CPreflightExtensionVector {
+    unsafe fn drop_c(self) {
+        for extension in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { extension.drop_c() };
+        }
+    }
 }
 
 #[repr(C)]
-#[derive(Copy, Clone)]
 pub struct CPreflightResult {
+    // Always PREFLIGHT_RESULT_VERSION, which callers check before reading the
+    // other fields
+    pub version: u32,
     // Error string in case of error, otherwise null
     pub error: *mut libc::c_char,
     // Error string in case of error, otherwise null
@@ -163,7 +1181,9 @@ pub struct CPreflightResult {
     // Minimum recommended resource fee
     pub min_fee: i64,
     // array of XDR ContractEvents
-    pub events: CXDRVector,
+    pub contract_events: CXDRVector,
+    // array of XDR DiagnosticEvents
+    pub diagnostic_events: CXDRVector,
     pub cpu_instructions: u64,
     pub memory_bytes: u64,
     // SorobanTransactionData XDR for a prerequired RestoreFootprint operation
@@ -172,22 +1192,103 @@ pub struct CPreflightResult {
     pub pre_restore_min_fee: i64,
     // Contains the ledger entry changes which would be caused by the transaction execution
     pub ledger_entry_diff: CXDRDiffVector,
+    // Bytes read and written for each entry of the footprint
+    pub footprint_bytes: CFootprintEntryBytesVector,
+    // Rent charged for each entry whose TTL is extended or restored
+    pub rent_changes: CRentChangeVector,
+    // Structured counterpart of the error string
+    pub error_details: CErrorDetails,
+    // array of XDR DiagnosticEvents explaining why an uploaded Wasm module was
+    // rejected (empty otherwise), recorded even when debugging is disabled
+    pub wasm_validation_events: CXDRVector,
+    // Non-fatal findings of the simulation
+    pub warnings: CPreflightWarningVector,
+    // Timings of the phases of the preflight
+    pub spans: CPreflightSpanVector,
+    // Linear memory allocated by the Wasm VMs of the invocation, only when
+    // requested with `CResourceConfig::measure_linear_memory`
+    pub has_linear_memory_bytes: bool,
+    pub linear_memory_bytes: u64,
+    // The diagnostic events as printed by the host, only when debugging
+    pub logs: CStringVector,
+    // Whether each key of a restore can be restored
+    pub restore_statuses: CRestoreKeyStatusVector,
+    // Optional sections, see `CPreflightExtension`
+    pub extensions: CPreflightExtensionVector,
 }
 
 impl Default for CPreflightResult {
     fn default() -> Self {
         Self {
-            error: CString::new(String::new()).unwrap().into_raw(),
+            version: PREFLIGHT_RESULT_VERSION,
+            error: CReprOf::c_repr_of(String::new()),
             auth: CXDRVector::default(),
             result: CXDR::default(),
             transaction_data: CXDR::default(),
             min_fee: 0,
-            events: CXDRVector::default(),
+            contract_events: CXDRVector::default(),
+            diagnostic_events: CXDRVector::default(),
             cpu_instructions: 0,
             memory_bytes: 0,
             pre_restore_transaction_data: CXDR::default(),
             pre_restore_min_fee: 0,
             ledger_entry_diff: CXDRDiffVector::default(),
+            footprint_bytes: CFootprintEntryBytesVector::default(),
+            rent_changes: CRentChangeVector::default(),
+            error_details: CErrorDetails::default(),
+            wasm_validation_events: CXDRVector::default(),
+            warnings: CPreflightWarningVector::default(),
+            spans: CPreflightSpanVector::default(),
+            has_linear_memory_bytes: false,
+            linear_memory_bytes: 0,
+            logs: CStringVector::default(),
+            restore_statuses: CRestoreKeyStatusVector::default(),
+            extensions: CPreflightExtensionVector::default(),
+        }
+    }
+}
+
+impl CDrop for CPreflightResult {
+    unsafe fn drop_c(self) {
+        unsafe {
+            self.error.drop_c();
+            self.auth.drop_c();
+            self.result.drop_c();
+            self.transaction_data.drop_c();
+            self.contract_events.drop_c();
+            self.diagnostic_events.drop_c();
+            self.pre_restore_transaction_data.drop_c();
+            self.ledger_entry_diff.drop_c();
+            self.footprint_bytes.drop_c();
+            self.rent_changes.drop_c();
+            self.error_details.drop_c();
+            self.wasm_validation_events.drop_c();
+            self.warnings.drop_c();
+            self.spans.drop_c();
+            self.logs.drop_c();
+            self.restore_statuses.drop_c();
+            self.extensions.drop_c();
+        }
+    }
+}
+
+#[repr(C)]
+pub struct CPreflightResultVector {
+    pub array: *mut CPreflightResult,
+    pub len: libc::size_t,
+}
+
+impl CReprOf<Vec<CPreflightResult>> for CPreflightResultVector {
+    fn c_repr_of(input: Vec<CPreflightResult>) -> Self {
+        let (array, len) = vec_to_c_array(input);
+        CPreflightResultVector { array, len }
+    }
+}
+
+impl CDrop for CPreflightResultVector {
+    unsafe fn drop_c(self) {
+        for result in unsafe { c_array_to_vec(self.array, self.len) } {
+            unsafe { result.drop_c() };
         }
     }
 }
@@ -198,110 +1299,424 @@ pub extern "C" fn preflight_invoke_hf_op(
     invoke_hf_op: CXDR,      // InvokeHostFunctionOp XDR in base64
     source_account: CXDR,    // AccountId XDR in base64
     ledger_info: CLedgerInfo,
+    ledger_entry_overrides: CXDRVector, // array of LedgerEntry XDR
+    footprint_keys: CXDRVector,         // array of LedgerKey XDR declared by the transaction
+    extra_footprint: CXDR, // LedgerFootprint XDR added to the simulated one, empty if none
     resource_config: CResourceConfig,
     enable_debug: bool,
     auth_mode: u32,
+    cancel_token: *const CancelToken, // null if the preflight can't be cancelled
 ) -> *mut CPreflightResult {
-    let proto = ledger_info.protocol_version;
     catch_preflight_panic(Box::new(move || {
+        let ledger_info = with_simulated_protocol_version(ledger_info)?;
+        let proto = ledger_info.protocol_version;
         if proto <= prev::PROTOCOL {
             prev::shared::preflight_invoke_hf_op_or_maybe_panic(
                 handle,
-                invoke_hf_op,
-                source_account,
+                &invoke_hf_op,
+                &source_account,
                 ledger_info,
+                &ledger_entry_overrides,
+                &footprint_keys,
+                &extra_footprint,
                 resource_config,
                 enable_debug,
                 auth_mode.into(),
+                cancel_token,
             )
         } else if proto == curr::PROTOCOL {
             curr::shared::preflight_invoke_hf_op_or_maybe_panic(
                 handle,
-                invoke_hf_op,
-                source_account,
+                &invoke_hf_op,
+                &source_account,
                 ledger_info,
+                &ledger_entry_overrides,
+                &footprint_keys,
+                &extra_footprint,
                 resource_config,
                 enable_debug,
                 auth_mode.into(),
+                cancel_token,
             )
         } else {
+            #[cfg(feature = "next")]
+            if proto == next::PROTOCOL {
+                return next::shared::preflight_invoke_hf_op_or_maybe_panic(
+                    handle,
+                    &invoke_hf_op,
+                    &source_account,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                    &footprint_keys,
+                    &extra_footprint,
+                    resource_config,
+                    enable_debug,
+                    auth_mode.into(),
+                    cancel_token,
+                );
+            }
             bail!("unsupported protocol version: {}", proto)
         }
     }))
 }
 
 #[no_mangle]
-pub extern "C" fn preflight_footprint_ttl_op(
+pub extern "C" fn preflight_invoke_hf_ops(
     handle: libc::uintptr_t, // Go Handle to forward to SnapshotSourceGet and SnapshotSourceHas
-    op_body: CXDR,           // OperationBody XDR
-    footprint: CXDR,         // LedgerFootprint XDR
+    invoke_hf_ops: CXDRVector, // array of InvokeHostFunctionOp XDR
+    source_accounts: CXDRVector, // array of AccountId XDR, one per operation
     ledger_info: CLedgerInfo,
+    ledger_entry_overrides: CXDRVector, // array of LedgerEntry XDR
+    resource_config: CResourceConfig,
+    enable_debug: bool,
+    auth_mode: u32,
+    cancel_token: *const CancelToken, // null if the preflight can't be cancelled
+) -> *mut CPreflightResultVector {
+    catch_preflight_batch_panic(
+        invoke_hf_ops.len,
+        Box::new(move || {
+            let ledger_info = with_simulated_protocol_version(ledger_info)?;
+            let proto = ledger_info.protocol_version;
+            if proto <= prev::PROTOCOL {
+                prev::shared::preflight_invoke_hf_ops_or_maybe_panic(
+                    handle,
+                    &invoke_hf_ops,
+                    &source_accounts,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                    resource_config,
+                    enable_debug,
+                    auth_mode.into(),
+                    cancel_token,
+                )
+            } else if proto == curr::PROTOCOL {
+                curr::shared::preflight_invoke_hf_ops_or_maybe_panic(
+                    handle,
+                    &invoke_hf_ops,
+                    &source_accounts,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                    resource_config,
+                    enable_debug,
+                    auth_mode.into(),
+                    cancel_token,
+                )
+            } else {
+                #[cfg(feature = "next")]
+                if proto == next::PROTOCOL {
+                    return next::shared::preflight_invoke_hf_ops_or_maybe_panic(
+                        handle,
+                        &invoke_hf_ops,
+                        &source_accounts,
+                        ledger_info,
+                        &ledger_entry_overrides,
+                        resource_config,
+                        enable_debug,
+                        auth_mode.into(),
+                        cancel_token,
+                    );
+                }
+                bail!("unsupported protocol version: {}", proto)
+            }
+        }),
+    )
+}
+
+// Like `preflight_invoke_hf_ops()`, but simulating against the given snapshot
+// (which must hold every entry the operations read, the others being
+// considered missing) without any call back into Go.
+#[no_mangle]
+pub extern "C" fn preflight_invoke_hf_ops_from_snapshot(
+    snapshot: CLedgerSnapshot,
+    invoke_hf_ops: CXDRVector,   // array of InvokeHostFunctionOp XDR
+    source_accounts: CXDRVector, // array of AccountId XDR, one per operation
+    ledger_info: CLedgerInfo,
+    ledger_entry_overrides: CXDRVector, // array of LedgerEntry XDR
+    resource_config: CResourceConfig,
+    enable_debug: bool,
+    auth_mode: u32,
+    cancel_token: *const CancelToken, // null if the preflight can't be cancelled
+) -> *mut CPreflightResultVector {
+    catch_preflight_batch_panic(
+        invoke_hf_ops.len,
+        Box::new(move || {
+            let ledger_info = with_simulated_protocol_version(ledger_info)?;
+            let proto = ledger_info.protocol_version;
+            if proto <= prev::PROTOCOL {
+                prev::shared::preflight_invoke_hf_ops_from_snapshot_or_maybe_panic(
+                    snapshot,
+                    &invoke_hf_ops,
+                    &source_accounts,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                    resource_config,
+                    enable_debug,
+                    auth_mode.into(),
+                    cancel_token,
+                )
+            } else if proto == curr::PROTOCOL {
+                curr::shared::preflight_invoke_hf_ops_from_snapshot_or_maybe_panic(
+                    snapshot,
+                    &invoke_hf_ops,
+                    &source_accounts,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                    resource_config,
+                    enable_debug,
+                    auth_mode.into(),
+                    cancel_token,
+                )
+            } else {
+                #[cfg(feature = "next")]
+                if proto == next::PROTOCOL {
+                    return next::shared::preflight_invoke_hf_ops_from_snapshot_or_maybe_panic(
+                        snapshot,
+                        &invoke_hf_ops,
+                        &source_accounts,
+                        ledger_info,
+                        &ledger_entry_overrides,
+                        resource_config,
+                        enable_debug,
+                        auth_mode.into(),
+                        cancel_token,
+                    );
+                }
+                bail!("unsupported protocol version: {}", proto)
+            }
+        }),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn preflight_extend_ttl_op(
+    handle: libc::uintptr_t, // Go Handle to forward to SnapshotSourceGet and SnapshotSourceHas
+    keys_to_extend: CXDRVector, // array of LedgerKey XDR
+    extend_to: u32,
+    ledger_info: CLedgerInfo,
+    ledger_entry_overrides: CXDRVector, // array of LedgerEntry XDR
 ) -> *mut CPreflightResult {
-    let proto = ledger_info.protocol_version;
     catch_preflight_panic(Box::new(move || {
+        let ledger_info = with_simulated_protocol_version(ledger_info)?;
+        let proto = ledger_info.protocol_version;
         if proto <= prev::PROTOCOL {
-            prev::shared::preflight_footprint_ttl_op_or_maybe_panic(
+            prev::shared::preflight_extend_ttl_op_or_maybe_panic(
                 handle,
-                op_body,
-                footprint,
+                &keys_to_extend,
+                extend_to,
                 ledger_info,
+                &ledger_entry_overrides,
             )
         } else if proto == curr::PROTOCOL {
-            curr::shared::preflight_footprint_ttl_op_or_maybe_panic(
+            curr::shared::preflight_extend_ttl_op_or_maybe_panic(
                 handle,
-                op_body,
-                footprint,
+                &keys_to_extend,
+                extend_to,
                 ledger_info,
+                &ledger_entry_overrides,
             )
         } else {
+            #[cfg(feature = "next")]
+            if proto == next::PROTOCOL {
+                return next::shared::preflight_extend_ttl_op_or_maybe_panic(
+                    handle,
+                    &keys_to_extend,
+                    extend_to,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                );
+            }
             bail!("unsupported protocol version: {}", proto)
         }
     }))
 }
 
+#[no_mangle]
+pub extern "C" fn preflight_restore_op(
+    handle: libc::uintptr_t, // Go Handle to forward to SnapshotSourceGet and SnapshotSourceHas
+    keys_to_restore: CXDRVector, // array of LedgerKey XDR
+    ledger_info: CLedgerInfo,
+    ledger_entry_overrides: CXDRVector, // array of LedgerEntry XDR
+) -> *mut CPreflightResult {
+    catch_preflight_panic(Box::new(move || {
+        let ledger_info = with_simulated_protocol_version(ledger_info)?;
+        let proto = ledger_info.protocol_version;
+        if proto <= prev::PROTOCOL {
+            prev::shared::preflight_restore_op_or_maybe_panic(
+                handle,
+                &keys_to_restore,
+                ledger_info,
+                &ledger_entry_overrides,
+            )
+        } else if proto == curr::PROTOCOL {
+            curr::shared::preflight_restore_op_or_maybe_panic(
+                handle,
+                &keys_to_restore,
+                ledger_info,
+                &ledger_entry_overrides,
+            )
+        } else {
+            #[cfg(feature = "next")]
+            if proto == next::PROTOCOL {
+                return next::shared::preflight_restore_op_or_maybe_panic(
+                    handle,
+                    &keys_to_restore,
+                    ledger_info,
+                    &ledger_entry_overrides,
+                );
+            }
+            bail!("unsupported protocol version: {}", proto)
+        }
+    }))
+}
+
+#[repr(C)]
+pub struct CNetworkConfigResult {
+    pub json: *mut libc::c_char,  // empty in case of error
+    pub error: *mut libc::c_char, // empty unless there was an error
+}
+
+impl CDrop for CNetworkConfigResult {
+    unsafe fn drop_c(self) {
+        unsafe {
+            self.json.drop_c();
+            self.error.drop_c();
+        }
+    }
+}
+
+/// Loads the network configuration (fees, limits and state archival settings)
+/// from the config settings of the ledger and returns it as JSON, so that the
+/// caller doesn't need to parse them itself.
+#[no_mangle]
+pub extern "C" fn preflight_get_network_config(
+    handle: libc::uintptr_t, // Go Handle to forward to SnapshotSourceGet and SnapshotSourceHas
+    ledger_info: CLedgerInfo,
+) -> *mut CNetworkConfigResult {
+    let result = catch_panic(Box::new(move || {
+        let ledger_info = with_simulated_protocol_version(ledger_info)?;
+        let proto = ledger_info.protocol_version;
+        if proto <= prev::PROTOCOL {
+            prev::shared::get_network_config_json_or_maybe_panic(handle, ledger_info)
+        } else if proto == curr::PROTOCOL {
+            curr::shared::get_network_config_json_or_maybe_panic(handle, ledger_info)
+        } else {
+            #[cfg(feature = "next")]
+            if proto == next::PROTOCOL {
+                return next::shared::get_network_config_json_or_maybe_panic(handle, ledger_info);
+            }
+            bail!("unsupported protocol version: {}", proto)
+        }
+    }));
+    let (json, error) = match result {
+        Err(panic_msg) => (String::new(), panic_msg),
+        Ok(Err(e)) => (String::new(), format!("{e:?}")),
+        Ok(Ok(json)) => (json, String::new()),
+    };
+    // transfer ownership to caller
+    // caller needs to invoke free_network_config_result(result) when done
+    Box::into_raw(Box::new(CNetworkConfigResult {
+        json: CReprOf::c_repr_of(json),
+        error: CReprOf::c_repr_of(error),
+    }))
+}
+
+// The ledger info to simulate with, whose protocol version is the one pinned by
+// the caller (if any). The pinned protocol can be at most one version away from
+// the one of the ledger, i.e. across a single upgrade boundary, the state of
+// the ledger being meaningless to the other protocols.
+fn with_simulated_protocol_version(mut ledger_info: CLedgerInfo) -> Result<CLedgerInfo> {
+    let simulated_protocol_version = ledger_info.simulated_protocol_version;
+    if simulated_protocol_version == 0 {
+        return Ok(ledger_info);
+    }
+    if simulated_protocol_version.abs_diff(ledger_info.protocol_version) > 1 {
+        return Err(anyhow!(
+            "cannot simulate protocol {simulated_protocol_version} against the state of a protocol {} ledger",
+            ledger_info.protocol_version
+        )
+        .context(InvalidInput));
+    }
+    ledger_info.protocol_version = simulated_protocol_version;
+    Ok(ledger_info)
+}
+
 fn preflight_error(str: String) -> CPreflightResult {
-    let c_str = CString::new(str).unwrap();
     CPreflightResult {
-        error: c_str.into_raw(),
+        error: CReprOf::c_repr_of(str),
         ..Default::default()
     }
 }
 
-fn catch_preflight_panic(op: Box<dyn Fn() -> Result<CPreflightResult>>) -> *mut CPreflightResult {
-    // catch panics before they reach foreign callers (which otherwise would result in
-    // undefined behavior)
-    let res: std::thread::Result<Result<CPreflightResult>> =
-        panic::catch_unwind(panic::AssertUnwindSafe(op));
-    let c_preflight_result = match res {
-        Err(panic) => match panic.downcast::<String>() {
-            Ok(panic_msg) => preflight_error(format!("panic during preflight() call: {panic_msg}")),
-            Err(_) => preflight_error("panic during preflight() call: unknown cause".to_string()),
-        },
-        // See https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
-        Ok(r) => r.unwrap_or_else(|e| preflight_error(format!("{e:?}"))),
+// See https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
+fn preflight_failure(e: &anyhow::Error) -> CPreflightResult {
+    let mut result = preflight_error(format!("{e:?}"));
+    if e.downcast_ref::<InvalidInput>().is_some() {
+        result.error_details.category = ERROR_CATEGORY_INVALID_INPUT;
+    }
+    result
+}
+
+fn catch_preflight_panic(
+    op: Box<dyn FnOnce() -> Result<CPreflightResult>>,
+) -> *mut CPreflightResult {
+    let c_preflight_result = match catch_panic(op) {
+        Err(panic_msg) => preflight_error(panic_msg),
+        Ok(r) => r.unwrap_or_else(|e| preflight_failure(&e)),
     };
     // transfer ownership to caller
     // caller needs to invoke free_preflight_result(result) when done
     Box::into_raw(Box::new(c_preflight_result))
 }
 
-fn string_to_c(str: String) -> *mut libc::c_char {
-    CString::new(str).unwrap().into_raw()
+// Like `catch_preflight_panic()`, but a failure of the whole batch is reported
+// in each of the `len` results, so that the caller always gets one result per
+// operation.
+fn catch_preflight_batch_panic(
+    len: usize,
+    op: Box<dyn FnOnce() -> Result<Vec<Result<CPreflightResult>>>>,
+) -> *mut CPreflightResultVector {
+    let c_preflight_results = match catch_panic(op) {
+        Err(panic_msg) => (0..len)
+            .map(|_| preflight_error(panic_msg.clone()))
+            .collect(),
+        Ok(Err(e)) => (0..len).map(|_| preflight_failure(&e)).collect(),
+        Ok(Ok(results)) => results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|e| preflight_failure(&e)))
+            .collect(),
+    };
+    // transfer ownership to caller
+    // caller needs to invoke free_preflight_result_vector(result) when done
+    Box::into_raw(Box::new(CPreflightResultVector::c_repr_of(
+        c_preflight_results,
+    )))
 }
 
-fn vec_to_c_array<T>(mut v: Vec<T>) -> (*mut T, libc::size_t) {
-    // Make sure length and capacity are the same
-    // (this allows using the length as the capacity when deallocating the vector)
-    v.shrink_to_fit();
-    let len = v.len();
-    assert_eq!(len, v.capacity());
+fn catch_panic<T: 'static>(op: Box<dyn FnOnce() -> T>) -> std::result::Result<T, String> {
+    pool::run(Box::new(move || {
+        // catch panics before they reach foreign callers (which otherwise would result in
+        // undefined behavior)
+        let res: std::thread::Result<T> = panic::catch_unwind(panic::AssertUnwindSafe(op));
+        res.map_err(|panic| match panic.downcast::<String>() {
+            Ok(panic_msg) => format!("panic during preflight() call: {panic_msg}"),
+            Err(_) => "panic during preflight() call: unknown cause".to_string(),
+        })
+    }))?
+}
 
-    // Get the pointer to our vector, we will deallocate it in free_c_null_terminated_char_array()
-    // TODO: replace by `out_vec.into_raw_parts()` once the API stabilizes
-    let ptr = v.as_mut_ptr();
-    mem::forget(v);
+/// Starts a pool of `worker_count` threads which run all subsequent
+/// preflights, with up to `queue_size` preflights waiting for a free worker.
+/// Preflights which don't fit in the queue fail right away. Returns false if
+/// the pool couldn't be started (e.g. because it already was).
+#[no_mangle]
+pub extern "C" fn preflight_start_worker_pool(worker_count: usize, queue_size: usize) -> bool {
+    pool::start(worker_count, queue_size).is_ok()
+}
 
-    (ptr, len)
+/// Returns the number of preflights waiting for a free worker of the pool.
+#[no_mangle]
+pub extern "C" fn preflight_worker_pool_queue_length() -> usize {
+    pool::queue_length()
 }
 
 /// .
@@ -315,95 +1730,249 @@ pub unsafe extern "C" fn free_preflight_result(result: *mut CPreflightResult) {
         return;
     }
     let boxed = Box::from_raw(result);
-    free_c_string(boxed.error);
-    free_c_xdr_array(boxed.auth);
-    free_c_xdr(boxed.result);
-    free_c_xdr(boxed.transaction_data);
-    free_c_xdr_array(boxed.events);
-    free_c_xdr(boxed.pre_restore_transaction_data);
-    free_c_xdr_diff_array(boxed.ledger_entry_diff);
+    boxed.drop_c();
 }
 
-fn free_c_xdr(xdr: CXDR) {
-    if xdr.xdr.is_null() {
+/// Frees a vector returned by `preflight_invoke_hf_ops` along with all its
+/// results.
+///
+/// # Safety
+///
+/// `result` must have been returned by `preflight_invoke_hf_ops` and must not
+/// have been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn free_preflight_result_vector(result: *mut CPreflightResultVector) {
+    if result.is_null() {
         return;
     }
-    unsafe {
-        _ = Vec::from_raw_parts(xdr.xdr, xdr.len, xdr.len);
-    }
+    let boxed = Box::from_raw(result);
+    boxed.drop_c();
 }
 
-fn free_c_xdr_array(xdr_array: CXDRVector) {
-    if xdr_array.array.is_null() {
+/// Frees a result returned by `preflight_get_network_config`.
+///
+/// # Safety
+///
+/// `result` must have been returned by `preflight_get_network_config` and
+/// must not have been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn free_network_config_result(result: *mut CNetworkConfigResult) {
+    if result.is_null() {
         return;
     }
-    unsafe {
-        let v = Vec::from_raw_parts(xdr_array.array, xdr_array.len, xdr_array.len);
-        for xdr in v {
-            free_c_xdr(xdr);
-        }
-    }
+    let boxed = Box::from_raw(result);
+    boxed.drop_c();
 }
 
-fn free_c_xdr_diff_array(xdr_array: CXDRDiffVector) {
-    if xdr_array.array.is_null() {
-        return;
-    }
-    unsafe {
-        let v = Vec::from_raw_parts(xdr_array.array, xdr_array.len, xdr_array.len);
-        for diff in v {
-            free_c_xdr(diff.before);
-            free_c_xdr(diff.after);
+#[repr(C)]
+pub struct CLedgerEntryAndTTL {
+    pub entry: CXDR,
+    pub ttl: i64, // -1 indicates that the TTL is missing
+}
+
+impl CLedgerEntryAndTTL {
+    fn as_entry_and_ttl(&self) -> Option<(Vec<u8>, Option<u32>)> {
+        if self.entry.xdr.is_null() {
+            return None;
+        }
+        let v = unsafe { self.entry.as_rust() };
+        if self.ttl < 0 {
+            Some((v, None))
+        } else {
+            let ttl = u32::try_from(self.ttl).ok()?;
+            Some((v, Some(ttl)))
         }
     }
 }
 
 #[repr(C)]
 #[derive(Copy, Clone)]
-pub struct CLedgerEntryAndTTL {
-    pub entry: CXDR,
-    pub ttl: i64, // -1 indicates that the TTL is missing
+pub struct CLedgerEntryAndTTLVector {
+    pub array: *mut CLedgerEntryAndTTL,
+    pub len: libc::size_t,
+}
+
+// A ledger entry of a snapshot handed over by Go
+#[repr(C)]
+pub struct CLedgerSnapshotEntry {
+    pub key: CXDR, // LedgerKey XDR
+    pub entry_and_ttl: CLedgerEntryAndTTL,
+}
+
+// The ledger entries available to a simulation, owned by Go
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct CLedgerSnapshot {
+    pub array: *mut CLedgerSnapshotEntry,
+    pub len: libc::size_t,
 }
 
 // Functions imported from Golang
 extern "C" {
     // Free data returned from Go functions
     fn FreeGoLedgerEntryAndTTL(ledger_entry_and_ttl: CLedgerEntryAndTTL);
-    // LedgerKey XDR to LedgerEntry XDR and TTL
-    fn SnapshotSourceGet(handle: libc::uintptr_t, ledger_key: CXDR) -> CLedgerEntryAndTTL;
+    fn FreeGoLedgerEntryAndTTLVector(ledger_entries_and_ttls: CLedgerEntryAndTTLVector);
+    // LedgerKey XDR to LedgerEntry XDR and TTL, as of the given ledger
+    fn SnapshotSourceGet(
+        handle: libc::uintptr_t,
+        ledger_key: CXDR,
+        ledger_seq: u32,
+    ) -> CLedgerEntryAndTTL;
+    // Batched counterpart of SnapshotSourceGet, returning one (possibly
+    // missing) entry per key or an empty vector on failure
+    fn SnapshotSourceGetMany(
+        handle: libc::uintptr_t,
+        ledger_keys: CXDRVector,
+        ledger_seq: u32,
+    ) -> CLedgerEntryAndTTLVector;
 }
 
+// LedgerKey XDR to LedgerEntry XDR and TTL
+type LedgerEntryOverrides = HashMap<Vec<u8>, (Vec<u8>, Option<u32>)>;
+
+// LedgerKey XDR to LedgerEntry XDR and TTL, None if the entry doesn't exist
+type PrefetchedLedgerEntries = HashMap<Vec<u8>, Option<(Vec<u8>, Option<u32>)>>;
+
+// Clones are handles to the same storage, which can be moved to other threads
+// so that a single snapshot serves simulations run in parallel. Each handle
+// records the storage errors of its own simulation.
 struct GoLedgerStorage {
-    golang_handle: libc::uintptr_t,
-    internal_error: RefCell<Option<anyhow::Error>>,
+    // None when every entry comes from a snapshot handed over by Go
+    golang_handle: Option<libc::uintptr_t>,
+    // Ledger whose state every entry is read from
+    ledger_seq: u32,
+    // Entries supplied by the caller, which take precedence over the ones
+    // stored in Go
+    overrides: Arc<LedgerEntryOverrides>,
+    // Entries fetched ahead of the simulation
+    prefetched: Arc<RwLock<PrefetchedLedgerEntries>>,
+    // Token through which the caller cancels the preflight
+    cancel_token: CancelTokenRef,
+    // Error of the last failed read, which the host doesn't propagate
+    internal_error: Mutex<Option<anyhow::Error>>,
+}
+
+impl Clone for GoLedgerStorage {
+    fn clone(&self) -> Self {
+        Self {
+            golang_handle: self.golang_handle,
+            ledger_seq: self.ledger_seq,
+            overrides: self.overrides.clone(),
+            prefetched: self.prefetched.clone(),
+            cancel_token: self.cancel_token,
+            internal_error: Mutex::default(),
+        }
+    }
 }
 
 impl GoLedgerStorage {
-    fn new(golang_handle: libc::uintptr_t) -> Self {
+    fn new(golang_handle: libc::uintptr_t, ledger_seq: u32) -> Self {
+        Self {
+            golang_handle: Some(golang_handle),
+            ledger_seq,
+            overrides: Arc::default(),
+            prefetched: Arc::default(),
+            cancel_token: CancelTokenRef::default(),
+            internal_error: Mutex::default(),
+        }
+    }
+
+    // Storage reading every entry from the given ones (entries missing from
+    // them being considered not to exist), without calling back into Go
+    fn with_entries(entries: PrefetchedLedgerEntries, ledger_seq: u32) -> Self {
         Self {
-            golang_handle,
-            internal_error: RefCell::new(None),
+            golang_handle: None,
+            ledger_seq,
+            overrides: Arc::default(),
+            prefetched: Arc::new(RwLock::new(entries)),
+            cancel_token: CancelTokenRef::default(),
+            internal_error: Mutex::default(),
+        }
+    }
+
+    // Storage reading every entry from the given snapshot, without calling
+    // back into Go. The snapshot is copied, so Go can release it afterwards.
+    fn from_snapshot(snapshot: CLedgerSnapshot, ledger_seq: u32) -> Self {
+        let entries = if snapshot.array.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(snapshot.array, snapshot.len) }
+        };
+        let prefetched = entries
+            .iter()
+            .map(|e| {
+                (
+                    unsafe { e.key.as_rust() },
+                    e.entry_and_ttl.as_entry_and_ttl(),
+                )
+            })
+            .collect();
+        Self::with_entries(prefetched, ledger_seq)
+    }
+
+    // Fails once the caller cancelled the preflight
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancel_token.is_cancelled() {
+            bail!(cancel::CANCELLED_ERROR);
         }
+        Ok(())
     }
 
     // Get the entry XDR and TTL
     fn get_xdr_internal(&self, key_xdr: &mut Vec<u8>) -> Option<(Vec<u8>, Option<u32>)> {
+        if let Some(entry_and_ttl) = self.overrides.get(key_xdr) {
+            return Some(entry_and_ttl.clone());
+        }
+        if let Some(entry_and_ttl) = self.prefetched.read().unwrap().get(key_xdr) {
+            return entry_and_ttl.clone();
+        }
+        // Entries missing from a snapshot don't exist
+        let golang_handle = self.golang_handle?;
         let key_c_xdr = CXDR {
             xdr: key_xdr.as_mut_ptr(),
             len: key_xdr.len(),
         };
-        let res = unsafe { SnapshotSourceGet(self.golang_handle, key_c_xdr) };
-        if res.entry.xdr.is_null() {
-            return None;
-        }
-        let v = unsafe { from_c_xdr(res.entry) };
+        let res = unsafe { SnapshotSourceGet(golang_handle, key_c_xdr, self.ledger_seq) };
+        let entry_and_ttl = res.as_entry_and_ttl();
         unsafe { FreeGoLedgerEntryAndTTL(res) };
-        if res.ttl < 0 {
-            Some((v, None))
-        } else {
-            let ttl = u32::try_from(res.ttl).ok()?;
-            Some((v, Some(ttl)))
+        entry_and_ttl
+    }
+
+    // Fetches the entries of the given LedgerKey XDRs in a single call to Go,
+    // rather than one call per entry as the simulation reads them. Failures
+    // are ignored, the entries being fetched one by one again when read.
+    fn prefetch(&self, mut keys_xdr: Vec<Vec<u8>>) {
+        let Some(golang_handle) = self.golang_handle else {
+            return;
+        };
+        keys_xdr.sort();
+        keys_xdr.dedup();
+        keys_xdr.retain(|k| {
+            !self.overrides.contains_key(k) && !self.prefetched.read().unwrap().contains_key(k)
+        });
+        if keys_xdr.is_empty() {
+            return;
+        }
+        let mut keys_c_xdr: Vec<CXDR> = keys_xdr
+            .iter_mut()
+            .map(|k| CXDR {
+                xdr: k.as_mut_ptr(),
+                len: k.len(),
+            })
+            .collect();
+        let keys_c_vector = CXDRVector {
+            array: keys_c_xdr.as_mut_ptr(),
+            len: keys_c_xdr.len(),
+        };
+        let res = unsafe { SnapshotSourceGetMany(golang_handle, keys_c_vector, self.ledger_seq) };
+        if res.len == keys_xdr.len() {
+            let entries = unsafe { slice::from_raw_parts(res.array, res.len) };
+            let mut prefetched = self.prefetched.write().unwrap();
+            for (key_xdr, entry) in keys_xdr.into_iter().zip(entries) {
+                prefetched.insert(key_xdr, entry.as_entry_and_ttl());
+            }
         }
+        unsafe { FreeGoLedgerEntryAndTTLVector(res) };
     }
 }
 
@@ -413,7 +1982,7 @@ fn extract_error_string<T>(simulation_result: &Result<T>, go_storage: &GoLedgerS
         Err(e) => {
             // Override any simulation result with a storage error (if any). Simulation does not propagate the storage
             // errors, but these provide more exact information on the root cause.
-            if let Some(e) = go_storage.internal_error.borrow().as_ref() {
+            if let Some(e) = go_storage.internal_error.lock().unwrap().as_ref() {
                 format!("{e:?}")
             } else {
                 format!("{e:?}")
@@ -421,3 +1990,33 @@ fn extract_error_string<T>(simulation_result: &Result<T>, go_storage: &GoLedgerS
         }
     }
 }
+
+// Go isn't linked into the unit tests, whose storage never calls back into it
+#[cfg(test)]
+mod go_stubs {
+    use super::{CLedgerEntryAndTTL, CLedgerEntryAndTTLVector, CXDRVector, CXDR};
+
+    #[no_mangle]
+    extern "C" fn FreeGoLedgerEntryAndTTL(_: CLedgerEntryAndTTL) {
+        unreachable!()
+    }
+
+    #[no_mangle]
+    extern "C" fn FreeGoLedgerEntryAndTTLVector(_: CLedgerEntryAndTTLVector) {
+        unreachable!()
+    }
+
+    #[no_mangle]
+    extern "C" fn SnapshotSourceGet(_: libc::uintptr_t, _: CXDR, _: u32) -> CLedgerEntryAndTTL {
+        unreachable!()
+    }
+
+    #[no_mangle]
+    extern "C" fn SnapshotSourceGetMany(
+        _: libc::uintptr_t,
+        _: CXDRVector,
+        _: u32,
+    ) -> CLedgerEntryAndTTLVector {
+        unreachable!()
+    }
+}
@@ -0,0 +1,111 @@
+// A bounded pool of threads running the preflights, so that the library rather
+// than its callers controls how many simulations run at once (and thus the
+// memory and CPU pressure they cause). Preflights wait in a bounded queue for
+// a free worker and are rejected right away once the queue is full.
+//
+// Until the pool is started, preflights keep running on the calling thread.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+// Reported to the caller when the queue is full. Go matches on it to tell
+// apart a full queue from a failed preflight.
+pub(crate) const QUEUE_FULL_ERROR: &str = "preflight queue full";
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct WorkerPool {
+    sender: SyncSender<Job>,
+    queued: Arc<AtomicUsize>,
+}
+
+static WORKER_POOL: OnceLock<WorkerPool> = OnceLock::new();
+
+// Preflight inputs and results hold raw pointers into memory owned by the
+// caller, which is blocked until the worker is done with them.
+struct AssertSend<T>(T);
+
+unsafe impl<T> Send for AssertSend<T> {}
+
+impl<T> AssertSend<T> {
+    // Taking `self` as a whole makes closures capture the wrapper, rather than
+    // just the (non-Send) field.
+    fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl WorkerPool {
+    fn new(worker_count: usize, queue_size: usize) -> std::io::Result<Self> {
+        let (sender, receiver) = sync_channel::<Job>(queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+        for i in 0..worker_count {
+            let receiver = receiver.clone();
+            let queued = queued.clone();
+            thread::Builder::new()
+                .name(format!("preflight-worker-{i}"))
+                .spawn(move || work(&receiver, &queued))?;
+        }
+        Ok(Self { sender, queued })
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>, queued: &AtomicUsize) {
+    loop {
+        // The lock is only held while waiting for the next job. Jobs catch
+        // their own panics, so it can't get poisoned.
+        let Ok(job) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+        queued.fetch_sub(1, Ordering::Relaxed);
+        job();
+    }
+}
+
+// Starts the pool, failing if it was already started.
+pub(crate) fn start(worker_count: usize, queue_size: usize) -> Result<(), String> {
+    if worker_count == 0 {
+        return Err("the preflight worker pool needs at least one worker".to_string());
+    }
+    let pool = WorkerPool::new(worker_count, queue_size).map_err(|e| e.to_string())?;
+    WORKER_POOL
+        .set(pool)
+        .map_err(|_| "the preflight worker pool was already started".to_string())
+}
+
+// Number of preflights waiting for a free worker.
+pub(crate) fn queue_length() -> usize {
+    WORKER_POOL
+        .get()
+        .map_or(0, |pool| pool.queued.load(Ordering::Relaxed))
+}
+
+// Runs `op` on a worker and waits for its result, or runs it on the calling
+// thread if the pool wasn't started.
+pub(crate) fn run<T: 'static>(op: Box<dyn FnOnce() -> T>) -> Result<T, String> {
+    let Some(pool) = WORKER_POOL.get() else {
+        return Ok(op());
+    };
+    let (result_sender, result_receiver) = sync_channel(1);
+    let op = AssertSend(op);
+    let job: Job = Box::new(move || {
+        let result = AssertSend(op.into_inner()());
+        // The caller waits for the result, so it can't have gone away
+        let _ = result_sender.send(result);
+    });
+    pool.queued.fetch_add(1, Ordering::Relaxed);
+    if let Err(e) = pool.sender.try_send(job) {
+        pool.queued.fetch_sub(1, Ordering::Relaxed);
+        return Err(match e {
+            TrySendError::Full(_) => QUEUE_FULL_ERROR.to_string(),
+            TrySendError::Disconnected(_) => "the preflight worker pool stopped".to_string(),
+        });
+    }
+    result_receiver
+        .recv()
+        .map(AssertSend::into_inner)
+        .map_err(|_| "preflight worker stopped before returning a result".to_string())
+}
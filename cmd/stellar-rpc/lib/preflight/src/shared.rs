@@ -11,16 +11,41 @@
 // We therefore import the different bindings for anything we use from
 // `soroban_env_host` or `soroban_simulation` from `super::` rather than
 // `crate::`.
+//
+// The preflights themselves (`preflight_invoke_hf_op()` and friends) take and
+// return Rust types, the `*_or_maybe_panic()` wrappers called by `lib.rs` only
+// converting from and to the C representations.
+use super::soroban_env_host::budget::Budget;
 use super::soroban_env_host::e2e_invoke::RecordingInvocationAuthMode;
+use super::soroban_env_host::events::HostEvent;
+use super::soroban_env_host::fees::{
+    compute_rent_fee, LedgerEntryRentChange, DATA_SIZE_1KB_INCREMENT, INSTRUCTIONS_INCREMENT,
+    TX_BASE_RESULT_SIZE,
+};
+use super::soroban_env_host::ledger_info::get_key_durability;
+use super::soroban_env_host::storage::{
+    AccessType, Footprint, FootprintMap, SnapshotSource, Storage, StorageMap,
+};
 use super::soroban_env_host::xdr::{
-    AccountId, ExtendFootprintTtlOp, InvokeHostFunctionOp, LedgerEntry, LedgerFootprint, LedgerKey,
-    OperationBody, ReadXdr, ScErrorCode, ScErrorType, SorobanTransactionData, WriteXdr,
+    AccountId, BytesM, ConfigSettingEntry, ConfigSettingId, ContractDataDurability,
+    ContractDataEntry, ContractEvent, ContractEventBody, DecoratedSignature, DiagnosticEvent,
+    Duration, ExtendFootprintTtlOp, ExtensionPoint, Hash, HostFunction, InvokeHostFunctionOp,
+    LedgerBounds, LedgerEntry, LedgerEntryData, LedgerEntryExt, LedgerFootprint, LedgerKey,
+    LedgerKeyAccount, LedgerKeyConfigSetting, LedgerKeyContractCode, LedgerKeyContractData,
+    LedgerKeyTrustLine, Memo, MuxedAccount, MuxedAccountMed25519, Operation, OperationBody,
+    Preconditions, PreconditionsV2, ReadXdr, RestoreFootprintOp, ScAddress, ScError, ScErrorCode,
+    ScErrorType, ScNonceKey, ScVal, ScVec, SequenceNumber, Signature, SignatureHint, SignerKey,
+    SignerKeyEd25519SignedPayload, SorobanAuthorizationEntry, SorobanAuthorizedFunction,
+    SorobanCredentials, SorobanTransactionData, TimeBounds, TimePoint, Transaction, TransactionExt,
+    TransactionV1Envelope, TtlEntry, Uint256, VecM, WriteXdr,
+};
+use super::soroban_env_host::{
+    DiagnosticLevel, Host, HostError, LedgerInfo, Vm, DEFAULT_XDR_RW_LIMITS,
 };
-use super::soroban_env_host::{LedgerInfo, DEFAULT_XDR_RW_LIMITS};
 use super::soroban_simulation::simulation::{
     simulate_extend_ttl_op, simulate_invoke_host_function_op, simulate_restore_op,
-    InvokeHostFunctionSimulationResult, LedgerEntryDiff, RestoreOpSimulationResult,
-    SimulationAdjustmentConfig,
+    InvokeHostFunctionSimulationResult, RestoreOpSimulationResult, SimulationAdjustmentConfig,
+    SimulationAdjustmentFactor,
 };
 use super::soroban_simulation::{AutoRestoringSnapshotSource, NetworkConfig};
 
@@ -28,287 +53,2421 @@ use super::soroban_simulation::{AutoRestoringSnapshotSource, NetworkConfig};
 // stored in the common grandparent module `crate` a.k.a. `lib.rs`. Both copies
 // of the `shared` module import the same definitions for these.
 
+use crate::cancel::{CancelToken, CancelTokenRef};
+use crate::spans::{
+    Span, SpanRecorder, SPAN_CONFIG_LOAD, SPAN_EXECUTION, SPAN_FOOTPRINT_MINIMIZATION,
+    SPAN_RESTORE_SIMULATION, SPAN_SNAPSHOT_PREFETCH,
+};
 use crate::{
-    anyhow, extract_error_string, from_c_string, from_c_xdr, string_to_c, vec_to_c_array,
-    CLedgerInfo, CPreflightResult, CResourceConfig, CXDRDiff, CXDRDiffVector, CXDRVector, Digest,
-    GoLedgerStorage, Result, Sha256, CXDR,
+    anyhow, bail, extract_error_string, fee_per_increment, AsRust, CAdjustmentFactor,
+    CErrorDetails, CFootprintEntryBytesVector, CLedgerInfo, CLedgerSnapshot,
+    CPreflightExtensionVector, CPreflightResult, CPreflightSpanVector, CPreflightWarningVector,
+    CRentChangeVector, CReprOf, CResourceConfig, CRestoreKeyStatusVector, CStringVector,
+    CXDRDiffVector, CXDRVector, Context, Digest, FeeBreakdown, GoLedgerStorage, InvalidInput,
+    LedgerEntryOverrides, PreflightLedgerInfo, Result, Sha256, CXDR, ERROR_CATEGORY_AUTH,
+    ERROR_CATEGORY_BUDGET, ERROR_CATEGORY_CONTRACT, ERROR_CATEGORY_HOST, ERROR_CATEGORY_NONE,
+    ERROR_CATEGORY_STORAGE, PREFLIGHT_EXTENSION_FEE_BREAKDOWN,
+    PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH, PREFLIGHT_RESULT_VERSION, RESTORE_STATUS_ALREADY_LIVE,
+    RESTORE_STATUS_NOT_FOUND, RESTORE_STATUS_RESTORABLE, RESTORE_STATUS_TOO_LARGE,
+    WARNING_DEPRECATED_HOST_FUNCTION, WARNING_ENTRY_NEAR_ARCHIVAL,
+    WARNING_LINEAR_MEMORY_UNMEASURED, WARNING_NON_ROOT_AUTH, WARNING_RESOURCE_NEAR_LIMIT,
 };
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
-use std::ptr::null_mut;
+use std::num::NonZeroUsize;
+use std::panic;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
 
 #[derive(Clone, Copy)]
 pub(crate) enum AuthMode {
     Enforce = 0,
     Record = 1,
     RecordAllowNonroot = 2,
+    // Enforces the auth entries of the operation if it has any, and records
+    // (requiring root auth) otherwise
+    Auto = 3,
+    // Enforces the structure of the auth entries of the operation (which
+    // addresses authorize which invocation trees) while treating their
+    // signatures as valid, see `check_mock_signature_auth()`
+    EnforceMockSignatures = 4,
+}
+
+// The outcome of a preflight, which the FFI wrappers below hand over to Go as a
+// `CPreflightResult` (see there for the meaning of each field).
+#[derive(Default)]
+pub(crate) struct PreflightResult {
+    pub(crate) error: String,
+    pub(crate) error_details: ErrorDetails,
+    pub(crate) auth: Vec<SorobanAuthorizationEntry>,
+    pub(crate) result: Option<ScVal>,
+    pub(crate) transaction_data: Option<SorobanTransactionData>,
+    pub(crate) min_fee: i64,
+    pub(crate) contract_events: Vec<ContractEvent>,
+    pub(crate) diagnostic_events: Vec<DiagnosticEvent>,
+    pub(crate) logs: Vec<String>,
+    pub(crate) cpu_instructions: u64,
+    pub(crate) memory_bytes: u64,
+    // Only when requested
+    pub(crate) linear_memory_bytes: Option<u64>,
+    pub(crate) pre_restore_transaction_data: Option<SorobanTransactionData>,
+    pub(crate) pre_restore_min_fee: i64,
+    // State of each modified entry before and after the change
+    pub(crate) ledger_entry_diff: Vec<(Option<LedgerEntry>, Option<LedgerEntry>)>,
+    // Footprint key, read bytes and written bytes
+    pub(crate) footprint_bytes: Vec<(LedgerKey, u32, u32)>,
+    // Key, new size, old and new live until ledgers, and rent fee
+    pub(crate) rent_changes: Vec<(LedgerKey, u32, u32, u32, i64)>,
+    pub(crate) wasm_validation_events: Vec<DiagnosticEvent>,
+    // One of the WARNING_* constants and the message
+    pub(crate) warnings: Vec<(u32, String)>,
+    // Key and one of the RESTORE_STATUS_* constants
+    pub(crate) restore_statuses: Vec<(LedgerKey, u32)>,
+    pub(crate) spans: Vec<Span>,
+    // Handed over as the PREFLIGHT_EXTENSION_FEE_BREAKDOWN extension
+    pub(crate) fee_breakdown: Option<FeeBreakdown>,
+    // Handed over as the PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH extension, only
+    // when `__check_auth()` is skipped
+    pub(crate) stubbed_check_auth: Option<Vec<ScAddress>>,
+}
+
+pub(crate) struct ErrorDetails {
+    // One of the ERROR_CATEGORY_* constants
+    pub(crate) category: u32,
+    pub(crate) sc_error: Option<ScError>,
+    pub(crate) contract_id: Option<[u8; 32]>,
+}
+
+impl Default for ErrorDetails {
+    fn default() -> Self {
+        ErrorDetails {
+            category: ERROR_CATEGORY_NONE,
+            sc_error: None,
+            contract_id: None,
+        }
+    }
 }
 
-fn fill_ledger_info(c_ledger_info: CLedgerInfo, network_config: &NetworkConfig) -> LedgerInfo {
-    let network_passphrase = unsafe { from_c_string(c_ledger_info.network_passphrase) };
+// The ledger seen by the simulation, whose sequence and timestamp can be
+// overridden e.g. to exercise time-locked contract logic. TTLs are checked
+// against the overridden sequence too.
+fn fill_ledger_info(
+    preflight_ledger_info: &PreflightLedgerInfo,
+    network_config: &NetworkConfig,
+) -> LedgerInfo {
     let mut ledger_info = LedgerInfo {
-        protocol_version: c_ledger_info.protocol_version,
-        sequence_number: c_ledger_info.sequence_number,
-        timestamp: c_ledger_info.timestamp,
-        network_id: Sha256::digest(network_passphrase).into(),
-        base_reserve: c_ledger_info.base_reserve,
+        protocol_version: preflight_ledger_info.protocol_version,
+        sequence_number: preflight_ledger_info
+            .sequence_number_override
+            .unwrap_or(preflight_ledger_info.sequence_number),
+        timestamp: preflight_ledger_info
+            .timestamp_override
+            .unwrap_or(preflight_ledger_info.timestamp),
+        network_id: Sha256::digest(&preflight_ledger_info.network_passphrase).into(),
+        base_reserve: preflight_ledger_info.base_reserve,
         ..Default::default()
     };
     network_config.fill_config_fields_in_ledger_info(&mut ledger_info);
     ledger_info
 }
 
-// This has to be a free function rather than a method on an impl because there
-// are two copies of this file mounted in the module tree and we can't define a
-// same-named method on a single Self-type twice.
-fn new_cpreflight_result_from_invoke_host_function(
+fn new_preflight_result_from_invoke_host_function(
     invoke_hf_result: InvokeHostFunctionSimulationResult,
     restore_preamble: Option<RestoreOpSimulationResult>,
     error: String,
-) -> CPreflightResult {
-    let mut result = CPreflightResult {
-        error: string_to_c(error),
-        auth: xdr_vec_to_c(&invoke_hf_result.auth),
-        result: option_xdr_to_c(invoke_hf_result.invoke_result.ok().as_ref()),
+    go_storage: &GoLedgerStorage,
+) -> Result<PreflightResult> {
+    let footprint_bytes = footprint_bytes(&invoke_hf_result, go_storage)?;
+    let mut result = PreflightResult {
+        error,
+        auth: invoke_hf_result.auth,
+        result: invoke_hf_result.invoke_result.ok(),
         min_fee: invoke_hf_result
             .transaction_data
             .as_ref()
             .map_or_else(|| 0, |r| r.resource_fee),
-        transaction_data: option_xdr_to_c(invoke_hf_result.transaction_data.as_ref()),
-        // TODO: Diagnostic and contract events should be separated in the response
-        events: xdr_vec_to_c(&invoke_hf_result.diagnostic_events),
+        transaction_data: invoke_hf_result.transaction_data,
+        contract_events: invoke_hf_result.contract_events,
+        logs: diagnostic_logs(&invoke_hf_result.diagnostic_events),
+        diagnostic_events: invoke_hf_result.diagnostic_events,
         cpu_instructions: u64::from(invoke_hf_result.simulated_instructions),
         memory_bytes: u64::from(invoke_hf_result.simulated_memory),
-        ledger_entry_diff: ledger_entry_diff_vec_to_c(&invoke_hf_result.modified_entries),
+        ledger_entry_diff: invoke_hf_result
+            .modified_entries
+            .into_iter()
+            .map(|diff| (diff.state_before, diff.state_after))
+            .collect(),
+        footprint_bytes,
         ..Default::default()
     };
     if let Some(p) = restore_preamble {
         result.pre_restore_min_fee = p.transaction_data.resource_fee;
-        result.pre_restore_transaction_data = xdr_to_c(&p.transaction_data);
+        result.pre_restore_transaction_data = Some(p.transaction_data);
     }
-    result
+    Ok(result)
 }
 
-// This has to be a free function rather than a method on an impl because there
-// are two copies of this file mounted in the module tree and we can't define a
-// same-named method on a single Self-type twice.
-fn new_cpreflight_result_from_transaction_data(
-    transaction_data: Option<&SorobanTransactionData>,
-    restore_preamble: Option<&RestoreOpSimulationResult>,
+// The diagnostic events (recorded only when debugging) as the host prints
+// them, e.g. the messages logged by contracts and the metrics of the budget.
+fn diagnostic_logs(events: &[DiagnosticEvent]) -> Vec<String> {
+    events
+        .iter()
+        .map(|e| {
+            HostEvent {
+                event: e.event.clone(),
+                failed_call: !e.in_successful_contract_call,
+            }
+            .to_string()
+        })
+        .collect()
+}
+
+fn new_preflight_result_from_transaction_data(
+    transaction_data: Option<SorobanTransactionData>,
+    restore_preamble: Option<RestoreOpSimulationResult>,
     error: String,
-) -> CPreflightResult {
-    let min_fee = transaction_data.map_or(0, |d| d.resource_fee);
-    let mut result = CPreflightResult {
-        error: string_to_c(error),
-        transaction_data: option_xdr_to_c(transaction_data),
+) -> PreflightResult {
+    let min_fee = transaction_data.as_ref().map_or(0, |d| d.resource_fee);
+    let mut result = PreflightResult {
+        error,
+        transaction_data,
         min_fee,
         ..Default::default()
     };
     if let Some(p) = restore_preamble {
         result.pre_restore_min_fee = p.transaction_data.resource_fee;
-        result.pre_restore_transaction_data = xdr_to_c(&p.transaction_data);
+        result.pre_restore_transaction_data = Some(p.transaction_data);
     }
     result
 }
 
+// This has to be a free function rather than a method on an impl because there
+// are two copies of this file mounted in the module tree and we can't define a
+// same-named method on a single Self-type twice.
+fn preflight_result_to_c(result: PreflightResult) -> CPreflightResult {
+    let footprint_bytes: Vec<_> = result
+        .footprint_bytes
+        .iter()
+        .map(|(key, read_bytes, write_bytes)| (xdr_to_bytes(key), *read_bytes, *write_bytes))
+        .collect();
+    let rent_changes: Vec<_> = result
+        .rent_changes
+        .iter()
+        .map(|(key, size, old_live_until, new_live_until, rent_fee)| {
+            (
+                xdr_to_bytes(key),
+                *size,
+                *old_live_until,
+                *new_live_until,
+                *rent_fee,
+            )
+        })
+        .collect();
+    let restore_statuses: Vec<_> = result
+        .restore_statuses
+        .iter()
+        .map(|(key, status)| (xdr_to_bytes(key), *status))
+        .collect();
+    let mut extensions = Vec::new();
+    if let Some(fee_breakdown) = &result.fee_breakdown {
+        extensions.push((PREFLIGHT_EXTENSION_FEE_BREAKDOWN, fee_breakdown.to_json()));
+    }
+    if let Some(stubbed_check_auth) = &result.stubbed_check_auth {
+        let addresses: Vec<ScVal> = stubbed_check_auth
+            .iter()
+            .map(|address| ScVal::Address(address.clone()))
+            .collect();
+        // Can't exceed the XDR limits, there being at most one per auth entry
+        let addresses = ScVal::Vec(Some(ScVec(addresses.try_into().unwrap())));
+        extensions.push((
+            PREFLIGHT_EXTENSION_STUBBED_CHECK_AUTH,
+            xdr_to_bytes(&addresses),
+        ));
+    }
+    CPreflightResult {
+        version: PREFLIGHT_RESULT_VERSION,
+        error: CReprOf::c_repr_of(result.error),
+        auth: xdr_vec_to_c(&result.auth),
+        result: option_xdr_to_c(result.result.as_ref()),
+        transaction_data: option_xdr_to_c(result.transaction_data.as_ref()),
+        min_fee: result.min_fee,
+        contract_events: xdr_vec_to_c(&result.contract_events),
+        diagnostic_events: xdr_vec_to_c(&result.diagnostic_events),
+        cpu_instructions: result.cpu_instructions,
+        memory_bytes: result.memory_bytes,
+        pre_restore_transaction_data: option_xdr_to_c(result.pre_restore_transaction_data.as_ref()),
+        pre_restore_min_fee: result.pre_restore_min_fee,
+        ledger_entry_diff: ledger_entry_diff_vec_to_c(&result.ledger_entry_diff),
+        footprint_bytes: CFootprintEntryBytesVector::c_repr_of(footprint_bytes),
+        rent_changes: CRentChangeVector::c_repr_of(rent_changes),
+        error_details: error_details_to_c(&result.error_details),
+        wasm_validation_events: xdr_vec_to_c(&result.wasm_validation_events),
+        warnings: CPreflightWarningVector::c_repr_of(result.warnings),
+        spans: CPreflightSpanVector::c_repr_of(result.spans),
+        has_linear_memory_bytes: result.linear_memory_bytes.is_some(),
+        linear_memory_bytes: result.linear_memory_bytes.unwrap_or(0),
+        logs: CStringVector::c_repr_of(result.logs),
+        restore_statuses: CRestoreKeyStatusVector::c_repr_of(restore_statuses),
+        extensions: CPreflightExtensionVector::c_repr_of(extensions),
+    }
+}
+
+fn error_details_to_c(error_details: &ErrorDetails) -> CErrorDetails {
+    CErrorDetails {
+        category: error_details.category,
+        sc_error: option_xdr_to_c(error_details.sc_error.as_ref()),
+        contract_id: CXDR::c_repr_of(error_details.contract_id.map(|id| id.to_vec())),
+    }
+}
+
+// The ledger snapshot and the network configuration loaded from it, which can
+// be shared by every simulation run against the same ledger.
+pub(crate) struct PreflightContext {
+    go_storage: GoLedgerStorage,
+    // Shared with the other operations of a batch once the limits are applied
+    network_config: Arc<NetworkConfig>,
+    ledger_info: LedgerInfo,
+    // The transaction-level CPU and memory limits of the network, which
+    // `network_config` no longer holds once the caller's limits are applied
+    tx_max_instructions: i64,
+    tx_memory_limit: u32,
+    // Phases of the preflights run so far, handed over with the next result
+    spans: SpanRecorder,
+}
+
+impl PreflightContext {
+    pub(crate) fn new(
+        mut go_storage: GoLedgerStorage,
+        preflight_ledger_info: &PreflightLedgerInfo,
+        ledger_entry_overrides: Vec<LedgerEntry>,
+    ) -> Result<Self> {
+        let spans = SpanRecorder::default();
+        let config_setting_keys_xdr = ledger_keys_xdr(&config_setting_keys())?;
+        spans.time(SPAN_SNAPSHOT_PREFETCH, || {
+            go_storage.prefetch(config_setting_keys_xdr);
+        });
+        // The network configuration always comes from the actual ledger, since
+        // config setting entries can't be overridden.
+        let network_config = spans.time(SPAN_CONFIG_LOAD, || {
+            NetworkConfig::load_from_snapshot(&go_storage, preflight_ledger_info.bucket_list_size)
+        });
+        go_storage.check_cancelled()?;
+        let network_config = network_config?;
+        let ledger_info = fill_ledger_info(preflight_ledger_info, &network_config);
+        go_storage.overrides = Arc::new(new_ledger_entry_overrides(
+            ledger_entry_overrides,
+            &go_storage,
+            &ledger_info,
+        )?);
+        Ok(Self {
+            go_storage,
+            tx_max_instructions: network_config.tx_max_instructions,
+            tx_memory_limit: network_config.tx_memory_limit,
+            network_config: Arc::new(network_config),
+            ledger_info,
+            spans,
+        })
+    }
+
+    // Context of one of the operations of a batch, sharing the storage and the
+    // network configuration but recording the phases of the operation apart
+    fn for_operation(&self) -> Self {
+        Self {
+            go_storage: self.go_storage.clone(),
+            network_config: self.network_config.clone(),
+            ledger_info: self.ledger_info.clone(),
+            tx_max_instructions: self.tx_max_instructions,
+            tx_memory_limit: self.tx_memory_limit,
+            spans: SpanRecorder::default(),
+        }
+    }
+
+    // Fetches the entries of the given keys ahead of the simulation
+    fn prefetch(&self, keys: &[LedgerKey]) -> Result<()> {
+        let keys_xdr = ledger_keys_xdr(keys)?;
+        self.spans.time(SPAN_SNAPSHOT_PREFETCH, || {
+            self.go_storage.prefetch(keys_xdr);
+        });
+        Ok(())
+    }
+
+    // Applies the caller's changes to the transaction-level limits enforced
+    // while simulating. Relaxing lifts the network limits (the other limits,
+    // i.e. read/write bytes and transaction size, aren't enforced by the
+    // simulation to begin with, so the resources it reports may already exceed
+    // them), whereas the budget caps can only lower them.
+    fn apply_resource_limits(&mut self, resource_config: &CResourceConfig) {
+        let network_config = Arc::get_mut(&mut self.network_config)
+            .expect("the network configuration is only shared once the limits are applied");
+        if resource_config.relax_network_limits {
+            // Simulated instructions are reported as u32
+            network_config.tx_max_instructions = i64::from(u32::MAX);
+            network_config.tx_memory_limit = u32::MAX;
+        }
+        if resource_config.max_instructions > 0 {
+            let cap = i64::try_from(resource_config.max_instructions).unwrap_or(i64::MAX);
+            network_config.tx_max_instructions = network_config.tx_max_instructions.min(cap);
+        }
+        if resource_config.max_memory_bytes > 0 {
+            let cap = u32::try_from(resource_config.max_memory_bytes).unwrap_or(u32::MAX);
+            network_config.tx_memory_limit = network_config.tx_memory_limit.min(cap);
+        }
+    }
+}
+
+// Decodes the inputs every preflight takes from C and loads the network
+// configuration from the given storage.
+fn preflight_context_from_c(
+    go_storage: GoLedgerStorage,
+    c_ledger_info: CLedgerInfo,
+    ledger_entry_overrides: &CXDRVector, // array of LedgerEntry XDR
+) -> Result<PreflightContext> {
+    let preflight_ledger_info = unsafe { c_ledger_info.as_rust() };
+    let ledger_entry_overrides = ledger_entries_from_c(ledger_entry_overrides)?;
+    PreflightContext::new(go_storage, &preflight_ledger_info, ledger_entry_overrides)
+}
+
+pub(crate) fn get_network_config_json_or_maybe_panic(
+    handle: libc::uintptr_t,
+    c_ledger_info: CLedgerInfo,
+) -> Result<String> {
+    let go_storage = GoLedgerStorage::new(handle, c_ledger_info.sequence_number);
+    let context = preflight_context_from_c(go_storage, c_ledger_info, &CXDRVector::default())?;
+    Ok(network_config_json(&context).to_string())
+}
+
+pub(crate) fn network_config_json(context: &PreflightContext) -> serde_json::Value {
+    let network_config = &context.network_config;
+    let rent_fee_configuration = &network_config.rent_fee_configuration;
+    serde_json::json!({
+        "fees": super::fees_json(network_config),
+        "limits": {
+            "txMaxInstructions": network_config.tx_max_instructions,
+            "txMemoryLimit": network_config.tx_memory_limit,
+        },
+        "stateArchival": {
+            "minTemporaryTtl": network_config.min_temp_entry_ttl,
+            "minPersistentTtl": network_config.min_persistent_entry_ttl,
+            "maxEntryTtl": network_config.max_entry_ttl,
+            "persistentRentRateDenominator": rent_fee_configuration.persistent_rent_rate_denominator,
+            "temporaryRentRateDenominator": rent_fee_configuration.temporary_rent_rate_denominator,
+        },
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn preflight_invoke_hf_op_or_maybe_panic(
     handle: libc::uintptr_t,
-    invoke_hf_op: CXDR,   // InvokeHostFunctionOp XDR in base64
-    source_account: CXDR, // AccountId XDR in base64
+    invoke_hf_op: &CXDR,   // InvokeHostFunctionOp XDR in base64
+    source_account: &CXDR, // AccountId XDR in base64
     c_ledger_info: CLedgerInfo,
+    ledger_entry_overrides: &CXDRVector, // array of LedgerEntry XDR
+    footprint_keys: &CXDRVector,         // array of LedgerKey XDR
+    extra_footprint: &CXDR,              // LedgerFootprint XDR, empty if none
     resource_config: CResourceConfig,
     enable_debug: bool,
     auth_mode: AuthMode,
+    cancel_token: *const CancelToken,
 ) -> Result<CPreflightResult> {
     let invoke_hf_op =
-        InvokeHostFunctionOp::from_xdr(unsafe { from_c_xdr(invoke_hf_op) }, DEFAULT_XDR_RW_LIMITS)
-            .unwrap();
+        InvokeHostFunctionOp::from_xdr(unsafe { invoke_hf_op.as_slice() }, DEFAULT_XDR_RW_LIMITS)
+            .context(InvalidInput)?;
     let source_account =
-        AccountId::from_xdr(unsafe { from_c_xdr(source_account) }, DEFAULT_XDR_RW_LIMITS).unwrap();
+        AccountId::from_xdr(unsafe { source_account.as_slice() }, DEFAULT_XDR_RW_LIMITS)
+            .context(InvalidInput)?;
+    let footprint_keys = ledger_keys_from_c(footprint_keys)?;
+    let extra_footprint = match unsafe { extra_footprint.as_slice() } {
+        [] => LedgerFootprint {
+            read_only: VecM::default(),
+            read_write: VecM::default(),
+        },
+        xdr => LedgerFootprint::from_xdr(xdr, DEFAULT_XDR_RW_LIMITS).context(InvalidInput)?,
+    };
 
-    let go_storage = Rc::new(GoLedgerStorage::new(handle));
-    let network_config =
-        NetworkConfig::load_from_snapshot(go_storage.as_ref(), c_ledger_info.bucket_list_size)?;
-    let ledger_info = fill_ledger_info(c_ledger_info, &network_config);
-    let auto_restore_snapshot = Rc::new(AutoRestoringSnapshotSource::new(
-        go_storage.clone(),
-        &ledger_info,
-    )?);
+    let mut go_storage = GoLedgerStorage::new(handle, c_ledger_info.sequence_number);
+    go_storage.cancel_token = CancelTokenRef(cancel_token);
+    let context = preflight_context_from_c(go_storage, c_ledger_info, ledger_entry_overrides)?;
+    let result = preflight_invoke_hf_op(
+        context,
+        invoke_hf_op,
+        &source_account,
+        &footprint_keys,
+        &extra_footprint,
+        &resource_config,
+        enable_debug,
+        auth_mode,
+    )?;
+    Ok(preflight_result_to_c(result))
+}
 
-    let mut adjustment_config = SimulationAdjustmentConfig::default_adjustment();
-    // It would be reasonable to extend `resource_config` to be compatible with `adjustment_config`
-    // in order to let the users customize the resource/fee adjustments in a more granular fashion.
+// Simulates the invocation of a host function. The keys of the footprint
+// declared by the transaction, if any, are only fetched ahead of it, while the
+// keys of `extra_footprint` are added to the simulated footprint (see
+// `add_extra_footprint()`).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn preflight_invoke_hf_op(
+    mut context: PreflightContext,
+    invoke_hf_op: InvokeHostFunctionOp,
+    source_account: &AccountId,
+    footprint_keys: &[LedgerKey],
+    extra_footprint: &LedgerFootprint,
+    resource_config: &CResourceConfig,
+    enable_debug: bool,
+    auth_mode: AuthMode,
+) -> Result<PreflightResult> {
+    let mut prefetched_keys = footprint_keys.to_vec();
+    prefetched_keys.extend(invoke_entry_keys(&invoke_hf_op, source_account));
+    prefetched_keys.extend(extra_footprint.read_only.iter().cloned());
+    prefetched_keys.extend(extra_footprint.read_write.iter().cloned());
+    context.prefetch(&prefetched_keys)?;
+    context.apply_resource_limits(resource_config);
+    let adjustment_config = new_invoke_adjustment_config(resource_config).context(InvalidInput)?;
+    let host_function = invoke_hf_op.host_function.clone();
+    let mut result = preflight_invoke_hf_op_in_context(
+        &context,
+        &adjustment_config,
+        resource_config,
+        invoke_hf_op,
+        source_account,
+        enable_debug,
+        auth_mode,
+    )?;
+    add_extra_footprint(
+        &context,
+        &adjustment_config,
+        &mut result,
+        host_function,
+        extra_footprint,
+    )?;
+    Ok(result)
+}
 
+// Adds the keys of `extra_footprint` missing from the footprint of a successful
+// invocation, e.g. state which may change between the simulation and the
+// submission of the transaction and make it touch other entries. Their reads
+// (and writes) are charged for at the current size of their entries, the
+// read-write keys found in the read-only footprint being moved.
+//
+// This happens once the footprint is minimized, which would drop the extra
+// keys the simulation doesn't touch.
+fn add_extra_footprint(
+    context: &PreflightContext,
+    adjustment_config: &SimulationAdjustmentConfig,
+    result: &mut PreflightResult,
+    host_function: HostFunction,
+    extra_footprint: &LedgerFootprint,
+) -> Result<()> {
+    let Some(transaction_data) = result.transaction_data.as_mut() else {
+        return Ok(());
+    };
+    if !result.error.is_empty()
+        || (extra_footprint.read_only.is_empty() && extra_footprint.read_write.is_empty())
+    {
+        return Ok(());
+    }
+    let operation = OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+        host_function,
+        auth: result.auth.clone().try_into()?,
+    });
+    let before = fee_breakdown(
+        context,
+        adjustment_config,
+        operation.clone(),
+        transaction_data,
+        0,
+    )?;
+    let resources = &mut transaction_data.resources;
+    let mut read_only = resources.footprint.read_only.to_vec();
+    let mut read_write = resources.footprint.read_write.to_vec();
+    for key in extra_footprint.read_write.iter() {
+        if read_write.contains(key) {
+            continue;
+        }
+        let entry_xdr_size = entry_xdr_size(context, key)?;
+        match read_only.iter().position(|k| k == key) {
+            Some(i) => {
+                read_only.remove(i);
+            }
+            None => super::add_read_entry_bytes(resources, key, entry_xdr_size),
+        }
+        resources.write_bytes = resources.write_bytes.saturating_add(entry_xdr_size);
+        read_write.push(key.clone());
+    }
+    for key in extra_footprint.read_only.iter() {
+        if read_only.contains(key) || read_write.contains(key) {
+            continue;
+        }
+        super::add_read_entry_bytes(resources, key, entry_xdr_size(context, key)?);
+        read_only.push(key.clone());
+    }
+    resources.footprint.read_only = read_only.try_into()?;
+    resources.footprint.read_write = read_write.try_into()?;
+    let after = fee_breakdown(context, adjustment_config, operation, transaction_data, 0)?;
+    let fee_increase: i64 =
+        after.resource_fees().iter().sum::<i64>() - before.resource_fees().iter().sum::<i64>();
+    transaction_data.resource_fee = transaction_data.resource_fee.saturating_add(fee_increase);
+    result.min_fee = transaction_data.resource_fee;
+    if let Some(fee_breakdown) = result.fee_breakdown.as_mut() {
+        fee_breakdown.add_increase(&before, &after);
+    }
+    Ok(())
+}
+
+// The size of the entry of the key in the snapshot, 0 if it doesn't exist
+fn entry_xdr_size(context: &PreflightContext, key: &LedgerKey) -> Result<u32> {
+    Ok(
+        match get_fallible_from_go_ledger_storage(&context.go_storage, key)? {
+            Some((entry, _)) => u32::try_from(entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len())?,
+            None => 0,
+        },
+    )
+}
+
+// The entries read by every invocation: its source account and, for contract
+// calls, the instance of the invoked contract.
+fn invoke_entry_keys(
+    invoke_hf_op: &InvokeHostFunctionOp,
+    source_account: &AccountId,
+) -> Vec<LedgerKey> {
+    let mut keys = vec![LedgerKey::Account(LedgerKeyAccount {
+        account_id: source_account.clone(),
+    })];
+    if let HostFunction::InvokeContract(args) = &invoke_hf_op.host_function {
+        keys.push(LedgerKey::ContractData(LedgerKeyContractData {
+            contract: args.contract_address.clone(),
+            key: ScVal::LedgerKeyContractInstance,
+            durability: ContractDataDurability::Persistent,
+        }));
+    }
+    keys
+}
+
+// Every simulation reads the network configuration, i.e. the config settings
+// which exist in its protocol (fetching the other ones is harmless).
+fn config_setting_keys() -> Vec<LedgerKey> {
+    ConfigSettingId::VARIANTS
+        .iter()
+        .map(|id| {
+            LedgerKey::ConfigSetting(LedgerKeyConfigSetting {
+                config_setting_id: *id,
+            })
+        })
+        .collect()
+}
+
+fn ledger_keys_xdr(keys: &[LedgerKey]) -> Result<Vec<Vec<u8>>> {
+    keys.iter()
+        .map(|key| Ok(key.to_xdr(DEFAULT_XDR_RW_LIMITS)?))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn preflight_invoke_hf_ops_or_maybe_panic(
+    handle: libc::uintptr_t,
+    invoke_hf_ops: &CXDRVector,   // array of InvokeHostFunctionOp XDR
+    source_accounts: &CXDRVector, // array of AccountId XDR, one per operation
+    c_ledger_info: CLedgerInfo,
+    ledger_entry_overrides: &CXDRVector, // array of LedgerEntry XDR
+    resource_config: CResourceConfig,
+    enable_debug: bool,
+    auth_mode: AuthMode,
+    cancel_token: *const CancelToken,
+) -> Result<Vec<Result<CPreflightResult>>> {
+    let invoke_hf_ops = invoke_hf_ops_from_c(invoke_hf_ops, source_accounts)?;
+    let mut go_storage = GoLedgerStorage::new(handle, c_ledger_info.sequence_number);
+    go_storage.cancel_token = CancelTokenRef(cancel_token);
+    let context = preflight_context_from_c(go_storage, c_ledger_info, ledger_entry_overrides)?;
+    preflight_decoded_invoke_hf_ops(
+        context,
+        invoke_hf_ops,
+        &resource_config,
+        enable_debug,
+        auth_mode,
+    )
+}
+
+// Like `preflight_invoke_hf_ops_or_maybe_panic()`, but every entry is read from
+// the given snapshot instead of being fetched from Go. Entries missing from the
+// snapshot are considered not to exist.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn preflight_invoke_hf_ops_from_snapshot_or_maybe_panic(
+    snapshot: CLedgerSnapshot,
+    invoke_hf_ops: &CXDRVector,   // array of InvokeHostFunctionOp XDR
+    source_accounts: &CXDRVector, // array of AccountId XDR, one per operation
+    c_ledger_info: CLedgerInfo,
+    ledger_entry_overrides: &CXDRVector, // array of LedgerEntry XDR
+    resource_config: CResourceConfig,
+    enable_debug: bool,
+    auth_mode: AuthMode,
+    cancel_token: *const CancelToken,
+) -> Result<Vec<Result<CPreflightResult>>> {
+    let invoke_hf_ops = invoke_hf_ops_from_c(invoke_hf_ops, source_accounts)?;
+    let mut go_storage = GoLedgerStorage::from_snapshot(snapshot, c_ledger_info.sequence_number);
+    go_storage.cancel_token = CancelTokenRef(cancel_token);
+    let context = preflight_context_from_c(go_storage, c_ledger_info, ledger_entry_overrides)?;
+    preflight_decoded_invoke_hf_ops(
+        context,
+        invoke_hf_ops,
+        &resource_config,
+        enable_debug,
+        auth_mode,
+    )
+}
+
+// Operations which can't be decoded are reported in their own results
+fn invoke_hf_ops_from_c(
+    invoke_hf_ops: &CXDRVector,
+    source_accounts: &CXDRVector,
+) -> Result<Vec<Result<(InvokeHostFunctionOp, AccountId)>>> {
+    let invoke_hf_ops = unsafe { invoke_hf_ops.as_slices() };
+    let source_accounts = unsafe { source_accounts.as_slices() };
+    if invoke_hf_ops.len() != source_accounts.len() {
+        return Err(anyhow!(
+            "got {} operations but {} source accounts",
+            invoke_hf_ops.len(),
+            source_accounts.len()
+        )
+        .context(InvalidInput));
+    }
+    Ok(invoke_hf_ops
+        .into_iter()
+        .zip(source_accounts)
+        .map(|(invoke_hf_op, source_account)| {
+            Ok((
+                InvokeHostFunctionOp::from_xdr(invoke_hf_op, DEFAULT_XDR_RW_LIMITS)
+                    .context(InvalidInput)?,
+                AccountId::from_xdr(source_account, DEFAULT_XDR_RW_LIMITS).context(InvalidInput)?,
+            ))
+        })
+        .collect())
+}
+
+// Simulates the operations which could be decoded, keeping the results in the
+// order of the operations
+fn preflight_decoded_invoke_hf_ops(
+    context: PreflightContext,
+    invoke_hf_ops: Vec<Result<(InvokeHostFunctionOp, AccountId)>>,
+    resource_config: &CResourceConfig,
+    enable_debug: bool,
+    auth_mode: AuthMode,
+) -> Result<Vec<Result<CPreflightResult>>> {
+    let mut decoding_errors = Vec::with_capacity(invoke_hf_ops.len());
+    let mut decoded_ops = Vec::with_capacity(invoke_hf_ops.len());
+    for invoke_hf_op in invoke_hf_ops {
+        match invoke_hf_op {
+            Ok(op) => {
+                decoded_ops.push(op);
+                decoding_errors.push(None);
+            }
+            Err(e) => decoding_errors.push(Some(e)),
+        }
+    }
+    let mut results = preflight_invoke_hf_ops(
+        context,
+        decoded_ops,
+        resource_config,
+        enable_debug,
+        auth_mode,
+    )?
+    .into_iter();
+    Ok(decoding_errors
+        .into_iter()
+        .map(|decoding_error| match decoding_error {
+            Some(e) => Err(e),
+            None => results
+                .next()
+                .expect("one result per decoded operation")
+                .map(preflight_result_to_c),
+        })
+        .collect())
+}
+
+// Simulates every operation, along with its source account, against the same
+// snapshot and network configuration. The operations are simulated in parallel,
+// on up to one thread per available CPU. A failure of an individual operation
+// is reported in its own result rather than failing the whole batch.
+pub(crate) fn preflight_invoke_hf_ops(
+    mut context: PreflightContext,
+    invoke_hf_ops: Vec<(InvokeHostFunctionOp, AccountId)>,
+    resource_config: &CResourceConfig,
+    enable_debug: bool,
+    auth_mode: AuthMode,
+) -> Result<Vec<Result<PreflightResult>>> {
+    let mut prefetched_keys = Vec::new();
+    for (invoke_hf_op, source_account) in &invoke_hf_ops {
+        prefetched_keys.extend(invoke_entry_keys(invoke_hf_op, source_account));
+    }
+    context.prefetch(&prefetched_keys)?;
+    context.apply_resource_limits(resource_config);
+    let adjustment_config = new_invoke_adjustment_config(resource_config).context(InvalidInput)?;
+
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(invoke_hf_ops.len());
+    // The first operation hands over the phases of the batch along with its own
+    let mut contexts: Vec<_> = (1..invoke_hf_ops.len())
+        .map(|_| context.for_operation())
+        .collect();
+    contexts.insert(0, context);
+    let mut queues: Vec<Vec<_>> = (0..workers).map(|_| Vec::new()).collect();
+    for (i, (invoke_hf_op, context)) in invoke_hf_ops.into_iter().zip(contexts).enumerate() {
+        queues[i % workers].push((i, invoke_hf_op, context));
+    }
+    let mut results: Vec<(usize, Result<PreflightResult>)> = thread::scope(|scope| {
+        let workers: Vec<_> = queues
+            .into_iter()
+            .map(|queue| {
+                let adjustment_config = &adjustment_config;
+                scope.spawn(move || {
+                    queue
+                        .into_iter()
+                        .map(|(i, (invoke_hf_op, source_account), context)| {
+                            let result = preflight_invoke_hf_op_in_context(
+                                &context,
+                                adjustment_config,
+                                resource_config,
+                                invoke_hf_op,
+                                &source_account,
+                                enable_debug,
+                                auth_mode,
+                            );
+                            (i, result)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+fn new_invoke_adjustment_config(
+    resource_config: &CResourceConfig,
+) -> Result<SimulationAdjustmentConfig> {
+    let mut adjustment_config = new_adjustment_config(resource_config)?;
     let instruction_leeway = u32::try_from(resource_config.instruction_leeway)?;
     adjustment_config.instructions.additive_factor = adjustment_config
         .instructions
         .additive_factor
         .max(instruction_leeway);
+    Ok(adjustment_config)
+}
+
+fn preflight_invoke_hf_op_in_context(
+    context: &PreflightContext,
+    adjustment_config: &SimulationAdjustmentConfig,
+    resource_config: &CResourceConfig,
+    invoke_hf_op: InvokeHostFunctionOp,
+    source_account: &AccountId,
+    enable_debug: bool,
+    auth_mode: AuthMode,
+) -> Result<PreflightResult> {
+    // Later operations of a cancelled batch aren't simulated at all
+    context.go_storage.check_cancelled()?;
+    // A handle of its own, so that the storage errors of this simulation don't
+    // leak into the other ones sharing the storage
+    let go_storage = Rc::new(context.go_storage.clone());
+    let auto_restore_snapshot = Rc::new(AutoRestoringSnapshotSource::new(
+        go_storage.clone(),
+        &context.ledger_info,
+    )?);
 
+    let auth_mode = stub_check_auth(auth_mode, resource_config, &invoke_hf_op.auth);
     let auth_entries = invoke_hf_op.auth.to_vec();
+    let mock_signature_auth_entries =
+        matches!(auth_mode, AuthMode::EnforceMockSignatures).then(|| auth_entries.clone());
+    let host_function = invoke_hf_op.host_function.clone();
 
-    // Behavior differs based on user-supplied `auth_mode`: if chosen,
-    // enforcement is done even without entries, while the recording modes
-    // ignore the list entirely even if it's present.
-    let auth_mode = match auth_mode {
-        AuthMode::Enforce => RecordingInvocationAuthMode::Enforcing(auth_entries),
-        AuthMode::Record => RecordingInvocationAuthMode::Recording(true),
-        AuthMode::RecordAllowNonroot => RecordingInvocationAuthMode::Recording(false),
-    };
+    let (auth_mode, disable_non_root_auth) = recording_auth_mode(auth_mode, auth_entries);
 
     // Invoke the host function. The user errors should normally be captured in
     // `invoke_hf_result.invoke_result` and this should return Err result for
     // misconfigured ledger.
-    let invoke_hf_result: InvokeHostFunctionSimulationResult = simulate_invoke_host_function_op(
-        auto_restore_snapshot.clone(),
-        &network_config,
-        &adjustment_config,
-        &ledger_info,
-        invoke_hf_op.host_function,
-        auth_mode,
-        &source_account,
-        rand::Rng::gen(&mut rand::thread_rng()),
-        enable_debug,
+    let base_prng_seed = resource_config
+        .base_prng_seed()
+        .unwrap_or_else(|| rand::Rng::gen(&mut rand::thread_rng()));
+    let invoke_hf_result = context.spans.time(SPAN_EXECUTION, || {
+        // Note that contract modules get compiled on every call: the recording
+        // mode invocation used by soroban-simulation doesn't accept a pre-built
+        // `ModuleCache` (only the enforcing `e2e_invoke::invoke_host_function()`
+        // does), so compiled modules can't be shared across preflights from here.
+        simulate_invoke_host_function_op(
+            auto_restore_snapshot.clone(),
+            &context.network_config,
+            adjustment_config,
+            &context.ledger_info,
+            invoke_hf_op.host_function,
+            auth_mode,
+            source_account,
+            base_prng_seed,
+            enable_debug,
+        )
+    });
+    // A cancelled invocation fails on its next ledger entry read, whose error
+    // is superseded by the cancellation
+    context.go_storage.check_cancelled()?;
+    let mut invoke_hf_result: InvokeHostFunctionSimulationResult = invoke_hf_result?;
+    if disable_non_root_auth.is_some() {
+        suggest_signature_expiration(context, resource_config, &mut invoke_hf_result.auth);
+    }
+    let (recorded_auth, auth_check) = check_mock_signature_auth(
+        &mut invoke_hf_result,
+        mock_signature_auth_entries,
+        source_account,
+    );
+    let maybe_restore_result = restore_preamble(
+        context,
+        resource_config,
+        &auto_restore_snapshot,
+        &invoke_hf_result,
+        auth_check,
+    );
+    let (error_str, error_details) =
+        invoke_error(&go_storage, &invoke_hf_result, &maybe_restore_result);
+    // Computed before the footprint is minimized, which leaves the resource fee
+    // as simulated
+    let fee_breakdown = invoke_fee_breakdown(
+        context,
+        adjustment_config,
+        &invoke_hf_result,
+        &host_function,
     )?;
-    let maybe_restore_result = match &invoke_hf_result.invoke_result {
-        Ok(_) => auto_restore_snapshot.simulate_restore_keys_op(
-            &network_config,
-            &SimulationAdjustmentConfig::default_adjustment(),
-            &ledger_info,
-        ),
-        Err(e) => Err(e.clone().into()),
+    let wasm_validation_events =
+        wasm_validation_events(context, &invoke_hf_result, &host_function)?;
+    let replay = FootprintReplay {
+        context,
+        snapshot: auto_restore_snapshot.as_ref(),
+        host_function: host_function.clone(),
+        source_account,
+        base_prng_seed,
+        auth_mode: replay_auth_mode(disable_non_root_auth, &invoke_hf_result.auth),
     };
-    let error_str = extract_error_string(&maybe_restore_result, go_storage.as_ref());
-    Ok(new_cpreflight_result_from_invoke_host_function(
+    if resource_config.minimize_footprint && disable_non_root_auth.is_some() {
+        minimize_footprint(&replay, &mut invoke_hf_result, &maybe_restore_result)?;
+    }
+    let mut warnings = invoke_warnings(
+        context,
+        &invoke_hf_result,
+        &host_function,
+        disable_non_root_auth == Some(false),
+    )?;
+    let linear_memory_bytes =
+        measure_linear_memory(&replay, resource_config, &invoke_hf_result, &mut warnings)?;
+    let mut result = new_preflight_result_from_invoke_host_function(
         invoke_hf_result,
         maybe_restore_result.unwrap_or(None),
         error_str,
+        &context.go_storage,
+    )?;
+    result.linear_memory_bytes = linear_memory_bytes;
+    result.error_details = error_details;
+    result.wasm_validation_events = wasm_validation_events;
+    result.warnings = warnings;
+    result.fee_breakdown = fee_breakdown;
+    result.stubbed_check_auth = stubbed_check_auth(resource_config, recorded_auth.as_deref());
+    if let Some(recorded_auth) = recorded_auth {
+        replace_nonces(
+            context,
+            adjustment_config,
+            &mut result,
+            host_function,
+            &recorded_auth,
+        )?;
+    }
+    result.spans = context.spans.take();
+    Ok(result)
+}
+
+// Simulates the restoration of the archived entries a successful invocation
+// reads, which fails along with the invocation or the check of its auth entries
+fn restore_preamble(
+    context: &PreflightContext,
+    resource_config: &CResourceConfig,
+    auto_restore_snapshot: &AutoRestoringSnapshotSource<GoLedgerStorage>,
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    auth_check: Result<()>,
+) -> Result<Option<RestoreOpSimulationResult>> {
+    match (&invoke_hf_result.invoke_result, auth_check) {
+        (Err(e), _) => Err(e.clone().into()),
+        (Ok(_), Err(e)) => Err(e),
+        (Ok(_), Ok(())) if resource_config.skip_restore_preamble => Ok(None),
+        (Ok(_), Ok(())) => context.spans.time(SPAN_RESTORE_SIMULATION, || {
+            auto_restore_snapshot.simulate_restore_keys_op(
+                &context.network_config,
+                &SimulationAdjustmentConfig::default_adjustment(),
+                &context.ledger_info,
+            )
+        }),
+    }
+}
+
+// Minimizes the footprint of a successful recording invocation. Footprints
+// requiring a restore aren't minimized, since their archived entries can't be
+// replayed without the restoration.
+fn minimize_footprint(
+    replay: &FootprintReplay,
+    invoke_hf_result: &mut InvokeHostFunctionSimulationResult,
+    maybe_restore_result: &Result<Option<RestoreOpSimulationResult>>,
+) -> Result<()> {
+    let (Some(transaction_data), Ok(None)) = (
+        invoke_hf_result.transaction_data.as_mut(),
+        maybe_restore_result,
+    ) else {
+        return Ok(());
+    };
+    replay.context.spans.time(SPAN_FOOTPRINT_MINIMIZATION, || {
+        replay.minimize_footprint(transaction_data)
+    })
+}
+
+// The replays run in the auth mode of the simulation: recording, or enforcing
+// the auth entries which the simulation returns as they are.
+fn replay_auth_mode(
+    disable_non_root_auth: Option<bool>,
+    auth: &[SorobanAuthorizationEntry],
+) -> RecordingInvocationAuthMode {
+    match disable_non_root_auth {
+        Some(disable_non_root_auth) => {
+            RecordingInvocationAuthMode::Recording(disable_non_root_auth)
+        }
+        None => RecordingInvocationAuthMode::Enforcing(auth.to_vec()),
+    }
+}
+
+// The linear memory of a successful invocation, only when requested since it
+// takes replaying the invocation. A failed replay is reported as a warning.
+fn measure_linear_memory(
+    replay: &FootprintReplay,
+    resource_config: &CResourceConfig,
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    warnings: &mut Vec<(u32, String)>,
+) -> Result<Option<u64>> {
+    let (true, Ok(_), Some(transaction_data)) = (
+        resource_config.measure_linear_memory,
+        &invoke_hf_result.invoke_result,
+        &invoke_hf_result.transaction_data,
+    ) else {
+        return Ok(None);
+    };
+    match replay.linear_memory_bytes(transaction_data)? {
+        Ok(linear_memory_bytes) => Ok(Some(linear_memory_bytes)),
+        Err(e) => {
+            let message = format!(
+                "the replay measuring the linear memory of the invocation failed: {:?}",
+                e.error
+            );
+            warnings.push((WARNING_LINEAR_MEMORY_UNMEASURED, message));
+            Ok(None)
+        }
+    }
+}
+
+// The error string and details of a failed invocation, or of the simulation of
+// its restore preamble
+fn invoke_error(
+    go_storage: &GoLedgerStorage,
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    maybe_restore_result: &Result<Option<RestoreOpSimulationResult>>,
+) -> (String, ErrorDetails) {
+    let error_str = match budget_exceeded_error(invoke_hf_result) {
+        Some(e) if go_storage.internal_error.lock().unwrap().is_none() => e,
+        _ => extract_error_string(maybe_restore_result, go_storage),
+    };
+    let failing_contract_id = match &invoke_hf_result.invoke_result {
+        Ok(_) => None,
+        Err(_) => innermost_contract_call(invoke_hf_result).map(|(contract_id, _, _)| contract_id),
+    };
+    let error_details = error_details(maybe_restore_result, go_storage, failing_contract_id);
+    (error_str, error_details)
+}
+
+// Behavior differs based on user-supplied `auth_mode`: if chosen,
+// enforcement is done even without entries, while the recording modes
+// ignore the list entirely even if it's present. Also returns whether non-root
+// auth is disabled, None when enforcing.
+fn recording_auth_mode(
+    auth_mode: AuthMode,
+    auth_entries: Vec<SorobanAuthorizationEntry>,
+) -> (RecordingInvocationAuthMode, Option<bool>) {
+    match auth_mode {
+        AuthMode::Enforce => (RecordingInvocationAuthMode::Enforcing(auth_entries), None),
+        AuthMode::Record => (RecordingInvocationAuthMode::Recording(true), Some(true)),
+        AuthMode::RecordAllowNonroot => {
+            (RecordingInvocationAuthMode::Recording(false), Some(false))
+        }
+        AuthMode::Auto if auth_entries.is_empty() => {
+            recording_auth_mode(AuthMode::Record, auth_entries)
+        }
+        AuthMode::Auto => recording_auth_mode(AuthMode::Enforce, auth_entries),
+        // Recorded, and then compared against the entries
+        AuthMode::EnforceMockSignatures => {
+            recording_auth_mode(AuthMode::RecordAllowNonroot, auth_entries)
+        }
+    }
+}
+
+// The auth mode to simulate with, which can't run the `__check_auth()` of the
+// contract accounts when it is skipped: the auth entries it would enforce are
+// then matched against the recorded authorizations (as
+// `AuthMode::EnforceMockSignatures` does) rather than verified. This lets
+// smart wallets whose `__check_auth()` rejects placeholder signatures estimate
+// the resources of their invocations before signing them.
+fn stub_check_auth(
+    auth_mode: AuthMode,
+    resource_config: &CResourceConfig,
+    auth_entries: &[SorobanAuthorizationEntry],
+) -> AuthMode {
+    match auth_mode {
+        AuthMode::Enforce if resource_config.skip_check_auth => AuthMode::EnforceMockSignatures,
+        AuthMode::Auto if resource_config.skip_check_auth && !auth_entries.is_empty() => {
+            AuthMode::EnforceMockSignatures
+        }
+        _ => auth_mode,
+    }
+}
+
+// The contract accounts whose `__check_auth()` would be called when enforcing
+// the auth entries, if it is skipped: the ones the invocation asked to
+// authorize, once per recorded authorization. Nothing is stubbed when the
+// invocation isn't checked against auth entries.
+fn stubbed_check_auth(
+    resource_config: &CResourceConfig,
+    recorded_auth: Option<&[SorobanAuthorizationEntry]>,
+) -> Option<Vec<ScAddress>> {
+    if !resource_config.skip_check_auth {
+        return None;
+    }
+    let contracts = recorded_auth
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| match &entry.credentials {
+            SorobanCredentials::Address(credentials)
+                if matches!(credentials.address, ScAddress::Contract(_)) =>
+            {
+                Some(credentials.address.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    Some(contracts)
+}
+
+// Recorded address credentials are left to expire at ledger 0, which every
+// client has to replace before signing them. They are instead given the
+// suggested expiration of `CResourceConfig::signature_expiration_ledgers`,
+// capped to the lifetime of their nonces.
+fn suggest_signature_expiration(
+    context: &PreflightContext,
+    resource_config: &CResourceConfig,
+    auth: &mut [SorobanAuthorizationEntry],
+) {
+    if resource_config.signature_expiration_ledgers == 0 {
+        return;
+    }
+    let ledger_info = &context.ledger_info;
+    // The nonces live until the expiration of the signatures
+    let ledgers = resource_config
+        .signature_expiration_ledgers
+        .min(ledger_info.max_entry_ttl.saturating_sub(1));
+    for entry in auth {
+        if let SorobanCredentials::Address(credentials) = &mut entry.credentials {
+            if credentials.signature_expiration_ledger == 0 {
+                credentials.signature_expiration_ledger =
+                    ledger_info.sequence_number.saturating_add(ledgers);
+            }
+        }
+    }
+}
+
+// Checks that the auth entries of the operation are the ones the invocation
+// requires, which is what enforcing them does short of verifying their
+// signatures (and nonces and expiration ledgers): each address must authorize
+// exactly the invocation tree it was recorded with. The entries can then carry
+// placeholder signatures, e.g. to validate a fully-assembled transaction before
+// collecting the real ones. The entries replace the recorded ones in the
+// result, as when enforcing them, and the recorded ones are returned (if
+// there are entries to check).
+//
+// Note that the resources used to verify the signatures (including running the
+// `__check_auth()` of the contract accounts) aren't accounted for.
+fn check_mock_signature_auth(
+    invoke_hf_result: &mut InvokeHostFunctionSimulationResult,
+    auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+    source_account: &AccountId,
+) -> (Option<Vec<SorobanAuthorizationEntry>>, Result<()>) {
+    let Some(auth_entries) = auth_entries else {
+        return (None, Ok(()));
+    };
+    let recorded_auth = std::mem::replace(&mut invoke_hf_result.auth, auth_entries);
+    // Entries are matched regardless of their order, and of whether the
+    // source account authorizes through its credentials or the transaction
+    let authorization = |entry: &SorobanAuthorizationEntry| {
+        let address = match &entry.credentials {
+            SorobanCredentials::SourceAccount => ScAddress::Account(source_account.clone()),
+            SorobanCredentials::Address(credentials) => credentials.address.clone(),
+        };
+        [xdr_to_bytes(&address), xdr_to_bytes(&entry.root_invocation)].concat()
+    };
+    let mut unmatched: Vec<Vec<u8>> = recorded_auth.iter().map(authorization).collect();
+    let mut unexpected = 0;
+    for entry in &invoke_hf_result.auth {
+        let authorization = authorization(entry);
+        match unmatched.iter().position(|a| *a == authorization) {
+            Some(i) => {
+                unmatched.swap_remove(i);
+            }
+            None => unexpected += 1,
+        }
+    }
+    if unmatched.is_empty() && unexpected == 0 {
+        return (Some(recorded_auth), Ok(()));
+    }
+    let error = Err(HostError::from(ScError::Auth(ScErrorCode::InvalidAction))).context(format!(
+        "auth entries don't match the authorizations required by the invocation: {} missing, {unexpected} unexpected",
+        unmatched.len()
+    ));
+    (Some(recorded_auth), error)
+}
+
+// The nonces consumed by the address credentials of auth entries, which live
+// in the temporary storage of their address
+fn nonce_entries(auth: &[SorobanAuthorizationEntry]) -> Vec<(LedgerKey, LedgerEntry)> {
+    auth.iter()
+        .filter_map(|entry| match &entry.credentials {
+            SorobanCredentials::Address(credentials) => Some(credentials),
+            SorobanCredentials::SourceAccount => None,
+        })
+        .map(|credentials| {
+            let key = ScVal::LedgerKeyNonce(ScNonceKey {
+                nonce: credentials.nonce,
+            });
+            let ledger_key = LedgerKey::ContractData(LedgerKeyContractData {
+                contract: credentials.address.clone(),
+                key: key.clone(),
+                durability: ContractDataDurability::Temporary,
+            });
+            // As written by the host when consuming the nonce
+            let entry = LedgerEntry {
+                last_modified_ledger_seq: 0,
+                data: LedgerEntryData::ContractData(ContractDataEntry {
+                    ext: ExtensionPoint::V0,
+                    contract: credentials.address.clone(),
+                    key,
+                    durability: ContractDataDurability::Temporary,
+                    val: ScVal::Void,
+                }),
+                ext: LedgerEntryExt::V0,
+            };
+            (ledger_key, entry)
+        })
+        .collect()
+}
+
+// The recorded authorizations consume random nonces, which remain in the
+// footprint and ledger changes once `check_mock_signature_auth()` swapped in
+// the checked entries. They are replaced by the nonces of the checked entries,
+// which enforcing them would consume. Their sizes differ by the type of their
+// address, and the source account authorizing through address credentials
+// consumes a nonce the recording didn't, so the writes and the rent (for the
+// longest lifetime, like the recorded nonces) are charged for the difference.
+//
+// This happens once the footprint is replayed, which regenerates the recorded
+// nonces.
+fn replace_nonces(
+    context: &PreflightContext,
+    adjustment_config: &SimulationAdjustmentConfig,
+    result: &mut PreflightResult,
+    host_function: HostFunction,
+    recorded_auth: &[SorobanAuthorizationEntry],
+) -> Result<()> {
+    let Some(transaction_data) = result.transaction_data.as_mut() else {
+        return Ok(());
+    };
+    if !result.error.is_empty() {
+        return Ok(());
+    }
+    let recorded = nonce_entries(recorded_auth);
+    let checked = nonce_entries(&result.auth);
+    let is_recorded = |key: &LedgerKey| recorded.iter().any(|(k, _)| k == key);
+    let operation = OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+        host_function,
+        auth: result.auth.clone().try_into()?,
+    });
+    let before = fee_breakdown(
+        context,
+        adjustment_config,
+        operation.clone(),
+        transaction_data,
+        0,
+    )?;
+
+    let ledger_info = &context.ledger_info;
+    let max_live_until_ledger = ledger_info
+        .sequence_number
+        .saturating_add(ledger_info.max_entry_ttl)
+        .saturating_sub(1);
+    // The write bytes and rent of consuming the nonces
+    let charges = |nonces: &[(LedgerKey, LedgerEntry)]| -> Result<(i64, i64)> {
+        let mut write_bytes = 0;
+        let mut rent_changes = Vec::with_capacity(nonces.len());
+        for (key, entry) in nonces {
+            write_bytes += entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len();
+            rent_changes.push(super::rent_change(
+                key,
+                false,
+                0,
+                entry_size_for_rent(entry, context)?,
+                0,
+                max_live_until_ledger,
+            ));
+        }
+        let rent = compute_rent_fee(
+            &rent_changes,
+            &context.network_config.rent_fee_configuration,
+            ledger_info.sequence_number,
+        );
+        Ok((i64::try_from(write_bytes)?, rent))
+    };
+    let (recorded_write_bytes, recorded_rent) = charges(&recorded)?;
+    let (checked_write_bytes, checked_rent) = charges(&checked)?;
+    let rent_increase = checked_rent - recorded_rent;
+
+    let resources = &mut transaction_data.resources;
+    let mut read_write: Vec<LedgerKey> = resources
+        .footprint
+        .read_write
+        .iter()
+        .filter(|key| !is_recorded(key))
+        .cloned()
+        .collect();
+    let mut ledger_entry_diff = Vec::with_capacity(result.ledger_entry_diff.len());
+    for (state_before, state_after) in std::mem::take(&mut result.ledger_entry_diff) {
+        if let (None, Some(entry)) = (&state_before, &state_after) {
+            if is_recorded(&ledger_entry_to_ledger_key(entry)?) {
+                continue;
+            }
+        }
+        ledger_entry_diff.push((state_before, state_after));
+    }
+    result
+        .footprint_bytes
+        .retain(|(key, _, _)| !is_recorded(key));
+    for (key, entry) in checked {
+        let entry_xdr_size = u32::try_from(entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len())?;
+        read_write.push(key.clone());
+        ledger_entry_diff.push((None, Some(entry)));
+        result.footprint_bytes.push((key, 0, entry_xdr_size));
+    }
+    // Like the footprints of the simulation
+    read_write.sort();
+    resources.footprint.read_write = read_write.try_into()?;
+    resources.write_bytes = u32::try_from(
+        (i64::from(resources.write_bytes) + checked_write_bytes - recorded_write_bytes).max(0),
+    )?;
+    result.ledger_entry_diff = ledger_entry_diff;
+
+    let after = fee_breakdown(context, adjustment_config, operation, transaction_data, 0)?;
+    let fee_increase: i64 = after.resource_fees().iter().sum::<i64>()
+        - before.resource_fees().iter().sum::<i64>()
+        + rent_increase;
+    transaction_data.resource_fee = transaction_data.resource_fee.saturating_add(fee_increase);
+    result.min_fee = transaction_data.resource_fee;
+    if let Some(fee_breakdown) = result.fee_breakdown.as_mut() {
+        fee_breakdown.add_increase(&before, &after);
+        fee_breakdown.rent += rent_increase;
+    }
+    Ok(())
+}
+
+// Entries whose TTL runs out within this many ledgers (about a day) are
+// reported as close to archival.
+const ARCHIVAL_WARNING_LEDGERS: u32 = 17_280;
+
+// Non-fatal findings of a successful invocation, which the submitter of the
+// transaction may want to act upon. Only the CPU and memory limits of the
+// network are checked, the other limits not being part of the configuration
+// of the simulation.
+fn invoke_warnings(
+    context: &PreflightContext,
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    host_function: &HostFunction,
+    allow_non_root_auth: bool,
+) -> Result<Vec<(u32, String)>> {
+    let mut warnings = Vec::new();
+    let Some(transaction_data) = &invoke_hf_result.transaction_data else {
+        return Ok(warnings);
+    };
+
+    let resources = [
+        (
+            "instructions",
+            u64::from(transaction_data.resources.instructions),
+            u64::try_from(context.tx_max_instructions)?,
+        ),
+        (
+            "memory bytes",
+            u64::from(invoke_hf_result.simulated_memory),
+            u64::from(context.tx_memory_limit),
+        ),
+    ];
+    for (name, used, limit) in resources {
+        // Within 5% of the limit
+        if used.saturating_mul(100) >= limit.saturating_mul(95) {
+            warnings.push((
+                WARNING_RESOURCE_NEAR_LIMIT,
+                format!("{name}: {used} used out of the {limit} allowed per transaction"),
+            ));
+        }
+    }
+
+    if let HostFunction::CreateContract(_) = host_function {
+        warnings.push((
+            WARNING_DEPRECATED_HOST_FUNCTION,
+            "CreateContract is deprecated in favor of CreateContractV2".to_string(),
+        ));
+    }
+
+    if let (true, HostFunction::InvokeContract(args)) = (allow_non_root_auth, host_function) {
+        for entry in &invoke_hf_result.auth {
+            let message = match &entry.root_invocation.function {
+                SorobanAuthorizedFunction::ContractFn(root) if root == args => continue,
+                SorobanAuthorizedFunction::ContractFn(root) => format!(
+                    "authorization recorded for the non-root call of function {}",
+                    root.function_name.0.to_utf8_string_lossy()
+                ),
+                _ => "authorization recorded for a non-root contract creation".to_string(),
+            };
+            warnings.push((WARNING_NON_ROOT_AUTH, message));
+        }
+    }
+
+    let footprint = &transaction_data.resources.footprint;
+    let ledger_seq = context.ledger_info.sequence_number;
+    for key in footprint
+        .read_only
+        .iter()
+        .chain(footprint.read_write.iter())
+    {
+        let kind = match key {
+            LedgerKey::ContractData(_) => "contract data",
+            LedgerKey::ContractCode(_) => "contract code",
+            _ => continue,
+        };
+        let Some((_, Some(live_until))) =
+            get_fallible_from_go_ledger_storage(&context.go_storage, key)?
+        else {
+            continue;
+        };
+        // Archived entries are reported through the restore preamble instead
+        if live_until >= ledger_seq && live_until - ledger_seq < ARCHIVAL_WARNING_LEDGERS {
+            warnings.push((
+                WARNING_ENTRY_NEAR_ARCHIVAL,
+                format!(
+                    "{kind} entry of the footprint is archived after ledger {live_until} unless its TTL is extended"
+                ),
+            ));
+        }
+    }
+    Ok(warnings)
+}
+
+// Replays a recording-auth invocation against a given footprint, enforcing it,
+// in order to find out which of the recorded keys the invocation can do without
+// and how much linear memory it uses.
+struct FootprintReplay<'a> {
+    context: &'a PreflightContext,
+    snapshot: &'a dyn SnapshotSource,
+    host_function: HostFunction,
+    source_account: &'a AccountId,
+    base_prng_seed: [u8; 32],
+    auth_mode: RecordingInvocationAuthMode,
+}
+
+impl FootprintReplay<'_> {
+    // Drops the read-only keys the invocation still succeeds without, one at a
+    // time. Account keys are kept: recording auth doesn't read the accounts
+    // which the signatures get verified against once the transaction is signed.
+    //
+    // Only the footprint and the read bytes are adjusted. The resource fee is
+    // left as simulated, the part of it unused by the smaller footprint being
+    // refunded.
+    fn minimize_footprint(&self, transaction_data: &mut SorobanTransactionData) -> Result<()> {
+        let footprint = &transaction_data.resources.footprint;
+        let read_write = footprint.read_write.to_vec();
+        let mut read_only = footprint.read_only.to_vec();
+        if !self.succeeds(&read_only, &read_write)? {
+            return Ok(());
+        }
+        let mut dropped = Vec::new();
+        let mut i = 0;
+        while i < read_only.len() {
+            if matches!(read_only[i], LedgerKey::Account(_)) {
+                i += 1;
+                continue;
+            }
+            let mut candidate = read_only.clone();
+            let key = candidate.remove(i);
+            if self.succeeds(&candidate, &read_write)? {
+                read_only = candidate;
+                dropped.push(key);
+            } else {
+                i += 1;
+            }
+        }
+        if dropped.is_empty() {
+            return Ok(());
+        }
+        for key in &dropped {
+            let entry_xdr_size = match self.snapshot.get(&Rc::new(key.clone()))? {
+                Some((entry, _)) => u32::try_from(entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len())?,
+                None => 0,
+            };
+            super::drop_read_entry_bytes(&mut transaction_data.resources, key, entry_xdr_size);
+        }
+        transaction_data.resources.footprint.read_only = read_only.try_into()?;
+        Ok(())
+    }
+
+    fn succeeds(&self, read_only: &[LedgerKey], read_write: &[LedgerKey]) -> Result<bool> {
+        let host = self.host(read_only, read_write)?;
+        let succeeded = host.invoke_function(self.host_function.clone()).is_ok();
+        self.context.go_storage.check_cancelled()?;
+        Ok(succeeded)
+    }
+
+    // The linear memory allocated by the VMs of the invocation, as metered by
+    // the budget of a replay against its footprint (the host of the simulation
+    // itself isn't observable), or the error of the replay.
+    fn linear_memory_bytes(
+        &self,
+        transaction_data: &SorobanTransactionData,
+    ) -> Result<std::result::Result<u64, HostError>> {
+        let footprint = &transaction_data.resources.footprint;
+        let host = self.host(&footprint.read_only, &footprint.read_write)?;
+        let replay = host.invoke_function(self.host_function.clone());
+        self.context.go_storage.check_cancelled()?;
+        match replay {
+            Ok(_) => Ok(Ok(host.budget_cloned().get_wasm_mem_alloc()?)),
+            Err(e) => Ok(Err(e)),
+        }
+    }
+
+    // A host replaying the invocation against the footprint. The replays don't
+    // read the ledger once their host is set up, so the cancellation is only
+    // noticed before and after them: the host doesn't let embedders hook into
+    // its budget to interrupt the execution itself.
+    fn host(&self, read_only: &[LedgerKey], read_write: &[LedgerKey]) -> Result<Host> {
+        self.context.go_storage.check_cancelled()?;
+        let network_config = &self.context.network_config;
+        let budget = Budget::try_from_configs(
+            u64::try_from(network_config.tx_max_instructions)?,
+            u64::from(network_config.tx_memory_limit),
+            network_config.cpu_cost_params.clone(),
+            network_config.memory_cost_params.clone(),
+        )?;
+        let read_only = read_only.iter().map(|k| (k, AccessType::ReadOnly));
+        let read_write = read_write.iter().map(|k| (k, AccessType::ReadWrite));
+        let mut footprint_map = FootprintMap::new();
+        let mut storage_map = StorageMap::new();
+        for (key, access_type) in read_only.chain(read_write) {
+            let key = Rc::new(key.clone());
+            let entry = self.snapshot.get(&key)?;
+            footprint_map = footprint_map.insert(key.clone(), access_type, &budget)?;
+            storage_map = storage_map.insert(key, entry, &budget)?;
+        }
+        let storage =
+            Storage::with_enforcing_footprint_and_map(Footprint(footprint_map), storage_map);
+        let host = Host::with_storage_and_budget(storage, budget);
+        host.set_ledger_info(self.context.ledger_info.clone())?;
+        host.set_source_account(self.source_account.clone())?;
+        host.set_base_prng_seed(self.base_prng_seed)?;
+        match &self.auth_mode {
+            RecordingInvocationAuthMode::Enforcing(auth_entries) => {
+                host.set_authorization_entries(auth_entries.clone())?;
+            }
+            RecordingInvocationAuthMode::Recording(disable_non_root_auth) => {
+                host.switch_to_recording_auth(*disable_non_root_auth)?;
+            }
+        }
+        Ok(host)
+    }
+}
+
+// Runs the env's validation of the Wasm module uploaded by a failed invocation
+// again, on a host recording diagnostics, so that the reasons the module is
+// rejected can be reported even when debugging is disabled.
+fn wasm_validation_events(
+    context: &PreflightContext,
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    host_function: &HostFunction,
+) -> Result<Vec<DiagnosticEvent>> {
+    let (Err(_), HostFunction::UploadContractWasm(wasm)) =
+        (&invoke_hf_result.invoke_result, host_function)
+    else {
+        return Ok(Vec::new());
+    };
+    let host = Host::default();
+    host.set_ledger_info(context.ledger_info.clone())?;
+    host.set_diagnostic_level(DiagnosticLevel::Debug)?;
+    host.budget_cloned().reset_unlimited()?;
+    // The previous protocol identifies contracts by Hash rather than ContractId
+    #[allow(clippy::useless_conversion)]
+    if Vm::new(&host, Hash([0; 32]).into(), wasm).is_ok() {
+        return Ok(Vec::new());
+    }
+    let events = host.get_events()?.0;
+    Ok(events
+        .into_iter()
+        .map(|e| DiagnosticEvent {
+            in_successful_contract_call: !e.failed_call,
+            event: e.event,
+        })
+        .collect())
+}
+
+// Reports the innermost contract call which was still executing when the
+// invocation ran out of budget, so that callers capping the budget know where
+// the simulation was aborted.
+fn budget_exceeded_error(invoke_hf_result: &InvokeHostFunctionSimulationResult) -> Option<String> {
+    let Err(e) = &invoke_hf_result.invoke_result else {
+        return None;
+    };
+    if !(e.error.is_type(ScErrorType::Budget) && e.error.is_code(ScErrorCode::ExceededLimit)) {
+        return None;
+    }
+    let (contract_id, function, depth) = innermost_contract_call(invoke_hf_result)?;
+    // The previous protocol identifies contracts by Hash rather than ContractId
+    #[allow(clippy::useless_conversion)]
+    let contract_address = ScAddress::Contract(Hash(contract_id).into());
+    Some(format!(
+        "budget exceeded at frame {depth} (contract {contract_address}, function {function}): {e:?}"
+    ))
+}
+
+// Returns the ID and function of the innermost contract call which was still
+// executing at the end of the invocation (i.e. the one which failed, if any),
+// along with the depth of that call. The calls are tracked through the fn_call
+// and fn_return diagnostic events, which are only recorded when debugging is
+// enabled.
+fn innermost_contract_call(
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+) -> Option<([u8; 32], String, usize)> {
+    let mut frames = Vec::new();
+    for event in &invoke_hf_result.diagnostic_events {
+        let ContractEventBody::V0(body) = &event.event.body;
+        match body.topics.as_slice() {
+            [ScVal::Symbol(topic), ScVal::Bytes(contract_id), ScVal::Symbol(function)]
+                if topic.0.as_slice() == b"fn_call" =>
+            {
+                frames.push((contract_id, function));
+            }
+            [ScVal::Symbol(topic), ..] if topic.0.as_slice() == b"fn_return" => {
+                frames.pop();
+            }
+            _ => {}
+        }
+    }
+    let (contract_id, function) = frames.last()?;
+    Some((
+        contract_id.as_slice().try_into().ok()?,
+        function.0.to_utf8_string_lossy(),
+        frames.len(),
     ))
 }
 
-pub(crate) fn preflight_footprint_ttl_op_or_maybe_panic(
+// Structured counterpart of extract_error_string(), letting callers tell
+// failures apart without parsing the error string.
+fn error_details<T>(
+    simulation_result: &Result<T>,
+    go_storage: &GoLedgerStorage,
+    contract_id: Option<[u8; 32]>,
+) -> ErrorDetails {
+    let Err(e) = simulation_result else {
+        return ErrorDetails::default();
+    };
+    let mut details = ErrorDetails {
+        category: ERROR_CATEGORY_HOST,
+        contract_id,
+        ..Default::default()
+    };
+    // Like in extract_error_string(), storage errors take precedence
+    if go_storage.internal_error.lock().unwrap().is_some() {
+        details.category = ERROR_CATEGORY_STORAGE;
+        return details;
+    }
+    let Some(sc_error) = e
+        .downcast_ref::<HostError>()
+        .and_then(|e| ScError::try_from(e.error).ok())
+    else {
+        return details;
+    };
+    details.category = match sc_error.discriminant() {
+        ScErrorType::Storage => ERROR_CATEGORY_STORAGE,
+        ScErrorType::Budget => ERROR_CATEGORY_BUDGET,
+        ScErrorType::Auth => ERROR_CATEGORY_AUTH,
+        ScErrorType::Contract => ERROR_CATEGORY_CONTRACT,
+        _ => ERROR_CATEGORY_HOST,
+    };
+    details.sc_error = Some(sc_error);
+    details
+}
+
+// Computes the size of each footprint entry before (read) and after (write)
+// the invocation. Every read-write entry is written back, modified or not.
+fn footprint_bytes(
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    go_storage: &GoLedgerStorage,
+) -> Result<Vec<(LedgerKey, u32, u32)>> {
+    let Some(transaction_data) = &invoke_hf_result.transaction_data else {
+        return Ok(Vec::new());
+    };
+    let entry_size = |entry: Option<&LedgerEntry>| -> Result<u32> {
+        let size = entry.map_or(Ok(0), |e| e.to_xdr(DEFAULT_XDR_RW_LIMITS).map(|x| x.len()))?;
+        Ok(u32::try_from(size)?)
+    };
+    let mut modified_sizes = HashMap::new();
+    for diff in &invoke_hf_result.modified_entries {
+        let Some(entry) = diff.state_before.as_ref().or(diff.state_after.as_ref()) else {
+            continue;
+        };
+        let key = ledger_entry_to_ledger_key(entry)?;
+        let sizes = (
+            entry_size(diff.state_before.as_ref())?,
+            entry_size(diff.state_after.as_ref())?,
+        );
+        modified_sizes.insert(key, sizes);
+    }
+
+    let footprint = &transaction_data.resources.footprint;
+    let read_only = footprint.read_only.iter().map(|k| (k, false));
+    let read_write = footprint.read_write.iter().map(|k| (k, true));
+    let mut footprint_bytes =
+        Vec::with_capacity(footprint.read_only.len() + footprint.read_write.len());
+    for (key, is_read_write) in read_only.chain(read_write) {
+        let mut key_xdr = key.to_xdr(DEFAULT_XDR_RW_LIMITS)?;
+        let (read_bytes, write_bytes) = if let Some(sizes) = modified_sizes.get(key) {
+            *sizes
+        } else {
+            let size = go_storage
+                .get_xdr_internal(&mut key_xdr)
+                .map_or(0, |(entry_xdr, _)| entry_xdr.len());
+            let size = u32::try_from(size)?;
+            (size, if is_read_write { size } else { 0 })
+        };
+        footprint_bytes.push((key.clone(), read_bytes, write_bytes));
+    }
+    Ok(footprint_bytes)
+}
+
+pub(crate) fn preflight_extend_ttl_op_or_maybe_panic(
     handle: libc::uintptr_t,
-    op_body: CXDR,
-    footprint: CXDR,
+    keys_to_extend: &CXDRVector, // array of LedgerKey XDR
+    extend_to: u32,
     c_ledger_info: CLedgerInfo,
+    ledger_entry_overrides: &CXDRVector, // array of LedgerEntry XDR
 ) -> Result<CPreflightResult> {
-    let op_body = OperationBody::from_xdr(unsafe { from_c_xdr(op_body) }, DEFAULT_XDR_RW_LIMITS)?;
-    let footprint =
-        LedgerFootprint::from_xdr(unsafe { from_c_xdr(footprint) }, DEFAULT_XDR_RW_LIMITS)?;
-    let go_storage = Rc::new(GoLedgerStorage::new(handle));
-    let network_config =
-        NetworkConfig::load_from_snapshot(go_storage.as_ref(), c_ledger_info.bucket_list_size)?;
-    let ledger_info = fill_ledger_info(c_ledger_info, &network_config);
-    // TODO: It would make for a better UX if the user passed only the necessary fields for every operation.
-    // That would remove a possibility of providing bad operation body, or a possibility of filling wrong footprint
-    // field.
-    match op_body {
-        OperationBody::ExtendFootprintTtl(extend_op) => {
-            preflight_extend_ttl_op(&extend_op, footprint.read_only.as_slice(), &go_storage, &network_config, &ledger_info)
-        }
-        OperationBody::RestoreFootprint(_) => {
-            Ok(preflight_restore_op(footprint.read_write.as_slice(), &go_storage, &network_config, &ledger_info))
-        }
-        _ => Err(anyhow!("encountered unsupported operation type: '{:?}', instead of 'ExtendFootprintTtl' or 'RestoreFootprint' operations.",
-            op_body.discriminant()))
-    }
-}
-
-fn preflight_extend_ttl_op(
-    extend_op: &ExtendFootprintTtlOp,
-    keys_to_extend: &[LedgerKey],
-    go_storage: &Rc<GoLedgerStorage>,
-    network_config: &NetworkConfig,
-    ledger_info: &LedgerInfo,
+    let keys_to_extend = ledger_keys_from_c(keys_to_extend)?;
+    let go_storage = GoLedgerStorage::new(handle, c_ledger_info.sequence_number);
+    let context = preflight_context_from_c(go_storage, c_ledger_info, ledger_entry_overrides)?;
+    let result = preflight_extend_ttl_op(&context, &keys_to_extend, extend_to)?;
+    Ok(preflight_result_to_c(result))
+}
+
+pub(crate) fn preflight_restore_op_or_maybe_panic(
+    handle: libc::uintptr_t,
+    keys_to_restore: &CXDRVector, // array of LedgerKey XDR
+    c_ledger_info: CLedgerInfo,
+    ledger_entry_overrides: &CXDRVector, // array of LedgerEntry XDR
 ) -> Result<CPreflightResult> {
-    let auto_restore_snapshot = AutoRestoringSnapshotSource::new(go_storage.clone(), ledger_info)?;
-    let simulation_result = simulate_extend_ttl_op(
-        &auto_restore_snapshot,
-        network_config,
-        &SimulationAdjustmentConfig::default_adjustment(),
-        ledger_info,
-        keys_to_extend,
-        extend_op.extend_to,
-    );
+    let keys_to_restore = ledger_keys_from_c(keys_to_restore)?;
+    let go_storage = GoLedgerStorage::new(handle, c_ledger_info.sequence_number);
+    let context = preflight_context_from_c(go_storage, c_ledger_info, ledger_entry_overrides)?;
+    let result = preflight_restore_op(&context, &keys_to_restore)?;
+    Ok(preflight_result_to_c(result))
+}
+
+fn ledger_keys_from_c(keys: &CXDRVector) -> Result<Vec<LedgerKey>> {
+    let keys = unsafe { keys.as_slices() };
+    keys.into_iter()
+        .map(|key| LedgerKey::from_xdr(key, DEFAULT_XDR_RW_LIMITS).context(InvalidInput))
+        .collect()
+}
+
+fn ledger_entries_from_c(entries: &CXDRVector) -> Result<Vec<LedgerEntry>> {
+    let entries = unsafe { entries.as_slices() };
+    entries
+        .into_iter()
+        .map(|entry| LedgerEntry::from_xdr(entry, DEFAULT_XDR_RW_LIMITS).context(InvalidInput))
+        .collect()
+}
+
+// Indexes the overriding entries by key. Entries with a TTL keep the one of
+// the entry they replace, and new ones live as long as a freshly created entry
+// would.
+fn new_ledger_entry_overrides(
+    entries: Vec<LedgerEntry>,
+    go_storage: &GoLedgerStorage,
+    ledger_info: &LedgerInfo,
+) -> Result<LedgerEntryOverrides> {
+    let mut overrides = HashMap::with_capacity(entries.len());
+    for entry in entries {
+        let key = ledger_entry_to_ledger_key(&entry).context(InvalidInput)?;
+        let mut key_xdr = key.to_xdr(DEFAULT_XDR_RW_LIMITS)?;
+        let live_until = match get_key_durability(&key) {
+            None => None,
+            Some(durability) => go_storage
+                .get_xdr_internal(&mut key_xdr)
+                .and_then(|(_, live_until)| live_until)
+                .or_else(|| ledger_info.min_live_until_ledger_checked(durability)),
+        };
+        let entry_xdr = entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?;
+        if overrides.insert(key_xdr, (entry_xdr, live_until)).is_some() {
+            return Err(
+                anyhow!("duplicate ledger entry override for key {key:?}").context(InvalidInput)
+            );
+        }
+    }
+    Ok(overrides)
+}
+
+fn ledger_entry_to_ledger_key(entry: &LedgerEntry) -> Result<LedgerKey> {
+    let key = match &entry.data {
+        LedgerEntryData::Account(a) => LedgerKey::Account(LedgerKeyAccount {
+            account_id: a.account_id.clone(),
+        }),
+        LedgerEntryData::Trustline(tl) => LedgerKey::Trustline(LedgerKeyTrustLine {
+            account_id: tl.account_id.clone(),
+            asset: tl.asset.clone(),
+        }),
+        LedgerEntryData::ContractData(cd) => LedgerKey::ContractData(LedgerKeyContractData {
+            contract: cd.contract.clone(),
+            key: cd.key.clone(),
+            durability: cd.durability,
+        }),
+        LedgerEntryData::ContractCode(code) => LedgerKey::ContractCode(LedgerKeyContractCode {
+            hash: code.hash.clone(),
+        }),
+        data => bail!("overriding {} ledger entries is not supported", data.name()),
+    };
+    Ok(key)
+}
+
+// Only contract data and code entries have a TTL, and only the persistent ones
+// can be restored. Catch bad keys early rather than letting the simulation fail
+// with a less helpful error.
+fn validate_soroban_keys(keys: &[LedgerKey], persistent_only: bool) -> Result<()> {
+    if keys.is_empty() {
+        bail!("no ledger keys provided");
+    }
+    let mut seen = HashSet::with_capacity(keys.len());
+    for key in keys {
+        match key {
+            LedgerKey::ContractCode(_) => {}
+            LedgerKey::ContractData(data) => {
+                if persistent_only && data.durability != ContractDataDurability::Persistent {
+                    bail!("temporary contract data entries cannot be restored: {key:?}");
+                }
+            }
+            _ => bail!(
+                "ledger key of type '{:?}' has no TTL, only contract data and contract code entries are allowed",
+                key.discriminant()
+            ),
+        }
+        if !seen.insert(key) {
+            bail!("duplicate ledger key: {key:?}");
+        }
+    }
+    Ok(())
+}
+
+// Starts from the default adjustment and overrides every factor the caller
+// explicitly set in `resource_config`.
+fn new_adjustment_config(resource_config: &CResourceConfig) -> Result<SimulationAdjustmentConfig> {
+    let mut adjustment_config = SimulationAdjustmentConfig::default_adjustment();
+    override_adjustment_factor(
+        &mut adjustment_config.instructions,
+        resource_config.instructions,
+        "instructions",
+    )?;
+    override_adjustment_factor(
+        &mut adjustment_config.read_bytes,
+        resource_config.read_bytes,
+        "read_bytes",
+    )?;
+    override_adjustment_factor(
+        &mut adjustment_config.write_bytes,
+        resource_config.write_bytes,
+        "write_bytes",
+    )?;
+    override_adjustment_factor(
+        &mut adjustment_config.tx_size,
+        resource_config.tx_size,
+        "tx_size",
+    )?;
+    override_adjustment_factor(
+        &mut adjustment_config.refundable_fee,
+        resource_config.refundable_fee,
+        "refundable_fee",
+    )?;
+    Ok(adjustment_config)
+}
+
+fn override_adjustment_factor(
+    factor: &mut SimulationAdjustmentFactor,
+    c_factor: CAdjustmentFactor,
+    name: &str,
+) -> Result<()> {
+    if !c_factor.set {
+        return Ok(());
+    }
+    // The adjusted value is `max(value * multiplicative_factor, value + additive_factor)`,
+    // so a multiplicative factor below 1 is a no-op and most likely a caller mistake.
+    if !c_factor.multiplicative_factor.is_finite() || c_factor.multiplicative_factor < 1.0 {
+        bail!(
+            "invalid {name} multiplicative factor {}: it must be a finite number no less than 1",
+            c_factor.multiplicative_factor
+        );
+    }
+    *factor =
+        SimulationAdjustmentFactor::new(c_factor.multiplicative_factor, c_factor.additive_factor);
+    Ok(())
+}
+
+// Simulates extending the TTL of the given entries to `extend_to` ledgers from
+// the simulated one.
+pub(crate) fn preflight_extend_ttl_op(
+    context: &PreflightContext,
+    keys_to_extend: &[LedgerKey],
+    extend_to: u32,
+) -> Result<PreflightResult> {
+    validate_soroban_keys(keys_to_extend, false).context(InvalidInput)?;
+    if extend_to == 0 {
+        return Err(anyhow!("extend_to must be greater than zero").context(InvalidInput));
+    }
+    // Checked ahead of the simulation, which doesn't guard against it
+    let Some(new_live_until_ledger) = context.ledger_info.sequence_number.checked_add(extend_to)
+    else {
+        return Err(
+            anyhow!("extend_to {extend_to} overflows the live until ledger").context(InvalidInput),
+        );
+    };
+    context.prefetch(keys_to_extend)?;
+    let go_storage = Rc::new(context.go_storage.clone());
+    let auto_restore_snapshot =
+        AutoRestoringSnapshotSource::new(go_storage.clone(), &context.ledger_info)?;
+    let simulation_result = context.spans.time(SPAN_EXECUTION, || {
+        simulate_extend_ttl_op(
+            &auto_restore_snapshot,
+            &context.network_config,
+            &SimulationAdjustmentConfig::default_adjustment(),
+            &context.ledger_info,
+            keys_to_extend,
+            extend_to,
+        )
+    });
     let (maybe_transaction_data, maybe_restore_result) = match simulation_result {
         Ok(r) => (
             Some(r.transaction_data),
-            auto_restore_snapshot.simulate_restore_keys_op(
-                network_config,
-                &SimulationAdjustmentConfig::default_adjustment(),
-                ledger_info,
-            ),
+            context.spans.time(SPAN_RESTORE_SIMULATION, || {
+                auto_restore_snapshot.simulate_restore_keys_op(
+                    &context.network_config,
+                    &SimulationAdjustmentConfig::default_adjustment(),
+                    &context.ledger_info,
+                )
+            }),
         ),
         Err(e) => (None, Err(e)),
     };
 
-    let error_str = extract_error_string(&maybe_restore_result, go_storage);
-    Ok(new_cpreflight_result_from_transaction_data(
-        maybe_transaction_data.as_ref(),
-        maybe_restore_result.ok().flatten().as_ref(),
+    let ttl_changes = if maybe_transaction_data.is_some() {
+        extend_ttl_changes(
+            keys_to_extend,
+            new_live_until_ledger,
+            &auto_restore_snapshot,
+            context,
+        )?
+    } else {
+        Vec::new()
+    };
+
+    let error_str = extract_error_string(&maybe_restore_result, &go_storage);
+    let error_details = error_details(&maybe_restore_result, &go_storage, None);
+    let mut result = new_preflight_result_from_transaction_data(
+        maybe_transaction_data,
+        maybe_restore_result.ok().flatten(),
         error_str,
-    ))
+    );
+    result.rent_changes = rent_changes(&ttl_changes, context);
+    result.ledger_entry_diff = ttl_changes_to_ledger_entry_diff(&ttl_changes, context)?;
+    result.error_details = error_details;
+    let operation = OperationBody::ExtendFootprintTtl(ExtendFootprintTtlOp {
+        ext: ExtensionPoint::V0,
+        extend_to,
+    });
+    result.fee_breakdown = transaction_fee_breakdown(context, operation, &result)?;
+    result.spans = context.spans.take();
+    Ok(result)
 }
 
-fn preflight_restore_op(
+// Simulates restoring the given archived persistent entries
+//
+// Entries evicted to the hot archive don't need any proof to be restored: the
+// validators look them up in the archive themselves, and Core hands them over
+// to us as archived entries. So the footprint of the restore, which the
+// simulation builds, is all a submittable restore needs (the index list of
+// archived entries, `archived_soroban_entries`, only applies to invocations
+// restoring their own footprint).
+pub(crate) fn preflight_restore_op(
+    context: &PreflightContext,
     keys_to_restore: &[LedgerKey],
-    go_storage: &Rc<GoLedgerStorage>,
-    network_config: &NetworkConfig,
-    ledger_info: &LedgerInfo,
-) -> CPreflightResult {
-    let simulation_result = simulate_restore_op(
-        go_storage.as_ref(),
-        network_config,
-        &SimulationAdjustmentConfig::default_adjustment(),
-        ledger_info,
-        keys_to_restore,
-    );
-    let error_str = extract_error_string(&simulation_result, go_storage.as_ref());
-    new_cpreflight_result_from_transaction_data(
-        simulation_result.ok().map(|r| r.transaction_data).as_ref(),
+) -> Result<PreflightResult> {
+    validate_soroban_keys(keys_to_restore, true).context(InvalidInput)?;
+    context.prefetch(keys_to_restore)?;
+    let simulation_result = context.spans.time(SPAN_EXECUTION, || {
+        simulate_restore_op(
+            &context.go_storage,
+            &context.network_config,
+            &SimulationAdjustmentConfig::default_adjustment(),
+            &context.ledger_info,
+            keys_to_restore,
+        )
+    });
+    let ttl_changes = if simulation_result.is_ok() {
+        restore_ttl_changes(keys_to_restore, context)?
+    } else {
+        Vec::new()
+    };
+
+    let error_str = extract_error_string(&simulation_result, &context.go_storage);
+    let error_details = error_details(&simulation_result, &context.go_storage, None);
+    let mut result = new_preflight_result_from_transaction_data(
+        simulation_result.ok().map(|r| r.transaction_data),
         None,
         error_str,
-    )
+    );
+    result.rent_changes = rent_changes(&ttl_changes, context);
+    result.ledger_entry_diff = ttl_changes_to_ledger_entry_diff(&ttl_changes, context)?;
+    result.error_details = error_details;
+    result.restore_statuses = restore_statuses(keys_to_restore, context)?;
+    let operation = OperationBody::RestoreFootprint(RestoreFootprintOp {
+        ext: ExtensionPoint::V0,
+    });
+    result.fee_breakdown = transaction_fee_breakdown(context, operation, &result)?;
+    result.spans = context.spans.take();
+    Ok(result)
 }
 
-// TODO: We could use something like https://github.com/sonos/ffi-convert-rs
-//       to replace all the free_* , *_to_c and from_c_* functions by implementations of CDrop,
-//       CReprOf and AsRust
-fn xdr_to_c(v: &impl WriteXdr) -> CXDR {
-    let (xdr, len) = vec_to_c_array(v.to_xdr(DEFAULT_XDR_RW_LIMITS).unwrap());
-    CXDR { xdr, len }
+fn invoke_fee_breakdown(
+    context: &PreflightContext,
+    adjustment_config: &SimulationAdjustmentConfig,
+    invoke_hf_result: &InvokeHostFunctionSimulationResult,
+    host_function: &HostFunction,
+) -> Result<Option<FeeBreakdown>> {
+    let (Ok(return_value), Some(transaction_data)) = (
+        &invoke_hf_result.invoke_result,
+        &invoke_hf_result.transaction_data,
+    ) else {
+        return Ok(None);
+    };
+    // The events are charged along with the return value, by their XDR size
+    let mut events_size = return_value.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len();
+    for event in &invoke_hf_result.contract_events {
+        events_size += event.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len();
+    }
+    let operation = OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+        host_function: host_function.clone(),
+        auth: invoke_hf_result.auth.clone().try_into()?,
+    });
+    Ok(Some(fee_breakdown(
+        context,
+        adjustment_config,
+        operation,
+        transaction_data,
+        u32::try_from(events_size)?,
+    )?))
 }
 
-fn option_xdr_to_c(v: Option<&impl WriteXdr>) -> CXDR {
-    v.map_or(
-        CXDR {
-            xdr: null_mut(),
-            len: 0,
+// For the TTL operations, which are simulated with the default adjustments and
+// don't emit any event
+fn transaction_fee_breakdown(
+    context: &PreflightContext,
+    operation: OperationBody,
+    result: &PreflightResult,
+) -> Result<Option<FeeBreakdown>> {
+    let Some(transaction_data) = &result.transaction_data else {
+        return Ok(None);
+    };
+    let adjustment_config = SimulationAdjustmentConfig::default_adjustment();
+    Ok(Some(fee_breakdown(
+        context,
+        &adjustment_config,
+        operation,
+        transaction_data,
+        0,
+    )?))
+}
+
+fn fee_breakdown(
+    context: &PreflightContext,
+    adjustment_config: &SimulationAdjustmentConfig,
+    operation: OperationBody,
+    transaction_data: &SorobanTransactionData,
+    events_size: u32,
+) -> Result<FeeBreakdown> {
+    let fees = &context.network_config.fee_configuration;
+    let resources = &transaction_data.resources;
+    let (read_entries, read_bytes) = super::read_fees(&context.network_config, transaction_data);
+    let transaction_size = adjust_u32(
+        &adjustment_config.tx_size,
+        max_transaction_size(operation, transaction_data)?,
+    );
+    let mut breakdown = FeeBreakdown {
+        instructions: fee_per_increment(
+            resources.instructions,
+            fees.fee_per_instruction_increment,
+            INSTRUCTIONS_INCREMENT,
+        ),
+        read_entries,
+        write_entries: fees
+            .fee_per_write_entry
+            .saturating_mul(i64::try_from(resources.footprint.read_write.len())?),
+        read_bytes,
+        write_bytes: fee_per_increment(
+            resources.write_bytes,
+            fees.fee_per_write_1kb,
+            DATA_SIZE_1KB_INCREMENT,
+        ),
+        historical: fee_per_increment(
+            transaction_size.saturating_add(TX_BASE_RESULT_SIZE),
+            fees.fee_per_historical_1kb,
+            DATA_SIZE_1KB_INCREMENT,
+        ),
+        transaction_size: fee_per_increment(
+            transaction_size,
+            fees.fee_per_transaction_size_1kb,
+            DATA_SIZE_1KB_INCREMENT,
+        ),
+        events: fee_per_increment(
+            events_size,
+            fees.fee_per_contract_event_1kb,
+            DATA_SIZE_1KB_INCREMENT,
+        ),
+        rent: 0,
+    };
+    breakdown.rent = breakdown
+        .resource_fees()
+        .iter()
+        .fold(transaction_data.resource_fee, |rent, fee| {
+            rent.saturating_sub(*fee)
+        });
+    Ok(breakdown)
+}
+
+// The size of the largest envelope the operation can be submitted in, which
+// the simulation charges the transaction size for. Like the simulation, this
+// assumes the most signatures and the largest preconditions and memo.
+fn max_transaction_size(
+    operation: OperationBody,
+    transaction_data: &SorobanTransactionData,
+) -> Result<u32> {
+    let source = MuxedAccount::MuxedEd25519(MuxedAccountMed25519 {
+        id: 0,
+        ed25519: Uint256([0; 32]),
+    });
+    let bytes64: BytesM<64> = vec![0; 64].try_into()?;
+    let signature = DecoratedSignature {
+        hint: SignatureHint([0; 4]),
+        signature: Signature(bytes64.clone()),
+    };
+    let signer_key = SignerKey::Ed25519SignedPayload(SignerKeyEd25519SignedPayload {
+        ed25519: Uint256([0; 32]),
+        payload: bytes64,
+    });
+    let envelope = TransactionV1Envelope {
+        tx: Transaction {
+            source_account: source.clone(),
+            fee: 0,
+            seq_num: SequenceNumber(0),
+            cond: Preconditions::V2(PreconditionsV2 {
+                time_bounds: Some(TimeBounds {
+                    min_time: TimePoint(0),
+                    max_time: TimePoint(0),
+                }),
+                ledger_bounds: Some(LedgerBounds {
+                    min_ledger: 0,
+                    max_ledger: 0,
+                }),
+                min_seq_num: Some(SequenceNumber(0)),
+                min_seq_age: Duration(0),
+                min_seq_ledger_gap: 0,
+                extra_signers: vec![signer_key.clone(), signer_key].try_into()?,
+            }),
+            memo: Memo::Hash(Hash([0; 32])),
+            operations: vec![Operation {
+                source_account: Some(source),
+                body: operation,
+            }]
+            .try_into()?,
+            // The values of the resources don't change the size of their XDR
+            ext: TransactionExt::V1(transaction_data.clone()),
         },
-        xdr_to_c,
+        signatures: vec![signature; 20].try_into()?,
+    };
+    Ok(u32::try_from(
+        envelope.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len(),
+    )?)
+}
+
+// Mirrors `SimulationAdjustmentFactor::adjust_u32()`, which isn't public
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn adjust_u32(factor: &SimulationAdjustmentFactor, value: u32) -> u32 {
+    if value == 0 {
+        return 0;
+    }
+    value.saturating_add(factor.additive_factor).max(
+        (f64::from(value) * factor.multiplicative_factor)
+            .clamp(0.0, f64::from(u32::MAX))
+            .floor() as u32,
     )
 }
 
-fn ledger_entry_diff_to_c(v: &LedgerEntryDiff) -> CXDRDiff {
-    CXDRDiff {
-        before: option_xdr_to_c(v.state_before.as_ref()),
-        after: option_xdr_to_c(v.state_after.as_ref()),
+// Whether each key can be restored, which tells apart the keys a failed
+// restore is to blame on. Entries larger than the write limit of a transaction
+// can't be restored even though the simulation doesn't fail on them.
+fn restore_statuses(
+    keys_to_restore: &[LedgerKey],
+    context: &PreflightContext,
+) -> Result<Vec<(LedgerKey, u32)>> {
+    let tx_max_write_bytes = tx_max_write_bytes(&context.go_storage)?;
+    keys_to_restore
+        .iter()
+        .map(|key| {
+            let status = match get_fallible_from_go_ledger_storage(&context.go_storage, key)? {
+                None => RESTORE_STATUS_NOT_FOUND,
+                Some((_, Some(live_until_ledger)))
+                    if live_until_ledger >= context.ledger_info.sequence_number =>
+                {
+                    RESTORE_STATUS_ALREADY_LIVE
+                }
+                Some((entry, _)) => {
+                    let entry_xdr_size = entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len();
+                    if entry_xdr_size > tx_max_write_bytes {
+                        RESTORE_STATUS_TOO_LARGE
+                    } else {
+                        RESTORE_STATUS_RESTORABLE
+                    }
+                }
+            };
+            Ok((key.clone(), status))
+        })
+        .collect()
+}
+
+fn tx_max_write_bytes(go_storage: &GoLedgerStorage) -> Result<usize> {
+    let key = LedgerKey::ConfigSetting(LedgerKeyConfigSetting {
+        config_setting_id: ConfigSettingId::ContractLedgerCostV0,
+    });
+    let Some((entry, _)) = get_fallible_from_go_ledger_storage(go_storage, &key)? else {
+        bail!("missing ledger cost config setting");
+    };
+    let LedgerEntryData::ConfigSetting(ConfigSettingEntry::ContractLedgerCostV0(cost)) =
+        &entry.data
+    else {
+        bail!("unexpected ledger cost config setting entry: {entry:?}");
+    };
+    Ok(usize::try_from(cost.tx_max_write_bytes)?)
+}
+
+// An entry whose TTL is extended, or which is restored
+struct TtlChange<'a> {
+    key: &'a LedgerKey,
+    entry: Rc<LedgerEntry>,
+    // The actual live until ledger, which for restored entries differs from
+    // the one rent is charged on
+    old_live_until_ledger: u32,
+    rent_change: LedgerEntryRentChange,
+    restored: bool,
+}
+
+// The TTL changes below mirror the ones the simulation computes internally
+// for its fee, which it doesn't expose per entry.
+fn extend_ttl_changes<'a>(
+    keys_to_extend: &'a [LedgerKey],
+    new_live_until_ledger: u32,
+    snapshot: &impl SnapshotSource,
+    context: &PreflightContext,
+) -> Result<Vec<TtlChange<'a>>> {
+    let mut ttl_changes = Vec::with_capacity(keys_to_extend.len());
+    for key in keys_to_extend {
+        let Some((entry, Some(old_live_until_ledger))) = snapshot.get(&Rc::new(key.clone()))?
+        else {
+            continue;
+        };
+        if new_live_until_ledger <= old_live_until_ledger {
+            continue;
+        }
+        let size = entry_size_for_rent(&entry, context)?;
+        let rent_change = super::rent_change(
+            key,
+            get_key_durability(key) == Some(ContractDataDurability::Persistent),
+            size,
+            size,
+            old_live_until_ledger,
+            new_live_until_ledger,
+        );
+        ttl_changes.push(TtlChange {
+            key,
+            entry,
+            old_live_until_ledger,
+            rent_change,
+            restored: false,
+        });
+    }
+    Ok(ttl_changes)
+}
+
+fn restore_ttl_changes<'a>(
+    keys_to_restore: &'a [LedgerKey],
+    context: &PreflightContext,
+) -> Result<Vec<TtlChange<'a>>> {
+    let Some(new_live_until_ledger) = context
+        .ledger_info
+        .min_live_until_ledger_checked(ContractDataDurability::Persistent)
+    else {
+        bail!("minimum persistent live until ledger overflows");
+    };
+    let mut ttl_changes = Vec::with_capacity(keys_to_restore.len());
+    for key in keys_to_restore {
+        let Some((entry, Some(old_live_until_ledger))) =
+            get_fallible_from_go_ledger_storage(&context.go_storage, key)?
+        else {
+            continue;
+        };
+        if old_live_until_ledger >= context.ledger_info.sequence_number {
+            continue;
+        }
+        // A restored entry is charged rent as if it were newly created
+        let rent_change = super::rent_change(
+            key,
+            true,
+            0,
+            entry_size_for_rent(&entry, context)?,
+            0,
+            new_live_until_ledger,
+        );
+        ttl_changes.push(TtlChange {
+            key,
+            entry,
+            old_live_until_ledger,
+            rent_change,
+            restored: true,
+        });
+    }
+    Ok(ttl_changes)
+}
+
+fn entry_size_for_rent(entry: &LedgerEntry, context: &PreflightContext) -> Result<u32> {
+    let entry_xdr_size = u32::try_from(entry.to_xdr(DEFAULT_XDR_RW_LIMITS)?.len())?;
+    super::entry_size_for_rent(&context.network_config, entry, entry_xdr_size)
+}
+
+// The per-entry rent fees are unadjusted, so they can add up to slightly less
+// than the refundable fee.
+fn rent_changes(
+    ttl_changes: &[TtlChange],
+    context: &PreflightContext,
+) -> Vec<(LedgerKey, u32, u32, u32, i64)> {
+    ttl_changes
+        .iter()
+        .map(|change| {
+            let rent_fee = compute_rent_fee(
+                std::slice::from_ref(&change.rent_change),
+                &context.network_config.rent_fee_configuration,
+                context.ledger_info.sequence_number,
+            );
+            (
+                change.key.clone(),
+                change.rent_change.new_size_bytes,
+                change.old_live_until_ledger,
+                change.rent_change.new_live_until_ledger,
+                rent_fee,
+            )
+        })
+        .collect()
+}
+
+// Extending a TTL updates the TTL entry of the extended entry, whereas
+// restoring an entry makes it (and its TTL entry) live again as if it were
+// created. The last modification ledger of TTL entries isn't known, so the one
+// of the entry they belong to is used for their state before the change.
+fn ttl_changes_to_ledger_entry_diff(
+    ttl_changes: &[TtlChange],
+    context: &PreflightContext,
+) -> Result<Vec<(Option<LedgerEntry>, Option<LedgerEntry>)>> {
+    let ledger_seq = context.ledger_info.sequence_number;
+    let mut diffs = Vec::with_capacity(ttl_changes.len() * 2);
+    for change in ttl_changes {
+        let key_hash = Hash(Sha256::digest(change.key.to_xdr(DEFAULT_XDR_RW_LIMITS)?).into());
+        let ttl_entry = |last_modified_ledger_seq, live_until_ledger_seq| LedgerEntry {
+            last_modified_ledger_seq,
+            data: LedgerEntryData::Ttl(TtlEntry {
+                key_hash: key_hash.clone(),
+                live_until_ledger_seq,
+            }),
+            ext: LedgerEntryExt::V0,
+        };
+        let new_ttl_entry = ttl_entry(ledger_seq, change.rent_change.new_live_until_ledger);
+        if change.restored {
+            let restored_entry = LedgerEntry {
+                last_modified_ledger_seq: ledger_seq,
+                ..change.entry.as_ref().clone()
+            };
+            diffs.push((None, Some(restored_entry)));
+            diffs.push((None, Some(new_ttl_entry)));
+        } else {
+            let old_ttl_entry = ttl_entry(
+                change.entry.last_modified_ledger_seq,
+                change.old_live_until_ledger,
+            );
+            diffs.push((Some(old_ttl_entry), Some(new_ttl_entry)));
+        }
     }
+    Ok(diffs)
+}
+
+// The XDR traits differ between the two soroban versions, so these can't be
+// blanket `CReprOf` implementations in the shared grandparent module.
+fn xdr_to_bytes(v: &impl WriteXdr) -> Vec<u8> {
+    v.to_xdr(DEFAULT_XDR_RW_LIMITS).unwrap()
+}
+
+fn option_xdr_to_c(v: Option<&impl WriteXdr>) -> CXDR {
+    CXDR::c_repr_of(v.map(xdr_to_bytes))
 }
 
 fn xdr_vec_to_c(v: &[impl WriteXdr]) -> CXDRVector {
-    let c_v = v.iter().map(xdr_to_c).collect();
-    let (array, len) = vec_to_c_array(c_v);
-    CXDRVector { array, len }
+    CXDRVector::c_repr_of(v.iter().map(xdr_to_bytes).collect::<Vec<_>>())
 }
 
-fn ledger_entry_diff_vec_to_c(modified_entries: &[LedgerEntryDiff]) -> CXDRDiffVector {
-    let c_diffs = modified_entries
+fn ledger_entry_diff_vec_to_c(
+    ledger_entry_diff: &[(Option<LedgerEntry>, Option<LedgerEntry>)],
+) -> CXDRDiffVector {
+    let diffs: Vec<_> = ledger_entry_diff
         .iter()
-        .map(ledger_entry_diff_to_c)
+        .map(|(before, after)| {
+            (
+                before.as_ref().map(xdr_to_bytes),
+                after.as_ref().map(xdr_to_bytes),
+            )
+        })
         .collect();
-    let (array, len) = vec_to_c_array(c_diffs);
-    CXDRDiffVector { array, len }
+    CXDRDiffVector::c_repr_of(diffs)
 }
 
 impl From<u32> for AuthMode {
@@ -317,6 +2476,8 @@ impl From<u32> for AuthMode {
             0 => AuthMode::Enforce,
             1 => AuthMode::Record,
             2 => AuthMode::RecordAllowNonroot,
+            3 => AuthMode::Auto,
+            4 => AuthMode::EnforceMockSignatures,
             _ => panic!("invalid AuthMode value"),
         }
     }
@@ -336,12 +2497,19 @@ pub(crate) fn get_fallible_from_go_ledger_storage(
     Option<super::soroban_env_host::storage::EntryWithLiveUntil>,
     super::soroban_env_host::HostError,
 > {
+    if let Err(e) = storage.check_cancelled() {
+        // Same error handling as below, halting the host right away
+        if let Ok(mut err) = storage.internal_error.try_lock() {
+            *err = Some(e);
+        }
+        return Err((ScErrorType::Storage, ScErrorCode::InternalError).into());
+    }
     let mut key_xdr = match key.to_xdr(DEFAULT_XDR_RW_LIMITS) {
         Ok(res) => res,
         Err(e) => {
             // Store the internal error in the storage as the info won't
             // be propagated from simulation.
-            if let Ok(mut err) = storage.internal_error.try_borrow_mut() {
+            if let Ok(mut err) = storage.internal_error.try_lock() {
                 *err = Some(e.into());
             }
             // Errors that occur in storage are not recoverable, so we
@@ -358,7 +2526,7 @@ pub(crate) fn get_fallible_from_go_ledger_storage(
         Ok(res) => res,
         Err(e) => {
             // Same error handling as above
-            if let Ok(mut err) = storage.internal_error.try_borrow_mut() {
+            if let Ok(mut err) = storage.internal_error.try_lock() {
                 *err = Some(e.into());
             }
             return Err((ScErrorType::Storage, ScErrorCode::InternalError).into());
@@ -367,3 +2535,270 @@ pub(crate) fn get_fallible_from_go_ledger_storage(
 
     Ok(Some((Rc::new(entry), live_until_ledger_seq)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::soroban_env_host::xdr::{
+        InvokeContractArgs, PublicKey, SorobanAddressCredentials, SorobanAuthorizedInvocation,
+    };
+    use super::*;
+
+    const LEDGER_SEQ: u32 = 100;
+
+    #[allow(clippy::useless_conversion)]
+    fn contract_data_entry(durability: ContractDataDurability) -> LedgerEntry {
+        LedgerEntry {
+            last_modified_ledger_seq: 0,
+            data: LedgerEntryData::ContractData(ContractDataEntry {
+                ext: ExtensionPoint::V0,
+                contract: ScAddress::Contract(Hash([1; 32]).into()),
+                key: ScVal::U32(1),
+                durability,
+                val: ScVal::U32(2),
+            }),
+            ext: LedgerEntryExt::V0,
+        }
+    }
+
+    fn contract_data_key(durability: ContractDataDurability) -> LedgerKey {
+        ledger_entry_to_ledger_key(&contract_data_entry(durability)).unwrap()
+    }
+
+    // A context reading the given entries (and their live until ledgers) from
+    // memory, with a network configuration charging no fees
+    fn test_context(entries: &[(LedgerEntry, Option<u32>)]) -> PreflightContext {
+        let entries = entries
+            .iter()
+            .map(|(entry, live_until_ledger)| {
+                let key = ledger_entry_to_ledger_key(entry).unwrap();
+                (
+                    xdr_to_bytes(&key),
+                    Some((xdr_to_bytes(entry), *live_until_ledger)),
+                )
+            })
+            .collect();
+        let network_config = NetworkConfig {
+            tx_max_instructions: 100_000_000,
+            tx_memory_limit: 40 * 1024 * 1024,
+            min_temp_entry_ttl: 16,
+            min_persistent_entry_ttl: 4096,
+            max_entry_ttl: 3_110_400,
+            ..Default::default()
+        };
+        let ledger_info = fill_ledger_info(
+            &PreflightLedgerInfo {
+                protocol_version: super::super::PROTOCOL,
+                sequence_number: LEDGER_SEQ,
+                timestamp: 0,
+                network_passphrase: "Test SDF Network ; September 2015".to_string(),
+                base_reserve: 0,
+                bucket_list_size: 0,
+                sequence_number_override: None,
+                timestamp_override: None,
+            },
+            &network_config,
+        );
+        PreflightContext {
+            go_storage: GoLedgerStorage::with_entries(entries, LEDGER_SEQ),
+            tx_max_instructions: network_config.tx_max_instructions,
+            tx_memory_limit: network_config.tx_memory_limit,
+            network_config: Arc::new(network_config),
+            ledger_info,
+            spans: SpanRecorder::default(),
+        }
+    }
+
+    #[test]
+    fn validate_soroban_keys_rejects_keys_without_ttl() {
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([0; 32]))),
+        });
+        assert!(validate_soroban_keys(&[key], false).is_err());
+    }
+
+    #[test]
+    fn validate_soroban_keys_rejects_temporary_entries_to_restore() {
+        let keys = [contract_data_key(ContractDataDurability::Temporary)];
+        assert!(validate_soroban_keys(&keys, false).is_ok());
+        assert!(validate_soroban_keys(&keys, true).is_err());
+    }
+
+    #[test]
+    fn validate_soroban_keys_rejects_duplicates() {
+        let key = contract_data_key(ContractDataDurability::Persistent);
+        assert!(validate_soroban_keys(&[key.clone()], true).is_ok());
+        assert!(validate_soroban_keys(&[key.clone(), key], true).is_err());
+        assert!(validate_soroban_keys(&[], true).is_err());
+    }
+
+    #[test]
+    fn extend_ttl_rejects_invalid_extend_to() {
+        let entry = contract_data_entry(ContractDataDurability::Persistent);
+        let context = test_context(&[(entry.clone(), Some(LEDGER_SEQ + 10))]);
+        let keys = [ledger_entry_to_ledger_key(&entry).unwrap()];
+        for extend_to in [0, u32::MAX] {
+            let Err(e) = preflight_extend_ttl_op(&context, &keys, extend_to) else {
+                panic!("extending the TTL to {extend_to} ledgers succeeded");
+            };
+            assert!(e.downcast_ref::<InvalidInput>().is_some(), "{e:?}");
+        }
+    }
+
+    #[test]
+    fn extend_ttl_reports_the_new_live_until_ledger() {
+        let entry = contract_data_entry(ContractDataDurability::Persistent);
+        let key = ledger_entry_to_ledger_key(&entry).unwrap();
+        let context = test_context(&[(entry, Some(LEDGER_SEQ + 10))]);
+        let result = preflight_extend_ttl_op(&context, &[key.clone()], 1000).unwrap();
+        assert_eq!(result.error, "");
+        assert!(result.transaction_data.is_some());
+
+        let [(rent_key, _, old_live_until_ledger, new_live_until_ledger, _)] =
+            result.rent_changes.as_slice()
+        else {
+            panic!("expected a single rent change: {:?}", result.rent_changes);
+        };
+        assert_eq!(rent_key, &key);
+        assert_eq!(*old_live_until_ledger, LEDGER_SEQ + 10);
+        assert_eq!(*new_live_until_ledger, LEDGER_SEQ + 1000);
+
+        let [(Some(before), Some(after))] = result.ledger_entry_diff.as_slice() else {
+            panic!(
+                "expected a single TTL change: {:?}",
+                result.ledger_entry_diff
+            );
+        };
+        let key_hash = Hash(Sha256::digest(xdr_to_bytes(&key)).into());
+        for (entry, live_until_ledger_seq) in
+            [(before, LEDGER_SEQ + 10), (after, LEDGER_SEQ + 1000)]
+        {
+            let LedgerEntryData::Ttl(ttl) = &entry.data else {
+                panic!("expected a TTL entry: {entry:?}");
+            };
+            assert_eq!(ttl.key_hash, key_hash);
+            assert_eq!(ttl.live_until_ledger_seq, live_until_ledger_seq);
+        }
+    }
+
+    #[test]
+    fn extend_ttl_skips_entries_living_long_enough() {
+        let entry = contract_data_entry(ContractDataDurability::Temporary);
+        let key = ledger_entry_to_ledger_key(&entry).unwrap();
+        let context = test_context(&[(entry, Some(LEDGER_SEQ + 2000))]);
+        let result = preflight_extend_ttl_op(&context, &[key], 1000).unwrap();
+        assert!(result.rent_changes.is_empty());
+        assert!(result.ledger_entry_diff.is_empty());
+    }
+
+    #[test]
+    fn storage_errors_are_kept_per_handle() {
+        let context = test_context(&[]);
+        let go_storage = context.go_storage.clone();
+        *go_storage.internal_error.lock().unwrap() = Some(anyhow!("storage failure"));
+        assert!(context.go_storage.internal_error.lock().unwrap().is_none());
+        assert!(go_storage.clone().internal_error.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn batched_operations_are_all_simulated() {
+        let context = test_context(&[]);
+        let source_account = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([0; 32])));
+        let invoke_hf_ops = (0..9)
+            .map(|_| {
+                let invoke_hf_op = InvokeHostFunctionOp {
+                    host_function: HostFunction::InvokeContract(contract_fn()),
+                    auth: VecM::default(),
+                };
+                (invoke_hf_op, source_account.clone())
+            })
+            .collect();
+        let results = preflight_invoke_hf_ops(
+            context,
+            invoke_hf_ops,
+            &CResourceConfig::default(),
+            false,
+            AuthMode::Record,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 9);
+        // The invoked contract doesn't exist
+        for result in results {
+            assert!(result.unwrap().error.contains("MissingValue"));
+        }
+    }
+
+    #[test]
+    fn adjust_u32_applies_the_larger_adjustment() {
+        let factor = SimulationAdjustmentFactor::new(1.5, 100);
+        assert_eq!(adjust_u32(&factor, 0), 0);
+        assert_eq!(adjust_u32(&factor, 10), 110);
+        assert_eq!(adjust_u32(&factor, 1000), 1500);
+        assert_eq!(adjust_u32(&factor, u32::MAX), u32::MAX);
+    }
+
+    #[allow(clippy::useless_conversion)]
+    fn contract_fn() -> InvokeContractArgs {
+        InvokeContractArgs {
+            contract_address: ScAddress::Contract(Hash([1; 32]).into()),
+            function_name: "transfer".try_into().unwrap(),
+            args: VecM::default(),
+        }
+    }
+
+    fn address_auth_entry(address: ScAddress, nonce: i64) -> SorobanAuthorizationEntry {
+        SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+                address,
+                nonce,
+                signature_expiration_ledger: LEDGER_SEQ + 100,
+                signature: ScVal::Void,
+            }),
+            root_invocation: SorobanAuthorizedInvocation {
+                function: SorobanAuthorizedFunction::ContractFn(contract_fn()),
+                sub_invocations: VecM::default(),
+            },
+        }
+    }
+
+    #[test]
+    #[allow(clippy::useless_conversion)]
+    fn mock_signature_auth_consumes_the_supplied_nonces() {
+        let context = test_context(&[]);
+        let address = ScAddress::Contract(Hash([2; 32]).into());
+        let recorded_auth = vec![address_auth_entry(address.clone(), 1)];
+        let auth = vec![address_auth_entry(address, 2)];
+        let (recorded_key, recorded_entry) = nonce_entries(&recorded_auth).remove(0);
+        let (supplied_key, supplied_entry) = nonce_entries(&auth).remove(0);
+        // No resources, whose fields differ between the protocols
+        let mut transaction_data =
+            SorobanTransactionData::from_xdr([0; 32], DEFAULT_XDR_RW_LIMITS).unwrap();
+        transaction_data.resources.write_bytes = 100;
+        transaction_data.resources.footprint.read_write =
+            vec![recorded_key.clone()].try_into().unwrap();
+        let mut result = PreflightResult {
+            auth,
+            transaction_data: Some(transaction_data),
+            ledger_entry_diff: vec![(None, Some(recorded_entry))],
+            footprint_bytes: vec![(recorded_key, 0, 0)],
+            ..Default::default()
+        };
+        replace_nonces(
+            &context,
+            &SimulationAdjustmentConfig::no_adjustments(),
+            &mut result,
+            HostFunction::InvokeContract(contract_fn()),
+            &recorded_auth,
+        )
+        .unwrap();
+        let transaction_data = result.transaction_data.unwrap();
+        assert_eq!(
+            transaction_data.resources.footprint.read_write.to_vec(),
+            vec![supplied_key.clone()]
+        );
+        assert_eq!(result.ledger_entry_diff, vec![(None, Some(supplied_entry))]);
+        assert_eq!(result.footprint_bytes.len(), 1);
+        assert_eq!(result.footprint_bytes[0].0, supplied_key);
+        // The nonces are the same size
+        assert_eq!(transaction_data.resources.write_bytes, 100);
+    }
+}
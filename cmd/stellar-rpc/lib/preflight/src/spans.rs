@@ -0,0 +1,42 @@
+// Timings of the phases of a preflight (loading the network configuration,
+// prefetching the snapshot entries, executing the host function, ...), which
+// are handed over to Go along with its result so that they show up as spans of
+// the traces of the server.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant, SystemTime};
+
+pub(crate) const SPAN_CONFIG_LOAD: &str = "preflight.config_load";
+pub(crate) const SPAN_SNAPSHOT_PREFETCH: &str = "preflight.snapshot_prefetch";
+pub(crate) const SPAN_EXECUTION: &str = "preflight.execution";
+pub(crate) const SPAN_RESTORE_SIMULATION: &str = "preflight.restore_simulation";
+pub(crate) const SPAN_FOOTPRINT_MINIMIZATION: &str = "preflight.footprint_minimization";
+
+pub(crate) struct Span {
+    pub(crate) name: &'static str,
+    pub(crate) start: SystemTime,
+    pub(crate) duration: Duration,
+}
+
+#[derive(Default)]
+pub(crate) struct SpanRecorder(RefCell<Vec<Span>>);
+
+impl SpanRecorder {
+    // Runs `op`, recording how long it took under the given name.
+    pub(crate) fn time<T>(&self, name: &'static str, op: impl FnOnce() -> T) -> T {
+        let start = SystemTime::now();
+        let instant = Instant::now();
+        let result = op();
+        self.0.borrow_mut().push(Span {
+            name,
+            start,
+            duration: instant.elapsed(),
+        });
+        result
+    }
+
+    // Returns the spans recorded so far, which aren't returned again.
+    pub(crate) fn take(&self) -> Vec<Span> {
+        self.0.take()
+    }
+}
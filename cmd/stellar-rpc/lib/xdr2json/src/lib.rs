@@ -63,13 +63,13 @@ pub unsafe extern "C" fn xdr_to_json(
     xdr: CXDR,
 ) -> *mut ConversionResult {
     let result = catch_json_to_xdr_panic(Box::new(move || {
-        let type_str = unsafe { from_c_string(typename) };
+        let type_str: String = unsafe { typename.cast_const().as_rust() };
         let the_type = match xdr::TypeVariant::from_str(&type_str) {
             Ok(t) => t,
             Err(e) => panic!("couldn't match type {type_str}: {e}"),
         };
 
-        let xdr_bytearray = unsafe { from_c_xdr(xdr) };
+        let xdr_bytearray: Vec<u8> = unsafe { xdr.as_rust() };
         let mut buffer = xdr::Limited::new(xdr_bytearray.as_slice(), DEFAULT_XDR_RW_LIMITS.clone());
 
         let t = match xdr::Type::read_xdr_to_end(the_type, &mut buffer) {
@@ -85,8 +85,8 @@ pub unsafe extern "C" fn xdr_to_json(
 
     // Caller is responsible for calling free_conversion_result.
     Box::into_raw(Box::new(ConversionResult {
-        json: string_to_c(result.json),
-        error: string_to_c(result.error),
+        json: CReprOf::c_repr_of(result.json),
+        error: CReprOf::c_repr_of(result.error),
     }))
 }
 
@@ -102,8 +102,8 @@ pub unsafe extern "C" fn free_conversion_result(ptr: *mut ConversionResult) {
     }
 
     unsafe {
-        free_c_string((*ptr).json);
-        free_c_string((*ptr).error);
+        (*ptr).json.drop_c();
+        (*ptr).error.drop_c();
         drop(Box::from_raw(ptr));
     }
 }
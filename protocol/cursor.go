@@ -1,7 +1,10 @@
 package protocol
 
 import (
+	"encoding/base64"
+	"encoding/binary"
 	"encoding/json"
+	"errors"
 	"fmt"
 	"math"
 	"strconv"
@@ -12,8 +15,21 @@ import (
 
 const (
 	numCursorParts = 2
+	// CursorVersion is the version of the encoding of the cursors returned by
+	// Cursor.Encode(), which is bumped whenever the encoding changes.
+	CursorVersion = 1
+	// CursorTooOldErrorCode is the JSON-RPC error code of the requests whose
+	// cursor precedes the retention window, which can't be resumed from.
+	CursorTooOldErrorCode = -32010
+
+	cursorVersionPrefix = "v"
+	cursorEncodedSize   = 16
 )
 
+// ErrUnsupportedCursorVersion is returned when parsing a cursor encoded with
+// an unknown version (e.g. by a newer server).
+var ErrUnsupportedCursorVersion = errors.New("unsupported cursor version")
+
 // Cursor represents the position of a Soroban event.
 // Soroban events are sorted in ascending order by
 // ledger sequence, transaction index, operation index,
@@ -47,6 +63,42 @@ func (c Cursor) String() string {
 	)
 }
 
+// Encode returns the opaque, versioned representation of this cursor (e.g.
+// "v1.AAAAewAAAAoAAAAFAAAAAQ"), which clients should pass back as is.
+//
+// Cursors only depend on the position of events within the ledgers, so they
+// remain valid across restarts, re-ingestion and upgrades (as long as their
+// ledger is retained), and ParseCursor keeps accepting the encodings of
+// previous versions.
+func (c Cursor) Encode() string {
+	b := make([]byte, 0, cursorEncodedSize)
+	b = binary.BigEndian.AppendUint32(b, c.Ledger)
+	b = binary.BigEndian.AppendUint32(b, c.Tx)
+	b = binary.BigEndian.AppendUint32(b, c.Op)
+	b = binary.BigEndian.AppendUint32(b, c.Event)
+	return fmt.Sprintf("%s%d.%s", cursorVersionPrefix, CursorVersion, base64.RawURLEncoding.EncodeToString(b))
+}
+
+func parseEncodedCursor(input string) (Cursor, error) {
+	version, encoded, ok := strings.Cut(strings.TrimPrefix(input, cursorVersionPrefix), ".")
+	if !ok {
+		return Cursor{}, fmt.Errorf("invalid cursor %s", input)
+	}
+	if version != strconv.Itoa(CursorVersion) {
+		return Cursor{}, fmt.Errorf("%w: %s", ErrUnsupportedCursorVersion, input)
+	}
+	b, err := base64.RawURLEncoding.DecodeString(encoded)
+	if err != nil || len(b) != cursorEncodedSize {
+		return Cursor{}, fmt.Errorf("invalid cursor %s", input)
+	}
+	return Cursor{
+		Ledger: binary.BigEndian.Uint32(b[0:4]),
+		Tx:     binary.BigEndian.Uint32(b[4:8]),
+		Op:     binary.BigEndian.Uint32(b[8:12]),
+		Event:  binary.BigEndian.Uint32(b[12:16]),
+	}, nil
+}
+
 // MarshalJSON marshals the cursor into JSON
 func (c Cursor) MarshalJSON() ([]byte, error) {
 	return json.Marshal(c.String())
@@ -67,8 +119,13 @@ func (c *Cursor) UnmarshalJSON(b []byte) error {
 	return nil
 }
 
-// ParseCursor parses the given string and returns the corresponding cursor
+// ParseCursor parses the given string and returns the corresponding cursor.
+// It accepts both the encoded cursors (see Cursor.Encode()) and the event ids
+// (see Cursor.String()).
 func ParseCursor(input string) (Cursor, error) {
+	if strings.HasPrefix(input, cursorVersionPrefix) {
+		return parseEncodedCursor(input)
+	}
 	parts := strings.SplitN(input, "-", numCursorParts)
 	if len(parts) != numCursorParts {
 		return Cursor{}, fmt.Errorf("invalid event id %s", input)
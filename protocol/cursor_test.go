@@ -33,9 +33,25 @@ func TestParseCursor(t *testing.T) {
 		parsed, err := ParseCursor(cursor.String())
 		require.NoError(t, err)
 		assert.Equal(t, cursor, parsed)
+
+		parsed, err = ParseCursor(cursor.Encode())
+		require.NoError(t, err)
+		assert.Equal(t, cursor, parsed)
 	}
 }
 
+func TestEncodedCursor(t *testing.T) {
+	cursor := Cursor{Ledger: 123, Tx: 10, Op: 5, Event: 1}
+	assert.Equal(t, "v1.AAAAewAAAAoAAAAFAAAAAQ", cursor.Encode())
+
+	_, err := ParseCursor("v2.AAAAewAAAAoAAAAFAAAAAQ")
+	require.ErrorIs(t, err, ErrUnsupportedCursorVersion)
+	_, err = ParseCursor("v1.AAAAewAAAAoAAAAF")
+	require.EqualError(t, err, "invalid cursor v1.AAAAewAAAAoAAAAF")
+	_, err = ParseCursor("v1")
+	require.Error(t, err)
+}
+
 func TestCursorJSON(t *testing.T) {
 	type options struct {
 		Cursor *Cursor `json:"cursor,omitempty"`
@@ -0,0 +1,58 @@
+package protocol
+
+const DiagnoseTransactionMethodName = "diagnoseTransaction"
+
+type DiagnoseTransactionRequest struct {
+	Hash string `json:"hash"`
+}
+
+// DiagnosedResource compares a resource declared by a transaction with the
+// amount its simulation requires and the amount it actually used.
+type DiagnosedResource struct {
+	Name      string `json:"name"` // instructions, diskReadBytes, writeBytes or resourceFee
+	Declared  int64  `json:"declared,string"`
+	Simulated int64  `json:"simulated,string"`
+	// Used is missing if the ledger didn't record it, see
+	// DiagnoseTransactionResponse. The used disk read bytes are all the ledger
+	// bytes read by the transaction.
+	Used *int64 `json:"used,omitempty,string"`
+}
+
+// DiagnoseTransactionResponse compares an executed Soroban transaction with
+// its simulation against the state right before it was applied, e.g. to find
+// out why a transaction which worked in simulation failed on chain.
+//
+// That state is rebuilt from the meta of the stored ledgers: the changes made
+// since the transaction (including the ones of the transactions applied after
+// it in its own ledger) are rolled back, while the fees charged in its ledger
+// and the changes of the transactions applied before it are kept. The ledger
+// of the transaction must still be stored, and at most
+// --max-ledger-entries-rollback-ledgers before the latest one. Temporary
+// entries evicted since then can't be recovered. The ledger only records the
+// resources used by a transaction (besides the fee charged) when Stellar Core
+// emits diagnostic events.
+type DiagnoseTransactionResponse struct {
+	LatestLedger uint32 `json:"latestLedger"`
+	// The ledger the transaction was included in
+	Ledger uint32 `json:"ledger"`
+	Status string `json:"status"` // TransactionStatusSuccess or TransactionStatusFailed
+
+	// The resource fee charged to the transaction
+	ResourceFeeCharged int64 `json:"resourceFeeCharged,string"`
+
+	// The simulation of the transaction against the state at the end of the
+	// previous ledger. The fields below are missing if it failed.
+	Simulation SimulateTransactionResponse `json:"simulation"`
+
+	Resources []DiagnosedResource `json:"resources,omitempty"`
+	// If present, the declared resources and footprint keys falling short of
+	// what the simulation requires, which likely made the transaction fail.
+	Shortfalls []string `json:"shortfalls,omitempty"`
+	// If present, the resources the transaction used more of than its
+	// simulation requires, e.g. because of the transactions applied before it
+	// in its ledger.
+	Underestimates []string `json:"underestimates,omitempty"`
+	// If present, the keys of the declared footprint (LedgerKey XDR in base64)
+	// which the simulation doesn't need.
+	UnusedFootprintKeys []string `json:"unusedFootprintKeys,omitempty"`
+}
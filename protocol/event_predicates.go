@@ -0,0 +1,195 @@
+package protocol
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"math/big"
+
+	"github.com/stellar/go/xdr"
+)
+
+// MaxPredicatesLimit is the maximum number of predicates of an event filter
+const MaxPredicatesLimit = 5
+
+// The operators of event predicates
+const (
+	PredicateOperatorEq  = "eq"
+	PredicateOperatorNe  = "ne"
+	PredicateOperatorGt  = "gt"
+	PredicateOperatorGte = "gte"
+	PredicateOperatorLt  = "lt"
+	PredicateOperatorLte = "lte"
+)
+
+// EventPredicate compares a topic segment or the value of the events with
+// Value. Integers (of any size) are compared by their numeric value, so that
+// the ordering operators only apply to them, other values can only be equal
+// (or not) to Value.
+//
+// For instance, {"field": "amount", "op": "gte", "value": <i128 10^7>} matches
+// the token transfers of at least 1 unit of a 7-decimal asset, whose value is
+// either the amount or a map of it along with the muxed ID of the recipient.
+type EventPredicate struct {
+	// Topic is the (1-based) position of the compared topic segment, the
+	// value of the events is compared if 0.
+	Topic uint `json:"topic,omitempty"`
+	// Field is the symbol key of the compared entry, when the topic segment
+	// (or the value) is a map. Other values are compared as a whole.
+	Field    string    `json:"field,omitempty"`
+	Operator string    `json:"op"`
+	Value    xdr.ScVal `json:"-"`
+}
+
+type eventPredicateJSON struct {
+	Topic    uint   `json:"topic,omitempty"`
+	Field    string `json:"field,omitempty"`
+	Operator string `json:"op"`
+	// Value is a base64-encoded ScVal
+	Value string `json:"value"`
+}
+
+func (p *EventPredicate) UnmarshalJSON(data []byte) error {
+	var raw eventPredicateJSON
+	if err := json.Unmarshal(data, &raw); err != nil {
+		return err
+	}
+	var value xdr.ScVal
+	if err := xdr.SafeUnmarshalBase64(raw.Value, &value); err != nil {
+		return fmt.Errorf("invalid predicate value: %w", err)
+	}
+	*p = EventPredicate{Topic: raw.Topic, Field: raw.Field, Operator: raw.Operator, Value: value}
+	return nil
+}
+
+func (p EventPredicate) MarshalJSON() ([]byte, error) {
+	value, err := xdr.MarshalBase64(p.Value)
+	if err != nil {
+		return nil, err
+	}
+	return json.Marshal(eventPredicateJSON{Topic: p.Topic, Field: p.Field, Operator: p.Operator, Value: value})
+}
+
+func (p *EventPredicate) Valid() error {
+	if p.Topic > MaxTopicCount {
+		return fmt.Errorf("topic must be at most %d", MaxTopicCount)
+	}
+	switch p.Operator {
+	case PredicateOperatorEq, PredicateOperatorNe:
+		return nil
+	case PredicateOperatorGt, PredicateOperatorGte, PredicateOperatorLt, PredicateOperatorLte:
+		if _, ok := scValInteger(p.Value); !ok {
+			return fmt.Errorf("value must be an integer with the %s operator", p.Operator)
+		}
+		return nil
+	default:
+		return errors.New("op must be one of eq, ne, gt, gte, lt and lte")
+	}
+}
+
+// Matches returns true if the event has the compared topic segment (or map
+// entry) and it satisfies the predicate.
+func (p *EventPredicate) Matches(event xdr.ContractEvent) bool {
+	v0, ok := event.Body.GetV0()
+	if !ok {
+		return false
+	}
+	compared := v0.Data
+	if p.Topic > 0 {
+		if uint(len(v0.Topics)) < p.Topic {
+			return false
+		}
+		compared = v0.Topics[p.Topic-1]
+	}
+	if p.Field != "" {
+		if compared, ok = scMapField(compared, p.Field); !ok {
+			return false
+		}
+	}
+
+	value, valueIsInteger := scValInteger(compared)
+	operand, operandIsInteger := scValInteger(p.Value)
+	if !valueIsInteger || !operandIsInteger {
+		equal := compared.Equals(p.Value)
+		return (p.Operator == PredicateOperatorEq && equal) || (p.Operator == PredicateOperatorNe && !equal)
+	}
+	return predicateHolds(p.Operator, value.Cmp(operand))
+}
+
+// predicateHolds tells whether the result of the comparison of the compared
+// value with the value of a predicate satisfies its operator.
+func predicateHolds(operator string, cmp int) bool {
+	switch operator {
+	case PredicateOperatorEq:
+		return cmp == 0
+	case PredicateOperatorNe:
+		return cmp != 0
+	case PredicateOperatorGt:
+		return cmp > 0
+	case PredicateOperatorGte:
+		return cmp >= 0
+	case PredicateOperatorLt:
+		return cmp < 0
+	case PredicateOperatorLte:
+		return cmp <= 0
+	default:
+		return false
+	}
+}
+
+// scMapField returns the value of the entry of a map keyed by the given
+// symbol, or the value itself if it isn't a map.
+func scMapField(value xdr.ScVal, field string) (xdr.ScVal, bool) {
+	scMap, ok := value.GetMap()
+	if !ok {
+		return value, true
+	}
+	if scMap == nil {
+		return xdr.ScVal{}, false
+	}
+	for _, entry := range *scMap {
+		if sym, ok := entry.Key.GetSym(); ok && string(sym) == field {
+			return entry.Val, true
+		}
+	}
+	return xdr.ScVal{}, false
+}
+
+// scValInteger returns the numeric value of the integer ScVals.
+func scValInteger(value xdr.ScVal) (*big.Int, bool) {
+	switch value.Type {
+	case xdr.ScValTypeScvU32:
+		return new(big.Int).SetUint64(uint64(*value.U32)), true
+	case xdr.ScValTypeScvI32:
+		return big.NewInt(int64(*value.I32)), true
+	case xdr.ScValTypeScvU64:
+		return new(big.Int).SetUint64(uint64(*value.U64)), true
+	case xdr.ScValTypeScvI64:
+		return big.NewInt(int64(*value.I64)), true
+	case xdr.ScValTypeScvU128:
+		return joinIntegerParts(new(big.Int).SetUint64(uint64(value.U128.Hi)), uint64(value.U128.Lo)), true
+	case xdr.ScValTypeScvI128:
+		return joinIntegerParts(big.NewInt(int64(value.I128.Hi)), uint64(value.I128.Lo)), true
+	case xdr.ScValTypeScvU256:
+		parts := value.U256
+		return joinIntegerParts(new(big.Int).SetUint64(uint64(parts.HiHi)),
+			uint64(parts.HiLo), uint64(parts.LoHi), uint64(parts.LoLo)), true
+	case xdr.ScValTypeScvI256:
+		parts := value.I256
+		return joinIntegerParts(big.NewInt(int64(parts.HiHi)),
+			uint64(parts.HiLo), uint64(parts.LoHi), uint64(parts.LoLo)), true
+	default:
+		return nil, false
+	}
+}
+
+// joinIntegerParts returns the integer made of its (signed) most significant
+// part followed by its 64-bit lower parts.
+func joinIntegerParts(high *big.Int, lower ...uint64) *big.Int {
+	result := high
+	for _, part := range lower {
+		result.Lsh(result, 64)
+		result.Add(result, new(big.Int).SetUint64(part))
+	}
+	return result
+}
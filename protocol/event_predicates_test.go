@@ -0,0 +1,135 @@
+package protocol
+
+import (
+	"encoding/json"
+	"math"
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+)
+
+func i128ScVal(hi int64, lo uint64) xdr.ScVal {
+	return xdr.ScVal{Type: xdr.ScValTypeScvI128, I128: &xdr.Int128Parts{Hi: xdr.Int64(hi), Lo: xdr.Uint64(lo)}}
+}
+
+func TestEventPredicateMatches(t *testing.T) {
+	transfer := xdr.ScSymbol("transfer")
+	amountKey := xdr.ScSymbol("amount")
+	muxedIDKey := xdr.ScSymbol("to_muxed_id")
+	muxedID := xdr.Uint64(1)
+	amount := i128ScVal(0, 20_000_000)
+	scMap := &xdr.ScMap{
+		{Key: xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &amountKey}, Val: amount},
+		{Key: xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &muxedIDKey}, Val: xdr.ScVal{
+			Type: xdr.ScValTypeScvU64, U64: &muxedID,
+		}},
+	}
+	transferEvent := func(data xdr.ScVal) xdr.ContractEvent {
+		return xdr.ContractEvent{
+			Type: xdr.ContractEventTypeContract,
+			Body: xdr.ContractEventBody{V: 0, V0: &xdr.ContractEventV0{
+				Topics: []xdr.ScVal{{Type: xdr.ScValTypeScvSymbol, Sym: &transfer}},
+				Data:   data,
+			}},
+		}
+	}
+	u32 := xdr.Uint32(10_000_000)
+	u32ScVal := xdr.ScVal{Type: xdr.ScValTypeScvU32, U32: &u32}
+
+	for _, tc := range []struct {
+		name      string
+		predicate EventPredicate
+		event     xdr.ContractEvent
+		expected  bool
+	}{
+		{
+			name:      "value gte",
+			predicate: EventPredicate{Operator: PredicateOperatorGte, Value: u32ScVal},
+			event:     transferEvent(amount),
+			expected:  true,
+		},
+		{
+			name:      "value lt",
+			predicate: EventPredicate{Operator: PredicateOperatorLt, Value: u32ScVal},
+			event:     transferEvent(amount),
+			expected:  false,
+		},
+		{
+			name:      "negative value",
+			predicate: EventPredicate{Operator: PredicateOperatorLt, Value: u32ScVal},
+			event:     transferEvent(i128ScVal(-1, math.MaxUint64)),
+			expected:  true,
+		},
+		{
+			name:      "map field",
+			predicate: EventPredicate{Field: "amount", Operator: PredicateOperatorGt, Value: u32ScVal},
+			event:     transferEvent(xdr.ScVal{Type: xdr.ScValTypeScvMap, Map: &scMap}),
+			expected:  true,
+		},
+		{
+			name:      "missing map field",
+			predicate: EventPredicate{Field: "fee", Operator: PredicateOperatorGt, Value: u32ScVal},
+			event:     transferEvent(xdr.ScVal{Type: xdr.ScValTypeScvMap, Map: &scMap}),
+			expected:  false,
+		},
+		{
+			name:      "ordering of a non-integer",
+			predicate: EventPredicate{Topic: 1, Operator: PredicateOperatorGt, Value: u32ScVal},
+			event:     transferEvent(amount),
+			expected:  false,
+		},
+		{
+			name: "topic eq",
+			predicate: EventPredicate{
+				Topic: 1, Operator: PredicateOperatorEq, Value: xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &transfer},
+			},
+			event:    transferEvent(amount),
+			expected: true,
+		},
+		{
+			name:      "topic ne",
+			predicate: EventPredicate{Topic: 1, Operator: PredicateOperatorNe, Value: amount},
+			event:     transferEvent(amount),
+			expected:  true,
+		},
+		{
+			name:      "missing topic",
+			predicate: EventPredicate{Topic: 2, Operator: PredicateOperatorNe, Value: amount},
+			event:     transferEvent(amount),
+			expected:  false,
+		},
+	} {
+		t.Run(tc.name, func(t *testing.T) {
+			require.NoError(t, tc.predicate.Valid())
+			assert.Equal(t, tc.expected, tc.predicate.Matches(tc.event))
+		})
+	}
+}
+
+func TestEventPredicateValid(t *testing.T) {
+	symbol := xdr.ScSymbol("transfer")
+	predicate := EventPredicate{
+		Operator: PredicateOperatorGt,
+		Value:    xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &symbol},
+	}
+	require.EqualError(t, predicate.Valid(), "value must be an integer with the gt operator")
+	predicate.Operator = "ge"
+	require.EqualError(t, predicate.Valid(), "op must be one of eq, ne, gt, gte, lt and lte")
+	predicate.Operator = PredicateOperatorEq
+	predicate.Topic = 5
+	require.EqualError(t, predicate.Valid(), "topic must be at most 4")
+}
+
+func TestEventPredicateJSON(t *testing.T) {
+	predicate := EventPredicate{Field: "amount", Operator: PredicateOperatorGte, Value: i128ScVal(0, 10_000_000)}
+	encoded, err := json.Marshal(predicate)
+	require.NoError(t, err)
+	assert.JSONEq(t, `{"field":"amount","op":"gte","value":"AAAACgAAAAAAAAAAAAAAAACYloA="}`, string(encoded))
+
+	var decoded EventPredicate
+	require.NoError(t, json.Unmarshal(encoded, &decoded))
+	assert.Equal(t, predicate, decoded)
+}
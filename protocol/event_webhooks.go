@@ -0,0 +1,69 @@
+package protocol
+
+import (
+	"errors"
+	"net/url"
+)
+
+const (
+	RegisterEventWebhookMethodName = "registerEventWebhook"
+	DeleteEventWebhookMethodName   = "deleteEventWebhook"
+)
+
+// EventWebhookSignatureHeader is the header of the webhook deliveries holding
+// "sha256=" followed by the hex-encoded HMAC-SHA256 of the delivery body,
+// keyed with the secret of the webhook.
+const EventWebhookSignatureHeader = "X-Stellar-Rpc-Signature"
+
+// RegisterEventWebhookRequest registers a URL to which the server POSTs the
+// events matching filters (as in getEvents), starting from StartLedger. The
+// host of the URL must only resolve to public addresses.
+type RegisterEventWebhookRequest struct {
+	URL         string        `json:"url"`
+	StartLedger uint32        `json:"startLedger"`
+	Filters     []EventFilter `json:"filters"`
+	Format      string        `json:"xdrFormat,omitempty"`
+}
+
+// EventsRequest returns the getEvents request of the first delivery of the
+// webhook.
+func (r *RegisterEventWebhookRequest) EventsRequest() GetEventsRequest {
+	return GetEventsRequest{
+		StartLedger: r.StartLedger,
+		Filters:     r.Filters,
+		Format:      r.Format,
+	}
+}
+
+func (r *RegisterEventWebhookRequest) Valid(maxContractIDs uint) error {
+	parsed, err := url.Parse(r.URL)
+	if err != nil || (parsed.Scheme != "http" && parsed.Scheme != "https") || parsed.Host == "" {
+		return errors.New("url must be an absolute http or https URL")
+	}
+	request := r.EventsRequest()
+	return request.ValidWithContractIDsLimit(0, maxContractIDs)
+}
+
+type RegisterEventWebhookResponse struct {
+	ID string `json:"id"`
+	// Secret is the key of the signatures of the deliveries, see
+	// EventWebhookSignatureHeader. It is only returned on registration.
+	Secret string `json:"secret"`
+}
+
+type DeleteEventWebhookRequest struct {
+	ID string `json:"id"`
+}
+
+type DeleteEventWebhookResponse struct{}
+
+// EventWebhookDelivery is the body POSTed to webhooks. Deliveries are retried
+// until they get a 2xx response, so the same events can be delivered more
+// than once. Cursor is where the next delivery resumes from (as in getEvents).
+// Webhooks failing too many times in a row, or falling behind the retention
+// window, are deleted.
+type EventWebhookDelivery struct {
+	WebhookID string      `json:"webhookId"`
+	Events    []EventInfo `json:"events"`
+	Cursor    string      `json:"cursor"`
+}
@@ -0,0 +1,60 @@
+package protocol
+
+import (
+	"errors"
+	"fmt"
+
+	"github.com/stellar/go/strkey"
+	"github.com/stellar/go/xdr"
+)
+
+const GetContractDataMethodName = "getContractData"
+
+// GetContractDataRequest enumerates the persistent (and instance) contract
+// data entries of a contract, in the order of the XDR of their keys.
+type GetContractDataRequest struct {
+	ContractID string `json:"contractId"`
+	// KeyPrefix (a base64 ScVal) restricts the entries to the ones whose key
+	// is KeyPrefix or, for a vector, the vectors starting with the elements of
+	// KeyPrefix (e.g. [Symbol("Balance")] for all the balances of a token).
+	KeyPrefix string `json:"keyPrefix,omitempty"`
+	// Pagination returns (up to Limit of) the entries after the key at the
+	// cursor, as returned by the previous page.
+	Pagination *LedgerPaginationOptions `json:"pagination,omitempty"`
+	Format     string                   `json:"xdrFormat,omitempty"`
+}
+
+func (req GetContractDataRequest) IsValid(maxLimit uint) error {
+	if _, err := strkey.Decode(strkey.VersionByteContract, req.ContractID); err != nil {
+		return errors.New("contractId must be a valid contract address")
+	}
+	if req.KeyPrefix != "" {
+		var prefix xdr.ScVal
+		if err := xdr.SafeUnmarshalBase64(req.KeyPrefix, &prefix); err != nil {
+			return errors.New("keyPrefix must be a base64 ScVal")
+		}
+	}
+	if req.Pagination != nil && req.Pagination.Limit > maxLimit {
+		return fmt.Errorf("limit must not exceed %d", maxLimit)
+	}
+	return IsValidFormat(req.Format)
+}
+
+// GetContractDataResponse holds the entries of a contract indexed by the
+// server, which may be incomplete: the index isn't seeded from a snapshot of
+// the ledger, so the entries untouched since the first indexed ledger, or
+// evicted since then, aren't enumerated.
+type GetContractDataResponse struct {
+	Entries []LedgerEntryResult `json:"entries"`
+	// Sequence number of the latest ledger at time of request.
+	LatestLedger uint32 `json:"latestLedger"`
+	// FirstIndexedLedger is the first ledger whose changes were indexed, the
+	// entries last changed before it being missing.
+	FirstIndexedLedger uint32 `json:"firstIndexedLedger"`
+	// Complete tells whether the entries of the contract are all enumerated,
+	// which is only the case when the server indexed every ledger since the
+	// genesis one (e.g. on a local network).
+	Complete bool `json:"complete"`
+	// Cursor of the next page, if there may be more entries.
+	Cursor string `json:"cursor,omitempty"`
+}
@@ -1,11 +1,15 @@
 package protocol
 
 import (
+	"bytes"
+	"encoding/hex"
 	"encoding/json"
 	"errors"
 	"fmt"
 	"slices"
+	"strconv"
 	"strings"
+	"time"
 
 	"github.com/stellar/go/strkey"
 	"github.com/stellar/go/xdr"
@@ -15,11 +19,22 @@ const (
 	GetEventsMethodName = "getEvents"
 	MaxFiltersLimit     = 5
 	MaxTopicsLimit      = 5
+	// MaxContractIDsLimit is the maximum number of contract IDs per filter
+	// accepted by Valid(), servers can be configured with a different one
 	MaxContractIDsLimit = 5
-	MinTopicCount       = 1
-	MaxTopicCount       = 4
-	WildCardExactOne    = "*"
-	WildCardZeroOrMore  = "**"
+	// MaxAlternativesLimit is the maximum number of alternatives of a topic
+	// segment
+	MaxAlternativesLimit = 5
+	MinTopicCount        = 1
+	MaxTopicCount        = 4
+	WildCardExactOne     = "*"
+	WildCardZeroOrMore   = "**"
+)
+
+// The orders in which getEvents returns events
+const (
+	EventOrderAsc  = "asc"
+	EventOrderDesc = "desc"
 )
 
 type EventInfo struct {
@@ -28,8 +43,13 @@ type EventInfo struct {
 	LedgerClosedAt string `json:"ledgerClosedAt"`
 	ContractID     string `json:"contractId"`
 	ID             string `json:"id"`
-	OpIndex        uint32 `json:"operationIndex"`
-	TxIndex        uint32 `json:"transactionIndex"`
+	// OpIndex is the index of the operation which emitted the event within
+	// its transaction. It is missing for the events of the transaction itself
+	// (e.g. fees), and for the events ingested before it was stored.
+	OpIndex *uint32 `json:"operationIndex,omitempty"`
+	// TxIndex is the (1-based) application order of the transaction within
+	// the ledger. Along with the ID, it orders the events of a ledger.
+	TxIndex uint32 `json:"transactionIndex"`
 
 	InSuccessfulContractCall bool   `json:"inSuccessfulContractCall"`
 	TransactionHash          string `json:"txHash"`
@@ -41,6 +61,25 @@ type EventInfo struct {
 	// ValueXDR is a base64-encoded ScVal
 	ValueXDR  string          `json:"value,omitempty"`
 	ValueJSON json.RawMessage `json:"valueJson,omitempty"`
+
+	// MatchedFilters are the (0-based) indexes of the filters of the request
+	// matched by the event, so that the events of several filters can be told
+	// apart. It is omitted when the request has no filters.
+	MatchedFilters []int `json:"matchedFilters,omitempty"`
+
+	// Decoded holds the named fields of the event, as described by the spec
+	// of its contract, when requested and available
+	Decoded *DecodedEvent `json:"decoded,omitempty"`
+}
+
+// DecodedEvent is an event decoded after the event spec embedded in the Wasm
+// of its contract, naming its topics and data
+type DecodedEvent struct {
+	// Name is the name of the event in the spec
+	Name string `json:"name"`
+	// FieldsXDR are base64-encoded ScVals by field name
+	FieldsXDR  map[string]string          `json:"fields,omitempty"`
+	FieldsJSON map[string]json.RawMessage `json:"fieldsJson,omitempty"`
 }
 
 const (
@@ -66,11 +105,17 @@ func GetEventTypeXDRFromEventType() map[string]xdr.ContractEventType {
 }
 
 func (e *EventFilter) Valid() error {
+	return e.ValidWithContractIDsLimit(MaxContractIDsLimit)
+}
+
+// ValidWithContractIDsLimit is Valid() with a given maximum number of contract
+// IDs.
+func (e *EventFilter) ValidWithContractIDsLimit(maxContractIDs uint) error {
 	if err := e.EventType.valid(); err != nil {
 		return fmt.Errorf("filter type invalid: %w", err)
 	}
-	if len(e.ContractIDs) > MaxContractIDsLimit {
-		return errors.New("maximum 5 contract IDs per filter")
+	if uint(len(e.ContractIDs)) > maxContractIDs {
+		return fmt.Errorf("maximum %d contract IDs per filter", maxContractIDs)
 	}
 	if len(e.Topics) > MaxTopicsLimit {
 		return errors.New("maximum 5 topics per filter")
@@ -86,6 +131,29 @@ func (e *EventFilter) Valid() error {
 			return fmt.Errorf("topic %d invalid: %w", i+1, err)
 		}
 	}
+	if err := e.validPredicates(); err != nil {
+		return err
+	}
+	if e.Token != nil {
+		if len(e.ContractIDs) > 0 || len(e.Topics) > 0 {
+			return errors.New("token cannot be set along with contractIds or topics")
+		}
+		if err := e.Token.Valid(); err != nil {
+			return fmt.Errorf("token invalid: %w", err)
+		}
+	}
+	return nil
+}
+
+func (e *EventFilter) validPredicates() error {
+	if len(e.Predicates) > MaxPredicatesLimit {
+		return fmt.Errorf("maximum %d predicates per filter", MaxPredicatesLimit)
+	}
+	for i, predicate := range e.Predicates {
+		if err := predicate.Valid(); err != nil {
+			return fmt.Errorf("predicate %d invalid: %w", i+1, err)
+		}
+	}
 	return nil
 }
 
@@ -150,28 +218,153 @@ type EventFilter struct {
 	EventType   EventTypeSet  `json:"type,omitempty"`
 	ContractIDs []string      `json:"contractIds,omitempty"`
 	Topics      []TopicFilter `json:"topics,omitempty"`
+	// Token is a shorthand for the contract ID and topics of the token
+	// events of an asset, see ExpandToken().
+	Token *TokenEventFilter `json:"token,omitempty"`
+	// Predicates must all be satisfied by the events, see EventPredicate.
+	Predicates []EventPredicate `json:"predicates,omitempty"`
+}
+
+// The token events emitted by the Stellar Asset Contract, which can be
+// filtered with a TokenEventFilter
+const (
+	TokenEventTransfer = "transfer"
+	TokenEventMint     = "mint"
+	TokenEventBurn     = "burn"
+	TokenEventClawback = "clawback"
+)
+
+// TokenEventFilter filters the token events of the Stellar Asset Contract of
+// an asset, without having to build its contract ID and the event topics.
+type TokenEventFilter struct {
+	// Asset is either "native" (or "XLM") or "CODE:ISSUER"
+	Asset string `json:"asset"`
+	// Events are among TokenEventTransfer, TokenEventMint, TokenEventBurn and
+	// TokenEventClawback, all of them if empty
+	Events []string `json:"events,omitempty"`
+}
+
+func (t *TokenEventFilter) Valid() error {
+	if _, err := t.ParseAsset(); err != nil {
+		return err
+	}
+	for _, event := range t.Events {
+		switch event {
+		case TokenEventTransfer, TokenEventMint, TokenEventBurn, TokenEventClawback:
+			// ok
+		default:
+			return fmt.Errorf("event must be either '%s', '%s', '%s' or '%s'",
+				TokenEventTransfer, TokenEventMint, TokenEventBurn, TokenEventClawback)
+		}
+	}
+	return nil
+}
+
+// ParseAsset canonicalizes the asset of the filter, accepting "XLM" for the
+// native asset and a lowercase issuer.
+func (t *TokenEventFilter) ParseAsset() (xdr.Asset, error) {
+	if strings.EqualFold(t.Asset, "native") || strings.EqualFold(t.Asset, "XLM") {
+		return xdr.MustNewNativeAsset(), nil
+	}
+	code, issuer, ok := strings.Cut(t.Asset, ":")
+	if !ok {
+		return xdr.Asset{}, errors.New("asset must be either 'native' or 'CODE:ISSUER'")
+	}
+	asset, err := xdr.NewCreditAsset(code, strings.ToUpper(issuer))
+	if err != nil {
+		return xdr.Asset{}, fmt.Errorf("asset invalid: %w", err)
+	}
+	return asset, nil
+}
+
+// ExpandToken returns the filter with its token shorthand replaced by the ID
+// of the Stellar Asset Contract of the asset on the given network and by one
+// topic filter per token event, matching their symbol as first topic.
+func (e EventFilter) ExpandToken(networkPassphrase string) (EventFilter, error) {
+	if e.Token == nil {
+		return e, nil
+	}
+	asset, err := e.Token.ParseAsset()
+	if err != nil {
+		return EventFilter{}, err
+	}
+	contractID, err := asset.ContractID(networkPassphrase)
+	if err != nil {
+		return EventFilter{}, fmt.Errorf("could not compute the asset contract ID: %w", err)
+	}
+
+	events := e.Token.Events
+	if len(events) == 0 {
+		events = []string{TokenEventTransfer, TokenEventMint, TokenEventBurn, TokenEventClawback}
+	}
+	zeroOrMore := WildCardZeroOrMore
+	topics := make([]TopicFilter, 0, len(events))
+	for _, event := range events {
+		symbol := xdr.ScSymbol(event)
+		topics = append(topics, TopicFilter{
+			{ScVal: &xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &symbol}},
+			{Wildcard: &zeroOrMore},
+		})
+	}
+
+	e.ContractIDs = []string{strkey.MustEncode(strkey.VersionByteContract, contractID[:])}
+	e.Topics = topics
+	e.Token = nil
+	return e, nil
 }
 
 type GetEventsRequest struct {
-	StartLedger uint32             `json:"startLedger,omitempty"`
-	EndLedger   uint32             `json:"endLedger,omitempty"`
-	Filters     []EventFilter      `json:"filters"`
-	Pagination  *PaginationOptions `json:"pagination,omitempty"`
-	Format      string             `json:"xdrFormat,omitempty"`
+	StartLedger uint32 `json:"startLedger,omitempty"`
+	// EndLedger is the (exclusive) end of the ledger range, which also bounds
+	// the pages requested with a cursor.
+	EndLedger  uint32             `json:"endLedger,omitempty"`
+	Filters    []EventFilter      `json:"filters"`
+	Pagination *PaginationOptions `json:"pagination,omitempty"`
+	Format     string             `json:"xdrFormat,omitempty"`
+	// Order is either EventOrderAsc (the default) or EventOrderDesc, in which
+	// case events are returned latest first, scanning backwards from the end
+	// of the ledger range (or from the cursor, exclusive).
+	Order string `json:"order,omitempty"`
+	// TransactionHash restricts the events to the ones emitted by the
+	// transaction with the given (hex-encoded) hash.
+	TransactionHash string `json:"transactionHash,omitempty"`
+	// Decode decodes the events described by the event spec of their
+	// contract, see EventInfo.Decoded.
+	Decode bool `json:"decode,omitempty"`
+	// StartTime and EndTime (exclusive) can be given instead of StartLedger
+	// and EndLedger, which they resolve to: the ledger range starts (or ends)
+	// with the first ledger with events closing at or after them.
+	StartTime *Timestamp `json:"startTime,omitempty"`
+	EndTime   *Timestamp `json:"endTime,omitempty"`
+	// IncludeLatestMatchingLedger looks up the latest ledger with an event
+	// matching the filters, see GetEventsResponse.LatestMatchingLedger. It
+	// takes an extra scan of up to 10000 ledgers.
+	IncludeLatestMatchingLedger bool `json:"includeLatestMatchingLedger,omitempty"`
 }
 
 func (g *GetEventsRequest) Valid(maxLimit uint) error {
+	return g.ValidWithContractIDsLimit(maxLimit, MaxContractIDsLimit)
+}
+
+// ValidWithContractIDsLimit is Valid() with a given maximum number of contract
+// IDs per filter.
+func (g *GetEventsRequest) ValidWithContractIDsLimit(maxLimit uint, maxContractIDs uint) error {
 	if err := IsValidFormat(g.Format); err != nil {
 		return err
 	}
 
-	// Validate the paging limit (if it exists)
-	if g.Pagination != nil && g.Pagination.Cursor != nil {
-		if g.StartLedger != 0 || g.EndLedger != 0 {
-			return errors.New("ledger ranges and cursor cannot both be set")
+	if g.Order != "" && g.Order != EventOrderAsc && g.Order != EventOrderDesc {
+		return fmt.Errorf("order must be either '%s' or '%s'", EventOrderAsc, EventOrderDesc)
+	}
+
+	if g.TransactionHash != "" {
+		if hash, err := hex.DecodeString(g.TransactionHash); err != nil || len(hash) != len(xdr.Hash{}) {
+			return errors.New("transactionHash must be a hex-encoded 32-byte hash")
 		}
-	} else if g.StartLedger <= 0 {
-		return errors.New("startLedger must be positive")
+	}
+
+	if err := g.validRange(); err != nil {
+		return err
 	}
 
 	if g.Pagination != nil && g.Pagination.Limit > maxLimit {
@@ -183,7 +376,7 @@ func (g *GetEventsRequest) Valid(maxLimit uint) error {
 		return errors.New("maximum 5 filters per request")
 	}
 	for i, filter := range g.Filters {
-		if err := filter.Valid(); err != nil {
+		if err := filter.ValidWithContractIDsLimit(maxContractIDs); err != nil {
 			return fmt.Errorf("filter %d invalid: %w", i+1, err)
 		}
 	}
@@ -191,6 +384,57 @@ func (g *GetEventsRequest) Valid(maxLimit uint) error {
 	return nil
 }
 
+func (g *GetEventsRequest) validTimes() error {
+	if g.StartTime != nil && g.StartLedger != 0 {
+		return errors.New("startLedger and startTime cannot both be set")
+	}
+	if g.EndTime != nil && g.EndLedger != 0 {
+		return errors.New("endLedger and endTime cannot both be set")
+	}
+	if g.StartTime != nil && g.EndTime != nil && !g.EndTime.After(g.StartTime.Time) {
+		return errors.New("endTime must be after startTime")
+	}
+	return nil
+}
+
+// validRange validates the start (and end) of the request, given either by
+// ledger, by time or by cursor.
+func (g *GetEventsRequest) validRange() error {
+	if err := g.validTimes(); err != nil {
+		return err
+	}
+
+	// Validate the paging limit (if it exists)
+	if g.Pagination != nil && g.Pagination.Cursor != nil {
+		if g.StartLedger != 0 {
+			return errors.New("startLedger and cursor cannot both be set")
+		}
+		if g.StartTime != nil {
+			return errors.New("startTime and cursor cannot both be set")
+		}
+	} else if g.StartLedger <= 0 && g.StartTime == nil {
+		return errors.New("startLedger must be positive")
+	} else if g.EndLedger != 0 && g.EndLedger <= g.StartLedger {
+		return errors.New("endLedger must be greater than startLedger")
+	}
+	return nil
+}
+
+// ExpandTokens expands the token shorthand of the filters, see
+// EventFilter.ExpandToken().
+func (g *GetEventsRequest) ExpandTokens(networkPassphrase string) error {
+	filters := make([]EventFilter, 0, len(g.Filters))
+	for i, filter := range g.Filters {
+		expanded, err := filter.ExpandToken(networkPassphrase)
+		if err != nil {
+			return fmt.Errorf("filter %d invalid: %w", i+1, err)
+		}
+		filters = append(filters, expanded)
+	}
+	g.Filters = filters
+	return nil
+}
+
 func (g *GetEventsRequest) Matches(event xdr.DiagnosticEvent) bool {
 	if len(g.Filters) == 0 {
 		return true
@@ -203,8 +447,30 @@ func (g *GetEventsRequest) Matches(event xdr.DiagnosticEvent) bool {
 	return false
 }
 
+// MatchingFilters returns the indexes of the filters matched by the event, nil
+// if it doesn't match any of them (or if there are no filters).
+func (g *GetEventsRequest) MatchingFilters(event xdr.DiagnosticEvent) []int {
+	var matching []int
+	for i, filter := range g.Filters {
+		if filter.Matches(event) {
+			matching = append(matching, i)
+		}
+	}
+	return matching
+}
+
 func (e *EventFilter) Matches(event xdr.DiagnosticEvent) bool {
-	return e.EventType.matches(event.Event) && e.matchesContractIDs(event.Event) && e.matchesTopics(event.Event)
+	return e.EventType.matches(event.Event) && e.matchesContractIDs(event.Event) && e.matchesTopics(event.Event) &&
+		e.matchesPredicates(event.Event)
+}
+
+func (e *EventFilter) matchesPredicates(event xdr.ContractEvent) bool {
+	for _, predicate := range e.Predicates {
+		if !predicate.Matches(event) {
+			return false
+		}
+	}
+	return true
 }
 
 func (e *EventFilter) matchesContractIDs(event xdr.ContractEvent) bool {
@@ -239,7 +505,7 @@ type TopicFilter []SegmentFilter
 // Valid checks if the filter is properly structured:
 // - must have at least one segment.
 // - cannot have more than 4 segments total (excluding trailing "**").
-// - each segment must be valid.
+// - each segment must be valid (an exact value, alternatives or a wildcard).
 // - The "**" wildcard, representing a flexible-length match, is only allowed as the last segment.
 // Returns an error if any of the rules fail.
 func (t TopicFilter) Valid() error {
@@ -288,7 +554,8 @@ func (t TopicFilter) hasTrailingZeroOrMoreWildcard() bool {
 //     as many topics as the filter excluding the "**".
 //   - If the filter does not end with "**", the event must have exactly the
 //     same number of topics as the filter.
-//   - Each segment must either match exactly or via a wildcard.
+//   - Each segment must either match exactly, match one of its alternatives
+//     or match via a wildcard.
 func (t TopicFilter) Matches(event []xdr.ScVal) bool {
 	var topics []SegmentFilter
 	switch {
@@ -319,6 +586,9 @@ func (t TopicFilter) Matches(event []xdr.ScVal) bool {
 type SegmentFilter struct {
 	Wildcard *string    `json:"-"`
 	ScVal    *xdr.ScVal `json:"-"`
+	// Alternatives are the values the segment can be equal to, encoded as an
+	// array of base64 ScVals (e.g. any of two token symbols).
+	Alternatives []xdr.ScVal `json:"-"`
 }
 
 func (s *SegmentFilter) Matches(segment xdr.ScVal) bool {
@@ -329,6 +599,8 @@ func (s *SegmentFilter) Matches(segment xdr.ScVal) bool {
 		if !s.ScVal.Equals(segment) {
 			return false
 		}
+	case len(s.Alternatives) > 0:
+		return slices.ContainsFunc(s.Alternatives, segment.Equals)
 	default:
 		panic("invalid segmentFilter")
 	}
@@ -341,6 +613,17 @@ func isValidWildCard(wildcard string) bool {
 }
 
 func (s *SegmentFilter) Valid() error {
+	if s.Alternatives != nil {
+		switch {
+		case s.Wildcard != nil || s.ScVal != nil:
+			return errors.New("cannot set alternatives along with a wildcard or scval")
+		case len(s.Alternatives) == 0:
+			return errors.New("alternatives cannot be empty")
+		case len(s.Alternatives) > MaxAlternativesLimit:
+			return fmt.Errorf("cannot have more than %d alternatives", MaxAlternativesLimit)
+		}
+		return nil
+	}
 	if s.Wildcard != nil && s.ScVal != nil {
 		return errors.New("cannot set both wildcard and scval")
 	}
@@ -358,6 +641,23 @@ func (s *SegmentFilter) Valid() error {
 func (s *SegmentFilter) UnmarshalJSON(p []byte) error {
 	s.Wildcard = nil
 	s.ScVal = nil
+	s.Alternatives = nil
+
+	if bytes.HasPrefix(bytes.TrimSpace(p), []byte("[")) {
+		var alternatives []string
+		if err := json.Unmarshal(p, &alternatives); err != nil {
+			return err
+		}
+		s.Alternatives = make([]xdr.ScVal, 0, len(alternatives))
+		for _, alternative := range alternatives {
+			var out xdr.ScVal
+			if err := xdr.SafeUnmarshalBase64(alternative, &out); err != nil {
+				return err
+			}
+			s.Alternatives = append(s.Alternatives, out)
+		}
+		return nil
+	}
 
 	var tmp string
 	if err := json.Unmarshal(p, &tmp); err != nil {
@@ -384,6 +684,18 @@ func (s SegmentFilter) MarshalJSON() ([]byte, error) {
 		return json.Marshal(*s.Wildcard)
 	}
 
+	if s.Alternatives != nil {
+		alternatives := make([]string, 0, len(s.Alternatives))
+		for _, alternative := range s.Alternatives {
+			scv, err := xdr.MarshalBase64(alternative)
+			if err != nil {
+				return nil, err
+			}
+			alternatives = append(alternatives, scv)
+		}
+		return json.Marshal(alternatives)
+	}
+
 	scv, err := xdr.MarshalBase64(s.ScVal)
 	if err != nil {
 		return nil, err
@@ -396,11 +708,52 @@ type PaginationOptions struct {
 	Limit  uint    `json:"limit,omitempty"`
 }
 
+// Timestamp is a time given either as an RFC3339 string or as a number of
+// seconds since the unix epoch (as a JSON number or string).
+type Timestamp struct {
+	time.Time
+}
+
+func (t Timestamp) MarshalJSON() ([]byte, error) {
+	return json.Marshal(t.UTC().Format(time.RFC3339))
+}
+
+func (t *Timestamp) UnmarshalJSON(data []byte) error {
+	var value string
+	if err := json.Unmarshal(data, &value); err != nil {
+		// not a string, it must be a number
+		value = string(data)
+	}
+	if seconds, err := strconv.ParseInt(value, 10, 64); err == nil {
+		t.Time = time.Unix(seconds, 0).UTC()
+		return nil
+	}
+	parsed, err := time.Parse(time.RFC3339, value)
+	if err != nil {
+		return fmt.Errorf("invalid time %s, it must be an RFC3339 time or a unix timestamp", data)
+	}
+	t.Time = parsed
+	return nil
+}
+
 type GetEventsResponse struct {
 	Events []EventInfo `json:"events"`
-	// Cursor represents last populated event ID if total events reach the limit
-	// or end of the search window
+	// Cursor represents last populated event if total events reach the limit
+	// or end of the search window (its start, when in descending order). It is
+	// opaque and versioned, see Cursor.Encode().
 	Cursor string `json:"cursor"`
+	// HasMore is set when the next page (from Cursor) may have events, i.e.
+	// when there are more events than the limit, or when ledgers of the range
+	// of the request are left to be scanned.
+	HasMore bool `json:"hasMore"`
+	// LastScannedLedger is the last ledger scanned for the events of the page
+	// (the first one, when in descending order).
+	LastScannedLedger uint32 `json:"lastScannedLedger"`
+	// LatestMatchingLedger is the latest ledger, up to the end of the range of
+	// the request, with an event matching the filters (among the last 10000
+	// ledgers of the range). It is only looked up with
+	// IncludeLatestMatchingLedger, and omitted if there is none.
+	LatestMatchingLedger uint32 `json:"latestMatchingLedger,omitempty"`
 
 	LatestLedger          uint32 `json:"latestLedger"`
 	OldestLedger          uint32 `json:"oldestLedger"`
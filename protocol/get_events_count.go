@@ -0,0 +1,63 @@
+package protocol
+
+import (
+	"fmt"
+)
+
+const GetEventsCountMethodName = "getEventsCount"
+
+// The keys by which getEventsCount groups the matching events
+const (
+	EventsCountGroupByLedger   = "ledger"
+	EventsCountGroupByContract = "contract"
+)
+
+// GetEventsCountRequest counts the events matching filters (as in getEvents)
+// within a ledger range, grouped by ledger or by contract.
+type GetEventsCountRequest struct {
+	StartLedger uint32        `json:"startLedger"`
+	EndLedger   uint32        `json:"endLedger,omitempty"`
+	Filters     []EventFilter `json:"filters"`
+	// GroupBy is either EventsCountGroupByLedger (the default) or
+	// EventsCountGroupByContract
+	GroupBy string `json:"groupBy,omitempty"`
+}
+
+// EventsRequest returns the getEvents request matching the same events.
+func (g *GetEventsCountRequest) EventsRequest() GetEventsRequest {
+	return GetEventsRequest{
+		StartLedger: g.StartLedger,
+		EndLedger:   g.EndLedger,
+		Filters:     g.Filters,
+	}
+}
+
+func (g *GetEventsCountRequest) Valid(maxContractIDs uint) error {
+	if g.GroupBy != "" && g.GroupBy != EventsCountGroupByLedger && g.GroupBy != EventsCountGroupByContract {
+		return fmt.Errorf("groupBy must be either '%s' or '%s'", EventsCountGroupByLedger, EventsCountGroupByContract)
+	}
+	request := g.EventsRequest()
+	return request.ValidWithContractIDsLimit(0, maxContractIDs)
+}
+
+// EventsCount is the number of matching events of a ledger or of a contract,
+// depending on how they are grouped.
+type EventsCount struct {
+	Ledger uint32 `json:"ledger,omitempty"`
+	// ContractID is empty for the events not emitted by a contract
+	ContractID string `json:"contractId,omitempty"`
+	Count      uint64 `json:"count"`
+}
+
+type GetEventsCountResponse struct {
+	// Counts are ordered by ledger, or by decreasing count when grouped by
+	// contract. Groups without matching events are omitted.
+	Counts []EventsCount `json:"counts"`
+	Total  uint64        `json:"total"`
+	// EndLedger is the (exclusive) end of the scanned ledger range, which
+	// can be shorter than the requested one. The next range starts there.
+	EndLedger uint32 `json:"endLedger"`
+
+	LatestLedger uint32 `json:"latestLedger"`
+	OldestLedger uint32 `json:"oldestLedger"`
+}
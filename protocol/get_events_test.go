@@ -3,13 +3,17 @@ package protocol
 import (
 	"encoding/json"
 	"fmt"
+	"slices"
 	"strings"
 	"testing"
+	"time"
 
 	"github.com/stretchr/testify/assert"
 	"github.com/stretchr/testify/require"
 
 	"github.com/stellar/go/keypair"
+	"github.com/stellar/go/network"
+	"github.com/stellar/go/strkey"
 	"github.com/stellar/go/xdr"
 )
 
@@ -245,6 +249,22 @@ func TestTopicFilterMatches(t *testing.T) {
 				{transfer, transfer},
 			},
 		},
+		// Alternatives
+		{
+			name: "[transfer|64]/*",
+			filter: []SegmentFilter{
+				{Alternatives: []xdr.ScVal{transfer, number}},
+				{Wildcard: &wildCardExactOne},
+			},
+			includes: []xdr.ScVec{
+				{transfer, number},
+				{number, transfer},
+			},
+			excludes: []xdr.ScVec{
+				{transfer},
+				{transfer, number, number},
+			},
+		},
 		{
 			name: "*/transfer",
 			filter: []SegmentFilter{
@@ -635,7 +655,19 @@ func TestGetEventsRequestValid(t *testing.T) {
 		StartLedger: 1,
 		Filters:     []EventFilter{},
 		Pagination:  &PaginationOptions{Cursor: &Cursor{}},
-	}).Valid(1000), "ledger ranges and cursor cannot both be set")
+	}).Valid(1000), "startLedger and cursor cannot both be set")
+
+	require.NoError(t, (&GetEventsRequest{
+		EndLedger:  10,
+		Filters:    []EventFilter{},
+		Pagination: &PaginationOptions{Cursor: &Cursor{}},
+	}).Valid(1000))
+
+	require.EqualError(t, (&GetEventsRequest{
+		StartLedger: 10,
+		EndLedger:   10,
+		Filters:     []EventFilter{},
+	}).Valid(1000), "endLedger must be greater than startLedger")
 
 	require.NoError(t, (&GetEventsRequest{
 		StartLedger: 1,
@@ -655,6 +687,41 @@ func TestGetEventsRequestValid(t *testing.T) {
 		Pagination:  nil,
 	}).Valid(1000), "startLedger must be positive")
 
+	require.NoError(t, (&GetEventsRequest{
+		StartLedger: 1,
+		Filters:     []EventFilter{},
+		Order:       EventOrderDesc,
+	}).Valid(1000))
+
+	require.EqualError(t, (&GetEventsRequest{
+		StartLedger: 1,
+		Filters:     []EventFilter{},
+		Order:       "latest",
+	}).Valid(1000), "order must be either 'asc' or 'desc'")
+
+	require.NoError(t, (&GetEventsRequest{
+		StartLedger:     1,
+		Filters:         []EventFilter{},
+		TransactionHash: "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20",
+	}).Valid(1000))
+
+	require.EqualError(t, (&GetEventsRequest{
+		StartLedger:     1,
+		Filters:         []EventFilter{},
+		TransactionHash: "0102",
+	}).Valid(1000), "transactionHash must be a hex-encoded 32-byte hash")
+
+	contractIDs := slices.Repeat([]string{"CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4"}, 6)
+	require.EqualError(t, (&GetEventsRequest{
+		StartLedger: 1,
+		Filters:     []EventFilter{{ContractIDs: contractIDs}},
+	}).Valid(1000), "filter 1 invalid: maximum 5 contract IDs per filter")
+
+	require.NoError(t, (&GetEventsRequest{
+		StartLedger: 1,
+		Filters:     []EventFilter{{ContractIDs: contractIDs}},
+	}).ValidWithContractIDsLimit(1000, 6))
+
 	require.EqualError(t, (&GetEventsRequest{
 		StartLedger: 1,
 		Filters: []EventFilter{
@@ -834,6 +901,57 @@ func TestGetEventsRequestValid(t *testing.T) {
 		Pagination: nil,
 	}).Valid(1000), "filter 1 invalid: topic 1 invalid: "+
 		"segment 1 invalid: wildcard '**' is only allowed as the last segment")
+
+	require.EqualError(t, (&GetEventsRequest{
+		StartLedger: 1,
+		Filters: []EventFilter{
+			{Topics: []TopicFilter{
+				[]SegmentFilter{
+					{Alternatives: []xdr.ScVal{}},
+				},
+			}},
+		},
+	}).Valid(1000), "filter 1 invalid: topic 1 invalid: segment 1 invalid: alternatives cannot be empty")
+
+	require.EqualError(t, (&GetEventsRequest{
+		StartLedger: 1,
+		Filters: []EventFilter{
+			{Topics: []TopicFilter{
+				[]SegmentFilter{
+					{Alternatives: make([]xdr.ScVal, MaxAlternativesLimit+1)},
+				},
+			}},
+		},
+	}).Valid(1000), "filter 1 invalid: topic 1 invalid: segment 1 invalid: cannot have more than 5 alternatives")
+}
+
+func TestGetEventsRequestTimes(t *testing.T) {
+	var request GetEventsRequest
+	require.NoError(t, json.Unmarshal(
+		[]byte(`{"filters": [], "startTime": "2025-01-02T03:04:05Z", "endTime": 1735790000}`),
+		&request,
+	))
+	require.NoError(t, request.Valid(1000))
+	assert.Equal(t, time.Date(2025, 1, 2, 3, 4, 5, 0, time.UTC), request.StartTime.Time)
+	assert.Equal(t, int64(1735790000), request.EndTime.Unix())
+
+	require.NoError(t, json.Unmarshal([]byte(`"1735787045"`), request.StartTime))
+	assert.Equal(t, time.Date(2025, 1, 2, 3, 4, 5, 0, time.UTC), request.StartTime.Time)
+	require.Error(t, json.Unmarshal([]byte(`"yesterday"`), request.StartTime))
+
+	start := &Timestamp{time.Unix(100, 0)}
+	require.EqualError(t, (&GetEventsRequest{StartLedger: 1, StartTime: start}).Valid(1000),
+		"startLedger and startTime cannot both be set")
+	require.EqualError(t, (&GetEventsRequest{StartTime: start, EndTime: start}).Valid(1000),
+		"endTime must be after startTime")
+	require.EqualError(t, (&GetEventsRequest{
+		StartTime:  start,
+		Pagination: &PaginationOptions{Cursor: &Cursor{}},
+	}).Valid(1000), "startTime and cursor cannot both be set")
+	require.NoError(t, (&GetEventsRequest{
+		EndTime:    start,
+		Pagination: &PaginationOptions{Cursor: &Cursor{}},
+	}).Valid(1000))
 }
 
 func TestEventFilterSerialization(t *testing.T) {
@@ -857,6 +975,7 @@ func TestEventFilterSerialization(t *testing.T) {
 	}{
 		{SegmentFilter{Wildcard: &wc}, `"*"`},
 		{SegmentFilter{ScVal: &scv}, fmt.Sprintf(`"%s"`, b64)},
+		{SegmentFilter{Alternatives: []xdr.ScVal{scv, scv}}, fmt.Sprintf(`["%s","%s"]`, b64, b64)},
 	} {
 		filter := EventFilter{Topics: []TopicFilter{{testCase.Filter}}}
 
@@ -864,6 +983,10 @@ func TestEventFilterSerialization(t *testing.T) {
 		require.NoError(t, err)
 		require.JSONEq(t, testCase.Encoded, string(b))
 
+		var decoded SegmentFilter
+		require.NoError(t, json.Unmarshal(b, &decoded))
+		require.Equal(t, testCase.Filter, decoded)
+
 		f, err := json.Marshal(filter)
 		require.NoError(t, err)
 		require.JSONEq(t, fmt.Sprintf(`{"topics":[[%s]]}`, string(b)), string(f))
@@ -872,3 +995,69 @@ func TestEventFilterSerialization(t *testing.T) {
 	_, err = json.Marshal(SegmentFilter{})
 	require.Error(t, err)
 }
+
+func TestEventFilterExpandToken(t *testing.T) {
+	issuer := keypair.MustRandom().Address()
+	usdc, err := xdr.NewCreditAsset("USDC", issuer)
+	require.NoError(t, err)
+	usdcContractID, err := usdc.ContractID(network.TestNetworkPassphrase)
+	require.NoError(t, err)
+
+	filter := EventFilter{Token: &TokenEventFilter{
+		Asset:  "USDC:" + strings.ToLower(issuer),
+		Events: []string{TokenEventTransfer, TokenEventBurn},
+	}}
+	require.NoError(t, filter.Valid())
+	expanded, err := filter.ExpandToken(network.TestNetworkPassphrase)
+	require.NoError(t, err)
+	assert.Nil(t, expanded.Token)
+	assert.Equal(t, []string{strkey.MustEncode(strkey.VersionByteContract, usdcContractID[:])}, expanded.ContractIDs)
+
+	symbol := func(s string) xdr.ScVal {
+		sym := xdr.ScSymbol(s)
+		return xdr.ScVal{Type: xdr.ScValTypeScvSymbol, Sym: &sym}
+	}
+	event := func(topics ...xdr.ScVal) xdr.DiagnosticEvent {
+		contractID := xdr.ContractId(usdcContractID)
+		return xdr.DiagnosticEvent{Event: xdr.ContractEvent{
+			ContractId: &contractID,
+			Type:       xdr.ContractEventTypeContract,
+			Body:       xdr.ContractEventBody{V0: &xdr.ContractEventV0{Topics: topics}},
+		}}
+	}
+	asset := xdr.ScString("USDC:" + issuer)
+	assetScVal := xdr.ScVal{Type: xdr.ScValTypeScvString, Str: &asset}
+	assert.True(t, expanded.Matches(event(symbol("transfer"), symbol("from"), symbol("to"), assetScVal)))
+	assert.True(t, expanded.Matches(event(symbol("burn"), symbol("from"), assetScVal)))
+	assert.False(t, expanded.Matches(event(symbol("mint"), symbol("to"), assetScVal)))
+
+	// All the token events of the native asset by default
+	filter = EventFilter{Token: &TokenEventFilter{Asset: "XLM"}}
+	require.NoError(t, filter.Valid())
+	expanded, err = filter.ExpandToken(network.TestNetworkPassphrase)
+	require.NoError(t, err)
+	nativeContractID, err := xdr.MustNewNativeAsset().ContractID(network.TestNetworkPassphrase)
+	require.NoError(t, err)
+	assert.Equal(t, []string{strkey.MustEncode(strkey.VersionByteContract, nativeContractID[:])}, expanded.ContractIDs)
+	assert.Len(t, expanded.Topics, 4)
+
+	for _, testCase := range []struct {
+		filter EventFilter
+		err    string
+	}{
+		{
+			EventFilter{Token: &TokenEventFilter{Asset: "native"}, ContractIDs: expanded.ContractIDs},
+			"token cannot be set along with contractIds or topics",
+		},
+		{
+			EventFilter{Token: &TokenEventFilter{Asset: "USDC"}},
+			"token invalid: asset must be either 'native' or 'CODE:ISSUER'",
+		},
+		{
+			EventFilter{Token: &TokenEventFilter{Asset: "native", Events: []string{"approve"}}},
+			"token invalid: event must be either 'transfer', 'mint', 'burn' or 'clawback'",
+		},
+	} {
+		require.EqualError(t, testCase.filter.Valid(), testCase.err)
+	}
+}
@@ -2,11 +2,32 @@ package protocol
 
 import "encoding/json"
 
-const GetLedgerEntriesMethodName = "getLedgerEntries"
+const (
+	GetLedgerEntriesMethodName = "getLedgerEntries"
+
+	// LedgerEntryStateLive is the state of the entries which can be used by
+	// transactions.
+	LedgerEntryStateLive = "live"
+	// LedgerEntryStateExpired is the state of the entries whose TTL ended but
+	// which weren't evicted yet. Persistent entries must be restored before
+	// being used again.
+	LedgerEntryStateExpired = "expired"
+	// LedgerEntryStateArchived is the state of the persistent entries evicted
+	// to the archive, which must be restored before being used again.
+	LedgerEntryStateArchived = "archived"
+)
 
 type GetLedgerEntriesRequest struct {
 	Keys   []string `json:"keys"`
 	Format string   `json:"xdrFormat,omitempty"`
+	// Pagination looks up (up to Limit of) the keys from the one at the cursor,
+	// the index of the first key not looked up by the previous page.
+	// Paginated requests may have more keys than a single page.
+	Pagination *LedgerPaginationOptions `json:"pagination,omitempty"`
+	// AtLedger returns the entries as they were at the end of a past ledger,
+	// which must be within the ledger retention window and the maximum number
+	// of ledgers the server rolls entries back by (the latest ledger if zero).
+	AtLedger uint32 `json:"atLedger,omitempty"`
 }
 
 type LedgerEntryResult struct {
@@ -20,6 +41,9 @@ type LedgerEntryResult struct {
 	LastModifiedLedger uint32 `json:"lastModifiedLedgerSeq"`
 	// The ledger sequence until the entry is live, available for entries that have associated ttl ledger entries.
 	LiveUntilLedgerSeq *uint32 `json:"liveUntilLedgerSeq,omitempty"`
+	// State is one of LedgerEntryStateLive, LedgerEntryStateExpired and
+	// LedgerEntryStateArchived.
+	State string `json:"state,omitempty"`
 }
 
 type GetLedgerEntriesResponse struct {
@@ -27,4 +51,6 @@ type GetLedgerEntriesResponse struct {
 	Entries []LedgerEntryResult `json:"entries"`
 	// Sequence number of the latest ledger at time of request.
 	LatestLedger uint32 `json:"latestLedger"`
+	// Cursor of the next page of a paginated request, if some keys are left.
+	Cursor string `json:"cursor,omitempty"`
 }
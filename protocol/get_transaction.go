@@ -32,9 +32,38 @@ type GetTransactionResponse struct {
 	// bug in which `createdAt` in getTransactions is encoded as a number
 	// whereas in getTransaction (singular) it's encoded as a string.
 	LedgerCloseTime int64 `json:"createdAt,string"`
+
+	// StateChanges are the ledger entries created, updated and deleted by the
+	// transaction, if requested.
+	StateChanges []LedgerEntryChange `json:"stateChanges,omitempty"`
+
+	// ResourceUsage compares the resources declared by a Soroban transaction
+	// with the ones it used. It's only present when the metrics of the
+	// transaction were emitted as diagnostic events (i.e. when Stellar Core
+	// runs with ENABLE_SOROBAN_DIAGNOSTIC_EVENTS).
+	ResourceUsage []TransactionResourceUsage `json:"resourceUsage,omitempty"`
+}
+
+// TransactionResourceUsage compares a resource declared by a Soroban
+// transaction with the amount it used.
+type TransactionResourceUsage struct {
+	Name     string `json:"name"` // instructions, readEntries, writeEntries, readBytes or writeBytes
+	Declared int64  `json:"declared,string"`
+	Used     int64  `json:"used,string"`
 }
 
 type GetTransactionRequest struct {
 	Hash   string `json:"hash"`
 	Format string `json:"xdrFormat,omitempty"`
+	// WaitFor is the number of seconds to wait for the transaction to be
+	// ingested (i.e. to be SUCCESS or FAILED) before responding, if it's not
+	// found yet. Servers cap it to their maximum wait duration.
+	WaitFor uint `json:"waitFor,omitempty"`
+	// EventsOnly omits the envelope, result and result meta of the
+	// transaction from the response, which only carries its (diagnostic)
+	// events along with its status.
+	EventsOnly bool `json:"eventsOnly,omitempty"`
+	// IncludeStateChanges adds the ledger entry changes of the transaction,
+	// derived from its meta, to the response.
+	IncludeStateChanges bool `json:"includeStateChanges,omitempty"`
 }
@@ -3,25 +3,153 @@ package protocol
 import (
 	"encoding/json"
 	"errors"
+	"fmt"
+
+	"github.com/stellar/go/strkey"
 )
 
-const GetTransactionsMethodName = "getTransactions"
+const (
+	GetTransactionsMethodName = "getTransactions"
+	TransactionsOrderAsc      = "asc"
+	TransactionsOrderDesc     = "desc"
+)
 
 // GetTransactionsRequest represents the request parameters for fetching transactions within a range of ledgers.
 type GetTransactionsRequest struct {
 	StartLedger uint32                   `json:"startLedger"`
 	Pagination  *LedgerPaginationOptions `json:"pagination,omitempty"`
 	Format      string                   `json:"xdrFormat,omitempty"`
+	// Account restricts the transactions to the ones whose source account
+	// (of the inner transaction, for fee-bump transactions) or fee source is
+	// the given account (G...).
+	Account string `json:"account,omitempty"`
+	// ContractIDs restricts the transactions to the ones which invoked one of
+	// the given contracts, directly or through sub-invocations, or whose
+	// contract events were emitted by one of them.
+	ContractIDs []string `json:"contractIds,omitempty"`
+	// Order is either TransactionsOrderAsc (the default) or
+	// TransactionsOrderDesc, in which case transactions are returned latest
+	// first, scanning backwards from the end of StartLedger (the latest ledger
+	// if not set) or from the cursor (exclusive).
+	Order string `json:"order,omitempty"`
+	// Status restricts the transactions to the successful
+	// (TransactionStatusSuccess) or failed (TransactionStatusFailed) ones.
+	Status string `json:"status,omitempty"`
+	// Memo restricts the transactions to the ones (or the ones whose inner
+	// transaction, for fee-bump transactions) with a matching memo.
+	Memo *MemoFilter `json:"memo,omitempty"`
+	// OperationTypes restricts the transactions to the ones with at least one
+	// operation of the given types, named as in the JSON encoding of XDR
+	// values (e.g. invoke_host_function).
+	OperationTypes []string `json:"operationTypes,omitempty"`
+	// AuthSigner restricts the transactions to the ones with a Soroban
+	// authorization entry signed by the given address (G... or C...), e.g.
+	// the transactions a smart wallet authorized without being their source.
+	AuthSigner string `json:"authSigner,omitempty"`
 }
 
 // IsValid checks the validity of the request parameters.
 func (req GetTransactionsRequest) IsValid(maxLimit uint, ledgerRange LedgerSeqRange) error {
+	startLedger := req.StartLedger
+	if req.Order == TransactionsOrderDesc && startLedger == 0 && (req.Pagination == nil || req.Pagination.Cursor == "") {
+		startLedger = ledgerRange.LastLedger
+	}
 	return errors.Join(
-		ValidatePagination(req.StartLedger, req.Pagination, maxLimit, ledgerRange),
+		ValidatePagination(startLedger, req.Pagination, maxLimit, ledgerRange),
 		IsValidFormat(req.Format),
+		req.validAccount(),
+		req.validContractIDs(),
+		req.validOrder(),
+		req.validStatus(),
+		req.validMemo(),
+		req.validOperationTypes(),
+		req.validAuthSigner(),
 	) // nils will coalesce
 }
 
+func (req GetTransactionsRequest) validOrder() error {
+	if req.Order != "" && req.Order != TransactionsOrderAsc && req.Order != TransactionsOrderDesc {
+		return fmt.Errorf("order must be either '%s' or '%s'", TransactionsOrderAsc, TransactionsOrderDesc)
+	}
+	return nil
+}
+
+func (req GetTransactionsRequest) validStatus() error {
+	if req.Status != "" && req.Status != TransactionStatusSuccess && req.Status != TransactionStatusFailed {
+		return fmt.Errorf("status must be either '%s' or '%s'", TransactionStatusSuccess, TransactionStatusFailed)
+	}
+	return nil
+}
+
+func (req GetTransactionsRequest) validMemo() error {
+	if req.Memo == nil {
+		return nil
+	}
+	return req.Memo.Valid()
+}
+
+func (req GetTransactionsRequest) validOperationTypes() error {
+	for _, name := range req.OperationTypes {
+		if _, ok := ParseOperationType(name); !ok {
+			return fmt.Errorf("unknown operation type: %s", name)
+		}
+	}
+	return nil
+}
+
+func (req GetTransactionsRequest) validAccount() error {
+	if req.Account == "" {
+		return nil
+	}
+	if !strkey.IsValidEd25519PublicKey(req.Account) {
+		return errors.New("account must be a valid account ID")
+	}
+	return nil
+}
+
+func (req GetTransactionsRequest) validAuthSigner() error {
+	if req.AuthSigner == "" {
+		return nil
+	}
+	if strkey.IsValidEd25519PublicKey(req.AuthSigner) {
+		return nil
+	}
+	if _, err := strkey.Decode(strkey.VersionByteContract, req.AuthSigner); err != nil {
+		return errors.New("auth signer must be a valid account ID or contract address")
+	}
+	return nil
+}
+
+func (req GetTransactionsRequest) validContractIDs() error {
+	if len(req.ContractIDs) > MaxContractIDsLimit {
+		return fmt.Errorf("maximum %d contract IDs", MaxContractIDsLimit)
+	}
+	for i, id := range req.ContractIDs {
+		if _, err := strkey.Decode(strkey.VersionByteContract, id); err != nil {
+			return fmt.Errorf("contract ID %d invalid", i+1)
+		}
+	}
+	return nil
+}
+
+// TransactionFees breaks down the fee of a transaction. For Soroban
+// transactions, the fee bid is split between the inclusion fee bid and the
+// declared resource fee, of which the unused refundable part is refunded.
+type TransactionFees struct {
+	// InclusionFeeBid is the maximum fee bid for the inclusion of the
+	// transaction (by the fee source, for fee-bump transactions).
+	InclusionFeeBid int64 `json:"inclusionFeeBid,omitempty"`
+	// ResourceFee is the resource fee declared by the Soroban transaction.
+	ResourceFee int64 `json:"resourceFee,omitempty"`
+	// NonRefundableResourceFeeCharged and RefundableResourceFeeCharged are the
+	// parts of the resource fee charged to the transaction.
+	NonRefundableResourceFeeCharged int64 `json:"nonRefundableResourceFeeCharged,omitempty"`
+	RefundableResourceFeeCharged    int64 `json:"refundableResourceFeeCharged,omitempty"`
+	// ResourceFeeRefund is the part of the resource fee refunded to the fee
+	// source.
+	ResourceFeeRefund int64 `json:"resourceFeeRefund,omitempty"`
+}
+
 type TransactionDetails struct {
 	// Status is one of: TransactionSuccess, TransactionFailed, TransactionNotFound.
 	Status string `json:"status"`
@@ -49,6 +177,8 @@ type TransactionDetails struct {
 	DiagnosticEventsJSON []json.RawMessage `json:"diagnosticEventsJson,omitempty"`
 	// Ledger is the sequence of the ledger which included the transaction.
 	Ledger uint32 `json:"ledger"`
+
+	TransactionFees
 }
 
 type TransactionInfo struct {
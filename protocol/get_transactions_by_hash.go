@@ -0,0 +1,41 @@
+package protocol
+
+import (
+	"errors"
+	"fmt"
+)
+
+const (
+	GetTransactionsByHashMethodName = "getTransactionsByHash"
+	// MaxTransactionHashesLimit is the maximum number of hashes of a
+	// getTransactionsByHash request
+	MaxTransactionHashesLimit = 200
+)
+
+// GetTransactionsByHashRequest looks up several transactions at once, like
+// as many getTransaction requests.
+type GetTransactionsByHashRequest struct {
+	Hashes []string `json:"hashes"`
+	Format string   `json:"xdrFormat,omitempty"`
+}
+
+func (req GetTransactionsByHashRequest) IsValid() error {
+	if len(req.Hashes) == 0 {
+		return errors.New("hashes must not be empty")
+	}
+	if len(req.Hashes) > MaxTransactionHashesLimit {
+		return fmt.Errorf("maximum %d hashes", MaxTransactionHashesLimit)
+	}
+	return IsValidFormat(req.Format)
+}
+
+type GetTransactionsByHashResponse struct {
+	// Transactions holds a transaction for each hash of the request, in the
+	// same order. The transactions which aren't found only carry their hash
+	// and the TransactionStatusNotFound status.
+	Transactions          []TransactionInfo `json:"transactions"`
+	LatestLedger          uint32            `json:"latestLedger"`
+	LatestLedgerCloseTime int64             `json:"latestLedgerCloseTimestamp"`
+	OldestLedger          uint32            `json:"oldestLedger"`
+	OldestLedgerCloseTime int64             `json:"oldestLedgerCloseTimestamp"`
+}
@@ -0,0 +1,98 @@
+package protocol
+
+import (
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"strconv"
+
+	"github.com/stellar/go/xdr"
+)
+
+// The memo types of memo filters
+const (
+	MemoTypeText   = "text"
+	MemoTypeID     = "id"
+	MemoTypeHash   = "hash"
+	MemoTypeReturn = "return"
+)
+
+// maxMemoTextLength is the maximum size (in bytes) of text memos
+const maxMemoTextLength = 28
+
+// MemoFilter matches the transactions with a memo of the given type and, if
+// set, the given value: the text of text memos, the (decimal) ID of id memos
+// or the hex-encoded hash of hash and return memos.
+type MemoFilter struct {
+	Type  string `json:"type"`
+	Value string `json:"value,omitempty"`
+}
+
+func (f MemoFilter) Valid() error {
+	switch f.Type {
+	case MemoTypeText:
+		if len(f.Value) > maxMemoTextLength {
+			return fmt.Errorf("text memo must be at most %d bytes", maxMemoTextLength)
+		}
+		return nil
+	case MemoTypeID:
+		if f.Value == "" {
+			return nil
+		}
+		if _, err := strconv.ParseUint(f.Value, 10, 64); err != nil {
+			return errors.New("id memo must be an unsigned 64-bit integer")
+		}
+		return nil
+	case MemoTypeHash, MemoTypeReturn:
+		if f.Value == "" {
+			return nil
+		}
+		if _, err := parseMemoHash(f.Value); err != nil {
+			return fmt.Errorf("%s memo must be a hex-encoded 32-byte hash", f.Type)
+		}
+		return nil
+	default:
+		return errors.New("memo type must be one of text, id, hash and return")
+	}
+}
+
+// Matches returns true if the memo is of the type and value of the filter.
+func (f MemoFilter) Matches(memo xdr.Memo) bool {
+	switch memo.Type {
+	case xdr.MemoTypeMemoText:
+		return f.Type == MemoTypeText && (f.Value == "" || *memo.Text == f.Value)
+	case xdr.MemoTypeMemoId:
+		return f.Type == MemoTypeID && f.matchesID(uint64(*memo.Id))
+	case xdr.MemoTypeMemoHash:
+		return f.Type == MemoTypeHash && f.matchesHash(*memo.Hash)
+	case xdr.MemoTypeMemoReturn:
+		return f.Type == MemoTypeReturn && f.matchesHash(*memo.RetHash)
+	default:
+		return false
+	}
+}
+
+func (f MemoFilter) matchesID(id uint64) bool {
+	if f.Value == "" {
+		return true
+	}
+	expected, err := strconv.ParseUint(f.Value, 10, 64)
+	return err == nil && expected == id
+}
+
+func (f MemoFilter) matchesHash(hash xdr.Hash) bool {
+	if f.Value == "" {
+		return true
+	}
+	expected, err := parseMemoHash(f.Value)
+	return err == nil && expected == hash
+}
+
+func parseMemoHash(value string) (xdr.Hash, error) {
+	var hash xdr.Hash
+	if hex.DecodedLen(len(value)) != len(hash) {
+		return xdr.Hash{}, errors.New("unexpected hash length")
+	}
+	_, err := hex.Decode(hash[:], []byte(value))
+	return hash, err
+}
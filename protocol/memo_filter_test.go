@@ -0,0 +1,48 @@
+package protocol
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+
+	"github.com/stellar/go/xdr"
+)
+
+func TestMemoFilterMatches(t *testing.T) {
+	text := xdr.MemoText("deposit 42")
+	hash := xdr.Hash{0xab}
+	hashHex := "ab00000000000000000000000000000000000000000000000000000000000000"
+
+	for _, tc := range []struct {
+		name     string
+		filter   MemoFilter
+		memo     xdr.Memo
+		expected bool
+	}{
+		{"text", MemoFilter{Type: MemoTypeText, Value: "deposit 42"}, text, true},
+		{"other text", MemoFilter{Type: MemoTypeText, Value: "deposit 4"}, text, false},
+		{"any text", MemoFilter{Type: MemoTypeText}, text, true},
+		{"id", MemoFilter{Type: MemoTypeID, Value: "42"}, xdr.MemoID(42), true},
+		{"other type", MemoFilter{Type: MemoTypeID, Value: "42"}, text, false},
+		{"hash", MemoFilter{Type: MemoTypeHash, Value: hashHex}, xdr.MemoHash(hash), true},
+		{"return", MemoFilter{Type: MemoTypeReturn, Value: hashHex}, xdr.MemoRetHash(hash), true},
+		{"hash of return", MemoFilter{Type: MemoTypeHash, Value: hashHex}, xdr.MemoRetHash(hash), false},
+		{"none", MemoFilter{Type: MemoTypeText}, xdr.Memo{Type: xdr.MemoTypeMemoNone}, false},
+	} {
+		t.Run(tc.name, func(t *testing.T) {
+			require.NoError(t, tc.filter.Valid())
+			assert.Equal(t, tc.expected, tc.filter.Matches(tc.memo))
+		})
+	}
+}
+
+func TestMemoFilterValid(t *testing.T) {
+	require.EqualError(t, MemoFilter{Type: "none"}.Valid(), "memo type must be one of text, id, hash and return")
+	require.EqualError(t, MemoFilter{Type: MemoTypeID, Value: "-1"}.Valid(),
+		"id memo must be an unsigned 64-bit integer")
+	require.EqualError(t, MemoFilter{Type: MemoTypeReturn, Value: "ab"}.Valid(),
+		"return memo must be a hex-encoded 32-byte hash")
+	require.EqualError(t, MemoFilter{Type: MemoTypeText, Value: "a text memo longer than 28 bytes"}.Valid(),
+		"text memo must be at most 28 bytes")
+}
@@ -0,0 +1,37 @@
+package protocol
+
+import (
+	"strings"
+	"unicode"
+
+	"github.com/stellar/go/xdr"
+)
+
+// OperationTypeName returns the name of an operation type as in the JSON
+// encoding of XDR values, e.g. invoke_host_function.
+func OperationTypeName(opType xdr.OperationType) string {
+	name := strings.TrimPrefix(opType.String(), "OperationType")
+	var result strings.Builder
+	for i, r := range name {
+		if unicode.IsUpper(r) {
+			if i > 0 {
+				result.WriteRune('_')
+			}
+			r = unicode.ToLower(r)
+		}
+		result.WriteRune(r)
+	}
+	return result.String()
+}
+
+// ParseOperationType returns the operation type of the given name (see
+// OperationTypeName).
+func ParseOperationType(name string) (xdr.OperationType, bool) {
+	var opType xdr.OperationType
+	for value := int32(0); opType.ValidEnum(value); value++ {
+		if OperationTypeName(xdr.OperationType(value)) == name {
+			return xdr.OperationType(value), true
+		}
+	}
+	return 0, false
+}
@@ -0,0 +1,21 @@
+package protocol
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+
+	"github.com/stellar/go/xdr"
+)
+
+func TestOperationTypeName(t *testing.T) {
+	assert.Equal(t, "invoke_host_function", OperationTypeName(xdr.OperationTypeInvokeHostFunction))
+	assert.Equal(t, "extend_footprint_ttl", OperationTypeName(xdr.OperationTypeExtendFootprintTtl))
+	assert.Equal(t, "create_account", OperationTypeName(xdr.OperationTypeCreateAccount))
+
+	opType, ok := ParseOperationType("restore_footprint")
+	assert.True(t, ok)
+	assert.Equal(t, xdr.OperationTypeRestoreFootprint, opType)
+	_, ok = ParseOperationType("InvokeHostFunction")
+	assert.False(t, ok)
+}
@@ -10,9 +10,59 @@ const (
 	SimulateTransactionMethodName        = "simulateTransaction"
 	DefaultInstructionLeeway      uint64 = 0
 
+	// About 8 minutes
+	DefaultSignatureExpirationLedgers uint32 = 100
+
 	AuthModeEnforce            = "enforce"
 	AuthModeRecord             = "record"
 	AuthModeRecordAllowNonroot = "record_allow_nonroot"
+	// AuthModeAuto enforces the auth entries of the operation if it has any,
+	// and records otherwise
+	AuthModeAuto = "auto"
+	// AuthModeEnforceMockSignatures enforces the structure of the auth entries
+	// of the operation (which addresses authorize which invocation trees)
+	// while treating their signatures as valid, e.g. to validate a
+	// fully-assembled transaction with placeholder signatures before
+	// collecting the real ones. Nonces and expiration ledgers aren't checked
+	// either, and the resources used to verify the signatures aren't
+	// accounted for.
+	AuthModeEnforceMockSignatures = "enforce_mock_signatures"
+
+	// Categories of simulation errors
+	ErrorCategoryStorage      = "storage"
+	ErrorCategoryBudget       = "budget"
+	ErrorCategoryAuth         = "auth"
+	ErrorCategoryContract     = "contract"
+	ErrorCategoryHost         = "host"
+	ErrorCategoryInvalidInput = "invalid_input"
+
+	// Kinds of reasons an uploaded Wasm module is rejected
+	WasmDiagnosticUnsupportedImport = "unsupported_import"
+	WasmDiagnosticMissingExport     = "missing_export"
+	WasmDiagnosticMemoryLimits      = "memory_limits"
+	WasmDiagnosticInterfaceVersion  = "interface_version"
+	WasmDiagnosticInvalidModule     = "invalid_module"
+
+	// Kinds of non-fatal simulation findings
+	WarningResourceNearLimit      = "resource_near_limit"
+	WarningNonRootAuth            = "non_root_auth"
+	WarningDeprecatedHostFunction = "deprecated_host_function"
+	WarningEntryNearArchival      = "entry_near_archival"
+	WarningLinearMemoryUnmeasured = "linear_memory_unmeasured"
+
+	// Codes of the transaction precondition failures, named after the
+	// transaction result codes of their submission
+	PreconditionNoAccount         = "txNO_ACCOUNT"
+	PreconditionBadSeq            = "txBAD_SEQ"
+	PreconditionTooEarly          = "txTOO_EARLY"
+	PreconditionTooLate           = "txTOO_LATE"
+	PreconditionBadMinSeqAgeOrGap = "txBAD_MIN_SEQ_AGE_OR_GAP"
+
+	// Whether a key of a RestoreFootprint operation can be restored
+	RestoreStatusRestorable  = "restorable"
+	RestoreStatusAlreadyLive = "already_live"
+	RestoreStatusNotFound    = "not_found"
+	RestoreStatusTooLarge    = "too_large" // larger than the write limit of a transaction
 )
 
 type SimulateTransactionRequest struct {
@@ -20,10 +70,125 @@ type SimulateTransactionRequest struct {
 	ResourceConfig *ResourceConfig `json:"resourceConfig,omitempty"`
 	AuthMode       string          `json:"authMode,omitempty"`
 	Format         string          `json:"xdrFormat,omitempty"`
+	// LedgerEntryOverrides are LedgerEntry XDRs in base64 which replace (or
+	// add to) the ledger state seen by the simulation, without modifying it.
+	LedgerEntryOverrides []string `json:"ledgerEntryOverrides,omitempty"`
+	// ConfigUpgradeSet is a ConfigUpgradeSet XDR in base64 whose network
+	// settings replace the current ones during the simulation, e.g. to preview
+	// the fees and limits resulting from a pending settings upgrade.
+	ConfigUpgradeSet string `json:"configUpgradeSet,omitempty"`
+	// Ledger is the sequence of the ledger whose state to simulate against,
+	// which must be one of the few latest ledgers whose state Stellar Core
+	// keeps (4 by default). Defaults to the latest ledger.
+	Ledger uint32 `json:"ledger,omitempty"`
+	// ValidatePreconditions checks the sequence number and the preconditions
+	// (time bounds, ledger bounds, minimum sequence number, age and ledger
+	// gap) of the transaction against the simulated ledger, reporting the ones
+	// its submission would fail in the preconditionFailures of the response.
+	ValidatePreconditions bool `json:"validatePreconditions,omitempty"`
+	// ProtocolVersion pins the protocol to simulate with, e.g. to preview the
+	// behavior of the transaction after an upcoming protocol upgrade. It can
+	// be at most one version away from the protocol of the simulated ledger.
+	// Defaults to the protocol of the simulated ledger.
+	ProtocolVersion uint32 `json:"protocolVersion,omitempty"`
+	// Operation is an InvokeHostFunctionOp XDR in base64 to simulate instead
+	// of a transaction, on behalf of SourceAccount (a G... or M... address).
+	// The server wraps it in a placeholder transaction, which saves building
+	// and encoding a full envelope e.g. for read-only contract calls.
+	Operation     string `json:"operation,omitempty"`
+	SourceAccount string `json:"sourceAccount,omitempty"`
+	// LedgerOverride optionally replaces the sequence and the close time of
+	// the ledger seen by the simulation, e.g. to exercise time-locked or
+	// sequence-gated contract logic. The state is still read from the
+	// simulated ledger, but TTLs are checked against the overridden sequence.
+	// Each can be skewed at most as far as the server allows.
+	LedgerOverride *LedgerOverride `json:"ledgerOverride,omitempty"`
+}
+
+// LedgerOverride replaces the sequence and the close time of the ledger seen by a simulation, the omitted ones being
+// left as they are.
+type LedgerOverride struct {
+	Sequence  uint32 `json:"sequence,omitempty"`
+	Timestamp int64  `json:"timestamp,string,omitempty"` // Unix time in seconds
 }
 
 type ResourceConfig struct {
 	InstructionLeeway uint64 `json:"instructionLeeway"`
+
+	// Optional overrides of the adjustments applied on top of the simulated
+	// resources and fee. When omitted, the simulation defaults are used.
+	Instructions  *AdjustmentFactor `json:"instructions,omitempty"`
+	ReadBytes     *AdjustmentFactor `json:"readBytes,omitempty"`
+	WriteBytes    *AdjustmentFactor `json:"writeBytes,omitempty"`
+	TxSize        *AdjustmentFactor `json:"txSize,omitempty"`
+	RefundableFee *AdjustmentFactor `json:"refundableFee,omitempty"`
+
+	// Optional seed for the pseudo-random number generator of the simulation
+	// (used e.g. for auth nonces). When omitted, a random seed is used.
+	PrngSeed *uint64 `json:"prngSeed,omitempty"`
+
+	// Skips simulating the RestoreFootprint operation required when the
+	// invocation reads archived entries, in which case no restorePreamble is
+	// returned. This is faster when only the invocation estimate is needed.
+	SkipRestorePreamble bool `json:"skipRestorePreamble,omitempty"`
+
+	// Simulates as if the transaction-level CPU and memory limits of the
+	// network were unbounded, so that the resources needed by an invocation
+	// exceeding them are still reported. The resulting transaction data may
+	// exceed the network limits and thus not be submittable as is.
+	RelaxNetworkLimits bool `json:"relaxNetworkLimits,omitempty"`
+
+	// Optional caps on the CPU instructions and memory bytes the invocation
+	// may consume, which abort the simulation as soon as they are exceeded.
+	// The network limits still apply when they are lower.
+	MaxInstructions uint64 `json:"maxInstructions,omitempty"`
+	MaxMemoryBytes  uint64 `json:"maxMemoryBytes,omitempty"`
+
+	// Replays the invocation without each of the read-only keys recorded in
+	// its footprint, dropping the ones it still succeeds without (e.g. probed
+	// but unused entries), which lowers the fees of complex invocations. This
+	// only applies to the recording auth modes and is skipped when a
+	// restorePreamble is needed.
+	MinimizeFootprint bool `json:"minimizeFootprint,omitempty"`
+
+	// Optional LedgerFootprint XDR in base64 whose keys are added to the
+	// footprint of the invocation, charging for reading (and writing) their
+	// entries at their current size. This provisions for state which may
+	// change between simulation and submission and make the transaction touch
+	// other entries. Together with the adjustment factors above (e.g. a larger
+	// instructions multiplier), this keeps such transactions from failing.
+	ExtraFootprint string `json:"extraFootprint,omitempty"`
+
+	// Skips running the __check_auth function of the contract accounts (e.g.
+	// smart wallets) authorizing the invocation, which may reject the
+	// placeholder signatures of auth entries which aren't signed yet. The auth
+	// entries which would be enforced are then only matched against the
+	// authorizations required by the invocation, as with the
+	// enforce_mock_signatures auth mode, and the contract accounts the
+	// invocation required to authorize are reported in stubbedCheckAuth. The
+	// resources consumed by __check_auth aren't accounted for, and should be
+	// provisioned for with the adjustment factors above.
+	SkipCheckAuth bool `json:"skipCheckAuth,omitempty"`
+
+	// Number of ledgers past the simulated one at which the signatures of the
+	// recorded auth entries are suggested to expire, which their
+	// signatureExpirationLedger is set to. It is capped to the maximum TTL of
+	// the network. Defaults to DefaultSignatureExpirationLedgers if missing,
+	// while 0 leaves the signatureExpirationLedger of the entries unset (0).
+	SignatureExpirationLedgers *uint32 `json:"signatureExpirationLedgers,omitempty"`
+
+	// Replays a successful invocation to measure the linear memory allocated
+	// by its Wasm VMs, reported in resourceUsage.linearMemoryBytes. This
+	// doubles the cost of the simulation. A failed replay is reported as a
+	// linear_memory_unmeasured warning.
+	MeasureLinearMemory bool `json:"measureLinearMemory,omitempty"`
+}
+
+// AdjustmentFactor adjusts a simulated value to
+// max(value * MultiplicativeFactor, value + AdditiveFactor)
+type AdjustmentFactor struct {
+	MultiplicativeFactor float64 `json:"multiplicativeFactor"`
+	AdditiveFactor       uint32  `json:"additiveFactor"`
 }
 
 func DefaultResourceConfig() ResourceConfig {
@@ -40,6 +205,43 @@ type SimulateHostFunctionResult struct {
 
 	ReturnValueXDR  *string         `json:"xdr,omitempty"`
 	ReturnValueJSON json.RawMessage `json:"returnValueJson,omitempty"`
+
+	// AuthTree describes what each of the auth entries authorizes (in the
+	// same order), so that it can be displayed without decoding the XDR
+	AuthTree []AuthorizationTree `json:"authTree,omitempty"`
+	// RequiredSigners lists the addresses which have to sign auth entries
+	// (besides the transaction source account)
+	RequiredSigners []RequiredSigner `json:"requiredSigners,omitempty"`
+	// ContractAddress is the address of the contract created by the host
+	// function, if it creates one
+	ContractAddress string `json:"contractAddress,omitempty"`
+}
+
+type RequiredSigner struct {
+	// Account or contract address
+	Address string `json:"address"`
+	// Ledger until which the signatures of the address are valid. Entries
+	// recorded without an expiration get one a short while after the latest
+	// ledger.
+	SignatureExpirationLedger uint32 `json:"signatureExpirationLedger"`
+}
+
+type AuthorizationTree struct {
+	// Address which has to sign the entry, omitted when the entry is
+	// authorized by the source account of the transaction
+	Address    string               `json:"address,omitempty"`
+	Invocation AuthorizedInvocation `json:"invocation"`
+}
+
+// AuthorizedInvocation is an invocation requiring authorization, along with
+// the sub-invocations it authorizes
+type AuthorizedInvocation struct {
+	// ContractAddress and FunctionName are set for contract function calls
+	ContractAddress string `json:"contractAddress,omitempty"`
+	FunctionName    string `json:"functionName,omitempty"`
+	// CreateContract is set for contract creations
+	CreateContract bool                   `json:"createContract,omitempty"`
+	SubInvocations []AuthorizedInvocation `json:"subInvocations,omitempty"`
 }
 
 type RestorePreamble struct {
@@ -48,7 +250,20 @@ type RestorePreamble struct {
 	TransactionDataJSON json.RawMessage `json:"transactionDataJson,omitempty"`
 
 	MinResourceFee int64 `json:"minResourceFee,string"`
+
+	// The archived entries which have to be restored
+	ArchivedKeysXDR  []string          `json:"archivedKeys,omitempty"` // LedgerKey XDR in base64
+	ArchivedKeysJSON []json.RawMessage `json:"archivedKeysJson,omitempty"`
 }
+// Kinds of ledger entries
+const (
+	// LedgerEntryKindContract designates contract data and code entries, along with their TTL entries
+	LedgerEntryKindContract = "contract"
+	// LedgerEntryKindClassic designates the entries of the classic protocol (e.g. the accounts and trustlines
+	// modified by the Stellar Asset Contract)
+	LedgerEntryKindClassic = "classic"
+)
+
 type LedgerEntryChangeType int //nolint:recvcheck
 
 const (
@@ -100,6 +315,8 @@ func (l *LedgerEntryChangeType) UnmarshalJSON(data []byte) error {
 // If Before is omitted, it constitutes a creation, if After is omitted, it constitutes a deletion.
 type LedgerEntryChange struct {
 	Type LedgerEntryChangeType `json:"type"`
+	// One of the LedgerEntryKind* values
+	EntryKind string `json:"entryKind,omitempty"`
 
 	KeyXDR  string          `json:"key,omitempty"` // LedgerEntryKey in base64
 	KeyJSON json.RawMessage `json:"keyJson,omitempty"`
@@ -111,8 +328,143 @@ type LedgerEntryChange struct {
 	AfterJSON json.RawMessage `json:"afterJson,omitempty"`
 }
 
+// FootprintEntryBytes is the number of bytes read and written for an entry
+// of the footprint during the simulation.
+type FootprintEntryBytes struct {
+	KeyXDR  string          `json:"key,omitempty"` // LedgerKey in base64
+	KeyJSON json.RawMessage `json:"keyJson,omitempty"`
+
+	ReadBytes  uint32 `json:"readBytes"`
+	WriteBytes uint32 `json:"writeBytes"`
+}
+
+// RentChange is the rent charged for extending or restoring the TTL of an
+// entry during the simulation.
+type RentChange struct {
+	KeyXDR  string          `json:"key,omitempty"` // LedgerKey in base64
+	KeyJSON json.RawMessage `json:"keyJson,omitempty"`
+
+	// Size the rent is charged on, after the change
+	SizeBytes          uint32 `json:"sizeBytes"`
+	OldLiveUntilLedger uint32 `json:"oldLiveUntilLedger"`
+	NewLiveUntilLedger uint32 `json:"newLiveUntilLedger"`
+	RentFee            int64  `json:"rentFee,string"`
+}
+
+// RestoreKeyStatus tells whether a key of the footprint of a simulated
+// RestoreFootprint operation can be restored.
+type RestoreKeyStatus struct {
+	KeyXDR  string          `json:"key,omitempty"` // LedgerKey in base64
+	KeyJSON json.RawMessage `json:"keyJson,omitempty"`
+
+	Status string `json:"status"` // one of the RestoreStatus* values
+}
+
+// FeeBumpSimulation is returned when simulating a fee-bump transaction, whose
+// inner transaction is the one being simulated.
+type FeeBumpSimulation struct {
+	// Minimum fee the fee-bump transaction must bid: the inclusion fee of
+	// the fee-bump and its inner operations plus the inner resource fee
+	MinFee int64 `json:"minFee,string"`
+	// If present, it lists the inner transaction resources (and the fee-bump
+	// fee) which don't cover the simulated ones
+	ValidationErrors []string `json:"validationErrors,omitempty"`
+}
+
+// CallFrame is a contract function invocation made during the simulation.
+type CallFrame struct {
+	ContractAddress string `json:"contractAddress"`
+	Function        string `json:"function"`
+
+	ArgsXDR  []string          `json:"args,omitempty"` // ScVal XDR in base64
+	ArgsJSON []json.RawMessage `json:"argsJson,omitempty"`
+
+	// If present, the value returned by the call
+	ReturnValueXDR  string          `json:"returnValue,omitempty"` // ScVal XDR in base64
+	ReturnValueJSON json.RawMessage `json:"returnValueJson,omitempty"`
+
+	// If present, the first error raised while the call was executing
+	Trap *CallTrap `json:"trap,omitempty"`
+
+	SubCalls []CallFrame `json:"subCalls,omitempty"`
+}
+
+type CallTrap struct {
+	ErrorXDR  string          `json:"error,omitempty"` // ScError XDR in base64
+	ErrorJSON json.RawMessage `json:"errorJson,omitempty"`
+	Message   string          `json:"message,omitempty"`
+}
+
+// SimulationResourceUsage breaks down the resources of a successful invocation, showing which of them drive its fee.
+type SimulationResourceUsage struct {
+	// Linear memory allocated by the Wasm VMs of the invocation (including its growth), as metered by its budget. Only
+	// present when requested with resourceConfig.measureLinearMemory.
+	LinearMemoryBytes *uint64 `json:"linearMemoryBytes,omitempty"`
+	// Number and total XDR size of the contract events emitted by the invocation.
+	ContractEvents      uint32 `json:"contractEvents"`
+	ContractEventsBytes uint32 `json:"contractEventsBytes"`
+	// XDR size of the value returned by the invocation.
+	ReturnValueBytes uint32 `json:"returnValueBytes"`
+}
+
+// ResourceFeeBreakdown splits the minimum resource fee by what it pays for, the parts adding up to the fee. The
+// written entries are charged as read entries too, and the rent includes the margin the refundable fee is adjusted
+// by.
+type ResourceFeeBreakdown struct {
+	Instructions int64 `json:"instructions,string"`
+	ReadEntries  int64 `json:"readEntries,string"`
+	WriteEntries int64 `json:"writeEntries,string"`
+	ReadBytes    int64 `json:"readBytes,string"`
+	WriteBytes   int64 `json:"writeBytes,string"`
+	// Storing the transaction and its result in the history archives
+	Historical int64 `json:"historical,string"`
+	// Propagating the transaction across the network
+	TransactionSize int64 `json:"transactionSize,string"`
+	// The contract events and return value of an invocation
+	Events int64 `json:"events,string"`
+	Rent   int64 `json:"rent,string"`
+}
+
+// SimulationWarning is a non-fatal finding of the simulation, e.g. a resource close to its network limit.
+type SimulationWarning struct {
+	Kind    string `json:"kind"` // one of the Warning* values
+	Message string `json:"message"`
+}
+
+// WasmDiagnostic is a reason the Wasm module uploaded by the simulated transaction is rejected.
+type WasmDiagnostic struct {
+	Kind    string `json:"kind"` // one of the WasmDiagnostic* values
+	Message string `json:"message,omitempty"`
+
+	ErrorXDR  string          `json:"error,omitempty"` // ScError XDR in base64
+	ErrorJSON json.RawMessage `json:"errorJson,omitempty"`
+
+	ArgsXDR  []string          `json:"args,omitempty"` // ScVal XDR in base64
+	ArgsJSON []json.RawMessage `json:"argsJson,omitempty"`
+}
+
+// SimulationErrorDetails is the machine-readable counterpart of the error of a simulation.
+type SimulationErrorDetails struct {
+	Category string `json:"category"` // one of the ErrorCategory* values
+
+	// If present, the error raised by the host
+	ErrorXDR  string          `json:"error,omitempty"` // ScError XDR in base64
+	ErrorJSON json.RawMessage `json:"errorJson,omitempty"`
+
+	// If present (only when the server has preflight debugging enabled), the innermost contract call executing
+	// when the error was raised.
+	ContractAddress string `json:"contractAddress,omitempty"`
+}
+
+type PreconditionFailure struct {
+	Code    string `json:"code"` // one of the Precondition* values
+	Message string `json:"message"`
+}
+
 type SimulateTransactionResponse struct {
 	Error string `json:"error,omitempty"`
+	// If present, the category of the error and, when available, the error raised by the host.
+	ErrorDetails *SimulationErrorDetails `json:"errorDetails,omitempty"`
 
 	TransactionDataXDR  string          `json:"transactionData,omitempty"` // SorobanTransactionData XDR in base64
 	TransactionDataJSON json.RawMessage `json:"transactionDataJson,omitempty"`
@@ -120,12 +472,51 @@ type SimulateTransactionResponse struct {
 	EventsXDR  []string          `json:"events,omitempty"` // DiagnosticEvent XDR in base64
 	EventsJSON []json.RawMessage `json:"eventsJson,omitempty"`
 
+	ContractEventsXDR  []string          `json:"contractEvents,omitempty"` // ContractEvent XDR in base64
+	ContractEventsJSON []json.RawMessage `json:"contractEventsJson,omitempty"`
+
 	MinResourceFee int64 `json:"minResourceFee,string,omitempty"`
+	// If present, the estimated size in bytes of the transaction envelope once assembled with the simulation results
+	// and signed by its source account (not counting the signatures of the auth entries).
+	TransactionSize uint32 `json:"transactionSize,omitempty"`
+	// If present, the suggested total fee of the assembled transaction: the minimum resource fee plus an inclusion fee
+	// based on the recent Soroban inclusion fee stats.
+	SuggestedFee int64 `json:"suggestedFee,string,omitempty"`
+	// If present, the minimum resource fee split by what it pays for (instructions, ledger reads and writes,
+	// transaction size, events and rent), explaining where the fee comes from.
+	ResourceFeeBreakdown *ResourceFeeBreakdown `json:"resourceFeeBreakdown,omitempty"`
 	// an array of the individual host function call results
 	Results []SimulateHostFunctionResult `json:"results,omitempty"`
 	// If present, it indicates that a prior RestoreFootprint is required
 	RestorePreamble *RestorePreamble `json:"restorePreamble,omitempty"`
 	// If present, it indicates how the state (ledger entries) will change as a result of the transaction execution.
 	StateChanges []LedgerEntryChange `json:"stateChanges,omitempty"`
-	LatestLedger uint32              `json:"latestLedger"`
+	// If present, it indicates how many bytes are read and written for each footprint entry.
+	FootprintBytes []FootprintEntryBytes `json:"footprintBytes,omitempty"`
+	// If present, it indicates the rent charged for each entry whose TTL is extended or restored.
+	RentChanges []RentChange `json:"rentChanges,omitempty"`
+	// If present, whether each key of the footprint of a RestoreFootprint operation can be restored.
+	RestoreStatuses []RestoreKeyStatus `json:"restoreStatuses,omitempty"`
+	// If present, the simulated transaction is a fee-bump and this contains its fee requirements.
+	FeeBump *FeeBumpSimulation `json:"feeBump,omitempty"`
+	// If present (only when the server has preflight debugging enabled), the tree of contract calls made by the
+	// invocation.
+	CallTrace []CallFrame `json:"callTrace,omitempty"`
+	// If present, the reasons the Wasm module uploaded by the transaction is rejected.
+	WasmDiagnostics []WasmDiagnostic `json:"wasmDiagnostics,omitempty"`
+	// If present, non-fatal findings of the simulation (resources close to the network limits, non-root auth,
+	// deprecated host functions, footprint entries close to archival).
+	Warnings []SimulationWarning `json:"warnings,omitempty"`
+	// If present, the breakdown of the resources of a successful invocation.
+	ResourceUsage *SimulationResourceUsage `json:"resourceUsage,omitempty"`
+	// If present (only when the server has preflight debugging enabled), the diagnostic events of the simulation
+	// in a human-readable form, e.g. the messages logged by the contracts and the budget metrics of the host.
+	Logs []string `json:"logs,omitempty"`
+	// If present (only when validatePreconditions is requested), the preconditions of the transaction its
+	// submission would fail.
+	PreconditionFailures []PreconditionFailure `json:"preconditionFailures,omitempty"`
+	// If present (only when skipCheckAuth is requested), the contract accounts whose __check_auth wasn't run, one per
+	// authorization the invocation required of a contract account.
+	StubbedCheckAuth []string `json:"stubbedCheckAuth,omitempty"`
+	LatestLedger     uint32   `json:"latestLedger"`
 }
@@ -18,3 +18,22 @@ func TestSimulatingNonRootAuth(t *testing.T) {
 	require.NoError(t, json.Unmarshal([]byte(requestString), &request))
 	require.Equal(t, AuthModeRecord, request.AuthMode)
 }
+
+func TestResourceConfigAdjustmentFactors(t *testing.T) {
+	var request SimulateTransactionRequest
+	requestString := `{
+		"transaction": "pretend this is XDR",
+		"resourceConfig": {
+			"instructionLeeway": 100,
+			"instructions": { "multiplicativeFactor": 1.5, "additiveFactor": 1000 }
+		}
+	}`
+
+	require.NoError(t, json.Unmarshal([]byte(requestString), &request))
+	require.NotNil(t, request.ResourceConfig)
+	require.Equal(t, uint64(100), request.ResourceConfig.InstructionLeeway)
+	require.Equal(t, &AdjustmentFactor{MultiplicativeFactor: 1.5, AdditiveFactor: 1000},
+		request.ResourceConfig.Instructions)
+	require.Nil(t, request.ResourceConfig.ReadBytes)
+	require.Nil(t, request.ResourceConfig.RefundableFee)
+}
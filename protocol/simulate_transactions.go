@@ -0,0 +1,28 @@
+package protocol
+
+const SimulateTransactionsMethodName = "simulateTransactions"
+
+// SimulateTransactionsRequest simulates several transactions at once, each as a
+// SimulateTransactionRequest with the other fields of the request.
+type SimulateTransactionsRequest struct {
+	Transactions   []string        `json:"transactions"`
+	ResourceConfig *ResourceConfig `json:"resourceConfig,omitempty"`
+	AuthMode       string          `json:"authMode,omitempty"`
+	Format         string          `json:"xdrFormat,omitempty"`
+	// Ledger is the sequence of the ledger whose state to simulate against,
+	// which must be one of the few latest ledgers whose state Stellar Core
+	// keeps (4 by default). Defaults to the latest ledger.
+	Ledger uint32 `json:"ledger,omitempty"`
+}
+
+// SimulateTransactionsResponse holds the simulations of the transactions of a
+// SimulateTransactionsRequest, which all ran against the state of the same
+// ledger even if ledgers closed meanwhile.
+type SimulateTransactionsResponse struct {
+	// One per transaction of the request, in the same order. A failed
+	// simulation is reported in the error of its result.
+	Results []SimulateTransactionResponse `json:"results"`
+	// The ledger the transactions were simulated against
+	Ledger       uint32 `json:"ledger"`
+	LatestLedger uint32 `json:"latestLedger"`
+}